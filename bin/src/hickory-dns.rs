@@ -21,6 +21,7 @@
 //!       -p, --port <PORT>        Listening port for DNS queries, overrides any value in config file
 //!           --disable-tcp        Disable TCP protocol, overrides any value in config file
 //!           --disable-udp        Disable UDP protocol, overrides any value in config file
+//!           --otlp-endpoint <URL> Endpoint of an OpenTelemetry collector to export traces to with OTLP over HTTP
 //!           --nsid <NSID>        Name server identifier (NSID) payload for EDNS responses. Use `0x` prefix for hex-encoded data. Mutually exclusive with --nsid-hostname
 //!           --nsid-hostname      Use the system hostname as the name server identifier (NSID) payload for EDNS responses. Mutually exclusive with --nsid
 //!       -h, --help               Print help
@@ -29,7 +30,11 @@
 
 #![recursion_limit = "128"]
 
-#[cfg(feature = "metrics")]
+#[cfg(feature = "__tls")]
+use std::fs;
+#[cfg(any(feature = "__tls", feature = "prometheus-metrics"))]
+use std::sync::Arc;
+#[cfg(any(feature = "metrics", feature = "__tls"))]
 use std::time::Duration;
 use std::{
     fmt,
@@ -43,11 +48,17 @@ use clap::Parser;
 use metrics::{Counter, Unit, counter, describe_counter, describe_gauge, gauge};
 #[cfg(feature = "metrics")]
 use metrics_process::Collector;
+#[cfg(feature = "otlp")]
+use opentelemetry::trace::TracerProvider;
+#[cfg(feature = "otlp")]
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+#[cfg(feature = "otlp")]
+use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
 use socket2::{Domain, Socket, Type};
 use time::OffsetDateTime;
 #[cfg(unix)]
 use tokio::signal::unix::{SignalKind, signal};
-#[cfg(feature = "metrics")]
+#[cfg(any(feature = "metrics", feature = "__tls"))]
 use tokio::time::sleep;
 use tokio::{
     net::{TcpListener, UdpSocket},
@@ -64,6 +75,8 @@ use tracing_subscriber::{
     util::SubscriberInitExt,
 };
 
+#[cfg(feature = "__acme")]
+use hickory_dns::AcmeConfig;
 use hickory_dns::Config;
 #[cfg(all(feature = "metrics", feature = "resolver"))]
 use hickory_dns::ExternalStoreConfig;
@@ -73,8 +86,12 @@ use hickory_dns::PrometheusServer;
 use hickory_dns::TlsCertConfig;
 #[cfg(feature = "metrics")]
 use hickory_dns::{ServerStoreConfig, ServerZoneConfig, ZoneConfig, ZoneTypeConfig};
+#[cfg(feature = "__acme")]
+use hickory_server::acme::Acme;
 use hickory_server::proto::ProtoError;
 use hickory_server::proto::rr::rdata::opt::NSIDPayload;
+#[cfg(feature = "__tls")]
+use hickory_server::server::ReloadableCertResolver;
 use hickory_server::{authority::Catalog, server::Server};
 
 /// Cli struct for all options managed with clap derive api.
@@ -145,6 +162,13 @@ struct Cli {
     )]
     prometheus_listen_addr: Option<SocketAddr>,
 
+    /// Endpoint of an OpenTelemetry collector to export traces to with OTLP over HTTP,
+    /// e.g. http://localhost:4318/v1/traces. The spans of lookups are at the DEBUG level,
+    /// use --debug or RUST_LOG to export them
+    #[cfg(feature = "otlp")]
+    #[clap(long = "otlp-endpoint", value_name = "URL")]
+    otlp_endpoint: Option<String>,
+
     /// Disable TCP protocol,
     /// overrides any value in config file
     #[clap(long = "disable-tcp")]
@@ -223,8 +247,15 @@ fn run() -> Result<(), String> {
         _ => Level::INFO,
     };
 
+    #[cfg(feature = "otlp")]
+    let tracer_provider = args
+        .otlp_endpoint
+        .as_deref()
+        .map(otlp_tracer_provider)
+        .transpose()?;
+
     // Setup tracing for logging based on input
-    tracing_subscriber::registry()
+    let registry = tracing_subscriber::registry()
         .with(tracing_subscriber::fmt::layer().event_format(TdnsFormatter))
         .with(
             EnvFilter::builder()
@@ -233,8 +264,12 @@ fn run() -> Result<(), String> {
                 .map_err(|err| {
                     format!("failed to parse environment variable for tracing: {err}")
                 })?,
-        )
-        .init();
+        );
+    #[cfg(feature = "otlp")]
+    let registry = registry.with(tracer_provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("hickory-dns"))
+    }));
+    registry.init();
 
     info!("Hickory DNS {} starting...", hickory_client::version());
 
@@ -247,7 +282,33 @@ fn run() -> Result<(), String> {
         .build()
         .map_err(|err| format!("failed to initialize Tokio runtime: {err}"))?;
 
-    runtime.block_on(async_run(args))
+    let result = runtime.block_on(async_run(args));
+
+    // flush the spans which are not exported yet
+    #[cfg(feature = "otlp")]
+    if let Some(provider) = tracer_provider {
+        if let Err(err) = provider.shutdown() {
+            eprintln!("failed to shut down the OTLP exporter: {err}");
+        }
+    }
+
+    result
+}
+
+/// Builds the provider of the tracer which exports spans to the OpenTelemetry collector at
+/// `endpoint`
+#[cfg(feature = "otlp")]
+fn otlp_tracer_provider(endpoint: &str) -> Result<SdkTracerProvider, String> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|err| format!("failed to build the OTLP exporter: {err}"))?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name("hickory-dns").build())
+        .build())
 }
 
 async fn async_run(args: Cli) -> Result<(), String> {
@@ -267,6 +328,11 @@ async fn async_run(args: Cli) -> Result<(), String> {
         .map(PathBuf::from)
         .unwrap_or(directory_config);
 
+    #[cfg(feature = "prometheus-metrics")]
+    let query_statistics = config
+        .query_statistics()
+        .map(|statistics| Arc::new(statistics.build()));
+
     #[cfg(feature = "prometheus-metrics")]
     let prometheus_server_opt = if !args.disable_prometheus && !config.disable_prometheus() {
         let socket_addr = args
@@ -280,7 +346,7 @@ async fn async_run(args: Cli) -> Result<(), String> {
             .map_err(|err| format!("failed to look up local address: {err}"))?;
 
         // Set up Prometheus HTTP server.
-        let server = PrometheusServer::new(listener)?;
+        let server = PrometheusServer::new(listener, query_statistics.clone())?;
         info!("listening for Prometheus metrics on {local_addr:?}");
         Some(server)
     } else {
@@ -313,6 +379,8 @@ async fn async_run(args: Cli) -> Result<(), String> {
 
     let mut catalog: Catalog = Catalog::new();
     catalog.set_nsid(args.nsid);
+    #[cfg(feature = "prometheus-metrics")]
+    catalog.set_statistics(query_statistics);
 
     if args.nsid_hostname {
         let hostname =
@@ -323,12 +391,13 @@ async fn async_run(args: Cli) -> Result<(), String> {
     }
 
     // configure our server based on the config_path
+    let memory_budget = config.zone_memory_budget();
     for zone in config.zones() {
         let zone_name = zone
             .zone()
             .map_err(|err| format!("failed to read zone name from {config_path:?}: {err}"))?;
 
-        match zone.load(&zone_dir).await {
+        match zone.load(&zone_dir, memory_budget.as_ref()).await {
             Ok(authority) => catalog.upsert(zone_name.into(), authority),
             Err(err) => return Err(format!("could not load zone {zone_name}: {err}")),
         }
@@ -337,6 +406,21 @@ async fn async_run(args: Cli) -> Result<(), String> {
         config_metrics.increment_zone_metrics(zone);
     }
 
+    // the challenges for the certificate are answered next to the zones
+    #[cfg(feature = "__acme")]
+    let acme = match config.acme() {
+        Some(acme_config) => {
+            let acme = acme_config
+                .build(&zone_dir)
+                .map_err(|err| format!("failed to configure ACME: {err}"))?;
+            for (name, authority) in acme.challenge_authorities() {
+                catalog.upsert(name, vec![authority]);
+            }
+            Some(acme)
+        }
+        None => None,
+    };
+
     let v4addr = config
         .listen_addrs_ipv4()
         .map_err(|err| format!("failed to parse IPv4 addresses from {config_path:?}: {err}"))?;
@@ -367,7 +451,8 @@ async fn async_run(args: Cli) -> Result<(), String> {
 
     // now, run the server, based on the config
     #[cfg_attr(not(feature = "__tls"), allow(unused_mut))]
-    let mut server = Server::with_access(catalog, deny_networks, allow_networks);
+    let mut server = Server::with_access(catalog, deny_networks, allow_networks)
+        .with_decompression_limits(config.decompression_limits());
 
     if !args.disable_udp && !config.disable_udp() {
         // load all udp listeners
@@ -412,7 +497,12 @@ async fn async_run(args: Cli) -> Result<(), String> {
     }
 
     #[cfg(feature = "__tls")]
-    if let Some(tls_cert_config) = config.tls_cert() {
+    if let Some((tls_cert, endpoint_name)) = load_tls_cert(
+        &config,
+        &zone_dir,
+        #[cfg(feature = "__acme")]
+        acme,
+    )? {
         #[cfg(feature = "__tls")]
         if !args.disable_tls && !config.disable_tls() {
             // setup TLS listeners
@@ -420,7 +510,7 @@ async fn async_run(args: Cli) -> Result<(), String> {
                 args.tls_port,
                 &mut server,
                 &config,
-                tls_cert_config,
+                &tls_cert,
                 &zone_dir,
                 &listen_addrs,
             )?;
@@ -435,8 +525,8 @@ async fn async_run(args: Cli) -> Result<(), String> {
                 args.https_port,
                 &mut server,
                 &config,
-                tls_cert_config,
-                &zone_dir,
+                &tls_cert,
+                endpoint_name.clone(),
                 &listen_addrs,
             )?;
         } else {
@@ -450,8 +540,8 @@ async fn async_run(args: Cli) -> Result<(), String> {
                 args.quic_port,
                 &mut server,
                 &config,
-                tls_cert_config,
-                &zone_dir,
+                &tls_cert,
+                endpoint_name.clone(),
                 &listen_addrs,
             )?;
         } else {
@@ -523,7 +613,7 @@ fn config_tls(
     tls_port: Option<u16>,
     server: &mut Server<Catalog>,
     config: &Config,
-    tls_cert_config: &TlsCertConfig,
+    tls_cert: &Arc<ReloadableCertResolver>,
     zone_dir: &Path,
     listen_addrs: &[IpAddr],
 ) -> Result<(), String> {
@@ -534,14 +624,12 @@ fn config_tls(
         return Ok(());
     }
 
-    for addr in listen_addrs {
-        let tls_cert_path = &tls_cert_config.path;
-        info!("loading cert for DNS over TLS: {tls_cert_path:?}");
-
-        let tls_cert = tls_cert_config.load(zone_dir).map_err(|err| {
-            format!("failed to load tls certificate files from {tls_cert_path:?}: {err}")
-        })?;
+    let client_verifier = match config.tls_cert() {
+        Some(tls_cert_config) => tls_cert_config.load_client_verifier(zone_dir)?,
+        None => None,
+    };
 
+    for addr in listen_addrs {
         info!("binding TLS to {addr:?}");
 
         let tls_listener = build_tcp_listener(*addr, tls_listen_port)
@@ -554,9 +642,20 @@ fn config_tls(
                 .map_err(|err| format!("failed to lookup local address: {err}"))?
         );
 
-        server
-            .register_tls_listener(tls_listener, config.tcp_request_timeout(), tls_cert)
-            .map_err(|err| format!("failed to register TLS listener: {err}"))?;
+        match &client_verifier {
+            Some(client_verifier) => server.register_tls_listener_with_client_auth(
+                tls_listener,
+                config.tcp_request_timeout(),
+                tls_cert.clone(),
+                client_verifier.clone(),
+            ),
+            None => server.register_tls_listener(
+                tls_listener,
+                config.tcp_request_timeout(),
+                tls_cert.clone(),
+            ),
+        }
+        .map_err(|err| format!("failed to register TLS listener: {err}"))?;
     }
     Ok(())
 }
@@ -566,8 +665,8 @@ fn config_https(
     https_port: Option<u16>,
     server: &mut Server<Catalog>,
     config: &Config,
-    tls_cert_config: &TlsCertConfig,
-    zone_dir: &Path,
+    tls_cert: &Arc<ReloadableCertResolver>,
+    endpoint_name: Option<String>,
     listen_addrs: &[IpAddr],
 ) -> Result<(), String> {
     let https_listen_port = https_port.unwrap_or_else(|| config.https_listen_port());
@@ -579,16 +678,6 @@ fn config_https(
     }
 
     for addr in listen_addrs {
-        let tls_cert_path = &tls_cert_config.path;
-        if let Some(endpoint_name) = &tls_cert_config.endpoint_name {
-            info!("loading cert for DNS over TLS named {endpoint_name} from {tls_cert_path:?}");
-        } else {
-            info!("loading cert for DNS over TLS from {tls_cert_path:?}");
-        }
-        let tls_cert = tls_cert_config.load(zone_dir).map_err(|err| {
-            format!("failed to load tls certificate files from {tls_cert_path:?}: {err}")
-        })?;
-
         info!("binding HTTPS to {addr:?}");
 
         let https_listener = build_tcp_listener(*addr, https_listen_port)
@@ -605,8 +694,8 @@ fn config_https(
             .register_https_listener(
                 https_listener,
                 config.tcp_request_timeout(),
-                tls_cert,
-                tls_cert_config.endpoint_name.clone(),
+                tls_cert.clone(),
+                endpoint_name.clone(),
                 endpoint_path.into(),
             )
             .map_err(|err| format!("failed to register HTTPS listener: {err}"))?;
@@ -620,8 +709,8 @@ fn config_quic(
     quic_port: Option<u16>,
     server: &mut Server<Catalog>,
     config: &Config,
-    tls_cert_config: &TlsCertConfig,
-    zone_dir: &Path,
+    tls_cert: &Arc<ReloadableCertResolver>,
+    endpoint_name: Option<String>,
     listen_addrs: &[IpAddr],
 ) -> Result<(), String> {
     let quic_listen_port = quic_port.unwrap_or_else(|| config.quic_listen_port());
@@ -632,16 +721,6 @@ fn config_quic(
     }
 
     for addr in listen_addrs {
-        let tls_cert_path = &tls_cert_config.path;
-        if let Some(endpoint_name) = &tls_cert_config.endpoint_name {
-            info!("loading cert for DNS over QUIC named {endpoint_name} from {tls_cert_path:?}");
-        } else {
-            info!("loading cert for DNS over QUIC from {tls_cert_path:?}",);
-        }
-        let tls_cert = tls_cert_config.load(zone_dir).map_err(|err| {
-            format!("failed to load tls certificate files from {tls_cert_path:?}: {err}")
-        })?;
-
         info!("Binding QUIC to {addr:?}");
 
         let quic_listener = build_udp_socket(*addr, quic_listen_port)
@@ -658,14 +737,105 @@ fn config_quic(
             .register_quic_listener(
                 quic_listener,
                 config.tcp_request_timeout(),
-                tls_cert,
-                tls_cert_config.endpoint_name.clone(),
+                tls_cert.clone(),
+                endpoint_name.clone(),
             )
             .map_err(|err| format!("failed to register QUIC listener: {err}"))?;
     }
     Ok(())
 }
 
+/// Loads the certificate for the TLS based listeners and keeps it up to date, returning it with
+/// the endpoint name of the server
+#[cfg(feature = "__tls")]
+#[allow(clippy::type_complexity)]
+fn load_tls_cert(
+    config: &Config,
+    zone_dir: &Path,
+    #[cfg(feature = "__acme")] acme: Option<Acme>,
+) -> Result<Option<(Arc<ReloadableCertResolver>, Option<String>)>, String> {
+    #[cfg(feature = "__acme")]
+    if let Some(acme) = acme {
+        if config.tls_cert().is_some() {
+            return Err("only one of tls_cert and acme can be configured".to_string());
+        }
+
+        let certified_key = acme
+            .initial_certificate()
+            .map_err(|err| format!("failed to load ACME certificate: {err}"))?;
+        let tls_cert = Arc::new(ReloadableCertResolver::new(certified_key));
+        tokio::spawn(acme.run(tls_cert.clone()));
+
+        let endpoint_name = config.acme().and_then(AcmeConfig::endpoint_name);
+        return Ok(Some((tls_cert, endpoint_name)));
+    }
+
+    let Some(tls_cert_config) = config.tls_cert() else {
+        return Ok(None);
+    };
+
+    let tls_cert_path = &tls_cert_config.path;
+    if let Some(endpoint_name) = &tls_cert_config.endpoint_name {
+        info!("loading cert for DNS over TLS named {endpoint_name} from {tls_cert_path:?}");
+    } else {
+        info!("loading cert for DNS over TLS from {tls_cert_path:?}");
+    }
+    let tls_cert = tls_cert_config.load(zone_dir).map_err(|err| {
+        format!("failed to load tls certificate files from {tls_cert_path:?}: {err}")
+    })?;
+
+    if let Some(interval) = tls_cert_config.reload_interval() {
+        tokio::spawn(reload_tls_cert(
+            tls_cert_config.clone(),
+            zone_dir.to_owned(),
+            interval,
+            tls_cert.clone(),
+        ));
+    }
+
+    Ok(Some((tls_cert, tls_cert_config.endpoint_name.clone())))
+}
+
+/// Polls the certificate and private key files, replacing the certificate presented by the TLS
+/// based listeners when either of them changed
+///
+/// A certificate which fails to load is logged and the current one kept, so that a pair of files
+/// which is not yet completely written is picked up on a later check.
+#[cfg(feature = "__tls")]
+async fn reload_tls_cert(
+    tls_cert_config: TlsCertConfig,
+    zone_dir: PathBuf,
+    interval: Duration,
+    tls_cert: Arc<ReloadableCertResolver>,
+) {
+    let paths = [
+        zone_dir.join(&tls_cert_config.path),
+        zone_dir.join(&tls_cert_config.private_key),
+    ];
+    let modified = || {
+        paths
+            .iter()
+            .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+            .collect::<Vec<_>>()
+    };
+
+    let mut last_modified = modified();
+    loop {
+        sleep(interval).await;
+
+        let current_modified = modified();
+        if current_modified == last_modified {
+            continue;
+        }
+        last_modified = current_modified;
+
+        match tls_cert_config.load_certified_key(&zone_dir) {
+            Ok(certified_key) => tls_cert.replace(certified_key),
+            Err(err) => warn!("failed to reload tls certificate, keeping the current one: {err}"),
+        }
+    }
+}
+
 fn banner() {
     #[cfg(feature = "ascii-art")]
     const HICKORY_DNS_LOGO: &str = include_str!("hickory-dns.ascii");
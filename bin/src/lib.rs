@@ -29,9 +29,10 @@ use cfg_if::cfg_if;
 use ipnet::IpNet;
 #[cfg(feature = "__tls")]
 use rustls::{
+    RootCertStore,
     pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
-    server::ResolvesServerCert,
-    sign::{CertifiedKey, SingleCertAndKey},
+    server::{WebPkiClientVerifier, danger::ClientCertVerifier},
+    sign::CertifiedKey,
 };
 use serde::de::{self, MapAccess, SeqAccess, Visitor};
 use serde::{self, Deserialize, Deserializer};
@@ -39,11 +40,17 @@ use tracing::{debug, info, warn};
 
 #[cfg(feature = "__tls")]
 use hickory_proto::rustls::default_provider;
-use hickory_proto::{ProtoError, rr::Name};
+use hickory_proto::{ProtoError, rr::Name, serialize::binary::DecompressionLimits};
+#[cfg(feature = "__acme")]
+use hickory_server::acme::{Acme, AcmeError, LETS_ENCRYPT_DIRECTORY};
+#[cfg(feature = "prometheus-metrics")]
+use hickory_server::authority::QueryStatistics;
 #[cfg(feature = "__dnssec")]
 use hickory_server::dnssec::NxProofKind;
 #[cfg(feature = "recursor")]
 use hickory_server::proto::runtime::TokioRuntimeProvider;
+#[cfg(feature = "__tls")]
+use hickory_server::server::ReloadableCertResolver;
 #[cfg(feature = "blocklist")]
 use hickory_server::store::blocklist::BlocklistAuthority;
 #[cfg(feature = "blocklist")]
@@ -52,16 +59,23 @@ use hickory_server::store::blocklist::BlocklistConfig;
 use hickory_server::store::forwarder::ForwardAuthority;
 #[cfg(feature = "resolver")]
 use hickory_server::store::forwarder::ForwardConfig;
+#[cfg(feature = "intercept")]
+use hickory_server::store::intercept::{InterceptAuthority, InterceptConfig};
 #[cfg(feature = "recursor")]
 use hickory_server::store::recursor::RecursiveAuthority;
 #[cfg(feature = "recursor")]
 use hickory_server::store::recursor::RecursiveConfig;
+#[cfg(feature = "blocklist")]
+use hickory_server::store::rpz::{RpzConfig, RpzZone};
 #[cfg(feature = "sqlite")]
 use hickory_server::store::sqlite::{SqliteAuthority, SqliteConfig};
 use hickory_server::{
     ConfigError,
-    authority::{Authority, AxfrPolicy, ZoneType},
-    store::file::{FileAuthority, FileConfig},
+    authority::{Authority, AxfrPolicy, MinimalResponses, SerialPolicy, TtlPolicy, ZoneType},
+    store::{
+        file::{FileAuthority, FileConfig},
+        memory::MemoryBudget,
+    },
 };
 
 #[cfg(feature = "prometheus-metrics")]
@@ -77,6 +91,12 @@ static DEFAULT_HTTPS_PORT: u16 = 443;
 static DEFAULT_QUIC_PORT: u16 = 853; // https://www.rfc-editor.org/rfc/rfc9250.html#name-reservation-of-a-dedicated-
 static DEFAULT_H3_PORT: u16 = 443;
 static DEFAULT_TCP_REQUEST_TIMEOUT: u64 = 5;
+#[cfg(feature = "prometheus-metrics")]
+static DEFAULT_QUERY_STATISTICS_TOP_N: usize = 10;
+#[cfg(feature = "prometheus-metrics")]
+static DEFAULT_QUERY_STATISTICS_SAMPLE_RATE: u32 = 100;
+#[cfg(feature = "__tls")]
+static DEFAULT_TLS_CERT_RELOAD_INTERVAL: u64 = 60;
 
 /// Server configuration
 #[derive(Deserialize, Debug)]
@@ -114,6 +134,9 @@ pub struct Config {
     /// Disable Prometheus metrics
     #[cfg(feature = "prometheus-metrics")]
     disable_prometheus: Option<bool>,
+    /// Collect per-zone query statistics, reported next to the Prometheus metrics
+    #[cfg(feature = "prometheus-metrics")]
+    query_statistics: Option<QueryStatisticsConfig>,
     /// Timeout associated to a request before it is closed.
     tcp_request_timeout: Option<u64>,
     /// Level at which to log, default is INFO
@@ -134,9 +157,15 @@ pub struct Config {
     #[serde(default)]
     #[serde(deserialize_with = "deserialize_with_file")]
     zones: Vec<ZoneConfig>,
+    /// Limit of the estimated memory in bytes used by the records of all primary and secondary
+    /// zones, and the entries of all block lists, together
+    max_zone_memory: Option<u64>,
     /// Certificate to associate to TLS connections (currently the same is used for HTTPS and TLS)
     #[cfg(feature = "__tls")]
     tls_cert: Option<TlsCertConfig>,
+    /// Obtain and renew the certificate for TLS connections with ACME, instead of `tls_cert`
+    #[cfg(feature = "__acme")]
+    acme: Option<AcmeConfig>,
     /// The HTTP endpoint where the DNS-over-HTTPS server provides service. Applicable
     /// to both HTTP/2 and HTTP/3 servers. Typically `/dns-query`.
     #[cfg(any(feature = "__https", feature = "__h3"))]
@@ -147,6 +176,9 @@ pub struct Config {
     /// Networks allowed to access the server
     #[serde(default)]
     allow_networks: Vec<IpNet>,
+    /// Limits on the decompression of names in requests
+    #[serde(default)]
+    decompression_limits: DecompressionLimits,
 }
 
 impl Config {
@@ -236,6 +268,12 @@ impl Config {
         self.disable_prometheus.unwrap_or_default()
     }
 
+    /// get the configuration of the per-zone query statistics, if they should be collected
+    #[cfg(feature = "prometheus-metrics")]
+    pub fn query_statistics(&self) -> Option<&QueryStatisticsConfig> {
+        self.query_statistics.as_ref()
+    }
+
     /// default timeout for all TCP connections before forcibly shutdown
     pub fn tcp_request_timeout(&self) -> Duration {
         Duration::from_secs(
@@ -276,6 +314,12 @@ impl Config {
         }
     }
 
+    /// the ACME configuration for obtaining the tls certificate
+    #[cfg(feature = "__acme")]
+    pub fn acme(&self) -> Option<&AcmeConfig> {
+        self.acme.as_ref()
+    }
+
     /// the HTTP endpoint from where requests are received
     #[cfg(any(feature = "__https", feature = "__h3"))]
     pub fn http_endpoint(&self) -> &str {
@@ -293,6 +337,43 @@ impl Config {
     pub fn allow_networks(&self) -> &[IpNet] {
         &self.allow_networks
     }
+
+    /// the limits on the decompression of names in requests
+    pub fn decompression_limits(&self) -> DecompressionLimits {
+        self.decompression_limits
+    }
+
+    /// the memory budget shared by the records of all zones, if limited
+    pub fn zone_memory_budget(&self) -> Option<Arc<MemoryBudget>> {
+        self.max_zone_memory
+            .map(|limit| Arc::new(MemoryBudget::new(limit)))
+    }
+}
+
+/// Configuration for per-zone query statistics
+///
+/// The statistics are served as text from the `/query-statistics` path of the Prometheus
+/// endpoint.
+#[cfg(feature = "prometheus-metrics")]
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct QueryStatisticsConfig {
+    /// Number of clients and names to report for each zone, defaults to 10
+    pub top_n: Option<usize>,
+    /// One in this many queries is sampled for the top clients and names, defaults to 100
+    pub sample_rate: Option<u32>,
+}
+
+#[cfg(feature = "prometheus-metrics")]
+impl QueryStatisticsConfig {
+    /// Construct the statistics described by this configuration
+    pub fn build(&self) -> QueryStatistics {
+        QueryStatistics::new(
+            self.top_n.unwrap_or(DEFAULT_QUERY_STATISTICS_TOP_N),
+            self.sample_rate
+                .unwrap_or(DEFAULT_QUERY_STATISTICS_SAMPLE_RATE),
+        )
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -355,8 +436,18 @@ pub struct ZoneConfig {
 }
 
 impl ZoneConfig {
+    /// Loads the authorities of the zone
+    ///
+    /// The records of primary and secondary zones, including response policy zones, and the
+    /// entries of block lists reserve their memory from `memory_budget`, see
+    /// [`Config::zone_memory_budget`]. The caches of forwarders and recursors are bounded by their
+    /// own configuration instead.
     #[warn(clippy::wildcard_enum_match_arm)] // make sure all cases are handled despite of non_exhaustive
-    pub async fn load(&self, zone_dir: &Path) -> Result<Vec<Arc<dyn Authority>>, String> {
+    pub async fn load(
+        &self,
+        zone_dir: &Path,
+        memory_budget: Option<&Arc<MemoryBudget>>,
+    ) -> Result<Vec<Arc<dyn Authority>>, String> {
         debug!("loading zone with config: {self:#?}");
 
         let zone_name = self
@@ -379,7 +470,6 @@ impl ZoneConfig {
                     let authority: Arc<dyn Authority> = match store {
                         #[cfg(feature = "sqlite")]
                         ServerStoreConfig::Sqlite(config) => {
-                            #[cfg_attr(not(feature = "__dnssec"), allow(unused_mut))]
                             let mut authority = SqliteAuthority::try_from_config(
                                 zone_name.clone(),
                                 zone_type,
@@ -391,15 +481,22 @@ impl ZoneConfig {
                                 server_config.nx_proof_kind.clone(),
                             )
                             .await?;
+                            authority.set_minimal_responses(server_config.minimal_responses);
+                            authority.set_serial_policy(server_config.serial_policy);
+                            authority.set_ttl_policy(server_config.ttl_policy);
+                            authority.set_xot_required(server_config.xot_required.clone());
 
                             #[cfg(feature = "__dnssec")]
                             dnssec::load_keys(&mut authority, &zone_name, &server_config.keys)
                                 .await?;
+                            authority.set_memory_limits(
+                                server_config.max_memory,
+                                memory_budget.cloned(),
+                            )?;
                             Arc::new(authority)
                         }
 
                         ServerStoreConfig::File(config) => {
-                            #[cfg_attr(not(feature = "__dnssec"), allow(unused_mut))]
                             let mut authority = FileAuthority::try_from_config(
                                 zone_name.clone(),
                                 zone_type,
@@ -409,12 +506,49 @@ impl ZoneConfig {
                                 #[cfg(feature = "__dnssec")]
                                 server_config.nx_proof_kind.clone(),
                             )?;
+                            authority.set_minimal_responses(server_config.minimal_responses);
+                            authority.set_serial_policy(server_config.serial_policy);
+                            authority.set_ttl_policy(server_config.ttl_policy);
+                            authority.set_xot_required(server_config.xot_required.clone());
 
                             #[cfg(feature = "__dnssec")]
                             dnssec::load_keys(&mut authority, &zone_name, &server_config.keys)
                                 .await?;
+                            authority.set_memory_limits(
+                                server_config.max_memory,
+                                memory_budget.cloned(),
+                            )?;
                             Arc::new(authority)
                         }
+
+                        #[cfg(feature = "blocklist")]
+                        ServerStoreConfig::Rpz(config) => {
+                            if zone_type != ZoneType::Primary {
+                                return Err(format!(
+                                    "rpz store is only supported for primary zones: {zone_name}"
+                                ));
+                            }
+
+                            let mut zone = RpzZone::try_from_config(
+                                zone_name.clone(),
+                                config,
+                                axfr_policy,
+                                Some(zone_dir),
+                                #[cfg(feature = "__dnssec")]
+                                server_config.nx_proof_kind.clone(),
+                            )
+                            .await?;
+                            zone.set_memory_limits(
+                                server_config.max_memory,
+                                memory_budget.cloned(),
+                            )?;
+
+                            let authority = zone.authority();
+                            if let Some(interval) = config.refresh_interval {
+                                tokio::spawn(refresh_rpz(zone, Duration::from_secs(interval)));
+                            }
+                            authority
+                        }
                         _ => return empty_stores_error(),
                     };
 
@@ -428,19 +562,27 @@ impl ZoneConfig {
                 );
 
                 #[cfg_attr(
-                    not(any(feature = "blocklist", feature = "resolver")),
+                    not(any(feature = "blocklist", feature = "intercept", feature = "resolver")),
                     allow(unreachable_code, unused_variables, clippy::never_loop)
                 )]
                 for store in stores {
                     let authority: Arc<dyn Authority> = match store {
                         #[cfg(feature = "blocklist")]
                         ExternalStoreConfig::Blocklist(config) => {
-                            Arc::new(BlocklistAuthority::try_from_config(
+                            let mut authority = BlocklistAuthority::try_from_config(
                                 zone_name.clone(),
                                 config,
                                 Some(zone_dir),
-                            )?)
+                            )?;
+                            if let Some(budget) = memory_budget {
+                                authority.set_memory_limits(None, Some(budget.clone()))?;
+                            }
+                            Arc::new(authority)
                         }
+                        #[cfg(feature = "intercept")]
+                        ExternalStoreConfig::Intercept(config) => Arc::new(
+                            InterceptAuthority::try_from_config(zone_name.clone(), config)?,
+                        ),
                         #[cfg(feature = "resolver")]
                         ExternalStoreConfig::Forward(config) => {
                             let forwarder = ForwardAuthority::builder_tokio(config.clone())
@@ -494,6 +636,18 @@ fn empty_stores_error<T>() -> Result<T, String> {
     Result::Err("empty [[zones.stores]] in config".to_owned())
 }
 
+/// Compiles the changed block lists of a response policy zone every `interval`
+#[cfg(feature = "blocklist")]
+async fn refresh_rpz(mut zone: RpzZone, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        if let Err(e) = zone.rebuild().await {
+            warn!("failed to rebuild response policy zone: {e}");
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(tag = "zone_type")]
 #[serde(deny_unknown_fields)]
@@ -530,6 +684,33 @@ pub struct ServerZoneConfig {
     /// By default, all AXFR requests are rejected
     #[serde(default)]
     pub axfr_policy: AxfrPolicy,
+    /// Which optional records are added to responses
+    ///
+    /// By default, the authority and additional sections are filled
+    #[serde(default)]
+    pub minimal_responses: MinimalResponses,
+    /// How the SOA serial is advanced after dynamic updates and when the zone is re-signed
+    ///
+    /// By default, the serial is incremented by one
+    #[serde(default)]
+    pub serial_policy: SerialPolicy,
+    /// Bounds of the TTLs of the records served from the zone
+    ///
+    /// By default, the TTLs of the records are served unchanged
+    #[serde(default)]
+    pub ttl_policy: TtlPolicy,
+    /// Networks of the secondaries which must transfer this zone over TLS (XoT, RFC 9103)
+    ///
+    /// Zone transfers from these networks over any other protocol are refused. When the TLS
+    /// certificate has a `client_ca`, the secondaries must also present a certificate signed by it
+    #[serde(default)]
+    pub xot_required: Vec<IpNet>,
+    /// Limit of the estimated memory in bytes used by the records of the zone
+    ///
+    /// Dynamic updates and rebuilds of response policy zones that would exceed the limit are
+    /// refused, and a zone that exceeds it when loaded fails to load. By default, only the shared
+    /// `max_zone_memory` applies
+    pub max_memory: Option<u64>,
     /// Keys for use by the zone
     #[cfg(feature = "__dnssec")]
     #[serde(default)]
@@ -557,6 +738,8 @@ impl ServerZoneConfig {
             ServerStoreConfig::File(file_config) => Some(&*file_config.zone_path),
             #[cfg(feature = "sqlite")]
             ServerStoreConfig::Sqlite(sqlite_config) => Some(&*sqlite_config.zone_path),
+            #[cfg(feature = "blocklist")]
+            ServerStoreConfig::Rpz(_) => None,
             ServerStoreConfig::Default => None,
         })
     }
@@ -589,6 +772,9 @@ pub enum ServerStoreConfig {
     /// Sqlite based configuration file
     #[cfg(feature = "sqlite")]
     Sqlite(SqliteConfig),
+    /// Response policy zone compiled from block lists
+    #[cfg(feature = "blocklist")]
+    Rpz(RpzConfig),
     /// This is used by the configuration processing code to represent a deprecated or main-block config without an associated store.
     #[default]
     Default,
@@ -603,6 +789,9 @@ pub enum ExternalStoreConfig {
     /// Blocklist configuration
     #[cfg(feature = "blocklist")]
     Blocklist(BlocklistConfig),
+    /// Canned responses for the queries matching a set of rules
+    #[cfg(feature = "intercept")]
+    Intercept(InterceptConfig),
     /// Forwarding Resolver
     #[cfg(feature = "resolver")]
     Forward(ForwardConfig),
@@ -659,19 +848,33 @@ where
 }
 
 /// Configuration for a TLS certificate
-#[derive(Deserialize, PartialEq, Eq, Debug)]
+#[derive(Deserialize, PartialEq, Eq, Clone, Debug)]
 #[serde(deny_unknown_fields)]
 #[non_exhaustive]
 pub struct TlsCertConfig {
     pub path: PathBuf,
     pub endpoint_name: Option<String>,
     pub private_key: PathBuf,
+    /// Seconds between checks of the certificate and private key files for changes, 0 disables
+    /// reloading them while the server runs
+    pub reload_interval: Option<u64>,
+    /// CA certificates which clients of the TLS listener must present a certificate signed by to
+    /// transfer zones over TLS; other queries do not require a certificate. Clients are not
+    /// authenticated when unset
+    pub client_ca: Option<PathBuf>,
 }
 
 #[cfg(feature = "__tls")]
 impl TlsCertConfig {
-    /// Load a Certificate from the path (with rustls)
-    pub fn load(&self, zone_dir: &Path) -> Result<Arc<dyn ResolvesServerCert>, String> {
+    /// Load a Certificate from the path (with rustls), which can be replaced while the server runs
+    pub fn load(&self, zone_dir: &Path) -> Result<Arc<ReloadableCertResolver>, String> {
+        Ok(Arc::new(ReloadableCertResolver::new(
+            self.load_certified_key(zone_dir)?,
+        )))
+    }
+
+    /// Load the certificate chain and private key from their paths (with rustls)
+    pub fn load_certified_key(&self, zone_dir: &Path) -> Result<CertifiedKey, String> {
         if self.path.extension().and_then(OsStr::to_str) != Some("pem") {
             return Err(format!(
                 "unsupported certificate file format (expected `.pem` extension): {}",
@@ -720,13 +923,123 @@ impl TlsCertConfig {
             ));
         };
 
-        let certified_key = CertifiedKey::from_der(cert_chain, key, &default_provider())
-            .map_err(|err| format!("failed to read certificate and keys: {err:?}"))?;
+        CertifiedKey::from_der(cert_chain, key, &default_provider())
+            .map_err(|err| format!("failed to read certificate and keys: {err:?}"))
+    }
+
+    /// Load the verifier of client certificates from the `client_ca` path (with rustls), if set
+    pub fn load_client_verifier(
+        &self,
+        zone_dir: &Path,
+    ) -> Result<Option<Arc<dyn ClientCertVerifier>>, String> {
+        let Some(client_ca) = &self.client_ca else {
+            return Ok(None);
+        };
+
+        let ca_path = zone_dir.join(client_ca);
+        info!(
+            "loading TLS client CA certificates from: {}",
+            ca_path.display()
+        );
+
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_file_iter(&ca_path)
+            .map_err(|e| format!("failed to read client CA from {}: {e}", ca_path.display()))?
+        {
+            let cert = cert.map_err(|e| {
+                format!("failed to parse client CA from {}: {e}", ca_path.display())
+            })?;
+            roots
+                .add(cert)
+                .map_err(|e| format!("invalid client CA in {}: {e}", ca_path.display()))?;
+        }
+
+        // clients without a certificate may still query, only zone transfers require one
+        WebPkiClientVerifier::builder_with_provider(Arc::new(roots), Arc::new(default_provider()))
+            .allow_unauthenticated()
+            .build()
+            .map(Some)
+            .map_err(|e| format!("failed to build client certificate verifier: {e}"))
+    }
 
-        Ok(Arc::new(SingleCertAndKey::from(certified_key)))
+    /// Interval between checks of the certificate and private key files for changes
+    pub fn reload_interval(&self) -> Option<Duration> {
+        match self
+            .reload_interval
+            .unwrap_or(DEFAULT_TLS_CERT_RELOAD_INTERVAL)
+        {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
     }
 }
 
+/// Configuration for obtaining the TLS certificate with ACME
+#[cfg(feature = "__acme")]
+#[derive(Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct AcmeConfig {
+    /// The directory of the ACME server, defaults to Let's Encrypt
+    pub directory: Option<String>,
+    /// The domains of the certificate, the first is the endpoint name
+    ///
+    /// This server must be authoritative for the zones of the domains, as it answers the DNS-01
+    /// challenges itself.
+    pub domains: Vec<Name>,
+    /// The contact URLs of the account, e.g. `mailto:hostmaster@example.com`
+    #[serde(default)]
+    pub contacts: Vec<String>,
+    /// Seconds before the expiration of the certificate it is renewed, defaults to 30 days
+    pub renew_before: Option<u64>,
+    /// The PEM files the certificate chain, its private key and the account key are stored in,
+    /// relative to the zone directory, so that they are reused after a restart
+    pub cache: Option<AcmeCacheConfig>,
+}
+
+#[cfg(feature = "__acme")]
+impl AcmeConfig {
+    /// Creates the ACME client for the certificate
+    pub fn build(&self, zone_dir: &Path) -> Result<Acme, AcmeError> {
+        let directory = self.directory.as_deref().unwrap_or(LETS_ENCRYPT_DIRECTORY);
+        let mut builder =
+            Acme::builder(directory, self.domains.clone()).with_contacts(self.contacts.clone());
+        if let Some(renew_before) = self.renew_before {
+            builder = builder.with_renew_before(Duration::from_secs(renew_before));
+        }
+        if let Some(cache) = &self.cache {
+            builder = builder
+                .with_cache(
+                    zone_dir.join(&cache.certificate),
+                    zone_dir.join(&cache.private_key),
+                )
+                .with_account_key(zone_dir.join(&cache.account_key));
+        }
+
+        builder.build()
+    }
+
+    /// The name of the server in the certificate
+    pub fn endpoint_name(&self) -> Option<String> {
+        let name = self.domains.first()?.to_ascii();
+        Some(name.strip_suffix('.').unwrap_or(&name).to_owned())
+    }
+}
+
+/// Files of the certificate obtained with ACME
+#[cfg(feature = "__acme")]
+#[derive(Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct AcmeCacheConfig {
+    /// The PEM file of the certificate chain
+    pub certificate: PathBuf,
+    /// The PEM file of the PKCS#8 private key
+    pub private_key: PathBuf,
+    /// The PEM file of the PKCS#8 key of the ACME account, created on the first start
+    pub account_key: PathBuf,
+}
+
 #[cfg(all(test, any(feature = "resolver", feature = "recursor")))]
 mod tests {
     use super::*;
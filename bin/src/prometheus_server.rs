@@ -1,4 +1,5 @@
 use std::future::{Ready, ready};
+use std::sync::Arc;
 
 use http::header::CONTENT_TYPE;
 use hyper::{Request, Response, body::Incoming, service::Service};
@@ -11,6 +12,11 @@ use tokio::{net::TcpListener, select, task::JoinHandle};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error};
 
+use hickory_server::authority::QueryStatistics;
+
+/// Path at which the query statistics are served, all other paths serve the metrics
+const QUERY_STATISTICS_PATH: &str = "/query-statistics";
+
 /// An HTTP server that responds to Prometheus scrape requests.
 pub struct PrometheusServer {
     join_handle: JoinHandle<()>,
@@ -20,13 +26,18 @@ pub struct PrometheusServer {
 impl PrometheusServer {
     /// Register a metrics recorder, and start an HTTP server with the provided listener to provide
     /// metrics to Prometheus.
-    pub fn new(listener: TcpListener) -> Result<Self, String> {
+    ///
+    /// If `statistics` are provided, a report of them is served at `/query-statistics`.
+    pub fn new(
+        listener: TcpListener,
+        statistics: Option<Arc<QueryStatistics>>,
+    ) -> Result<Self, String> {
         // Set up metrics recorder.
         let handle = PrometheusBuilder::new()
             .install_recorder()
             .map_err(|e| format!("failed to install prometheus endpoint {e}"))?;
 
-        let service = PrometheusService::new(handle);
+        let service = PrometheusService::new(handle, statistics);
         let cancellation_token = CancellationToken::new();
         let token_clone = cancellation_token.clone();
         let shutdown = GracefulShutdown::new();
@@ -78,11 +89,20 @@ impl PrometheusServer {
 #[derive(Clone)]
 struct PrometheusService {
     handle: PrometheusHandle,
+    statistics: Option<Arc<QueryStatistics>>,
 }
 
 impl PrometheusService {
-    fn new(handle: PrometheusHandle) -> Self {
-        Self { handle }
+    fn new(handle: PrometheusHandle, statistics: Option<Arc<QueryStatistics>>) -> Self {
+        Self { handle, statistics }
+    }
+
+    fn render_statistics(statistics: &QueryStatistics) -> String {
+        statistics
+            .report()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
     }
 }
 
@@ -94,10 +114,17 @@ impl Service<Request<Incoming>> for PrometheusService {
     type Future =
         Ready<Result<Response<String>, Box<dyn std::error::Error + Send + Sync + 'static>>>;
 
-    fn call(&self, _req: Request<Incoming>) -> Self::Future {
-        let response_builder =
-            Response::builder().header(CONTENT_TYPE, "text/plain; version=0.0.4");
-        match response_builder.body(self.handle.render()) {
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        let response = match &self.statistics {
+            Some(statistics) if req.uri().path() == QUERY_STATISTICS_PATH => Response::builder()
+                .header(CONTENT_TYPE, "text/plain")
+                .body(Self::render_statistics(statistics)),
+            _ => Response::builder()
+                .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+                .body(self.handle.render()),
+        };
+
+        match response {
             Ok(response) => ready(Ok(response)),
             Err(e) => ready(Err(Box::new(e))),
         }
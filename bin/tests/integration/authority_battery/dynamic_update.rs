@@ -4,6 +4,7 @@ use std::{
     net::{Ipv4Addr, SocketAddr},
     path::PathBuf,
     str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use futures_executor::block_on;
@@ -29,12 +30,30 @@ use hickory_server::{
 
 const TEST_HEADER: &Header = &Header::new(10, MessageType::Query, OpCode::Query);
 
+fn now() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time went backwards")
+        .as_secs() as u32
+}
+
 fn update_authority(
+    message: Message,
+    key: &SigSigner,
+    authority: &mut impl Authority,
+) -> UpdateResult<bool> {
+    update_authority_at(message, key, now(), authority)
+}
+
+fn update_authority_at(
     mut message: Message,
     key: &SigSigner,
+    inception_time: u32,
     authority: &mut impl Authority,
 ) -> UpdateResult<bool> {
-    message.finalize(key, 1).expect("failed to sign message");
+    message
+        .finalize(key, inception_time)
+        .expect("failed to sign message");
     let bytes = message.to_bytes().unwrap();
     let request = Request::from_bytes(
         bytes,
@@ -727,6 +746,83 @@ pub fn test_delete_all(mut authority: impl Authority, keys: &[SigSigner]) {
     }
 }
 
+pub fn test_expired_signature(mut authority: impl Authority, keys: &[SigSigner]) {
+    let name = Name::from_str("expired.example.com.").unwrap();
+    for key in keys {
+        let name = Name::from_str(key.key().algorithm().as_str())
+            .unwrap()
+            .append_name(&name)
+            .unwrap();
+
+        let record = Record::from_rdata(name.clone(), 8, RData::A(A4::new(127, 0, 0, 10)));
+        let message = update_message::create(
+            record.clone().into(),
+            Name::from_str("example.com.").unwrap(),
+            true,
+        );
+
+        // SIG(0) signatures expire five minutes after their inception
+        assert_eq!(
+            update_authority_at(message, key, now() - 60 * 60, &mut authority),
+            Err(ResponseCode::Refused)
+        );
+
+        let request = Request::from_message(
+            MessageRequest::mock(*TEST_HEADER, Query::query(name, RecordType::A)),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 53)),
+            Protocol::Udp,
+        )
+        .unwrap();
+
+        let lookup = block_on(authority.search(&request, LookupOptions::default()));
+        assert_eq!(
+            *lookup.0.unwrap_err().as_response_code().unwrap(),
+            ResponseCode::NXDomain
+        );
+    }
+}
+
+pub fn test_signature_within_window(mut authority: impl Authority, keys: &[SigSigner]) {
+    let name = Name::from_str("window.example.com.").unwrap();
+    for key in keys {
+        let name = Name::from_str(key.key().algorithm().as_str())
+            .unwrap()
+            .append_name(&name)
+            .unwrap();
+
+        let record = Record::from_rdata(name.clone(), 8, RData::A(A4::new(127, 0, 0, 10)));
+        let message = update_message::create(
+            record.clone().into(),
+            Name::from_str("example.com.").unwrap(),
+            true,
+        );
+
+        // signed a minute ago, still within the five minute validity period
+        assert!(
+            update_authority_at(message, key, now() - 60, &mut authority).expect("create failed")
+        );
+
+        let request = Request::from_message(
+            MessageRequest::mock(*TEST_HEADER, Query::query(name, RecordType::A)),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 53)),
+            Protocol::Udp,
+        )
+        .unwrap();
+
+        let lookup = block_on(authority.search(&request, LookupOptions::default()))
+            .0
+            .unwrap();
+        assert_eq!(
+            lookup
+                .into_iter()
+                .next()
+                .expect("A record not found in authority")
+                .data(),
+            &RData::A(A4::new(127, 0, 0, 10))
+        );
+    }
+}
+
 pub fn add_auth<A: DnssecAuthority>(authority: &mut A) -> Vec<SigSigner> {
     let update_name = Name::from_str("update")
         .unwrap()
@@ -809,6 +905,8 @@ macro_rules! dynamic_update {
                     test_delete_by_rdata_multi,
                     test_delete_rrset,
                     test_delete_all,
+                    test_expired_signature,
+                    test_signature_within_window,
                 );
             }
         }
@@ -27,7 +27,12 @@ use toml::value::Array;
 use toml::{Table, Value};
 
 use hickory_dns::{Config, ServerZoneConfig};
+#[cfg(all(feature = "resolver", feature = "__tls"))]
+use hickory_dns::{ExternalStoreConfig, ZoneTypeConfig};
+use hickory_proto::serialize::binary::DecompressionLimits;
 use hickory_server::authority::ZoneType;
+#[cfg(all(feature = "resolver", feature = "__tls"))]
+use hickory_server::store::forwarder::ForwardAuthority;
 
 #[test]
 fn test_read_config() {
@@ -226,6 +231,249 @@ tls_listen_port = 8853
         config.tls_cert().unwrap().path,
         Path::new("path/to/some.pkcs12")
     );
+    assert_eq!(
+        config.tls_cert().unwrap().reload_interval(),
+        Some(Duration::from_secs(60))
+    );
+
+    let config = Config::from_toml(
+        "tls_cert = { path = \"cert.pem\", private_key = \"key.pem\", reload_interval = 0 }",
+    )
+    .unwrap();
+
+    assert_eq!(config.tls_cert().unwrap().reload_interval(), None);
+}
+
+#[test]
+#[cfg(all(feature = "resolver", feature = "__tls"))]
+fn test_parse_forwarder_tls() {
+    let workspace = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "..".to_owned());
+    let ca_file = PathBuf::from(workspace).join("tests/test-data/ca.pem");
+    let config = Config::from_toml(&format!(
+        "[[zones]]
+zone = \".\"
+zone_type = \"External\"
+[zones.stores]
+type = \"forward\"
+name_servers = [{{ ip = \"192.0.2.53\", connections = [{{ protocol = {{ type = \"tls\", server_name = \"ns.example.com\" }} }}] }}]
+tls = [{{ server_name = \"ns.example.com\", ca_file = {ca_file:?} }}]
+",
+    ))
+    .unwrap();
+
+    let ZoneTypeConfig::External { stores } = &config.zones()[0].zone_type_config else {
+        panic!("expected external zone");
+    };
+    let ExternalStoreConfig::Forward(forward) = &stores[0] else {
+        panic!("expected forward store");
+    };
+    assert_eq!(forward.tls[0].ca_file.as_deref(), Some(ca_file.as_path()));
+    assert!(forward.tls[0].client_config().is_ok());
+    assert!(
+        ForwardAuthority::builder_tokio(forward.clone())
+            .build()
+            .is_ok()
+    );
+
+    // the settings must apply to an encrypted upstream connection
+    let mut other = forward.clone();
+    other.tls[0].server_name = "other.example.com".to_owned();
+    assert!(ForwardAuthority::builder_tokio(other).build().is_err());
+
+    // client certificates need their private key
+    let mut forward = forward.clone();
+    forward.tls[0].client_cert = Some(PathBuf::from("cert.pem"));
+    assert!(forward.tls[0].client_config().is_err());
+}
+
+#[test]
+#[cfg(feature = "blocklist")]
+fn test_parse_rpz() {
+    use hickory_dns::ServerStoreConfig;
+    use hickory_server::store::rpz::RpzAction;
+
+    let config = Config::from_toml(
+        "[[zones]]
+zone = \"rpz.example\"
+zone_type = \"Primary\"
+axfr_policy = \"AllowAll\"
+[zones.stores]
+type = \"rpz\"
+lists = [\"default/blocklist.txt\", \"default/blocklist2.txt\"]
+action = \"Nodata\"
+refresh_interval = 60
+",
+    )
+    .unwrap();
+
+    let zone = &config.zones()[0];
+    let ServerStoreConfig::Rpz(rpz) = &zone.zone_type_config.as_server().unwrap().stores[0] else {
+        panic!("expected rpz store");
+    };
+    assert_eq!(rpz.lists.len(), 2);
+    assert_eq!(rpz.action, RpzAction::Nodata);
+    assert_eq!(rpz.ttl, 300);
+    assert_eq!(rpz.refresh_interval, Some(60));
+
+    let workspace = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "..".to_owned());
+    let zone_dir = PathBuf::from(workspace).join("tests/test-data/test_configs");
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let authorities = runtime.block_on(zone.load(&zone_dir, None)).unwrap();
+    assert_eq!(authorities.len(), 1);
+    assert_eq!(authorities[0].zone_type(), ZoneType::Primary);
+}
+
+#[test]
+#[cfg(all(feature = "intercept", feature = "resolver"))]
+fn test_parse_intercept() {
+    use hickory_dns::{ExternalStoreConfig, ZoneTypeConfig};
+    use hickory_server::proto::op::ResponseCode;
+    use hickory_server::proto::rr::RecordType;
+
+    let workspace = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "..".to_owned());
+    let zone_dir = PathBuf::from(workspace).join("tests/test-data/test_configs");
+    let config = Config::read_config(&zone_dir.join("chained_intercept.toml")).unwrap();
+
+    let zone = config.zones().last().unwrap();
+    let ZoneTypeConfig::External { stores } = &zone.zone_type_config else {
+        panic!("expected external zone");
+    };
+    let ExternalStoreConfig::Intercept(intercept) = &stores[0] else {
+        panic!("expected intercept store");
+    };
+    assert_eq!(intercept.ttl, 60);
+    assert_eq!(intercept.rules.len(), 3);
+    assert_eq!(intercept.rules[0].types, vec![RecordType::A]);
+    assert_eq!(intercept.rules[1].rcode, Some(ResponseCode::NXDomain));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let authorities = runtime.block_on(zone.load(&zone_dir, None)).unwrap();
+    assert_eq!(authorities.len(), 2);
+}
+
+#[test]
+fn test_parse_decompression_limits() {
+    let config = Config::from_toml(
+        "[decompression_limits]
+max_pointer_jumps = 8
+",
+    )
+    .unwrap();
+    let limits = config.decompression_limits();
+    assert_eq!(limits.max_pointer_jumps, 8);
+    assert_eq!(limits.max_name_length, 255);
+    assert_eq!(limits.max_expansion, 1 << 20);
+
+    let config = Config::from_toml("").unwrap();
+    assert_eq!(
+        config.decompression_limits(),
+        DecompressionLimits::default()
+    );
+}
+
+#[test]
+fn test_parse_memory_limits() {
+    let config = Config::from_toml(
+        "max_zone_memory = 1000000
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Primary\"
+max_memory = 100
+[zones.stores]
+type = \"file\"
+zone_path = \"example.com.zone\"
+",
+    )
+    .unwrap();
+    assert_eq!(server_zone(&config, 0).max_memory, Some(100));
+
+    let workspace = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "..".to_owned());
+    let zone_dir = PathBuf::from(workspace).join("tests/test-data/test_configs");
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let budget = config.zone_memory_budget().unwrap();
+    assert_eq!(budget.limit(), 1_000_000);
+
+    // the zone exceeds its own limit
+    let zone = &config.zones()[0];
+    assert!(
+        runtime
+            .block_on(zone.load(&zone_dir, Some(&budget)))
+            .is_err()
+    );
+    assert_eq!(budget.used(), 0);
+
+    let config = Config::from_toml(
+        "[[zones]]
+zone = \"example.com\"
+zone_type = \"Primary\"
+[zones.stores]
+type = \"file\"
+zone_path = \"example.com.zone\"
+",
+    )
+    .unwrap();
+    assert!(config.zone_memory_budget().is_none());
+
+    let zone = &config.zones()[0];
+    let authorities = runtime
+        .block_on(zone.load(&zone_dir, Some(&budget)))
+        .unwrap();
+    assert!(budget.used() > 0);
+
+    drop(authorities);
+    assert_eq!(budget.used(), 0);
+}
+
+#[test]
+#[cfg(feature = "prometheus-metrics")]
+fn test_parse_query_statistics() {
+    let config = Config::from_toml("").unwrap();
+    assert!(config.query_statistics().is_none());
+
+    let config = Config::from_toml("query_statistics = {}").unwrap();
+    let statistics = config.query_statistics().unwrap();
+    assert_eq!(statistics.top_n, None);
+    assert_eq!(statistics.sample_rate, None);
+
+    let config = Config::from_toml(
+        "[query_statistics]
+top_n = 5
+sample_rate = 1000
+",
+    )
+    .unwrap();
+    let statistics = config.query_statistics().unwrap();
+    assert_eq!(statistics.top_n, Some(5));
+    assert_eq!(statistics.sample_rate, Some(1000));
+
+    assert!(Config::from_toml("query_statistics = { top = 5 }").is_err());
+}
+
+#[test]
+#[cfg(feature = "__acme")]
+fn test_parse_acme() {
+    let config = Config::from_toml(
+        "[acme]
+domains = [\"ns.example.com\", \"*.example.com\"]
+contacts = [\"mailto:hostmaster@example.com\"]
+cache = { certificate = \"acme/cert.pem\", private_key = \"acme/key.pem\", account_key = \"acme/account.pem\" }
+",
+    )
+    .unwrap();
+
+    let acme = config.acme().unwrap();
+    assert_eq!(acme.domains.len(), 2);
+    assert_eq!(acme.endpoint_name().as_deref(), Some("ns.example.com"));
+    assert_eq!(
+        acme.cache.as_ref().unwrap().certificate,
+        Path::new("acme/cert.pem")
+    );
+
+    // the account key is created on the first start
+    let zone_dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(zone_dir.path().join("acme")).unwrap();
+    assert!(acme.build(zone_dir.path()).is_ok());
+    assert!(zone_dir.path().join("acme/account.pem").exists());
 }
 
 fn test_config(path: &str) {
@@ -254,6 +502,8 @@ define_test_config!(all_supported_dnssec);
 define_test_config!(chained_blocklist);
 #[cfg(feature = "blocklist")]
 define_test_config!(consulting_blocklist);
+#[cfg(all(feature = "intercept", feature = "resolver"))]
+define_test_config!(chained_intercept);
 #[cfg(feature = "__https")]
 define_test_config!(dns_over_https);
 #[cfg(feature = "__tls")]
@@ -476,6 +726,13 @@ fn test_reject_unknown_fields() {
                     break;
                 }
 
+                #[cfg(not(feature = "intercept"))]
+                if _store_type == "intercept" {
+                    println!("skipping due to intercept store");
+                    skip = true;
+                    break;
+                }
+
                 #[cfg(not(feature = "sqlite"))]
                 if _store_type == "sqlite" {
                     println!("skipping due to sqlite store");
@@ -10,7 +10,6 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 #[cfg(all(feature = "__dnssec", feature = "sqlite"))]
-use std::{env, path::Path};
 use test_support::subscribe;
 
 use hickory_client::client::{Client, ClientHandle};
@@ -33,7 +32,7 @@ use rustls_pki_types::PrivatePkcs8KeyDer;
 use tokio::runtime::Runtime;
 use tokio::time::sleep;
 
-use crate::server_harness::{ServerProtocol, SocketPorts, named_test_harness};
+use crate::server_harness::{ServerProtocol, SocketPorts, TestConfigFile, named_test_harness};
 
 #[test]
 fn test_prometheus_endpoint_startup() {
@@ -275,6 +274,13 @@ fn test_request_response() {
             verify_metric(metrics, "hickory_response_flags_total", &flag, Some(value))
         });
 
+        verify_metric(
+            metrics,
+            "hickory_request_duration_seconds_count",
+            &[("zone", "localhost."), ("code", "no_error")],
+            Some(1f64),
+        );
+
         // check store lookups
         verify_metric(
             metrics,
@@ -307,6 +313,7 @@ fn test_request_response() {
 #[cfg(all(feature = "__dnssec", feature = "sqlite"))]
 fn test_updates() {
     subscribe();
+    let _journal = TestConfigFile::new("example.com_dnssec_update_2.jrnl");
 
     named_test_harness("dnssec_with_update_2.toml", |socket_ports| {
         let io_loop = Runtime::new().unwrap();
@@ -392,13 +399,6 @@ fn test_updates() {
             Some(1f64),
         );
     });
-
-    // Clean up database.
-    let server_path = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "..".to_owned());
-    let server_path = Path::new(&server_path);
-    let database =
-        server_path.join("tests/test-data/test_configs/example.com_dnssec_update_2.jrnl");
-    std::fs::remove_file(&database).expect("failed to cleanup after test");
 }
 
 async fn create_local_client(
@@ -445,7 +445,13 @@ fn test_metrics_description_present(scrape: Scrape) {
         .samples
         .into_iter()
         .filter_map(|s| {
-            if !scrape.docs.contains_key(&s.metric) {
+            // summaries and histograms are described by their base name
+            let metric = s
+                .metric
+                .strip_suffix("_sum")
+                .or_else(|| s.metric.strip_suffix("_count"))
+                .unwrap_or(&s.metric);
+            if !scrape.docs.contains_key(metric) {
                 Some(s.metric)
             } else {
                 None
@@ -8,7 +8,7 @@ use std::sync::Arc;
 
 use tokio::runtime::Runtime;
 
-use crate::server_harness::{named_test_harness, query_a, query_all_dnssec};
+use crate::server_harness::{TestConfigFile, named_test_harness, query_a, query_all_dnssec};
 use hickory_client::client::Client;
 use hickory_dns::dnssec::key_from_file;
 use hickory_proto::dnssec::{Algorithm, DnssecDnsHandle, TrustAnchors};
@@ -148,11 +148,8 @@ fn test_rsa_sha1_fails() {
 fn test_dnssec_restart_with_update_journal() {
     subscribe();
 
-    // TODO: make journal path configurable, it should be in target/tests/...
-    let server_path = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "..".to_owned());
-    let server_path = Path::new(&server_path);
-    let journal = server_path.join("tests/test-data/test_configs/example.com_dnssec_update.jrnl");
-    std::fs::remove_file(&journal).ok();
+    let journal = TestConfigFile::new("example.com_dnssec_update.jrnl");
+    let journal = journal.path();
 
     generic_test(
         "dnssec_with_update.toml",
@@ -172,8 +169,4 @@ fn test_dnssec_restart_with_update_journal() {
 
     // and journal should still exist
     assert!(journal.exists());
-
-    // cleanup...
-    // TODO: fix journal path so that it doesn't leave the dir dirty... this might make windows an option after that
-    std::fs::remove_file(&journal).expect("failed to cleanup after test");
 }
@@ -13,6 +13,9 @@ use std::{
     time::*,
 };
 
+#[cfg(all(feature = "__dnssec", feature = "sqlite"))]
+use std::path::{Path, PathBuf};
+
 use regex::Regex;
 use tokio::runtime::Runtime;
 use tracing::{info, warn};
@@ -29,6 +32,36 @@ use hickory_proto::{
     xfer::Protocol,
 };
 
+/// A file the server under test writes to the test configurations, such as a journal
+///
+/// The file is removed when created and again when dropped, also if the test panics, so that tests
+/// don't leave the test configurations dirty.
+#[cfg(all(feature = "__dnssec", feature = "sqlite"))]
+pub struct TestConfigFile(PathBuf);
+
+#[cfg(all(feature = "__dnssec", feature = "sqlite"))]
+impl TestConfigFile {
+    pub fn new(name: &str) -> Self {
+        let server_path = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "..".to_owned());
+        let path = Path::new(&server_path)
+            .join("tests/test-data/test_configs")
+            .join(name);
+        std::fs::remove_file(&path).ok();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(all(feature = "__dnssec", feature = "sqlite"))]
+impl Drop for TestConfigFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.0).ok();
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct SocketPort {
     v4: u16,
@@ -12,12 +12,11 @@ const NON_EXISTENT_FQDN: &str = "charlie.alice.com.";
 const WILDCARD_FQDN: &str = "*.alice.com.";
 const NSEC3_OWNER_FQDN: &str = "llkh4l6i60vhapp6vrm3dfr9ri8ak9i0.alice.com.";
 
-// These hashes are computed with 1 iteration of SHA-1 without salt and must be recomputed if
-// those parameters were to change.
-const TLD_HASH: &str = "LLKH4L6I60VHAPP6VRM3DFR9RI8AK9I0"; /* h(alice.com.) */
-const NON_EXISTENT_HASH: &str = "99P1CCPQ2N64LIRMT2838O4HK0QFA51B"; /* h(charlie.alice.com.) */
-const WILDCARD_HASH: &str = "19GBV5V1BO0P51H34JQDH1C8CIAA5RAQ"; /* h(*.alice.com.) */
-const NSEC3_OWNER_HASH: &str = "T5LJ8DV3O2C0BNVLRRUTQ2NKPQE3N385"; /* h(llkh4l6i60vhapp6vrm3dfr9ri8ak9i0.alice.com.) */
+// The NSEC3 hash of `fqdn` with the parameters `ldns-signzone` uses by default: 1 iteration of SHA-1
+// without salt.
+fn nsec3_hash(fqdn: &'static str) -> String {
+    dns_test::nsec3::nsec3_hash(&FQDN(fqdn).unwrap(), None, 1, 1)
+}
 
 // This test checks that name servers produce a name error response compliant with section 7.2.2.
 // of RFC5155.
@@ -44,9 +43,8 @@ fn name_error_response() -> Result<()> {
     // The next closer name of a name is the name one label longer than its closest encloser. In
     // this scenario, the closest encloser is `alice.com.` which means that the next closer name is `charlie.alice.com.`
 
-    // If this panics, it probably means that the precomputed hashes must be recomputed.
     let (closest_encloser_rr, next_closer_name_rr) = nsec3_rrs
-        .closest_encloser_proof(TLD_HASH, NON_EXISTENT_HASH)
+        .closest_encloser_proof(&nsec3_hash(TLD_FQDN), &nsec3_hash(NON_EXISTENT_FQDN))
         .expect("Cannot find a closest encloser proof in the zonefile");
 
     // Wildcard at the closet encloser RR: Must cover the wildcard at the closest encloser of
@@ -57,9 +55,8 @@ fn name_error_response() -> Result<()> {
     //
     // This NSEC3 RR must cover the hash of the wildcard at the closests encloser.
 
-    // if this panics, it probably means that the precomputed hashes must be recomputed.
     let wildcard_rr = nsec3_rrs
-        .find_cover(WILDCARD_HASH)
+        .find_cover(&nsec3_hash(WILDCARD_FQDN))
         .expect("No RR in the zonefile covers the wildcard");
 
     // Now we check that the response has the three NSEC3 RRs.
@@ -99,9 +96,8 @@ fn no_data_response_not_ds() -> Result<()> {
 
     // The server MUST include the NSEC3 RR that matches QNAME.
 
-    // if this panics, it probably means that the precomputed hashes must be recomputed.
     let qname_rr = nsec3_rrs
-        .find_match(TLD_HASH)
+        .find_match(&nsec3_hash(TLD_FQDN))
         .expect("No RR in the zonefile matches QNAME");
 
     find_records(
@@ -130,9 +126,8 @@ fn no_data_response_ds_match() -> Result<()> {
 
     // If there is an NSEC3 RR that matches QNAME, the server MUST return it in the response.
 
-    // if this panics, it probably means that the precomputed hashes must be recomputed.
     let qname_rr = nsec3_rrs
-        .find_match(TLD_HASH)
+        .find_match(&nsec3_hash(TLD_FQDN))
         .expect("No RR in the zonefile matches QNAME");
 
     find_records(
@@ -171,9 +166,8 @@ fn no_data_response_ds_no_match() -> Result<()> {
     // The next closer name of a name is the name one label longer than its closest encloser. In
     // this scenario, the closest encloser is `alice.com.` which means that the next closer name is `charlie.alice.com.`
 
-    // If this panics, it probably means that the precomputed hashes must be recomputed.
     let (closest_encloser_rr, next_closer_name_rr) = nsec3_rrs
-        .closest_encloser_proof(TLD_HASH, NON_EXISTENT_HASH)
+        .closest_encloser_proof(&nsec3_hash(TLD_FQDN), &nsec3_hash(NON_EXISTENT_FQDN))
         .expect("Cannot find a closest encloser proof in the zonefile");
 
     find_records(
@@ -222,16 +216,14 @@ fn wildcard_no_data_response() -> Result<()> {
     // The next closer name of a name is the name one label longer than its closest encloser. In
     // this scenario, the closest encloser is `alice.com.` which means that the next closer name is `charlie.alice.com.`
 
-    // If this panics, it probably means that the precomputed hashes must be recomputed.
     let (closest_encloser_rr, next_closer_name_rr) = nsec3_rrs
-        .closest_encloser_proof(TLD_HASH, NON_EXISTENT_HASH)
+        .closest_encloser_proof(&nsec3_hash(TLD_FQDN), &nsec3_hash(NON_EXISTENT_FQDN))
         .expect("Cannot find a closest encloser proof in the zonefile");
 
     // Wildcard RR: This NSEC3 RR must match `*.alice.com`.
 
-    // If this panics, it probably means that the precomputed hashes must be recomputed.
     let wildcard_rr = nsec3_rrs
-        .find_match(WILDCARD_HASH)
+        .find_match(&nsec3_hash(WILDCARD_FQDN))
         .expect("No RR in the zonefile matches the wildcard");
 
     find_records(
@@ -275,9 +267,8 @@ fn wildcard_answer_response() -> Result<()> {
     // The next closer name of a name is the name one label longer than its closest encloser. In
     // this scenario, the closest encloser is `alice.com.` which means that the next closer name is `charlie.alice.com.`
 
-    // If this panics, it probably means that the precomputed hashes must be recomputed.
     let next_closer_name_rr = nsec3_rrs
-        .find_cover(NON_EXISTENT_HASH)
+        .find_cover(&nsec3_hash(NON_EXISTENT_FQDN))
         .expect("No RR in the zonefile covers the next closer name");
 
     find_records(
@@ -311,11 +302,11 @@ fn nsec3_owner_name() -> Result<()> {
     // This is the NSEC3 record that matches the query name. The authoritative server should still
     // send an NXDOMAIN response as if this NSEC3 record does not exist.
     let _matching_nsec3_record = nsec3_rrs
-        .find_match(TLD_HASH)
+        .find_match(&nsec3_hash(TLD_FQDN))
         .expect("Query name is not the owner name of any NSEC3 RR");
 
     let cover = nsec3_rrs
-        .find_cover(NSEC3_OWNER_HASH)
+        .find_cover(&nsec3_hash(NSEC3_OWNER_FQDN))
         .expect("No RR in the zonefile covers the query name");
 
     find_records(
@@ -9,7 +9,7 @@ use dns_test::{
     FQDN, Network, Resolver, Result,
     client::{Client, DigSettings},
     name_server::NameServer,
-    record::{RRSIG, RecordType, SOA},
+    record::{RRSIG, RecordType, SOA, Timestamp},
     zone_file::SignSettings,
 };
 
@@ -151,9 +151,6 @@ fn rrsig_and_rr_return_the_same_adjusted_ttl() -> Result<()> {
 /// Check that Serial Number arithemtics support the case where the timesamp is `1 << 31` beyond UNIX_EPOCH.
 #[test]
 fn rrsig_rr_expiration_time_is_1_to_the_power_of_31_beyond_unix_epoch() -> Result<()> {
-    // The representation in the record uses format `YYYYMMDDhhmmss`
-    const MAX_UNIX_TIMESTAMP: u64 = 20380119031408;
-
     let settings = SignSettings::default().expiration_from_u64(1 << 31);
 
     let network = &Network::new()?;
@@ -180,7 +177,7 @@ fn rrsig_rr_expiration_time_is_1_to_the_power_of_31_beyond_unix_epoch() -> Resul
     let rrsig: RRSIG = rrsig.try_into_rrsig().unwrap();
 
     assert_eq!(soa.ttl, rrsig.ttl);
-    assert_eq!(MAX_UNIX_TIMESTAMP, rrsig.signature_expiration);
+    assert_eq!(1 << 31, rrsig.signature_expiration.as_unix());
 
     Ok(())
 }
@@ -195,7 +192,7 @@ fn rrsig_rr_expiration_time_is_1_to_the_power_of_32_beyond_unix_epoch() -> Resul
 
     // `1 << 32` from Unix Epoch results in Sunday, February 7, 2106 6:28:16 AM
     if let Some(rrsig) = ns.signed_zone_file_mut().rrsig_mut(RecordType::SOA) {
-        rrsig.signature_expiration = 21060207062816;
+        rrsig.signature_expiration = Timestamp::from_unix(1 << 32);
     }
 
     let ns = ns.start()?;
@@ -1,12 +1,12 @@
 mod no_rrsig_dnskey;
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::Duration;
 
 use dns_test::{
     FQDN, Network, PEER, Resolver, Result, TrustAnchor,
     client::{Client, DigOutput, DigSettings, ExtendedDnsError},
     name_server::{Graph, NameServer, Sign},
-    record::{DNSKEY, DNSKEYRData, DS, RRSIG, Record, RecordType},
+    record::{DNSKEY, DNSKEYRData, DS, RRSIG, Record, RecordType, Timestamp},
     zone_file::{SignSettings, Signer},
 };
 
@@ -436,10 +436,7 @@ AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
         },
     });
     // Add some RRSIG records, to skip an early check for a non-empty DS RRset.
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    let now = Timestamp::now();
     leaf_ns.add(RRSIG {
         fqdn: leaf_zone.clone(),
         ttl: 86400,
@@ -448,8 +445,8 @@ AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
         labels: 2,
         key_tag: 0,
         original_ttl: 86400,
-        signature_expiration: now + 3600 * 24,
-        signature_inception: now - 3600,
+        signature_expiration: now + Duration::from_secs(3600 * 24),
+        signature_inception: now - Duration::from_secs(3600),
         signer_name: leaf_zone.clone(),
         signature: "AAAAAAAAAAA=".to_owned(),
     });
@@ -461,8 +458,8 @@ AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
         labels: 2,
         key_tag: 0,
         original_ttl: 86400,
-        signature_expiration: now + 3600 * 24,
-        signature_inception: now - 3600,
+        signature_expiration: now + Duration::from_secs(3600 * 24),
+        signature_inception: now - Duration::from_secs(3600),
         signer_name: leaf_zone.clone(),
         signature: "AAAAAAAAAAA=".to_owned(),
     });
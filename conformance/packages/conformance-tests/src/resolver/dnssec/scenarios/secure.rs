@@ -2,11 +2,11 @@ use std::net::Ipv4Addr;
 use std::time::Duration;
 
 use dns_test::client::{Client, DigSettings};
-use dns_test::name_server::NameServer;
+use dns_test::name_server::{Graph, NameServer, Sign};
 use dns_test::record::{Record, RecordType};
 use dns_test::tshark::{Capture, Direction};
 use dns_test::zone_file::{Nsec, SignSettings};
-use dns_test::{FQDN, Network, PEER, Resolver, Result, TrustAnchor};
+use dns_test::{FQDN, Network, PEER, Resolver, ResolverMatrix, Result, TrustAnchor};
 
 use crate::resolver::dnssec::fixtures;
 
@@ -37,8 +37,7 @@ fn can_validate_without_delegation() -> Result<()> {
     let settings = *DigSettings::default().recurse().authentic_data();
     let output = client.dig(settings, resolver_addr, RecordType::SOA, &FQDN::ROOT)?;
 
-    assert!(output.status.is_noerror());
-    assert!(output.flags.authenticated_data);
+    output.assert_validated();
 
     Ok(())
 }
@@ -60,9 +59,7 @@ fn can_validate_with_delegation() -> Result<()> {
     let settings = *DigSettings::default().recurse().authentic_data();
     let output = client.dig(settings, resolver_addr, RecordType::A, &needle_fqdn)?;
 
-    assert!(output.status.is_noerror());
-
-    assert!(output.flags.authenticated_data);
+    output.assert_validated();
 
     let [a] = output.answer.try_into().unwrap();
     let a = a.try_into_a().unwrap();
@@ -253,8 +250,7 @@ fn ds_of_zsk() -> Result<()> {
 
     dbg!(&output);
 
-    assert!(output.status.is_noerror());
-    assert!(output.flags.authenticated_data);
+    output.assert_validated();
 
     Ok(())
 }
@@ -380,3 +376,47 @@ fn no_root_ds_query() -> Result<()> {
 
     Ok(())
 }
+
+// the AD flag must track whether validation is enabled, regardless of the other settings
+#[test]
+fn ad_flag_across_resolver_matrix() -> Result<()> {
+    let expected_ipv4_addr = Ipv4Addr::new(1, 2, 3, 4);
+    let needle_fqdn = FQDN::EXAMPLE_SUBDOMAIN;
+
+    let network = Network::new()?;
+    let mut leaf_ns = NameServer::new(&dns_test::PEER, FQDN::TEST_DOMAIN, &network)?;
+    leaf_ns.add(Record::a(needle_fqdn.clone(), expected_ipv4_addr));
+
+    let Graph {
+        nameservers: _nameservers,
+        root,
+        trust_anchor,
+    } = Graph::build(
+        leaf_ns,
+        Sign::Yes {
+            settings: SignSettings::default(),
+        },
+    )?;
+    let trust_anchor = trust_anchor.unwrap();
+    let client = Client::new(&network)?;
+
+    let report = ResolverMatrix::default().run(|permutation| {
+        let resolver = Resolver::new(&network, root.clone())
+            .trust_anchor(&trust_anchor)
+            .permutation(permutation)
+            .start()?;
+
+        let settings = *DigSettings::default().recurse().authentic_data();
+        let output = client.dig(settings, resolver.ipv4_addr(), RecordType::A, &needle_fqdn)?;
+
+        assert!(output.status.is_noerror());
+        assert_eq!(permutation.validation, output.flags.authenticated_data);
+
+        Ok(())
+    });
+
+    eprintln!("{report}");
+    report.assert_ok();
+
+    Ok(())
+}
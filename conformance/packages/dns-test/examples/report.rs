@@ -0,0 +1,28 @@
+//! Aggregates the results written to a `DNS_TEST_REPORT` file into a JSON report
+//!
+//! When the results of a second run, e.g. against a reference implementation, are also given, the
+//! scenarios whose outcome differs between the two runs are printed instead.
+
+use std::env;
+
+use dns_test::Result;
+use dns_test::report::Report;
+
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next() else {
+        return Err("usage: report RESULTS_FILE [REFERENCE_RESULTS_FILE]".into());
+    };
+
+    let report = Report::read(path)?;
+    match args.next() {
+        Some(reference) => {
+            let reference = Report::read(reference)?;
+            let differences = report.differences(&reference);
+            println!("{}", serde_json::to_string_pretty(&differences)?);
+        }
+        None => println!("{}", report.to_json()?),
+    }
+
+    Ok(())
+}
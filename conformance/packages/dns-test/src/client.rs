@@ -1,6 +1,7 @@
+use core::fmt;
 use core::str::FromStr;
-use std::collections::BTreeSet;
-use std::net::Ipv4Addr;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use crate::container::{Container, Image, Network};
 use crate::record::{Record, RecordType};
@@ -9,12 +10,76 @@ use crate::{Error, FQDN, Result};
 
 pub struct Client {
     inner: Container,
+    tsig: Option<Tsig>,
+    encryption: Option<Encryption>,
 }
 
 impl Client {
     pub fn new(network: &Network) -> Result<Self> {
         Ok(Self {
             inner: Container::run(&Image::Client, network)?,
+            tsig: None,
+            encryption: None,
+        })
+    }
+
+    /// Signs the queries, zone transfers and updates sent from now on with the `tsig` key, or stops
+    /// signing them if `None`
+    pub fn set_tsig(&mut self, tsig: Option<Tsig>) {
+        self.tsig = tsig;
+    }
+
+    /// Sends the queries and zone transfers from now on over DNS-over-TLS (RFC7858) with the
+    /// `tls` settings, or over plain UDP and TCP again if `None`
+    ///
+    /// This replaces the settings of `set_https`. Updates are always sent in plain text, as
+    /// `nsupdate` does not support TLS.
+    pub fn set_tls(&mut self, tls: Option<Tls>) {
+        self.encryption = tls.map(Encryption::Tls);
+    }
+
+    /// Sends the queries from now on over DNS-over-HTTPS (RFC8484) with the `https` settings, or
+    /// over plain UDP and TCP again if `None`
+    ///
+    /// This replaces the settings of `set_tls`. Zone transfers are not supported over HTTPS and
+    /// updates are always sent in plain text, as `nsupdate` does not support HTTPS.
+    pub fn set_https(&mut self, https: Option<Https>) {
+        self.encryption = https.map(Encryption::Https);
+    }
+
+    /// Sends the queries from now on over DNS-over-QUIC (RFC9250) with the `quic` settings, or
+    /// over plain UDP and TCP again if `None`
+    ///
+    /// This replaces the settings of `set_tls` and `set_https`. The queries are sent with `kdig`,
+    /// as `dig` does not support QUIC, which limits the `DigSettings` that can be used. Zone
+    /// transfers are not supported over QUIC and updates are always sent in plain text.
+    pub fn set_quic(&mut self, quic: Option<Quic>) {
+        self.encryption = quic.map(Encryption::Quic);
+    }
+
+    /// The `dig` arguments for the TLS or HTTPS settings, if any
+    ///
+    /// This copies the CA certificates into the container.
+    fn encryption_args(&self) -> Result<Vec<String>> {
+        const CA_CERTIFICATES_PATH: &str = "/tmp/tls-ca.pem";
+
+        let Some(encryption) = &self.encryption else {
+            return Ok(vec![]);
+        };
+
+        let ca_certificates = match encryption {
+            Encryption::Tls(tls) => &tls.ca_certificates,
+            Encryption::Https(https) => &https.ca_certificates,
+            Encryption::Quic(quic) => &quic.ca_certificates,
+        };
+        if let Some(ca_certificates) = ca_certificates {
+            self.inner.cp(CA_CERTIFICATES_PATH, ca_certificates)?;
+        }
+
+        Ok(match encryption {
+            Encryption::Tls(tls) => tls.args(CA_CERTIFICATES_PATH),
+            Encryption::Https(https) => https.args(CA_CERTIFICATES_PATH),
+            Encryption::Quic(quic) => quic.args(CA_CERTIFICATES_PATH),
         })
     }
 
@@ -30,6 +95,11 @@ impl Client {
         self.inner.ipv4_addr()
     }
 
+    /// Returns the IPv6 address of the client, if its network is dual-stack
+    pub fn ipv6_addr(&self) -> Option<Ipv6Addr> {
+        self.inner.ipv6_addr()
+    }
+
     pub fn delv(
         &self,
         server: Ipv4Addr,
@@ -56,13 +126,19 @@ impl Client {
         ])
     }
 
+    /// Sends a query to `server`, over IPv6 if it is an IPv6 address
     pub fn dig(
         &self,
         settings: DigSettings,
-        server: Ipv4Addr,
+        server: impl Into<IpAddr>,
         record_type: RecordType,
         fqdn: &FQDN,
     ) -> Result<DigOutput> {
+        let server = server.into();
+        if let Some(Encryption::Quic(_)) = self.encryption {
+            return self.kdig(settings, server, record_type, fqdn);
+        }
+
         let timeoutflag = &settings.timeoutflag();
         let ednsflag = settings.ednsflag();
         let opcodeflag = settings.opcodeflag();
@@ -101,6 +177,12 @@ impl Client {
         if let Some(subnetflag) = settings.subnetflag() {
             command_and_args.push(subnetflag);
         }
+        let tsig_arg = self.tsig.as_ref().map(Tsig::arg);
+        if let Some(tsig_arg) = tsig_arg.as_ref() {
+            command_and_args.extend_from_slice(&["-y", tsig_arg]);
+        }
+        let encryption_args = self.encryption_args()?;
+        command_and_args.extend(encryption_args.iter().map(String::as_str));
 
         let server_arg = format!("@{server}");
         let record_type_name = record_type.as_name();
@@ -114,6 +196,594 @@ impl Client {
 
         output.parse()
     }
+
+    /// Sends the query with `kdig`, which supports QUIC, and parses its output like `dig`'s
+    fn kdig(
+        &self,
+        settings: DigSettings,
+        server: IpAddr,
+        record_type: RecordType,
+        fqdn: &FQDN,
+    ) -> Result<DigOutput> {
+        let settings_args = settings.kdig_args()?;
+        let mut command_and_args = vec!["kdig"];
+        command_and_args.extend(settings_args.iter().map(String::as_str));
+        let tsig_arg = self.tsig.as_ref().map(Tsig::arg);
+        if let Some(tsig_arg) = tsig_arg.as_ref() {
+            command_and_args.extend_from_slice(&["-y", tsig_arg]);
+        }
+        let encryption_args = self.encryption_args()?;
+        command_and_args.extend(encryption_args.iter().map(String::as_str));
+
+        let server_arg = format!("@{server}");
+        let record_type_name = record_type.as_name();
+        command_and_args.extend_from_slice(&[
+            server_arg.as_str(),
+            record_type_name.as_ref(),
+            fqdn.as_str(),
+        ]);
+
+        let output = self.inner.stdout(&command_and_args)?;
+
+        kdig_to_dig(&output).parse()
+    }
+
+    /// Transfers the `zone` from the name server at `server` with `dig AXFR`
+    ///
+    /// The SOA record, which ends the transfer, appears only once in the returned records.
+    pub fn axfr(&self, server: Ipv4Addr, zone: &FQDN) -> Result<Vec<Record>> {
+        self.zone_transfer(server, zone, ZoneTransfer::Axfr)
+    }
+
+    /// Transfers the `zone` from the name server at `server` over TCP
+    ///
+    /// The records of all the messages of the transfer are returned in order. For
+    /// `ZoneTransfer::Axfr`, the SOA record that ends the transfer is left out. For
+    /// `ZoneTransfer::Ixfr`, the records are returned as sent (RFC1995 section 4): the new SOA
+    /// record, then for every version the old SOA record, the deleted records, the new SOA record
+    /// and the added records, and finally the new SOA record again. A server that can not provide
+    /// the differences may answer with the full zone instead, like an AXFR.
+    pub fn zone_transfer(
+        &self,
+        server: Ipv4Addr,
+        zone: &FQDN,
+        transfer: ZoneTransfer,
+    ) -> Result<Vec<Record>> {
+        match self.encryption {
+            Some(Encryption::Https(_)) => {
+                return Err("zone transfers are not supported over DNS-over-HTTPS".into());
+            }
+            Some(Encryption::Quic(_)) => {
+                return Err("zone transfers are not supported over DNS-over-QUIC".into());
+            }
+            _ => {}
+        }
+
+        let server_arg = format!("@{server}");
+        let transfer_arg = match transfer {
+            ZoneTransfer::Axfr => "AXFR".to_string(),
+            ZoneTransfer::Ixfr(serial) => format!("IXFR={serial}"),
+        };
+
+        let mut command_and_args = vec!["dig", "+tcp", "+nocmd", "+nostats"];
+        if transfer == ZoneTransfer::Axfr {
+            command_and_args.push("+onesoa");
+        }
+        let tsig_arg = self.tsig.as_ref().map(Tsig::arg);
+        if let Some(tsig_arg) = tsig_arg.as_ref() {
+            command_and_args.extend_from_slice(&["-y", tsig_arg]);
+        }
+        let encryption_args = self.encryption_args()?;
+        command_and_args.extend(encryption_args.iter().map(String::as_str));
+        command_and_args.extend_from_slice(&[
+            server_arg.as_str(),
+            transfer_arg.as_str(),
+            zone.as_str(),
+        ]);
+
+        let output = self.inner.stdout(&command_and_args)?;
+
+        parse_axfr(&output)
+    }
+
+    /// Sends a dynamic update (RFC2136) of the `zone` to the name server at `server` with
+    /// `nsupdate`
+    ///
+    /// Returns an error if the update was not applied, e.g. because the server refused it.
+    pub fn update(&self, server: Ipv4Addr, zone: &FQDN, updates: &[Update]) -> Result<()> {
+        let status = self.update_with_prerequisites(server, zone, &[], updates)?;
+        if !status.is_noerror() {
+            return Err(format!("update of {zone} at {server} failed: {status:?}").into());
+        }
+
+        Ok(())
+    }
+
+    /// Sends a dynamic update (RFC2136) of the `zone` to the name server at `server` with
+    /// `nsupdate`, which is only applied if all `prerequisites` hold
+    ///
+    /// Returns the response code of the server, e.g. `REFUSED` if its update policy does not
+    /// permit the update or `NXRRSET` if a prerequisite does not hold. Errors are returned if no
+    /// response code was received, e.g. because the response failed TSIG verification.
+    pub fn update_with_prerequisites(
+        &self,
+        server: Ipv4Addr,
+        zone: &FQDN,
+        prerequisites: &[Prerequisite],
+        updates: &[Update],
+    ) -> Result<DigStatus> {
+        const SCRIPT_PATH: &str = "/tmp/nsupdate.txt";
+
+        self.inner.cp(
+            SCRIPT_PATH,
+            &nsupdate_script(server, zone, prerequisites, updates),
+        )?;
+
+        let mut command_and_args = vec!["nsupdate"];
+        let tsig_arg = self.tsig.as_ref().map(Tsig::arg);
+        if let Some(tsig_arg) = tsig_arg.as_ref() {
+            command_and_args.extend_from_slice(&["-y", tsig_arg]);
+        }
+        command_and_args.push(SCRIPT_PATH);
+
+        let output = self.inner.output(&command_and_args)?;
+        if output.status.success() {
+            return Ok(DigStatus::NOERROR);
+        }
+
+        parse_nsupdate_status(&output.stderr)
+    }
+
+    /// Asserts that the `zone`, as transferred from the name server at `server`, contains exactly
+    /// the `expected` records
+    ///
+    /// The order and the TTLs of the records are not compared. Errors are returned if the zone
+    /// cannot be transferred; a mismatch panics with the differences.
+    pub fn assert_zone(&self, server: Ipv4Addr, zone: &FQDN, expected: &[Record]) -> Result<()> {
+        let actual = self.axfr(server, zone)?;
+
+        let diff = ZoneDiff::new(&actual, expected);
+        assert!(
+            diff.is_empty(),
+            "zone {zone} at {server} differs from the expected records\n{diff}"
+        );
+
+        Ok(())
+    }
+}
+
+fn parse_axfr(input: &str) -> Result<Vec<Record>> {
+    let mut records = vec![];
+    for line in input.lines() {
+        let line = line.trim();
+        if line.contains("Transfer failed") {
+            return Err(format!("zone transfer failed: {line}").into());
+        }
+
+        if line.contains("Couldn't verify signature") {
+            return Err(format!("zone transfer failed TSIG verification: {line}").into());
+        }
+
+        // `dig -y` prints the TSIG record of every message of the transfer
+        let is_tsig = line.split_whitespace().nth(3) == Some("TSIG");
+        if line.is_empty() || line.starts_with(';') || is_tsig {
+            continue;
+        }
+
+        records.push(line.parse()?);
+    }
+
+    if records.is_empty() {
+        return Err("zone transfer returned no records".into());
+    }
+
+    Ok(records)
+}
+
+/// The kind of zone transfer performed by `Client::zone_transfer`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ZoneTransfer {
+    /// Full zone transfer (RFC5936)
+    Axfr,
+    /// Incremental zone transfer (RFC1995) from the version with the given SOA serial
+    Ixfr(u32),
+}
+
+/// A TSIG key (RFC8945) that authenticates the messages a `Client` sends, like `dig -y`
+#[derive(Clone, Debug)]
+pub struct Tsig {
+    pub keyname: FQDN,
+    pub algorithm: TsigAlgorithm,
+    /// base64 encoded
+    pub secret: String,
+}
+
+impl Tsig {
+    /// The argument of the `-y` option of `dig` and `nsupdate`
+    fn arg(&self) -> String {
+        let Self {
+            keyname,
+            algorithm,
+            secret,
+        } = self;
+
+        format!("{}:{}:{secret}", algorithm.as_name(), keyname.as_str())
+    }
+}
+
+/// DNS-over-TLS settings of a `Client`, like `dig +tls`
+#[derive(Clone, Debug)]
+pub struct Tls {
+    /// The port of the name servers, 853 by default
+    pub port: u16,
+    /// The name that the certificate of the name server is verified against, instead of its
+    /// address, and that is sent with SNI. This has no effect without `ca_certificates`
+    pub server_name: Option<String>,
+    /// PEM encoded CA certificates that the certificate of the name server must be issued by. If
+    /// `None`, the certificate is not verified at all
+    pub ca_certificates: Option<String>,
+}
+
+impl Default for Tls {
+    fn default() -> Self {
+        Self {
+            port: 853,
+            server_name: None,
+            ca_certificates: None,
+        }
+    }
+}
+
+impl Tls {
+    /// The `dig` arguments, with the CA certificates at `ca_certificates_path`
+    fn args(&self, ca_certificates_path: &str) -> Vec<String> {
+        let Self {
+            port,
+            server_name,
+            ca_certificates,
+        } = self;
+
+        let mut args = vec!["+tls".to_string(), "-p".to_string(), port.to_string()];
+        if ca_certificates.is_some() {
+            args.push(format!("+tls-ca={ca_certificates_path}"));
+        }
+        if let Some(server_name) = server_name {
+            args.push(format!("+tls-hostname={server_name}"));
+        }
+
+        args
+    }
+}
+
+/// DNS-over-HTTPS settings of a `Client`, like `dig +https`
+///
+/// The queries are sent over HTTP/2.
+#[derive(Clone, Debug)]
+pub struct Https {
+    /// The port of the name servers, 443 by default
+    pub port: u16,
+    /// The path of the DNS query endpoint, `/dns-query` by default
+    pub path: String,
+    /// Whether the queries are sent with `GET` or `POST` requests
+    pub method: HttpMethod,
+    /// The name that the certificate of the name server is verified against, instead of its
+    /// address, and that is sent with SNI. This has no effect without `ca_certificates`
+    pub server_name: Option<String>,
+    /// PEM encoded CA certificates that the certificate of the name server must be issued by. If
+    /// `None`, the certificate is not verified at all
+    pub ca_certificates: Option<String>,
+}
+
+impl Default for Https {
+    fn default() -> Self {
+        Self {
+            port: 443,
+            path: "/dns-query".to_string(),
+            method: HttpMethod::Post,
+            server_name: None,
+            ca_certificates: None,
+        }
+    }
+}
+
+impl Https {
+    /// The `dig` arguments, with the CA certificates at `ca_certificates_path`
+    fn args(&self, ca_certificates_path: &str) -> Vec<String> {
+        let Self {
+            port,
+            path,
+            method,
+            server_name,
+            ca_certificates,
+        } = self;
+
+        let flag = match method {
+            HttpMethod::Get => "+https-get",
+            HttpMethod::Post => "+https",
+        };
+        let mut args = vec![format!("{flag}={path}"), "-p".to_string(), port.to_string()];
+        if ca_certificates.is_some() {
+            args.push(format!("+tls-ca={ca_certificates_path}"));
+        }
+        if let Some(server_name) = server_name {
+            args.push(format!("+tls-hostname={server_name}"));
+        }
+
+        args
+    }
+}
+
+/// The HTTP method of DNS-over-HTTPS queries (RFC8484 section 4.1)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HttpMethod {
+    /// The query is encoded in the `dns` parameter of the URL
+    Get,
+    /// The query is the body of the request
+    Post,
+}
+
+/// DNS-over-QUIC settings of a `Client`, like `kdig +quic`
+///
+/// Every query is sent on a new connection, in its own bidirectional stream (RFC9250 section
+/// 4.2). 0-RTT data is never sent, so the queries can not be replayed.
+#[derive(Clone, Debug)]
+pub struct Quic {
+    /// The port of the name servers, 853 by default
+    pub port: u16,
+    /// The name that the certificate of the name server is verified against, instead of its
+    /// address, and that is sent with SNI. This has no effect without `ca_certificates`
+    pub server_name: Option<String>,
+    /// PEM encoded CA certificates that the certificate of the name server must be issued by. If
+    /// `None`, the certificate is not verified at all
+    pub ca_certificates: Option<String>,
+}
+
+impl Default for Quic {
+    fn default() -> Self {
+        Self {
+            port: 853,
+            server_name: None,
+            ca_certificates: None,
+        }
+    }
+}
+
+impl Quic {
+    /// The `kdig` arguments, with the CA certificates at `ca_certificates_path`
+    fn args(&self, ca_certificates_path: &str) -> Vec<String> {
+        let Self {
+            port,
+            server_name,
+            ca_certificates,
+        } = self;
+
+        let mut args = vec!["+quic".to_string(), "-p".to_string(), port.to_string()];
+        if ca_certificates.is_some() {
+            args.push(format!("+tls-ca={ca_certificates_path}"));
+        }
+        if let Some(server_name) = server_name {
+            args.push(format!("+tls-hostname={server_name}"));
+        }
+
+        args
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Encryption {
+    Tls(Tls),
+    Https(Https),
+    Quic(Quic),
+}
+
+/// Rewrites the output of `kdig` into the format of `dig`, as far as `DigOutput` parses it
+///
+/// The records of the sections are printed the same way by both.
+fn kdig_to_dig(output: &str) -> String {
+    const HEADER_PREFIX: &str = ";; ->>HEADER<<- ";
+    const FLAGS_PREFIX: &str = ";; Flags: ";
+    const EDNS_PREFIX: &str = ";; Version: ";
+    const EDE_PREFIX: &str = ";; EDE: ";
+
+    let mut lines = vec![];
+    for line in output.lines() {
+        let line = if let Some(unprefixed) = line.strip_prefix(HEADER_PREFIX) {
+            format!("{HEADER_PREFIX}{}", unprefixed.replace("; ", ", "))
+        } else if let Some(unprefixed) = line.strip_prefix(FLAGS_PREFIX) {
+            format!(";; flags: {unprefixed}")
+        } else if line.starts_with(";; EDNS PSEUDOSECTION:") {
+            ";; OPT PSEUDOSECTION:".to_string()
+        } else if let Some(unprefixed) = line.strip_prefix(EDNS_PREFIX) {
+            let (version, rest) = unprefixed.split_once(';').unwrap_or((unprefixed, ""));
+            format!("; EDNS: version: {version},{rest}")
+        } else if let Some(unprefixed) = line.strip_prefix(EDE_PREFIX) {
+            format!("; EDE: {unprefixed}")
+        } else {
+            line.to_string()
+        };
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TsigAlgorithm {
+    HmacMd5,
+    HmacSha1,
+    HmacSha224,
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+}
+
+impl TsigAlgorithm {
+    /// The name of the algorithm as used by BIND
+    pub fn as_name(&self) -> &'static str {
+        match self {
+            Self::HmacMd5 => "hmac-md5",
+            Self::HmacSha1 => "hmac-sha1",
+            Self::HmacSha224 => "hmac-sha224",
+            Self::HmacSha256 => "hmac-sha256",
+            Self::HmacSha384 => "hmac-sha384",
+            Self::HmacSha512 => "hmac-sha512",
+        }
+    }
+}
+
+/// A change made by a dynamic update, see `Client::update`
+#[derive(Clone, Debug)]
+pub enum Update {
+    /// Adds the record to its RRset
+    Add(Record),
+    /// Deletes the record from its RRset
+    Delete(Record),
+    /// Deletes all records of the given type
+    DeleteRRset(FQDN, RecordType),
+    /// Deletes all records of the name
+    DeleteName(FQDN),
+}
+
+/// A condition that must hold for a dynamic update to be applied, see
+/// `Client::update_with_prerequisites`
+#[derive(Clone, Debug)]
+pub enum Prerequisite {
+    /// The name has at least one record
+    NameInUse(FQDN),
+    /// The name has no records
+    NameNotInUse(FQDN),
+    /// The name has records of the given type
+    RRsetExists(FQDN, RecordType),
+    /// The RRset of the record consists of exactly the records given as prerequisites, the TTL
+    /// is ignored
+    RRsetExistsWithRecords(Record),
+    /// The name has no records of the given type
+    RRsetNotExists(FQDN, RecordType),
+}
+
+fn nsupdate_script(
+    server: Ipv4Addr,
+    zone: &FQDN,
+    prerequisites: &[Prerequisite],
+    updates: &[Update],
+) -> String {
+    use core::fmt::Write as _;
+
+    let mut script = format!("server {server}\nzone {zone}\n");
+    for prerequisite in prerequisites {
+        match prerequisite {
+            Prerequisite::NameInUse(fqdn) => writeln!(script, "prereq yxdomain {fqdn}"),
+            Prerequisite::NameNotInUse(fqdn) => writeln!(script, "prereq nxdomain {fqdn}"),
+            Prerequisite::RRsetExists(fqdn, record_type) => {
+                writeln!(script, "prereq yxrrset {fqdn} {}", record_type.as_name())
+            }
+            Prerequisite::RRsetExistsWithRecords(record) => {
+                // unlike `update add`, `prereq` does not take a TTL
+                let record = record.to_string();
+                let fields = record.split('\t').collect::<Vec<_>>();
+                writeln!(
+                    script,
+                    "prereq yxrrset {} {}",
+                    fields[0],
+                    fields[2..].join(" ")
+                )
+            }
+            Prerequisite::RRsetNotExists(fqdn, record_type) => {
+                writeln!(script, "prereq nxrrset {fqdn} {}", record_type.as_name())
+            }
+        }
+        .unwrap();
+    }
+
+    for update in updates {
+        match update {
+            Update::Add(record) => writeln!(script, "update add {record}"),
+            Update::Delete(record) => writeln!(script, "update delete {record}"),
+            Update::DeleteRRset(fqdn, record_type) => {
+                writeln!(script, "update delete {fqdn} {}", record_type.as_name())
+            }
+            Update::DeleteName(fqdn) => writeln!(script, "update delete {fqdn}"),
+        }
+        .unwrap();
+    }
+    script.push_str("send\n");
+
+    script
+}
+
+/// Extracts the response code from the output of a failed `nsupdate`, e.g.
+/// `update failed: REFUSED`
+fn parse_nsupdate_status(stderr: &str) -> Result<DigStatus> {
+    stderr
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("update failed: "))
+        .ok_or_else(|| format!("nsupdate failed without a response code: {stderr}"))?
+        .trim()
+        .parse()
+}
+
+/// The differences between the records of a zone and the expected ones
+#[derive(Debug, Default)]
+pub struct ZoneDiff {
+    /// Expected records that are not in the zone
+    pub missing: Vec<Record>,
+    /// Records in the zone that were not expected
+    pub unexpected: Vec<Record>,
+}
+
+impl ZoneDiff {
+    /// Compares `actual` to `expected`, ignoring the order and the TTLs of the records
+    pub fn new(actual: &[Record], expected: &[Record]) -> Self {
+        let mut remaining = BTreeMap::<_, Vec<_>>::new();
+        for record in actual {
+            remaining.entry(diff_key(record)).or_default().push(record);
+        }
+
+        let mut diff = Self::default();
+        for record in expected {
+            match remaining.get_mut(&diff_key(record)).and_then(Vec::pop) {
+                Some(_) => {}
+                None => diff.missing.push(record.clone()),
+            }
+        }
+
+        diff.unexpected = remaining.into_values().flatten().cloned().collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl fmt::Display for ZoneDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for record in &self.missing {
+            writeln!(f, "- {record}")?;
+        }
+
+        for record in &self.unexpected {
+            writeln!(f, "+ {record}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The text representation of `record` without its TTL, and with a lowercase owner name
+fn diff_key(record: &Record) -> String {
+    let text = record.to_string();
+    let mut columns = text.split_whitespace();
+    let owner = columns.next().unwrap_or_default().to_ascii_lowercase();
+    // skip the TTL
+    columns.next();
+
+    let mut key = owner;
+    for column in columns {
+        key.push(' ');
+        key.push_str(column);
+    }
+    key
 }
 
 #[derive(Clone, Copy)]
@@ -399,6 +1069,59 @@ impl DigSettings {
         }
     }
 
+    /// The `kdig` arguments, an error for the settings `kdig` does not support
+    fn kdig_args(&self) -> Result<Vec<String>> {
+        let Self {
+            adflag: _,
+            cdflag: _,
+            dnssec: _,
+            recurse: _,
+            timeout: _,
+            edns: _,
+            zflag: _,
+            opcode,
+            header_only,
+            // there is no truncation over QUIC, nor a choice of UDP or TCP
+            tcp: _,
+            ignore_truncation: _,
+            cookie: _,
+            // `kdig` does not negotiate the EDNS version
+            ednsneg: _,
+            extra_edns_option: _,
+            extra_edns_flags,
+            bufsize: _,
+            nsid: _,
+            expire,
+            subnet_zero: _,
+        } = *self;
+
+        if opcode != 0 || header_only || expire || extra_edns_flags.is_some() {
+            return Err(
+                "the opcode, header only, expire and EDNS flags settings are not supported by kdig"
+                    .into(),
+            );
+        }
+
+        let mut args = [
+            self.rdflag(),
+            self.do_bit(),
+            self.adflag(),
+            self.cdflag(),
+            self.zflag(),
+            self.cookieflag(),
+            self.nsidflag(),
+        ]
+        .map(String::from)
+        .to_vec();
+        args.push(self.timeoutflag());
+        args.push(self.ednsflag());
+        args.extend(self.ednsoptionflag());
+        args.extend(self.bufsizeflag());
+        args.extend(self.subnetflag().map(String::from));
+
+        Ok(args)
+    }
+
     pub fn subnet_zero(&mut self) -> &mut Self {
         self.subnet_zero = true;
         self
@@ -613,14 +1336,75 @@ impl FromStr for DigOutput {
     }
 }
 
+impl DigOutput {
+    /// Asserts that the response passed DNSSEC validation: NOERROR with the AD flag set
+    #[track_caller]
+    pub fn assert_validated(&self) {
+        assert!(
+            self.status.is_noerror(),
+            "expected NOERROR but got {:?}; output: {self:?}",
+            self.status
+        );
+        assert!(
+            self.flags.authenticated_data,
+            "expected the AD flag to be set; output: {self:?}"
+        );
+    }
+
+    /// Asserts that the response failed DNSSEC validation: SERVFAIL without the AD flag
+    ///
+    /// when `expected_ede` is `Some`, the response must also carry that Extended DNS Error.
+    /// pass `None` when the subject does not support EDE (see `Implementation::supports_ede`)
+    #[track_caller]
+    pub fn assert_bogus(&self, expected_ede: Option<ExtendedDnsError>) {
+        assert!(
+            self.status.is_servfail(),
+            "expected SERVFAIL but got {:?}; output: {self:?}",
+            self.status
+        );
+        assert!(
+            !self.flags.authenticated_data,
+            "expected the AD flag to be unset; output: {self:?}"
+        );
+
+        if let Some(expected) = expected_ede {
+            assert!(
+                self.ede.contains(&expected),
+                "expected EDE {expected:?} but got {:?}",
+                self.ede
+            );
+        }
+    }
+}
+
+/// Extended DNS Error codes, as registered in section 5.2 of RFC8914
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub enum ExtendedDnsError {
+    Other = 0,
     UnsupportedDnskeyAlgorithm = 1,
+    UnsupportedDsDigestType = 2,
+    StaleAnswer = 3,
+    ForgedAnswer = 4,
+    DnssecIndeterminate = 5,
     DnssecBogus = 6,
+    SignatureExpired = 7,
+    SignatureNotYetValid = 8,
     DnskeyMissing = 9,
     RrsigsMissing = 10,
+    NoZoneKeyBitSet = 11,
+    NsecMissing = 12,
+    CachedError = 13,
+    NotReady = 14,
+    Blocked = 15,
+    Censored = 16,
+    Filtered = 17,
     Prohibited = 18,
+    StaleNxdomainAnswer = 19,
+    NotAuthoritative = 20,
+    NotSupported = 21,
     NoReachableAuthority = 22,
+    NetworkError = 23,
+    InvalidData = 24,
 }
 
 impl FromStr for ExtendedDnsError {
@@ -630,13 +1414,32 @@ impl FromStr for ExtendedDnsError {
         let code: u16 = input.parse()?;
 
         let code = match code {
+            0 => Self::Other,
             1 => Self::UnsupportedDnskeyAlgorithm,
+            2 => Self::UnsupportedDsDigestType,
+            3 => Self::StaleAnswer,
+            4 => Self::ForgedAnswer,
+            5 => Self::DnssecIndeterminate,
             6 => Self::DnssecBogus,
+            7 => Self::SignatureExpired,
+            8 => Self::SignatureNotYetValid,
             9 => Self::DnskeyMissing,
             10 => Self::RrsigsMissing,
+            11 => Self::NoZoneKeyBitSet,
+            12 => Self::NsecMissing,
+            13 => Self::CachedError,
+            14 => Self::NotReady,
+            15 => Self::Blocked,
+            16 => Self::Censored,
+            17 => Self::Filtered,
             18 => Self::Prohibited,
+            19 => Self::StaleNxdomainAnswer,
+            20 => Self::NotAuthoritative,
+            21 => Self::NotSupported,
             22 => Self::NoReachableAuthority,
-            _ => todo!("EDE {code} has not yet been implemented"),
+            23 => Self::NetworkError,
+            24 => Self::InvalidData,
+            _ => return Err(format!("unknown EDE code: {code}").into()),
         };
 
         Ok(code)
@@ -695,11 +1498,17 @@ impl FromStr for DigFlags {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DigStatus {
     BADVERS,
+    FORMERR,
     NOERROR,
+    NOTAUTH,
     NOTIMP,
+    NOTZONE,
     NXDOMAIN,
+    NXRRSET,
     REFUSED,
     SERVFAIL,
+    YXDOMAIN,
+    YXRRSET,
 }
 
 impl DigStatus {
@@ -725,11 +1534,17 @@ impl FromStr for DigStatus {
     fn from_str(input: &str) -> Result<Self> {
         let status = match input {
             "BADVERS" => Self::BADVERS,
+            "FORMERR" => Self::FORMERR,
             "NOERROR" => Self::NOERROR,
+            "NOTAUTH" => Self::NOTAUTH,
             "NOTIMP" => Self::NOTIMP,
+            "NOTZONE" => Self::NOTZONE,
             "NXDOMAIN" => Self::NXDOMAIN,
+            "NXRRSET" => Self::NXRRSET,
             "REFUSED" => Self::REFUSED,
             "SERVFAIL" => Self::SERVFAIL,
+            "YXDOMAIN" => Self::YXDOMAIN,
+            "YXRRSET" => Self::YXRRSET,
             _ => return Err(format!("unknown status: {input}").into()),
         };
 
@@ -913,6 +1728,87 @@ l.root-servers.net. 518400  IN  A   199.7.83.42
         Ok(())
     }
 
+    #[test]
+    fn assert_validated() -> Result<()> {
+        let input = "; <<>> DiG 9.18.28-1~deb12u2-Debian <<>> +recurse +nodnssec +adflag +nocdflag @172.19.0.3 A example.hickory-dns.testing.
+; (1 server found)
+;; global options: +cmd
+;; Got answer:
+;; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 25341
+;; flags: qr rd ra ad; QUERY: 1, ANSWER: 1, AUTHORITY: 0, ADDITIONAL: 1
+
+;; OPT PSEUDOSECTION:
+; EDNS: version: 0, flags:; udp: 1232
+;; QUESTION SECTION:
+;example.hickory-dns.testing.	IN	A
+
+;; ANSWER SECTION:
+example.hickory-dns.testing. 86400 IN	A	1.2.3.4
+
+;; Query time: 12 msec
+;; SERVER: 172.19.0.3#53(172.19.0.3) (UDP)
+;; WHEN: Sat Oct 17 10:12:41 UTC 2026
+;; MSG SIZE  rcvd: 72
+";
+
+        let output: DigOutput = input.parse()?;
+
+        assert!(output.flags.authenticated_data);
+        assert!(!output.flags.checking_disabled);
+        output.assert_validated();
+
+        let output: DigOutput = input.replace(" ad;", ";").parse()?;
+        let result = std::panic::catch_unwind(|| output.assert_validated());
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn assert_bogus() -> Result<()> {
+        let input = "; <<>> DiG 9.18.28-1~deb12u2-Debian <<>> +recurse +nodnssec +adflag +nocdflag @172.19.0.3 A example.hickory-dns.testing.
+; (1 server found)
+;; global options: +cmd
+;; Got answer:
+;; ->>HEADER<<- opcode: QUERY, status: SERVFAIL, id: 40611
+;; flags: qr rd ra; QUERY: 1, ANSWER: 0, AUTHORITY: 0, ADDITIONAL: 1
+
+;; OPT PSEUDOSECTION:
+; EDNS: version: 0, flags:; udp: 1232
+; EDE: 10 (RRSIGs Missing): (validation failure <example.hickory-dns.testing. A IN>)
+;; QUESTION SECTION:
+;example.hickory-dns.testing.	IN	A
+
+;; Query time: 20 msec
+;; SERVER: 172.19.0.3#53(172.19.0.3) (UDP)
+;; WHEN: Sat Oct 17 10:14:02 UTC 2026
+;; MSG SIZE  rcvd: 120
+";
+
+        let output: DigOutput = input.parse()?;
+
+        output.assert_bogus(None);
+        output.assert_bogus(Some(ExtendedDnsError::RrsigsMissing));
+
+        let result =
+            std::panic::catch_unwind(|| output.assert_bogus(Some(ExtendedDnsError::DnssecBogus)));
+        assert!(result.is_err());
+
+        let result = std::panic::catch_unwind(|| output.assert_validated());
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_ede() {
+        assert_eq!(
+            Some(ExtendedDnsError::InvalidData),
+            "24".parse::<ExtendedDnsError>().ok()
+        );
+        assert!("25".parse::<ExtendedDnsError>().is_err());
+    }
+
     #[test]
     fn no_opt_pseudosection() -> Result<()> {
         let input="; <<>> DiG 9.18.28-1~deb12u2-Debian <<>> +norecurse +nodnssec +noadflag +nocdflag +timeout +noedns @172.19.0.2 SOA hickory-dns.testing.
@@ -1068,4 +1964,315 @@ primary0.hickory-dns.testing. 86400 IN	RRSIG	A 8 3 86400 20250107210902 20241210
 
         Ok(())
     }
+
+    #[test]
+    fn axfr() -> Result<()> {
+        // $ dig +tcp +nocmd +nostats +onesoa @192.168.0.2 AXFR hickory-dns.testing.
+        let input = "
+hickory-dns.testing.	86400	IN	SOA	primary0.hickory-dns.testing. admin0.hickory-dns.testing. 2024010101 1800 900 604800 86400
+hickory-dns.testing.	86400	IN	NS	primary0.hickory-dns.testing.
+primary0.hickory-dns.testing. 86400	IN	A	192.168.0.2
+";
+
+        let records = parse_axfr(input)?;
+
+        assert_eq!(3, records.len());
+        assert!(records[0].is_soa());
+
+        let failed = "; Transfer failed.";
+        assert!(parse_axfr(failed).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn ixfr() -> Result<()> {
+        // $ dig +tcp +nocmd +nostats @192.168.0.2 IXFR=2024010101 hickory-dns.testing.
+        let input = "
+hickory-dns.testing.	86400	IN	SOA	primary0.hickory-dns.testing. admin0.hickory-dns.testing. 2024010102 1800 900 604800 86400
+hickory-dns.testing.	86400	IN	SOA	primary0.hickory-dns.testing. admin0.hickory-dns.testing. 2024010101 1800 900 604800 86400
+example.hickory-dns.testing. 86400	IN	A	192.0.2.1
+hickory-dns.testing.	86400	IN	SOA	primary0.hickory-dns.testing. admin0.hickory-dns.testing. 2024010102 1800 900 604800 86400
+example.hickory-dns.testing. 86400	IN	A	192.0.2.2
+hickory-dns.testing.	86400	IN	SOA	primary0.hickory-dns.testing. admin0.hickory-dns.testing. 2024010102 1800 900 604800 86400
+";
+
+        let records = parse_axfr(input)?;
+
+        let soa_serials = records
+            .iter()
+            .filter_map(|record| record.clone().try_into_soa().ok())
+            .map(|soa| soa.settings.serial)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            [2024010102, 2024010101, 2024010102, 2024010102],
+            soa_serials.as_slice()
+        );
+        assert_eq!(6, records.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn axfr_tsig() -> Result<()> {
+        // $ dig +nocmd +nostats +onesoa -y hmac-sha256:tsig-key.:<secret> @192.168.0.2 AXFR hickory-dns.testing.
+        let input = "
+hickory-dns.testing.	86400	IN	SOA	primary0.hickory-dns.testing. admin0.hickory-dns.testing. 2024010101 1800 900 604800 86400
+primary0.hickory-dns.testing. 86400	IN	A	192.168.0.2
+tsig-key.		0	ANY	TSIG	hmac-sha256. 1733864942 300 32 xmGY1f3Y8iGyTvDDnsSy3dqFlT8A1Jf1P3Bq/o6UuEI= 41263 NOERROR 0
+";
+
+        let records = parse_axfr(input)?;
+
+        assert_eq!(2, records.len());
+
+        let unverified = ";; Couldn't verify signature: tsig indicates error";
+        assert!(parse_axfr(unverified).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn tls_args() {
+        assert_eq!(
+            vec!["+tls", "-p", "853"],
+            Tls::default().args("/tmp/ca.pem")
+        );
+
+        let tls = Tls {
+            port: 8853,
+            server_name: Some("dns.hickory-dns.testing".to_string()),
+            ca_certificates: Some("-----BEGIN CERTIFICATE-----".to_string()),
+        };
+        assert_eq!(
+            vec![
+                "+tls",
+                "-p",
+                "8853",
+                "+tls-ca=/tmp/ca.pem",
+                "+tls-hostname=dns.hickory-dns.testing"
+            ],
+            tls.args("/tmp/ca.pem")
+        );
+    }
+
+    #[test]
+    fn https_args() {
+        assert_eq!(
+            vec!["+https=/dns-query", "-p", "443"],
+            Https::default().args("/tmp/ca.pem")
+        );
+
+        let https = Https {
+            port: 8443,
+            path: "/resolve".to_string(),
+            method: HttpMethod::Get,
+            server_name: Some("dns.hickory-dns.testing".to_string()),
+            ca_certificates: Some("-----BEGIN CERTIFICATE-----".to_string()),
+        };
+        assert_eq!(
+            vec![
+                "+https-get=/resolve",
+                "-p",
+                "8443",
+                "+tls-ca=/tmp/ca.pem",
+                "+tls-hostname=dns.hickory-dns.testing"
+            ],
+            https.args("/tmp/ca.pem")
+        );
+    }
+
+    #[test]
+    fn quic_args() {
+        assert_eq!(
+            vec!["+quic", "-p", "853"],
+            Quic::default().args("/tmp/ca.pem")
+        );
+    }
+
+    #[test]
+    fn kdig_args() -> Result<()> {
+        let mut settings = DigSettings::default();
+        settings.recurse().dnssec().nsid().bufsize(1232);
+        assert_eq!(
+            vec![
+                "+recurse",
+                "+dnssec",
+                "+noadflag",
+                "+nocdflag",
+                "+nozflag",
+                "+cookie",
+                "+nsid",
+                "+timeout=5",
+                "+edns=0",
+                "+bufsize=1232"
+            ],
+            settings.kdig_args()?
+        );
+
+        assert!(DigSettings::default().opcode(2).kdig_args().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn kdig_output() -> Result<()> {
+        // `kdig +quic +dnssec @192.0.2.1 A nameservers.com.`
+        let output =
+            ";; QUIC session (QUICv1)-(TLS1.3)-(ECDHE-X25519)-(EdDSA-Ed25519)-(AES-256-GCM)
+;; ->>HEADER<<- opcode: QUERY; status: NOERROR; id: 0
+;; Flags: qr aa; QUERY: 1; ANSWER: 1; AUTHORITY: 0; ADDITIONAL: 1
+
+;; EDNS PSEUDOSECTION:
+;; Version: 0; flags: do; UDP size: 1232 B; ext-rcode: NOERROR
+;; EDE: 18 (Prohibited)
+
+;; QUESTION SECTION:
+;; nameservers.com.    		IN	A
+
+;; ANSWER SECTION:
+nameservers.com.    	86400	IN	A	192.0.2.1
+
+;; Received 78 B
+;; Time 2024-05-01 12:00:00 UTC
+;; From 192.0.2.1@853(QUIC) in 1.2 ms
+";
+
+        let output: DigOutput = kdig_to_dig(output).parse()?;
+        assert!(output.status.is_noerror());
+        assert!(output.flags.qr && output.flags.authoritative_answer);
+        assert!(!output.flags.recursion_desired);
+        assert_eq!("QUERY", output.opcode);
+        assert!(output.opt);
+        assert_eq!(Some(0), output.edns_version);
+        assert!(output.dnssec_ok_flag);
+        assert!(output.ede.contains(&ExtendedDnsError::Prohibited));
+        assert_eq!(1, output.answer.len());
+        assert!(output.additional.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn tsig_arg() -> Result<()> {
+        let tsig = Tsig {
+            keyname: FQDN("tsig-key.")?,
+            algorithm: TsigAlgorithm::HmacSha256,
+            secret: "c2VjcmV0".to_string(),
+        };
+
+        assert_eq!("hmac-sha256:tsig-key.:c2VjcmV0", tsig.arg());
+
+        Ok(())
+    }
+
+    #[test]
+    fn nsupdate() -> Result<()> {
+        let updates = [
+            Update::Add(Record::a(
+                FQDN::EXAMPLE_SUBDOMAIN,
+                Ipv4Addr::new(192, 0, 2, 1),
+            )),
+            Update::DeleteRRset(FQDN::EXAMPLE_SUBDOMAIN, RecordType::AAAA),
+        ];
+
+        let expected = "server 192.168.0.2
+zone hickory-dns.testing.
+update add example.hickory-dns.testing.\t86400\tIN\tA\t192.0.2.1
+update delete example.hickory-dns.testing. AAAA
+send
+";
+        assert_eq!(
+            expected,
+            nsupdate_script(
+                Ipv4Addr::new(192, 168, 0, 2),
+                &FQDN::TEST_DOMAIN,
+                &[],
+                &updates
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn nsupdate_prerequisites() -> Result<()> {
+        let prerequisites = [
+            Prerequisite::NameInUse(FQDN::TEST_DOMAIN),
+            Prerequisite::NameNotInUse(FQDN::EXAMPLE_SUBDOMAIN),
+            Prerequisite::RRsetExists(FQDN::TEST_DOMAIN, RecordType::SOA),
+            Prerequisite::RRsetExistsWithRecords(Record::a(
+                FQDN::TEST_DOMAIN,
+                Ipv4Addr::new(192, 0, 2, 1),
+            )),
+            Prerequisite::RRsetNotExists(FQDN::TEST_DOMAIN, RecordType::AAAA),
+        ];
+        let updates = [Update::DeleteName(FQDN::EXAMPLE_SUBDOMAIN)];
+
+        let expected = "server 192.168.0.2
+zone hickory-dns.testing.
+prereq yxdomain hickory-dns.testing.
+prereq nxdomain example.hickory-dns.testing.
+prereq yxrrset hickory-dns.testing. SOA
+prereq yxrrset hickory-dns.testing. IN A 192.0.2.1
+prereq nxrrset hickory-dns.testing. AAAA
+update delete example.hickory-dns.testing.
+send
+";
+        assert_eq!(
+            expected,
+            nsupdate_script(
+                Ipv4Addr::new(192, 168, 0, 2),
+                &FQDN::TEST_DOMAIN,
+                &prerequisites,
+                &updates
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn nsupdate_status() -> Result<()> {
+        assert_eq!(
+            DigStatus::NXRRSET,
+            parse_nsupdate_status("update failed: NXRRSET")?
+        );
+        assert_eq!(
+            DigStatus::REFUSED,
+            parse_nsupdate_status(
+                "; Communication with 192.168.0.2#53 ok\nupdate failed: REFUSED\n"
+            )?
+        );
+        assert!(parse_nsupdate_status("; TSIG error with server: tsig verify failure").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn zone_diff_ignores_order_and_ttl() -> Result<()> {
+        let actual = [
+            "hickory-dns.testing.	300	IN	NS	primary0.hickory-dns.testing.".parse()?,
+            "example.hickory-dns.testing.	300	IN	A	192.0.2.1".parse()?,
+            "example.hickory-dns.testing.	300	IN	A	192.0.2.2".parse()?,
+        ];
+        let expected = [
+            Record::a(FQDN::EXAMPLE_SUBDOMAIN, Ipv4Addr::new(192, 0, 2, 2)),
+            Record::ns(FQDN::TEST_DOMAIN, FQDN("primary0.hickory-dns.testing.")?),
+            Record::a(FQDN::EXAMPLE_SUBDOMAIN, Ipv4Addr::new(192, 0, 2, 1)),
+        ];
+
+        assert!(ZoneDiff::new(&actual, &expected).is_empty());
+
+        let diff = ZoneDiff::new(&actual[1..], &expected[..2]);
+        assert_eq!(1, diff.missing.len());
+        assert!(matches!(diff.missing[0], Record::NS(_)));
+        assert_eq!(1, diff.unexpected.len());
+        assert!(
+            diff.to_string()
+                .ends_with("+ example.hickory-dns.testing.\t300\tIN\tA\t192.0.2.1\n")
+        );
+
+        Ok(())
+    }
 }
@@ -2,7 +2,7 @@ mod network;
 
 use core::{fmt, str};
 use std::ffi::OsStr;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::process::{self, ChildStderr, ChildStdout, ExitStatus};
 use std::process::{Command, Stdio};
 use std::sync::atomic::AtomicUsize;
@@ -31,6 +31,7 @@ pub enum Image {
         dnssec_feature: HickoryDnssecFeature,
     },
     Unbound,
+    Knot,
     EdeDotCom,
 }
 
@@ -49,6 +50,7 @@ impl Image {
             Self::Client => include_str!("docker/client.Dockerfile"),
             Self::Hickory { .. } => include_str!("docker/hickory.Dockerfile"),
             Self::Unbound => include_str!("docker/unbound.Dockerfile"),
+            Self::Knot => include_str!("docker/knot.Dockerfile"),
             Self::EdeDotCom => include_str!("docker/ede-dot-com/Dockerfile"),
         }
     }
@@ -80,6 +82,11 @@ impl Image {
                 &UNBOUND_ONCE
             }
 
+            Self::Knot => {
+                static KNOT_ONCE: Once = Once::new();
+                &KNOT_ONCE
+            }
+
             Self::EdeDotCom => {
                 static EDE_ONCE: Once = Once::new();
                 &EDE_ONCE
@@ -94,6 +101,7 @@ impl From<Implementation> for Image {
             Implementation::Bind => Self::Bind,
             Implementation::Dnslib => Self::Dnslib,
             Implementation::Unbound => Self::Unbound,
+            Implementation::Knot => Self::Knot,
             Implementation::Hickory {
                 repo,
                 dnssec_feature,
@@ -114,6 +122,7 @@ impl fmt::Display for Image {
             Self::Dnslib => f.write_str("dnslib"),
             Self::Hickory { dnssec_feature, .. } => write!(f, "hickory-{dnssec_feature}"),
             Self::Unbound => f.write_str("unbound"),
+            Self::Knot => f.write_str("knot"),
             Self::EdeDotCom => f.write_str("ede-dot-com"),
         }
     }
@@ -122,93 +131,7 @@ impl fmt::Display for Image {
 impl Container {
     /// Starts the container in a "parked" state
     pub fn run(image: &Image, network: &Network) -> Result<Self> {
-        let image_tag = format!("{PACKAGE_NAME}-{image}");
-
-        if !skip_docker_build() {
-            image.once().call_once(|| {
-                let dockerfile = image.dockerfile();
-                let docker_build_dir =
-                    TempDir::new().expect("failed to create temporary directory");
-                let docker_build_dir = docker_build_dir.path();
-                fs::write(docker_build_dir.join("Dockerfile"), dockerfile)
-                    .expect("failed to write Dockerfile");
-
-                let mut command = Command::new("docker");
-                command
-                    .args(["build", "--load", "-t"])
-                    .arg(&image_tag)
-                    .arg(docker_build_dir);
-                // Use BuildKit instead of the legacy builder. We need to choose this in order to
-                // pass the `--load` flag above. Depending on which BuildKit build driver is in use,
-                // the `--load` flag may be necessary, in order to load the resulting image as a
-                // local Docker image.
-                command.env("DOCKER_BUILDKIT", "1");
-
-                if let Image::Hickory { dnssec_feature, .. } = image {
-                    command.arg(format!("--build-arg=DNSSEC_FEATURE={dnssec_feature}"));
-                };
-
-                if docker_build_gha_cache() {
-                    let scope = match image {
-                        Image::Bind => "bind",
-                        Image::Dnslib => "dnslib",
-                        Image::Client => "client",
-                        Image::Hickory {
-                            dnssec_feature: HickoryDnssecFeature::AwsLcRs,
-                            ..
-                        } => "hickory-dnssec-aws-lc-rs",
-                        Image::Hickory {
-                            dnssec_feature: HickoryDnssecFeature::Ring,
-                            ..
-                        } => "hickory-dnssec-ring",
-                        Image::Unbound => "unbound",
-                        Image::EdeDotCom => "ede-dot-com",
-                    };
-
-                    command.arg(format!("--cache-from=type=gha,scope=${scope}"));
-                    if let Image::Hickory { .. } = image {
-                        command.arg(format!(
-                            "--cache-to=type=gha,scope=${scope},mode=max,ignore-error=true"
-                        ));
-                    } else {
-                        command.arg(format!(
-                            "--cache-to=type=gha,scope=${scope},ignore-error=true"
-                        ));
-                    }
-                }
-
-                if let Image::Hickory { repo, .. } = image {
-                    let mut cp_r = Command::new("git");
-                    cp_r.args([
-                        "clone",
-                        "--depth",
-                        "1",
-                        repo.as_str(),
-                        &docker_build_dir.join("src").display().to_string(),
-                    ]);
-
-                    exec_or_panic(&mut cp_r, false);
-                }
-
-                if let Image::EdeDotCom = image {
-                    fs::write(
-                        docker_build_dir.join("configure_child.sh"),
-                        include_str!("docker/ede-dot-com/configure_child.sh"),
-                    )
-                    .expect("could not copy configure_child.sh");
-                    fs::write(
-                        docker_build_dir.join("configure_parent.sh"),
-                        include_str!("docker/ede-dot-com/configure_parent.sh"),
-                    )
-                    .expect("could not copy configure_parent.sh");
-                }
-
-                fs::write(docker_build_dir.join(".dockerignore"), "src/.git")
-                    .expect("could not create .dockerignore file");
-
-                exec_or_panic(&mut command, verbose_docker_build());
-            });
-        }
+        let image_tag = build_image(image);
 
         let mut command = Command::new("docker");
         let pid = process::id();
@@ -232,12 +155,13 @@ impl Container {
         let output: Output = checked_output(&mut command)?.try_into()?;
         let id = output.stdout;
 
-        let ipv4_addr = get_ipv4_addr(&id)?;
+        let (ipv4_addr, ipv6_addr) = get_ip_addrs(&id)?;
 
         let inner = Inner {
             id,
             name,
             ipv4_addr,
+            ipv6_addr,
             network: network.clone(),
         };
         Ok(Self {
@@ -331,6 +255,11 @@ impl Container {
         self.inner.ipv4_addr
     }
 
+    /// Returns the IPv6 address of the container, if its network is dual-stack
+    pub fn ipv6_addr(&self) -> Option<Ipv6Addr> {
+        self.inner.ipv6_addr
+    }
+
     pub fn id(&self) -> &str {
         &self.inner.id
     }
@@ -344,6 +273,99 @@ impl Container {
     }
 }
 
+/// Builds `image`, once per test run, and returns its tag
+pub(crate) fn build_image(image: &Image) -> String {
+    let image_tag = format!("{PACKAGE_NAME}-{image}");
+
+    if !skip_docker_build() {
+        image.once().call_once(|| {
+            let dockerfile = image.dockerfile();
+            let docker_build_dir = TempDir::new().expect("failed to create temporary directory");
+            let docker_build_dir = docker_build_dir.path();
+            fs::write(docker_build_dir.join("Dockerfile"), dockerfile)
+                .expect("failed to write Dockerfile");
+
+            let mut command = Command::new("docker");
+            command
+                .args(["build", "--load", "-t"])
+                .arg(&image_tag)
+                .arg(docker_build_dir);
+            // Use BuildKit instead of the legacy builder. We need to choose this in order to
+            // pass the `--load` flag above. Depending on which BuildKit build driver is in use,
+            // the `--load` flag may be necessary, in order to load the resulting image as a
+            // local Docker image.
+            command.env("DOCKER_BUILDKIT", "1");
+
+            if let Image::Hickory { dnssec_feature, .. } = image {
+                command.arg(format!("--build-arg=DNSSEC_FEATURE={dnssec_feature}"));
+            };
+
+            if docker_build_gha_cache() {
+                let scope = match image {
+                    Image::Bind => "bind",
+                    Image::Dnslib => "dnslib",
+                    Image::Client => "client",
+                    Image::Hickory {
+                        dnssec_feature: HickoryDnssecFeature::AwsLcRs,
+                        ..
+                    } => "hickory-dnssec-aws-lc-rs",
+                    Image::Hickory {
+                        dnssec_feature: HickoryDnssecFeature::Ring,
+                        ..
+                    } => "hickory-dnssec-ring",
+                    Image::Unbound => "unbound",
+                    Image::Knot => "knot",
+                    Image::EdeDotCom => "ede-dot-com",
+                };
+
+                command.arg(format!("--cache-from=type=gha,scope=${scope}"));
+                if let Image::Hickory { .. } = image {
+                    command.arg(format!(
+                        "--cache-to=type=gha,scope=${scope},mode=max,ignore-error=true"
+                    ));
+                } else {
+                    command.arg(format!(
+                        "--cache-to=type=gha,scope=${scope},ignore-error=true"
+                    ));
+                }
+            }
+
+            if let Image::Hickory { repo, .. } = image {
+                let mut cp_r = Command::new("git");
+                cp_r.args([
+                    "clone",
+                    "--depth",
+                    "1",
+                    repo.as_str(),
+                    &docker_build_dir.join("src").display().to_string(),
+                ]);
+
+                exec_or_panic(&mut cp_r, false);
+            }
+
+            if let Image::EdeDotCom = image {
+                fs::write(
+                    docker_build_dir.join("configure_child.sh"),
+                    include_str!("docker/ede-dot-com/configure_child.sh"),
+                )
+                .expect("could not copy configure_child.sh");
+                fs::write(
+                    docker_build_dir.join("configure_parent.sh"),
+                    include_str!("docker/ede-dot-com/configure_parent.sh"),
+                )
+                .expect("could not copy configure_parent.sh");
+            }
+
+            fs::write(docker_build_dir.join(".dockerignore"), "src/.git")
+                .expect("could not create .dockerignore file");
+
+            exec_or_panic(&mut command, verbose_docker_build());
+        });
+    }
+
+    image_tag
+}
+
 fn verbose_docker_build() -> bool {
     env::var("DNS_TEST_VERBOSE_DOCKER_BUILD").as_deref().is_ok()
 }
@@ -380,8 +402,8 @@ fn container_count() -> usize {
 struct Inner {
     name: String,
     id: String,
-    // TODO probably also want the IPv6 address
     ipv4_addr: Ipv4Addr,
+    ipv6_addr: Option<Ipv6Addr>,
     network: Network,
 }
 
@@ -470,7 +492,7 @@ impl TryFrom<process::Output> for Output {
     }
 }
 
-fn checked_output(command: &mut Command) -> Result<process::Output> {
+pub(crate) fn checked_output(command: &mut Command) -> Result<process::Output> {
     let output = command.output()?;
     if output.status.success() {
         Ok(output)
@@ -479,13 +501,13 @@ fn checked_output(command: &mut Command) -> Result<process::Output> {
     }
 }
 
-fn get_ipv4_addr(container_id: &str) -> Result<Ipv4Addr> {
+fn get_ip_addrs(container_id: &str) -> Result<(Ipv4Addr, Option<Ipv6Addr>)> {
     let mut command = Command::new("docker");
     command
         .args([
             "inspect",
             "-f",
-            "{{range.NetworkSettings.Networks}}{{.IPAddress}}{{end}}",
+            "{{range.NetworkSettings.Networks}}{{.IPAddress}} {{.GlobalIPv6Address}}{{end}}",
         ])
         .arg(container_id);
 
@@ -494,9 +516,12 @@ fn get_ipv4_addr(container_id: &str) -> Result<Ipv4Addr> {
         return Err(format!("`{command:?}` failed").into());
     }
 
-    let ipv4_addr = str::from_utf8(&output.stdout)?.trim().to_string();
+    let stdout = str::from_utf8(&output.stdout)?;
+    let mut addrs = stdout.split_whitespace();
+    let ipv4_addr = addrs.next().unwrap_or_default().parse()?;
+    let ipv6_addr = addrs.next().map(str::parse).transpose()?;
 
-    Ok(ipv4_addr.parse()?)
+    Ok((ipv4_addr, ipv6_addr))
 }
 
 // this ensures the container gets deleted and does not linger after the test runner process ends
@@ -539,6 +564,22 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn ipv6_addr_works() -> Result<()> {
+        let network = Network::dual_stack()?;
+        let container = Container::run(&Image::Client, &network)?;
+        let ipv6_addr = container.ipv6_addr().expect("dual-stack network");
+
+        let output = container.output(&["ping", "-6", "-c1", &format!("{ipv6_addr}")])?;
+        assert!(output.status.success());
+
+        let network = Network::new()?;
+        let container = Container::run(&Image::Client, &network)?;
+        assert!(container.ipv6_addr().is_none());
+
+        Ok(())
+    }
+
     #[test]
     fn cp_works() -> Result<()> {
         let network = Network::new()?;
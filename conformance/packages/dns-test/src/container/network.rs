@@ -7,6 +7,7 @@ use std::{
 };
 
 use crate::Result;
+use crate::pcap::{self, PacketCapture};
 
 /// Represents a network in which to put containers into.
 #[derive(Clone)]
@@ -22,30 +23,87 @@ impl Network {
     pub fn netmask(&self) -> &str {
         &self.0.config.subnet
     }
+
+    /// Returns the IPv6 subnet mask, if this is a dual-stack network
+    pub fn ipv6_netmask(&self) -> Option<&str> {
+        self.0.config.ipv6_subnet.as_deref()
+    }
+
+    /// Whether the containers in this network also get an IPv6 address
+    pub fn is_dual_stack(&self) -> bool {
+        self.0.config.ipv6_subnet.is_some()
+    }
+
+    /// Starts capturing the packets exchanged by the containers in this network
+    ///
+    /// See [`PacketCapture`] for the requirements. This is independent of the capture started
+    /// for every network when `DNS_TEST_PCAP_DIR` is set.
+    pub fn capture(&self) -> Result<PacketCapture> {
+        PacketCapture::start(self.name(), &self.bridge_interface())
+    }
+
+    /// The name of the bridge interface of this network on the host
+    fn bridge_interface(&self) -> String {
+        self.0.config.bridge_interface()
+    }
 }
 
 struct NetworkInner {
     name: String,
     config: NetworkConfig,
+    /// The capture started because `DNS_TEST_PCAP_DIR` is set, saved when the network is removed
+    capture: Mutex<Option<PacketCapture>>,
 }
 
 impl Network {
     pub fn new() -> Result<Self> {
         let pid = process::id();
         let network_name = env!("CARGO_PKG_NAME");
-        Ok(Self(Arc::new(NetworkInner::new(pid, network_name, true)?)))
+        Ok(Self(Arc::new(NetworkInner::new(
+            pid,
+            network_name,
+            true,
+            false,
+        )?)))
     }
 
     pub fn with_internet_access() -> Result<Self> {
         let pid = process::id();
         let network_name = env!("CARGO_PKG_NAME");
-        Ok(Self(Arc::new(NetworkInner::new(pid, network_name, false)?)))
+        Ok(Self(Arc::new(NetworkInner::new(
+            pid,
+            network_name,
+            false,
+            false,
+        )?)))
+    }
+
+    /// Creates a network in which containers get both an IPv4 and an IPv6 address
+    ///
+    /// The IPv6 subnet is a unique local address (ULA) prefix, so like [`Network::new`] the
+    /// network has no internet access.
+    pub fn dual_stack() -> Result<Self> {
+        let pid = process::id();
+        let network_name = env!("CARGO_PKG_NAME");
+        Ok(Self(Arc::new(NetworkInner::new(
+            pid,
+            network_name,
+            true,
+            true,
+        )?)))
     }
 }
 
 /// This ensure the Docker network is deleted after the test runner process ends.
 impl Drop for NetworkInner {
     fn drop(&mut self) {
+        let capture = self.capture.get_mut().ok().and_then(Option::take);
+        if let Some(capture) = capture {
+            if let Err(e) = capture.stop() {
+                eprintln!("failed to save the packet capture of {}: {e}", self.name);
+            }
+        }
+
         let _ = Command::new("docker")
             .args(["network", "rm", "--force", self.name.as_str()])
             .stdout(Stdio::null())
@@ -55,7 +113,7 @@ impl Drop for NetworkInner {
 }
 
 impl NetworkInner {
-    pub fn new(pid: u32, network_name: &str, internal: bool) -> Result<Self> {
+    pub fn new(pid: u32, network_name: &str, internal: bool, ipv6: bool) -> Result<Self> {
         static CRITICAL_SECTION: Mutex<()> = Mutex::new(());
 
         let count = network_count();
@@ -66,6 +124,15 @@ impl NetworkInner {
         if internal {
             command.arg("--internal");
         }
+        if ipv6 {
+            // Docker does not allocate IPv6 subnets unless its daemon is configured to do so
+            command.arg("--ipv6").arg(format!(
+                "--subnet=fd00:{:x}:{:x}:{:x}::/64",
+                pid >> 16,
+                pid & 0xffff,
+                count & 0xffff
+            ));
+        }
         command.arg("--attachable").arg(&network_name);
 
         // create network
@@ -86,17 +153,38 @@ impl NetworkInner {
         // inspect & parse network details
         let config = get_network_config(&network_name)?;
 
+        let capture = if pcap::pcap_dir().is_some() {
+            Some(PacketCapture::start(
+                &network_name,
+                &config.bridge_interface(),
+            )?)
+        } else {
+            None
+        };
+
         Ok(Self {
             name: network_name,
             config,
+            capture: Mutex::new(capture),
         })
     }
 }
 
 /// Collects all important configs.
 pub struct NetworkConfig {
+    /// The ID of the network, which also names its bridge interface
+    id: String,
     /// The CIDR subnet mask, e.g. "172.21.0.0/16"
     subnet: String,
+    /// The CIDR subnet mask of a dual-stack network, e.g. "fd00:0:1234:1::/64"
+    ipv6_subnet: Option<String>,
+}
+
+impl NetworkConfig {
+    /// Docker names the bridge of a network after the first 12 characters of its ID
+    fn bridge_interface(&self) -> String {
+        format!("br-{}", &self.id[..self.id.len().min(12)])
+    }
 }
 
 /// Return network config
@@ -107,7 +195,7 @@ fn get_network_config(network_name: &str) -> Result<NetworkConfig> {
             "network",
             "inspect",
             "-f",
-            "{{range .IPAM.Config}}{{.Subnet}}{{end}}",
+            "{{.Id}}{{range .IPAM.Config}} {{.Subnet}}{{end}}",
         ])
         .arg(network_name);
 
@@ -116,8 +204,20 @@ fn get_network_config(network_name: &str) -> Result<NetworkConfig> {
         return Err(format!("{command:?} failed").into());
     }
 
-    let subnet = std::str::from_utf8(&output.stdout)?.trim().to_string();
-    Ok(NetworkConfig { subnet })
+    let stdout = std::str::from_utf8(&output.stdout)?.trim();
+    let mut columns = stdout.split_whitespace();
+    let id = columns.next();
+    let (ipv6_subnets, ipv4_subnets) =
+        columns.partition::<Vec<_>, _>(|subnet| subnet.contains(':'));
+    let (Some(id), Some(subnet)) = (id, ipv4_subnets.first()) else {
+        return Err(format!("unexpected output of {command:?}: {stdout}").into());
+    };
+
+    Ok(NetworkConfig {
+        id: id.to_string(),
+        subnet: subnet.to_string(),
+        ipv6_subnet: ipv6_subnets.first().map(|subnet| subnet.to_string()),
+    })
 }
 
 fn network_count() -> usize {
@@ -152,6 +252,17 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn dual_stack_works() -> Result<()> {
+        let network = Network::dual_stack()?;
+        assert!(network.is_dual_stack());
+        assert!(network.ipv6_netmask().unwrap().starts_with("fd00:"));
+
+        let network = Network::new()?;
+        assert!(!network.is_dual_stack());
+        Ok(())
+    }
+
     #[test]
     fn remove_network_works() -> Result<()> {
         let network = Network::new().expect("Failed to create network");
@@ -1,6 +1,8 @@
+use core::cmp::Ordering;
 use core::fmt;
 use core::str::FromStr;
 use std::borrow::Cow;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use crate::{Error, Result};
 
@@ -9,7 +11,12 @@ pub struct FQDN {
     inner: Cow<'static, str>,
 }
 
-// TODO likely needs further validation
+/// Maximum length of a label, in octets
+const MAX_LABEL_LEN: usize = 63;
+
+/// Maximum length of a name in wire format, in octets
+const MAX_NAME_LEN: usize = 255;
+
 #[allow(non_snake_case)]
 pub fn FQDN(input: impl Into<Cow<'static, str>>) -> Result<FQDN> {
     let input = input.into();
@@ -22,9 +29,62 @@ pub fn FQDN(input: impl Into<Cow<'static, str>>) -> Result<FQDN> {
         return Err("non-root FQDN cannot start with a `.`".into());
     }
 
+    if input != "." {
+        check_labels(&input[..input.len() - 1])?;
+    }
+
     Ok(FQDN { inner: input })
 }
 
+/// Checks the labels of a relative name, returning its length in wire format
+fn check_labels(relative: &str) -> Result<usize> {
+    // the root label
+    let mut len = 1;
+    for label in relative.split('.') {
+        len += 1 + check_label(label)?;
+    }
+
+    if len > MAX_NAME_LEN {
+        return Err(format!("name is longer than {MAX_NAME_LEN} octets").into());
+    }
+
+    Ok(len)
+}
+
+/// Checks a label in presentation format, returning its length in octets
+fn check_label(label: &str) -> Result<usize> {
+    if label.is_empty() {
+        return Err("empty label".into());
+    }
+
+    if let Some(c) = label.chars().find(|c| !c.is_ascii_graphic()) {
+        return Err(format!("invalid character {c:?} in label `{label}`").into());
+    }
+
+    // escapes, `\X` or `\DDD`, stand for a single octet
+    let mut len = 0;
+    let mut chars = label.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(digit) if digit.is_ascii_digit() => {
+                    chars.next();
+                    chars.next();
+                }
+                Some(_) => {}
+                None => return Err(format!("unterminated escape in label `{label}`").into()),
+            }
+        }
+        len += 1;
+    }
+
+    if len > MAX_LABEL_LEN {
+        return Err(format!("label `{label}` is longer than {MAX_LABEL_LEN} octets").into());
+    }
+
+    Ok(len)
+}
+
 impl FQDN {
     pub const ROOT: FQDN = FQDN {
         inner: Cow::Borrowed("."),
@@ -50,6 +110,40 @@ impl FQDN {
         inner: Cow::Borrowed("example.hickory-dns.testing."),
     };
 
+    pub const IN_ADDR_ARPA: FQDN = FQDN {
+        inner: Cow::Borrowed("in-addr.arpa."),
+    };
+
+    pub const IP6_ARPA: FQDN = FQDN {
+        inner: Cow::Borrowed("ip6.arpa."),
+    };
+
+    /// Returns the reverse lookup name of `addr`, under `in-addr.arpa.` or `ip6.arpa.`
+    pub fn reverse(addr: IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(addr) => Self::reverse_ipv4(addr),
+            IpAddr::V6(addr) => Self::reverse_ipv6(addr),
+        }
+    }
+
+    /// Returns the reverse lookup name of `addr`, e.g. `1.2.0.192.in-addr.arpa.` for `192.0.2.1`
+    pub fn reverse_ipv4(addr: Ipv4Addr) -> Self {
+        let [a, b, c, d] = addr.octets();
+        FQDN(format!("{d}.{c}.{b}.{a}.{}", Self::IN_ADDR_ARPA)).unwrap()
+    }
+
+    /// Returns the reverse lookup name of `addr`, made of its nibbles in reverse order under
+    /// `ip6.arpa.`
+    pub fn reverse_ipv6(addr: Ipv6Addr) -> Self {
+        let nibbles = addr
+            .octets()
+            .iter()
+            .rev()
+            .map(|octet| format!("{:x}.{:x}.", octet & 0xf, octet >> 4))
+            .collect::<String>();
+        FQDN(format!("{nibbles}{}", Self::IP6_ARPA)).unwrap()
+    }
+
     pub fn is_root(&self) -> bool {
         self.inner == "."
     }
@@ -58,13 +152,91 @@ impl FQDN {
         &self.inner
     }
 
+    /// Returns the child of this name with the given label
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `label` is not a single, valid label, or if the resulting name
+    /// is too long
     pub fn push_label(&self, label: &str) -> Self {
-        assert!(!label.is_empty());
-        assert!(!label.contains('.'));
+        assert!(!label.contains('.'), "`{label}` is not a single label");
+
+        self.join(label)
+            .unwrap_or_else(|e| panic!("cannot push label `{label}` to {self}: {e}"))
+    }
+
+    /// Appends this name to the `relative` name, e.g. `www.sub` to get `www.sub.example.com.`
+    pub fn join(&self, relative: &str) -> Result<Self> {
+        if relative.ends_with('.') {
+            return Err(format!("`{relative}` is not a relative name").into());
+        }
+
+        check_labels(relative)?;
+        let inner = if self.is_root() {
+            format!("{relative}.")
+        } else {
+            format!("{relative}.{}", self.inner)
+        };
+
+        FQDN(inner)
+    }
+
+    /// Returns the wildcard name of this zone, e.g. `*.example.com.` for `example.com.`
+    pub fn wildcard(&self) -> Self {
+        self.push_label("*")
+    }
+
+    /// Whether this is a wildcard name, i.e. its leftmost label is `*`
+    pub fn is_wildcard(&self) -> bool {
+        self.inner.starts_with("*.")
+    }
+
+    /// Whether this name is equal to or below `other`
+    pub fn is_subdomain_of(&self, other: &FQDN) -> bool {
+        let (name, other) = (
+            self.inner.to_ascii_lowercase(),
+            other.inner.to_ascii_lowercase(),
+        );
+        other == "." || name == other || name.ends_with(&format!(".{other}"))
+    }
+
+    /// Whether the wildcard name `wildcard` matches this name
+    ///
+    /// Names which exist in the zone, and thus are not synthesized from the wildcard, are not
+    /// taken into account.
+    pub fn is_matched_by(&self, wildcard: &FQDN) -> bool {
+        let Some(parent) = wildcard.parent().filter(|_| wildcard.is_wildcard()) else {
+            return false;
+        };
+
+        self.num_labels() > parent.num_labels() && self.is_subdomain_of(&parent)
+    }
+
+    /// Compares names in DNSSEC canonical order (RFC4034 section 6.1): label by label starting
+    /// from the rightmost one, ignoring ASCII case
+    pub fn canonical_cmp(&self, other: &FQDN) -> Ordering {
+        let labels = |fqdn: &FQDN| {
+            fqdn.as_str()
+                .split('.')
+                .filter(|label| !label.is_empty())
+                .rev()
+                .map(|label| label.to_ascii_lowercase())
+                .collect::<Vec<_>>()
+        };
+
+        labels(self).cmp(&labels(other))
+    }
 
-        Self {
-            inner: format!("{label}.{}", self.inner).into(),
+    /// The wire format of this name in its canonical, lowercase, form (RFC4034 section 6.2)
+    pub(crate) fn canonical_wire_format(&self) -> Vec<u8> {
+        let mut wire_format = Vec::with_capacity(self.inner.len() + 1);
+        for label in self.inner.split('.').filter(|label| !label.is_empty()) {
+            wire_format.push(label.len() as u8);
+            wire_format.extend(label.bytes().map(|byte| byte.to_ascii_lowercase()));
         }
+        wire_format.push(0);
+
+        wire_format
     }
 
     pub fn into_owned(self) -> FQDN {
@@ -154,4 +326,104 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn validation() {
+        assert!(FQDN("example..com.").is_err());
+        assert!(FQDN("exa mple.com.").is_err());
+        assert!(FQDN(format!("{}.com.", "a".repeat(64))).is_err());
+        assert!(FQDN(format!("{}.com.", "a".repeat(63))).is_ok());
+        assert!(FQDN(format!("{}.", vec!["a".repeat(63); 4].join("."))).is_err());
+        assert!(FQDN(format!("{}.", vec!["a".repeat(62); 4].join("."))).is_ok());
+
+        // escapes are a single octet
+        assert!(FQDN(format!("{}\\000.com.", "a".repeat(62))).is_ok());
+        assert!(FQDN("\\.com.").is_err());
+    }
+
+    #[test]
+    fn join() -> Result<()> {
+        assert_eq!(
+            FQDN("www.sub.hickory-dns.testing.")?,
+            FQDN::TEST_DOMAIN.join("www.sub")?
+        );
+        assert_eq!(FQDN::TEST_TLD, FQDN::ROOT.join("testing")?);
+        assert_eq!(
+            FQDN::EXAMPLE_SUBDOMAIN,
+            FQDN::TEST_DOMAIN.push_label("example")
+        );
+
+        assert!(FQDN::TEST_DOMAIN.join("www.").is_err());
+        assert!(FQDN::TEST_DOMAIN.join("").is_err());
+        assert!(FQDN::TEST_DOMAIN.join("www..sub").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn wildcard() -> Result<()> {
+        let wildcard = FQDN::TEST_DOMAIN.wildcard();
+        assert_eq!("*.hickory-dns.testing.", wildcard.as_str());
+        assert!(wildcard.is_wildcard());
+        assert!(!FQDN::TEST_DOMAIN.is_wildcard());
+
+        assert!(FQDN::EXAMPLE_SUBDOMAIN.is_matched_by(&wildcard));
+        assert!(FQDN("a.b.hickory-dns.testing.")?.is_matched_by(&wildcard));
+        assert!(!FQDN::TEST_DOMAIN.is_matched_by(&wildcard));
+        assert!(!FQDN("example.testing.")?.is_matched_by(&wildcard));
+        assert!(!FQDN::EXAMPLE_SUBDOMAIN.is_matched_by(&FQDN::TEST_DOMAIN));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reverse() {
+        assert_eq!(
+            "1.2.0.192.in-addr.arpa.",
+            FQDN::reverse(Ipv4Addr::new(192, 0, 2, 1).into()).as_str()
+        );
+        assert_eq!(
+            "b.a.9.8.7.6.5.0.4.0.0.0.3.0.0.0.2.0.0.0.1.0.0.0.0.0.0.0.1.2.3.4.ip6.arpa.",
+            FQDN::reverse(Ipv6Addr::new(0x4321, 0, 1, 2, 3, 4, 0x567, 0x89ab).into()).as_str()
+        );
+        assert!(
+            FQDN::reverse_ipv4(Ipv4Addr::new(192, 0, 2, 1)).is_subdomain_of(&FQDN::IN_ADDR_ARPA)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn push_label_rejects_long_labels() {
+        FQDN::TEST_DOMAIN.push_label(&"a".repeat(64));
+    }
+
+    #[test]
+    fn canonical_cmp() -> Result<()> {
+        // the example from RFC4034 section 6.1
+        let expected = [
+            "example.",
+            "a.example.",
+            "yljkjljk.a.example.",
+            "Z.a.example.",
+            "zABC.a.EXAMPLE.",
+            "z.example.",
+            "*.z.example.",
+        ];
+
+        let mut names = expected
+            .iter()
+            .rev()
+            .map(|name| name.parse())
+            .collect::<Result<Vec<FQDN>>>()?;
+        names.sort_by(FQDN::canonical_cmp);
+
+        let actual = names.iter().map(FQDN::as_str).collect::<Vec<_>>();
+        assert_eq!(expected, actual.as_slice());
+        assert_eq!(
+            Ordering::Equal,
+            FQDN("Example.")?.canonical_cmp(&FQDN("eXample.")?)
+        );
+
+        Ok(())
+    }
 }
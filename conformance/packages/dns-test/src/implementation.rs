@@ -7,6 +7,7 @@ use std::str::FromStr;
 
 use url::Url;
 
+use crate::container::Container;
 use crate::zone_file::ZoneFile;
 use crate::{Error, FQDN};
 
@@ -16,10 +17,14 @@ pub enum Config<'a> {
         origin: &'a FQDN,
         use_dnssec: bool,
         additional_zones: HashMap<FQDN, ZoneFile>,
+        /// Whether the server has an IPv6 address to listen on
+        ipv6: bool,
     },
     Resolver {
         use_dnssec: bool,
         netmask: &'a str,
+        /// The IPv6 subnet mask of a dual-stack network
+        ipv6_netmask: Option<&'a str>,
         /// Extended DNS error (RFC8914)
         ede: bool,
         case_randomization: bool,
@@ -56,6 +61,8 @@ pub enum Implementation {
         dnssec_feature: HickoryDnssecFeature,
     },
     Unbound,
+    /// Knot DNS, which only supports the name server role
+    Knot,
     EdeDotCom,
 }
 
@@ -66,6 +73,7 @@ impl Implementation {
             Implementation::Dnslib => true,
             Implementation::Hickory { .. } => true,
             Implementation::Unbound => true,
+            Implementation::Knot => false, // does not support running a resolver
             Implementation::EdeDotCom => false, // does not support running a resolver
         }
     }
@@ -105,11 +113,17 @@ impl Implementation {
         matches!(self, Self::Unbound)
     }
 
+    #[must_use]
+    pub fn is_knot(&self) -> bool {
+        matches!(self, Self::Knot)
+    }
+
     pub(crate) fn format_config(&self, config: Config) -> String {
         match config {
             Config::Resolver {
                 use_dnssec,
                 netmask,
+                ipv6_netmask,
                 ede,
                 case_randomization,
             } => match self {
@@ -133,6 +147,7 @@ impl Implementation {
                     minijinja::render!(
                         include_str!("templates/hickory.resolver.toml.jinja"),
                         use_dnssec => use_dnssec,
+                        ipv6_netmask => ipv6_netmask,
                         case_randomization => case_randomization,
                     )
                 }
@@ -142,11 +157,14 @@ impl Implementation {
                         include_str!("templates/unbound.conf.jinja"),
                         use_dnssec => use_dnssec,
                         netmask => netmask,
+                        ipv6_netmask => ipv6_netmask,
                         ede => ede,
                         case_randomization => case_randomization,
                     )
                 }
 
+                Self::Knot => panic!("Knot DNS does not support the resolver role"),
+
                 Self::EdeDotCom => {
                     // Does not support running a resolver
                     "".into()
@@ -157,6 +175,7 @@ impl Implementation {
                 origin,
                 use_dnssec,
                 additional_zones,
+                ipv6,
             } => match self {
                 Self::Bind => {
                     minijinja::render!(
@@ -179,6 +198,15 @@ impl Implementation {
                     )
                 }
 
+                Self::Knot => {
+                    minijinja::render!(
+                        include_str!("templates/knot.conf.jinja"),
+                        fqdn => origin.as_str(),
+                        ipv6 => ipv6,
+                        additional_zones => additional_zones.keys().map(|x| x.as_str()).collect::<Vec<&str>>(),
+                    )
+                }
+
                 Self::Hickory { dnssec_feature, .. } => {
                     let use_pkcs8 = matches!(dnssec_feature, HickoryDnssecFeature::Ring);
                     minijinja::render!(
@@ -220,6 +248,8 @@ impl Implementation {
                     use_dnssec => use_dnssec,
                 ),
 
+                Self::Knot => panic!("Knot DNS does not support the forwarder role"),
+
                 Self::EdeDotCom => {
                     // Does not support running a forwarder
                     "".into()
@@ -241,6 +271,8 @@ impl Implementation {
                 Role::Resolver | Role::Forwarder => Some("/etc/unbound/unbound.conf"),
             },
 
+            Self::Knot => Some("/etc/knot/knot.conf"),
+
             Self::EdeDotCom => Some("/etc/named.conf"),
         }
     }
@@ -254,6 +286,11 @@ impl Implementation {
                 Role::NameServer => "nsd -d",
                 Role::Resolver | Role::Forwarder => "unbound -d",
             },
+            Implementation::Knot => match role {
+                Role::NameServer => "knotd",
+                Role::Resolver => panic!("Knot DNS does not support the resolver role"),
+                Role::Forwarder => panic!("Knot DNS does not support the forwarder role"),
+            },
         };
 
         vec![
@@ -267,6 +304,24 @@ impl Implementation {
         ]
     }
 
+    /// Returns the version reported by the implementation running in `container`
+    pub(crate) fn version(&self, container: &Container, role: Role) -> Result<String, Error> {
+        let cmd = match self {
+            Self::Bind | Self::EdeDotCom => "named -v",
+            Self::Dnslib => "python3 -c 'import dnslib; print(dnslib.version)'",
+            Self::Hickory { .. } => "hickory-dns --version",
+            Self::Unbound => match role {
+                Role::NameServer => "nsd -v",
+                Role::Resolver | Role::Forwarder => "unbound -V",
+            },
+            Self::Knot => "knotd -V",
+        };
+
+        // `nsd` prints its version to stderr and `unbound` adds its build options after it
+        let output = container.stdout(&["sh", "-c", &format!("{cmd} 2>&1 | head -n 1")])?;
+        Ok(output.trim().to_string())
+    }
+
     pub(crate) fn stdout_logfile(&self, role: Role) -> String {
         self.logfile(role, Stream::Stdout)
     }
@@ -289,6 +344,8 @@ impl Implementation {
                 Role::NameServer => "/tmp/nsd",
                 Role::Resolver | Role::Forwarder => "/tmp/unbound",
             },
+
+            Implementation::Knot => "/tmp/knot",
         };
 
         format!("{path}.{suffix}")
@@ -11,7 +11,7 @@ pub use crate::container::Network;
 pub use crate::forwarder::Forwarder;
 pub use crate::fqdn::FQDN;
 pub use crate::implementation::{HickoryDnssecFeature, Implementation, Repository};
-pub use crate::resolver::Resolver;
+pub use crate::resolver::{Resolver, ResolverMatrix, ResolverMatrixReport, ResolverPermutation};
 pub use crate::trust_anchor::TrustAnchor;
 
 pub mod client;
@@ -20,8 +20,11 @@ mod forwarder;
 mod fqdn;
 mod implementation;
 pub mod name_server;
+pub mod nsec;
 pub mod nsec3;
+pub mod pcap;
 pub mod record;
+pub mod report;
 mod resolver;
 mod trust_anchor;
 pub mod tshark;
@@ -130,6 +133,10 @@ fn parse_implementation(env_var: &str) -> Implementation {
             return Implementation::Bind;
         }
 
+        if subject == "knot" {
+            return Implementation::Knot;
+        }
+
         if subject.starts_with("hickory ") {
             let tokens = subject.split_ascii_whitespace().collect::<Vec<_>>();
             let Ok([_, url, dnssec_feature]) = <[&str; 3]>::try_from(tokens) else {
@@ -1,11 +1,12 @@
 use core::sync::atomic::{self, AtomicUsize};
-use std::{collections::HashMap, net::Ipv4Addr, thread, time::Duration};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::{collections::HashMap, thread, time::Duration};
 
 use crate::container::{Child, Container, Network};
 use crate::implementation::{Config, Role};
 use crate::record::{self, DS, Record, SOA, SoaSettings};
 use crate::tshark::Tshark;
-use crate::zone_file::{self, Root, SigningKeys, ZoneFile};
+use crate::zone_file::{Root, SigningKeys, ZoneFile};
 use crate::zone_file::{SignSettings, Signer};
 use crate::{DEFAULT_TTL, FQDN, Implementation, Result, TrustAnchor};
 
@@ -66,6 +67,9 @@ impl Graph {
             let nameserver = NameServer::new(&implementation, parent.clone(), &network)?;
 
             nameservers_ns.add(nameserver.a());
+            if let Some(aaaa) = nameserver.aaaa() {
+                nameservers_ns.add(aaaa);
+            }
             nameservers.push(nameserver);
 
             zone = parent;
@@ -170,7 +174,8 @@ impl NameServerBuilder {
     /// it is started.
     ///
     /// The zone file will initially contain an SOA record, an NS record pointing to this name
-    /// server, and an A record with the address of this server.
+    /// server, and an A record with the address of this server. On a dual-stack network, it also
+    /// contains an AAAA record with the IPv6 address of this server.
     pub fn build(self) -> Result<NameServer<Stopped>> {
         let Self {
             zone,
@@ -198,6 +203,9 @@ impl NameServerBuilder {
         zone_file.add(Record::ns(zone, nameserver.clone()));
         // BIND requires that `nameserver` has an A record
         zone_file.add(Record::a(nameserver.clone(), container.ipv4_addr()));
+        if let Some(ipv6_addr) = container.ipv6_addr() {
+            zone_file.add(Record::aaaa(nameserver.clone(), ipv6_addr));
+        }
 
         Ok(NameServer {
             container,
@@ -237,6 +245,7 @@ impl NameServer<Stopped> {
     /// - one NS record, with this name server's FQDN set as the only available name server for
     ///   the zone
     /// - one A record, with this name server's IP address
+    /// - one AAAA record, with this name server's IPv6 address, on a dual-stack network
     pub fn new(implementation: &Implementation, zone: FQDN, network: &Network) -> Result<Self> {
         Self::builder(implementation.clone(), zone, network.clone()).build()
     }
@@ -261,12 +270,18 @@ impl NameServer<Stopped> {
     }
 
     /// Adds a NS + A record pair to the zone file from another NameServer
+    ///
+    /// An AAAA glue record is added as well if the other name server has an IPv6 address
     pub fn referral_nameserver<T>(&mut self, nameserver: &NameServer<T>) -> &mut Self {
         self.referral(
             nameserver.zone().clone(),
             nameserver.fqdn().clone(),
             nameserver.ipv4_addr(),
-        )
+        );
+        if let Some(aaaa) = nameserver.aaaa() {
+            self.zone_file.add(aaaa);
+        }
+        self
     }
 
     /// Adds a record to the name server's zone file
@@ -349,6 +364,7 @@ impl NameServer<Stopped> {
             origin: zone_file.origin(),
             use_dnssec: false,
             additional_zones: additional_zones.clone(),
+            ipv6: container.ipv6_addr().is_some(),
         };
 
         if let Some(conf_file_path) = implementation.conf_file_path(config.role()) {
@@ -436,6 +452,7 @@ impl NameServer<Signed> {
             origin: zone_file.origin(),
             use_dnssec: state.use_dnssec,
             additional_zones: additional_zones.clone(),
+            ipv6: container.ipv6_addr().is_some(),
         };
 
         if let Some(conf_file_path) = implementation.conf_file_path(config.role()) {
@@ -520,6 +537,91 @@ impl NameServer<Running> {
         self.state.trust_anchor.as_ref()
     }
 
+    /// Adds a record to the zone served by this running name server
+    ///
+    /// See [`NameServer::update_zone`]
+    pub fn add(&mut self, record: impl Into<Record>) -> Result<()> {
+        let record = record.into();
+        self.update_zone(|zone_file| zone_file.add(record))
+    }
+
+    /// Removes the records that match `predicate` from the zone served by this running name
+    /// server
+    ///
+    /// See [`NameServer::update_zone`]
+    pub fn remove(&mut self, mut predicate: impl FnMut(&Record) -> bool) -> Result<()> {
+        self.update_zone(|zone_file| zone_file.records.retain(|record| !predicate(record)))
+    }
+
+    /// Applies `update` to the zone file, bumps the SOA serial and makes the running server
+    /// load the new version of the zone
+    ///
+    /// BIND, NSD and Knot are signalled to reload their zones; Hickory, which has no reload mechanism,
+    /// is restarted. Reloading happens asynchronously so the new records may not be served
+    /// immediately after this method returns
+    ///
+    /// Only unsigned zones served from a zone file are supported
+    pub fn update_zone(&mut self, update: impl FnOnce(&mut ZoneFile)) -> Result<()> {
+        if self.state.trust_anchor.is_some() {
+            return Err("updating a signed zone is not supported".into());
+        }
+
+        if self.implementation.is_dnslib() {
+            return Err("dnslib name servers do not serve zone files".into());
+        }
+
+        update(&mut self.zone_file);
+        let serial = &mut self.zone_file.soa.settings.serial;
+        *serial = serial.wrapping_add(1);
+
+        self.container
+            .cp(&zone_file_path(), &self.zone_file.to_string())?;
+
+        match &self.implementation {
+            Implementation::Bind | Implementation::EdeDotCom => {
+                self.container
+                    .status_ok(&["sh", "-c", "kill -HUP $(cat /tmp/named.pid)"])?;
+            }
+
+            Implementation::Unbound => {
+                self.container
+                    .status_ok(&["sh", "-c", "kill -HUP $(cat /tmp/nsd.pid)"])?;
+            }
+
+            Implementation::Knot => {
+                self.container.status_ok(&[
+                    "knotc",
+                    "--socket",
+                    "/tmp/knot.sock",
+                    "zone-reload",
+                ])?;
+            }
+
+            Implementation::Hickory { .. } => {
+                // wait for the old process to exit so the new one can bind the DNS port
+                self.container.status_ok(&[
+                    "sh",
+                    "-c",
+                    "pid=$(pidof hickory-dns) && kill $pid && while kill -0 $pid 2>/dev/null; do sleep 0.1; done",
+                ])?;
+
+                self.state._child = self
+                    .container
+                    .spawn(&self.implementation.cmd_args(Role::NameServer))?;
+            }
+
+            Implementation::Dnslib => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the version reported by the implementation
+    pub fn version(&self) -> Result<String> {
+        self.implementation
+            .version(&self.container, Role::NameServer)
+    }
+
     /// Returns the logs collected so far
     pub fn logs(&self) -> Result<String> {
         if self.implementation.is_hickory() || self.implementation.is_dnslib() {
@@ -557,10 +659,19 @@ impl<S> NameServer<S> {
         &self.container
     }
 
+    pub fn implementation(&self) -> &Implementation {
+        &self.implementation
+    }
+
     pub fn ipv4_addr(&self) -> Ipv4Addr {
         self.container.ipv4_addr()
     }
 
+    /// Returns the IPv6 address of this server, if its network is dual-stack
+    pub fn ipv6_addr(&self) -> Option<Ipv6Addr> {
+        self.container.ipv6_addr()
+    }
+
     /// Zone file BEFORE signing
     pub fn zone_file(&self) -> &ZoneFile {
         &self.zone_file
@@ -579,9 +690,18 @@ impl<S> NameServer<S> {
         Record::a(self.fqdn().clone(), self.ipv4_addr())
     }
 
+    /// Returns the [`Record::AAAA`] record for this server, if it has an IPv6 address.
+    pub fn aaaa(&self) -> Option<Record> {
+        self.ipv6_addr()
+            .map(|ipv6_addr| Record::aaaa(self.fqdn().clone(), ipv6_addr))
+    }
+
     /// Returns the [`Root`] hint for this server.
     pub fn root_hint(&self) -> Root {
-        Root::new(self.fqdn().clone(), self.ipv4_addr())
+        Root {
+            ipv6_addr: self.ipv6_addr(),
+            ..Root::new(self.fqdn().clone(), self.ipv4_addr())
+        }
     }
 }
 
@@ -594,30 +714,6 @@ pub struct DS2 {
     pub zsk: DS,
 }
 
-impl DS2 {
-    pub(crate) fn classify(dses: Vec<DS>, zsk: &zone_file::DNSKEY, ksk: &zone_file::DNSKEY) -> DS2 {
-        let mut ksk_ds = None;
-        let mut zsk_ds = None;
-
-        let zsk_tag = zsk.rdata().calculate_key_tag();
-        let ksk_tag = ksk.rdata().calculate_key_tag();
-        for ds in dses {
-            if ds.key_tag == zsk_tag {
-                assert!(zsk_ds.is_none());
-                zsk_ds = Some(ds);
-            } else if ds.key_tag == ksk_tag {
-                assert!(ksk_ds.is_none());
-                ksk_ds = Some(ds);
-            }
-        }
-
-        DS2 {
-            ksk: ksk_ds.expect("DS for KSK not found"),
-            zsk: zsk_ds.expect("DS for ZSK not found"),
-        }
-    }
-}
-
 pub struct Signed {
     pub(crate) ds: DS2,
     pub(crate) zsk: record::DNSKEY,
@@ -700,6 +796,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn dual_stack() -> Result<()> {
+        let network = Network::dual_stack()?;
+        let mut root_ns = NameServer::new(&Implementation::Unbound, FQDN::ROOT, &network)?;
+        let tld_ns = NameServer::new(&Implementation::Unbound, FQDN::TEST_TLD, &network)?;
+        let tld_ns_ipv6_addr = tld_ns.ipv6_addr().expect("dual-stack network");
+        root_ns.referral_nameserver(&tld_ns);
+        assert!(root_ns.root_hint().ipv6_addr.is_some());
+
+        let root_ns = root_ns.start()?;
+        let client = Client::new(&network)?;
+        let ipv6_addr = root_ns.ipv6_addr().expect("dual-stack network");
+        let output = client.dig(
+            DigSettings::default(),
+            ipv6_addr,
+            RecordType::NS,
+            &FQDN::TEST_TLD,
+        )?;
+
+        assert!(output.status.is_noerror());
+        let glue = output
+            .additional
+            .into_iter()
+            .filter_map(|record| record.try_into_aaaa().ok())
+            .collect::<Vec<_>>();
+        let [aaaa] = glue.try_into().expect("exactly one AAAA glue record");
+        assert_eq!(tld_ns_ipv6_addr, aaaa.ipv6_addr);
+
+        Ok(())
+    }
+
     #[test]
     fn with_referral() -> Result<()> {
         let network = Network::new()?;
@@ -790,6 +917,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn knot_logs_works() -> Result<()> {
+        let network = Network::new()?;
+        let ns = NameServer::new(&Implementation::Knot, FQDN::ROOT, &network)?.start()?;
+        // no way to block until the server has finished starting up so we just give it some
+        // arbitrary amount of time
+        thread::sleep(Duration::from_secs(1));
+        let logs = ns.logs()?;
+
+        eprintln!("{logs}");
+        assert!(logs.contains("Knot DNS") && logs.contains("starting"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn nsd_update_zone() -> Result<()> {
+        update_zone_fixture(Implementation::Unbound)
+    }
+
+    #[test]
+    fn named_update_zone() -> Result<()> {
+        update_zone_fixture(Implementation::Bind)
+    }
+
+    #[test]
+    fn knot_update_zone() -> Result<()> {
+        update_zone_fixture(Implementation::Knot)
+    }
+
+    fn update_zone_fixture(implementation: Implementation) -> Result<()> {
+        let network = Network::new()?;
+        let mut ns = NameServer::new(&implementation, FQDN::TEST_DOMAIN, &network)?.start()?;
+        let ns_addr = ns.ipv4_addr();
+        let initial_serial = ns.zone_file().soa.settings.serial;
+
+        let needle_fqdn = FQDN::EXAMPLE_SUBDOMAIN;
+        let expected_ipv4_addr = Ipv4Addr::new(1, 2, 3, 4);
+        ns.add(Record::a(needle_fqdn.clone(), expected_ipv4_addr))?;
+        assert_eq!(initial_serial + 1, ns.zone_file().soa.settings.serial);
+
+        // no way to block until the server has reloaded the zone so we just give it some
+        // arbitrary amount of time
+        thread::sleep(Duration::from_secs(1));
+
+        let client = Client::new(&network)?;
+        let output = client.dig(DigSettings::default(), ns_addr, RecordType::A, &needle_fqdn)?;
+
+        assert!(output.status.is_noerror());
+        let [a] = output.answer.try_into().expect("exactly one record");
+        assert_eq!(expected_ipv4_addr, a.try_into_a().unwrap().ipv4_addr);
+
+        ns.remove(|record| matches!(record, Record::A(a) if a.fqdn == needle_fqdn))?;
+        thread::sleep(Duration::from_secs(1));
+
+        let output = client.dig(DigSettings::default(), ns_addr, RecordType::A, &needle_fqdn)?;
+
+        assert!(output.status.is_nxdomain());
+
+        Ok(())
+    }
+
     #[test]
     fn hickory_logs_works() -> Result<()> {
         let network = Network::new()?;
@@ -831,6 +1020,12 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn knot_multizone_works() -> Result<()> {
+        multizone_test(&Implementation::Knot)?;
+        Ok(())
+    }
+
     #[cfg(test)]
     fn multizone_test(implementation: &Implementation) -> Result<()> {
         let network = Network::new()?;
@@ -0,0 +1,190 @@
+use crate::Result;
+use crate::record::{NSEC, Record, RecordType};
+
+/// Sorts records into DNSSEC canonical order (RFC4034 section 6): by owner name in canonical
+/// order and then by record type.
+///
+/// Records that belong to the same RRset keep their relative order, as ordering them by RDATA
+/// requires their wire format.
+pub fn sort_canonical(records: &mut [Record]) {
+    records.sort_by(|a, b| {
+        a.owner()
+            .canonical_cmp(b.owner())
+            .then_with(|| a.record_type().code().cmp(&b.record_type().code()))
+    });
+}
+
+/// Computes the NSEC chain a signer is expected to produce for the zone made up of `records`.
+///
+/// The zone apex is given by the SOA record. DNSSEC records already present in `records` (RRSIG,
+/// NSEC and NSEC3) are ignored. Names below a delegation are glue and are left out of the chain,
+/// while the delegation itself only lists its NS and DS records (RFC4035 section 2.3). Empty
+/// non-terminals do not get an NSEC record.
+///
+/// The NSEC records are returned in canonical order and use the smaller of the SOA TTL and the
+/// SOA MINIMUM field as their TTL (RFC9077).
+pub fn nsec_chain(records: &[Record]) -> Result<Vec<NSEC>> {
+    let soa = records
+        .iter()
+        .find_map(|record| match record {
+            Record::SOA(soa) => Some(soa),
+            _ => None,
+        })
+        .ok_or("zone has no SOA record")?;
+    let apex = &soa.zone;
+    let ttl = soa.ttl.min(soa.settings.minimum);
+
+    let mut records = records
+        .iter()
+        .filter(|record| {
+            !matches!(
+                record.record_type(),
+                RecordType::RRSIG | RecordType::NSEC | RecordType::NSEC3
+            )
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if let Some(outside) = records
+        .iter()
+        .find(|record| !record.owner().is_subdomain_of(apex))
+    {
+        return Err(format!("{} is not within zone {apex}", outside.owner()).into());
+    }
+
+    let delegations = records
+        .iter()
+        .filter(|record| record.record_type() == RecordType::NS && record.owner() != apex)
+        .map(|record| record.owner().clone())
+        .collect::<Vec<_>>();
+    let is_glue = |record: &Record| {
+        delegations.iter().any(|delegation| {
+            record.owner() != delegation && record.owner().is_subdomain_of(delegation)
+        })
+    };
+    records.retain(|record| !is_glue(record));
+
+    sort_canonical(&mut records);
+
+    let mut owners = Vec::<(_, Vec<RecordType>)>::new();
+    for record in &records {
+        let owner = record.owner();
+        let record_type = record.record_type();
+
+        let is_delegation = delegations.contains(owner);
+        if is_delegation && !matches!(record_type, RecordType::NS | RecordType::DS) {
+            continue;
+        }
+
+        match owners.last_mut() {
+            Some((last, record_types)) if owner.canonical_cmp(last).is_eq() => {
+                if !record_types.contains(&record_type) {
+                    record_types.push(record_type);
+                }
+            }
+            _ => owners.push((owner.clone(), vec![record_type])),
+        }
+    }
+
+    let mut nsecs = Vec::with_capacity(owners.len());
+    for (index, (fqdn, mut record_types)) in owners.iter().cloned().enumerate() {
+        // an unsigned delegation has no RRSIG records
+        if !delegations.contains(&fqdn) || record_types.contains(&RecordType::DS) {
+            record_types.push(RecordType::RRSIG);
+        }
+        record_types.push(RecordType::NSEC);
+        record_types.sort_by_key(RecordType::code);
+
+        // the last NSEC record points back to the apex
+        let next_domain = owners
+            .get(index + 1)
+            .map_or_else(|| apex.clone(), |(next, _)| next.clone());
+
+        nsecs.push(NSEC {
+            fqdn,
+            ttl,
+            next_domain,
+            record_types,
+        });
+    }
+
+    Ok(nsecs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zone_file::ZoneFile;
+
+    #[test]
+    fn sort() -> Result<()> {
+        let mut records = ZoneFile::parse(
+            "$ORIGIN example.
+$TTL 3600
+z       A       192.0.2.3
+a       TXT     \"a\"
+a       A       192.0.2.1
+Z.a     A       192.0.2.2
+@       NS      ns.example.
+@       SOA     ns.example. admin.example. 1 2 3 4 5
+",
+        )?;
+        sort_canonical(&mut records);
+
+        let actual = records
+            .iter()
+            .map(|record| format!("{} {}", record.owner(), record.record_type()))
+            .collect::<Vec<_>>();
+        let expected = [
+            "example. NS",
+            "example. SOA",
+            "a.example. A",
+            "a.example. TXT",
+            "Z.a.example. A",
+            "z.example. A",
+        ];
+        assert_eq!(expected, actual.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn chain() -> Result<()> {
+        let records = ZoneFile::parse(
+            "$ORIGIN example.
+$TTL 3600
+@               SOA     ns.example. admin.example. 1 2 3 4 300
+@               NS      ns.example.
+ns              A       192.0.2.1
+www.sub         A       192.0.2.2
+unsigned        NS      ns.unsigned.example.
+ns.unsigned     A       192.0.2.3
+signed          NS      ns.signed.example.
+signed          DS      12345 8 2 0123456789ABCDEF
+signed          TXT     \"occluded\"
+*               TXT     \"wildcard\"
+",
+        )?;
+
+        let actual = nsec_chain(&records)?
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let expected = [
+            "example.\t300\tIN\tNSEC\t*.example. NS SOA RRSIG NSEC",
+            "*.example.\t300\tIN\tNSEC\tns.example. TXT RRSIG NSEC",
+            "ns.example.\t300\tIN\tNSEC\tsigned.example. A RRSIG NSEC",
+            "signed.example.\t300\tIN\tNSEC\twww.sub.example. NS DS RRSIG NSEC",
+            "www.sub.example.\t300\tIN\tNSEC\tunsigned.example. A RRSIG NSEC",
+            "unsigned.example.\t300\tIN\tNSEC\texample. NS NSEC",
+        ];
+        assert_eq!(expected, actual.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn chain_requires_soa() {
+        assert!(nsec_chain(&[]).is_err());
+    }
+}
@@ -1,6 +1,43 @@
 use std::collections::BTreeMap;
 
-use crate::{record::NSEC3, zone_file::ZoneFile};
+use data_encoding::BASE32HEX_NOPAD;
+use sha1::{Digest, Sha1};
+
+use crate::{FQDN, record::NSEC3, zone_file::ZoneFile};
+
+/// Computes the hashed owner name of `fqdn` (RFC5155 section 5), base32hex encoded in uppercase
+/// as used by `NSEC3Records`, so that the NSEC3 record of a name can be looked up
+///
+/// `salt` is hex encoded, like in the NSEC3PARAM record, and `algorithm` is the hash algorithm
+/// number.
+///
+/// # Panics
+///
+/// This function panics if `algorithm` is not SHA-1 (1), the only algorithm defined, or if `salt`
+/// is not valid hex
+pub fn nsec3_hash(fqdn: &FQDN, salt: Option<&str>, iterations: u16, algorithm: u8) -> String {
+    assert_eq!(
+        1, algorithm,
+        "unsupported NSEC3 hash algorithm: {algorithm}"
+    );
+    let salt = salt
+        .map(|salt| hex::decode(salt).unwrap_or_else(|e| panic!("invalid salt `{salt}`: {e}")))
+        .unwrap_or_default();
+
+    let hash = |data: &[u8]| {
+        let mut hasher = Sha1::new();
+        hasher.update(data);
+        hasher.update(&salt);
+        hasher.finalize().to_vec()
+    };
+
+    let mut digest = hash(&fqdn.canonical_wire_format());
+    for _ in 0..iterations {
+        digest = hash(&digest);
+    }
+
+    BASE32HEX_NOPAD.encode(&digest)
+}
 
 pub struct NSEC3Records {
     records: BTreeMap<String, NSEC3>,
@@ -64,3 +101,37 @@ impl NSEC3Records {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash() -> crate::Result<()> {
+        // 1 iteration without salt, the defaults of `ldns-signzone`
+        assert_eq!(
+            "LLKH4L6I60VHAPP6VRM3DFR9RI8AK9I0",
+            nsec3_hash(&FQDN("alice.com.")?, None, 1, 1)
+        );
+        assert_eq!(
+            "19GBV5V1BO0P51H34JQDH1C8CIAA5RAQ",
+            nsec3_hash(&FQDN("*.Alice.COM.")?, None, 1, 1)
+        );
+        assert_eq!(
+            "4MQ63TFGQ53CN7BLM4013T77HAK1PNBS",
+            nsec3_hash(&FQDN("alice.com.")?, None, 0, 1)
+        );
+
+        // the example zone of RFC5155 appendix A
+        assert_eq!(
+            "0P9MHAVEQVM6T7VBL5LOP2U3T2RP3TOM",
+            nsec3_hash(&FQDN("example.")?, Some("aabbccdd"), 12, 1)
+        );
+        assert_eq!(
+            "35MTHGPGCU1QG68FAB165KLNSNK3DPVL",
+            nsec3_hash(&FQDN("a.example.")?, Some("aabbccdd"), 12, 1)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,472 @@
+//! Packet captures of whole networks
+//!
+//! Unlike [`crate::tshark::Tshark`], which captures the DNS messages of a single container as
+//! they happen, a [`PacketCapture`] records every packet exchanged in a [`crate::Network`] into a
+//! pcap file, which can be inspected with e.g. Wireshark after the test has finished. Setting the
+//! `DNS_TEST_PCAP_DIR` env var captures every network and saves the pcap files into
+//! `$DNS_TEST_PCAP_DIR/$TEST_NAME/`.
+
+use core::result::Result as CoreResult;
+use std::io::{BufRead, BufReader};
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::atomic::{self, AtomicUsize};
+use std::thread::{self, JoinHandle};
+use std::{env, fs};
+
+use serde::Deserialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+use crate::Result;
+use crate::container::{self, Image, Output};
+use crate::tshark::Message;
+
+const PCAP_DIR_ENV_VAR: &str = "DNS_TEST_PCAP_DIR";
+const CAPTURE_PATH: &str = "/tmp/capture.pcap";
+const PID_PATH: &str = "/tmp/tcpdump.pid";
+
+/// The directory that the pcap files of every network are saved into, if captures are enabled
+pub(crate) fn pcap_dir() -> Option<PathBuf> {
+    env::var_os(PCAP_DIR_ENV_VAR).map(PathBuf::from)
+}
+
+/// A `tcpdump` capture of the bridge interface of a network
+///
+/// `tcpdump` runs in a container on the host network, which requires the Docker daemon to run on
+/// the same (virtual) machine as the bridges of its networks. Rootless Docker is not supported.
+pub struct PacketCapture {
+    /// ID of the container running `tcpdump`
+    id: String,
+
+    /// Subprocess running `docker exec` that in turn runs `tcpdump` inside the container.
+    child: Option<process::Child>,
+
+    /// Thread join handle for the thread capturing standard error.
+    stderr_handle: Option<JoinHandle<String>>,
+
+    /// Where to save the pcap file, if `DNS_TEST_PCAP_DIR` is set
+    output: Option<PathBuf>,
+}
+
+impl PacketCapture {
+    pub(crate) fn start(network_name: &str, bridge_interface: &str) -> Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let image_tag = container::build_image(&Image::Client);
+        let count = COUNT.fetch_add(1, atomic::Ordering::Relaxed);
+        let name = format!("{}-pcap-{}-{count}", env!("CARGO_PKG_NAME"), process::id());
+
+        let mut command = Command::new("docker");
+        command
+            .args([
+                "run",
+                "--rm",
+                "--detach",
+                "--cap-add=NET_RAW",
+                "--cap-add=NET_ADMIN",
+                "--network",
+                "host",
+                "--name",
+                &name,
+            ])
+            .arg(image_tag)
+            .args(["sleep", "infinity"]);
+        let output: Output = container::checked_output(&mut command)?.try_into()?;
+
+        let output_path = pcap_dir().map(|dir| {
+            let thread = thread::current();
+            let test_name = thread.name().unwrap_or("main").replace("::", "-");
+            dir.join(test_name)
+                .join(format!("{network_name}-{count}.pcap"))
+        });
+
+        // from here on, `Drop` removes the container if anything fails
+        let mut this = Self {
+            id: output.stdout,
+            child: None,
+            stderr_handle: None,
+            output: output_path,
+        };
+
+        let tcpdump = format!(
+            "echo $$ > {PID_PATH}
+exec tcpdump -i {bridge_interface} -U -w {CAPTURE_PATH}"
+        );
+        let mut child = Command::new("docker")
+            .args(["exec", &this.id, "sh", "-c", &tcpdump])
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stderr = child.stderr.take().ok_or("could not retrieve stderr")?;
+        this.child = Some(child);
+
+        let mut stderr = BufReader::new(stderr).lines();
+        let mut early_output = String::new();
+        loop {
+            let Some(line) = stderr.next() else {
+                return Err(format!("`tcpdump` exited early\n{early_output}").into());
+            };
+            let line = line?;
+
+            if line.contains("listening on") {
+                break;
+            }
+            early_output.push_str(&line);
+            early_output.push('\n');
+        }
+
+        // keep reading standard error so that `tcpdump` can not block on it
+        this.stderr_handle = Some(thread::spawn(move || {
+            let mut buf = String::new();
+            for line in stderr.map_while(CoreResult::ok) {
+                buf.push_str(&line);
+                buf.push('\n');
+            }
+            buf
+        }));
+
+        Ok(this)
+    }
+
+    /// Stops the capture, saves the pcap file if `DNS_TEST_PCAP_DIR` is set and parses the DNS
+    /// messages of the capture
+    pub fn stop(mut self) -> Result<Pcap> {
+        let kill = format!("kill -INT $(cat {PID_PATH})");
+        let mut command = Command::new("docker");
+        command.args(["exec", &self.id, "sh", "-c", &kill]);
+        container::checked_output(&mut command)?;
+
+        // wait until `tcpdump` has flushed the pcap file and exited
+        if let Some(mut child) = self.child.take() {
+            let status = child.wait()?;
+            let stderr = self
+                .stderr_handle
+                .take()
+                .map(|handle| handle.join().unwrap_or_default())
+                .unwrap_or_default();
+            if !status.success() {
+                return Err(format!("the `tcpdump` process failed\n{stderr}").into());
+            }
+        }
+
+        if let Some(output) = &self.output {
+            if let Some(dir) = output.parent() {
+                fs::create_dir_all(dir)?;
+            }
+
+            let mut command = Command::new("docker");
+            command
+                .arg("cp")
+                .arg(format!("{}:{CAPTURE_PATH}", self.id))
+                .arg(output);
+            container::checked_output(&mut command)?;
+        }
+
+        let mut command = Command::new("docker");
+        command.args([
+            "exec",
+            &self.id,
+            "tshark",
+            "-r",
+            CAPTURE_PATH,
+            "-T",
+            "json",
+            "--no-duplicate-keys",
+            "-Y",
+            "dns",
+            "-O",
+            "dns",
+        ]);
+        let output: Output = container::checked_output(&mut command)?.try_into()?;
+
+        Ok(Pcap {
+            path: self.output.take(),
+            packets: parse(&output.stdout)?,
+        })
+    }
+}
+
+impl Drop for PacketCapture {
+    fn drop(&mut self) {
+        let _ = Command::new("docker")
+            .args(["rm", "--force", self.id.as_str()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// The DNS messages of a stopped [`PacketCapture`]
+#[derive(Debug)]
+pub struct Pcap {
+    path: Option<PathBuf>,
+    packets: Vec<Packet>,
+}
+
+impl Pcap {
+    /// The path that the pcap file was saved to, `None` if `DNS_TEST_PCAP_DIR` is not set
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The DNS messages in the order they were captured
+    pub fn packets(&self) -> &[Packet] {
+        &self.packets
+    }
+
+    /// Pairs every query with the first response to it
+    ///
+    /// A response answers a query if it has the same ID and transport and goes back to the
+    /// address that the query came from. Responses that answer no query are left out.
+    pub fn exchanges(&self) -> Vec<Exchange<'_>> {
+        let mut answered = vec![false; self.packets.len()];
+        let mut exchanges = vec![];
+        for (index, query) in self.packets.iter().enumerate() {
+            if query.message.is_response() {
+                continue;
+            }
+
+            let response = self.packets[index + 1..]
+                .iter()
+                .zip(&mut answered[index + 1..])
+                .find(|(response, answered)| !**answered && response.answers(query))
+                .map(|(response, answered)| {
+                    *answered = true;
+                    response
+                });
+
+            exchanges.push(Exchange { query, response });
+        }
+
+        exchanges
+    }
+}
+
+/// A captured DNS message
+#[derive(Debug)]
+pub struct Packet {
+    pub source: SocketAddrV4,
+    pub destination: SocketAddrV4,
+    pub transport: Transport,
+    pub message: Message,
+}
+
+impl Packet {
+    fn answers(&self, query: &Packet) -> bool {
+        self.message.is_response()
+            && self.message.id() == query.message.id()
+            && self.transport == query.transport
+            && self.source == query.destination
+            && self.destination == query.source
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+/// A query and the response to it, if one was captured
+#[derive(Debug)]
+pub struct Exchange<'a> {
+    pub query: &'a Packet,
+    pub response: Option<&'a Packet>,
+}
+
+/// Parses the output of `tshark -T json --no-duplicate-keys`, skipping the packets that are not
+/// carried by IPv4 and UDP or TCP
+fn parse(json: &str) -> Result<Vec<Packet>> {
+    let entries: Vec<Entry> = serde_json::from_str(json)?;
+
+    let mut packets = vec![];
+    for entry in entries {
+        let Layers { ip, udp, tcp, dns } = entry._source.layers;
+        let (Some(ip), Some(dns)) = (ip, dns) else {
+            continue;
+        };
+        let (transport, ports) = match (udp, tcp) {
+            (Some(ports), _) => (Transport::Udp, ports),
+            (None, Some(ports)) => (Transport::Tcp, ports),
+            (None, None) => continue,
+        };
+
+        // a TCP segment may carry several messages
+        let messages = match dns {
+            serde_json::Value::Array(messages) => messages,
+            message => vec![message],
+        };
+        for message in messages {
+            packets.push(Packet {
+                source: SocketAddrV4::new(ip.src, ports.src),
+                destination: SocketAddrV4::new(ip.dst, ports.dst),
+                transport,
+                message: Message::new(message),
+            });
+        }
+    }
+
+    Ok(packets)
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    _source: Source,
+}
+
+#[derive(Deserialize)]
+struct Source {
+    layers: Layers,
+}
+
+#[derive(Deserialize)]
+struct Layers {
+    ip: Option<Ip>,
+    udp: Option<Ports>,
+    tcp: Option<Ports>,
+    dns: Option<serde_json::Value>,
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+struct Ip {
+    #[serde(rename = "ip.src")]
+    #[serde_as(as = "DisplayFromStr")]
+    src: Ipv4Addr,
+
+    #[serde(rename = "ip.dst")]
+    #[serde_as(as = "DisplayFromStr")]
+    dst: Ipv4Addr,
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+struct Ports {
+    #[serde(rename = "udp.srcport", alias = "tcp.srcport")]
+    #[serde_as(as = "DisplayFromStr")]
+    src: u16,
+
+    #[serde(rename = "udp.dstport", alias = "tcp.dstport")]
+    #[serde_as(as = "DisplayFromStr")]
+    dst: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::{Client, DigSettings};
+    use crate::name_server::NameServer;
+    use crate::record::RecordType;
+    use crate::{FQDN, Implementation, Network};
+
+    use super::*;
+
+    const COOKIE: u16 = 10;
+
+    #[test]
+    fn exchanges() -> Result<()> {
+        let json = r#"[
+  {
+    "_source": {
+      "layers": {
+        "ip": { "ip.src": "172.21.0.3", "ip.dst": "172.21.0.2" },
+        "udp": { "udp.srcport": "40000", "udp.dstport": "53" },
+        "dns": {
+          "dns.id": "0x1a2b",
+          "dns.flags_tree": { "dns.flags.response": "0", "dns.flags.recdesired": "1" },
+          "Additional records": {
+            "<Root>: type OPT": {
+              "dns.rr.udp_payload_size": "1232",
+              "dns.resp.z_tree": { "dns.resp.z.do": "1" },
+              "Option: COOKIE": { "dns.opt.code": "10", "dns.opt.len": "8" }
+            }
+          }
+        }
+      }
+    }
+  },
+  {
+    "_source": {
+      "layers": {
+        "ip": { "ip.src": "172.21.0.3", "ip.dst": "172.21.0.4" },
+        "udp": { "udp.srcport": "40001", "udp.dstport": "53" },
+        "dns": {
+          "dns.id": "0x0001",
+          "dns.flags_tree": { "dns.flags.response": "0", "dns.flags.recdesired": "0" }
+        }
+      }
+    }
+  },
+  {
+    "_source": {
+      "layers": {
+        "ip": { "ip.src": "172.21.0.2", "ip.dst": "172.21.0.3" },
+        "udp": { "udp.srcport": "53", "udp.dstport": "40000" },
+        "dns": {
+          "dns.id": "0x1a2b",
+          "dns.flags_tree": { "dns.flags.response": "1", "dns.flags.recdesired": "1" }
+        }
+      }
+    }
+  }
+]"#;
+
+        let pcap = Pcap {
+            path: None,
+            packets: parse(json)?,
+        };
+        assert_eq!(3, pcap.packets().len());
+
+        let exchanges = pcap.exchanges();
+        let [answered, unanswered] = exchanges.try_into().expect("2 exchanges");
+
+        let query = answered.query;
+        assert_eq!(Transport::Udp, query.transport);
+        assert_eq!("172.21.0.3:40000", query.source.to_string());
+        assert_eq!(Some(0x1a2b), query.message.id());
+        assert!(query.message.is_rd_flag_set());
+        assert_eq!(Some(true), query.message.is_do_bit_set());
+        assert_eq!(vec![COOKIE], query.message.edns_option_codes());
+
+        let response = answered.response.expect("response");
+        assert!(response.message.is_response());
+        assert_eq!(None, response.message.is_do_bit_set());
+        assert!(response.message.edns_option_codes().is_empty());
+
+        assert_eq!(Some(1), unanswered.query.message.id());
+        assert!(unanswered.response.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn network() -> Result<()> {
+        let network = &Network::new()?;
+        let ns = NameServer::new(&Implementation::Unbound, FQDN::ROOT, network)?.start()?;
+        let capture = network.capture()?;
+
+        let client = Client::new(network)?;
+        let resp = client.dig(
+            DigSettings::default(),
+            ns.ipv4_addr(),
+            RecordType::SOA,
+            &FQDN::ROOT,
+        )?;
+        assert!(resp.status.is_noerror());
+
+        let pcap = capture.stop()?;
+        let exchanges = pcap.exchanges();
+        let [exchange] = exchanges.try_into().expect("1 exchange");
+        assert_eq!(client.ipv4_addr(), *exchange.query.source.ip());
+        assert_eq!(ns.ipv4_addr(), *exchange.query.destination.ip());
+        assert!(exchange.response.is_some());
+
+        Ok(())
+    }
+}
@@ -5,15 +5,22 @@ use core::str::FromStr;
 use core::{array, fmt};
 use std::borrow::Cow;
 use std::fmt::Write;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::{Add, Sub};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{any, mem};
 
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use x509_cert::Certificate;
+use x509_cert::der::{Decode, Encode};
+
 use crate::{DEFAULT_TTL, Error, FQDN, Result};
 
 const CLASS: &str = "IN"; // "internet"
 
 macro_rules! record_types {
-    ($($variant:ident),*) => {
+    ($($variant:ident = $code:literal),*) => {
         #[allow(clippy::upper_case_acronyms)]
         #[derive(Debug, PartialEq, Clone)]
         pub enum RecordType {
@@ -28,6 +35,14 @@ macro_rules! record_types {
                     Self::Unknown(code) => Cow::Owned(format!("type{code}")),
                 }
             }
+
+            /// The numeric value of this type, as used on the wire
+            pub fn code(&self) -> u16 {
+                match self {
+                    $(Self::$variant => $code),*,
+                    Self::Unknown(code) => *code,
+                }
+            }
         }
 
         impl FromStr for RecordType {
@@ -58,23 +73,48 @@ macro_rules! record_types {
 }
 
 record_types!(
-    A, AAAA, CAA, CNAME, DNSKEY, DS, MX, NS, NSEC, NSEC3, NSEC3PARAM, RRSIG, SOA, TXT
+    A = 1,
+    AAAA = 28,
+    CAA = 257,
+    CNAME = 5,
+    DNSKEY = 48,
+    DS = 43,
+    HTTPS = 65,
+    MX = 15,
+    NS = 2,
+    NSEC = 47,
+    NSEC3 = 50,
+    NSEC3PARAM = 51,
+    PTR = 12,
+    RRSIG = 46,
+    SOA = 6,
+    SRV = 33,
+    SVCB = 64,
+    TLSA = 52,
+    TXT = 16
 );
 
 #[derive(Debug, Clone)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum Record {
     A(A),
+    AAAA(AAAA),
     CAA(CAA),
     CNAME(CNAME),
     DNSKEY(DNSKEY),
     DS(DS),
+    HTTPS(HTTPS),
+    MX(MX),
     NS(NS),
     NSEC(NSEC),
     NSEC3(NSEC3),
     NSEC3PARAM(NSEC3PARAM),
+    PTR(PTR),
     RRSIG(RRSIG),
     SOA(SOA),
+    SRV(SRV),
+    SVCB(SVCB),
+    TLSA(TLSA),
     TXT(TXT),
     Unknown(UnknownRdata),
 }
@@ -97,24 +137,48 @@ impl From<DS> for Record {
     }
 }
 
+impl From<HTTPS> for Record {
+    fn from(v: HTTPS) -> Self {
+        Self::HTTPS(v)
+    }
+}
+
 impl From<A> for Record {
     fn from(v: A) -> Self {
         Self::A(v)
     }
 }
 
+impl From<AAAA> for Record {
+    fn from(v: AAAA) -> Self {
+        Self::AAAA(v)
+    }
+}
+
 impl From<CNAME> for Record {
     fn from(v: CNAME) -> Self {
         Self::CNAME(v)
     }
 }
 
+impl From<MX> for Record {
+    fn from(v: MX) -> Self {
+        Self::MX(v)
+    }
+}
+
 impl From<NS> for Record {
     fn from(v: NS) -> Self {
         Self::NS(v)
     }
 }
 
+impl From<PTR> for Record {
+    fn from(v: PTR) -> Self {
+        Self::PTR(v)
+    }
+}
+
 impl From<RRSIG> for Record {
     fn from(v: RRSIG) -> Self {
         Self::RRSIG(v)
@@ -127,7 +191,77 @@ impl From<SOA> for Record {
     }
 }
 
+impl From<SRV> for Record {
+    fn from(v: SRV) -> Self {
+        Self::SRV(v)
+    }
+}
+
+impl From<SVCB> for Record {
+    fn from(v: SVCB) -> Self {
+        Self::SVCB(v)
+    }
+}
+
+impl From<TLSA> for Record {
+    fn from(v: TLSA) -> Self {
+        Self::TLSA(v)
+    }
+}
+
 impl Record {
+    /// The owner name of this record
+    pub fn owner(&self) -> &FQDN {
+        match self {
+            Record::A(a) => &a.fqdn,
+            Record::AAAA(aaaa) => &aaaa.fqdn,
+            Record::CAA(caa) => &caa.zone,
+            Record::CNAME(cname) => &cname.fqdn,
+            Record::DNSKEY(dnskey) => &dnskey.zone,
+            Record::DS(ds) => &ds.zone,
+            Record::HTTPS(https) => &https.fqdn,
+            Record::MX(mx) => &mx.fqdn,
+            Record::NS(ns) => &ns.zone,
+            Record::NSEC(nsec) => &nsec.fqdn,
+            Record::NSEC3(nsec3) => &nsec3.fqdn,
+            Record::NSEC3PARAM(nsec3param) => &nsec3param.zone,
+            Record::PTR(ptr) => &ptr.fqdn,
+            Record::RRSIG(rrsig) => &rrsig.fqdn,
+            Record::SOA(soa) => &soa.zone,
+            Record::SRV(srv) => &srv.fqdn,
+            Record::SVCB(svcb) => &svcb.fqdn,
+            Record::TLSA(tlsa) => &tlsa.fqdn,
+            Record::TXT(txt) => &txt.zone,
+            Record::Unknown(unknown) => &unknown.zone,
+        }
+    }
+
+    /// The type of this record
+    pub fn record_type(&self) -> RecordType {
+        match self {
+            Record::A(_) => RecordType::A,
+            Record::AAAA(_) => RecordType::AAAA,
+            Record::CAA(_) => RecordType::CAA,
+            Record::CNAME(_) => RecordType::CNAME,
+            Record::DNSKEY(_) => RecordType::DNSKEY,
+            Record::DS(_) => RecordType::DS,
+            Record::HTTPS(_) => RecordType::HTTPS,
+            Record::MX(_) => RecordType::MX,
+            Record::NS(_) => RecordType::NS,
+            Record::NSEC(_) => RecordType::NSEC,
+            Record::NSEC3(_) => RecordType::NSEC3,
+            Record::NSEC3PARAM(_) => RecordType::NSEC3PARAM,
+            Record::PTR(_) => RecordType::PTR,
+            Record::RRSIG(_) => RecordType::RRSIG,
+            Record::SOA(_) => RecordType::SOA,
+            Record::SRV(_) => RecordType::SRV,
+            Record::SVCB(_) => RecordType::SVCB,
+            Record::TLSA(_) => RecordType::TLSA,
+            Record::TXT(_) => RecordType::TXT,
+            Record::Unknown(unknown) => RecordType::Unknown(unknown.r#type),
+        }
+    }
+
     pub fn as_rrsig_mut(&mut self) -> Option<&mut RRSIG> {
         if let Self::RRSIG(rrsig) = self {
             Some(rrsig)
@@ -144,6 +278,14 @@ impl Record {
         }
     }
 
+    pub fn try_into_aaaa(self) -> CoreResult<AAAA, Self> {
+        if let Self::AAAA(v) = self {
+            Ok(v)
+        } else {
+            Err(self)
+        }
+    }
+
     pub fn try_into_cname(self) -> CoreResult<CNAME, Self> {
         if let Self::CNAME(v) = self {
             Ok(v)
@@ -152,6 +294,14 @@ impl Record {
         }
     }
 
+    pub fn try_into_ptr(self) -> CoreResult<PTR, Self> {
+        if let Self::PTR(v) = self {
+            Ok(v)
+        } else {
+            Err(self)
+        }
+    }
+
     pub fn try_into_rrsig(self) -> CoreResult<RRSIG, Self> {
         if let Self::RRSIG(v) = self {
             Ok(v)
@@ -168,6 +318,30 @@ impl Record {
         }
     }
 
+    pub fn try_into_srv(self) -> CoreResult<SRV, Self> {
+        if let Self::SRV(v) = self {
+            Ok(v)
+        } else {
+            Err(self)
+        }
+    }
+
+    pub fn try_into_svcb(self) -> CoreResult<SVCB, Self> {
+        if let Self::SVCB(v) = self {
+            Ok(v)
+        } else {
+            Err(self)
+        }
+    }
+
+    pub fn try_into_tlsa(self) -> CoreResult<TLSA, Self> {
+        if let Self::TLSA(v) = self {
+            Ok(v)
+        } else {
+            Err(self)
+        }
+    }
+
     pub fn try_into_txt(self) -> CoreResult<TXT, Self> {
         if let Self::TXT(txt) = self {
             Ok(txt)
@@ -189,6 +363,15 @@ impl Record {
         .into()
     }
 
+    pub fn aaaa(fqdn: FQDN, ipv6_addr: Ipv6Addr) -> Self {
+        AAAA {
+            fqdn,
+            ttl: DEFAULT_TTL,
+            ipv6_addr,
+        }
+        .into()
+    }
+
     pub fn cname(fqdn: FQDN, target: FQDN) -> Self {
         CNAME {
             fqdn,
@@ -198,6 +381,16 @@ impl Record {
         .into()
     }
 
+    pub fn mx(fqdn: FQDN, preference: u16, exchange: FQDN) -> Self {
+        MX {
+            fqdn,
+            ttl: DEFAULT_TTL,
+            preference,
+            exchange,
+        }
+        .into()
+    }
+
     pub fn ns(zone: FQDN, nameserver: FQDN) -> Self {
         NS {
             zone,
@@ -207,6 +400,28 @@ impl Record {
         .into()
     }
 
+    /// A PTR record from the reverse lookup name of `addr` to `target`
+    pub fn ptr(addr: IpAddr, target: FQDN) -> Self {
+        PTR {
+            fqdn: FQDN::reverse(addr),
+            ttl: DEFAULT_TTL,
+            target,
+        }
+        .into()
+    }
+
+    pub fn srv(fqdn: FQDN, priority: u16, weight: u16, port: u16, target: FQDN) -> Self {
+        SRV {
+            fqdn,
+            ttl: DEFAULT_TTL,
+            priority,
+            weight,
+            port,
+            target,
+        }
+        .into()
+    }
+
     pub fn try_into_ds(self) -> CoreResult<DS, Self> {
         if let Self::DS(v) = self {
             Ok(v)
@@ -215,6 +430,22 @@ impl Record {
         }
     }
 
+    pub fn try_into_https(self) -> CoreResult<HTTPS, Self> {
+        if let Self::HTTPS(v) = self {
+            Ok(v)
+        } else {
+            Err(self)
+        }
+    }
+
+    pub fn try_into_mx(self) -> CoreResult<MX, Self> {
+        if let Self::MX(v) = self {
+            Ok(v)
+        } else {
+            Err(self)
+        }
+    }
+
     pub fn try_into_nsec3(self) -> CoreResult<NSEC3, Self> {
         if let Self::NSEC3(v) = self {
             Ok(v)
@@ -242,16 +473,23 @@ impl FromStr for Record {
 
         let record = match record_type {
             "A" => Record::A(input.parse()?),
+            "AAAA" => Record::AAAA(input.parse()?),
             "CAA" => Record::CAA(input.parse()?),
             "CNAME" => Record::CNAME(input.parse()?),
             "DNSKEY" => Record::DNSKEY(input.parse()?),
             "DS" => Record::DS(input.parse()?),
+            "HTTPS" => Record::HTTPS(input.parse()?),
+            "MX" => Record::MX(input.parse()?),
             "NS" => Record::NS(input.parse()?),
             "NSEC" => Record::NSEC(input.parse()?),
             "NSEC3" => Record::NSEC3(input.parse()?),
             "NSEC3PARAM" => Record::NSEC3PARAM(input.parse()?),
+            "PTR" => Record::PTR(input.parse()?),
             "RRSIG" => Record::RRSIG(input.parse()?),
             "SOA" => Record::SOA(input.parse()?),
+            "SRV" => Record::SRV(input.parse()?),
+            "SVCB" => Record::SVCB(input.parse()?),
+            "TLSA" => Record::TLSA(input.parse()?),
             "TXT" => Record::TXT(input.parse()?),
             _ => {
                 if record_type.starts_with("TYPE") {
@@ -270,16 +508,23 @@ impl fmt::Display for Record {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Record::A(a) => write!(f, "{a}"),
+            Record::AAAA(aaaa) => write!(f, "{aaaa}"),
             Record::CAA(caa) => write!(f, "{caa}"),
             Record::CNAME(cname) => write!(f, "{cname}"),
             Record::DS(ds) => write!(f, "{ds}"),
+            Record::HTTPS(https) => write!(f, "{https}"),
             Record::DNSKEY(dnskey) => write!(f, "{dnskey}"),
+            Record::MX(mx) => write!(f, "{mx}"),
             Record::NS(ns) => write!(f, "{ns}"),
             Record::NSEC(nsec) => write!(f, "{nsec}"),
             Record::NSEC3(nsec3) => write!(f, "{nsec3}"),
             Record::NSEC3PARAM(nsec3param) => write!(f, "{nsec3param}"),
+            Record::PTR(ptr) => write!(f, "{ptr}"),
             Record::RRSIG(rrsig) => write!(f, "{rrsig}"),
             Record::SOA(soa) => write!(f, "{soa}"),
+            Record::SRV(srv) => write!(f, "{srv}"),
+            Record::SVCB(svcb) => write!(f, "{svcb}"),
+            Record::TLSA(tlsa) => write!(f, "{tlsa}"),
             Record::TXT(txt) => write!(f, "{txt}"),
             Record::Unknown(other) => write!(f, "{other}"),
         }
@@ -335,6 +580,56 @@ impl fmt::Display for A {
     }
 }
 
+#[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct AAAA {
+    pub fqdn: FQDN,
+    pub ttl: u32,
+    pub ipv6_addr: Ipv6Addr,
+}
+
+impl FromStr for AAAA {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut columns = input.split_whitespace();
+
+        let [
+            Some(fqdn),
+            Some(ttl),
+            Some(class),
+            Some(record_type),
+            Some(ipv6_addr),
+            None,
+        ] = array::from_fn(|_| columns.next())
+        else {
+            return Err("expected 5 columns".into());
+        };
+
+        check_record_type::<Self>(record_type)?;
+        check_class(class)?;
+
+        Ok(Self {
+            fqdn: fqdn.parse()?,
+            ttl: ttl.parse()?,
+            ipv6_addr: ipv6_addr.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for AAAA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            fqdn,
+            ttl,
+            ipv6_addr,
+        } = self;
+
+        let record_type = unqualified_type_name::<Self>();
+        write!(f, "{fqdn}\t{ttl}\t{CLASS}\t{record_type}\t{ipv6_addr}")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CNAME {
     pub fqdn: FQDN,
@@ -420,6 +715,76 @@ impl DNSKEY {
     pub fn is_zone_signing_key(&self) -> bool {
         !self.is_key_signing_key()
     }
+
+    /// Returns a different key with the same key tag, flags and algorithm as this one
+    ///
+    /// The colliding key is found by brute forcing two 16-bit words in the middle of the public
+    /// key material, so it has the same length as this key but does not correspond to any private
+    /// key: signatures never verify with it. This is meant for testing how validators cope with
+    /// key tag collisions, e.g. KeyTrap (CVE-2023-50387).
+    pub fn colliding_key(&self) -> Result<Self> {
+        use base64::prelude::*;
+
+        let key_tag = self.rdata.calculate_key_tag();
+        let original = self.rdata.decode_public_key();
+        if original.len() < 8 {
+            return Err("public key is too short to collide".into());
+        }
+
+        // RDATA starts with 4 bytes of flags, protocol and algorithm so an even index within the
+        // public key is the start of a 16-bit word of the key tag checksum. The middle is clear of
+        // the RSA exponent and of the bytes that make up the key tag of RSA/MD5 keys
+        let index = (original.len() / 2) & !1;
+        let word = |index: usize| u16::from_be_bytes([original[index], original[index + 1]]);
+        let (first, second) = (word(index), word(index + 2));
+
+        let mut rdata = self.rdata.clone();
+        let mut public_key = original.clone();
+        // moving `delta` from one word to the other keeps the checksum unless either word wraps
+        for delta in 1..=u16::MAX {
+            public_key[index..index + 2].copy_from_slice(&first.wrapping_add(delta).to_be_bytes());
+            public_key[index + 2..index + 4]
+                .copy_from_slice(&second.wrapping_sub(delta).to_be_bytes());
+            rdata.public_key = BASE64_STANDARD.encode(&public_key);
+
+            if rdata.calculate_key_tag() == key_tag {
+                return Ok(Self {
+                    zone: self.zone.clone(),
+                    ttl: self.ttl,
+                    rdata,
+                });
+            }
+        }
+
+        Err(format!("found no key colliding with key tag {key_tag}").into())
+    }
+
+    /// Computes the DS record that refers to this key, using the digest algorithm `digest_type`
+    /// (RFC4034 section 5.1.4)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digest_type` is not one of the `DS::DIGEST_TYPE_*` constants
+    pub fn to_ds(&self, digest_type: u8) -> DS {
+        let mut data = self.zone.canonical_wire_format();
+        data.extend(self.rdata.wire_format());
+
+        let digest = match digest_type {
+            DS::DIGEST_TYPE_SHA1 => hex::encode_upper(Sha1::digest(&data)),
+            DS::DIGEST_TYPE_SHA256 => hex::encode_upper(Sha256::digest(&data)),
+            DS::DIGEST_TYPE_SHA384 => hex::encode_upper(Sha384::digest(&data)),
+            _ => panic!("unsupported DS digest type: {digest_type}"),
+        };
+
+        DS {
+            zone: self.zone.clone(),
+            ttl: self.ttl,
+            key_tag: self.rdata.calculate_key_tag(),
+            algorithm: self.rdata.algorithm,
+            digest_type,
+            digest,
+        }
+    }
 }
 
 impl FromStr for DNSKEY {
@@ -501,23 +866,14 @@ pub struct DNSKEYRData {
 impl DNSKEYRData {
     // as per appendix B of RFC4034
     pub fn calculate_key_tag(&self) -> u16 {
-        use base64::prelude::*;
-
-        let public_key = &BASE64_STANDARD
-            .decode(self.public_key.as_bytes())
-            .expect("base64 decoding failed");
-
         if self.algorithm == 1 {
+            let public_key = self.decode_public_key();
             let len = public_key.len();
             let start = len - 3;
             return u16::from_be_bytes(public_key[start..start + 2].try_into().unwrap());
         }
 
-        let mut rdata = Vec::<u8>::new();
-        rdata.extend_from_slice(self.flags.to_be_bytes().as_slice());
-        rdata.push(3); // protocol
-        rdata.push(self.algorithm);
-        rdata.extend_from_slice(public_key);
+        let rdata = self.wire_format();
 
         let mut acc = 0u32;
         for chunk in rdata.chunks(2) {
@@ -532,6 +888,24 @@ impl DNSKEYRData {
 
         acc as u16
     }
+
+    /// The wire format of this RDATA
+    fn wire_format(&self) -> Vec<u8> {
+        let mut rdata = Vec::<u8>::new();
+        rdata.extend_from_slice(self.flags.to_be_bytes().as_slice());
+        rdata.push(self.protocol);
+        rdata.push(self.algorithm);
+        rdata.extend(self.decode_public_key());
+        rdata
+    }
+
+    fn decode_public_key(&self) -> Vec<u8> {
+        use base64::prelude::*;
+
+        BASE64_STANDARD
+            .decode(self.public_key.as_bytes())
+            .expect("base64 decoding failed")
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -544,6 +918,12 @@ pub struct DS {
     pub digest: String,
 }
 
+impl DS {
+    pub const DIGEST_TYPE_SHA1: u8 = 1;
+    pub const DIGEST_TYPE_SHA256: u8 = 2;
+    pub const DIGEST_TYPE_SHA384: u8 = 4;
+}
+
 impl FromStr for DS {
     type Err = Error;
 
@@ -603,6 +983,109 @@ impl fmt::Display for DS {
     }
 }
 
+/// An HTTPS record, which is an SVCB record for HTTP origins
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub struct HTTPS {
+    pub fqdn: FQDN,
+    pub ttl: u32,
+    /// 0 for alias mode, service mode otherwise
+    pub priority: u16,
+    pub target: FQDN,
+    pub params: Vec<SvcParam>,
+}
+
+impl FromStr for HTTPS {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let (fqdn, ttl, priority, target, params) = parse_service_binding::<Self>(input)?;
+
+        Ok(Self {
+            fqdn,
+            ttl,
+            priority,
+            target,
+            params,
+        })
+    }
+}
+
+impl fmt::Display for HTTPS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            fqdn,
+            ttl,
+            priority,
+            target,
+            params,
+        } = self;
+
+        let record_type = unqualified_type_name::<Self>();
+        write!(
+            f,
+            "{fqdn}\t{ttl}\t{CLASS}\t{record_type}\t{priority} {target}"
+        )?;
+        write_svc_params(f, params)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MX {
+    pub fqdn: FQDN,
+    pub ttl: u32,
+    pub preference: u16,
+    pub exchange: FQDN,
+}
+
+impl FromStr for MX {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut columns = input.split_whitespace();
+
+        let [
+            Some(fqdn),
+            Some(ttl),
+            Some(class),
+            Some(record_type),
+            Some(preference),
+            Some(exchange),
+            None,
+        ] = array::from_fn(|_| columns.next())
+        else {
+            return Err("expected 6 columns".into());
+        };
+
+        check_record_type::<Self>(record_type)?;
+        check_class(class)?;
+
+        Ok(Self {
+            fqdn: fqdn.parse()?,
+            ttl: ttl.parse()?,
+            preference: preference.parse()?,
+            exchange: exchange.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for MX {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            fqdn,
+            ttl,
+            preference,
+            exchange,
+        } = self;
+
+        let record_type = unqualified_type_name::<Self>();
+        write!(
+            f,
+            "{fqdn}\t{ttl}\t{CLASS}\t{record_type}\t{preference} {exchange}"
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NS {
     pub zone: FQDN,
@@ -804,6 +1287,8 @@ pub struct NSEC3PARAM {
     pub hash_alg: u8,
     pub flags: u8,
     pub iterations: u16,
+    /// hex encoded salt; `None` when the zone is not salted (`-`)
+    pub salt: Option<String>,
 }
 
 impl FromStr for NSEC3PARAM {
@@ -820,7 +1305,7 @@ impl FromStr for NSEC3PARAM {
             Some(hash_alg),
             Some(flags),
             Some(iterations),
-            Some(dash),
+            Some(salt),
             None,
         ] = array::from_fn(|_| columns.next())
         else {
@@ -830,9 +1315,13 @@ impl FromStr for NSEC3PARAM {
         check_record_type::<Self>(record_type)?;
         check_class(class)?;
 
-        if dash != "-" {
-            todo!("salt is not implemented")
-        }
+        let salt = if salt == "-" {
+            None
+        } else if salt.len() % 2 == 0 && salt.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            Some(salt.to_string())
+        } else {
+            return Err(format!("invalid NSEC3PARAM salt: {salt}").into());
+        };
 
         Ok(Self {
             zone: zone.parse()?,
@@ -840,6 +1329,7 @@ impl FromStr for NSEC3PARAM {
             hash_alg: hash_alg.parse()?,
             flags: flags.parse()?,
             iterations: iterations.parse()?,
+            salt,
         })
     }
 }
@@ -852,16 +1342,64 @@ impl fmt::Display for NSEC3PARAM {
             hash_alg,
             flags,
             iterations,
+            salt,
         } = self;
 
         let record_type = unqualified_type_name::<Self>();
+        let salt = salt.as_deref().unwrap_or("-");
         write!(
             f,
-            "{zone}\t{ttl}\t{CLASS}\t{record_type}\t{hash_alg} {flags} {iterations} -"
+            "{zone}\t{ttl}\t{CLASS}\t{record_type}\t{hash_alg} {flags} {iterations} {salt}"
         )
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub struct PTR {
+    pub fqdn: FQDN,
+    pub ttl: u32,
+    pub target: FQDN,
+}
+
+impl FromStr for PTR {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut columns = input.split_whitespace();
+
+        let [
+            Some(fqdn),
+            Some(ttl),
+            Some(class),
+            Some(record_type),
+            Some(target),
+            None,
+        ] = array::from_fn(|_| columns.next())
+        else {
+            return Err("expected 5 columns".into());
+        };
+
+        check_record_type::<Self>(record_type)?;
+        check_class(class)?;
+
+        Ok(Self {
+            fqdn: fqdn.parse()?,
+            ttl: ttl.parse()?,
+            target: target.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for PTR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { fqdn, ttl, target } = self;
+
+        let record_type = unqualified_type_name::<Self>();
+        write!(f, "{fqdn}\t{ttl}\t{CLASS}\t{record_type}\t{target}")
+    }
+}
+
 // integer types chosen based on bit sizes in section 3.1 of RFC4034
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
@@ -872,11 +1410,8 @@ pub struct RRSIG {
     pub algorithm: u8,
     pub labels: u8,
     pub original_ttl: u32,
-    // NOTE on the wire these are 32-bit UNIX timestamps but in text representation they are
-    // `strftime` formatted
-    // TODO switch these to `chrono::DateTime<Utc>`?
-    pub signature_expiration: u64,
-    pub signature_inception: u64,
+    pub signature_expiration: Timestamp,
+    pub signature_inception: Timestamp,
     pub key_tag: u16,
     pub signer_name: FQDN,
     /// base64 encoded
@@ -957,6 +1492,181 @@ impl fmt::Display for RRSIG {
     }
 }
 
+impl RRSIG {
+    /// Whether the signature is valid at `time` according to its inception and expiration times
+    ///
+    /// NOTE this compares the full timestamps; it does not apply the serial number arithmetic
+    /// (RFC1982) that validators use on the 32-bit wire values
+    pub fn is_valid_at(&self, time: Timestamp) -> bool {
+        self.signature_inception <= time && time <= self.signature_expiration
+    }
+}
+
+/// An RRSIG signature expiration or inception time (RFC4034 section 3.1.5)
+///
+/// On the wire these are 32-bit UNIX timestamps but in text representation they are formatted as
+/// `YYYYMMDDHHmmSS` in UTC. Both representations are accepted when parsing; the latter is used for
+/// display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    unix: u64,
+}
+
+impl Timestamp {
+    // 9999-12-31 23:59:59, the last timestamp that fits in `YYYYMMDDHHmmSS`
+    const MAX_UNIX: u64 = 253_402_300_799;
+
+    pub fn from_unix(seconds: u64) -> Self {
+        assert!(
+            seconds <= Self::MAX_UNIX,
+            "timestamp {seconds} is after the year 9999"
+        );
+        Self { unix: seconds }
+    }
+
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+
+    /// Seconds since the UNIX epoch
+    pub fn as_unix(&self) -> u64 {
+        self.unix
+    }
+
+    /// The 32-bit value that represents this timestamp on the wire
+    pub fn to_wire(&self) -> u32 {
+        self.unix as u32
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        let since_epoch = time
+            .duration_since(UNIX_EPOCH)
+            .expect("time is before the UNIX epoch");
+        Self::from_unix(since_epoch.as_secs())
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        UNIX_EPOCH + Duration::from_secs(timestamp.unix)
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Self;
+
+    fn add(self, duration: Duration) -> Self {
+        Self::from_unix(self.unix + duration.as_secs())
+    }
+}
+
+impl Sub<Duration> for Timestamp {
+    type Output = Self;
+
+    fn sub(self, duration: Duration) -> Self {
+        let unix = self
+            .unix
+            .checked_sub(duration.as_secs())
+            .expect("timestamp is before the UNIX epoch");
+        Self::from_unix(unix)
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = Error;
+
+    fn from_str(input: &str) -> CoreResult<Self, Self::Err> {
+        // RFC4034 section 3.2: a value that is not 14 digits long is a number of seconds
+        if input.len() != 14 {
+            let unix = input.parse()?;
+            if unix > Self::MAX_UNIX {
+                return Err(format!("timestamp {unix} is after the year 9999").into());
+            }
+            return Ok(Self { unix });
+        }
+
+        if !input.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(format!("invalid timestamp: {input}").into());
+        }
+
+        let field = |range: core::ops::Range<usize>| -> u64 { input[range].parse().unwrap() };
+        let (year, month, day) = (field(0..4), field(4..6), field(6..8));
+        let (hour, minute, second) = (field(8..10), field(10..12), field(12..14));
+
+        if year < 1970
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return Err(format!("invalid timestamp: {input}").into());
+        }
+
+        let days = days_from_civil(year, month, day);
+        Ok(Self {
+            unix: days * 86_400 + hour * 3_600 + minute * 60 + second,
+        })
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.unix / 86_400);
+        let seconds = self.unix % 86_400;
+        let (hour, minute, second) = (seconds / 3_600, seconds / 60 % 60, seconds % 60);
+
+        write!(
+            f,
+            "{year:04}{month:02}{day:02}{hour:02}{minute:02}{second:02}"
+        )
+    }
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        // let the calendar arithmetic below deal with leap years
+        2 => days_from_civil(year, 3, 1) - days_from_civil(year, 2, 1),
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// the following two functions convert between dates of the proleptic Gregorian calendar and days
+// since the UNIX epoch; see http://howardhinnant.github.io/date_algorithms.html
+// they only handle dates from 1970 onwards
+
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    // years start in March so that the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    // 719_468 is the number of days from 0000-03-01 to 1970-01-01
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = (month_from_march + 2) % 12 + 1;
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 pub struct SOA {
@@ -1063,11 +1773,392 @@ impl fmt::Display for SoaSettings {
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub struct SRV {
+    pub fqdn: FQDN,
+    pub ttl: u32,
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: FQDN,
+}
+
+impl FromStr for SRV {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut columns = input.split_whitespace();
+
+        let [
+            Some(fqdn),
+            Some(ttl),
+            Some(class),
+            Some(record_type),
+            Some(priority),
+            Some(weight),
+            Some(port),
+            Some(target),
+            None,
+        ] = array::from_fn(|_| columns.next())
+        else {
+            return Err("expected 8 columns".into());
+        };
+
+        check_record_type::<Self>(record_type)?;
+        check_class(class)?;
+
+        Ok(Self {
+            fqdn: fqdn.parse()?,
+            ttl: ttl.parse()?,
+            priority: priority.parse()?,
+            weight: weight.parse()?,
+            port: port.parse()?,
+            target: target.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for SRV {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            fqdn,
+            ttl,
+            priority,
+            weight,
+            port,
+            target,
+        } = self;
+
+        let record_type = unqualified_type_name::<Self>();
+        write!(
+            f,
+            "{fqdn}\t{ttl}\t{CLASS}\t{record_type}\t{priority} {weight} {port} {target}"
+        )
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub struct SVCB {
+    pub fqdn: FQDN,
+    pub ttl: u32,
+    /// 0 for alias mode, service mode otherwise
+    pub priority: u16,
+    pub target: FQDN,
+    pub params: Vec<SvcParam>,
+}
+
+impl FromStr for SVCB {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let (fqdn, ttl, priority, target, params) = parse_service_binding::<Self>(input)?;
+
+        Ok(Self {
+            fqdn,
+            ttl,
+            priority,
+            target,
+            params,
+        })
+    }
+}
+
+impl fmt::Display for SVCB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            fqdn,
+            ttl,
+            priority,
+            target,
+            params,
+        } = self;
+
+        let record_type = unqualified_type_name::<Self>();
+        write!(
+            f,
+            "{fqdn}\t{ttl}\t{CLASS}\t{record_type}\t{priority} {target}"
+        )?;
+        write_svc_params(f, params)
+    }
+}
+
+/// A SvcParam of an SVCB or HTTPS record, in the presentation format of section 7 of RFC9460
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SvcParam {
+    /// The keys which clients must support to use the record
+    Mandatory(Vec<String>),
+    /// ALPN protocol identifiers, e.g. `h2` and `h3`
+    Alpn(Vec<String>),
+    NoDefaultAlpn,
+    Port(u16),
+    Ipv4Hint(Vec<Ipv4Addr>),
+    /// Base64 encoded ECHConfigList
+    Ech(String),
+    Ipv6Hint(Vec<Ipv6Addr>),
+    /// A key without a name, in the `keyNNNNN` format
+    Unknown {
+        key: u16,
+        value: Option<String>,
+    },
+}
+
+impl FromStr for SvcParam {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let (key, value) = match input.split_once('=') {
+            Some((key, value)) => {
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value);
+                (key, Some(value))
+            }
+            None => (input, None),
+        };
+
+        let list = || -> Result<Vec<&str>> {
+            match value {
+                Some(value) if !value.is_empty() => Ok(value.split(',').collect()),
+                _ => Err(format!("SvcParam `{key}` requires a value").into()),
+            }
+        };
+
+        Ok(match key {
+            "mandatory" => Self::Mandatory(list()?.into_iter().map(String::from).collect()),
+            "alpn" => Self::Alpn(list()?.into_iter().map(String::from).collect()),
+            "no-default-alpn" => {
+                if value.is_some() {
+                    return Err("SvcParam `no-default-alpn` takes no value".into());
+                }
+                Self::NoDefaultAlpn
+            }
+            "port" => Self::Port(value.ok_or("SvcParam `port` requires a value")?.parse()?),
+            "ipv4hint" => Self::Ipv4Hint(
+                list()?
+                    .into_iter()
+                    .map(str::parse)
+                    .collect::<CoreResult<_, _>>()?,
+            ),
+            "ech" => Self::Ech(value.ok_or("SvcParam `ech` requires a value")?.to_string()),
+            "ipv6hint" => Self::Ipv6Hint(
+                list()?
+                    .into_iter()
+                    .map(str::parse)
+                    .collect::<CoreResult<_, _>>()?,
+            ),
+            _ => {
+                let Some(key) = key.strip_prefix("key").and_then(|key| key.parse().ok()) else {
+                    return Err(format!("unknown SvcParam key: {key}").into());
+                };
+                Self::Unknown {
+                    key,
+                    value: value.map(String::from),
+                }
+            }
+        })
+    }
+}
+
+impl fmt::Display for SvcParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn join(items: impl IntoIterator<Item = impl ToString>) -> String {
+            items
+                .into_iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        }
+
+        match self {
+            Self::Mandatory(keys) => write!(f, "mandatory={}", keys.join(",")),
+            Self::Alpn(ids) => write!(f, "alpn=\"{}\"", ids.join(",")),
+            Self::NoDefaultAlpn => f.write_str("no-default-alpn"),
+            Self::Port(port) => write!(f, "port={port}"),
+            Self::Ipv4Hint(addrs) => write!(f, "ipv4hint={}", join(addrs)),
+            Self::Ech(config) => write!(f, "ech={config}"),
+            Self::Ipv6Hint(addrs) => write!(f, "ipv6hint={}", join(addrs)),
+            Self::Unknown { key, value: None } => write!(f, "key{key}"),
+            Self::Unknown {
+                key,
+                value: Some(value),
+            } => write!(f, "key{key}=\"{value}\""),
+        }
+    }
+}
+
+/// Parses the columns shared by the SVCB and HTTPS records
+fn parse_service_binding<T>(input: &str) -> Result<(FQDN, u32, u16, FQDN, Vec<SvcParam>)> {
+    let mut columns = input.split_whitespace();
+
+    let [
+        Some(fqdn),
+        Some(ttl),
+        Some(class),
+        Some(record_type),
+        Some(priority),
+        Some(target),
+    ] = array::from_fn(|_| columns.next())
+    else {
+        return Err("expected at least 6 columns".into());
+    };
+
+    check_record_type::<T>(record_type)?;
+    check_class(class)?;
+
+    let params = columns.map(str::parse).collect::<Result<Vec<_>>>()?;
+
+    Ok((
+        fqdn.parse()?,
+        ttl.parse()?,
+        priority.parse()?,
+        target.parse()?,
+        params,
+    ))
+}
+
+fn write_svc_params(f: &mut fmt::Formatter<'_>, params: &[SvcParam]) -> fmt::Result {
+    for param in params {
+        write!(f, " {param}")?;
+    }
+    Ok(())
+}
+
+// integer types chosen based on bit sizes in section 2.1 of RFC6698
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub struct TLSA {
+    pub fqdn: FQDN,
+    pub ttl: u32,
+    pub usage: u8,
+    pub selector: u8,
+    pub matching_type: u8,
+    /// Hex encoded certificate association data
+    pub association_data: String,
+}
+
+impl TLSA {
+    /// Selects the full certificate
+    pub const SELECTOR_FULL_CERTIFICATE: u8 = 0;
+    /// Selects the SubjectPublicKeyInfo of the certificate
+    pub const SELECTOR_SUBJECT_PUBLIC_KEY_INFO: u8 = 1;
+
+    /// The selected content is used as is
+    pub const MATCHING_TYPE_FULL: u8 = 0;
+    pub const MATCHING_TYPE_SHA256: u8 = 1;
+    pub const MATCHING_TYPE_SHA512: u8 = 2;
+
+    /// Computes the association data of the DER encoded `certificate`, as selected by `selector`
+    /// and hashed according to `matching_type`
+    pub fn from_certificate(
+        fqdn: FQDN,
+        usage: u8,
+        selector: u8,
+        matching_type: u8,
+        certificate: &[u8],
+    ) -> Result<Self> {
+        let selected = match selector {
+            Self::SELECTOR_FULL_CERTIFICATE => Cow::Borrowed(certificate),
+            Self::SELECTOR_SUBJECT_PUBLIC_KEY_INFO => {
+                Cow::Owned(subject_public_key_info(certificate)?)
+            }
+            _ => return Err(format!("unknown TLSA selector: {selector}").into()),
+        };
+
+        let association_data = match matching_type {
+            Self::MATCHING_TYPE_FULL => hex::encode_upper(&selected),
+            Self::MATCHING_TYPE_SHA256 => hex::encode_upper(Sha256::digest(&selected)),
+            Self::MATCHING_TYPE_SHA512 => hex::encode_upper(Sha512::digest(&selected)),
+            _ => return Err(format!("unknown TLSA matching type: {matching_type}").into()),
+        };
+
+        Ok(Self {
+            fqdn,
+            ttl: DEFAULT_TTL,
+            usage,
+            selector,
+            matching_type,
+            association_data,
+        })
+    }
+}
+
+impl FromStr for TLSA {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut columns = input.split_whitespace();
+
+        let [
+            Some(fqdn),
+            Some(ttl),
+            Some(class),
+            Some(record_type),
+            Some(usage),
+            Some(selector),
+            Some(matching_type),
+        ] = array::from_fn(|_| columns.next())
+        else {
+            return Err("expected at least 7 columns".into());
+        };
+
+        check_record_type::<Self>(record_type)?;
+        check_class(class)?;
+
+        let mut association_data = String::new();
+        for column in columns {
+            association_data.push_str(column);
+        }
+
+        Ok(Self {
+            fqdn: fqdn.parse()?,
+            ttl: ttl.parse()?,
+            usage: usage.parse()?,
+            selector: selector.parse()?,
+            matching_type: matching_type.parse()?,
+            association_data,
+        })
+    }
+}
+
+impl fmt::Display for TLSA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            fqdn,
+            ttl,
+            usage,
+            selector,
+            matching_type,
+            association_data,
+        } = self;
+
+        let record_type = unqualified_type_name::<Self>();
+        write!(
+            f,
+            "{fqdn}\t{ttl}\t{CLASS}\t{record_type}\t{usage} {selector} {matching_type}"
+        )?;
+
+        write_split_long_string(f, association_data)
+    }
+}
+
+/// Returns the DER encoded SubjectPublicKeyInfo of a DER encoded X.509 certificate
+fn subject_public_key_info(certificate: &[u8]) -> Result<Vec<u8>> {
+    let certificate = Certificate::from_der(certificate)?;
+    Ok(certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()?)
+}
+
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 pub struct TXT {
     pub zone: FQDN,
     pub ttl: u32,
+    /// unescaped strings where each `char` stands for one octet
     pub character_strings: Vec<String>,
 }
 
@@ -1109,7 +2200,8 @@ impl FromStr for TXT {
         }
 
         let mut state = State::Whitespace;
-        for character in rest.chars() {
+        let mut characters = rest.chars();
+        while let Some(character) = characters.next() {
             if !character.is_ascii() {
                 return Err("non-ASCII characters in TXT records are not supported".into());
             }
@@ -1136,8 +2228,13 @@ impl FromStr for TXT {
                         "denoting the current origin with @ in TXT records is not supported".into(),
                     );
                 }
-                (_, '\\') => {
-                    return Err("backslash escapes in TXT records are not supported".into());
+                (State::QuotedString, '\\') => {
+                    current_string.push(parse_escape(&mut characters)?);
+                    state = State::QuotedString;
+                }
+                (State::Whitespace | State::UnquotedString, '\\') => {
+                    current_string.push(parse_escape(&mut characters)?);
+                    state = State::UnquotedString;
                 }
                 (State::Whitespace | State::UnquotedString, character) => {
                     current_string.push(character);
@@ -1180,16 +2277,53 @@ impl fmt::Display for TXT {
         let mut is_first = true;
         for string in character_strings.iter() {
             if is_first {
-                write!(f, "\t\"{string}\"")?;
+                f.write_str("\t\"")?;
                 is_first = false;
             } else {
-                write!(f, " \"{string}\"")?;
+                f.write_str(" \"")?;
             }
+
+            for character in string.chars() {
+                match character {
+                    '"' | '\\' => write!(f, "\\{character}")?,
+                    ' '..='~' => write!(f, "{character}")?,
+                    _ => write!(f, "\\{:03}", u32::from(character))?,
+                }
+            }
+
+            f.write_str("\"")?;
         }
         Ok(())
     }
 }
 
+/// Parses the part of a character-string escape sequence that follows the backslash: either a
+/// `DDD` decimal octet or a single character that is taken literally (section 5.1 of RFC1035)
+///
+/// Octets are returned as the `char` with the same value so every `char` of a TXT character
+/// string stands for one octet
+fn parse_escape(characters: &mut core::str::Chars<'_>) -> Result<char> {
+    let Some(first) = characters.next() else {
+        return Err("TXT record ends with an incomplete escape sequence".into());
+    };
+
+    if !first.is_ascii_digit() {
+        return Ok(first);
+    }
+
+    let digits = characters.as_str().get(..2).unwrap_or_default();
+    if digits.len() != 2 || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(format!("invalid decimal escape in TXT record: \\{first}{digits}").into());
+    }
+
+    let octet = format!("{first}{digits}")
+        .parse::<u8>()
+        .map_err(|_| format!("decimal escape in TXT record is out of range: \\{first}{digits}"))?;
+    characters.nth(1);
+
+    Ok(char::from(octet))
+}
+
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 pub struct CAA {
@@ -1381,6 +2515,9 @@ mod tests {
     // dig A a.root-servers.net
     const A_INPUT: &str = "a.root-servers.net.	77859	IN	A	198.41.0.4";
 
+    // dig AAAA a.root-servers.net
+    const AAAA_INPUT: &str = "a.root-servers.net.	77859	IN	AAAA	2001:503:ba3e::2:30";
+
     #[test]
     fn a() -> Result<()> {
         let a @ A {
@@ -1399,6 +2536,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn aaaa() -> Result<()> {
+        let aaaa @ AAAA {
+            fqdn,
+            ttl,
+            ipv6_addr,
+        } = &AAAA_INPUT.parse()?;
+
+        assert_eq!("a.root-servers.net.", fqdn.as_str());
+        assert_eq!(77859, *ttl);
+        assert_eq!(
+            Ipv6Addr::new(0x2001, 0x503, 0xba3e, 0, 0, 0, 2, 0x30),
+            *ipv6_addr
+        );
+
+        let output = aaaa.to_string();
+        assert_eq!(AAAA_INPUT, output);
+
+        Ok(())
+    }
+
     // dig CNAME www.isc.org
     const CNAME_INPUT: &str = "www.isc.org.	277	IN	CNAME	isc.map.fastlydns.net.";
 
@@ -1537,6 +2695,112 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn colliding_key() -> Result<()> {
+        let dsa_key = "dsa.extended-dns-errors.com. 600 IN DNSKEY 257 3 3 \
+                       CPn4bkeyFLewxmOnFPoNLE1dTSHh/sDgPPPtKvXgtp9N \
+                       3r8Z4TEOKzrtmTf/+7o09GFQPApzqabgU2Wx3mxbgawJ \
+                       jbu1JEp1nkYuu0tdHcndLKnPjJpkQx5kOZveQ8ggxUiX \
+                       iq9Q8plA3n/k8UZXmnf1BC3kb9FEDH7v9SIrn7tyE1Z+ \
+                       H5MXxw5Z+Qn4n34v3Z/L7UdQ9+aY70l5Maip0fhQksGZ \
+                       CbPqZeuKGvUIA4HR70HGvrrXZ/Bti/h1i92JCZRxnKrq \
+                       rBKN7tz6L2iWRrAuOnznbuc+yKbBQC+hw0a9SqB3cxFz \
+                       CjXekaZ5XX99c+QZCXQdqTa44bE2WsLbSgv4JfCeLzLS \
+                       5ChYMHvqnCoe33wC6yvB967+9OxPoH5Ctndtc8SOW2bE \
+                       dI4njhw/27k9Z5zN6B/QgIFEPwY3nHkduNif3ugOHmEY \
+                       IefHyDDZq8NcZfFVM7Hu27utxxZhv2NkGsFLqKMuUi5i \
+                       AX7BO7BglvRnPRq31BQ0vpiw8vJzAbx6p+jdKXvpa1tG \
+                       zG4m3iwDQacz";
+        let rsamd5_key = "rsamd5.extended-dns-errors.com. 268 IN DNSKEY 257 3 1 \
+                          AwEAAcpRn4ct2tt2a6RRqOYEDMtK8zETcLvpSoHhthWF \
+                          8WBvko0XodJJYlstLN6JMb5NwRAgcfddH3sR/ELdw2Hk \
+                          Hrp/jRRBW4wAHiVZU1uDRml0pD9ZEWQ3It+eDp/lG+Cp \
+                          Q3e5BGibTPCoWtOvx5uZQDkLlQBAXu2vTn1w2VXCMuwP";
+
+        for input in [DNSKEY_INPUT, dsa_key, rsamd5_key] {
+            let dnskey: DNSKEY = input.parse()?;
+            let colliding = dnskey.colliding_key()?;
+
+            assert_eq!(
+                dnskey.rdata.calculate_key_tag(),
+                colliding.rdata.calculate_key_tag()
+            );
+            assert_ne!(dnskey.rdata.public_key, colliding.rdata.public_key);
+            assert_eq!(
+                dnskey.rdata.public_key.len(),
+                colliding.rdata.public_key.len()
+            );
+            assert_eq!(dnskey.rdata.flags, colliding.rdata.flags);
+            assert_eq!(dnskey.rdata.algorithm, colliding.rdata.algorithm);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn dnskey_to_ds() -> Result<()> {
+        // RFC4034 section 5.4
+        let dnskey: DNSKEY = "dskey.example.com. 86400 IN DNSKEY 256 3 5 \
+                              AQOeiiR0GOMYkDshWoSKz9XzfwJr1AYtsmx3TGkJaNXVbfi/ \
+                              2pHm822aJ5iI9BMzNXxeYCmZDRD99WYwYqUSdjMmmAphXdvx \
+                              egXd/M5+X7OrzKBaMbCVdFLUUh6DhweJBjEVv5f2wwjM9Xzc \
+                              nOf+EPbtG9DMBmADjFDc2w/rljwvFw=="
+            .parse()?;
+
+        let ds = dnskey.to_ds(DS::DIGEST_TYPE_SHA1);
+        assert_eq!(
+            "dskey.example.com.\t86400\tIN\tDS\t60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118",
+            ds.to_string()
+        );
+
+        // RFC4509 section 2.3
+        let ds = dnskey.to_ds(DS::DIGEST_TYPE_SHA256);
+        assert_eq!(
+            "D4B7D520E7BB5F0F67674A0CCEB1E3E0614B93C4F9E99B8383F6A1E4469DA50A",
+            ds.digest
+        );
+
+        // RFC6605 section 6.2
+        let dnskey: DNSKEY = "example.net. 3600 IN DNSKEY 257 3 14 \
+                              xKYaNhWdGOfJ+nPrL8/arkwf2EY3MDJ+SErKivBVSum1w/egsXvSADtNJhyem5RC \
+                              OpgQ6K8X1DRSEkrbYQ+OB+v8/uX45NBwY8rp65F6Glur8I/mlVNgF6W/qTI37m40"
+            .parse()?;
+
+        let ds = dnskey.to_ds(DS::DIGEST_TYPE_SHA384);
+        assert_eq!(10771, ds.key_tag);
+        assert_eq!(14, ds.algorithm);
+        assert_eq!(
+            "72D7B62976CE06438E9C0BF319013CF801F09ECC84B8D7E9495F27E305C6A9B0\
+             563A9B5F4D288405C3008A946DF983D6",
+            ds.digest
+        );
+
+        Ok(())
+    }
+
+    // dig MX isc.org
+    const MX_INPUT: &str = "isc.org.	7200	IN	MX	10 mx.pao1.isc.org.";
+
+    #[test]
+    fn mx() -> Result<()> {
+        let mx @ MX {
+            fqdn,
+            ttl,
+            preference,
+            exchange,
+        } = &MX_INPUT.parse()?;
+
+        assert_eq!("isc.org.", fqdn.as_str());
+        assert_eq!(7200, *ttl);
+        assert_eq!(10, *preference);
+        assert_eq!("mx.pao1.isc.org.", exchange.as_str());
+
+        let output = mx.to_string();
+        assert_eq!(MX_INPUT, output);
+
+        Ok(())
+    }
+
     // dig NS .
     const NS_INPUT: &str = ".	86400	IN	NS	f.root-servers.net.";
 
@@ -1648,6 +2912,7 @@ mod tests {
             hash_alg,
             flags,
             iterations,
+            salt,
         } = &NSEC3PARAM_INPUT.parse()?;
 
         assert_eq!(FQDN("com.")?, *zone);
@@ -1655,6 +2920,7 @@ mod tests {
         assert_eq!(1, *hash_alg);
         assert_eq!(0, *flags);
         assert_eq!(0, *iterations);
+        assert_eq!(None, *salt);
 
         let output = nsec3param.to_string();
         assert_eq!(NSEC3PARAM_INPUT, output);
@@ -1662,6 +2928,58 @@ mod tests {
         Ok(())
     }
 
+    // as produced by `dnssec-signzone -3 53BCBC5805D2B761 -H 5`
+    const SALTED_NSEC3PARAM_INPUT: &str =
+        "hickory-dns.testing.	0	IN	NSEC3PARAM	1 0 5 53BCBC5805D2B761";
+
+    #[test]
+    fn salted_nsec3param() -> Result<()> {
+        let nsec3param: NSEC3PARAM = SALTED_NSEC3PARAM_INPUT.parse()?;
+
+        assert_eq!(5, nsec3param.iterations);
+        assert_eq!(Some("53BCBC5805D2B761"), nsec3param.salt.as_deref());
+
+        let output = nsec3param.to_string();
+        assert_eq!(SALTED_NSEC3PARAM_INPUT, output);
+
+        assert!(
+            "hickory-dns.testing.	0	IN	NSEC3PARAM	1 0 5 53BCBC5805D2B76"
+                .parse::<NSEC3PARAM>()
+                .is_err()
+        );
+        assert!(
+            "hickory-dns.testing.	0	IN	NSEC3PARAM	1 0 5 XYZ0"
+                .parse::<NSEC3PARAM>()
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    // dig -x 192.0.32.10
+    const PTR_INPUT: &str = "10.32.0.192.in-addr.arpa.	21600	IN	PTR	www.example.com.";
+
+    #[test]
+    fn ptr() -> Result<()> {
+        let ptr @ PTR { fqdn, ttl, target } = &PTR_INPUT.parse()?;
+
+        assert_eq!("10.32.0.192.in-addr.arpa.", fqdn.as_str());
+        assert_eq!(21600, *ttl);
+        assert_eq!("www.example.com.", target.as_str());
+
+        let output = ptr.to_string();
+        assert_eq!(PTR_INPUT, output);
+
+        let record = Record::ptr(
+            Ipv4Addr::new(192, 0, 32, 10).into(),
+            FQDN("www.example.com.")?,
+        );
+        let ptr = record.try_into_ptr().unwrap();
+        assert_eq!(fqdn, &ptr.fqdn);
+
+        Ok(())
+    }
+
     // dig +dnssec SOA .
     const RRSIG_INPUT: &str = ".	1800	IN	RRSIG	SOA 7 0 1800 20240306132701 20240207132701 11264 . wXpRU4elJPGYm2kgVVsIwGf1IkYJcQ3UE4mwmItWdxj0XWSWY07MO4Ll DMJgsE0u64Q/345Ck7+aQ904uLebwCvpFnsmkyCxk82XIAfHN9FiwzSy qoR/zZEvBONaej3vrvsqPwh8q/pvypLft9647HcFdwY0juzZsbrAaDAX 8WY=";
 
@@ -1687,8 +3005,12 @@ mod tests {
         assert_eq!(7, *algorithm);
         assert_eq!(0, *labels);
         assert_eq!(1800, *original_ttl);
-        assert_eq!(20240306132701, *signature_expiration);
-        assert_eq!(20240207132701, *signature_inception);
+        assert_eq!("20240306132701", signature_expiration.to_string());
+        assert_eq!("20240207132701", signature_inception.to_string());
+        assert_eq!(1709731621, signature_expiration.as_unix());
+        assert_eq!(1707312421, signature_inception.as_unix());
+        assert!(rrsig.is_valid_at(Timestamp::from_unix(1707312421)));
+        assert!(!rrsig.is_valid_at(Timestamp::from_unix(1709731622)));
         assert_eq!(11264, *key_tag);
         assert_eq!(FQDN::ROOT, *signer_name);
         let expected = "wXpRU4elJPGYm2kgVVsIwGf1IkYJcQ3UE4mwmItWdxj0XWSWY07MO4LlDMJgsE0u64Q/345Ck7+aQ904uLebwCvpFnsmkyCxk82XIAfHN9FiwzSyqoR/zZEvBONaej3vrvsqPwh8q/pvypLft9647HcFdwY0juzZsbrAaDAX8WY=";
@@ -1700,6 +3022,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn timestamp() -> Result<()> {
+        for (text, unix) in [
+            ("19700101000000", 0),
+            ("20000229120000", 951825600),
+            ("20380119031408", 1 << 31),
+            ("21060207062816", 1 << 32),
+            ("99991231235959", 253402300799),
+        ] {
+            let timestamp = text.parse::<Timestamp>()?;
+            assert_eq!(unix, timestamp.as_unix());
+            assert_eq!(text, Timestamp::from_unix(unix).to_string());
+        }
+
+        // RFC4034 section 3.2: values that are not 14 digits long are seconds
+        assert_eq!(Timestamp::from_unix(1 << 31), "2147483648".parse()?);
+
+        assert_eq!(0, Timestamp::from_unix(1 << 32).to_wire());
+
+        let timestamp = Timestamp::from_unix(1_000);
+        assert_eq!(1_060, (timestamp + Duration::from_secs(60)).as_unix());
+        assert_eq!(940, (timestamp - Duration::from_secs(60)).as_unix());
+        assert_eq!(
+            timestamp,
+            Timestamp::from(SystemTime::from(timestamp)),
+            "SystemTime roundtrip"
+        );
+
+        for invalid in [
+            "20230229000000",
+            "20241301000000",
+            "20240101240000",
+            "19691231235959",
+            "2024010100000x",
+        ] {
+            assert!(invalid.parse::<Timestamp>().is_err(), "{invalid}");
+        }
+
+        Ok(())
+    }
+
     // dig SOA .
     const SOA_INPUT: &str = ".	15633	IN	SOA	a.root-servers.net. nstld.verisign-grs.com. 2024020501 1800 900 604800 86400";
 
@@ -1724,9 +3087,241 @@ mod tests {
         Ok(())
     }
 
+    // dig SRV _sip._udp.sip.voice.google.com
+    const SRV_INPUT: &str =
+        "_sip._udp.sip.voice.google.com.	300	IN	SRV	10 1 5060 sip-anycast-1.voice.google.com.";
+
+    #[test]
+    fn srv() -> Result<()> {
+        let srv @ SRV {
+            fqdn,
+            ttl,
+            priority,
+            weight,
+            port,
+            target,
+        } = &SRV_INPUT.parse()?;
+
+        assert_eq!("_sip._udp.sip.voice.google.com.", fqdn.as_str());
+        assert_eq!(300, *ttl);
+        assert_eq!(10, *priority);
+        assert_eq!(1, *weight);
+        assert_eq!(5060, *port);
+        assert_eq!("sip-anycast-1.voice.google.com.", target.as_str());
+
+        let output = srv.to_string();
+        assert_eq!(SRV_INPUT, output);
+
+        Ok(())
+    }
+
+    // dig HTTPS cloudflare.com
+    const HTTPS_INPUT: &str = r#"cloudflare.com.	300	IN	HTTPS	1 . alpn="h3,h2" ipv4hint=104.16.132.229,104.16.133.229 ipv6hint=2606:4700::6810:84e5,2606:4700::6810:85e5"#;
+
+    #[test]
+    fn https() -> Result<()> {
+        let https @ HTTPS {
+            fqdn,
+            ttl,
+            priority,
+            target,
+            params,
+        } = &HTTPS_INPUT.parse()?;
+
+        assert_eq!("cloudflare.com.", fqdn.as_str());
+        assert_eq!(300, *ttl);
+        assert_eq!(1, *priority);
+        assert_eq!(FQDN::ROOT, *target);
+        assert_eq!(
+            [
+                SvcParam::Alpn(vec!["h3".to_string(), "h2".to_string()]),
+                SvcParam::Ipv4Hint(vec![
+                    Ipv4Addr::new(104, 16, 132, 229),
+                    Ipv4Addr::new(104, 16, 133, 229)
+                ]),
+                SvcParam::Ipv6Hint(vec![
+                    "2606:4700::6810:84e5".parse()?,
+                    "2606:4700::6810:85e5".parse()?
+                ]),
+            ],
+            params.as_slice()
+        );
+
+        let output = https.to_string();
+        assert_eq!(HTTPS_INPUT, output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn https_alias_mode() -> Result<()> {
+        let input = "hickory-dns.testing.	3600	IN	HTTPS	0 cdn.hickory-dns.testing.";
+        let https: HTTPS = input.parse()?;
+
+        assert_eq!(0, https.priority);
+        assert_eq!("cdn.hickory-dns.testing.", https.target.as_str());
+        assert!(https.params.is_empty());
+        assert_eq!(input, https.to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn https_ech() -> Result<()> {
+        let input = "hickory-dns.testing.	3600	IN	HTTPS	1 . alpn=\"h2\" port=8443 ech=AEX+DQBBpQAgACB/ECHXaRxQbpXlaqTNkZWkGNr7VVAf9BuVY6ptbqqgCAAEAAEAAQASY2xvdWRmbGFyZS1lY2guY29tAAA= no-default-alpn";
+        let https: HTTPS = input.parse()?;
+
+        assert_eq!(
+            [
+                SvcParam::Alpn(vec!["h2".to_string()]),
+                SvcParam::Port(8443),
+                SvcParam::Ech("AEX+DQBBpQAgACB/ECHXaRxQbpXlaqTNkZWkGNr7VVAf9BuVY6ptbqqgCAAEAAEAAQASY2xvdWRmbGFyZS1lY2guY29tAAA=".to_string()),
+                SvcParam::NoDefaultAlpn,
+            ],
+            https.params.as_slice()
+        );
+        assert_eq!(input, https.to_string());
+
+        Ok(())
+    }
+
+    const SVCB_INPUT: &str = r#"_dns.resolver.arpa.	300	IN	SVCB	1 dns.google. mandatory=alpn,port alpn="dot" port=853 key65000="custom""#;
+
+    #[test]
+    fn svcb() -> Result<()> {
+        let svcb @ SVCB {
+            fqdn,
+            ttl,
+            priority,
+            target,
+            params,
+        } = &SVCB_INPUT.parse()?;
+
+        assert_eq!("_dns.resolver.arpa.", fqdn.as_str());
+        assert_eq!(300, *ttl);
+        assert_eq!(1, *priority);
+        assert_eq!("dns.google.", target.as_str());
+        assert_eq!(
+            [
+                SvcParam::Mandatory(vec!["alpn".to_string(), "port".to_string()]),
+                SvcParam::Alpn(vec!["dot".to_string()]),
+                SvcParam::Port(853),
+                SvcParam::Unknown {
+                    key: 65000,
+                    value: Some("custom".to_string())
+                },
+            ],
+            params.as_slice()
+        );
+
+        let output = svcb.to_string();
+        assert_eq!(SVCB_INPUT, output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn svc_param_errors() {
+        assert!("port".parse::<SvcParam>().is_err());
+        assert!("alpn=".parse::<SvcParam>().is_err());
+        assert!("ipv4hint=not-an-ip".parse::<SvcParam>().is_err());
+        assert!("no-default-alpn=1".parse::<SvcParam>().is_err());
+        assert!("bogus=1".parse::<SvcParam>().is_err());
+    }
+
     // from the `truncated_with_tcp_fallback.py` test server.
     const TXT_INPUT: &str = r#"example.testing.	0	IN	TXT	"protocol=TCP" "counter=0""#;
 
+    const TLSA_INPUT: &str = "_443._tcp.hickory-dns.testing.	3600	IN	TLSA	3 1 1 7828540E08079D3419F4FE2E6940039B205613CE85615224255C700C 5992E50A";
+
+    // self-signed P-256 certificate for `hickory-dns.testing`, base64 encoded DER
+    const CERTIFICATE: &str = concat!(
+        "MIIBkzCCATmgAwIBAgIUfESn9zjglmVMnq5HSuNh6PX3SmQwCgYIKoZIzj0EAwIwHjEcMBoGA1UE",
+        "AwwTaGlja29yeS1kbnMudGVzdGluZzAgFw0yNjEwMTcwNTA1MThaGA8yMTI2MDkyMzA1MDUxOFow",
+        "HjEcMBoGA1UEAwwTaGlja29yeS1kbnMudGVzdGluZzBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IA",
+        "BJS5cgAJQOgAJKApA0Q4OjsFfFHe3qSbvbcso5QOGBNsWR0MbqALbH5iY9CvQfzwUavi1tPCAMuh",
+        "UcHjiwhVz+SjUzBRMB0GA1UdDgQWBBRnjF7508bqVw2wc7ToSYK1swt2ZTAfBgNVHSMEGDAWgBRn",
+        "jF7508bqVw2wc7ToSYK1swt2ZTAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIDdS",
+        "fbfcx8uU12qS+B0Kfd9mW/2lVlUzXnZXfEj6bHRKAiEA6jZn0s+JoR7NHuV7jSDNAZoBcuekCE0K",
+        "1D62TCuqYfw=",
+    );
+
+    #[test]
+    fn tlsa() -> Result<()> {
+        let tlsa @ TLSA {
+            fqdn,
+            ttl,
+            usage,
+            selector,
+            matching_type,
+            association_data,
+        } = &TLSA_INPUT.parse()?;
+
+        assert_eq!("_443._tcp.hickory-dns.testing.", fqdn.as_str());
+        assert_eq!(3600, *ttl);
+        assert_eq!(3, *usage);
+        assert_eq!(1, *selector);
+        assert_eq!(1, *matching_type);
+        assert_eq!(
+            "7828540E08079D3419F4FE2E6940039B205613CE85615224255C700C5992E50A",
+            association_data
+        );
+
+        let output = tlsa.to_string();
+        assert_eq!(TLSA_INPUT, output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tlsa_from_certificate() -> Result<()> {
+        use base64::prelude::*;
+
+        let certificate = BASE64_STANDARD.decode(CERTIFICATE)?;
+        let tlsa = |selector, matching_type| {
+            TLSA::from_certificate(
+                FQDN("_443._tcp.hickory-dns.testing.")?,
+                3,
+                selector,
+                matching_type,
+                &certificate,
+            )
+        };
+
+        // expected values computed with `openssl x509 -pubkey | openssl pkey -pubin -outform der`
+        // and `openssl dgst`
+        assert_eq!(
+            "7828540E08079D3419F4FE2E6940039B205613CE85615224255C700C5992E50A",
+            tlsa(
+                TLSA::SELECTOR_SUBJECT_PUBLIC_KEY_INFO,
+                TLSA::MATCHING_TYPE_SHA256
+            )?
+            .association_data
+        );
+        assert_eq!(
+            "1E865114812C1248FD7D93C2D8BA4A7CC1D1FA798AD88F8133FAAED50B85C97BB9279CE488BBD96B38F9A4C23A1B9B92378BE429EC1E47F3C31CDF604F71A05C",
+            tlsa(
+                TLSA::SELECTOR_SUBJECT_PUBLIC_KEY_INFO,
+                TLSA::MATCHING_TYPE_SHA512
+            )?
+            .association_data
+        );
+        assert_eq!(
+            "488BC0611E9F9F316D1581D3DC17CA4CB76F7FA84CEFC368DA861E9596007EEA",
+            tlsa(TLSA::SELECTOR_FULL_CERTIFICATE, TLSA::MATCHING_TYPE_SHA256)?.association_data
+        );
+        assert_eq!(
+            hex::encode_upper(&certificate),
+            tlsa(TLSA::SELECTOR_FULL_CERTIFICATE, TLSA::MATCHING_TYPE_FULL)?.association_data
+        );
+
+        assert!(tlsa(2, TLSA::MATCHING_TYPE_SHA256).is_err());
+        assert!(tlsa(TLSA::SELECTOR_FULL_CERTIFICATE, 3).is_err());
+        assert!(subject_public_key_info(&certificate[..100]).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn txt() -> Result<()> {
         let txt: TXT = TXT_INPUT.parse()?;
@@ -1744,6 +3339,58 @@ mod tests {
         Ok(())
     }
 
+    // a DKIM style record, with escapes added to exercise all the escape forms
+    const ESCAPED_TXT_INPUT: &str = r#"google._domainkey.hickory-dns.testing.	300	IN	TXT	"v=DKIM1\; k=rsa\; p=MIGfMA0" "say \"hi\"" "back\\slash" "tab\009bell\007" "high\255""#;
+
+    #[test]
+    fn txt_escapes() -> Result<()> {
+        let txt: TXT = ESCAPED_TXT_INPUT.parse()?;
+
+        assert_eq!(
+            vec![
+                "v=DKIM1; k=rsa; p=MIGfMA0".to_owned(),
+                "say \"hi\"".to_owned(),
+                "back\\slash".to_owned(),
+                "tab\tbell\u{7}".to_owned(),
+                "high\u{ff}".to_owned(),
+            ],
+            txt.character_strings
+        );
+
+        // `\;` is not a canonical escape so it is not emitted back
+        let output = txt.to_string();
+        assert_eq!(ESCAPED_TXT_INPUT.replace("\\;", ";"), output);
+        assert_eq!(
+            txt.character_strings,
+            output.parse::<TXT>()?.character_strings
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn txt_unquoted_escapes() -> Result<()> {
+        let txt: TXT = r"example.testing.	0	IN	TXT	two\ words \065BC".parse()?;
+
+        assert_eq!(
+            vec!["two words".to_owned(), "ABC".to_owned()],
+            txt.character_strings
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn txt_invalid_escapes() {
+        for input in [
+            r#"example.testing.	0	IN	TXT	"trailing\"#,
+            r#"example.testing.	0	IN	TXT	"short\12""#,
+            r#"example.testing.	0	IN	TXT	"range\256""#,
+        ] {
+            assert!(input.parse::<TXT>().is_err(), "{input}");
+        }
+    }
+
     const CAA_INPUT: &str = "certs.example.com.	86400	IN	CAA	0 issue ca1.example.net";
 
     #[test]
@@ -1771,15 +3418,22 @@ mod tests {
     #[test]
     fn any() -> Result<()> {
         assert!(matches!(A_INPUT.parse()?, Record::A(..)));
+        assert!(matches!(AAAA_INPUT.parse()?, Record::AAAA(..)));
         assert!(matches!(CAA_INPUT.parse()?, Record::CAA(..)));
         assert!(matches!(DNSKEY_INPUT.parse()?, Record::DNSKEY(..)));
         assert!(matches!(DS_INPUT.parse()?, Record::DS(..)));
+        assert!(matches!(HTTPS_INPUT.parse()?, Record::HTTPS(..)));
+        assert!(matches!(MX_INPUT.parse()?, Record::MX(..)));
         assert!(matches!(NS_INPUT.parse()?, Record::NS(..)));
         assert!(matches!(NSEC_INPUT.parse()?, Record::NSEC(..)));
         assert!(matches!(NSEC3_INPUT.parse()?, Record::NSEC3(..)));
         assert!(matches!(NSEC3PARAM_INPUT.parse()?, Record::NSEC3PARAM(..)));
+        assert!(matches!(PTR_INPUT.parse()?, Record::PTR(..)));
         assert!(matches!(RRSIG_INPUT.parse()?, Record::RRSIG(..)));
         assert!(matches!(SOA_INPUT.parse()?, Record::SOA(..)));
+        assert!(matches!(SRV_INPUT.parse()?, Record::SRV(..)));
+        assert!(matches!(SVCB_INPUT.parse()?, Record::SVCB(..)));
+        assert!(matches!(TLSA_INPUT.parse()?, Record::TLSA(..)));
         assert!(matches!(TXT_INPUT.parse()?, Record::TXT(..)));
 
         Ok(())
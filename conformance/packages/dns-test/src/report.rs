@@ -0,0 +1,371 @@
+//! Machine-readable reports of test scenario results
+//!
+//! Tests wrapped in [`run`] record their outcome, together with the implementations and the DNS
+//! messages attached to their [`Scenario`], as one line of JSON in the file named by the
+//! `DNS_TEST_REPORT` env var. A [`Report`] aggregates these lines, e.g. to compare the results of
+//! `hickory-dns` against those of a reference implementation.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::net::Ipv4Addr;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::container::Image;
+use crate::name_server::{NameServer, Running};
+use crate::tshark::{Capture, Direction};
+use crate::{Implementation, Resolver, Result};
+
+const REPORT_ENV_VAR: &str = "DNS_TEST_REPORT";
+
+/// Prevents the lines of scenarios running in parallel from interleaving
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+/// Runs the `test` scenario and records its result if `DNS_TEST_REPORT` is set
+///
+/// The scenario fails if `test` returns an error or panics; the error is returned, and the panic
+/// resumed, as if `test` had been called directly.
+pub fn run<T>(name: &str, test: impl FnOnce(&mut Scenario) -> Result<T>) -> Result<T> {
+    let mut scenario = Scenario::default();
+    let start = Instant::now();
+    let res = panic::catch_unwind(AssertUnwindSafe(|| test(&mut scenario)));
+    let duration_ms = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+    let outcome = match &res {
+        Ok(Ok(_)) => Outcome::Pass,
+        Ok(Err(e)) => Outcome::Fail {
+            message: e.to_string(),
+        },
+        Err(payload) => Outcome::Fail {
+            message: panic_message(payload.as_ref()),
+        },
+    };
+
+    if let Ok(path) = env::var(REPORT_ENV_VAR) {
+        let result = ScenarioResult {
+            name: name.to_string(),
+            subject: ImplementationInfo::new(&crate::SUBJECT),
+            peer: ImplementationInfo::new(&crate::PEER),
+            outcome,
+            duration_ms,
+            implementations: scenario.implementations,
+            captures: scenario.captures,
+        };
+
+        if let Err(e) = result.append_to(Path::new(&path)) {
+            eprintln!("failed to write the result of {name} to {path}: {e}");
+        }
+    }
+
+    match res {
+        Ok(res) => res,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_string()
+    }
+}
+
+/// What a test attaches to its result
+#[derive(Default)]
+pub struct Scenario {
+    implementations: Vec<ImplementationVersion>,
+    captures: Vec<CapturedMessage>,
+}
+
+impl Scenario {
+    /// Records the implementation and version of a resolver taking part in the scenario
+    pub fn resolver(&mut self, resolver: &Resolver) {
+        self.implementations.push(ImplementationVersion {
+            role: "resolver".to_string(),
+            implementation: ImplementationInfo::new(resolver.implementation()),
+            version: resolver.version().ok(),
+        });
+    }
+
+    /// Records the implementation and version of a name server taking part in the scenario
+    pub fn name_server(&mut self, name_server: &NameServer<Running>) {
+        self.implementations.push(ImplementationVersion {
+            role: "name-server".to_string(),
+            implementation: ImplementationInfo::new(name_server.implementation()),
+            version: name_server.version().ok(),
+        });
+    }
+
+    /// Records the DNS messages captured with `tshark`
+    pub fn captures(&mut self, captures: &[Capture]) {
+        self.captures
+            .extend(captures.iter().map(CapturedMessage::from));
+    }
+}
+
+/// The result of one scenario
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    pub name: String,
+    pub subject: ImplementationInfo,
+    pub peer: ImplementationInfo,
+    pub outcome: Outcome,
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub implementations: Vec<ImplementationVersion>,
+    #[serde(default)]
+    pub captures: Vec<CapturedMessage>,
+}
+
+impl ScenarioResult {
+    fn append_to(&self, path: &Path) -> Result<()> {
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+
+        // write the whole line at once; `O_APPEND` keeps the lines of concurrent test processes
+        // apart, the lock those of concurrent threads
+        let _guard = APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "result")]
+pub enum Outcome {
+    Pass,
+    Fail { message: String },
+}
+
+impl Outcome {
+    pub fn is_pass(&self) -> bool {
+        matches!(self, Self::Pass)
+    }
+}
+
+/// An implementation, as selected by e.g. `DNS_TEST_SUBJECT`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImplementationInfo {
+    pub name: String,
+    pub repository: Option<String>,
+}
+
+impl ImplementationInfo {
+    fn new(implementation: &Implementation) -> Self {
+        let repository = match implementation {
+            Implementation::Hickory { repo, .. } => Some(repo.as_str().to_string()),
+            _ => None,
+        };
+
+        Self {
+            name: Image::from(implementation.clone()).to_string(),
+            repository,
+        }
+    }
+}
+
+/// An implementation taking part in a scenario, as reported by the implementation itself
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImplementationVersion {
+    pub role: String,
+    pub implementation: ImplementationInfo,
+    pub version: Option<String>,
+}
+
+/// A DNS message captured with `tshark`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CapturedMessage {
+    pub direction: CaptureDirection,
+    pub peer: Ipv4Addr,
+    /// The message as decoded by `tshark`
+    pub message: serde_json::Value,
+}
+
+impl From<&Capture> for CapturedMessage {
+    fn from(capture: &Capture) -> Self {
+        let direction = match capture.direction {
+            Direction::Incoming { .. } => CaptureDirection::Incoming,
+            Direction::Outgoing { .. } => CaptureDirection::Outgoing,
+        };
+
+        Self {
+            direction,
+            peer: capture.direction.peer_addr(),
+            message: capture.message.as_value().clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureDirection {
+    Incoming,
+    Outgoing,
+}
+
+/// The aggregated results of a test run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Report {
+    pub passed: usize,
+    pub failed: usize,
+    pub scenarios: Vec<ScenarioResult>,
+}
+
+impl Report {
+    /// Aggregates the results written to a `DNS_TEST_REPORT` file
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Aggregates results, one JSON object per line
+    pub fn parse(lines: &str) -> Result<Self> {
+        let scenarios = lines
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<core::result::Result<Vec<ScenarioResult>, _>>()?;
+
+        Ok(Self::from(scenarios))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Returns the scenarios whose outcome in `self` differs from the outcome in `other`
+    ///
+    /// Scenarios that are missing from either report are not included.
+    pub fn differences<'a>(&'a self, other: &'a Report) -> Vec<Difference<'a>> {
+        let others = other
+            .scenarios
+            .iter()
+            .map(|scenario| (scenario.name.as_str(), scenario))
+            .collect::<BTreeMap<_, _>>();
+
+        self.scenarios
+            .iter()
+            .filter_map(|ours| {
+                let theirs = others.get(ours.name.as_str()).copied()?;
+                (ours.outcome.is_pass() != theirs.outcome.is_pass()).then_some(Difference {
+                    name: &ours.name,
+                    ours: &ours.outcome,
+                    theirs: &theirs.outcome,
+                })
+            })
+            .collect()
+    }
+}
+
+impl From<Vec<ScenarioResult>> for Report {
+    fn from(mut scenarios: Vec<ScenarioResult>) -> Self {
+        scenarios.sort_by(|a, b| a.name.cmp(&b.name));
+        let passed = scenarios
+            .iter()
+            .filter(|scenario| scenario.outcome.is_pass())
+            .count();
+
+        Self {
+            passed,
+            failed: scenarios.len() - passed,
+            scenarios,
+        }
+    }
+}
+
+/// A scenario with a different outcome in two reports
+#[derive(Debug, Serialize)]
+pub struct Difference<'a> {
+    pub name: &'a str,
+    pub ours: &'a Outcome,
+    pub theirs: &'a Outcome,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, subject: &str, outcome: Outcome) -> ScenarioResult {
+        let implementation = ImplementationInfo {
+            name: subject.to_string(),
+            repository: None,
+        };
+
+        ScenarioResult {
+            name: name.to_string(),
+            subject: implementation.clone(),
+            peer: implementation,
+            outcome,
+            duration_ms: 0,
+            implementations: vec![],
+            captures: vec![],
+        }
+    }
+
+    fn fail() -> Outcome {
+        Outcome::Fail {
+            message: "SERVFAIL".to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_aggregates_lines() -> Result<()> {
+        let mut lines = String::new();
+        for scenario in [
+            result("b", "unbound", Outcome::Pass),
+            result("a", "unbound", fail()),
+        ] {
+            lines.push_str(&serde_json::to_string(&scenario)?);
+            lines.push('\n');
+        }
+
+        let report = Report::parse(&lines)?;
+
+        assert_eq!(1, report.passed);
+        assert_eq!(1, report.failed);
+        let names = report
+            .scenarios
+            .iter()
+            .map(|scenario| scenario.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(["a", "b"], names.as_slice());
+
+        Ok(())
+    }
+
+    #[test]
+    fn differences() {
+        let hickory = Report::from(vec![
+            result("a", "hickory", Outcome::Pass),
+            result("b", "hickory", fail()),
+            result("c", "hickory", fail()),
+        ]);
+        let unbound = Report::from(vec![
+            result("a", "unbound", Outcome::Pass),
+            result("b", "unbound", Outcome::Pass),
+        ]);
+
+        let differences = hickory.differences(&unbound);
+
+        assert_eq!(1, differences.len());
+        assert_eq!("b", differences[0].name);
+        assert!(!differences[0].ours.is_pass());
+        assert!(differences[0].theirs.is_pass());
+    }
+
+    #[test]
+    fn panic_message_of_formatted_panic() {
+        let payload = panic::catch_unwind(|| panic!("expected {}", "NOERROR")).unwrap_err();
+        assert_eq!("expected NOERROR", panic_message(payload.as_ref()));
+    }
+}
@@ -1,5 +1,6 @@
-use core::fmt::Write;
+use core::fmt::{self, Write};
 use std::net::Ipv4Addr;
+use std::panic::{self, AssertUnwindSafe};
 
 use crate::container::{Child, Container, Network};
 use crate::implementation::{Config, Role};
@@ -25,6 +26,7 @@ impl Resolver {
             trust_anchor: TrustAnchor::empty(),
             custom_config: None,
             case_randomization: false,
+            validation: true,
         }
     }
 
@@ -48,6 +50,15 @@ impl Resolver {
         self.container.ipv4_addr()
     }
 
+    pub fn implementation(&self) -> &Implementation {
+        &self.implementation
+    }
+
+    /// Returns the version reported by the implementation
+    pub fn version(&self) -> Result<String> {
+        self.implementation.version(&self.container, Role::Resolver)
+    }
+
     /// Returns the logs collected so far
     pub fn logs(&self) -> Result<String> {
         if self.implementation.is_hickory() {
@@ -76,6 +87,7 @@ pub struct ResolverSettings {
     trust_anchor: TrustAnchor,
     custom_config: Option<String>,
     case_randomization: bool,
+    validation: bool,
 }
 
 impl ResolverSettings {
@@ -100,13 +112,14 @@ impl ResolverSettings {
 
         container.cp("/etc/root.hints", &hints)?;
 
-        let use_dnssec = !self.trust_anchor.is_empty();
+        let use_dnssec = self.validation && !self.trust_anchor.is_empty();
         let config_contents = if let Some(custom_config) = &self.custom_config {
             custom_config
         } else {
             let config = Config::Resolver {
                 use_dnssec,
                 netmask: self.network.netmask(),
+                ipv6_netmask: self.network.ipv6_netmask(),
                 ede: self.ede,
                 case_randomization: self.case_randomization,
             };
@@ -177,6 +190,164 @@ impl ResolverSettings {
         self.case_randomization = true;
         self
     }
+
+    /// Disables DNSSEC validation, even if a trust anchor has been configured.
+    pub fn disable_validation(&mut self) -> &mut Self {
+        self.validation = false;
+        self
+    }
+
+    /// Applies the settings of one permutation of a [`ResolverMatrix`].
+    pub fn permutation(&mut self, permutation: ResolverPermutation) -> &mut Self {
+        let ResolverPermutation {
+            validation,
+            case_randomization,
+        } = permutation;
+
+        self.validation = validation;
+        self.case_randomization = case_randomization;
+        self
+    }
+}
+
+/// The resolver configurations a scenario is run against
+///
+/// Every combination of the values of each dimension is a [`ResolverPermutation`]. By default
+/// all the values of all the dimensions are used.
+///
+/// Only the settings that the Hickory recursor can be configured with are covered.
+#[derive(Clone, Debug)]
+pub struct ResolverMatrix {
+    validation: Vec<bool>,
+    case_randomization: Vec<bool>,
+}
+
+impl Default for ResolverMatrix {
+    fn default() -> Self {
+        Self {
+            validation: vec![false, true],
+            case_randomization: vec![false, true],
+        }
+    }
+}
+
+impl ResolverMatrix {
+    /// Restricts the DNSSEC validation dimension to the given values
+    pub fn validation(&mut self, values: &[bool]) -> &mut Self {
+        self.validation = values.to_vec();
+        self
+    }
+
+    /// Restricts the case randomization dimension to the given values
+    pub fn case_randomization(&mut self, values: &[bool]) -> &mut Self {
+        self.case_randomization = values.to_vec();
+        self
+    }
+
+    /// Returns all the permutations of this matrix
+    pub fn permutations(&self) -> Vec<ResolverPermutation> {
+        let mut permutations = vec![];
+        for &validation in &self.validation {
+            for &case_randomization in &self.case_randomization {
+                permutations.push(ResolverPermutation {
+                    validation,
+                    case_randomization,
+                });
+            }
+        }
+        permutations
+    }
+
+    /// Runs `scenario` once per permutation and collects the outcome of each run
+    ///
+    /// A panic in `scenario`, e.g. a failed assertion, is recorded as a failure of that
+    /// permutation and does not prevent the remaining permutations from running.
+    pub fn run(
+        &self,
+        mut scenario: impl FnMut(ResolverPermutation) -> Result<()>,
+    ) -> ResolverMatrixReport {
+        let mut outcomes = vec![];
+        for permutation in self.permutations() {
+            let outcome = match panic::catch_unwind(AssertUnwindSafe(|| scenario(permutation))) {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(payload) => Err(panic_message(payload.as_ref())),
+            };
+            outcomes.push((permutation, outcome));
+        }
+
+        ResolverMatrixReport { outcomes }
+    }
+}
+
+fn panic_message(payload: &(dyn core::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        format!("panicked: {message}")
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        format!("panicked: {message}")
+    } else {
+        "panicked".to_string()
+    }
+}
+
+/// One resolver configuration of a [`ResolverMatrix`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResolverPermutation {
+    /// Whether DNSSEC validation is enabled; only effective when a trust anchor is configured
+    pub validation: bool,
+    pub case_randomization: bool,
+}
+
+impl fmt::Display for ResolverPermutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_off = |enabled| if enabled { "on" } else { "off" };
+        write!(
+            f,
+            "validation={} case_randomization={}",
+            on_off(self.validation),
+            on_off(self.case_randomization)
+        )
+    }
+}
+
+/// The outcome of running a scenario against each permutation of a [`ResolverMatrix`]
+#[derive(Debug)]
+pub struct ResolverMatrixReport {
+    pub outcomes: Vec<(ResolverPermutation, core::result::Result<(), String>)>,
+}
+
+impl ResolverMatrixReport {
+    /// Returns the permutations the scenario failed on, along with the error
+    pub fn failures(&self) -> impl Iterator<Item = (ResolverPermutation, &str)> {
+        self.outcomes
+            .iter()
+            .filter_map(|(permutation, outcome)| match outcome {
+                Ok(()) => None,
+                Err(e) => Some((*permutation, e.as_str())),
+            })
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Panics, with a per-permutation summary, if the scenario failed on any permutation
+    #[track_caller]
+    pub fn assert_ok(&self) {
+        assert!(self.is_ok(), "scenario failed:\n{self}");
+    }
+}
+
+impl fmt::Display for ResolverMatrixReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (permutation, outcome) in &self.outcomes {
+            match outcome {
+                Ok(()) => writeln!(f, "ok     {permutation}")?,
+                Err(e) => writeln!(f, "FAILED {permutation}: {e}")?,
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -187,6 +358,55 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn matrix_permutations() {
+        let matrix = ResolverMatrix::default();
+        assert_eq!(4, matrix.permutations().len());
+
+        let permutations = ResolverMatrix::default().validation(&[true]).permutations();
+        assert_eq!(
+            vec![
+                ResolverPermutation {
+                    validation: true,
+                    case_randomization: false,
+                },
+                ResolverPermutation {
+                    validation: true,
+                    case_randomization: true,
+                },
+            ],
+            permutations
+        );
+    }
+
+    #[test]
+    fn matrix_report_aggregates_failures() {
+        let report = ResolverMatrix::default().run(|permutation| {
+            if permutation.case_randomization {
+                return Err("lost the 0x20 bits".into());
+            }
+            assert!(permutation.validation, "validation is required");
+            Ok(())
+        });
+
+        assert_eq!(4, report.outcomes.len());
+        assert!(!report.is_ok());
+
+        let failures = report.failures().collect::<Vec<_>>();
+        assert_eq!(3, failures.len());
+        assert!(
+            failures
+                .iter()
+                .any(|(permutation, e)| !permutation.validation
+                    && !permutation.case_randomization
+                    && e.contains("validation is required"))
+        );
+
+        let summary = report.to_string();
+        assert!(summary.contains("ok     validation=on case_randomization=off"));
+        assert!(summary.contains("FAILED validation=on case_randomization=on: lost the 0x20 bits"));
+    }
+
     #[test]
     fn unbound_logs_works() -> Result<()> {
         let network = Network::new()?;
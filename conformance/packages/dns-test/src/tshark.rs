@@ -202,6 +202,55 @@ pub struct Message {
 }
 
 impl Message {
+    pub(crate) fn new(inner: serde_json::Value) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the ID of the message
+    pub fn id(&self) -> Option<u16> {
+        let id = self.inner.get("dns.id")?.as_str()?;
+        u16::from_str_radix(id.strip_prefix("0x")?, 16).ok()
+    }
+
+    /// Returns `true` if the message is a response
+    pub fn is_response(&self) -> bool {
+        self.inner["dns.flags_tree"]
+            .get("dns.flags.response")
+            .and_then(|response| response.as_str())
+            == Some("1")
+    }
+
+    /// Returns the codes of the options in the OPT pseudo-RR
+    ///
+    /// Returns an empty list if there's no OPT pseudo-RR
+    pub fn edns_option_codes(&self) -> Vec<u16> {
+        fn collect(value: &serde_json::Value, codes: &mut Vec<u16>) {
+            match value {
+                serde_json::Value::Object(object) => {
+                    for (key, value) in object {
+                        if key == "dns.opt.code" {
+                            codes.extend(value.as_str().and_then(|code| code.parse::<u16>().ok()));
+                        } else {
+                            collect(value, codes);
+                        }
+                    }
+                }
+                serde_json::Value::Array(array) => {
+                    for value in array {
+                        collect(value, codes);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut codes = vec![];
+        if let Some(opt_record) = self.opt_record() {
+            collect(opt_record, &mut codes);
+        }
+        codes
+    }
+
     /// Returns `true` if the DO bit is set
     ///
     /// Returns `None` if there's no OPT pseudo-RR
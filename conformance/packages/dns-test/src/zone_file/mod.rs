@@ -6,12 +6,13 @@
 
 use core::fmt;
 use std::array;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
 
 use crate::record::{self, DNSKEYRData, RRSIG, Record, RecordType, SOA, write_split_long_string};
 use crate::{DEFAULT_TTL, Error, FQDN, Result};
 
+mod parser;
 mod signer;
 
 pub use signer::{Nsec, SignSettings, Signer};
@@ -114,6 +115,7 @@ impl FromStr for ZoneFile {
 #[derive(Clone)]
 pub struct Root {
     pub ipv4_addr: Ipv4Addr,
+    pub ipv6_addr: Option<Ipv6Addr>,
     pub ns: FQDN,
     pub ttl: u32,
 }
@@ -123,6 +125,7 @@ impl Root {
     pub fn new(ns: FQDN, ipv4_addr: Ipv4Addr) -> Self {
         Self {
             ipv4_addr,
+            ipv6_addr: None,
             ns,
             ttl: DEFAULT_TTL,
         }
@@ -131,6 +134,7 @@ impl Root {
     pub fn public_dns() -> Root {
         Root {
             ipv4_addr: Ipv4Addr::new(198, 41, 0, 4),
+            ipv6_addr: None,
             ns: FQDN("a.root-servers.net.").unwrap(),
             ttl: DEFAULT_TTL,
         }
@@ -139,10 +143,19 @@ impl Root {
 
 impl fmt::Display for Root {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let Self { ipv4_addr, ns, ttl } = self;
+        let Self {
+            ipv4_addr,
+            ipv6_addr,
+            ns,
+            ttl,
+        } = self;
 
         writeln!(f, ".\t{ttl}\tNS\t{ns}")?;
-        write!(f, "{ns}\t{ttl}\tA\t{ipv4_addr}")
+        write!(f, "{ns}\t{ttl}\tA\t{ipv4_addr}")?;
+        if let Some(ipv6_addr) = ipv6_addr {
+            write!(f, "\n{ns}\t{ttl}\tAAAA\t{ipv6_addr}")?;
+        }
+        Ok(())
     }
 }
 
@@ -162,10 +175,6 @@ impl DNSKEY {
 
         record::DNSKEY { zone, ttl, rdata }
     }
-
-    pub(crate) fn rdata(&self) -> &DNSKEYRData {
-        &self.rdata
-    }
 }
 
 impl FromStr for DNSKEY {
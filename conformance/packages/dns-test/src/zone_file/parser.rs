@@ -0,0 +1,407 @@
+//! Master file parser (section 5 of RFC1035)
+
+use std::mem;
+
+use super::ZoneFile;
+use crate::record::{Record, RecordType};
+use crate::{Error, FQDN, Result};
+
+impl ZoneFile {
+    /// Parses the entries of a zone file in the master file format
+    ///
+    /// Unlike `ZoneFile::from_str`, which expects the canonical one-record-per-line format produced
+    /// by this crate and by the signing tools, this accepts zone files written by hand or captured
+    /// from real deployments. The following syntax is supported:
+    ///
+    /// - the `$ORIGIN` and `$TTL` directives
+    /// - `@` and names relative to the current origin, both as owner names and in the RDATA of the
+    ///   record types that contain domain names
+    /// - entries that omit the owner name, which is then the owner of the previous entry
+    /// - entries that omit the TTL and/or the class column, in either order
+    /// - TTLs with unit suffixes, e.g. `1h30m`
+    /// - comments and entries that span multiple lines using parentheses
+    ///
+    /// When the TTL is omitted, the value set by `$TTL` is used or, in its absence, the TTL of the
+    /// previous entry. `$INCLUDE` and classes other than `IN` are not supported.
+    pub fn parse(input: &str) -> Result<Vec<Record>> {
+        let mut records = vec![];
+        let mut origin: Option<FQDN> = None;
+        let mut default_ttl = None;
+        let mut last_ttl = None;
+        let mut last_owner: Option<FQDN> = None;
+
+        for (line_number, entry) in entries(input)? {
+            let with_line = |e: Error| -> Error { format!("line {line_number}: {e}").into() };
+
+            let mut tokens = entry.text.split_whitespace();
+            let Some(first) = tokens.next() else {
+                continue;
+            };
+
+            if let Some(directive) = first.strip_prefix('$') {
+                let argument = tokens.next();
+                match (directive.to_ascii_uppercase().as_str(), argument) {
+                    ("ORIGIN", Some(name)) => {
+                        origin = Some(qualify(name, origin.as_ref()).map_err(with_line)?);
+                    }
+                    ("TTL", Some(ttl)) => default_ttl = Some(parse_ttl(ttl).map_err(with_line)?),
+                    ("INCLUDE", _) => {
+                        return Err(with_line("the $INCLUDE directive is not supported".into()));
+                    }
+                    _ => return Err(with_line(format!("invalid directive: {entry}").into())),
+                }
+                continue;
+            }
+
+            let owner = if entry.inherits_owner {
+                last_owner
+                    .clone()
+                    .ok_or_else(|| with_line("entry has no owner name".into()))?
+            } else {
+                qualify(first, origin.as_ref()).map_err(with_line)?
+            };
+
+            let mut tokens = if entry.inherits_owner {
+                entry.text.split_whitespace()
+            } else {
+                tokens
+            };
+
+            let mut ttl = None;
+            let mut record_type = None;
+            for token in tokens.by_ref().take(3) {
+                if ttl.is_none() && token.starts_with(|c: char| c.is_ascii_digit()) {
+                    ttl = Some(parse_ttl(token).map_err(with_line)?);
+                } else if token.eq_ignore_ascii_case("IN") {
+                    continue;
+                } else if is_class(token) {
+                    return Err(with_line(format!("unsupported class: {token}").into()));
+                } else {
+                    record_type = Some(parse_record_type(token).map_err(with_line)?);
+                    break;
+                }
+            }
+
+            let record_type =
+                record_type.ok_or_else(|| with_line("entry has no record type".into()))?;
+            let ttl = ttl
+                .or(default_ttl)
+                .or(last_ttl)
+                .ok_or_else(|| with_line("entry has no TTL and no default was set".into()))?;
+
+            let rdata = rest(&entry.text, tokens);
+            let rdata = qualify_rdata(&record_type, rdata, origin.as_ref()).map_err(with_line)?;
+
+            let record = format!("{owner}\t{ttl}\tIN\t{record_type}\t{rdata}")
+                .parse()
+                .map_err(with_line)?;
+            records.push(record);
+
+            last_owner = Some(owner);
+            last_ttl = Some(ttl);
+        }
+
+        Ok(records)
+    }
+}
+
+/// A logical entry: one line, or several lines joined by parentheses
+struct Entry {
+    /// comments and parentheses removed
+    text: String,
+    /// the entry starts with whitespace so the owner name is the one of the previous entry
+    inherits_owner: bool,
+}
+
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.text.trim())
+    }
+}
+
+/// Splits the input into logical entries, along with the line number each entry starts at
+fn entries(input: &str) -> Result<Vec<(usize, Entry)>> {
+    let mut entries = vec![];
+    let mut current = String::new();
+    let mut start = 0;
+    let mut inherits_owner = false;
+    let mut depth = 0_usize;
+
+    for (index, line) in input.lines().enumerate() {
+        if depth == 0 {
+            start = index + 1;
+            inherits_owner = line.starts_with(|c: char| c.is_ascii_whitespace());
+        }
+
+        let mut in_quotes = false;
+        let mut characters = line.chars();
+        while let Some(character) = characters.next() {
+            match character {
+                '\\' => {
+                    current.push(character);
+                    current.extend(characters.next());
+                }
+                '"' => {
+                    in_quotes = !in_quotes;
+                    current.push(character);
+                }
+                ';' if !in_quotes => break,
+                '(' if !in_quotes => {
+                    depth += 1;
+                    current.push(' ');
+                }
+                ')' if !in_quotes => {
+                    depth = depth
+                        .checked_sub(1)
+                        .ok_or_else(|| format!("line {}: unbalanced parentheses", index + 1))?;
+                    current.push(' ');
+                }
+                _ => current.push(character),
+            }
+        }
+
+        if in_quotes {
+            return Err(format!("line {}: quoted string was not closed", index + 1).into());
+        }
+
+        if depth == 0 {
+            let text = mem::take(&mut current);
+            if !text.trim().is_empty() {
+                entries.push((
+                    start,
+                    Entry {
+                        text,
+                        inherits_owner,
+                    },
+                ));
+            }
+        } else {
+            current.push(' ');
+        }
+    }
+
+    if depth != 0 {
+        return Err(format!("line {start}: parentheses were not closed").into());
+    }
+
+    Ok(entries)
+}
+
+/// Returns the part of `text` that `tokens`, an iterator over the whitespace separated tokens of
+/// `text`, has not yielded yet
+fn rest<'a>(text: &'a str, mut tokens: std::str::SplitWhitespace<'a>) -> &'a str {
+    match tokens.next() {
+        Some(next) => {
+            // `next` is a subslice of `text`
+            let offset = next.as_ptr() as usize - text.as_ptr() as usize;
+            text[offset..].trim()
+        }
+        None => "",
+    }
+}
+
+fn is_class(token: &str) -> bool {
+    ["CH", "HS", "CS", "NONE", "ANY"]
+        .iter()
+        .any(|class| token.eq_ignore_ascii_case(class))
+}
+
+fn parse_record_type(token: &str) -> Result<RecordType> {
+    token
+        .to_ascii_uppercase()
+        .parse()
+        .or_else(|_: Error| token.parse())
+}
+
+/// Parses a TTL in seconds, or in the `1w2d3h4m5s` format
+fn parse_ttl(input: &str) -> Result<u32> {
+    if let Ok(ttl) = input.parse() {
+        return Ok(ttl);
+    }
+
+    let invalid = || -> Error { format!("invalid TTL: {input}").into() };
+
+    let mut ttl = 0_u32;
+    let mut digits = input;
+    while !digits.is_empty() {
+        let unit_index = digits
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let value: u32 = digits[..unit_index].parse().map_err(|_| invalid())?;
+        let unit = match digits.as_bytes()[unit_index].to_ascii_lowercase() {
+            b's' => 1,
+            b'm' => 60,
+            b'h' => 60 * 60,
+            b'd' => 24 * 60 * 60,
+            b'w' => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        ttl = value
+            .checked_mul(unit)
+            .and_then(|seconds| ttl.checked_add(seconds))
+            .ok_or_else(invalid)?;
+        digits = &digits[unit_index + 1..];
+    }
+
+    Ok(ttl)
+}
+
+/// Turns `name` into a fully qualified domain name, using `origin` for `@` and relative names
+fn qualify(name: &str, origin: Option<&FQDN>) -> Result<FQDN> {
+    if name.ends_with('.') && !name.ends_with("\\.") {
+        return FQDN(name.to_string());
+    }
+
+    let origin = origin.ok_or_else(|| format!("relative name `{name}` used before $ORIGIN"))?;
+    if name == "@" {
+        return Ok(origin.clone());
+    }
+
+    match origin.as_str() {
+        "." => FQDN(format!("{name}.")),
+        origin => FQDN(format!("{name}.{origin}")),
+    }
+}
+
+/// Qualifies the domain names in the RDATA of the record types that contain them
+fn qualify_rdata(record_type: &RecordType, rdata: &str, origin: Option<&FQDN>) -> Result<String> {
+    let name_columns: &[usize] = match record_type {
+        RecordType::CNAME | RecordType::NS | RecordType::NSEC | RecordType::PTR => &[0],
+        RecordType::HTTPS | RecordType::MX | RecordType::SVCB => &[1],
+        RecordType::SOA => &[0, 1],
+        RecordType::SRV => &[3],
+        RecordType::RRSIG => &[7],
+        _ => return Ok(rdata.to_string()),
+    };
+
+    let mut columns = rdata
+        .split_whitespace()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    for &index in name_columns {
+        if let Some(column) = columns.get_mut(index) {
+            *column = qualify(column, origin)?.as_str().to_string();
+        }
+    }
+
+    Ok(columns.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const ZONE: &str = r#"
+$ORIGIN hickory-dns.testing.
+$TTL 1h
+; the apex
+@	IN	SOA	ns1 admin (
+		2024010101 ; serial
+		7200       ; refresh
+		3600 1209600 300 )
+	IN	NS	ns1
+ns1		A	192.0.2.1
+www	300	IN	CNAME	ns1
+mail	IN	1d	MX	10 mx.example.com.
+@	TXT	"v=spf1 -all ; not a comment" ( "second string" )
+_sip._udp	SRV	10 1 5060 ns1
+
+$ORIGIN sub
+a	a	192.0.2.2 ; trailing comment
+"#;
+
+    #[test]
+    fn parse() -> Result<()> {
+        let records = ZoneFile::parse(ZONE)?;
+        assert_eq!(8, records.len());
+
+        let mut records = records.into_iter();
+
+        let soa = records.next().unwrap().try_into_soa().unwrap();
+        assert_eq!("hickory-dns.testing.", soa.zone.as_str());
+        assert_eq!(3600, soa.ttl);
+        assert_eq!("ns1.hickory-dns.testing.", soa.nameserver.as_str());
+        assert_eq!("admin.hickory-dns.testing.", soa.admin.as_str());
+        assert_eq!(2024010101, soa.settings.serial);
+        assert_eq!(300, soa.settings.minimum);
+
+        let Some(Record::NS(ns)) = records.next() else {
+            panic!("expected a NS record");
+        };
+        assert_eq!("hickory-dns.testing.", ns.zone.as_str());
+        assert_eq!("ns1.hickory-dns.testing.", ns.nameserver.as_str());
+
+        let a = records.next().unwrap().try_into_a().unwrap();
+        assert_eq!("ns1.hickory-dns.testing.", a.fqdn.as_str());
+        assert_eq!(3600, a.ttl);
+        assert_eq!(Ipv4Addr::new(192, 0, 2, 1), a.ipv4_addr);
+
+        let cname = records.next().unwrap().try_into_cname().unwrap();
+        assert_eq!(300, cname.ttl);
+        assert_eq!("ns1.hickory-dns.testing.", cname.target.as_str());
+
+        let mx = records.next().unwrap().try_into_mx().unwrap();
+        assert_eq!("mail.hickory-dns.testing.", mx.fqdn.as_str());
+        assert_eq!(86400, mx.ttl);
+        assert_eq!("mx.example.com.", mx.exchange.as_str());
+
+        let txt = records.next().unwrap().try_into_txt().unwrap();
+        assert_eq!("hickory-dns.testing.", txt.zone.as_str());
+        assert_eq!(
+            vec![
+                "v=spf1 -all ; not a comment".to_owned(),
+                "second string".to_owned()
+            ],
+            txt.character_strings
+        );
+
+        let srv = records.next().unwrap().try_into_srv().unwrap();
+        assert_eq!("_sip._udp.hickory-dns.testing.", srv.fqdn.as_str());
+        assert_eq!("ns1.hickory-dns.testing.", srv.target.as_str());
+
+        let a = records.next().unwrap().try_into_a().unwrap();
+        assert_eq!("a.sub.hickory-dns.testing.", a.fqdn.as_str());
+
+        Ok(())
+    }
+
+    #[test]
+    fn ttl_defaults_to_previous_entry() -> Result<()> {
+        let records = ZoneFile::parse(
+            "example.testing. 60 IN A 192.0.2.1\nwww.example.testing. A 192.0.2.2",
+        )?;
+
+        let a = records[1].clone().try_into_a().unwrap();
+        assert_eq!(60, a.ttl);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ttl_units() -> Result<()> {
+        assert_eq!(5400, parse_ttl("1h30m")?);
+        assert_eq!(694861, parse_ttl("1W1d1H1M1S")?);
+        assert!(parse_ttl("1x").is_err());
+        assert!(parse_ttl("h").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        for input in [
+            "www A 192.0.2.1",
+            "www.example.testing. A 192.0.2.1",
+            "$TTL 60\nwww.example.testing. CH A 192.0.2.1",
+            "$INCLUDE other.zone",
+            "$TTL 60\nexample.testing. SOA ns admin ( 1 2 3 4 5",
+            "$TTL 60\n\tA 192.0.2.1",
+        ] {
+            assert!(ZoneFile::parse(input).is_err(), "{input}");
+        }
+    }
+}
@@ -268,17 +268,6 @@ impl<'a> Signer<'a> {
         let signzone = format!("cd {ZONES_DIR} && {signzone_cmd}");
         self.container.status_ok(&["sh", "-c", &signzone])?;
 
-        // TODO do we want to make the hashing algorithm configurable?
-        // -2 = use SHA256 for the DS hash
-        let key2ds = format!("cd {ZONES_DIR} && ldns-key2ds -f -n -2 {ZONE_FILENAME}.signed");
-        let dses = self
-            .container
-            .stdout(&["sh", "-c", &key2ds])?
-            .lines()
-            .map(|line| line.parse())
-            .collect::<Result<Vec<DS>, _>>()?;
-        let ds = DS2::classify(dses, &keys.zsk.public, &keys.ksk.public);
-
         let signed: ZoneFile = self
             .container
             .stdout(&["cat", &format!("{zone_file_path}.signed")])?
@@ -287,6 +276,12 @@ impl<'a> Signer<'a> {
         let ksk = keys.ksk.public.clone().with_ttl(ttl);
         let zsk = keys.zsk.public.clone().with_ttl(ttl);
 
+        // TODO do we want to make the hashing algorithm configurable?
+        let ds = DS2 {
+            ksk: ksk.to_ds(DS::DIGEST_TYPE_SHA256),
+            zsk: zsk.to_ds(DS::DIGEST_TYPE_SHA256),
+        };
+
         Ok(Signed {
             ds,
             signed,
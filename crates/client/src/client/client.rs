@@ -15,7 +15,7 @@ use std::{
 
 use futures_util::{
     ready,
-    stream::{Stream, StreamExt},
+    stream::{self, Stream, StreamExt, TryStreamExt},
 };
 use tracing::debug;
 
@@ -30,6 +30,8 @@ use hickory_proto::{
     },
 };
 
+use super::{UpdateBuilder, ZoneSnapshot};
+
 #[doc(hidden)]
 #[deprecated(since = "0.25.0", note = "use `Client` instead")]
 pub type ClientFuture = Client;
@@ -554,13 +556,46 @@ pub trait ClientHandle: 'static + Clone + DnsHandle + Send {
         ClientResponse(self.send(DnsRequest::from(message)))
     }
 
+    /// Sends a dynamic update assembled with an [`UpdateBuilder`]
+    ///
+    /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
+    ///
+    /// All changes of the update are applied atomically if the prerequisites hold. Whether EDNS
+    /// is used follows this handle, like for the other update operations.
+    ///
+    /// # Arguments
+    ///
+    /// * `update` - the update to send, fails if it is inconsistent, see [`UpdateBuilder::build`]
+    ///
+    /// The update must go to a zone authority (i.e. the server used in the ClientConnection).
+    fn update(
+        &mut self,
+        update: UpdateBuilder,
+    ) -> Result<ClientResponse<<Self as DnsHandle>::Response>, ProtoError> {
+        let request = update.use_edns(self.is_using_edns()).build()?;
+        Ok(ClientResponse(self.send(request)))
+    }
+
+    /// Requests the changes to the zone of `snapshot` since its version, with an IXFR query
+    ///
+    /// [RFC 1995](https://tools.ietf.org/html/rfc1995), Incremental Zone Transfer in DNS
+    ///
+    /// The changes are applied to the snapshot by [`ClientStreamXfr::apply_to`], which also
+    /// handles servers falling back to a transfer of the whole zone.
+    fn ixfr(&mut self, snapshot: &ZoneSnapshot) -> ClientStreamXfr<<Self as DnsHandle>::Response> {
+        self.zone_transfer(snapshot.origin().clone(), Some(snapshot.soa().clone()))
+    }
+
     /// Download all records from a zone, or all records modified since given SOA was observed.
     /// The request will either be a AXFR Query (ask for full zone transfer) if a SOA was not
     /// provided, or a IXFR Query (incremental zone transfer) if a SOA was provided.
     ///
+    /// The responses of an AXFR are collected into the records of the zone by
+    /// [`ClientStreamXfr::snapshot`].
+    ///
     /// # Arguments
     /// * `zone_origin` - the zone name to update, i.e. SOA name
-    /// * `last_soa` - the last SOA known, if any. If provided, name must match `zone_origin`
+    /// * `last_soa` - the last SOA known of the zone, if any
     fn zone_transfer(
         &mut self,
         zone_origin: Name,
@@ -629,6 +664,33 @@ where
             state: ClientStreamXfrState::Start { inner, maybe_incr },
         }
     }
+
+    /// Returns the records of the answer sections of the zone transfer, in order
+    pub fn records(self) -> impl Stream<Item = Result<Record, ProtoError>> + Send + Unpin {
+        self.map_ok(|response| {
+            stream::iter(response.into_message().take_answers().into_iter().map(Ok))
+        })
+        .try_flatten()
+    }
+
+    /// Collects the records of an AXFR into a snapshot of the zone
+    ///
+    /// The whole zone is received before this returns. Fails if the transfer fails, or if the
+    /// records are not a valid AXFR, see [`ZoneSnapshot::from_axfr`]. The responses are verified
+    /// by the signer of the connection, e.g. for TSIG.
+    pub async fn snapshot(self) -> Result<ZoneSnapshot, ProtoError> {
+        let records = self.records().try_collect::<Vec<_>>().await?;
+        ZoneSnapshot::from_axfr(records)
+    }
+
+    /// Applies the records of an IXFR to `snapshot`, and returns the new serial of the zone
+    ///
+    /// The whole transfer is received before `snapshot` is updated, which is replaced if the
+    /// server fell back to an AXFR. See [`ZoneSnapshot::apply_ixfr`].
+    pub async fn apply_to(self, snapshot: &mut ZoneSnapshot) -> Result<u32, ProtoError> {
+        let records = self.records().try_collect::<Vec<_>>().await?;
+        snapshot.apply_ixfr(records)
+    }
 }
 
 /// State machine for ClientStreamXfr, implementing almost all logic
@@ -917,6 +979,37 @@ mod tests {
         assert!(stream.next().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_stream_xfr_snapshot() {
+        subscribe();
+        let stream = get_stream_testcase(vec![
+            vec![soa_record(3), a_record(1)],
+            vec![a_record(2)],
+            vec![soa_record(3)],
+            vec![a_record(3)], // will be ignored as connection is dropped before reading this message
+        ]);
+        let snapshot = ClientStreamXfr::new(stream, false)
+            .snapshot()
+            .await
+            .unwrap();
+
+        assert_eq!(snapshot.serial(), 3);
+        let www = Name::from_ascii("www.example.com.").unwrap();
+        let rrset = snapshot.get(&www, RecordType::A).unwrap();
+        assert_eq!(rrset.records_without_rrsigs().count(), 2);
+
+        // an IXFR is not a snapshot of the zone
+        let stream = get_stream_testcase(vec![vec![
+            soa_record(3),
+            soa_record(1),
+            a_record(1),
+            soa_record(3),
+            a_record(2),
+            soa_record(3),
+        ]]);
+        assert!(ClientStreamXfr::new(stream, true).snapshot().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_stream_xfr_empty_axfr() {
         subscribe();
@@ -1061,6 +1154,34 @@ mod tests {
         assert!(stream.next().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_stream_xfr_apply_to() {
+        subscribe();
+        let stream = get_stream_testcase(vec![vec![soa_record(2), a_record(1), soa_record(2)]]);
+        let mut snapshot = ClientStreamXfr::new(stream, false)
+            .snapshot()
+            .await
+            .unwrap();
+
+        let stream = get_stream_testcase(vec![
+            vec![soa_record(3), soa_record(2)],
+            vec![a_record(1), soa_record(3)],
+            vec![a_record(2), soa_record(3)],
+        ]);
+        let serial = ClientStreamXfr::new(stream, true)
+            .apply_to(&mut snapshot)
+            .await
+            .unwrap();
+        assert_eq!(serial, 3);
+
+        let www = Name::from_ascii("www.example.com.").unwrap();
+        let rrset = snapshot.get(&www, RecordType::A).unwrap();
+        assert_eq!(
+            rrset.records_without_rrsigs().collect::<Vec<_>>(),
+            [&a_record(2)]
+        );
+    }
+
     #[tokio::test]
     async fn async_client() {
         subscribe();
@@ -23,9 +23,18 @@ pub(crate) mod dnssec_client;
 
 mod memoize_client_handle;
 mod rc_stream;
+mod update_builder;
+mod zone_snapshot;
 
 #[allow(deprecated)]
-pub use self::client::{Client, ClientFuture, ClientHandle, ClientStreamingResponse};
+pub use self::client::{
+    Client, ClientFuture, ClientHandle, ClientStreamXfr, ClientStreamingResponse,
+};
 #[cfg(feature = "__dnssec")]
 pub use self::dnssec_client::{AsyncSecureClientBuilder, DnssecClient};
 pub use self::memoize_client_handle::MemoizeClientHandle;
+pub use self::update_builder::{
+    Prerequisite, UpdateBuilder, name_in_use, name_not_in_use, rr_exists, rr_not_exists,
+    rrset_equals,
+};
+pub use self::zone_snapshot::{IncrementalTransfer, ZoneDiff, ZoneSnapshot};
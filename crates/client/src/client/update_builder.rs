@@ -0,0 +1,535 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A builder for [RFC 2136](https://tools.ietf.org/html/rfc2136) dynamic updates
+
+use std::sync::Arc;
+
+use hickory_proto::{
+    ProtoError,
+    op::{Edns, Message, MessageSigner, OpCode, Query, update_message::MAX_PAYLOAD_LEN},
+    rr::{DNSClass, Name, Record, RecordSet, RecordType},
+    xfer::DnsRequest,
+};
+
+/// A prerequisite of a dynamic update, which must hold for the update to be applied
+///
+/// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
+///
+/// ```text
+/// 2.4 - Prerequisite Section
+///
+///   The Prerequisite Section contains a set of RRsets which must exist
+///   (or not exist) on the Primary Zone Server in order for the update to
+///   be applied.
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Prerequisite {
+    /// At least one record with the name and type exists, 2.4.1
+    RrsetExists(Name, RecordType),
+    /// An RRset exists with exactly the given records, 2.4.2
+    RrsetEquals(RecordSet),
+    /// No record with the name and type exists, 2.4.3
+    RrsetNotExists(Name, RecordType),
+    /// At least one record of any type exists with the name, 2.4.4
+    NameInUse(Name),
+    /// No record of any type exists with the name, 2.4.5
+    NameNotInUse(Name),
+}
+
+impl Prerequisite {
+    /// Returns the name this prerequisite applies to
+    pub fn name(&self) -> &Name {
+        match self {
+            Self::RrsetExists(name, _)
+            | Self::RrsetNotExists(name, _)
+            | Self::NameInUse(name)
+            | Self::NameNotInUse(name) => name,
+            Self::RrsetEquals(rrset) => rrset.name(),
+        }
+    }
+
+    /// Returns true if `other` can never hold at the same time as this prerequisite
+    fn contradicts(&self, other: &Self) -> bool {
+        use Prerequisite::*;
+
+        if !self.name().eq_ignore_root(other.name()) {
+            return false;
+        }
+
+        let exists = |prerequisite: &Self| match prerequisite {
+            RrsetExists(_, record_type) | RrsetNotExists(_, record_type) => Some(*record_type),
+            RrsetEquals(rrset) => Some(rrset.record_type()),
+            NameInUse(_) | NameNotInUse(_) => None,
+        };
+
+        match (self, other) {
+            (NameInUse(_), NameNotInUse(_)) | (NameNotInUse(_), NameInUse(_)) => true,
+            (RrsetExists(..) | RrsetEquals(_), NameNotInUse(_))
+            | (NameNotInUse(_), RrsetExists(..) | RrsetEquals(_)) => true,
+            (RrsetExists(..) | RrsetEquals(_), RrsetNotExists(..))
+            | (RrsetNotExists(..), RrsetExists(..) | RrsetEquals(_)) => {
+                exists(self) == exists(other)
+            }
+            _ => false,
+        }
+    }
+
+    /// Appends the records encoding this prerequisite for a zone of `dns_class`
+    fn push_records(self, dns_class: DNSClass, records: &mut Vec<Record>) {
+        let (name, record_type, class) = match self {
+            Self::RrsetExists(name, record_type) => (name, record_type, DNSClass::ANY),
+            Self::RrsetNotExists(name, record_type) => (name, record_type, DNSClass::NONE),
+            Self::NameInUse(name) => (name, RecordType::ANY, DNSClass::ANY),
+            Self::NameNotInUse(name) => (name, RecordType::ANY, DNSClass::NONE),
+            Self::RrsetEquals(mut rrset) => {
+                // the TTL must be zero, and is ignored in the comparison
+                rrset.set_dns_class(dns_class);
+                rrset.set_ttl(0);
+                records.extend(rrset);
+                return;
+            }
+        };
+
+        let mut record = Record::update0(name, 0, record_type);
+        record.set_dns_class(class);
+        records.push(record);
+    }
+}
+
+/// Requires that at least one record with `name` and `record_type` exists, RFC 2136 2.4.1
+pub fn rr_exists(name: Name, record_type: RecordType) -> Prerequisite {
+    Prerequisite::RrsetExists(name, record_type)
+}
+
+/// Requires that the RRset of `rrset` exists with exactly the same records, RFC 2136 2.4.2
+pub fn rrset_equals(rrset: impl Into<RecordSet>) -> Prerequisite {
+    Prerequisite::RrsetEquals(rrset.into())
+}
+
+/// Requires that no record with `name` and `record_type` exists, RFC 2136 2.4.3
+pub fn rr_not_exists(name: Name, record_type: RecordType) -> Prerequisite {
+    Prerequisite::RrsetNotExists(name, record_type)
+}
+
+/// Requires that at least one record with `name` exists, RFC 2136 2.4.4
+pub fn name_in_use(name: Name) -> Prerequisite {
+    Prerequisite::NameInUse(name)
+}
+
+/// Requires that no record with `name` exists, RFC 2136 2.4.5
+pub fn name_not_in_use(name: Name) -> Prerequisite {
+    Prerequisite::NameNotInUse(name)
+}
+
+/// A change to the records of the zone, in the update section of a dynamic update
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Change {
+    /// Add the records to their RRset, 2.5.1
+    Add(RecordSet),
+    /// Delete the whole RRset, 2.5.2
+    DeleteRrset(Name, RecordType),
+    /// Delete all RRsets of the name, 2.5.3
+    DeleteAll(Name),
+    /// Delete the records from their RRset, 2.5.4
+    Delete(RecordSet),
+}
+
+impl Change {
+    fn name(&self) -> &Name {
+        match self {
+            Self::Add(rrset) | Self::Delete(rrset) => rrset.name(),
+            Self::DeleteRrset(name, _) | Self::DeleteAll(name) => name,
+        }
+    }
+
+    /// Appends the records encoding this change for a zone of `dns_class`
+    fn push_records(self, dns_class: DNSClass, records: &mut Vec<Record>) {
+        let (name, record_type) = match self {
+            Self::Add(mut rrset) => {
+                rrset.set_dns_class(dns_class);
+                records.extend(rrset);
+                return;
+            }
+            Self::Delete(mut rrset) => {
+                // the class must be none to delete a record, and the TTL should be 0
+                rrset.set_dns_class(DNSClass::NONE);
+                rrset.set_ttl(0);
+                records.extend(rrset);
+                return;
+            }
+            Self::DeleteRrset(name, record_type) => (name, record_type),
+            Self::DeleteAll(name) => (name, RecordType::ANY),
+        };
+
+        // the class must be any to delete rrsets, without rdata
+        let mut record = Record::update0(name, 0, record_type);
+        record.set_dns_class(DNSClass::ANY);
+        records.push(record);
+    }
+}
+
+/// Assembles a dynamic update of a zone, [RFC 2136](https://tools.ietf.org/html/rfc2136)
+///
+/// The zone, prerequisite and update sections are filled in from the operations, and their
+/// consistency is checked by [`UpdateBuilder::build`]. This replaces the manual construction
+/// of update messages, and allows several changes to be applied atomically.
+///
+/// ```
+/// use std::str::FromStr;
+///
+/// use hickory_client::client::{UpdateBuilder, rr_not_exists};
+/// use hickory_client::proto::op::UpdateMessage;
+/// use hickory_client::proto::rr::{Name, RData, Record, RecordType, rdata::A};
+///
+/// let zone = Name::from_str("example.com.").unwrap();
+/// let www = Name::from_str("www.example.com.").unwrap();
+///
+/// let request = UpdateBuilder::zone(zone)
+///     .prerequisite(rr_not_exists(www.clone(), RecordType::AAAA))
+///     .delete_rrset(www.clone(), RecordType::A)
+///     .add(Record::from_rdata(www, 300, RData::A(A::new(192, 0, 2, 1))))
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(request.prerequisites().len(), 1);
+/// assert_eq!(request.updates().len(), 2);
+/// ```
+#[derive(Clone)]
+pub struct UpdateBuilder {
+    zone: Name,
+    dns_class: DNSClass,
+    prerequisites: Vec<Prerequisite>,
+    changes: Vec<Change>,
+    use_edns: bool,
+    signer: Option<Arc<dyn MessageSigner>>,
+}
+
+impl UpdateBuilder {
+    /// Starts an update of the zone `zone`, i.e. the name of its SOA record, in the IN class
+    pub fn zone(zone: Name) -> Self {
+        Self {
+            zone,
+            dns_class: DNSClass::IN,
+            prerequisites: Vec::new(),
+            changes: Vec::new(),
+            use_edns: true,
+            signer: None,
+        }
+    }
+
+    /// Sets the class of the zone, IN by default
+    pub fn dns_class(mut self, dns_class: DNSClass) -> Self {
+        self.dns_class = dns_class;
+        self
+    }
+
+    /// Adds a prerequisite which must hold for the update to be applied
+    ///
+    /// See [`rr_exists`], [`rrset_equals`], [`rr_not_exists`], [`name_in_use`] and
+    /// [`name_not_in_use`].
+    pub fn prerequisite(mut self, prerequisite: Prerequisite) -> Self {
+        self.prerequisites.push(prerequisite);
+        self
+    }
+
+    /// Adds the record(s) to their RRset, creating it if necessary, RFC 2136 2.5.1
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, rrset: impl Into<RecordSet>) -> Self {
+        self.changes.push(Change::Add(rrset.into()));
+        self
+    }
+
+    /// Deletes the RRset with `name` and `record_type`, RFC 2136 2.5.2
+    pub fn delete_rrset(mut self, name: Name, record_type: RecordType) -> Self {
+        self.changes.push(Change::DeleteRrset(name, record_type));
+        self
+    }
+
+    /// Deletes all RRsets with `name`, RFC 2136 2.5.3
+    pub fn delete_all(mut self, name: Name) -> Self {
+        self.changes.push(Change::DeleteAll(name));
+        self
+    }
+
+    /// Deletes the record(s) with matching rdata from their RRset, RFC 2136 2.5.4
+    pub fn delete(mut self, rrset: impl Into<RecordSet>) -> Self {
+        self.changes.push(Change::Delete(rrset.into()));
+        self
+    }
+
+    /// Adds EDNS options to the request if true, the default
+    pub fn use_edns(mut self, use_edns: bool) -> Self {
+        self.use_edns = use_edns;
+        self
+    }
+
+    /// Signs the request with `signer`, e.g. a TSIG or SIG(0) signer
+    ///
+    /// This takes precedence over the signer of the connection the request is sent on.
+    pub fn sign(mut self, signer: Arc<dyn MessageSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Checks the update and assembles the request
+    ///
+    /// Fails if a record is not in the zone, is of another class than the zone, if an RRset
+    /// required to exist with given records is empty, or if two prerequisites contradict each
+    /// other such that the update could never be applied.
+    pub fn build(self) -> Result<DnsRequest, ProtoError> {
+        let Self {
+            zone,
+            dns_class,
+            prerequisites,
+            changes,
+            use_edns,
+            signer,
+        } = self;
+
+        let names = prerequisites
+            .iter()
+            .map(Prerequisite::name)
+            .chain(changes.iter().map(Change::name));
+        for name in names {
+            if !zone.zone_of(name) {
+                return Err(format!("{name} is not in the zone {zone}").into());
+            }
+        }
+
+        let rrsets = prerequisites
+            .iter()
+            .filter_map(|prerequisite| match prerequisite {
+                Prerequisite::RrsetEquals(rrset) => Some(rrset),
+                _ => None,
+            })
+            .chain(changes.iter().filter_map(|change| match change {
+                Change::Add(rrset) | Change::Delete(rrset) => Some(rrset),
+                _ => None,
+            }));
+        for rrset in rrsets {
+            if rrset.is_empty() {
+                return Err(format!(
+                    "the {} RRset of {} has no records",
+                    rrset.record_type(),
+                    rrset.name()
+                )
+                .into());
+            }
+
+            if rrset.dns_class() != dns_class {
+                return Err(format!(
+                    "the {} RRset of {} is of class {}, not the zone class {dns_class}",
+                    rrset.record_type(),
+                    rrset.name(),
+                    rrset.dns_class()
+                )
+                .into());
+            }
+        }
+
+        for (i, prerequisite) in prerequisites.iter().enumerate() {
+            if let Some(other) = prerequisites[i + 1..]
+                .iter()
+                .find(|other| prerequisite.contradicts(other))
+            {
+                return Err(
+                    format!("contradicting prerequisites {prerequisite:?} and {other:?}").into(),
+                );
+            }
+        }
+
+        // for updates, the query section is used for the zone
+        let mut query = Query::new();
+        query
+            .set_name(zone)
+            .set_query_class(dns_class)
+            .set_query_type(RecordType::SOA);
+
+        let mut message = Message::query();
+        message
+            .set_op_code(OpCode::Update)
+            .set_recursion_desired(false)
+            .add_query(query);
+
+        let mut records = Vec::new();
+        for prerequisite in prerequisites {
+            prerequisite.push_records(dns_class, &mut records);
+        }
+        message.add_answers(records);
+
+        let mut records = Vec::new();
+        for change in changes {
+            change.push_records(dns_class, &mut records);
+        }
+        message.add_name_servers(records);
+
+        if use_edns {
+            message
+                .extensions_mut()
+                .get_or_insert_with(Edns::new)
+                .set_max_payload(MAX_PAYLOAD_LEN)
+                .set_version(0);
+        }
+
+        Ok(DnsRequest::from(message).with_signer(signer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use hickory_proto::op::UpdateMessage;
+    use hickory_proto::rr::{RData, rdata::A};
+
+    use super::*;
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn a_record(owner: &str, ip: u8) -> Record {
+        Record::from_rdata(name(owner), 300, RData::A(A::new(192, 0, 2, ip)))
+    }
+
+    #[test]
+    fn test_sections() {
+        let request = UpdateBuilder::zone(name("example.com."))
+            .prerequisite(rr_exists(name("www.example.com."), RecordType::A))
+            .prerequisite(name_not_in_use(name("new.example.com.")))
+            .prerequisite(rrset_equals(a_record("old.example.com.", 1)))
+            .delete_rrset(name("www.example.com."), RecordType::A)
+            .add(a_record("www.example.com.", 2))
+            .delete(a_record("old.example.com.", 1))
+            .delete_all(name("gone.example.com."))
+            .build()
+            .unwrap();
+
+        assert_eq!(request.op_code(), OpCode::Update);
+        assert!(!request.recursion_desired());
+        assert_eq!(request.zones()[0].name(), &name("example.com."));
+        assert_eq!(request.zones()[0].query_type(), RecordType::SOA);
+        assert!(request.extensions().is_some());
+
+        let classes = |records: &[Record]| {
+            records
+                .iter()
+                .map(|r| (r.record_type(), r.dns_class(), r.ttl()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            classes(request.prerequisites()),
+            [
+                (RecordType::A, DNSClass::ANY, 0),
+                (RecordType::ANY, DNSClass::NONE, 0),
+                (RecordType::A, DNSClass::IN, 0),
+            ]
+        );
+        assert_eq!(
+            classes(request.updates()),
+            [
+                (RecordType::A, DNSClass::ANY, 0),
+                (RecordType::A, DNSClass::IN, 300),
+                (RecordType::A, DNSClass::NONE, 0),
+                (RecordType::ANY, DNSClass::ANY, 0),
+            ]
+        );
+        assert_eq!(request.updates()[0].data(), &RData::Update0(RecordType::A));
+        assert_eq!(request.updates()[2].data(), a_record("x.", 1).data());
+
+        // the sections survive the round trip
+        let bytes = request.to_vec().unwrap();
+        let message = Message::from_vec(&bytes).unwrap();
+        assert_eq!(message.prerequisites(), request.prerequisites());
+        assert_eq!(message.updates(), request.updates());
+    }
+
+    #[test]
+    fn test_out_of_zone() {
+        assert!(
+            UpdateBuilder::zone(name("example.com."))
+                .add(a_record("www.example.net.", 1))
+                .build()
+                .is_err()
+        );
+        assert!(
+            UpdateBuilder::zone(name("example.com."))
+                .prerequisite(name_in_use(name("example.org.")))
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_class() {
+        let mut record = a_record("www.example.com.", 1);
+        record.set_dns_class(DNSClass::CH);
+        assert!(
+            UpdateBuilder::zone(name("example.com."))
+                .add(record.clone())
+                .build()
+                .is_err()
+        );
+        assert!(
+            UpdateBuilder::zone(name("example.com."))
+                .dns_class(DNSClass::CH)
+                .add(record)
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_contradicting_prerequisites() {
+        let www = || name("www.example.com.");
+        let contradicting = [
+            (name_in_use(www()), name_not_in_use(www())),
+            (rr_exists(www(), RecordType::A), name_not_in_use(www())),
+            (
+                rr_exists(www(), RecordType::A),
+                rr_not_exists(www(), RecordType::A),
+            ),
+            (
+                rr_not_exists(www(), RecordType::A),
+                rrset_equals(a_record("www.example.com.", 1)),
+            ),
+        ];
+
+        for (first, second) in contradicting {
+            assert!(
+                UpdateBuilder::zone(name("example.com."))
+                    .prerequisite(first)
+                    .prerequisite(second)
+                    .build()
+                    .is_err()
+            );
+        }
+
+        // other types or names don't contradict
+        assert!(
+            UpdateBuilder::zone(name("example.com."))
+                .prerequisite(rr_exists(www(), RecordType::A))
+                .prerequisite(rr_not_exists(www(), RecordType::AAAA))
+                .prerequisite(name_not_in_use(name("new.example.com.")))
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_empty_rrset() {
+        let rrset = RecordSet::new(name("www.example.com."), RecordType::A, 0);
+        assert!(
+            UpdateBuilder::zone(name("example.com."))
+                .prerequisite(rrset_equals(rrset))
+                .build()
+                .is_err()
+        );
+    }
+}
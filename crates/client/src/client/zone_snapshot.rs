@@ -0,0 +1,636 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A snapshot of the records of a zone, as received in a zone transfer
+
+use std::collections::{BTreeMap, btree_map::Entry};
+
+use hickory_proto::{
+    ProtoError, ProtoErrorKind,
+    rr::{LowerName, Name, Record, RecordSet, RecordType, RrKey, rdata::SOA},
+};
+
+/// The records of a zone, indexed by name and type
+///
+/// A snapshot is built from the records of an AXFR, see [`ClientStreamXfr::snapshot`], which
+/// are bracketed by the SOA record of the zone.
+///
+/// [`ClientStreamXfr::snapshot`]: super::client::ClientStreamXfr::snapshot
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZoneSnapshot {
+    origin: Name,
+    soa: SOA,
+    rrsets: BTreeMap<RrKey, RecordSet>,
+}
+
+impl ZoneSnapshot {
+    /// Builds the snapshot from the records of an AXFR, in order
+    ///
+    /// [RFC 5936](https://tools.ietf.org/html/rfc5936), DNS Zone Transfer Protocol (AXFR)
+    ///
+    /// ```text
+    /// 2.2.  AXFR Response
+    ///
+    ///    The AXFR response will consist of one or more messages.  The first
+    ///    message MUST begin with the SOA resource record of the zone, and the
+    ///    last message MUST conclude with the same SOA resource record.
+    ///    Intermediate messages MUST NOT contain the SOA resource record.
+    /// ```
+    ///
+    /// Fails if the records are not bracketed by the same SOA, contain another SOA, or records
+    /// outside of the zone.
+    pub fn from_axfr(records: impl IntoIterator<Item = Record>) -> Result<Self, ProtoError> {
+        let mut records = records.into_iter();
+
+        let first = records.next().ok_or(ProtoErrorKind::Message(
+            "invalid zone transfer, contains no records",
+        ))?;
+        let origin = first.name().clone();
+        let soa = first
+            .data()
+            .as_soa()
+            .ok_or(ProtoErrorKind::Message(
+                "invalid zone transfer, does not start with a SOA",
+            ))?
+            .clone();
+
+        let mut snapshot = Self {
+            origin,
+            soa,
+            rrsets: BTreeMap::new(),
+        };
+        snapshot.insert_unchecked(first);
+
+        let mut ended = false;
+        for record in records {
+            if ended {
+                return Err(ProtoErrorKind::Message(
+                    "invalid zone transfer, contains trailing records",
+                )
+                .into());
+            }
+
+            if let Some(soa) = record.data().as_soa() {
+                if record.name() != &snapshot.origin || soa != &snapshot.soa {
+                    return Err(ProtoErrorKind::Message(
+                        "invalid zone transfer, ends with a different SOA",
+                    )
+                    .into());
+                }
+
+                ended = true;
+                continue;
+            }
+
+            snapshot.insert(record)?;
+        }
+
+        if !ended {
+            return Err(
+                ProtoErrorKind::Message("invalid zone transfer, does not end with a SOA").into(),
+            );
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Returns the name of the zone
+    pub fn origin(&self) -> &Name {
+        &self.origin
+    }
+
+    /// Returns the SOA of the zone
+    pub fn soa(&self) -> &SOA {
+        &self.soa
+    }
+
+    /// Returns the serial of the SOA of the zone
+    pub fn serial(&self) -> u32 {
+        self.soa.serial()
+    }
+
+    /// Returns the RRset with `name` and `record_type`, if any
+    pub fn get(&self, name: &Name, record_type: RecordType) -> Option<&RecordSet> {
+        self.rrsets
+            .get(&RrKey::new(LowerName::new(name), record_type))
+    }
+
+    /// Returns all RRsets of the zone, ordered by name and type
+    pub fn rrsets(&self) -> impl Iterator<Item = &RecordSet> + '_ {
+        self.rrsets.values()
+    }
+
+    /// Returns all records of the zone, ordered by name and type
+    pub fn records(&self) -> impl Iterator<Item = &Record> + '_ {
+        self.rrsets
+            .values()
+            .flat_map(RecordSet::records_without_rrsigs)
+    }
+
+    /// Returns the number of RRsets in the zone
+    pub fn len(&self) -> usize {
+        self.rrsets.len()
+    }
+
+    /// Returns true if the zone has no RRsets, which is never the case for a transferred zone
+    pub fn is_empty(&self) -> bool {
+        self.rrsets.is_empty()
+    }
+
+    /// Applies the records of an IXFR, in order, and returns the new serial of the zone
+    ///
+    /// The snapshot is replaced if the server fell back to an AXFR, and left unchanged if it is
+    /// up to date. See [`IncrementalTransfer::from_ixfr`] for the validation of the records.
+    pub fn apply_ixfr(
+        &mut self,
+        records: impl IntoIterator<Item = Record>,
+    ) -> Result<u32, ProtoError> {
+        match IncrementalTransfer::from_ixfr(records)? {
+            IncrementalTransfer::UpToDate(soa) => {
+                if soa.serial() != self.serial() {
+                    return Err(ProtoErrorKind::Message(
+                        "invalid zone transfer, single SOA is not the current version",
+                    )
+                    .into());
+                }
+            }
+            IncrementalTransfer::Full(snapshot) => {
+                if !snapshot.origin.eq_ignore_root(&self.origin) {
+                    return Err(ProtoErrorKind::Message(
+                        "invalid zone transfer, transferred another zone",
+                    )
+                    .into());
+                }
+
+                *self = snapshot;
+            }
+            IncrementalTransfer::Diffs(diffs) => {
+                // the diffs are applied to a copy, a failure leaves the snapshot unchanged
+                let mut updated = self.clone();
+                for diff in &diffs {
+                    updated.apply(diff)?;
+                }
+
+                *self = updated;
+            }
+        }
+
+        Ok(self.serial())
+    }
+
+    /// Applies a single difference between two versions of the zone
+    ///
+    /// Fails if the difference doesn't start at the current serial of the zone, deletes a record
+    /// not in the zone, or adds a record outside of the zone.
+    pub fn apply(&mut self, diff: &ZoneDiff) -> Result<(), ProtoError> {
+        if diff.from_serial() != self.serial() {
+            return Err(ProtoErrorKind::Message(
+                "invalid zone transfer, difference does not start at the current serial",
+            )
+            .into());
+        }
+
+        for record in &diff.deleted {
+            let key = RrKey::new(LowerName::new(record.name()), record.record_type());
+            let Some(rrset) = self.rrsets.get_mut(&key) else {
+                return Err(ProtoErrorKind::Message(
+                    "invalid zone transfer, deleted record is not in the zone",
+                )
+                .into());
+            };
+
+            if !rrset.remove(record, 0) {
+                return Err(ProtoErrorKind::Message(
+                    "invalid zone transfer, deleted record is not in the zone",
+                )
+                .into());
+            }
+
+            if rrset.is_empty() {
+                self.rrsets.remove(&key);
+            }
+        }
+
+        for record in &diff.added {
+            self.insert(record.clone())?;
+        }
+
+        // the SOA can't be inserted into its RRset, as serials may wrap around
+        let soa = diff.to.clone();
+        self.soa = soa
+            .data()
+            .as_soa()
+            .expect("ZoneDiff always ends with a SOA")
+            .clone();
+        self.rrsets.insert(
+            RrKey::new(LowerName::new(soa.name()), RecordType::SOA),
+            RecordSet::from(soa),
+        );
+
+        Ok(())
+    }
+
+    /// Inserts a record, which must be in the zone
+    fn insert(&mut self, record: Record) -> Result<(), ProtoError> {
+        if !self.origin.zone_of(record.name()) {
+            return Err(ProtoErrorKind::Message(
+                "invalid zone transfer, record outside of the zone",
+            )
+            .into());
+        }
+
+        self.insert_unchecked(record);
+        Ok(())
+    }
+
+    fn insert_unchecked(&mut self, record: Record) {
+        let key = RrKey::new(LowerName::new(record.name()), record.record_type());
+        match self.rrsets.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().insert(record, 0);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(RecordSet::from(record));
+            }
+        }
+    }
+}
+
+/// The response to an IXFR query
+///
+/// [RFC 1995](https://tools.ietf.org/html/rfc1995), Incremental Zone Transfer in DNS
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IncrementalTransfer {
+    /// The version of the client is current, only the SOA of the zone was returned
+    UpToDate(SOA),
+    /// The server fell back to a transfer of the whole zone
+    Full(ZoneSnapshot),
+    /// The differences from the version of the client to the current version, in order
+    Diffs(Vec<ZoneDiff>),
+}
+
+impl IncrementalTransfer {
+    /// Parses the records of an IXFR, in order
+    ///
+    /// ```text
+    /// 4. Response Format
+    ///
+    ///    If incremental zone transfer is available, one or more difference
+    ///    sequences is returned.  The list of difference sequences is preceded
+    ///    and followed by a copy of the server's current version of the SOA.
+    ///
+    ///    Each difference sequence represents one update to the zone (one SOA
+    ///    serial change) consisting of deleted RRs and added RRs.  The first RR
+    ///    of the deleted RRs is the older SOA RR and the first RR of the added
+    ///    RRs is the newer SOA RR.
+    ///
+    ///    If incremental zone transfer is not available, the entire zone is
+    ///    returned.  The first and the last RR of the response is the SOA
+    ///    record of the zone.  I.e. the behavior is the same as an AXFR
+    ///    response except the query type is IXFR.
+    ///
+    ///    If the client's SOA is up to date, the response consists of a single
+    ///    SOA record.
+    /// ```
+    ///
+    /// The response is a full transfer if its second record is not a SOA, or if it only consists
+    /// of the same SOA twice, which is the AXFR of an empty zone.
+    pub fn from_ixfr(records: impl IntoIterator<Item = Record>) -> Result<Self, ProtoError> {
+        let records = records.into_iter().collect::<Vec<_>>();
+
+        let current = match records.first().map(|r| r.data().as_soa()) {
+            Some(Some(soa)) => soa.clone(),
+            Some(None) => {
+                return Err(ProtoErrorKind::Message(
+                    "invalid zone transfer, does not start with a SOA",
+                )
+                .into());
+            }
+            None => {
+                return Err(
+                    ProtoErrorKind::Message("invalid zone transfer, contains no records").into(),
+                );
+            }
+        };
+
+        let is_full = match records.get(1).map(|r| r.data().as_soa()) {
+            None => return Ok(Self::UpToDate(current)),
+            Some(None) => true,
+            Some(Some(soa)) => records.len() == 2 && soa == &current,
+        };
+        if is_full {
+            return ZoneSnapshot::from_axfr(records).map(Self::Full);
+        }
+
+        let origin = records[0].name().clone();
+        let mut records = records.into_iter().skip(1).peekable();
+        let mut diffs = Vec::new();
+        loop {
+            let from = records
+                .next()
+                .filter(|r| r.record_type() == RecordType::SOA && r.name() == &origin)
+                .ok_or(ProtoErrorKind::Message(
+                    "invalid zone transfer, expected the SOA of a difference",
+                ))?;
+
+            // the final copy of the current SOA
+            if records.peek().is_none() {
+                if from.data().as_soa() != Some(&current) {
+                    return Err(ProtoErrorKind::Message(
+                        "invalid zone transfer, ends with a different SOA",
+                    )
+                    .into());
+                }
+
+                break;
+            }
+
+            let mut deleted = Vec::new();
+            while let Some(record) = records.next_if(|r| r.record_type() != RecordType::SOA) {
+                deleted.push(record);
+            }
+
+            let to =
+                records
+                    .next()
+                    .filter(|r| r.name() == &origin)
+                    .ok_or(ProtoErrorKind::Message(
+                        "invalid zone transfer, difference has no new SOA",
+                    ))?;
+
+            let mut added = Vec::new();
+            while let Some(record) = records.next_if(|r| r.record_type() != RecordType::SOA) {
+                added.push(record);
+            }
+
+            diffs.push(ZoneDiff {
+                from,
+                to,
+                deleted,
+                added,
+            });
+        }
+
+        Ok(Self::Diffs(diffs))
+    }
+}
+
+/// A difference sequence of an IXFR, changing the zone from one serial to the next
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZoneDiff {
+    from: Record,
+    to: Record,
+    deleted: Vec<Record>,
+    added: Vec<Record>,
+}
+
+impl ZoneDiff {
+    /// Returns the serial of the zone before this difference
+    pub fn from_serial(&self) -> u32 {
+        self.from
+            .data()
+            .as_soa()
+            .map(SOA::serial)
+            .unwrap_or_default()
+    }
+
+    /// Returns the serial of the zone after this difference
+    pub fn to_serial(&self) -> u32 {
+        self.to.data().as_soa().map(SOA::serial).unwrap_or_default()
+    }
+
+    /// Returns the records deleted from the zone, without the SOA
+    pub fn deleted(&self) -> &[Record] {
+        &self.deleted
+    }
+
+    /// Returns the records added to the zone, without the SOA
+    pub fn added(&self) -> &[Record] {
+        &self.added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hickory_proto::rr::{
+        RData,
+        rdata::{A, NS},
+    };
+
+    use super::*;
+
+    fn name(name: &str) -> Name {
+        Name::from_ascii(name).unwrap()
+    }
+
+    fn soa_record(serial: u32) -> Record {
+        let soa = SOA::new(
+            name("example.com."),
+            name("admin.example.com."),
+            serial,
+            60,
+            60,
+            60,
+            60,
+        );
+        Record::from_rdata(name("example.com."), 600, RData::SOA(soa))
+    }
+
+    fn a_record(owner: &str, ip: u8) -> Record {
+        Record::from_rdata(name(owner), 600, RData::A(A::new(192, 0, 2, ip)))
+    }
+
+    #[test]
+    fn test_from_axfr() {
+        let ns = Record::from_rdata(
+            name("example.com."),
+            600,
+            RData::NS(NS(name("ns.example.com."))),
+        );
+        let snapshot = ZoneSnapshot::from_axfr([
+            soa_record(7),
+            ns.clone(),
+            a_record("www.example.com.", 1),
+            a_record("WWW.example.com.", 2),
+            a_record("ns.example.com.", 3),
+            soa_record(7),
+        ])
+        .unwrap();
+
+        assert_eq!(snapshot.origin(), &name("example.com."));
+        assert_eq!(snapshot.serial(), 7);
+        assert_eq!(snapshot.len(), 4);
+        assert_eq!(snapshot.records().count(), 5);
+
+        let www = snapshot
+            .get(&name("www.example.com."), RecordType::A)
+            .unwrap();
+        assert_eq!(www.records_without_rrsigs().count(), 2);
+        assert_eq!(
+            snapshot
+                .get(&name("example.com."), RecordType::NS)
+                .unwrap()
+                .records_without_rrsigs()
+                .collect::<Vec<_>>(),
+            [&ns]
+        );
+        assert!(
+            snapshot
+                .get(&name("www.example.com."), RecordType::AAAA)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_invalid_axfr() {
+        // no records, or no SOA to start
+        assert!(ZoneSnapshot::from_axfr([]).is_err());
+        assert!(ZoneSnapshot::from_axfr([a_record("www.example.com.", 1), soa_record(1)]).is_err());
+
+        // no SOA to end, or a different one
+        assert!(ZoneSnapshot::from_axfr([soa_record(1), a_record("www.example.com.", 1)]).is_err());
+        assert!(ZoneSnapshot::from_axfr([soa_record(1), soa_record(2)]).is_err());
+
+        // trailing records, or records outside of the zone
+        assert!(
+            ZoneSnapshot::from_axfr([
+                soa_record(1),
+                soa_record(1),
+                a_record("www.example.com.", 1)
+            ])
+            .is_err()
+        );
+        assert!(
+            ZoneSnapshot::from_axfr([
+                soa_record(1),
+                a_record("www.example.net.", 1),
+                soa_record(1)
+            ])
+            .is_err()
+        );
+
+        // an empty zone only has the SOA
+        let snapshot = ZoneSnapshot::from_axfr([soa_record(1), soa_record(1)]).unwrap();
+        assert_eq!(snapshot.len(), 1);
+    }
+
+    fn zone(serial: u32) -> ZoneSnapshot {
+        ZoneSnapshot::from_axfr([
+            soa_record(serial),
+            a_record("www.example.com.", 1),
+            a_record("www.example.com.", 2),
+            a_record("old.example.com.", 3),
+            soa_record(serial),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_apply_ixfr() {
+        let mut snapshot = zone(1);
+        let serial = snapshot
+            .apply_ixfr([
+                soa_record(3),
+                soa_record(1),
+                a_record("www.example.com.", 2),
+                a_record("old.example.com.", 3),
+                soa_record(2),
+                a_record("new.example.com.", 4),
+                soa_record(2),
+                soa_record(3),
+                a_record("www.example.com.", 5),
+                soa_record(3),
+            ])
+            .unwrap();
+
+        assert_eq!(serial, 3);
+        assert_eq!(snapshot.serial(), 3);
+        let www = snapshot
+            .get(&name("www.example.com."), RecordType::A)
+            .unwrap();
+        assert_eq!(www.records_without_rrsigs().count(), 2);
+        assert!(
+            snapshot
+                .get(&name("old.example.com."), RecordType::A)
+                .is_none()
+        );
+        assert!(
+            snapshot
+                .get(&name("new.example.com."), RecordType::A)
+                .is_some()
+        );
+        assert_eq!(
+            snapshot
+                .get(&name("example.com."), RecordType::SOA)
+                .unwrap()
+                .records_without_rrsigs()
+                .next(),
+            Some(&soa_record(3))
+        );
+    }
+
+    #[test]
+    fn test_apply_ixfr_fallback() {
+        // up to date
+        let mut snapshot = zone(1);
+        assert_eq!(snapshot.apply_ixfr([soa_record(1)]).unwrap(), 1);
+        assert_eq!(snapshot, zone(1));
+
+        // AXFR fallback
+        let serial = snapshot
+            .apply_ixfr([
+                soa_record(4),
+                a_record("www.example.com.", 9),
+                soa_record(4),
+            ])
+            .unwrap();
+        assert_eq!(serial, 4);
+        assert_eq!(snapshot.len(), 2);
+
+        // empty zone
+        assert!(matches!(
+            IncrementalTransfer::from_ixfr([soa_record(4), soa_record(4)]).unwrap(),
+            IncrementalTransfer::Full(_)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_ixfr() {
+        // the difference doesn't start at the current version
+        let mut snapshot = zone(1);
+        assert!(
+            snapshot
+                .apply_ixfr([soa_record(3), soa_record(2), soa_record(3), soa_record(3)])
+                .is_err()
+        );
+
+        // the deleted record doesn't exist, and the snapshot is left unchanged
+        assert!(
+            snapshot
+                .apply_ixfr([
+                    soa_record(2),
+                    soa_record(1),
+                    a_record("www.example.com.", 7),
+                    soa_record(2),
+                    soa_record(2),
+                ])
+                .is_err()
+        );
+        assert_eq!(snapshot, zone(1));
+
+        // missing the final SOA
+        assert!(
+            IncrementalTransfer::from_ixfr([
+                soa_record(2),
+                soa_record(1),
+                soa_record(2),
+                a_record("www.example.com.", 7),
+            ])
+            .is_err()
+        );
+    }
+}
@@ -138,6 +138,9 @@
 //!
 //! Currently `hickory-client` supports SIG(0) signed records for authentication and authorization
 //! of dynamic DNS updates. Consult the [`client::DnssecClient`] API for more information.
+//!
+//! Updates combining several changes and prerequisites are assembled with the
+//! [`client::UpdateBuilder`], which can also sign each update with its own TSIG or SIG(0) signer.
 
 pub mod client;
 #[cfg(test)]
@@ -4,7 +4,8 @@
 extern crate test;
 
 use hickory_proto::op::{Header, Message, MessageType, OpCode, ResponseCode};
-use hickory_proto::rr::{Name, Record, RecordType};
+use hickory_proto::rr::rdata::{A, AAAA, MX, SOA, SRV, TXT};
+use hickory_proto::rr::{Name, RData, Record, RecordType};
 use hickory_proto::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder};
 
 use test::Bencher;
@@ -138,3 +139,56 @@ fn bench_parse_real_message(b: &mut Bencher) {
         assert!(Message::read(&mut decoder).is_ok());
     })
 }
+
+#[bench]
+fn bench_parse_rdata_message(b: &mut Bencher) {
+    let origin = Name::from_ascii("example.com.").unwrap();
+    let mut message = Message::response(10, OpCode::Query);
+    for i in 0..4u8 {
+        let name = Name::from_ascii(format!("host-{i}.example.com.")).unwrap();
+        message.add_answer(Record::from_rdata(
+            name.clone(),
+            300,
+            RData::A(A::new(192, 0, 2, i)),
+        ));
+        message.add_answer(Record::from_rdata(
+            name.clone(),
+            300,
+            RData::AAAA(AAAA::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, u16::from(i))),
+        ));
+        message.add_answer(Record::from_rdata(
+            origin.clone(),
+            300,
+            RData::MX(MX::new(u16::from(i), name.clone())),
+        ));
+        message.add_answer(Record::from_rdata(
+            origin.clone(),
+            300,
+            RData::SRV(SRV::new(1, 2, 443, name)),
+        ));
+        message.add_answer(Record::from_rdata(
+            origin.clone(),
+            300,
+            RData::TXT(TXT::new(vec!["v=spf1 -all".to_string(); 4])),
+        ));
+    }
+    message.add_name_server(Record::from_rdata(
+        origin.clone(),
+        3600,
+        RData::SOA(SOA::new(
+            Name::from_ascii("ns.example.com.").unwrap(),
+            Name::from_ascii("hostmaster.example.com.").unwrap(),
+            1,
+            7200,
+            3600,
+            1209600,
+            300,
+        )),
+    ));
+    let byte_vec = message.to_vec().unwrap();
+
+    b.iter(|| {
+        let mut decoder = BinDecoder::new(&byte_vec);
+        assert!(Message::read(&mut decoder).is_ok());
+    })
+}
@@ -0,0 +1,192 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! `Arbitrary` implementations for the DNSSEC record data
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::{bytes, vec_of};
+use crate::dnssec::rdata::{
+    CDNSKEY, CDS, DNSKEY, DNSSECRData, DS, KEY, NSEC, NSEC3, NSEC3PARAM, RRSIG, SIG, SigInput,
+    TKEY, TSIG, key, tkey, tsig,
+};
+use crate::dnssec::{Algorithm, DigestType, Nsec3HashAlgorithm, PublicKeyBuf, SupportedAlgorithms};
+use crate::rr::{Name, RecordType, SerialNumber};
+
+/// Keeps keys, digests and signatures short, their contents are not interpreted while decoding
+const MAX_KEY_LEN: usize = 128;
+
+/// Hashes and salts are prefixed by their length in a single byte
+const MAX_HASH_LEN: usize = u8::MAX as usize;
+
+/// Generates all record data except `Unknown`, unknown record types are never decoded as DNSSEC
+impl<'a> Arbitrary<'a> for DNSSECRData {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let rdata = match u.int_in_range(0..=11)? {
+            0 => Self::CDNSKEY(CDNSKEY::with_flags(
+                u16::arbitrary(u)?,
+                optional_algorithm(u)?,
+                bytes(u, MAX_KEY_LEN)?,
+            )),
+            1 => Self::CDS(CDS::new(
+                u16::arbitrary(u)?,
+                optional_algorithm(u)?,
+                DigestType::from(u8::arbitrary(u)?),
+                bytes(u, MAX_KEY_LEN)?,
+            )),
+            2 => Self::DNSKEY(DNSKEY::with_flags(
+                u16::arbitrary(u)?,
+                PublicKeyBuf::new(bytes(u, MAX_KEY_LEN)?, Algorithm::arbitrary(u)?),
+            )),
+            3 => Self::DS(DS::new(
+                u16::arbitrary(u)?,
+                Algorithm::arbitrary(u)?,
+                DigestType::from(u8::arbitrary(u)?),
+                bytes(u, MAX_KEY_LEN)?,
+            )),
+            4 => Self::KEY(KEY::arbitrary(u)?),
+            5 => Self::NSEC(NSEC::new(
+                Name::arbitrary(u)?,
+                vec_of(u, 8, RecordType::arbitrary)?,
+            )),
+            6 => Self::NSEC3(NSEC3::new(
+                Nsec3HashAlgorithm::SHA1,
+                bool::arbitrary(u)?,
+                u16::arbitrary(u)?,
+                bytes(u, MAX_HASH_LEN)?,
+                bytes(u, MAX_HASH_LEN)?,
+                vec_of(u, 8, RecordType::arbitrary)?,
+            )),
+            7 => Self::NSEC3PARAM(NSEC3PARAM::new(
+                Nsec3HashAlgorithm::SHA1,
+                bool::arbitrary(u)?,
+                u16::arbitrary(u)?,
+                bytes(u, MAX_HASH_LEN)?,
+            )),
+            8 => Self::RRSIG(RRSIG::from_sig(SIG::arbitrary(u)?)),
+            9 => Self::SIG(SIG::arbitrary(u)?),
+            10 => Self::TKEY(TKEY::arbitrary(u)?),
+            _ => Self::TSIG(TSIG::arbitrary(u)?),
+        };
+
+        Ok(rdata)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Algorithm {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::from_u8(u8::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for SupportedAlgorithms {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::from_vec(&vec_of(u, 8, Algorithm::arbitrary)?))
+    }
+}
+
+/// The algorithm of CDS and CDNSKEY records, where zero requests the removal of the delegation
+fn optional_algorithm(u: &mut Unstructured<'_>) -> Result<Option<Algorithm>> {
+    Ok(match u8::arbitrary(u)? {
+        0 => None,
+        value => Some(Algorithm::from_u8(value)),
+    })
+}
+
+impl<'a> Arbitrary<'a> for KEY {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // the reserved bits and the extended flags are rejected while decoding
+        let flags = u16::arbitrary(u)? & !0b0011_1100_1111_0000;
+
+        Ok(Self::new(
+            key::KeyTrust::from(flags),
+            key::KeyUsage::from(flags),
+            #[allow(deprecated)]
+            key::UpdateScope::from(flags),
+            key::Protocol::from(u8::arbitrary(u)?),
+            Algorithm::arbitrary(u)?,
+            bytes(u, MAX_KEY_LEN)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for SIG {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            input: SigInput {
+                type_covered: RecordType::arbitrary(u)?,
+                algorithm: Algorithm::arbitrary(u)?,
+                num_labels: u8::arbitrary(u)?,
+                original_ttl: u32::arbitrary(u)?,
+                sig_expiration: SerialNumber(u32::arbitrary(u)?),
+                sig_inception: SerialNumber(u32::arbitrary(u)?),
+                key_tag: u16::arbitrary(u)?,
+                signer_name: Name::arbitrary(u)?,
+            },
+            sig: bytes(u, MAX_KEY_LEN)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for TKEY {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // a zero error is decoded as none
+        Ok(Self::new(
+            tsig_algorithm(u)?,
+            u32::arbitrary(u)?,
+            u32::arbitrary(u)?,
+            tkey::TkeyMode::from(u16::arbitrary(u)?),
+            match u16::arbitrary(u)? {
+                0 => None,
+                error => Some(tsig::TsigError::from(error)),
+            },
+            bytes(u, MAX_KEY_LEN)?,
+            bytes(u, 16)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for TSIG {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // the time is sent as a 48 bit integer, and a zero error is decoded as none
+        Ok(Self::new(
+            tsig_algorithm(u)?,
+            u64::arbitrary(u)? & 0xffff_ffff_ffff,
+            u16::arbitrary(u)?,
+            bytes(u, MAX_KEY_LEN)?,
+            u16::arbitrary(u)?,
+            match u16::arbitrary(u)? {
+                0 => None,
+                error => Some(tsig::TsigError::from(error)),
+            },
+            bytes(u, 16)?,
+        ))
+    }
+}
+
+fn tsig_algorithm(u: &mut Unstructured<'_>) -> Result<tsig::TsigAlgorithm> {
+    use tsig::TsigAlgorithm::*;
+
+    Ok(match u.int_in_range(0..=10)? {
+        0 => HmacMd5,
+        1 => Gss,
+        2 => HmacSha1,
+        3 => HmacSha224,
+        4 => HmacSha256,
+        5 => HmacSha256_128,
+        6 => HmacSha384,
+        7 => HmacSha384_192,
+        8 => HmacSha512,
+        9 => HmacSha512_256,
+        _ => {
+            // algorithm names are decoded as relative names
+            let mut name = Name::arbitrary(u)?;
+            name.set_fqdn(false);
+            Unknown(name)
+        }
+    })
+}
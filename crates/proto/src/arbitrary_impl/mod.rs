@@ -0,0 +1,210 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! `Arbitrary` implementations for generating structured names, records and messages
+//!
+//! The generated values are always valid, i.e. they can be encoded and decode to an equal value,
+//! so that fuzz targets spend their time on the code under test instead of on input validation.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+use crate::op::{Edns, Message, MessageType, OpCode, Query, ResponseCode};
+use crate::rr::{DNSClass, Name, RData, Record, RecordType};
+
+#[cfg(feature = "__dnssec")]
+mod dnssec;
+#[cfg(feature = "proptest")]
+mod proptest;
+mod rdata;
+
+/// The most records generated for each section of a message
+const MAX_SECTION_LEN: usize = 3;
+
+impl<'a> Arbitrary<'a> for Name {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // four labels of at most 62 bytes, with their lengths and the root, stay below 255 bytes
+        let count = u.int_in_range(0..=4)?;
+        let mut labels = Vec::with_capacity(count);
+        for _ in 0..count {
+            let len = u.int_in_range(1..=62)?;
+            let label = u.bytes(len.min(u.len()))?;
+            if label.is_empty() {
+                break;
+            }
+
+            labels.push(label);
+        }
+
+        let mut name = Self::from_labels(labels).map_err(|_| Error::IncorrectFormat)?;
+        name.set_fqdn(true);
+        Ok(name)
+    }
+}
+
+impl<'a> Arbitrary<'a> for RecordType {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::from(u16::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for DNSClass {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // with mDNS the top bit of a query class is the unicast-response bit, not part of the class
+        Ok(Self::from(u16::arbitrary(u)? & 0x7FFF))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Query {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut query = Self::query(Name::arbitrary(u)?, RecordType::arbitrary(u)?);
+        query.set_query_class(DNSClass::arbitrary(u)?);
+        Ok(query)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Record {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let name = Name::arbitrary(u)?;
+        let ttl = u32::arbitrary(u)?;
+
+        // OPT records carry the EDNS payload size in place of the class, and are only valid in
+        //  the additional section of a message
+        let rdata = match RData::arbitrary(u)? {
+            RData::OPT(_) => RData::A(Arbitrary::arbitrary(u)?),
+            rdata => rdata,
+        };
+
+        let mut record = Self::from_rdata(name, ttl, rdata);
+        record.set_dns_class(*u.choose(&[DNSClass::IN, DNSClass::CH, DNSClass::HS])?);
+        Ok(record)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Edns {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut edns = Self::new();
+        edns.set_max_payload(u.int_in_range(512..=u16::MAX)?)
+            .set_version(u8::arbitrary(u)?)
+            .set_dnssec_ok(bool::arbitrary(u)?);
+        *edns.options_mut() = Arbitrary::arbitrary(u)?;
+        Ok(edns)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Message {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let message_type = *u.choose(&[MessageType::Query, MessageType::Response])?;
+        let op_code = OpCode::from_u8(u.int_in_range(0..=15)?);
+        let mut message = Self::new(u16::arbitrary(u)?, message_type, op_code);
+
+        message
+            .set_authoritative(bool::arbitrary(u)?)
+            .set_truncated(bool::arbitrary(u)?)
+            .set_recursion_desired(bool::arbitrary(u)?)
+            .set_recursion_available(bool::arbitrary(u)?)
+            .set_authentic_data(bool::arbitrary(u)?)
+            .set_checking_disabled(bool::arbitrary(u)?);
+
+        for _ in 0..u.int_in_range(0..=2)? {
+            message.add_query(Query::arbitrary(u)?);
+        }
+        for _ in 0..u.int_in_range(0..=MAX_SECTION_LEN)? {
+            message.add_answer(section_record(u)?);
+        }
+        for _ in 0..u.int_in_range(0..=MAX_SECTION_LEN)? {
+            message.add_name_server(section_record(u)?);
+        }
+        for _ in 0..u.int_in_range(0..=MAX_SECTION_LEN)? {
+            message.add_additional(section_record(u)?);
+        }
+
+        // the upper bits of the response code are only representable with EDNS
+        match bool::arbitrary(u)? {
+            true => {
+                let response_code = ResponseCode::from(u8::arbitrary(u)?, u8::arbitrary(u)?);
+                let mut edns = Edns::arbitrary(u)?;
+                edns.set_rcode_high(response_code.high());
+                message.set_response_code(response_code).set_edns(edns);
+            }
+            false => {
+                message.set_response_code(ResponseCode::from_low(u8::arbitrary(u)?));
+            }
+        }
+
+        Ok(message)
+    }
+}
+
+/// A record for the answer, authority or additional section of a message
+///
+/// Signatures of the whole message, SIG(0) and TSIG, are only valid as the last additional
+/// record, and are left out.
+fn section_record(u: &mut Unstructured<'_>) -> Result<Record> {
+    let record = Record::arbitrary(u)?;
+
+    #[cfg(feature = "__dnssec")]
+    if matches!(record.record_type(), RecordType::SIG | RecordType::TSIG) {
+        let mut replaced = Record::from_rdata(
+            record.name().clone(),
+            record.ttl(),
+            RData::A(Arbitrary::arbitrary(u)?),
+        );
+        replaced.set_dns_class(record.dns_class());
+        return Ok(replaced);
+    }
+
+    Ok(record)
+}
+
+/// Arbitrary bytes, at most `max` of them
+fn bytes(u: &mut Unstructured<'_>, max: usize) -> Result<Vec<u8>> {
+    let len = u.int_in_range(0..=max)?;
+    Ok(u.bytes(len.min(u.len()))?.to_vec())
+}
+
+/// Arbitrary bytes, at least one and at most `max` of them
+fn non_empty_bytes(u: &mut Unstructured<'_>, max: usize) -> Result<Vec<u8>> {
+    let mut bytes = bytes(u, max)?;
+    if bytes.is_empty() {
+        bytes.push(u8::arbitrary(u)?);
+    }
+
+    Ok(bytes)
+}
+
+/// An arbitrary string of at most `max` bytes
+fn string(u: &mut Unstructured<'_>, max: usize) -> Result<String> {
+    let mut string = String::arbitrary(u)?;
+    let mut len = string.len().min(max);
+    while !string.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    string.truncate(len);
+    Ok(string)
+}
+
+/// Up to `max` values generated by `f`
+fn vec_of<'a, T>(
+    u: &mut Unstructured<'a>,
+    max: usize,
+    mut f: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Vec<T>> {
+    (0..u.int_in_range(0..=max)?).map(|_| f(u)).collect()
+}
+
+/// At least one and up to `max` values generated by `f`
+fn non_empty_vec_of<'a, T>(
+    u: &mut Unstructured<'a>,
+    max: usize,
+    mut f: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Vec<T>> {
+    (0..u.int_in_range(1..=max)?).map(|_| f(u)).collect()
+}
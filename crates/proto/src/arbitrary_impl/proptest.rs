@@ -0,0 +1,95 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! `proptest` strategies, derived from the `Arbitrary` implementations
+
+use arbitrary::{Arbitrary as _, Unstructured};
+use proptest::arbitrary::{Arbitrary, any};
+use proptest::collection::vec;
+use proptest::strategy::{BoxedStrategy, Strategy};
+
+use crate::op::{Edns, Message, Query};
+use crate::rr::{DNSClass, Name, RData, Record, RecordType};
+
+/// Enough input for a message with full sections
+const MAX_INPUT_LEN: usize = 4096;
+
+macro_rules! impl_arbitrary {
+    ($($ty:ty),+) => {
+        $(
+            impl Arbitrary for $ty {
+                type Parameters = ();
+                type Strategy = BoxedStrategy<Self>;
+
+                fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                    vec(any::<u8>(), 0..MAX_INPUT_LEN)
+                        .prop_filter_map(concat!("not enough input for ", stringify!($ty)), |input| {
+                            Self::arbitrary_take_rest(Unstructured::new(&input)).ok()
+                        })
+                        .boxed()
+                }
+            }
+        )+
+    };
+}
+
+impl_arbitrary!(
+    Name, RecordType, DNSClass, Query, RData, Record, Edns, Message
+);
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, Restrict};
+
+    proptest! {
+        #[test]
+        fn name_round_trip(name in any::<Name>()) {
+            let bytes = name.to_bytes().unwrap();
+            prop_assert_eq!(Name::from_bytes(&bytes).unwrap(), name);
+        }
+
+        #[test]
+        fn rdata_round_trip(rdata in any::<RData>()) {
+            let mut bytes = Vec::new();
+            let mut encoder = BinEncoder::new(&mut bytes);
+            rdata.emit(&mut encoder).unwrap();
+
+            let length = Restrict::new(u16::try_from(bytes.len()).unwrap());
+            let mut decoder = BinDecoder::new(&bytes);
+            let decoded = RData::read(&mut decoder, rdata.record_type(), length).unwrap();
+            prop_assert_eq!(decoded, rdata);
+        }
+
+        #[test]
+        fn record_round_trip(record in any::<Record>()) {
+            let bytes = record.to_bytes().unwrap();
+            prop_assert_eq!(Record::from_bytes(&bytes).unwrap(), record);
+        }
+
+        #[test]
+        fn message_round_trip(message in any::<Message>()) {
+            let bytes = message.to_vec().unwrap();
+            let decoded = Message::from_vec(&bytes).unwrap();
+
+            // the counts in the header are only filled in while encoding
+            prop_assert_eq!(decoded.id(), message.id());
+            prop_assert_eq!(decoded.op_code(), message.op_code());
+            prop_assert_eq!(decoded.response_code(), message.response_code());
+            prop_assert_eq!(decoded.queries(), message.queries());
+            prop_assert_eq!(decoded.answers(), message.answers());
+            prop_assert_eq!(decoded.name_servers(), message.name_servers());
+            prop_assert_eq!(decoded.additionals(), message.additionals());
+            prop_assert_eq!(decoded.extensions(), message.extensions());
+            prop_assert_eq!(decoded.to_vec().unwrap(), bytes);
+        }
+    }
+}
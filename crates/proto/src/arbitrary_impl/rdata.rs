@@ -0,0 +1,399 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! `Arbitrary` implementations for the record data
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::{bytes, non_empty_bytes, non_empty_vec_of, string, vec_of};
+#[cfg(feature = "__dnssec")]
+use crate::dnssec::rdata::DNSSECRData;
+use crate::rr::rdata::{
+    A, AAAA, ANAME, CAA, CERT, CNAME, CSYNC, HINFO, HTTPS, L32, L64, LP, MX, NAPTR, NID, NS, NULL,
+    OPENPGPKEY, OPT, PTR, SOA, SRV, SSHFP, SVCB, TLSA, TXT, cert, opt, sshfp, svcb, tlsa,
+};
+use crate::rr::{Name, RData, RecordType};
+
+/// Character strings are prefixed by their length in a single byte
+const MAX_CHARACTER_STRING_LEN: usize = u8::MAX as usize;
+
+/// Keeps the generated record data well below the 65535 bytes a record can hold
+const MAX_DATA_LEN: usize = 512;
+
+/// Generates all record data except the `Update0` and `ZERO` placeholders of dynamic updates
+///
+/// Record data is never empty, as empty data is decoded as such a placeholder.
+impl<'a> Arbitrary<'a> for RData {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let rdata = match u.int_in_range(0..=27)? {
+            0 => Self::A(A::arbitrary(u)?),
+            1 => Self::AAAA(AAAA::arbitrary(u)?),
+            2 => Self::ANAME(ANAME(Name::arbitrary(u)?)),
+            3 => Self::CAA(CAA::arbitrary(u)?),
+            4 => Self::CERT(CERT::arbitrary(u)?),
+            5 => Self::CNAME(CNAME(Name::arbitrary(u)?)),
+            6 => Self::CSYNC(CSYNC::arbitrary(u)?),
+            7 => Self::HINFO(HINFO::arbitrary(u)?),
+            8 => Self::HTTPS(HTTPS(SVCB::arbitrary(u)?)),
+            9 => Self::L32(L32::arbitrary(u)?),
+            10 => Self::L64(L64::arbitrary(u)?),
+            11 => Self::LP(LP::arbitrary(u)?),
+            12 => Self::MX(MX::arbitrary(u)?),
+            13 => Self::NAPTR(NAPTR::arbitrary(u)?),
+            14 => Self::NID(NID::arbitrary(u)?),
+            15 => Self::NULL(NULL::arbitrary(u)?),
+            16 => Self::NS(NS(Name::arbitrary(u)?)),
+            17 => Self::OPENPGPKEY(OPENPGPKEY::arbitrary(u)?),
+            18 => Self::OPT(OPT::arbitrary(u)?),
+            19 => Self::PTR(PTR(Name::arbitrary(u)?)),
+            20 => Self::SOA(SOA::arbitrary(u)?),
+            21 => Self::SRV(SRV::arbitrary(u)?),
+            22 => Self::SSHFP(SSHFP::arbitrary(u)?),
+            23 => Self::SVCB(SVCB::arbitrary(u)?),
+            24 => Self::TLSA(TLSA::arbitrary(u)?),
+            25 => Self::TXT(TXT::arbitrary(u)?),
+            #[cfg(feature = "__dnssec")]
+            26 => Self::DNSSEC(DNSSECRData::arbitrary(u)?),
+            _ => {
+                // only codes without a known record type are decoded as unknown data
+                let code = match RecordType::from(u16::arbitrary(u)?) {
+                    code @ RecordType::Unknown(_) => code,
+                    _ => RecordType::Unknown(65_280),
+                };
+
+                Self::Unknown {
+                    code,
+                    rdata: NULL::arbitrary(u)?,
+                }
+            }
+        };
+
+        Ok(rdata)
+    }
+}
+
+impl<'a> Arbitrary<'a> for A {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::from(Ipv4Addr::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for AAAA {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::from(Ipv6Addr::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for CAA {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        const TAG_CHARACTERS: &[u8] =
+            b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+        // the well known tags have values with structure, others are uninterpreted
+        let raw_tag = match u.int_in_range(0..=3)? {
+            0 => "issue".to_owned(),
+            1 => "issuewild".to_owned(),
+            2 => "iodef".to_owned(),
+            _ => (0..u.int_in_range(1..=15)?)
+                .map(|_| u.choose(TAG_CHARACTERS).map(|&c| char::from(c)))
+                .collect::<Result<String>>()?,
+        };
+
+        Ok(Self {
+            issuer_critical: bool::arbitrary(u)?,
+            reserved_flags: u.int_in_range(0..=0x7f)?,
+            raw_tag,
+            raw_value: bytes(u, MAX_DATA_LEN)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for CERT {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(
+            cert::CertType::from(u16::arbitrary(u)?),
+            u16::arbitrary(u)?,
+            cert::Algorithm::from(u8::arbitrary(u)?),
+            non_empty_bytes(u, MAX_DATA_LEN)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for CSYNC {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(
+            u32::arbitrary(u)?,
+            bool::arbitrary(u)?,
+            bool::arbitrary(u)?,
+            vec_of(u, 8, RecordType::arbitrary)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for HINFO {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(
+            string(u, MAX_CHARACTER_STRING_LEN)?,
+            string(u, MAX_CHARACTER_STRING_LEN)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for L32 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(u16::arbitrary(u)?, Ipv4Addr::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for L64 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(u16::arbitrary(u)?, u64::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for LP {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(u16::arbitrary(u)?, Name::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for MX {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(u16::arbitrary(u)?, Name::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for NAPTR {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        const FLAG_CHARACTERS: &[u8] = b"ASUPasup0123456789";
+
+        let flags = (0..u.int_in_range(0..=4)?)
+            .map(|_| u.choose(FLAG_CHARACTERS).copied())
+            .collect::<Result<Vec<u8>>>()?;
+
+        Ok(Self::new(
+            u16::arbitrary(u)?,
+            u16::arbitrary(u)?,
+            flags.into_boxed_slice(),
+            bytes(u, MAX_CHARACTER_STRING_LEN)?.into_boxed_slice(),
+            bytes(u, MAX_CHARACTER_STRING_LEN)?.into_boxed_slice(),
+            Name::arbitrary(u)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for NID {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(u16::arbitrary(u)?, u64::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for NULL {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::with(non_empty_bytes(u, MAX_DATA_LEN)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for OPENPGPKEY {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(non_empty_bytes(u, MAX_DATA_LEN)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for OPT {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut opt = Self::default();
+        for option in vec_of(u, 3, opt::EdnsOption::arbitrary)? {
+            opt.insert(option);
+        }
+
+        Ok(opt)
+    }
+}
+
+impl<'a> Arbitrary<'a> for opt::EdnsOption {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let option = match u.int_in_range(0..=4)? {
+            0 => Self::Subnet(opt::ClientSubnet::arbitrary(u)?),
+            1 => Self::NSID(
+                opt::NSIDPayload::new(bytes(u, 64)?)
+                    .map_err(|_| arbitrary::Error::IncorrectFormat)?,
+            ),
+            #[cfg(feature = "__dnssec")]
+            2 => Self::DAU(Arbitrary::arbitrary(u)?),
+            3 => Self::Cookie(opt::CookieOption::arbitrary(u)?),
+            _ => {
+                // the codes of the options above are decoded into them
+                let code = match u16::arbitrary(u)? {
+                    code @ (3 | 5 | 8 | 10 | 15) => code + 100,
+                    code => code,
+                };
+
+                Self::Unknown(code, bytes(u, 64)?)
+            }
+        };
+
+        Ok(option)
+    }
+}
+
+impl<'a> Arbitrary<'a> for opt::CookieOption {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let client = <[u8; Self::CLIENT_COOKIE_LEN]>::arbitrary(u)?;
+        if bool::arbitrary(u)? {
+            return Ok(Self::new(client));
+        }
+
+        let len = u.int_in_range(Self::MIN_SERVER_COOKIE_LEN..=Self::MAX_SERVER_COOKIE_LEN)?;
+        let server = u.bytes(len)?;
+        Self::with_server_cookie(client, server).map_err(|_| arbitrary::Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for opt::ClientSubnet {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // only the bits of the source prefix are sent
+        let (address, max_prefix) = match bool::arbitrary(u)? {
+            true => (IpAddr::V4(Ipv4Addr::arbitrary(u)?), 32),
+            false => (IpAddr::V6(Ipv6Addr::arbitrary(u)?), 128),
+        };
+        let source_prefix = u.int_in_range(0..=max_prefix)?;
+        let scope_prefix = u.int_in_range(0..=max_prefix)?;
+
+        let address = match address {
+            IpAddr::V4(ip) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(source_prefix))
+                    .unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+            }
+            IpAddr::V6(ip) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(source_prefix))
+                    .unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+            }
+        };
+
+        Ok(Self::new(address, source_prefix, scope_prefix))
+    }
+}
+
+impl<'a> Arbitrary<'a> for SOA {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(
+            Name::arbitrary(u)?,
+            Name::arbitrary(u)?,
+            u32::arbitrary(u)?,
+            i32::arbitrary(u)?,
+            i32::arbitrary(u)?,
+            i32::arbitrary(u)?,
+            u32::arbitrary(u)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for SRV {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(
+            u16::arbitrary(u)?,
+            u16::arbitrary(u)?,
+            u16::arbitrary(u)?,
+            Name::arbitrary(u)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for SSHFP {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(
+            sshfp::Algorithm::from(u8::arbitrary(u)?),
+            sshfp::FingerprintType::from(u8::arbitrary(u)?),
+            bytes(u, 64)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for SVCB {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // keys must be unique and in increasing order
+        let mut keys = vec_of(u, 4, u16::arbitrary)?;
+        keys.sort_unstable();
+        keys.dedup();
+
+        let svc_params = keys
+            .into_iter()
+            .map(|key| {
+                let key = svcb::SvcParamKey::from(key);
+                Ok((key, svc_param_value(u, key)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self::new(
+            u16::arbitrary(u)?,
+            Name::arbitrary(u)?,
+            svc_params,
+        ))
+    }
+}
+
+/// A value in the format expected for the key
+fn svc_param_value(
+    u: &mut Unstructured<'_>,
+    key: svcb::SvcParamKey,
+) -> Result<svcb::SvcParamValue> {
+    use svcb::{SvcParamKey, SvcParamValue};
+
+    let value = match key {
+        SvcParamKey::Mandatory => {
+            SvcParamValue::Mandatory(svcb::Mandatory(non_empty_vec_of(u, 4, |u| {
+                Ok(SvcParamKey::from(u16::arbitrary(u)?))
+            })?))
+        }
+        SvcParamKey::Alpn => SvcParamValue::Alpn(svcb::Alpn(non_empty_vec_of(u, 3, |u| {
+            string(u, MAX_CHARACTER_STRING_LEN)
+        })?)),
+        SvcParamKey::NoDefaultAlpn => SvcParamValue::NoDefaultAlpn,
+        SvcParamKey::Port => SvcParamValue::Port(u16::arbitrary(u)?),
+        SvcParamKey::Ipv4Hint => SvcParamValue::Ipv4Hint(svcb::IpHint(vec_of(u, 4, A::arbitrary)?)),
+        SvcParamKey::EchConfigList => {
+            SvcParamValue::EchConfigList(svcb::EchConfigList(bytes(u, 128)?))
+        }
+        SvcParamKey::Ipv6Hint => {
+            SvcParamValue::Ipv6Hint(svcb::IpHint(vec_of(u, 4, AAAA::arbitrary)?))
+        }
+        SvcParamKey::Key(_) | SvcParamKey::Key65535 | SvcParamKey::Unknown(_) => {
+            SvcParamValue::Unknown(svcb::Unknown(bytes(u, 64)?))
+        }
+    };
+
+    Ok(value)
+}
+
+impl<'a> Arbitrary<'a> for TLSA {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(
+            tlsa::CertUsage::from(u8::arbitrary(u)?),
+            tlsa::Selector::from(u8::arbitrary(u)?),
+            tlsa::Matching::from(u8::arbitrary(u)?),
+            bytes(u, MAX_DATA_LEN)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for TXT {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(non_empty_vec_of(u, 4, |u| {
+            string(u, MAX_CHARACTER_STRING_LEN)
+        })?))
+    }
+}
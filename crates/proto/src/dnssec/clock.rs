@@ -0,0 +1,44 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The source of the current time for DNSSEC validation
+
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of the current time, used to check the validity period of RRSIGs
+///
+/// With `std` the [`SystemClock`] is used by default, targets without `std` must provide their
+/// own, e.g. backed by an RTC or a time synchronized over the network.
+pub trait Clock: Send + Sync {
+    /// Returns the current time in seconds since the UNIX epoch
+    ///
+    /// Like the RRSIG inception and expiration fields, the value wraps around, it is compared
+    /// using serial number arithmetic.
+    fn now(&self) -> u32;
+}
+
+impl<F: Fn() -> u32 + Send + Sync> Clock for F {
+    fn now(&self) -> u32 {
+        self()
+    }
+}
+
+/// A [`Clock`] reading the current time of the system
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> u32 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as u32
+    }
+}
@@ -243,7 +243,11 @@ impl PublicKey for Ec {
         let alg = match self.algorithm {
             Algorithm::ECDSAP256SHA256 => &signature::ECDSA_P256_SHA256_FIXED,
             Algorithm::ECDSAP384SHA384 => &signature::ECDSA_P384_SHA384_FIXED,
-            _ => return Err("only ECDSAP256SHA256 and ECDSAP384SHA384 are supported by `Ec`".into()),
+            _ => {
+                return Err(
+                    "only ECDSAP256SHA256 and ECDSAP384SHA384 are supported by `Ec`".into(),
+                );
+            }
         };
         let public_key = signature::UnparsedPublicKey::new(alg, self.prefixed_bytes());
         public_key.verify(message, signature).map_err(Into::into)
@@ -300,8 +304,11 @@ impl PublicKey for Ed25519<'_> {
 
 /// Rsa public key
 pub struct Rsa<'k> {
+    /// The raw public key bytes, in DNSKEY wire format
     pub raw: &'k [u8],
+    /// The parsed RSA public key
     pub pkey: RSAPublicKey<'k>,
+    /// The RSA algorithm this key is used with
     pub algorithm: Algorithm,
 }
 
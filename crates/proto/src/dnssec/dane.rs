@@ -0,0 +1,418 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DANE matching of TLS certificate chains against TLSA records, RFC 6698 and RFC 7671
+//!
+//! Only the records of a TLSA RRset which was validated as [`Proof::Secure`](super::Proof::Secure)
+//! may be used for DANE. If the RRset is insecure or does not exist, the TLS client falls back to
+//! its usual PKIX validation.
+//!
+//! The functions here only perform the matching of the records. For the DANE-TA usage the caller
+//! must still check that the presented chain leads from the end entity certificate to the matched
+//! trust anchor, and check the name of the peer, RFC 7671, section 5.2.
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use x509_cert::Certificate;
+use x509_cert::der::{Decode, Encode};
+
+use super::ring_like::digest;
+use crate::error::{ProtoError, ProtoResult};
+use crate::rr::rdata::tlsa::{CertUsage, Matching, Selector, TLSA};
+
+/// The outcome of matching a certificate chain against a TLSA RRset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DaneVerification<'a> {
+    /// A usable record matched the certificate at `index` in the chain
+    Matched {
+        /// The record which matched
+        record: &'a TLSA,
+        /// The index of the matched certificate in the chain, the end entity certificate being 0
+        index: usize,
+    },
+    /// None of the records is usable, the connection is to be treated as if there were no TLSA
+    /// records at all, RFC 6698, section 4.1
+    NoUsableRecords,
+    /// There are usable records but none of them matched, the connection must be aborted
+    Mismatch,
+}
+
+impl DaneVerification<'_> {
+    /// Returns true if a record matched the certificate chain
+    pub fn is_matched(&self) -> bool {
+        matches!(self, Self::Matched { .. })
+    }
+}
+
+/// Matches the certificate chain presented by the peer against the records of a TLSA RRset
+///
+/// `chain` holds the DER encoded certificates in the order presented in the TLS handshake, the end
+/// entity certificate first. `pkix_valid` is the outcome of the PKIX path validation of the chain
+/// by the client, which is required by the PKIX-TA and PKIX-EE usages, but ignored by the DANE-TA
+/// and DANE-EE usages.
+///
+/// Records with unassigned or private certificate usages, selectors or matching types are not
+/// usable and are skipped, RFC 7671, section 4.1.
+pub fn verify<'a, C: AsRef<[u8]>>(
+    records: impl IntoIterator<Item = &'a TLSA>,
+    chain: &[C],
+    pkix_valid: bool,
+) -> ProtoResult<DaneVerification<'a>> {
+    let Some((end_entity, issuers)) = chain.split_first() else {
+        return Err("no certificates in the chain".into());
+    };
+
+    let mut usable = false;
+    for record in records {
+        if !is_usable(record) {
+            continue;
+        }
+        usable = true;
+
+        let found = match record.cert_usage() {
+            CertUsage::DaneEe => matches(record, end_entity.as_ref())?.then_some(0),
+            CertUsage::PkixEe if pkix_valid => matches(record, end_entity.as_ref())?.then_some(0),
+            // the trust anchor is either presented by the peer or part of the local trust store
+            CertUsage::PkixTa if pkix_valid => position(record, chain, 0)?,
+            CertUsage::DaneTa => position(record, issuers, 1)?,
+            _ => None,
+        };
+
+        if let Some(index) = found {
+            return Ok(DaneVerification::Matched { record, index });
+        }
+    }
+
+    Ok(match usable {
+        true => DaneVerification::Mismatch,
+        false => DaneVerification::NoUsableRecords,
+    })
+}
+
+/// Returns true if the certificate usage, selector and matching type of `record` are all known
+pub fn is_usable(record: &TLSA) -> bool {
+    matches!(
+        record.cert_usage(),
+        CertUsage::PkixTa | CertUsage::PkixEe | CertUsage::DaneTa | CertUsage::DaneEe
+    ) && matches!(record.selector(), Selector::Full | Selector::Spki)
+        && matches!(
+            record.matching(),
+            Matching::Raw | Matching::Sha256 | Matching::Sha512
+        )
+}
+
+/// Returns true if the DER encoded `certificate` matches the association data of `record`
+///
+/// This only compares the data selected from the certificate with the selector and matching type
+/// of the record, the certificate usage is ignored. Records which are not usable never match.
+pub fn matches(record: &TLSA, certificate: &[u8]) -> ProtoResult<bool> {
+    let spki;
+    let selected = match record.selector() {
+        Selector::Full => certificate,
+        Selector::Spki => {
+            spki = subject_public_key_info(certificate)?;
+            spki.as_slice()
+        }
+        Selector::Unassigned(_) | Selector::Private => return Ok(false),
+    };
+
+    let algorithm = match record.matching() {
+        Matching::Raw => return Ok(selected == record.cert_data()),
+        Matching::Sha256 => &digest::SHA256,
+        Matching::Sha512 => &digest::SHA512,
+        Matching::Unassigned(_) | Matching::Private => return Ok(false),
+    };
+
+    Ok(digest::digest(algorithm, selected).as_ref() == record.cert_data())
+}
+
+fn position<C: AsRef<[u8]>>(
+    record: &TLSA,
+    certificates: &[C],
+    offset: usize,
+) -> ProtoResult<Option<usize>> {
+    for (index, certificate) in certificates.iter().enumerate() {
+        if matches(record, certificate.as_ref())? {
+            return Ok(Some(index + offset));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The DER encoded SubjectPublicKeyInfo of an X.509 certificate, RFC 5280, section 4.1
+fn subject_public_key_info(certificate: &[u8]) -> ProtoResult<Vec<u8>> {
+    let certificate = Certificate::from_der(certificate)
+        .map_err(|err| ProtoError::from(format!("malformed certificate: {err}")))?;
+
+    certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|err| ProtoError::from(format!("failed to encode public key: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use data_encoding::{BASE64, HEXLOWER};
+
+    use super::*;
+
+    const CA: &[u8] = include_bytes!("../../../../tests/test-data/ca.der");
+    const CA_SPKI_SHA256: &str = "3e29b8f8a27bfcadef4c9cddbbc18228143385207111e7167312c348274cca0a";
+    const CA_SPKI_SHA512: &str = "b2e1f514b359bd2d09642441818062e95426f304e7e6b7afb31f0da644660817\
+                                  ae9be6afa77e88855632cdc4f6881f891a593dde18ae92da641af4284f2d3a20";
+    const CA_SHA256: &str = "8d3c836792431c02f748c7c38cf696378e2926a19abd7aabafb216aa041efbe5";
+
+    /// The end entity certificate issued by the CA
+    fn end_entity() -> Vec<u8> {
+        let pem = include_str!("../../../../tests/test-data/cert.pem");
+        let base64 = pem
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect::<alloc::string::String>();
+        BASE64.decode(base64.as_bytes()).unwrap()
+    }
+
+    fn tlsa(cert_usage: CertUsage, selector: Selector, matching: Matching, data: &str) -> TLSA {
+        TLSA::new(
+            cert_usage,
+            selector,
+            matching,
+            HEXLOWER.decode(data.as_bytes()).unwrap(),
+        )
+    }
+
+    fn sha256(data: &[u8]) -> alloc::string::String {
+        HEXLOWER.encode(digest::digest(&digest::SHA256, data).as_ref())
+    }
+
+    #[test]
+    fn test_matches() {
+        let spki = subject_public_key_info(CA).unwrap();
+        assert!(
+            matches(
+                &tlsa(
+                    CertUsage::DaneTa,
+                    Selector::Full,
+                    Matching::Sha256,
+                    CA_SHA256
+                ),
+                CA
+            )
+            .unwrap()
+        );
+        assert!(
+            matches(
+                &tlsa(
+                    CertUsage::DaneTa,
+                    Selector::Spki,
+                    Matching::Sha256,
+                    CA_SPKI_SHA256
+                ),
+                CA
+            )
+            .unwrap()
+        );
+        assert!(
+            matches(
+                &tlsa(
+                    CertUsage::DaneTa,
+                    Selector::Spki,
+                    Matching::Sha512,
+                    CA_SPKI_SHA512
+                ),
+                CA
+            )
+            .unwrap()
+        );
+        assert!(
+            matches(
+                &tlsa(
+                    CertUsage::DaneTa,
+                    Selector::Spki,
+                    Matching::Raw,
+                    &HEXLOWER.encode(&spki)
+                ),
+                CA
+            )
+            .unwrap()
+        );
+
+        // the digest of the certificate doesn't match the digest of the key
+        assert!(
+            !matches(
+                &tlsa(
+                    CertUsage::DaneTa,
+                    Selector::Full,
+                    Matching::Sha256,
+                    CA_SPKI_SHA256
+                ),
+                CA
+            )
+            .unwrap()
+        );
+        assert!(
+            !matches(
+                &tlsa(
+                    CertUsage::DaneTa,
+                    Selector::Spki,
+                    Matching::Private,
+                    CA_SPKI_SHA256
+                ),
+                CA
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_spki_of_v3_certificate() {
+        // the end entity certificate carries a version, unlike the v1 CA certificate
+        let end_entity = end_entity();
+        let spki = subject_public_key_info(&end_entity).unwrap();
+        assert_eq!(spki[0], 0x30);
+        assert!(end_entity.windows(spki.len()).any(|window| window == spki));
+        assert_ne!(spki, subject_public_key_info(CA).unwrap());
+    }
+
+    #[test]
+    fn test_malformed_certificate() {
+        assert!(subject_public_key_info(&[]).is_err());
+        assert!(subject_public_key_info(&CA[..CA.len() / 2]).is_err());
+        assert!(subject_public_key_info(&[0x30, 0x85, 0, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_verify_dane_ee() {
+        let end_entity = end_entity();
+        let chain = [end_entity.as_slice(), CA];
+        let record = tlsa(
+            CertUsage::DaneEe,
+            Selector::Full,
+            Matching::Sha256,
+            &sha256(&end_entity),
+        );
+
+        // PKIX validation is not required
+        let verification = verify([&record], &chain, false).unwrap();
+        assert_eq!(
+            verification,
+            DaneVerification::Matched {
+                record: &record,
+                index: 0
+            }
+        );
+
+        // a trust anchor can't match an end entity record
+        let record = tlsa(
+            CertUsage::DaneEe,
+            Selector::Full,
+            Matching::Sha256,
+            CA_SHA256,
+        );
+        assert_eq!(
+            verify([&record], &chain, true).unwrap(),
+            DaneVerification::Mismatch
+        );
+    }
+
+    #[test]
+    fn test_verify_dane_ta() {
+        let end_entity = end_entity();
+        let chain = [end_entity.as_slice(), CA];
+        let record = tlsa(
+            CertUsage::DaneTa,
+            Selector::Spki,
+            Matching::Sha256,
+            CA_SPKI_SHA256,
+        );
+
+        assert_eq!(
+            verify([&record], &chain, false).unwrap(),
+            DaneVerification::Matched {
+                record: &record,
+                index: 1
+            }
+        );
+
+        // the end entity certificate is not a trust anchor
+        let record = tlsa(
+            CertUsage::DaneTa,
+            Selector::Full,
+            Matching::Sha256,
+            &sha256(&end_entity),
+        );
+        assert_eq!(
+            verify([&record], &chain, false).unwrap(),
+            DaneVerification::Mismatch
+        );
+    }
+
+    #[test]
+    fn test_verify_pkix() {
+        let end_entity = end_entity();
+        let chain = [end_entity.as_slice(), CA];
+        let ta = tlsa(
+            CertUsage::PkixTa,
+            Selector::Full,
+            Matching::Sha256,
+            CA_SHA256,
+        );
+        let ee = tlsa(
+            CertUsage::PkixEe,
+            Selector::Full,
+            Matching::Sha256,
+            &sha256(&end_entity),
+        );
+
+        assert!(verify([&ta], &chain, true).unwrap().is_matched());
+        assert!(verify([&ee], &chain, true).unwrap().is_matched());
+
+        // both usages require the chain to pass PKIX validation
+        assert_eq!(
+            verify([&ta, &ee], &chain, false).unwrap(),
+            DaneVerification::Mismatch
+        );
+    }
+
+    #[test]
+    fn test_verify_unusable() {
+        let chain = [CA];
+        let records = [
+            tlsa(
+                CertUsage::Private,
+                Selector::Full,
+                Matching::Sha256,
+                CA_SHA256,
+            ),
+            tlsa(
+                CertUsage::DaneEe,
+                Selector::Unassigned(2),
+                Matching::Sha256,
+                CA_SHA256,
+            ),
+            tlsa(
+                CertUsage::DaneEe,
+                Selector::Full,
+                Matching::Unassigned(3),
+                CA_SHA256,
+            ),
+        ];
+
+        assert_eq!(
+            verify(&records, &chain, true).unwrap(),
+            DaneVerification::NoUsableRecords
+        );
+        assert_eq!(
+            verify([], &chain, true).unwrap(),
+            DaneVerification::NoUsableRecords
+        );
+        assert!(verify(&records, &[] as &[&[u8]], true).is_err());
+    }
+}
@@ -7,22 +7,25 @@
 
 //! The `DnssecDnsHandle` is used to validate all DNS responses for correct DNSSEC signatures.
 
-use alloc::{borrow::ToOwned, boxed::Box, string::ToString, sync::Arc, vec::Vec};
-use core::{clone::Clone, pin::Pin};
-use std::{
-    collections::{HashMap, HashSet},
-    time::{SystemTime, UNIX_EPOCH},
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    string::ToString,
+    sync::Arc,
+    vec::Vec,
 };
+use core::{clone::Clone, pin::Pin};
 
 use futures_util::{
-    future::{self, TryFutureExt},
-    stream::{self, Stream, TryStreamExt},
+    future::{self, FutureExt, TryFutureExt},
+    stream::{self, Stream, StreamExt, TryStreamExt},
 };
-use tracing::{debug, error, trace, warn};
+use tracing::{Instrument, debug, debug_span, error, trace, warn};
 
 use crate::{
     dnssec::{
-        Proof, ProofError, ProofErrorKind, TrustAnchors, Verifier,
+        Clock, Proof, ProofError, ProofErrorKind, TrustAnchors, Verifier,
         rdata::{DNSKEY, DS, RRSIG},
     },
     error::{NoRecords, ProtoError, ProtoErrorKind},
@@ -36,6 +39,8 @@ use self::rrset::Rrset;
 mod nsec3_validation;
 use nsec3_validation::verify_nsec3;
 
+#[cfg(feature = "std")]
+use super::SystemClock;
 use super::rdata::{DNSSECRData, NSEC};
 
 /// Performs DNSSEC validation of all DNS responses from the wrapped DnsHandle
@@ -48,9 +53,11 @@ use super::rdata::{DNSSECRData, NSEC};
 pub struct DnssecDnsHandle<H> {
     handle: H,
     trust_anchor: Arc<TrustAnchors>,
+    clock: Arc<dyn Clock>,
     request_depth: usize,
     nsec3_soft_iteration_limit: u16,
     nsec3_hard_iteration_limit: u16,
+    verification_concurrency: usize,
 }
 
 impl<H: DnsHandle> DnssecDnsHandle<H> {
@@ -60,6 +67,7 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
     ///
     /// # Arguments
     /// * `handle` - handle to use for all connections to a remote server.
+    #[cfg(feature = "std")]
     pub fn new(handle: H) -> Self {
         Self::with_trust_anchor(handle, Arc::new(TrustAnchors::default()))
     }
@@ -71,15 +79,30 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
     /// # Arguments
     /// * `handle` - handle to use for all connections to a remote server.
     /// * `trust_anchor` - custom DNSKEYs that will be trusted, can be used to pin trusted keys.
+    #[cfg(feature = "std")]
     pub fn with_trust_anchor(handle: H, trust_anchor: Arc<TrustAnchors>) -> Self {
+        Self::with_clock(handle, trust_anchor, Arc::new(SystemClock))
+    }
+
+    /// Create a new DnssecDnsHandle wrapping the specified handle.
+    ///
+    /// This allows the source of the current time to be defined, which is required without `std`.
+    ///
+    /// # Arguments
+    /// * `handle` - handle to use for all connections to a remote server.
+    /// * `trust_anchor` - custom DNSKEYs that will be trusted, can be used to pin trusted keys.
+    /// * `clock` - the time against which the validity period of RRSIGs is checked.
+    pub fn with_clock(handle: H, trust_anchor: Arc<TrustAnchors>, clock: Arc<dyn Clock>) -> Self {
         Self {
             handle,
             trust_anchor,
+            clock,
             request_depth: 0,
             // These default values are based on
             // [RFC 9276 Appendix A](https://www.rfc-editor.org/rfc/rfc9276.html#appendix-A)
             nsec3_soft_iteration_limit: 100,
             nsec3_hard_iteration_limit: 500,
+            verification_concurrency: DEFAULT_VERIFICATION_CONCURRENCY,
         }
     }
 
@@ -106,6 +129,20 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
         self
     }
 
+    /// Set the number of RRsets of a response that are verified concurrently
+    ///
+    /// The RRSIGs of each RRset are always checked concurrently. With the `tokio` feature, the
+    /// signatures are verified on the blocking thread pool of the current runtime, so a slow
+    /// verification, e.g. of a large RSA key, does not hold up the others.
+    ///
+    /// # Arguments
+    /// * `limit` - the maximum number of RRsets verified at once, `1` verifies them serially.
+    ///   Defaults to 8, `0` is treated as `1`.
+    pub fn verification_concurrency(mut self, limit: usize) -> Self {
+        self.verification_concurrency = limit.max(1);
+        self
+    }
+
     /// Extracts the different sections of a message and verifies the RRSIGs
     async fn verify_response(
         self,
@@ -124,9 +161,15 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
         let nameservers = message.take_name_servers();
         let additionals = message.take_additionals();
 
-        let answers = self.verify_rrsets(answers, options).await;
-        let nameservers = self.verify_rrsets(nameservers, options).await;
-        let additionals = self.verify_rrsets(additionals, options).await;
+        // the DNSKEYs of the signers are shared by all sections
+        let mut signer_keys = SignerKeys::new();
+        let answers = self.verify_rrsets(answers, &mut signer_keys, options).await;
+        let nameservers = self
+            .verify_rrsets(nameservers, &mut signer_keys, options)
+            .await;
+        let additionals = self
+            .verify_rrsets(additionals, &mut signer_keys, options)
+            .await;
 
         message.insert_answers(answers);
         message.insert_name_servers(nameservers);
@@ -137,8 +180,13 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
 
     /// This pulls all answers returned in a Message response and returns a future which will
     ///  validate all of them.
-    async fn verify_rrsets(&self, records: Vec<Record>, options: DnsRequestOptions) -> Vec<Record> {
-        let mut rrset_types: HashSet<(Name, RecordType)> = HashSet::new();
+    async fn verify_rrsets(
+        &self,
+        records: Vec<Record>,
+        signer_keys: &mut SignerKeys,
+        options: DnsRequestOptions,
+    ) -> Vec<Record> {
+        let mut rrset_types: BTreeSet<(Name, RecordType)> = BTreeSet::new();
 
         for rrset in records
             .iter()
@@ -172,8 +220,9 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
             .into_iter()
             .partition::<Vec<_>, _>(|r| r.record_type().is_rrsig());
 
+        // collect all the rrsets to verify, along with the RRSIGs covering them
+        let mut rrsets = Vec::with_capacity(rrset_types.len());
         for (name, record_type) in rrset_types {
-            // collect all the rrsets to verify
             let current_rrset;
             (current_rrset, records) = records
                 .into_iter()
@@ -186,50 +235,53 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
                     .unwrap_or_default()
             });
 
-            // TODO: we can do a better job here, no need for all the vec creation and clones in the Rrset.
-            let mut rrs_to_verify = current_rrset.iter();
-            let mut rrset = Rrset::new(rrs_to_verify.next().unwrap());
-            rrs_to_verify.for_each(|rr| rrset.add(rr));
-
-            // RRSIGS are never modified after this point
-            let rrsigs: Vec<_> = current_rrsigs
-                .iter()
-                .filter_map(|rr| rr.try_borrow::<RRSIG>())
-                .filter(|rr| rr.name() == &name)
-                .filter(|rrsig| rrsig.data().input().type_covered == record_type)
-                .collect();
-
-            // if there is already an active validation going on, assume the other validation will
-            //  complete properly or error if it is invalid
+            rrsets.push((Arc::new(current_rrset), current_rrsigs));
+        }
 
-            // TODO: support non-IN classes?
-            debug!(
-                "verifying: {name} record_type: {record_type}, rrsigs: {rrsig_len}",
-                rrsig_len = rrsigs.len()
-            );
+        // look up the DNSKEYs of each signer once, instead of once per RRSIG
+        let signers = rrsets
+            .iter()
+            .filter(|(rrset, _)| rrset[0].record_type() != RecordType::DNSKEY)
+            .flat_map(|(_, rrsigs)| rrsigs.iter().take(MAX_RRSIGS_PER_RRSET + 1))
+            .filter_map(|rrsig| rrsig.try_borrow::<RRSIG>())
+            .map(|rrsig| rrsig.data().input().signer_name.clone())
+            .filter(|signer| !signer_keys.contains_key(signer))
+            .collect::<BTreeSet<_>>();
+
+        let lookups = signers.into_iter().map(|signer| {
+            let query = Query::query(signer.clone(), RecordType::DNSKEY);
+            self.lookup(query.clone(), options)
+                .first_answer()
+                .map_err(|proto| {
+                    ProofError::new(Proof::Bogus, ProofErrorKind::Proto { query, proto })
+                })
+                .map(move |response| (signer, response))
+        });
+
+        signer_keys.extend(
+            stream::iter(lookups)
+                .buffer_unordered(self.verification_concurrency)
+                .collect::<Vec<_>>()
+                .await,
+        );
 
-            // verify this rrset
-            let proof = self.verify_rrset(&rrset, rrsigs, options).await;
+        // verify the rrsets concurrently, the proofs are yielded in the order of the rrsets
+        let mut verifications = Vec::with_capacity(rrsets.len());
+        for (rrset, rrsigs) in &rrsets {
+            verifications.push(self.verify_rrset_records(rrset, rrsigs, signer_keys, options));
+        }
 
-            let proof = match proof {
-                Ok(proof) => {
-                    debug!("verified: {name} record_type: {record_type}",);
-                    proof
-                }
-                Err(err) => {
-                    match err.kind() {
-                        ProofErrorKind::DsResponseNsec { .. } => {
-                            debug!("verified insecure {name}/{record_type}")
-                        }
-                        kind => {
-                            debug!("failed to verify: {name} record_type: {record_type}: {kind}")
-                        }
-                    }
-                    (err.proof, None, None)
-                }
-            };
+        let proofs = stream::iter(verifications)
+            .buffered(self.verification_concurrency)
+            .collect::<Vec<_>>()
+            .await;
 
-            let (proof, adjusted_ttl, rrsig_idx) = proof;
+        for ((current_rrset, mut current_rrsigs), (proof, adjusted_ttl, rrsig_idx)) in
+            rrsets.into_iter().zip(proofs)
+        {
+            // all verifications are complete, so the records are no longer shared
+            let current_rrset =
+                Arc::try_unwrap(current_rrset).unwrap_or_else(|rrset| rrset.as_ref().clone());
             for mut record in current_rrset {
                 record.set_proof(proof);
                 if let (Proof::Secure, Some(ttl)) = (proof, adjusted_ttl) {
@@ -240,7 +292,6 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
             }
 
             // only mark the RRSIG used for the proof
-            let mut current_rrsigs = current_rrsigs;
             if let Some(rrsig_idx) = rrsig_idx {
                 if let Some(rrsig) = current_rrsigs.get_mut(rrsig_idx) {
                     rrsig.set_proof(proof);
@@ -265,6 +316,67 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
         return_records
     }
 
+    /// Verifies the records of a single RRset against the RRSIGs covering it
+    ///
+    /// # Returns
+    ///
+    /// The set of (Proof, AdjustedTTL, and IndexOfRRSIG), failures are returned with the proof of
+    ///   the error and no TTL or index.
+    async fn verify_rrset_records(
+        &self,
+        records: &Arc<Vec<Record>>,
+        rrsigs: &[Record],
+        signer_keys: &SignerKeys,
+        options: DnsRequestOptions,
+    ) -> (Proof, Option<u32>, Option<usize>) {
+        // TODO: we can do a better job here, no need for all the vec creation and clones in the Rrset.
+        let mut rrs_to_verify = records.iter();
+        let mut rrset = Rrset::new(rrs_to_verify.next().unwrap());
+        rrs_to_verify.for_each(|rr| rrset.add(rr));
+
+        let name = rrset.name();
+        let record_type = rrset.record_type();
+
+        // RRSIGS are never modified after this point
+        let rrsigs: Vec<_> = rrsigs
+            .iter()
+            .filter_map(|rr| rr.try_borrow::<RRSIG>())
+            .filter(|rr| rr.name() == name)
+            .filter(|rrsig| rrsig.data().input().type_covered == record_type)
+            .collect();
+
+        // if there is already an active validation going on, assume the other validation will
+        //  complete properly or error if it is invalid
+
+        // TODO: support non-IN classes?
+        debug!(
+            "verifying: {name} record_type: {record_type}, rrsigs: {rrsig_len}",
+            rrsig_len = rrsigs.len()
+        );
+
+        // verify this rrset
+        match self
+            .verify_rrset(&rrset, records, rrsigs, signer_keys, options)
+            .await
+        {
+            Ok(proof) => {
+                debug!("verified: {name} record_type: {record_type}",);
+                proof
+            }
+            Err(err) => {
+                match err.kind() {
+                    ProofErrorKind::DsResponseNsec { .. } => {
+                        debug!("verified insecure {name}/{record_type}")
+                    }
+                    kind => {
+                        debug!("failed to verify: {name} record_type: {record_type}: {kind}")
+                    }
+                }
+                (err.proof, None, None)
+            }
+        }
+    }
+
     /// Generic entrypoint to verify any RRSET against the provided signatures.
     ///
     /// Generally, the RRSET will be validated by `verify_default_rrset()`. There are additional
@@ -281,22 +393,24 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
     async fn verify_rrset(
         &self,
         rrset: &Rrset<'_>,
+        records: &Arc<Vec<Record>>,
         rrsigs: Vec<RecordRef<'_, RRSIG>>,
+        signer_keys: &SignerKeys,
         options: DnsRequestOptions,
     ) -> Result<(Proof, Option<u32>, Option<usize>), ProofError> {
         // use the same current time value for all rrsig + rrset pairs.
-        let current_time = current_time();
+        let current_time = self.clock.now();
 
         // DNSKEYS have different logic for their verification
         if matches!(rrset.record_type(), RecordType::DNSKEY) {
             let proof = self
-                .verify_dnskey_rrset(rrset, &rrsigs, current_time, options)
+                .verify_dnskey_rrset(rrset, records, &rrsigs, current_time, options)
                 .await?;
 
             return Ok(proof);
         }
 
-        self.verify_default_rrset(rrset, &rrsigs, current_time, options)
+        self.verify_default_rrset(rrset, records, &rrsigs, signer_keys, current_time, options)
             .await
     }
 
@@ -320,6 +434,7 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
     async fn verify_dnskey_rrset(
         &self,
         rrset: &Rrset<'_>,
+        records: &Arc<Vec<Record>>,
         rrsigs: &Vec<RecordRef<'_, RRSIG>>,
         current_time: u32,
         options: DnsRequestOptions,
@@ -402,7 +517,7 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
             // These should all match, but double checking...
             let signer_name = &rrsig.data().input().signer_name;
 
-            let rrset_proof = rrset
+            let dnskeys = rrset
                 .records()
                 .iter()
                 .zip(dnskey_proofs.iter())
@@ -411,14 +526,22 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
                 .filter_map(|(r, (proof, ..))| {
                     RecordRef::<'_, DNSKEY>::try_from(*r)
                         .ok()
-                        .map(|r| (r, proof))
-                })
-                .find_map(|(dnskey, proof)| {
-                    verify_rrset_with_dnskey(dnskey, *proof, rrsig, rrset, current_time).ok()
+                        .map(|r| (r, *proof))
                 });
 
-            if let Some(rrset_proof) = rrset_proof {
-                return Ok((rrset_proof.0, rrset_proof.1, Some(i)));
+            for (dnskey, proof) in dnskeys {
+                if let Ok(rrset_proof) = verify_rrset_with_dnskey_blocking(
+                    dnskey,
+                    proof,
+                    rrsig,
+                    rrset,
+                    records,
+                    current_time,
+                )
+                .await
+                {
+                    return Ok((rrset_proof.0, rrset_proof.1, Some(i)));
+                }
             }
         }
 
@@ -639,7 +762,9 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
     async fn verify_default_rrset(
         &self,
         rrset: &Rrset<'_>,
+        records: &Arc<Vec<Record>>,
         rrsigs: &Vec<RecordRef<'_, RRSIG>>,
+        signer_keys: &SignerKeys,
         current_time: u32,
         options: DnsRequestOptions,
     ) -> Result<(Proof, Option<u32>, Option<usize>), ProofError> {
@@ -693,7 +818,7 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
         .iter()
         .enumerate()
         .filter_map(|(i, rrsig)| {
-            let query = Query::query(rrsig.data().input().signer_name.clone(), RecordType::DNSKEY);
+            let signer_name = &rrsig.data().input().signer_name;
 
             if i > MAX_RRSIGS_PER_RRSET {
                 warn!("too many ({i}) RRSIGs for rrset {rrset:?}; skipping");
@@ -701,14 +826,20 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
             }
 
             // TODO: Should this sig.signer_name should be confirmed to be in the same zone as the rrsigs and rrset?
-            Some(self
-                .lookup(query.clone(), options)
-                .first_answer()
-                .map_err(|proto| {
-                    ProofError::new(Proof::Bogus, ProofErrorKind::Proto { query, proto })
-                })
-                .map_ok(move |message| {
-                    let mut tag_count = HashMap::<u16, usize>::new();
+            let message = match signer_keys.get(signer_name) {
+                Some(Ok(message)) => Ok(message),
+                Some(Err(err)) => Err(err.clone()),
+                None => Err(ProofError::new(
+                    Proof::Bogus,
+                    ProofErrorKind::DnskeyNotFound {
+                        name: signer_name.clone(),
+                    },
+                )),
+            };
+
+            Some(Box::pin(future::ready(message)
+                .and_then(move |message| async move {
+                    let mut tag_count = BTreeMap::<u16, usize>::new();
 
                     // DNSKEYs were already validated by the inner query in the above lookup
                     let dnskeys = message
@@ -745,9 +876,9 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
                             Proof::Secure => {
                                 all_insecure = Some(false);
                                 if let Ok(proof) =
-                                    verify_rrset_with_dnskey(dnskey, dnskey.proof(), rrsig, rrset, current_time)
+                                    verify_rrset_with_dnskey_blocking(dnskey, dnskey.proof(), rrsig, rrset, records, current_time).await
                                 {
-                                    return Some((proof.0, proof.1, Some(i)));
+                                    return Ok(Some((proof.0, proof.1, Some(i))));
                                 }
                             }
                             Proof::Insecure => {
@@ -759,11 +890,11 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
 
                     if all_insecure.unwrap_or(false) {
                         // inherit Insecure state
-                        Some((Proof::Insecure, None, None))
+                        Ok(Some((Proof::Insecure, None, None)))
                     } else {
-                        None
+                        Ok(None)
                     }
-                }))
+                })))
         })
         .collect::<Vec<_>>();
 
@@ -801,14 +932,15 @@ impl<H: DnsHandle> DnssecDnsHandle<H> {
         Self {
             handle: self.handle.clone(),
             trust_anchor: Arc::clone(&self.trust_anchor),
+            clock: Arc::clone(&self.clock),
             request_depth: self.request_depth + 1,
             nsec3_soft_iteration_limit: self.nsec3_soft_iteration_limit,
             nsec3_hard_iteration_limit: self.nsec3_hard_iteration_limit,
+            verification_concurrency: self.verification_concurrency,
         }
     }
 }
 
-#[cfg(any(feature = "std", feature = "no-std-rand"))]
 impl<H: DnsHandle> DnsHandle for DnssecDnsHandle<H> {
     type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send>>;
 
@@ -854,6 +986,7 @@ impl<H: DnsHandle> DnsHandle for DnssecDnsHandle<H> {
 
         let soft_iteration_limit = self.nsec3_soft_iteration_limit;
         let hard_iteration_limit = self.nsec3_hard_iteration_limit;
+        let name = query.name().clone();
 
         Box::pin(
             self.handle
@@ -893,7 +1026,15 @@ impl<H: DnsHandle> DnsHandle for DnssecDnsHandle<H> {
                     }
                 })
                 .and_then(move |message_response| {
-                    handle.clone().verify_response(message_response, options)
+                    let span = debug_span!(
+                        "dnssec_validation",
+                        name = %name,
+                        depth = handle.request_depth,
+                    );
+                    handle
+                        .clone()
+                        .verify_response(message_response, options)
+                        .instrument(span)
                 })
                 .and_then(move |verified_message| {
                     future::ready(check_nsec(
@@ -1109,6 +1250,48 @@ fn verify_dnskey(
     ))
 }
 
+/// Verifies the given SIG of the RRSET with the DNSKEY, see [`verify_rrset_with_dnskey`].
+///
+/// If a Tokio runtime is available, the verification is moved to its blocking thread pool, so that
+/// concurrent verifications run in parallel and do not stall the executor. `records` are the
+/// records `rrset` was built from, they are shared with the blocking task.
+#[cfg_attr(not(feature = "tokio"), allow(unused_variables))]
+async fn verify_rrset_with_dnskey_blocking(
+    dnskey: RecordRef<'_, DNSKEY>,
+    dnskey_proof: Proof,
+    rrsig: &RecordRef<'_, RRSIG>,
+    rrset: &Rrset<'_>,
+    records: &Arc<Vec<Record>>,
+    current_time: u32,
+) -> Result<(Proof, Option<u32>), ProofError> {
+    #[cfg(feature = "tokio")]
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        let dnskey = dnskey.to_owned().into_record_of_rdata();
+        let rrsig = rrsig.to_owned().into_record_of_rdata();
+        let records = Arc::clone(records);
+
+        let verification = runtime.spawn_blocking(move || {
+            let (Some(dnskey), Some(rrsig)) = (dnskey.try_borrow(), rrsig.try_borrow()) else {
+                unreachable!("the records were converted from these types");
+            };
+
+            let mut records = records.iter();
+            let mut rrset = Rrset::new(records.next().expect("rrsets are never empty"));
+            records.for_each(|rr| rrset.add(rr));
+            verify_rrset_with_dnskey(dnskey, dnskey_proof, &rrsig, &rrset, current_time)
+        });
+
+        return verification.await.unwrap_or_else(|err| {
+            Err(ProofError::new(
+                Proof::Bogus,
+                ProofErrorKind::Msg(format!("signature verification failed: {err}")),
+            ))
+        });
+    }
+
+    verify_rrset_with_dnskey(dnskey, dnskey_proof, rrsig, rrset, current_time)
+}
+
 /// Verifies the given SIG of the RRSET with the DNSKEY.
 fn verify_rrset_with_dnskey(
     dnskey: RecordRef<'_, DNSKEY>,
@@ -1410,14 +1593,6 @@ pub fn verify_nsec(query: &Query, soa_name: &Name, nsecs: &[(&Name, &NSEC)]) ->
     }
 }
 
-/// Returns the current system time as Unix timestamp in seconds.
-fn current_time() -> u32 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as u32
-}
-
 /// Logs a debug message and yields a Proof type for return
 fn proof_log_yield(proof: Proof, name: &Name, nsec_type: &str, msg: &str) -> Proof {
     debug!("{nsec_type} proof for {name}, returning {proof}: {msg}");
@@ -1491,3 +1666,9 @@ const MAX_KEY_TAG_COLLISIONS: usize = 2;
 
 /// The maximum number of RRSIGs to attempt to validate for each RRSET.
 const MAX_RRSIGS_PER_RRSET: usize = 8;
+
+/// The default number of RRsets of a response verified concurrently.
+const DEFAULT_VERIFICATION_CONCURRENCY: usize = 8;
+
+/// The validated DNSKEY responses of the signers of a response, by signer name
+type SignerKeys = BTreeMap<Name, Result<DnsResponse, ProofError>>;
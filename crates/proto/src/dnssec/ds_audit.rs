@@ -0,0 +1,326 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Consistency audit of the DS RRset of a parent zone against the DNSKEY RRset of its child
+//!
+//! Validation only needs one DS record that matches a key of the child. An operator rolling keys
+//! also wants to know about the DS records that match nothing and the keys without a DS record,
+//! which [`audit_ds`] reports.
+
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use super::rdata::{DNSKEY, DS};
+use super::{Algorithm, DigestType, Verifier};
+use crate::rr::Name;
+
+/// Matches every DS record of `ds_records` against the `dnskeys` of the child zone `name`
+///
+/// A DS record matches a key if the key tag, the algorithm and the digest of the key agree with
+/// it. Only the records are compared, the signatures over both RRsets are not verified.
+pub fn audit_ds<'a>(name: &Name, ds_records: &'a [DS], dnskeys: &'a [DNSKEY]) -> DsAudit<'a> {
+    let entries = ds_records
+        .iter()
+        .map(|ds| DsEntry {
+            ds,
+            status: ds_status(name, ds, dnskeys),
+        })
+        .collect();
+
+    DsAudit { entries, dnskeys }
+}
+
+fn ds_status<'a>(name: &Name, ds: &DS, dnskeys: &'a [DNSKEY]) -> DsStatus<'a> {
+    if !ds.algorithm().is_supported() {
+        return DsStatus::UnsupportedAlgorithm(ds.algorithm());
+    }
+    if !ds.digest_type().is_supported() {
+        return DsStatus::UnsupportedDigestType(ds.digest_type());
+    }
+
+    let mut status = DsStatus::Orphaned;
+    for dnskey in dnskeys {
+        if dnskey.algorithm() != ds.algorithm()
+            || dnskey.calculate_key_tag().ok() != Some(ds.key_tag())
+        {
+            continue;
+        }
+
+        let Ok(digest) = dnskey.to_digest(name, ds.digest_type()) else {
+            continue;
+        };
+        if digest.as_ref() != ds.digest() {
+            continue;
+        }
+
+        // the DS record may also be published for a zone key of the same tag, keep looking
+        if dnskey.zone_key() {
+            return DsStatus::Matches(dnskey);
+        }
+        status = DsStatus::NotZoneKey(dnskey);
+    }
+
+    status
+}
+
+/// The result of [`audit_ds`]
+#[derive(Clone, Debug)]
+pub struct DsAudit<'a> {
+    entries: Vec<DsEntry<'a>>,
+    dnskeys: &'a [DNSKEY],
+}
+
+impl<'a> DsAudit<'a> {
+    /// The status of every DS record, in the order of the DS RRset
+    pub fn entries(&self) -> &[DsEntry<'a>] {
+        &self.entries
+    }
+
+    /// The DS records that match a zone key of the child, with that key
+    pub fn matches(&self) -> impl Iterator<Item = (&'a DS, &'a DNSKEY)> + '_ {
+        self.entries.iter().filter_map(|entry| match entry.status {
+            DsStatus::Matches(dnskey) => Some((entry.ds, dnskey)),
+            _ => None,
+        })
+    }
+
+    /// The DS records that can be used for validation, but match no zone key of the child
+    pub fn orphaned(&self) -> impl Iterator<Item = &'a DS> + '_ {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.status, DsStatus::Orphaned | DsStatus::NotZoneKey(_)))
+            .map(|entry| entry.ds)
+    }
+
+    /// The DS records with an algorithm or a digest type that is not supported
+    pub fn unsupported(&self) -> impl Iterator<Item = &'a DS> + '_ {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status.is_unsupported())
+            .map(|entry| entry.ds)
+    }
+
+    /// The keys of the child that no DS record matches
+    ///
+    /// These are usually zone signing keys, which are signed by a key signing key instead.
+    pub fn keys_without_ds(&self) -> impl Iterator<Item = &'a DNSKEY> + '_ {
+        self.dnskeys.iter().filter(|dnskey| {
+            !self
+                .matches()
+                .any(|(_, matched)| core::ptr::eq(matched, *dnskey))
+        })
+    }
+
+    /// Whether a DS record matches a zone key, so that the chain of trust reaches the child
+    pub fn is_linked(&self) -> bool {
+        self.matches().next().is_some()
+    }
+
+    /// Whether every DS record is unsupported, in which case the child is insecure (RFC 4035
+    /// section 5.2)
+    pub fn is_unsupported(&self) -> bool {
+        !self.entries.is_empty()
+            && self
+                .entries
+                .iter()
+                .all(|entry| entry.status.is_unsupported())
+    }
+}
+
+/// Lists the problems of the audit, e.g. as the text of an extended DNS error
+impl Display for DsAudit<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut problems = self
+            .entries
+            .iter()
+            .filter(|entry| !matches!(entry.status, DsStatus::Matches(_)));
+
+        match problems.next() {
+            Some(first) => {
+                write!(f, "{first}")?;
+                for entry in problems {
+                    write!(f, "; {entry}")?;
+                }
+                Ok(())
+            }
+            None if self.entries.is_empty() => f.write_str("no DS records"),
+            None => f.write_str("all DS records match a DNSKEY"),
+        }
+    }
+}
+
+/// A DS record and how it matches the keys of the child
+#[derive(Clone, Debug)]
+pub struct DsEntry<'a> {
+    /// The DS record
+    pub ds: &'a DS,
+    /// How the DS record matches the keys of the child
+    pub status: DsStatus<'a>,
+}
+
+impl Display for DsEntry<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DS {} {} {}: {}",
+            self.ds.key_tag(),
+            u8::from(self.ds.algorithm()),
+            u8::from(self.ds.digest_type()),
+            self.status
+        )
+    }
+}
+
+/// How a DS record matches the keys of the child
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum DsStatus<'a> {
+    /// The DS record matches this zone key
+    Matches(&'a DNSKEY),
+    /// The DS record matches this key, which does not have the zone key flag and must therefore
+    /// not be used for validation (RFC 4034 section 5.2)
+    NotZoneKey(&'a DNSKEY),
+    /// The DS record matches no key
+    Orphaned,
+    /// The algorithm of the DS record is not supported
+    UnsupportedAlgorithm(Algorithm),
+    /// The digest type of the DS record is not supported
+    UnsupportedDigestType(DigestType),
+}
+
+impl DsStatus<'_> {
+    /// Whether the DS record can not be used for validation by this implementation
+    pub fn is_unsupported(&self) -> bool {
+        matches!(
+            self,
+            Self::UnsupportedAlgorithm(_) | Self::UnsupportedDigestType(_)
+        )
+    }
+}
+
+impl Display for DsStatus<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Matches(_) => f.write_str("matches a DNSKEY"),
+            Self::NotZoneKey(_) => f.write_str("matches a DNSKEY without the zone key flag"),
+            Self::Orphaned => f.write_str("matches no DNSKEY"),
+            Self::UnsupportedAlgorithm(algorithm) => {
+                write!(f, "unsupported algorithm {}", u8::from(*algorithm))
+            }
+            Self::UnsupportedDigestType(digest_type) => {
+                write!(f, "unsupported digest type {}", u8::from(*digest_type))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    use super::*;
+    use crate::dnssec::{PublicKey, PublicKeyBuf, SigningKey, crypto::EcdsaSigningKey};
+
+    fn dnskey(zone_key: bool, secure_entry_point: bool) -> DNSKEY {
+        let algorithm = Algorithm::ECDSAP256SHA256;
+        let pkcs8 = EcdsaSigningKey::generate_pkcs8(algorithm).unwrap();
+        let signing_key = EcdsaSigningKey::from_pkcs8(&pkcs8, algorithm).unwrap();
+        DNSKEY::new(
+            zone_key,
+            secure_entry_point,
+            false,
+            PublicKeyBuf::new(
+                signing_key.to_public_key().unwrap().public_bytes().to_vec(),
+                algorithm,
+            ),
+        )
+    }
+
+    fn ds(name: &Name, dnskey: &DNSKEY, digest_type: DigestType) -> DS {
+        DS::new(
+            dnskey.calculate_key_tag().unwrap(),
+            dnskey.algorithm(),
+            digest_type,
+            dnskey
+                .to_digest(name, digest_type)
+                .unwrap()
+                .as_ref()
+                .to_vec(),
+        )
+    }
+
+    #[test]
+    fn test_audit_ds() {
+        let name = Name::parse("example.com.", None).unwrap();
+        let ksk = dnskey(true, true);
+        let zsk = dnskey(true, false);
+        let removed = dnskey(true, true);
+        let not_zone_key = dnskey(false, true);
+
+        let ds_records = vec![
+            ds(&name, &ksk, DigestType::SHA256),
+            ds(&name, &removed, DigestType::SHA256),
+            ds(&name, &not_zone_key, DigestType::SHA384),
+            DS::new(1, Algorithm::Unknown(200), DigestType::SHA256, vec![0; 32]),
+            DS::new(2, Algorithm::ED25519, DigestType::Unknown(6), vec![0; 32]),
+        ];
+        let dnskeys = vec![ksk.clone(), zsk.clone(), not_zone_key];
+
+        let audit = audit_ds(&name, &ds_records, &dnskeys);
+        assert!(audit.is_linked());
+        assert!(!audit.is_unsupported());
+
+        let matches = audit.matches().collect::<Vec<_>>();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].0, &ds_records[0]);
+        assert_eq!(matches[0].1, &ksk);
+
+        assert_eq!(
+            audit.orphaned().collect::<Vec<_>>(),
+            vec![&ds_records[1], &ds_records[2]]
+        );
+        assert!(matches!(audit.entries()[2].status, DsStatus::NotZoneKey(_)));
+        assert_eq!(
+            audit.unsupported().collect::<Vec<_>>(),
+            vec![&ds_records[3], &ds_records[4]]
+        );
+        assert_eq!(
+            audit.keys_without_ds().collect::<Vec<_>>(),
+            vec![&dnskeys[1], &dnskeys[2]]
+        );
+
+        let text = audit.to_string();
+        assert!(text.contains(&format!(
+            "DS {} 13 2: matches no DNSKEY",
+            removed.calculate_key_tag().unwrap()
+        )));
+        assert!(text.contains("DS 1 200 2: unsupported algorithm 200"));
+        assert!(text.contains("DS 2 15 6: unsupported digest type 6"));
+    }
+
+    #[test]
+    fn test_audit_ds_unsupported() {
+        let name = Name::parse("example.com.", None).unwrap();
+        let dnskeys = vec![dnskey(true, true)];
+        let ds_records = vec![DS::new(
+            1,
+            Algorithm::Unknown(200),
+            DigestType::SHA256,
+            vec![0; 32],
+        )];
+
+        let audit = audit_ds(&name, &ds_records, &dnskeys);
+        assert!(!audit.is_linked());
+        assert!(audit.is_unsupported());
+
+        let audit = audit_ds(&name, &[], &dnskeys);
+        assert!(!audit.is_unsupported());
+        assert_eq!(audit.to_string(), "no DS records");
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! MD5 message digest, [RFC 1321](https://www.rfc-editor.org/rfc/rfc1321)
+//!
+//! Only used where a protocol mandates it, like the key derivation of the Diffie-Hellman mode of
+//! TKEY, the cryptographic backends don't provide it.
+
+/// Incremental MD5 digest
+pub(crate) struct Md5 {
+    state: [u32; 4],
+    block: [u8; 64],
+    len: u64,
+}
+
+impl Md5 {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            block: [0; 64],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let offset = (self.len % 64) as usize;
+            let take = data.len().min(64 - offset);
+            self.block[offset..offset + take].copy_from_slice(&data[..take]);
+            self.len += take as u64;
+            data = &data[take..];
+
+            if offset + take == 64 {
+                let block = self.block;
+                self.compress(&block);
+            }
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> [u8; 16] {
+        let bits = self.len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.len % 64 != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_le_bytes());
+
+        let mut digest = [0; 16];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut words = [0u32; 16];
+        for (word, chunk) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(SINES[i])
+                .wrapping_add(words[g])
+                .rotate_left(SHIFTS[i / 16][i % 4]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+
+        for (state, word) in self.state.iter_mut().zip([a, b, c, d]) {
+            *state = state.wrapping_add(word);
+        }
+    }
+}
+
+/// The per round shift amounts, RFC 1321, section 3.4
+const SHIFTS: [[u32; 4]; 4] = [
+    [7, 12, 17, 22],
+    [5, 9, 14, 20],
+    [4, 11, 16, 23],
+    [6, 10, 15, 21],
+];
+
+/// The integer part of 2^32 * abs(sin(i + 1)), RFC 1321, section 3.4
+const SINES: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn md5(data: &[u8]) -> [u8; 16] {
+        let mut md5 = Md5::new();
+        md5.update(data);
+        md5.finish()
+    }
+
+    #[test]
+    fn test_rfc1321_suite() {
+        let hex = |digest: [u8; 16]| data_encoding::HEXLOWER.encode(&digest);
+        assert_eq!(hex(md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(md5(b"abc")), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            hex(md5(b"abcdefghijklmnopqrstuvwxyz")),
+            "c3fcd3d76192e4007dfb496cca67e13b"
+        );
+        assert_eq!(
+            hex(md5(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            )),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+
+    #[test]
+    fn test_incremental() {
+        let data = [0x5a; 200];
+        let mut md5_parts = Md5::new();
+        for chunk in data.chunks(7) {
+            md5_parts.update(chunk);
+        }
+        assert_eq!(md5_parts.finish(), md5(&data));
+    }
+}
@@ -13,7 +13,7 @@ use core::fmt;
 
 #[cfg(feature = "backtrace")]
 use backtrace::Backtrace;
-use rdata::tsig::TsigAlgorithm;
+use rdata::tsig::{TsigAlgorithm, TsigError};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -23,18 +23,28 @@ use crate::error::{ProtoError, ProtoErrorKind};
 use crate::trace;
 
 mod algorithm;
+mod clock;
+// validating requires sending queries, which needs a source of random ids
+#[cfg(any(feature = "std", feature = "no-std-rand"))]
 mod dnssec_dns_handle;
+#[cfg(any(feature = "std", feature = "no-std-rand"))]
 pub use dnssec_dns_handle::DnssecDnsHandle;
 #[doc(hidden)]
+#[cfg(any(feature = "std", feature = "no-std-rand"))]
 pub use dnssec_dns_handle::verify_nsec;
 /// Cryptographic backend implementations of DNSSEC traits.
 pub mod crypto;
+pub mod dane;
+mod ds_audit;
 mod ec_public_key;
+mod md5;
 mod nsec3;
 pub mod proof;
 pub mod public_key;
 pub mod rdata;
+mod relay;
 mod rsa_public_key;
+mod sig0;
 mod signer;
 mod supported_algorithm;
 pub mod tbs;
@@ -43,9 +53,15 @@ pub mod tsig;
 mod verifier;
 
 pub use self::algorithm::Algorithm;
+pub use self::clock::Clock;
+#[cfg(feature = "std")]
+pub use self::clock::SystemClock;
+pub use self::ds_audit::{DsAudit, DsEntry, DsStatus, audit_ds};
 pub use self::nsec3::Nsec3HashAlgorithm;
 pub use self::proof::{Proof, ProofError, ProofErrorKind, ProofFlags, Proven};
 pub use self::public_key::{PublicKey, PublicKeyBuf};
+pub use self::relay::{Authentication, RelayError, RelayedRequest};
+pub use self::sig0::Sig0Keyring;
 pub use self::signer::SigSigner;
 pub use self::supported_algorithm::SupportedAlgorithms;
 pub use self::tbs::TBS;
@@ -240,11 +256,11 @@ pub enum DnsSecErrorKind {
 
     /// A ring error
     #[error("ring error: {0}")]
-    RingKeyRejected(#[from] ring_like::KeyRejected),
+    RingKeyRejected(#[cfg_attr(feature = "std", from)] ring_like::KeyRejected),
 
     /// A ring error
     #[error("ring error: {0}")]
-    RingUnspecified(#[from] ring_like::Unspecified),
+    RingUnspecified(#[cfg_attr(feature = "std", from)] ring_like::Unspecified),
 
     /// A request timed out
     #[error("request timed out")]
@@ -258,6 +274,25 @@ pub enum DnsSecErrorKind {
     /// Tsig key verification failed
     #[error("Tsig key wrong key error")]
     TsigWrongKey,
+
+    /// The server answered a TKEY query with an error
+    #[error("TKEY error: {0:?}")]
+    Tkey(TsigError),
+}
+
+// ring only implements `Error` for its errors with `std`, so these can't be sources without it
+#[cfg(not(feature = "std"))]
+impl From<ring_like::KeyRejected> for DnsSecErrorKind {
+    fn from(e: ring_like::KeyRejected) -> Self {
+        Self::RingKeyRejected(e)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<ring_like::Unspecified> for DnsSecErrorKind {
+    fn from(e: ring_like::Unspecified) -> Self {
+        Self::RingUnspecified(e)
+    }
 }
 
 impl Clone for DnsSecErrorKind {
@@ -274,6 +309,7 @@ impl Clone for DnsSecErrorKind {
             Timeout => Timeout,
             TsigUnsupportedMacAlgorithm(alg) => TsigUnsupportedMacAlgorithm(alg.clone()),
             TsigWrongKey => TsigWrongKey,
+            Tkey(error) => Tkey(*error),
         }
     }
 }
@@ -290,7 +326,7 @@ pub(crate) mod aws_lc_rs_impl {
         digest,
         error::{KeyRejected, Unspecified},
         hmac,
-        rand::SystemRandom,
+        rand::{SecureRandom, SystemRandom},
         rsa::PublicKeyComponents,
         signature::{
             self, ECDSA_P256_SHA256_FIXED_SIGNING, ECDSA_P384_SHA384_FIXED_SIGNING,
@@ -306,7 +342,7 @@ pub(crate) mod ring_impl {
         digest,
         error::{KeyRejected, Unspecified},
         hmac,
-        rand::SystemRandom,
+        rand::{SecureRandom, SystemRandom},
         rsa::PublicKeyComponents,
         signature::{
             self, ECDSA_P256_SHA256_FIXED_SIGNING, ECDSA_P384_SHA384_FIXED_SIGNING,
@@ -112,6 +112,7 @@ impl fmt::Display for Proof {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Proof {}
 
 #[test]
@@ -76,7 +76,9 @@ use super::DNSSECRData;
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct DNSKEY {
+    /// The raw flags field of the key
     pub flags: u16,
+    /// The public key material
     pub public_key: PublicKeyBuf,
 }
 
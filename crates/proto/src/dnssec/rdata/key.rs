@@ -502,7 +502,7 @@ impl fmt::Display for KEY {
             "{flags} {proto} {alg} {key}",
             flags = self.flags(),
             proto = u8::from(self.protocol),
-            alg = self.algorithm,
+            alg = u8::from(self.algorithm),
             key = data_encoding::BASE64.encode(&self.public_key)
         )
     }
@@ -516,24 +516,19 @@ impl From<KEY> for RData {
 
 /// Specifies in what contexts this key may be trusted for use
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
 pub enum KeyTrust {
     /// Use of the key is prohibited for authentication
     NotAuth,
     /// Use of the key is prohibited for confidentiality
     NotPrivate,
     /// Use of the key for authentication and/or confidentiality is permitted
+    #[default]
     AuthOrPrivate,
     /// If both bits are one, the "no key" value, (revocation?)
     DoNotTrust,
 }
 
-impl Default for KeyTrust {
-    fn default() -> Self {
-        Self::AuthOrPrivate
-    }
-}
-
 impl From<u16> for KeyTrust {
     fn from(flags: u16) -> Self {
         // we only care about the first two bits, zero out the rest
@@ -567,7 +562,7 @@ impl From<KeyTrust> for u16 {
 }
 
 /// Declares what this key is for
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum KeyUsage {
     /// key associated with a "user" or "account" at an end entity, usually a host
@@ -576,17 +571,12 @@ pub enum KeyUsage {
     #[deprecated = "For Zone signing DNSKEY should be used"]
     Zone,
     /// associated with the non-zone "entity" whose name is the RR owner name
+    #[default]
     Entity,
     /// Reserved
     Reserved,
 }
 
-impl Default for KeyUsage {
-    fn default() -> Self {
-        Self::Entity
-    }
-}
-
 impl From<u16> for KeyUsage {
     fn from(flags: u16) -> Self {
         // we only care about the 6&7 two bits, zero out the rest
@@ -823,7 +813,7 @@ impl From<UpdateScope> for u16 {
 /// All Protocol Octet values except DNSSEC (3) are eliminated
 /// ```
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
 pub enum Protocol {
     /// Not in use
     #[deprecated = "Deprecated by RFC3445"]
@@ -835,6 +825,7 @@ pub enum Protocol {
     #[deprecated = "Deprecated by RFC3445"]
     Email,
     /// Reserved for use with DNSSEC (Hickory DNS only supports DNSKEY with DNSSEC)
+    #[default]
     DNSSEC,
     /// Reserved to refer to the Oakley/IPSEC
     #[deprecated = "Deprecated by RFC3445"]
@@ -847,12 +838,6 @@ pub enum Protocol {
     All,
 }
 
-impl Default for Protocol {
-    fn default() -> Self {
-        Self::DNSSEC
-    }
-}
-
 impl From<u8> for Protocol {
     fn from(field: u8) -> Self {
         match field {
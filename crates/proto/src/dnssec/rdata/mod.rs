@@ -26,6 +26,7 @@ pub mod nsec3;
 pub mod nsec3param;
 pub mod rrsig;
 pub mod sig;
+pub mod tkey;
 pub mod tsig;
 
 use enum_as_inner::EnumAsInner;
@@ -33,7 +34,10 @@ use tracing::trace;
 
 use crate::{
     error::*,
-    rr::{RData, RecordDataDecodable, RecordType, rdata::NULL},
+    rr::{
+        RData, RecordDataDecodable, RecordType,
+        rdata::{NULL, null},
+    },
     serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, Restrict},
 };
 
@@ -47,6 +51,7 @@ pub use self::nsec3::NSEC3;
 pub use self::nsec3param::NSEC3PARAM;
 pub use self::rrsig::RRSIG;
 pub use self::sig::{SIG, SigInput};
+pub use self::tkey::TKEY;
 pub use self::tsig::TSIG;
 
 /// The type of the resource record, for DNSSEC-specific records.
@@ -564,6 +569,16 @@ pub enum DNSSECRData {
     /// ```
     TSIG(TSIG),
 
+    /// [RFC 2930, Secret Key Establishment for DNS](https://www.rfc-editor.org/rfc/rfc2930#section-2)
+    ///
+    /// ```text
+    /// 2. The TKEY Resource Record
+    ///
+    ///    The TKEY resource record (RR) has the structure given below.  Its RR
+    ///    type code is 249.
+    /// ```
+    TKEY(TKEY),
+
     /// Unknown or unsupported DNSSEC record data
     Unknown {
         /// RecordType code
@@ -624,6 +639,10 @@ impl DNSSECRData {
                 trace!("reading TSIG");
                 TSIG::read_data(decoder, rdata_length).map(Self::TSIG)
             }
+            RecordType::TKEY => {
+                trace!("reading TKEY");
+                TKEY::read_data(decoder, rdata_length).map(Self::TKEY)
+            }
             r => {
                 panic!("not a dnssec RecordType: {}", r);
             }
@@ -643,6 +662,7 @@ impl DNSSECRData {
             Self::RRSIG(rrsig) => rrsig.emit(encoder),
             Self::SIG(sig) => sig.emit(encoder),
             Self::TSIG(tsig) => tsig.emit(encoder),
+            Self::TKEY(tkey) => tkey.emit(encoder),
             Self::Unknown { rdata, .. } => rdata.emit(encoder),
         }
     }
@@ -660,6 +680,7 @@ impl DNSSECRData {
             Self::SIG(..) => RecordType::SIG,
             Self::RRSIG(..) => RecordType::RRSIG,
             Self::TSIG(..) => RecordType::TSIG,
+            Self::TKEY(..) => RecordType::TKEY,
             Self::Unknown { code, .. } => RecordType::Unknown(*code),
         }
     }
@@ -682,7 +703,9 @@ impl fmt::Display for DNSSECRData {
             Self::NSEC3PARAM(nsec3param) => w(f, nsec3param),
             Self::SIG(sig) => w(f, sig),
             Self::RRSIG(rrsig) => w(f, rrsig),
-            Self::TSIG(tsig) => w(f, tsig),
+            // TSIG and TKEY have no presentation format, their own Display is only informational
+            Self::TSIG(tsig) => null::write_generic(f, &tsig.to_bytes().map_err(|_| fmt::Error)?),
+            Self::TKEY(tkey) => null::write_generic(f, &tkey.to_bytes().map_err(|_| fmt::Error)?),
             Self::Unknown { rdata, .. } => w(f, rdata),
         }
     }
@@ -132,6 +132,7 @@ impl NSEC {
         self.type_bit_maps.iter()
     }
 
+    #[cfg_attr(not(any(feature = "std", feature = "no-std-rand")), expect(dead_code))]
     pub(crate) fn type_set(&self) -> &RecordTypeSet {
         &self.type_bit_maps
     }
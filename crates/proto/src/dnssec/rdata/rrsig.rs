@@ -28,6 +28,12 @@ use crate::{
 pub struct RRSIG(pub(super) SIG);
 
 impl RRSIG {
+    /// Wraps the SIG record data, e.g. when it was parsed from its presentation format
+    #[cfg(any(feature = "arbitrary", feature = "text-parsing"))]
+    pub(crate) fn from_sig(sig: SIG) -> Self {
+        Self(sig)
+    }
+
     /// Creates a new RRSIG record data from the given record set and signer.
     pub fn from_rrset(
         rr_set: &RecordSet,
@@ -364,7 +364,7 @@ impl fmt::Display for SIG {
             f,
             "{ty_covered} {alg} {num_labels} {original_ttl} {expire} {inception} {tag} {signer} {sig}",
             ty_covered = self.input.type_covered,
-            alg = self.input.algorithm,
+            alg = u8::from(self.input.algorithm),
             num_labels = self.input.num_labels,
             original_ttl = self.input.original_ttl,
             expire = self.input.sig_expiration.0,
@@ -0,0 +1,1062 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TKEY for the establishment of secret keys used by TSIG
+//!
+//! [RFC 2930, Secret Key Establishment for DNS (TKEY RR)](https://www.rfc-editor.org/rfc/rfc2930)
+//!
+//! A TKEY query has the name of the key as its question, with the type TKEY, and carries a TKEY
+//! record in the additional section. The server answers with a TKEY record in the answer section,
+//! see [`tkey_query`] and [`tkey_response`].
+//!
+//! - In the Diffie-Hellman mode the resolver adds a KEY record with its Diffie-Hellman public key
+//!   to the additional section of the query, and the server returns its own KEY record in the
+//!   answer section. The key data of the TKEY records are nonces mixed into the derived key, see
+//!   [`diffie_hellman_query`] and [`diffie_hellman_response`], RFC 2930, section 4.1.
+//! - In the GSS-API mode the key data of the TKEY records carry the tokens of the GSS-API context
+//!   establishment, which are exchanged until the context is complete, RFC 3645, section 3.1.
+
+#[cfg(any(feature = "dnssec-aws-lc-rs", feature = "dnssec-ring"))]
+use alloc::vec;
+use alloc::{format, vec::Vec};
+use core::{convert::TryInto, fmt};
+
+use num_bigint::BigUint;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::tsig::{TsigAlgorithm, TsigError};
+#[allow(deprecated)]
+use super::{
+    DNSSECRData, KEY,
+    key::{KeyTrust, KeyUsage, Protocol, UpdateScope},
+};
+#[cfg(any(feature = "dnssec-aws-lc-rs", feature = "dnssec-ring"))]
+use crate::dnssec::ring_like::{SecureRandom, SystemRandom};
+use crate::{
+    dnssec::{Algorithm, DnsSecError, DnsSecErrorKind, md5::Md5},
+    error::{ProtoError, ProtoResult},
+    op::{Message, MessageType, OpCode, Query, ResponseCode},
+    rr::{
+        Name, Record, RecordData, RecordDataDecodable, dns_class::DNSClass, rdata::sshfp,
+        record_data::RData, record_type::RecordType,
+    },
+    serialize::binary::{
+        BinDecodable, BinDecoder, BinEncodable, BinEncoder, RDataEncoding, Restrict, RestrictedMath,
+    },
+};
+
+/// [RFC 2930, Secret Key Establishment for DNS](https://www.rfc-editor.org/rfc/rfc2930#section-2)
+///
+/// ```text
+/// 2. The TKEY Resource Record
+///
+///    The TKEY resource record (RR) has the structure given below.  Its RR
+///    type code is 249.
+///
+///       Field       Type         Comment
+///       -----       ----         -------
+///
+///       NAME         domain      see description below
+///       TTYPE        u_int16_t   TKEY = 249
+///       CLASS        u_int16_t   ignored, SHOULD be 255 (ANY)
+///       TTL          u_int32_t   ignored, SHOULD be zero
+///       RDLEN        u_int16_t   size of RDATA
+///       RDATA:
+///            Algorithm:   domain
+///            Inception:   u_int32_t
+///            Expiration:  u_int32_t
+///            Mode:        u_int16_t
+///            Error:       u_int16_t
+///            Key Size:    u_int16_t
+///            Key Data:    octet-stream
+///            Other Size:  u_int16_t
+///            Other Data:  octet-stream  undefined by this specification
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct TKEY {
+    algorithm: TsigAlgorithm,
+    inception: u32,
+    expiration: u32,
+    mode: TkeyMode,
+    error: Option<TsigError>,
+    key: Vec<u8>,
+    other: Vec<u8>,
+}
+
+impl TKEY {
+    /// Constructs a new TKEY
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - the algorithm of the key being established
+    /// * `inception` - start of the validity of the key, in seconds since the epoch
+    /// * `expiration` - end of the validity of the key, in seconds since the epoch
+    /// * `mode` - the scheme used to establish the key
+    /// * `error` - the TKEY error RCODE, always none in queries
+    /// * `key` - the key data, whose meaning depends on the mode
+    /// * `other` - other data, not defined by RFC 2930
+    pub fn new(
+        algorithm: TsigAlgorithm,
+        inception: u32,
+        expiration: u32,
+        mode: TkeyMode,
+        error: Option<TsigError>,
+        key: Vec<u8>,
+        other: Vec<u8>,
+    ) -> Self {
+        Self {
+            algorithm,
+            inception,
+            expiration,
+            mode,
+            error,
+            key,
+            other,
+        }
+    }
+
+    /// A TKEY for the query of a Diffie-Hellman exchange, RFC 2930, section 4.1
+    ///
+    /// The `nonce` is mixed into the derived key, see [`diffie_hellman_query`].
+    pub fn diffie_hellman(
+        algorithm: TsigAlgorithm,
+        inception: u32,
+        expiration: u32,
+        nonce: Vec<u8>,
+    ) -> Self {
+        Self::new(
+            algorithm,
+            inception,
+            expiration,
+            TkeyMode::DiffieHellman,
+            None,
+            nonce,
+            Vec::new(),
+        )
+    }
+
+    /// A TKEY for a query of a GSS-API context establishment, RFC 3645, section 3.1.1
+    ///
+    /// `token` is the output token of the last call to `GSS_Init_sec_context`.
+    pub fn gss_api(inception: u32, expiration: u32, token: Vec<u8>) -> Self {
+        Self::new(
+            TsigAlgorithm::Gss,
+            inception,
+            expiration,
+            TkeyMode::GssApi,
+            None,
+            token,
+            Vec::new(),
+        )
+    }
+
+    /// A TKEY for the deletion of the key used to sign the query, RFC 2930, section 4.5
+    pub fn deletion(algorithm: TsigAlgorithm) -> Self {
+        Self::new(
+            algorithm,
+            0,
+            0,
+            TkeyMode::KeyDeletion,
+            None,
+            Vec::new(),
+            Vec::new(),
+        )
+    }
+
+    /// Returns the algorithm of the key being established
+    pub fn algorithm(&self) -> &TsigAlgorithm {
+        &self.algorithm
+    }
+
+    /// Returns the start of the validity of the key, in seconds since the epoch
+    pub fn inception(&self) -> u32 {
+        self.inception
+    }
+
+    /// Returns the end of the validity of the key, in seconds since the epoch
+    pub fn expiration(&self) -> u32 {
+        self.expiration
+    }
+
+    /// Returns the scheme used to establish the key
+    pub fn mode(&self) -> TkeyMode {
+        self.mode
+    }
+
+    /// Returns the TKEY error RCODE
+    ///
+    /// This is separate from the top-level error RCODE of a response
+    pub fn error(&self) -> &Option<TsigError> {
+        &self.error
+    }
+
+    /// Set the TKEY error RCODE
+    pub fn set_error(&mut self, error: TsigError) {
+        self.error = Some(error)
+    }
+
+    /// Returns the key data, e.g. the nonce of a Diffie-Hellman exchange or a GSS-API token
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Returns the other data
+    pub fn other(&self) -> &[u8] {
+        &self.other
+    }
+}
+
+impl BinEncodable for TKEY {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        let mut encoder = encoder.with_rdata_behavior(RDataEncoding::Other);
+        self.algorithm.emit(&mut encoder)?;
+        encoder.emit_u32(self.inception)?;
+        encoder.emit_u32(self.expiration)?;
+        encoder.emit_u16(self.mode.into())?;
+        encoder.emit_u16(match self.error {
+            None => 0,
+            Some(err) => u16::from(err),
+        })?;
+        encoder.emit_u16(
+            self.key
+                .len()
+                .try_into()
+                .map_err(|_| ProtoError::from("invalid key data, longer than 65535 B in TKEY"))?,
+        )?;
+        encoder.emit_vec(&self.key)?;
+        encoder.emit_u16(
+            self.other
+                .len()
+                .try_into()
+                .map_err(|_| ProtoError::from("invalid other data, longer than 65535 B in TKEY"))?,
+        )?;
+        encoder.emit_vec(&self.other)?;
+        Ok(())
+    }
+}
+
+impl<'r> RecordDataDecodable<'r> for TKEY {
+    fn read_data(decoder: &mut BinDecoder<'r>, length: Restrict<u16>) -> ProtoResult<Self> {
+        let end_idx = length.map(|rdl| rdl as usize)
+        .checked_add(decoder.index())
+        .map_err(|_| ProtoError::from("rdata end position overflow"))? // no legal message is long enough to trigger that
+        .unverified(/*used only as length safely*/);
+
+        let algorithm = TsigAlgorithm::read(decoder)?;
+        let inception = decoder.read_u32()?.unverified(/*valid as any u32*/);
+        let expiration = decoder.read_u32()?.unverified(/*valid as any u32*/);
+        let mode = TkeyMode::from(decoder.read_u16()?.unverified(/*valid as any u16*/));
+        let error = match decoder.read_u16()?.unverified(/*valid as any u16*/) {
+            0 => None,
+            code => Some(TsigError::from(code)),
+        };
+        let key_size = decoder
+            .read_u16()?
+            .verify_unwrap(|&size| decoder.index() + size as usize + 2 /* 1 u16 */ <= end_idx)
+            .map_err(|_| ProtoError::from("invalid key size in TKEY"))?;
+        let key =
+            decoder.read_vec(key_size as usize)?.unverified(/*valid as any vec of the right size*/);
+        let other_size = decoder
+            .read_u16()?
+            .verify_unwrap(|&size| decoder.index() + size as usize == end_idx)
+            .map_err(|_| ProtoError::from("invalid other size in TKEY"))?;
+        let other = decoder.read_vec(other_size as usize)?.unverified(/*valid as any vec of the right size*/);
+
+        Ok(Self {
+            algorithm,
+            inception,
+            expiration,
+            mode,
+            error,
+            key,
+            other,
+        })
+    }
+}
+
+impl RecordData for TKEY {
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::DNSSEC(DNSSECRData::TKEY(tkey)) => Some(tkey),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::TKEY
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::DNSSEC(DNSSECRData::TKEY(self))
+    }
+}
+
+// Does not have a presentation format, this is only informational
+impl fmt::Display for TKEY {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{algorithm} {inception} {expiration} {mode} {error} {key} {other}",
+            algorithm = self.algorithm,
+            inception = self.inception,
+            expiration = self.expiration,
+            mode = u16::from(self.mode),
+            error = self.error.map(Into::into).unwrap_or(0),
+            key = sshfp::HEX.encode(&self.key),
+            other = sshfp::HEX.encode(&self.other),
+        )
+    }
+}
+
+/// The scheme used to establish a key
+///
+/// [RFC 2930, Secret Key Establishment for DNS](https://www.rfc-editor.org/rfc/rfc2930#section-2.5)
+///
+/// ```text
+///       Value    Description
+///       -----    -----------
+///        0        - reserved, see section 7
+///        1       server assignment
+///        2       Diffie-Hellman exchange
+///        3       GSS-API negotiation
+///        4       resolver assignment
+///        5       key deletion
+///       6-65534   - available, see section 7
+///       65535     - reserved, see section 7
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum TkeyMode {
+    /// The server generates the key and returns it encrypted for the resolver
+    ServerAssignment,
+    /// The key is derived from a Diffie-Hellman exchange
+    DiffieHellman,
+    /// The key is the context established by a GSS-API negotiation, RFC 3645
+    GssApi,
+    /// The resolver generates the key and sends it encrypted for the server
+    ResolverAssignment,
+    /// Deletes the key used to sign the query
+    KeyDeletion,
+    /// A reserved or unassigned mode
+    Unknown(u16),
+}
+
+impl From<u16> for TkeyMode {
+    fn from(value: u16) -> Self {
+        match value {
+            1 => Self::ServerAssignment,
+            2 => Self::DiffieHellman,
+            3 => Self::GssApi,
+            4 => Self::ResolverAssignment,
+            5 => Self::KeyDeletion,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<TkeyMode> for u16 {
+    fn from(mode: TkeyMode) -> Self {
+        match mode {
+            TkeyMode::ServerAssignment => 1,
+            TkeyMode::DiffieHellman => 2,
+            TkeyMode::GssApi => 3,
+            TkeyMode::ResolverAssignment => 4,
+            TkeyMode::KeyDeletion => 5,
+            TkeyMode::Unknown(value) => value,
+        }
+    }
+}
+
+/// Helper function to make a TKEY record from the name of the key, and the TKEY RData
+pub fn make_tkey_record(name: Name, rdata: TKEY) -> Record {
+    // https://www.rfc-editor.org/rfc/rfc2930#section-2
+    //   CLASS        u_int16_t   ignored, SHOULD be 255 (ANY)
+    //   TTL          u_int32_t   ignored, SHOULD be zero
+    let mut tkey = Record::from_rdata(name, 0, DNSSECRData::TKEY(rdata).into());
+    tkey.set_dns_class(DNSClass::ANY);
+    tkey
+}
+
+/// Builds the query establishing the key `key_name` as requested by `rdata`
+///
+/// ```text
+/// 4. Exchanges
+///
+///    TKEY is a meta-RR that is not stored or cached in the DNS and does
+///    not appear in zone files.  It supports a variety of modes for the
+///    establishment and deletion of shared secret keys information between
+///    DNS resolvers and servers.  The establishment of such a shared key
+///    requires that state be maintained at both ends and the allocation of
+///    the resources to maintain such state may require mutual agreement.
+/// ```
+///
+/// The query must be signed if the mode requires it, e.g. with the key to be deleted for
+/// [`TkeyMode::KeyDeletion`]. Further records, like the KEY record of the Diffie-Hellman mode,
+/// are added to the additional section before the query is signed.
+pub fn tkey_query(id: u16, key_name: Name, rdata: TKEY) -> Message {
+    let mut query = Query::query(key_name.clone(), RecordType::TKEY);
+    query.set_query_class(DNSClass::ANY);
+
+    let mut message = Message::new(id, MessageType::Query, OpCode::Query);
+    message
+        .add_query(query)
+        .add_additional(make_tkey_record(key_name, rdata));
+    message
+}
+
+/// Returns the TKEY record answering a query built by [`tkey_query`]
+///
+/// Fails if the server returned an error, either in the RCODE of the response or the TKEY
+/// record, or if the answer doesn't match the key or mode of the query.
+pub fn tkey_response<'a>(
+    response: &'a Message,
+    key_name: &Name,
+    mode: TkeyMode,
+) -> Result<&'a TKEY, DnsSecError> {
+    if response.message_type() != MessageType::Response {
+        return Err(DnsSecErrorKind::Message("TKEY response is not a response").into());
+    }
+
+    if response.response_code() != ResponseCode::NoError {
+        return Err(DnsSecErrorKind::Msg(format!(
+            "TKEY query failed: {}",
+            response.response_code()
+        ))
+        .into());
+    }
+
+    let tkey = response
+        .answers()
+        .iter()
+        .filter(|record| record.name().eq_ignore_root(key_name))
+        .find_map(|record| TKEY::try_borrow(record.data()))
+        .ok_or(DnsSecErrorKind::Message("no TKEY record in response"))?;
+
+    if let Some(error) = tkey.error {
+        return Err(DnsSecErrorKind::Tkey(error).into());
+    }
+
+    if tkey.mode != mode {
+        return Err(DnsSecErrorKind::Message("TKEY response has a different mode").into());
+    }
+
+    Ok(tkey)
+}
+
+/// Returns the Diffie-Hellman public keys of the server from the answer section of a response
+///
+/// ```text
+/// 4.1 Query for Diffie-Hellman Exchanged Keying
+///
+///    If the server supports the Diffie-Hellman mode and the
+///    query is valid, it returns ... a TKEY RR in the answer
+///    section specifying the Diffie-Hellman mode and a KEY RR in
+///    the answer section giving its Diffie-Hellman key.
+/// ```
+pub fn server_keys(response: &Message) -> impl Iterator<Item = (&Name, &KEY)> + '_ {
+    response.answers().iter().filter_map(|record| {
+        KEY::try_borrow(record.data())
+            .filter(|key| key.algorithm() == DIFFIE_HELLMAN)
+            .map(|key| (record.name(), key))
+    })
+}
+
+/// Builds the query of a Diffie-Hellman exchange establishing the key `key_name`
+///
+/// ```text
+/// 4.1 Query for Diffie-Hellman Exchanged Keying
+///
+///    A resolver sends a query for type TKEY accompanied by a TKEY RR in
+///    the additional information section specifying the Diffie-Hellman
+///    mode and accompanied by a KEY RR also in the additional information
+///    section specifying a resolver Diffie-Hellman key.
+/// ```
+///
+/// The KEY record of `key_pair` is owned by `key_owner`, the name of the resolver's key. `rdata`
+/// is usually built with [`TKEY::diffie_hellman`], its key data is the nonce of the resolver.
+pub fn diffie_hellman_query(
+    id: u16,
+    key_name: Name,
+    rdata: TKEY,
+    key_owner: Name,
+    key_pair: &DhKeyPair,
+) -> Message {
+    let mut message = tkey_query(id, key_name, rdata);
+    message.add_additional(Record::from_rdata(
+        key_owner,
+        0,
+        DNSSECRData::KEY(key_pair.to_key()).into(),
+    ));
+    message
+}
+
+/// Derives the key established by the response to a query of [`diffie_hellman_query`]
+///
+/// `query_nonce` is the key data of the TKEY record of the query.
+pub fn diffie_hellman_response(
+    response: &Message,
+    key_name: &Name,
+    query_nonce: &[u8],
+    key_pair: &DhKeyPair,
+) -> Result<Vec<u8>, DnsSecError> {
+    let tkey = tkey_response(response, key_name, TkeyMode::DiffieHellman)?;
+    let server_key = server_keys(response)
+        .filter_map(|(_, key)| DhPublicKey::from_bytes(key.public_key()).ok())
+        .find(|key| key.prime == key_pair.prime() && key.public != key_pair.public)
+        .ok_or(DnsSecErrorKind::Message(
+            "no Diffie-Hellman KEY of the server in TKEY response",
+        ))?;
+
+    let shared_secret = key_pair.shared_secret(&server_key)?;
+    Ok(diffie_hellman_key(&shared_secret, query_nonce, tkey.key()))
+}
+
+/// Derives the keying material of a Diffie-Hellman exchange
+///
+/// [RFC 2930, Secret Key Establishment for DNS](https://www.rfc-editor.org/rfc/rfc2930#section-4.1)
+///
+/// ```text
+///    keying material =
+///         XOR ( DH value, MD5 ( query data | DH value ) |
+///                         MD5 ( server data | DH value ) )
+///
+///    Where XOR is an exclusive-OR operation and "|" is byte-stream
+///    concatenation.  The shorter of the two operands to XOR should be
+///    byte-wise left justified and padded with zero-valued bytes to match
+///    the length of the other operand.
+/// ```
+///
+/// `shared_secret` is the DH value, see [`DhKeyPair::shared_secret`], `query_nonce` and
+/// `server_nonce` are the key data of the TKEY records of the query and of the response.
+pub fn diffie_hellman_key(
+    shared_secret: &[u8],
+    query_nonce: &[u8],
+    server_nonce: &[u8],
+) -> Vec<u8> {
+    let mut digests = Vec::with_capacity(32);
+    for nonce in [query_nonce, server_nonce] {
+        let mut md5 = Md5::new();
+        md5.update(nonce);
+        md5.update(shared_secret);
+        digests.extend_from_slice(&md5.finish());
+    }
+
+    let mut key = shared_secret.to_vec();
+    if key.len() < digests.len() {
+        key.resize(digests.len(), 0);
+    }
+
+    for (byte, digest) in key.iter_mut().zip(digests) {
+        *byte ^= digest;
+    }
+    key
+}
+
+/// A well-known Diffie-Hellman group
+///
+/// [RFC 2539, Storage of Diffie-Hellman Keys in the DNS](https://www.rfc-editor.org/rfc/rfc2539#appendix-A)
+///
+/// ```text
+///    The generator for both of these well known primes is 2.
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[non_exhaustive]
+pub enum DhGroup {
+    /// Well known prime 1, the 768 bit prime of the first Oakley group
+    Oakley768,
+    /// Well known prime 2, the 1024 bit prime of the second Oakley group
+    Oakley1024,
+}
+
+impl DhGroup {
+    fn from_index(index: u16) -> Option<Self> {
+        match index {
+            1 => Some(Self::Oakley768),
+            2 => Some(Self::Oakley1024),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> u8 {
+        match self {
+            Self::Oakley768 => 1,
+            Self::Oakley1024 => 2,
+        }
+    }
+
+    fn prime(self) -> BigUint {
+        let prime = match self {
+            Self::Oakley768 => OAKLEY_768,
+            Self::Oakley1024 => OAKLEY_1024,
+        };
+
+        BigUint::parse_bytes(prime.as_bytes(), 16).expect("well-known primes are valid hex")
+    }
+}
+
+/// Well known prime 1, RFC 2539, appendix A
+const OAKLEY_768: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A63A3620FFFFFFFFFFFFFFFF",
+);
+
+/// Well known prime 2, RFC 2539, appendix A
+const OAKLEY_1024: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE65381FFFFFFFFFFFFFFFF",
+);
+
+/// The generator of the well-known groups
+const GENERATOR: u32 = 2;
+
+/// A Diffie-Hellman public key, the public key data of a KEY record with the Diffie-Hellman
+/// algorithm
+///
+/// [RFC 2539, Storage of Diffie-Hellman Keys in the DNS](https://www.rfc-editor.org/rfc/rfc2539#section-2)
+///
+/// ```text
+///            1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///        0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///       +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///       |           KEY flags           |    protocol   |  algorithm=2  |
+///       +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///       |     prime length (or flag)    |    prime (p) (or special)     /
+///       +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///       /  prime (p) (variable length)  |       generator length        |
+///       +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///       | generator (g) (variable length)                               |
+///       +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///       |     public value length       | public value (g^i mod p)      /
+///       +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///       /    public value (g^i mod p)    (variable length)              |
+///       +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///
+///    If "prime length" field is 1 or 2, then the "prime" field is actually
+///    an unsigned index into a table of 65,536 prime/generator pairs and the
+///    generator length SHOULD be zero.
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct DhPublicKey {
+    group: Option<DhGroup>,
+    prime: BigUint,
+    generator: BigUint,
+    public: BigUint,
+}
+
+impl DhPublicKey {
+    /// Parses the public key data of a KEY record
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DnsSecError> {
+        fn read_field<'a>(decoder: &mut BinDecoder<'a>) -> Result<&'a [u8], ProtoError> {
+            let len = decoder.read_u16()?.unverified(/*checked by read_slice*/);
+            Ok(decoder.read_slice(len as usize)?.unverified(/*any value is checked below*/))
+        }
+
+        let mut decoder = BinDecoder::new(bytes);
+        let prime = read_field(&mut decoder)?;
+        let generator = read_field(&mut decoder)?;
+        let public = BigUint::from_bytes_be(read_field(&mut decoder)?);
+
+        let (group, prime, generator) = match prime.len() {
+            1 | 2 => {
+                let index = prime
+                    .iter()
+                    .fold(0, |index, byte| index << 8 | u16::from(*byte));
+                let group = DhGroup::from_index(index).ok_or(DnsSecErrorKind::Message(
+                    "unknown well-known prime in Diffie-Hellman KEY",
+                ))?;
+                (Some(group), group.prime(), BigUint::from(GENERATOR))
+            }
+            0 | 3..=15 => {
+                return Err(DnsSecErrorKind::Message(
+                    "reserved prime length in Diffie-Hellman KEY",
+                )
+                .into());
+            }
+            _ => (
+                None,
+                BigUint::from_bytes_be(prime),
+                BigUint::from_bytes_be(generator),
+            ),
+        };
+
+        Ok(Self {
+            group,
+            prime,
+            generator,
+            public,
+        })
+    }
+
+    /// Encodes the key as the public key data of a KEY record
+    ///
+    /// Keys of a well-known group refer to its prime by index.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut emit_field = |field: &[u8]| {
+            bytes.extend_from_slice(&(field.len() as u16).to_be_bytes());
+            bytes.extend_from_slice(field);
+        };
+
+        match self.group {
+            Some(group) => {
+                emit_field(&[group.index()]);
+                emit_field(&[]);
+            }
+            None => {
+                emit_field(&self.prime.to_bytes_be());
+                emit_field(&self.generator.to_bytes_be());
+            }
+        }
+        emit_field(&self.public.to_bytes_be());
+
+        bytes
+    }
+
+    /// Returns the group of the key, if it uses a well-known prime by index
+    pub fn group(&self) -> Option<DhGroup> {
+        self.group
+    }
+}
+
+/// The Diffie-Hellman key pair of one side of an exchange
+pub struct DhKeyPair {
+    group: DhGroup,
+    private: BigUint,
+    public: BigUint,
+}
+
+impl DhKeyPair {
+    /// Creates the key pair of the private value `private`, a big-endian integer
+    ///
+    /// The private value must be greater than 1 and less than the prime of the group minus 1.
+    pub fn from_private(group: DhGroup, private: &[u8]) -> Result<Self, DnsSecError> {
+        let prime = group.prime();
+        let private = BigUint::from_bytes_be(private);
+        if private <= BigUint::from(1u32) || private >= &prime - 1u32 {
+            return Err(
+                DnsSecErrorKind::Message("Diffie-Hellman private value out of range").into(),
+            );
+        }
+
+        let public = BigUint::from(GENERATOR).modpow(&private, &prime);
+        Ok(Self {
+            group,
+            private,
+            public,
+        })
+    }
+
+    /// Generates a key pair with a random private value
+    #[cfg(any(feature = "dnssec-aws-lc-rs", feature = "dnssec-ring"))]
+    pub fn generate(group: DhGroup) -> Result<Self, DnsSecError> {
+        let prime = group.prime();
+        let mut bytes = vec![0; (prime.bits() as usize).div_ceil(8)];
+        SystemRandom::new().fill(&mut bytes)?;
+
+        // map the random value to 2..=p-2
+        let private = BigUint::from_bytes_be(&bytes) % (&prime - 3u32) + 2u32;
+        Self::from_private(group, &private.to_bytes_be())
+    }
+
+    /// Returns the public key of the pair
+    pub fn public_key(&self) -> DhPublicKey {
+        DhPublicKey {
+            group: Some(self.group),
+            prime: self.prime(),
+            generator: BigUint::from(GENERATOR),
+            public: self.public.clone(),
+        }
+    }
+
+    /// Returns the KEY record data of the public key
+    pub fn to_key(&self) -> KEY {
+        KEY::new(
+            KeyTrust::default(),
+            KeyUsage::Host,
+            #[allow(deprecated)]
+            UpdateScope::default(),
+            Protocol::DNSSEC,
+            DIFFIE_HELLMAN,
+            self.public_key().to_bytes(),
+        )
+    }
+
+    /// Computes the value shared with the owner of `peer`, as a big-endian integer
+    ///
+    /// Fails if `peer` is not of the group of this key pair, or its public value is out of range.
+    pub fn shared_secret(&self, peer: &DhPublicKey) -> Result<Vec<u8>, DnsSecError> {
+        let prime = self.prime();
+        if peer.prime != prime || peer.generator != BigUint::from(GENERATOR) {
+            return Err(DnsSecErrorKind::Message("Diffie-Hellman KEY of a different group").into());
+        }
+
+        if peer.public <= BigUint::from(1u32) || peer.public >= &prime - 1u32 {
+            return Err(
+                DnsSecErrorKind::Message("Diffie-Hellman public value out of range").into(),
+            );
+        }
+
+        Ok(peer.public.modpow(&self.private, &prime).to_bytes_be())
+    }
+
+    fn prime(&self) -> BigUint {
+        self.group.prime()
+    }
+}
+
+impl fmt::Debug for DhKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DhKeyPair")
+            .field("group", &self.group)
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The Diffie-Hellman algorithm of KEY records, RFC 2539
+const DIFFIE_HELLMAN: Algorithm = Algorithm::Unknown(2);
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec;
+    use core::str::FromStr;
+
+    use super::*;
+
+    fn key_name() -> Name {
+        Name::from_str("tkey.example.com.").unwrap()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let rdata = TKEY::new(
+            TsigAlgorithm::HmacSha256,
+            1_700_000_000,
+            1_700_003_600,
+            TkeyMode::DiffieHellman,
+            Some(TsigError::BadMode),
+            vec![1, 2, 3, 4],
+            vec![5],
+        );
+
+        let bytes = rdata.to_bytes().unwrap();
+        let mut expected = b"\x0bhmac-sha256\x00".to_vec();
+        expected.extend_from_slice(&1_700_000_000u32.to_be_bytes());
+        expected.extend_from_slice(&1_700_003_600u32.to_be_bytes());
+        expected.extend_from_slice(&[0, 2, 0, 19, 0, 4, 1, 2, 3, 4, 0, 1, 5]);
+        assert_eq!(bytes, expected);
+
+        let mut decoder = BinDecoder::new(&bytes);
+        let read_rdata = TKEY::read_data(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap();
+        assert_eq!(read_rdata, rdata);
+
+        // the key data may not extend past the end of the rdata
+        let mut decoder = BinDecoder::new(&bytes);
+        assert!(TKEY::read_data(&mut decoder, Restrict::new(bytes.len() as u16 - 2)).is_err());
+    }
+
+    #[test]
+    fn test_mode() {
+        for value in 0..=6 {
+            assert_eq!(u16::from(TkeyMode::from(value)), value);
+        }
+        assert_eq!(TkeyMode::from(3), TkeyMode::GssApi);
+        assert_eq!(TkeyMode::from(0), TkeyMode::Unknown(0));
+    }
+
+    #[test]
+    fn test_query() {
+        let rdata = TKEY::gss_api(1_700_000_000, 1_700_086_400, b"token".to_vec());
+        let message = tkey_query(42, key_name(), rdata.clone());
+
+        let bytes = message.to_bytes().unwrap();
+        let message = Message::from_bytes(&bytes).unwrap();
+        assert_eq!(message.queries()[0].name(), &key_name());
+        assert_eq!(message.queries()[0].query_type(), RecordType::TKEY);
+        assert_eq!(message.queries()[0].query_class(), DNSClass::ANY);
+
+        let record = &message.additionals()[0];
+        assert_eq!(record.name(), &key_name());
+        assert_eq!(record.dns_class(), DNSClass::ANY);
+        assert_eq!(record.ttl(), 0);
+        assert_eq!(TKEY::try_borrow(record.data()), Some(&rdata));
+        assert_eq!(
+            rdata.to_string(),
+            "gss-tsig 1700000000 1700086400 3 0 746f6b656e "
+        );
+    }
+
+    #[test]
+    fn test_response() {
+        let answer = |error| {
+            let mut rdata = TKEY::gss_api(0, 3600, b"reply".to_vec());
+            if let Some(error) = error {
+                rdata.set_error(error);
+            }
+
+            let mut response = Message::response(42, OpCode::Query);
+            response.add_answer(make_tkey_record(key_name(), rdata));
+            response
+        };
+
+        let response = answer(None);
+        let tkey = tkey_response(&response, &key_name(), TkeyMode::GssApi).unwrap();
+        assert_eq!(tkey.key(), b"reply");
+
+        // the key, the mode and the errors of the answer are checked
+        let other_name = Name::from_str("other.example.com.").unwrap();
+        assert!(tkey_response(&response, &other_name, TkeyMode::GssApi).is_err());
+        assert!(tkey_response(&response, &key_name(), TkeyMode::DiffieHellman).is_err());
+        assert!(
+            tkey_response(
+                &answer(Some(TsigError::BadKey)),
+                &key_name(),
+                TkeyMode::GssApi
+            )
+            .is_err()
+        );
+
+        let mut refused = answer(None);
+        refused.set_response_code(ResponseCode::Refused);
+        assert!(tkey_response(&refused, &key_name(), TkeyMode::GssApi).is_err());
+    }
+
+    #[test]
+    fn test_diffie_hellman() {
+        let resolver_name = Name::from_str("resolver.example.com.").unwrap();
+        let server_name = Name::from_str("ns.example.com.").unwrap();
+        let resolver_pair = DhKeyPair::from_private(DhGroup::Oakley1024, &[0x5a; 20]).unwrap();
+        let server_pair = DhKeyPair::from_private(DhGroup::Oakley1024, &[0xa5; 20]).unwrap();
+
+        let rdata = TKEY::diffie_hellman(TsigAlgorithm::HmacSha256, 0, 3600, vec![1; 16]);
+        let query =
+            diffie_hellman_query(7, key_name(), rdata, resolver_name.clone(), &resolver_pair);
+        let query = Message::from_bytes(&query.to_bytes().unwrap()).unwrap();
+
+        // the server derives the key from the TKEY and KEY records of the query
+        let query_nonce = query
+            .additionals()
+            .iter()
+            .find_map(|record| TKEY::try_borrow(record.data()))
+            .unwrap()
+            .key();
+        let (owner, resolver_key) = query
+            .additionals()
+            .iter()
+            .find_map(|record| KEY::try_borrow(record.data()).map(|key| (record.name(), key)))
+            .unwrap();
+        assert_eq!(owner, &resolver_name);
+        assert_eq!(resolver_key.algorithm(), DIFFIE_HELLMAN);
+        let resolver_key = DhPublicKey::from_bytes(resolver_key.public_key()).unwrap();
+        assert_eq!(resolver_key, resolver_pair.public_key());
+
+        let shared_secret = server_pair.shared_secret(&resolver_key).unwrap();
+        let server_nonce = vec![2; 16];
+        let server_secret = diffie_hellman_key(&shared_secret, query_nonce, &server_nonce);
+
+        let mut response = Message::response(7, OpCode::Query);
+        response
+            .add_answer(make_tkey_record(
+                key_name(),
+                TKEY::diffie_hellman(TsigAlgorithm::HmacSha256, 0, 3600, server_nonce),
+            ))
+            .add_answer(Record::from_rdata(
+                server_name.clone(),
+                0,
+                DNSSECRData::KEY(server_pair.to_key()).into(),
+            ));
+        let response = Message::from_bytes(&response.to_bytes().unwrap()).unwrap();
+        assert_eq!(server_keys(&response).count(), 1);
+
+        // and so does the resolver from the response
+        let resolver_secret =
+            diffie_hellman_response(&response, &key_name(), &[1; 16], &resolver_pair).unwrap();
+        assert_eq!(resolver_secret, server_secret);
+        assert_eq!(resolver_secret.len(), shared_secret.len());
+
+        // another nonce derives another key
+        assert_ne!(
+            diffie_hellman_response(&response, &key_name(), &[3; 16], &resolver_pair).unwrap(),
+            server_secret
+        );
+
+        // the key of the server is required
+        let mut no_key = Message::response(7, OpCode::Query);
+        no_key.add_answer(response.answers()[0].clone());
+        assert!(diffie_hellman_response(&no_key, &key_name(), &[1; 16], &resolver_pair).is_err());
+    }
+
+    #[test]
+    fn test_diffie_hellman_key() {
+        // the digests are longer than the shared secret, which is padded with zeros
+        let key = diffie_hellman_key(&[1, 2, 3, 4], b"query", b"server");
+        assert_eq!(
+            data_encoding::HEXLOWER.encode(&key),
+            "cb67c8a77354c0cfd807540909105f36cfcefeeb9c5e153cfca82a2cd20c0580"
+        );
+    }
+
+    #[test]
+    fn test_dh_public_key() {
+        let pair = DhKeyPair::from_private(DhGroup::Oakley768, &[7; 16]).unwrap();
+        let public_key = pair.public_key();
+        let bytes = public_key.to_bytes();
+
+        // the well-known prime is referred to by its index, without generator
+        assert_eq!(bytes[..5], [0, 1, 1, 0, 0]);
+        assert_eq!(DhPublicKey::from_bytes(&bytes).unwrap(), public_key);
+        assert_eq!(public_key.group(), Some(DhGroup::Oakley768));
+
+        // the same group with an explicit prime and generator
+        let mut explicit = Vec::new();
+        for field in [
+            BigUint::parse_bytes(OAKLEY_768.as_bytes(), 16)
+                .unwrap()
+                .to_bytes_be(),
+            vec![2],
+            public_key.public.to_bytes_be(),
+        ] {
+            explicit.extend_from_slice(&(field.len() as u16).to_be_bytes());
+            explicit.extend_from_slice(&field);
+        }
+        let explicit = DhPublicKey::from_bytes(&explicit).unwrap();
+        assert_eq!(explicit.group(), None);
+        let other = DhKeyPair::from_private(DhGroup::Oakley768, &[9; 16]).unwrap();
+        assert_eq!(
+            other.shared_secret(&explicit).unwrap(),
+            pair.shared_secret(&other.public_key()).unwrap()
+        );
+
+        // keys of other groups, reserved prime lengths and invalid values are rejected
+        let other_group = DhKeyPair::from_private(DhGroup::Oakley1024, &[9; 16]).unwrap();
+        assert!(other_group.shared_secret(&public_key).is_err());
+        assert!(DhPublicKey::from_bytes(&[0, 3, 1, 2, 3, 0, 0, 0, 1, 2]).is_err());
+        assert!(DhPublicKey::from_bytes(&[0, 1, 3, 0, 0, 0, 1, 2]).is_err());
+        assert!(DhPublicKey::from_bytes(&[0, 1, 1, 0, 0, 0, 4, 2]).is_err());
+        assert!(DhKeyPair::from_private(DhGroup::Oakley768, &[1]).is_err());
+        let unit = DhPublicKey::from_bytes(&[0, 1, 1, 0, 0, 0, 1, 1]).unwrap();
+        assert!(pair.shared_secret(&unit).is_err());
+    }
+
+    #[cfg(any(feature = "dnssec-aws-lc-rs", feature = "dnssec-ring"))]
+    #[test]
+    fn test_generate() {
+        let resolver = DhKeyPair::generate(DhGroup::Oakley768).unwrap();
+        let server = DhKeyPair::generate(DhGroup::Oakley768).unwrap();
+        assert_ne!(resolver.public, server.public);
+        assert_eq!(
+            resolver.shared_secret(&server.public_key()).unwrap(),
+            server.shared_secret(&resolver.public_key()).unwrap()
+        );
+    }
+}
@@ -645,9 +645,10 @@ impl BinDecodable<'_> for TsigAlgorithm {
     }
 }
 
-/// A TSIG RR error rcode
+/// A TSIG or TKEY RR error rcode
 ///
-/// See <https://www.rfc-editor.org/rfc/rfc8945.html#section-3>
+/// See <https://www.rfc-editor.org/rfc/rfc8945.html#section-3> and
+/// <https://www.rfc-editor.org/rfc/rfc2930.html#section-2.6>
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[derive(Debug, Eq, PartialEq, PartialOrd, Copy, Clone, Hash)]
 pub enum TsigError {
@@ -657,6 +658,12 @@ pub enum TsigError {
     BadKey,
     /// Bad signature time
     BadTime,
+    /// Bad TKEY mode
+    BadMode,
+    /// Duplicate key name in TKEY
+    BadName,
+    /// Algorithm not supported by TKEY
+    BadAlg,
     /// Bad truncated request MAC
     BadTrunc,
     /// An unknown error
@@ -669,6 +676,9 @@ impl From<u16> for TsigError {
             16 => Self::BadSig,
             17 => Self::BadKey,
             18 => Self::BadTime,
+            19 => Self::BadMode,
+            20 => Self::BadName,
+            21 => Self::BadAlg,
             22 => Self::BadTrunc,
             code => Self::Unknown(code),
         }
@@ -681,6 +691,9 @@ impl From<TsigError> for u16 {
             TsigError::BadSig => 16,
             TsigError::BadKey => 17,
             TsigError::BadTime => 18,
+            TsigError::BadMode => 19,
+            TsigError::BadName => 20,
+            TsigError::BadAlg => 21,
             TsigError::BadTrunc => 22,
             TsigError::Unknown(code) => code,
         }
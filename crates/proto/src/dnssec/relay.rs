@@ -0,0 +1,410 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Relaying of TSIG and SIG(0) signed messages by proxies and forwarders
+//!
+//! A relay, e.g. a gateway from DNS-over-HTTPS to plain DNS, can not pass a signed request on as
+//! it is when it changes the message or signs it with its own key for the next hop. Instead, it
+//! verifies and strips the signature of the request with [`RelayedRequest::verify`], forwards the
+//! message re-signed with [`RelayedRequest::forward`], and signs the response with
+//! [`RelayedRequest::sign_response`] for the client. The MAC of a TSIG signed request is kept, as
+//! the response must be signed in reply to it (RFC 8945 section 5.3).
+
+use alloc::boxed::Box;
+
+use thiserror::Error;
+use tracing::debug;
+
+use super::Sig0Keyring;
+use super::rdata::tsig::{TSIG, TsigError};
+use super::rdata::{DNSSECRData, KEY};
+use super::tsig::{TSigResponseContext, TSigner};
+use crate::error::{ProtoError, ProtoResult};
+use crate::op::message::ResponseSigner;
+use crate::op::{Message, MessageSignature, MessageSigner, MessageVerifier};
+use crate::rr::Name;
+use crate::serialize::binary::BinEncodable;
+
+/// A request received by a relay, with its signature verified and removed
+pub struct RelayedRequest {
+    message: Message,
+    signature: MessageSignature,
+    authentication: Authentication,
+    id: u16,
+    time: u64,
+}
+
+impl RelayedRequest {
+    /// Decodes a request received by the relay and verifies its signature, if any
+    ///
+    /// TSIG signatures are verified by the signer among `tsig_signers` with the same key name and
+    /// SIG(0) signatures by the `sig0_keys`. Unsigned requests are accepted, the caller decides
+    /// whether that is acceptable from the [`Authentication`].
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - the request as received, with its signature
+    /// * `tsig_signers` - the keys clients may sign requests with using TSIG
+    /// * `sig0_keys` - the keys clients may sign requests with using SIG(0)
+    /// * `now` - the current time, in seconds since the Unix epoch
+    pub fn verify(
+        buffer: &[u8],
+        tsig_signers: &[TSigner],
+        sig0_keys: &Sig0Keyring,
+        now: u64,
+    ) -> Result<Self, RelayError> {
+        let mut message = Message::from_vec(buffer)?;
+
+        let authentication = match message.signature() {
+            MessageSignature::Unsigned => Authentication::Unsigned,
+            MessageSignature::Tsig(record) => {
+                let Some(tsig) = record.data().as_dnssec().and_then(DNSSECRData::as_tsig) else {
+                    return Err(ProtoError::from("TSIG record without TSIG data").into());
+                };
+
+                let Some(signer) = tsig_signers.iter().find(|signer| {
+                    signer.signer_name() == record.name() && signer.algorithm() == tsig.algorithm()
+                }) else {
+                    return Err(RelayError::UnknownKey(record.name().clone()));
+                };
+
+                let range = match signer.verify_message_byte(buffer, None, true) {
+                    Ok((_, _, range)) => range,
+                    Err(err) => {
+                        debug!("invalid TSIG signature: id {}: {err}", message.id());
+                        return Err(RelayError::BadSignature(Some(signer.clone())));
+                    }
+                };
+
+                if !range.contains(&now) {
+                    return Err(RelayError::BadTime {
+                        signer: signer.clone(),
+                        request: Box::new(tsig.clone()),
+                    });
+                }
+
+                Authentication::Tsig {
+                    signer: signer.clone(),
+                    request: tsig.clone(),
+                }
+            }
+            MessageSignature::Sig0(record) => {
+                let Some(sig) = record.data().as_dnssec().and_then(DNSSECRData::as_sig) else {
+                    return Err(ProtoError::from("SIG record without SIG data").into());
+                };
+
+                match sig0_keys.verify_message(&message, sig, now as u32) {
+                    Ok(key) => Authentication::Sig0 {
+                        signer_name: sig.input().signer_name.clone(),
+                        key: key.clone(),
+                    },
+                    Err(err) => {
+                        debug!("invalid SIG(0) signature: id {}: {err}", message.id());
+                        return Err(RelayError::BadSignature(None));
+                    }
+                }
+            }
+        };
+
+        let signature = message.take_signature();
+        Ok(Self {
+            id: message.id(),
+            message,
+            signature,
+            authentication,
+            time: now,
+        })
+    }
+
+    /// The request without its signature
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// A mutable reference to the request without its signature
+    ///
+    /// The response is sent back with the ID of the original request, whatever the ID of the
+    /// forwarded request.
+    pub fn message_mut(&mut self) -> &mut Message {
+        &mut self.message
+    }
+
+    /// The original signature of the request, including the MAC of a TSIG signature
+    pub fn signature(&self) -> &MessageSignature {
+        &self.signature
+    }
+
+    /// How the request was authenticated
+    pub fn authentication(&self) -> &Authentication {
+        &self.authentication
+    }
+
+    /// Returns the request to send to the next hop, signed by `signer` at the time `now`
+    ///
+    /// The returned verifier, if any, authenticates the response of the next hop, see
+    /// [`Message::finalize`].
+    pub fn forward(
+        &self,
+        signer: Option<&dyn MessageSigner>,
+        now: u32,
+    ) -> ProtoResult<(Message, Option<MessageVerifier>)> {
+        let mut message = self.message.clone();
+        let verifier = match signer {
+            Some(signer) => message.finalize(signer, now)?,
+            None => None,
+        };
+
+        Ok((message, verifier))
+    }
+
+    /// Prepares the `response` of the next hop to be sent back to the client
+    ///
+    /// The signature of the next hop is removed, the ID of the original request is restored and,
+    /// if the request was signed with TSIG, the response is signed with the same key in reply to
+    /// the MAC of the request. Responses to SIG(0) signed requests are left unsigned, as the relay
+    /// does not hold the keys of the name server.
+    pub fn sign_response(&self, response: &mut Message) -> ProtoResult<()> {
+        response.take_signature();
+        response.set_id(self.id);
+
+        let Authentication::Tsig { signer, request } = &self.authentication else {
+            return Ok(());
+        };
+
+        let signer =
+            TSigResponseContext::new(response.id(), self.time).sign(request, None, signer.clone());
+        let signature = signer.sign(&response.to_bytes()?)?;
+        response.set_signature(signature);
+        Ok(())
+    }
+}
+
+/// How a [`RelayedRequest`] was authenticated
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum Authentication {
+    /// The request was not signed
+    Unsigned,
+    /// The request was signed with TSIG
+    Tsig {
+        /// The key the request was signed with
+        signer: TSigner,
+        /// The TSIG data of the request, with its MAC
+        request: TSIG,
+    },
+    /// The request was signed with SIG(0)
+    Sig0 {
+        /// The name of the signer
+        signer_name: Name,
+        /// The key that verified the signature
+        key: KEY,
+    },
+}
+
+/// A request that could not be relayed, see [`RelayedRequest::verify`]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RelayError {
+    /// The request could not be decoded
+    #[error("invalid request: {0}")]
+    Proto(#[from] ProtoError),
+    /// The request was signed with TSIG by an unknown key
+    #[error("unknown TSIG key: {0}")]
+    UnknownKey(Name),
+    /// The signature of the request did not verify, with the TSIG key if signed with TSIG
+    #[error("invalid signature")]
+    BadSignature(Option<TSigner>),
+    /// The TSIG signature of the request was made outside of its allowed time range
+    #[error("TSIG signature time out of range")]
+    BadTime {
+        /// The key the request was signed with
+        signer: TSigner,
+        /// The TSIG data of the request
+        request: Box<TSIG>,
+    },
+}
+
+impl RelayError {
+    /// The signer of the error response, which is a NOTAUTH response for TSIG errors
+    ///
+    /// `request_id` is the ID of the request and `now` the current time, in seconds since the
+    /// Unix epoch. This follows RFC 8945 section 5.2: the responses to unknown keys and bad
+    /// signatures are unsigned TSIG records, the response to a bad time is signed.
+    pub fn response_signer(self, request_id: u16, now: u64) -> Option<Box<dyn ResponseSigner>> {
+        let cx = TSigResponseContext::new(request_id, now);
+        match self {
+            Self::Proto(_) | Self::BadSignature(None) => None,
+            Self::UnknownKey(key_name) => Some(cx.unknown_key(key_name)),
+            Self::BadSignature(Some(signer)) => Some(cx.bad_signature(signer)),
+            Self::BadTime { signer, request } => {
+                Some(cx.sign(&request, Some(TsigError::BadTime), signer))
+            }
+        }
+    }
+}
+
+impl core::fmt::Debug for RelayedRequest {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RelayedRequest")
+            .field("message", &self.message)
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::dnssec::rdata::tsig::TsigAlgorithm;
+    use crate::dnssec::{
+        Algorithm, SigSigner, SigningKey, crypto::EcdsaSigningKey, rdata::key::KeyUsage,
+    };
+    use crate::op::{MessageType, Query, ResponseCode};
+    use crate::rr::{RData, Record, RecordType, rdata::A};
+
+    const NOW: u32 = 1_700_000_000;
+
+    fn query() -> Message {
+        let mut message = Message::query();
+        message.set_id(1234);
+        message.add_query(Query::query(
+            Name::from_ascii("www.example.com.").unwrap(),
+            RecordType::A,
+        ));
+        message
+    }
+
+    fn tsigner(key: &[u8], name: &str) -> TSigner {
+        TSigner::new(
+            key.to_vec(),
+            TsigAlgorithm::HmacSha256,
+            Name::from_ascii(name).unwrap(),
+            300,
+        )
+        .unwrap()
+    }
+
+    fn response(request: &Message) -> Message {
+        let mut response = Message::response(request.id(), request.op_code());
+        response.add_queries(request.queries().to_vec());
+        response.add_answer(Record::from_rdata(
+            Name::from_ascii("www.example.com.").unwrap(),
+            300,
+            RData::A(A::new(192, 0, 2, 1)),
+        ));
+        response
+    }
+
+    #[test]
+    fn test_tsig_relay() {
+        let client = tsigner(b"client key", "client.");
+        let upstream = tsigner(b"upstream key", "upstream.");
+
+        let mut request = query();
+        let mut client_verifier = request.finalize(&client, NOW).unwrap().unwrap();
+        let buffer = request.to_bytes().unwrap();
+
+        let relayed = RelayedRequest::verify(
+            &buffer,
+            &[upstream.clone(), client.clone()],
+            &Sig0Keyring::empty(),
+            u64::from(NOW),
+        )
+        .unwrap();
+        assert!(matches!(relayed.signature(), MessageSignature::Tsig(_)));
+        assert_eq!(relayed.message().signature(), &MessageSignature::Unsigned);
+        assert!(matches!(
+            relayed.authentication(),
+            Authentication::Tsig { signer, .. } if signer.signer_name() == client.signer_name()
+        ));
+
+        // the next hop verifies the request with its own key and signs the response
+        let (forwarded, verifier) = relayed.forward(Some(&upstream), NOW).unwrap();
+        let mut verifier = verifier.unwrap();
+        let forwarded = RelayedRequest::verify(
+            &forwarded.to_bytes().unwrap(),
+            core::slice::from_ref(&upstream),
+            &Sig0Keyring::empty(),
+            u64::from(NOW),
+        )
+        .unwrap();
+        let mut upstream_response = response(forwarded.message());
+        forwarded.sign_response(&mut upstream_response).unwrap();
+        verifier(&upstream_response.to_bytes().unwrap()).unwrap();
+
+        // the client verifies the response with the MAC of its request
+        relayed.sign_response(&mut upstream_response).unwrap();
+        let response = client_verifier(&upstream_response.to_bytes().unwrap()).unwrap();
+        assert_eq!(response.id(), 1234);
+        assert_eq!(response.message_type(), MessageType::Response);
+        assert_eq!(response.answers().len(), 1);
+    }
+
+    #[test]
+    fn test_tsig_errors() {
+        let client = tsigner(b"client key", "client.");
+        let mut request = query();
+        request.finalize(&client, NOW).unwrap();
+        let buffer = request.to_bytes().unwrap();
+        let keyring = Sig0Keyring::empty();
+
+        let err = RelayedRequest::verify(&buffer, &[], &keyring, u64::from(NOW)).unwrap_err();
+        assert!(matches!(&err, RelayError::UnknownKey(name) if name == client.signer_name()));
+        assert!(err.response_signer(1234, u64::from(NOW)).is_some());
+
+        let other = tsigner(b"other key", "client.");
+        let err = RelayedRequest::verify(&buffer, &[other], &keyring, u64::from(NOW)).unwrap_err();
+        assert!(matches!(err, RelayError::BadSignature(Some(_))));
+
+        let err = RelayedRequest::verify(&buffer, &[client], &keyring, u64::from(NOW) + 3_600)
+            .unwrap_err();
+        assert!(matches!(err, RelayError::BadTime { .. }));
+
+        let signature = err.response_signer(1234, u64::from(NOW)).unwrap();
+        let MessageSignature::Tsig(record) = signature.sign(&query().to_bytes().unwrap()).unwrap()
+        else {
+            panic!("expected TSIG");
+        };
+        let tsig = record.data().as_dnssec().unwrap().as_tsig().unwrap();
+        assert_eq!(tsig.error(), &Some(TsigError::BadTime));
+    }
+
+    #[test]
+    fn test_sig0_relay() {
+        let pkcs8 = EcdsaSigningKey::generate_pkcs8(Algorithm::ECDSAP256SHA256).unwrap();
+        let key = EcdsaSigningKey::from_pkcs8(&pkcs8, Algorithm::ECDSAP256SHA256).unwrap();
+        let public = KEY::new_sig0key_with_usage(&key.to_public_key().unwrap(), KeyUsage::Host);
+        let name = Name::from_ascii("update.example.com.").unwrap();
+        let signer = SigSigner::sig0(public.clone(), Box::new(key), name.clone());
+
+        let mut request = query();
+        request.finalize(&signer, NOW).unwrap();
+        let buffer = request.to_bytes().unwrap();
+
+        let mut keyring = Sig0Keyring::empty();
+        assert!(matches!(
+            RelayedRequest::verify(&buffer, &[], &keyring, u64::from(NOW)),
+            Err(RelayError::BadSignature(None))
+        ));
+
+        keyring.insert(name.clone(), public.clone());
+        let relayed = RelayedRequest::verify(&buffer, &[], &keyring, u64::from(NOW)).unwrap();
+        assert!(matches!(
+            relayed.authentication(),
+            Authentication::Sig0 { signer_name, key } if *signer_name == name && *key == public
+        ));
+
+        let (forwarded, verifier) = relayed.forward(None, NOW).unwrap();
+        assert!(verifier.is_none());
+        assert_eq!(forwarded.signature(), &MessageSignature::Unsigned);
+
+        let mut response = response(&forwarded);
+        response.set_response_code(ResponseCode::NoError);
+        relayed.sign_response(&mut response).unwrap();
+        assert_eq!(response.signature(), &MessageSignature::Unsigned);
+    }
+}
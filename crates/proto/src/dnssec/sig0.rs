@@ -0,0 +1,219 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Verification of SIG(0) message signatures against a set of trusted keys
+
+use alloc::vec::Vec;
+
+use tracing::debug;
+
+use super::{
+    Verifier,
+    rdata::{DNSSECRData, KEY, SIG, key::KeyTrust},
+};
+use crate::{
+    error::{ProtoError, ProtoResult},
+    rr::{Name, Record, RecordType, SerialNumber},
+    serialize::binary::BinEncodable,
+};
+
+/// The KEY records trusted to verify [RFC 2931](https://tools.ietf.org/html/rfc2931) SIG(0)
+/// message signatures, by the name of their owner
+///
+/// The keys are either configured locally, or looked up in the DNS, in which case the lookup
+/// should be DNSSEC validated, e.g. with a [`DnssecDnsHandle`](super::DnssecDnsHandle), before
+/// the records are added.
+#[derive(Clone, Debug, Default)]
+pub struct Sig0Keyring {
+    keys: Vec<(Name, KEY)>,
+}
+
+impl Sig0Keyring {
+    /// Creates a new empty keyring
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Trusts the `key` of the signer `name`, returns false if it was already trusted
+    pub fn insert(&mut self, name: Name, key: KEY) -> bool {
+        if self.keys.iter().any(|(n, k)| *n == name && *k == key) {
+            return false;
+        }
+
+        self.keys.push((name, key));
+        true
+    }
+
+    /// Trusts the KEY records among `records`, returns the number of newly trusted keys
+    pub fn extend_from_records<'a>(
+        &mut self,
+        records: impl IntoIterator<Item = &'a Record>,
+    ) -> usize {
+        records
+            .into_iter()
+            .filter(|record| record.record_type() == RecordType::KEY)
+            .filter_map(|record| {
+                let key = record.data().as_dnssec().and_then(DNSSECRData::as_key)?;
+                Some((record.name().clone(), key.clone()))
+            })
+            .filter(|(name, key)| self.insert(name.clone(), key.clone()))
+            .count()
+    }
+
+    /// Number of trusted keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns true if no keys are trusted
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Verifies the SIG(0) signature `sig` of `message` at the time `now`, returning the key which
+    /// verified it
+    ///
+    /// The signature must be within its validity period, and made by a trusted key of the signer
+    /// with a matching algorithm, which may be used for authentication. The key tag is not
+    /// required to match, signers created with the deprecated `SigSigner::new` derive it from a
+    /// DNSKEY rather than their KEY.
+    pub fn verify_message(
+        &self,
+        message: &impl BinEncodable,
+        sig: &SIG,
+        now: u32,
+    ) -> ProtoResult<&KEY> {
+        let input = sig.input();
+        if input.type_covered != RecordType::ZERO {
+            return Err(ProtoError::from("not a SIG(0) signature"));
+        }
+
+        // RFC 2931 section 3.1: the time the signature was generated and its expiration, compared
+        // using serial number arithmetic
+        let now = SerialNumber::from(now);
+        if !(now >= input.sig_inception && now <= input.sig_expiration) {
+            return Err(ProtoError::from(
+                "SIG(0) signature is not within its validity period",
+            ));
+        }
+
+        let key = self
+            .keys
+            .iter()
+            .filter(|(name, key)| {
+                *name == input.signer_name
+                    && key.algorithm() == input.algorithm
+                    && !matches!(key.key_trust(), KeyTrust::NotAuth | KeyTrust::DoNotTrust)
+            })
+            .map(|(_, key)| key)
+            .find(|key| match key.verify_message(message, sig.sig(), input) {
+                Ok(()) => true,
+                Err(err) => {
+                    debug!("did not verify SIG(0) with key {key}: {err}");
+                    false
+                }
+            });
+
+        key.ok_or_else(|| {
+            ProtoError::from(format!(
+                "no trusted key of {} verified the SIG(0) signature",
+                input.signer_name
+            ))
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use alloc::boxed::Box;
+
+    use super::*;
+    use crate::dnssec::{
+        Algorithm, SigSigner, SigningKey,
+        crypto::EcdsaSigningKey,
+        rdata::{KEY, key::KeyUsage},
+    };
+    use crate::op::{Message, MessageSignature, Query};
+    use crate::rr::{RData, rdata::A};
+
+    const NOW: u32 = 1_700_000_000;
+
+    fn signed_message(signer: &SigSigner) -> (Message, SIG) {
+        let mut message = Message::query();
+        message.add_query(Query::query(
+            Name::from_ascii("www.example.com.").unwrap(),
+            RecordType::A,
+        ));
+        message.finalize(signer, NOW).unwrap();
+
+        let MessageSignature::Sig0(sig0) = message.signature() else {
+            panic!("expected SIG(0)");
+        };
+        let sig = sig0.data().as_dnssec().unwrap().as_sig().unwrap().clone();
+        (message, sig)
+    }
+
+    fn signer() -> (SigSigner, KEY) {
+        let pkcs8 = EcdsaSigningKey::generate_pkcs8(Algorithm::ECDSAP256SHA256).unwrap();
+        let key = EcdsaSigningKey::from_pkcs8(&pkcs8, Algorithm::ECDSAP256SHA256).unwrap();
+        let public = KEY::new_sig0key_with_usage(&key.to_public_key().unwrap(), KeyUsage::Host);
+        let name = Name::from_ascii("update.example.com.").unwrap();
+        (SigSigner::sig0(public.clone(), Box::new(key), name), public)
+    }
+
+    #[test]
+    fn test_verify_message() {
+        let (signer, key) = signer();
+        let (message, sig) = signed_message(&signer);
+
+        let mut keyring = Sig0Keyring::empty();
+        assert!(keyring.verify_message(&message, &sig, NOW).is_err());
+
+        assert!(keyring.insert(signer.signer_name().clone(), key.clone()));
+        assert!(!keyring.insert(signer.signer_name().clone(), key.clone()));
+        assert_eq!(keyring.verify_message(&message, &sig, NOW).unwrap(), &key);
+
+        // the signature expires after five minutes
+        assert!(keyring.verify_message(&message, &sig, NOW - 1).is_err());
+        assert!(keyring.verify_message(&message, &sig, NOW + 5 * 60).is_ok());
+        assert!(
+            keyring
+                .verify_message(&message, &sig, NOW + 5 * 60 + 1)
+                .is_err()
+        );
+
+        // the key must be trusted for the signer
+        let mut keyring = Sig0Keyring::empty();
+        keyring.insert(Name::from_ascii("other.example.com.").unwrap(), key);
+        assert!(keyring.verify_message(&message, &sig, NOW).is_err());
+    }
+
+    #[test]
+    fn test_extend_from_records() {
+        let (other, _) = signer();
+        let (signer, key) = signer();
+        let (message, sig) = signed_message(&signer);
+
+        let mut keyring = Sig0Keyring::empty();
+        let records = [
+            Record::from_rdata(signer.signer_name().clone(), 300, RData::from(key)),
+            Record::from_rdata(
+                signer.signer_name().clone(),
+                300,
+                RData::A(A::new(192, 0, 2, 1)),
+            ),
+        ];
+        assert_eq!(keyring.extend_from_records(&records), 1);
+        assert_eq!(keyring.extend_from_records(&records), 0);
+        assert_eq!(keyring.len(), 1);
+        assert!(keyring.verify_message(&message, &sig, NOW).is_ok());
+
+        // a signature by an untrusted key of the same signer is rejected
+        let (message, sig) = signed_message(&other);
+        assert!(keyring.verify_message(&message, &sig, NOW).is_err());
+    }
+}
@@ -6,21 +6,22 @@
 // copied, modified, or distributed except according to those terms.
 
 //! signer is a structure for performing many of the signing processes of the DNSSEC specification
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
 use core::time::Duration;
 
 use tracing::debug;
 
-use super::{DnsSecResult, SigningKey};
+use super::{Clock, DnsSecResult, Sig0Keyring, SigningKey};
 use crate::{
     dnssec::{
         TBS,
         rdata::{DNSKEY, DNSSECRData, KEY, SIG, SigInput},
     },
-    error::{ProtoErrorKind, ProtoResult},
+    error::{ProtoError, ProtoErrorKind, ProtoResult},
     op::{Message, MessageSignature, MessageSigner, MessageVerifier},
     rr::{DNSClass, Name, RData, Record, RecordType, SerialNumber},
     serialize::binary::{BinEncodable, BinEncoder},
+    xfer::DnsResponse,
 };
 
 /// Use for performing signing and validation of DNSSEC based components. The SigSigner can be used for singing requests and responses with SIG0, or DNSSEC RRSIG records. The format is based on the SIG record type.
@@ -231,6 +232,7 @@ pub struct SigSigner {
     signer_name: Name,
     sig_duration: Duration,
     is_zone_signing_key: bool,
+    response_keyring: Option<(Arc<Sig0Keyring>, Arc<dyn Clock>)>,
 }
 
 impl SigSigner {
@@ -255,6 +257,7 @@ impl SigSigner {
             key,
             signer_name,
             sig_duration,
+            response_keyring: None,
         }
     }
 
@@ -273,6 +276,7 @@ impl SigSigner {
             signer_name,
             sig_duration: Duration::ZERO,
             is_zone_signing_key: false,
+            response_keyring: None,
         }
     }
 
@@ -294,9 +298,21 @@ impl SigSigner {
             signer_name,
             sig_duration,
             is_zone_signing_key,
+            response_keyring: None,
         }
     }
 
+    /// Requires the responses to messages signed by this signer to be signed with SIG(0) by one of
+    /// the keys in `keyring`, at the time of `clock`
+    pub fn with_response_keyring(
+        mut self,
+        keyring: Arc<Sig0Keyring>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        self.response_keyring = Some((keyring, clock));
+        self
+    }
+
     /// Return the key used for validation/signing
     pub fn key(&self) -> &dyn SigningKey {
         &*self.key
@@ -524,7 +540,23 @@ impl MessageSigner for SigSigner {
         // The CLASS field SHOULD be ANY
         sig0.set_dns_class(DNSClass::ANY);
 
-        Ok((MessageSignature::Sig0(sig0), None))
+        let Some((keyring, clock)) = self.response_keyring.clone() else {
+            return Ok((MessageSignature::Sig0(sig0), None));
+        };
+
+        let verifier = move |response: &[u8]| {
+            let response = DnsResponse::from_buffer(response.to_vec())?;
+            let MessageSignature::Sig0(sig0) = response.signature() else {
+                return Err(ProtoError::from("response is not signed with SIG(0)"));
+            };
+            let Some(sig) = sig0.data().as_dnssec().and_then(DNSSECRData::as_sig) else {
+                return Err(ProtoError::from("invalid SIG(0) record in response"));
+            };
+
+            keyring.verify_message(&*response, sig, clock.now())?;
+            Ok(response)
+        };
+        Ok((MessageSignature::Sig0(sig0), Some(Box::new(verifier))))
     }
 }
 
@@ -543,7 +575,7 @@ mod tests {
         crypto::RsaSigningKey,
         rdata::{DNSSECRData, KEY, key::KeyUsage},
     };
-    use crate::op::{Message, MessageSignature, Query};
+    use crate::op::{Message, MessageSignature, OpCode, Query};
     use crate::rr::rdata::{CNAME, NS};
     use crate::rr::{DNSClass, Name, RData, Record, RecordType};
 
@@ -622,6 +654,45 @@ mod tests {
         assert!(sig0key.verify_message(&question, sig.sig(), &input).is_ok());
     }
 
+    #[test]
+    fn test_verify_sig0_response() {
+        let signer = |name: Name| {
+            let key =
+                RsaSigningKey::from_pkcs8(&PrivatePkcs8KeyDer::from(RSA_KEY), Algorithm::RSASHA256)
+                    .unwrap();
+            let sig0key = KEY::new_sig0key(&key.to_public_key().unwrap());
+            (
+                SigSigner::sig0(sig0key.clone(), Box::new(key), name),
+                sig0key,
+            )
+        };
+
+        let server_name = Name::from_ascii("ns.example.com.").unwrap();
+        let (server, server_key) = signer(server_name.clone());
+        let mut keyring = Sig0Keyring::empty();
+        keyring.insert(server_name, server_key);
+        let keyring = Arc::new(keyring);
+
+        let mut response = Message::response(7, OpCode::Query);
+        let unsigned = response.to_vec().unwrap();
+        response.finalize(&server, 0).unwrap();
+        let signed = response.to_vec().unwrap();
+
+        let verify = |now: u32, response: &[u8]| {
+            let client = signer(Name::root())
+                .0
+                .with_response_keyring(keyring.clone(), Arc::new(move || now));
+            let mut question = Message::query();
+            let mut verifier = question.finalize(&client, 0).unwrap().unwrap();
+            verifier(response).is_ok()
+        };
+
+        assert!(!verify(100, &unsigned));
+        assert!(verify(100, &signed));
+        // the signature of the response expires after five minutes
+        assert!(!verify(301, &signed));
+    }
+
     #[test]
     #[allow(deprecated)]
     fn test_sign_and_verify_rrset() {
@@ -644,7 +715,7 @@ mod tests {
             signer_name: origin.clone(),
         };
 
-        let rrset = vec![
+        let rrset = [
             Record::from_rdata(
                 origin.clone(),
                 86400,
@@ -703,7 +774,7 @@ mod tests {
             signer_name: origin.clone(),
         };
 
-        let rrset = vec![
+        let rrset = [
             Record::from_rdata(
                 origin.clone(),
                 86400,
@@ -723,7 +794,7 @@ mod tests {
         let tbs = TBS::from_input(&origin, DNSClass::IN, &input, rrset.iter()).unwrap();
         assert!(!tbs.as_ref().is_empty());
 
-        let rrset = vec![
+        let rrset = [
             Record::from_rdata(
                 origin.clone(),
                 86400,
@@ -17,6 +17,7 @@ use alloc::boxed::Box;
 use alloc::string::ToString;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::fmt;
 use core::mem;
 use core::ops::Range;
 
@@ -362,6 +363,17 @@ impl TSigner {
     }
 }
 
+impl fmt::Debug for TSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the key is left out on purpose
+        f.debug_struct("TSigner")
+            .field("algorithm", &self.0.algorithm)
+            .field("signer_name", &self.0.signer_name)
+            .field("fudge", &self.0.fudge)
+            .finish_non_exhaustive()
+    }
+}
+
 impl MessageSigner for TSigner {
     fn sign_message(
         &self,
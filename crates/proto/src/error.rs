@@ -22,6 +22,7 @@ use std::{io, sync};
 #[cfg(feature = "backtrace")]
 pub use backtrace::Backtrace as ExtBacktrace;
 use enum_as_inner::EnumAsInner;
+use once_cell::race::OnceBox;
 #[cfg(feature = "backtrace")]
 use once_cell::sync::Lazy;
 use thiserror::Error;
@@ -80,6 +81,16 @@ pub enum ProtoErrorKind {
     #[error("resource too busy")]
     Busy,
 
+    /// A lookup was shed because too many lookups were already outstanding and queued
+    ///
+    /// Unlike [`ProtoErrorKind::Busy`], this is not retried internally, the caller is expected to
+    /// back off before making further requests.
+    #[error("too many outstanding lookups, {queued} already queued")]
+    LookupQueueFull {
+        /// The number of lookups waiting for an earlier lookup to complete
+        queued: usize,
+    },
+
     /// An error caused by a canceled future
     #[error("future was canceled: {0:?}")]
     Canceled(futures_channel::oneshot::Canceled),
@@ -117,6 +128,10 @@ pub enum ProtoErrorKind {
         proof: Proof,
     },
 
+    /// An error reported by the decoder of the binary format
+    #[error("{0}")]
+    Decode(DecodeError),
+
     /// DNS protocol version doesn't have the expected version 3
     #[error("dns key value unknown, must be 3: {0}")]
     DnsKeyProtocolNot3(u8),
@@ -152,6 +167,18 @@ pub enum ProtoErrorKind {
     #[error("label bytes exceed 63: {0}")]
     LabelBytesTooLong(usize),
 
+    /// A length read from the message exceeds the remaining data
+    #[non_exhaustive]
+    #[error("{field} length too large for remaining bytes, need: {len} remain: {remaining}")]
+    LengthExceedsRemaining {
+        /// The field the length was read for
+        field: &'static str,
+        /// The length that was read
+        len: usize,
+        /// The number of bytes remaining
+        remaining: usize,
+    },
+
     /// Label bytes exceeded the limit of 63
     #[non_exhaustive]
     #[error("label points to data not prior to idx: {idx} ptr: {ptr}")]
@@ -183,10 +210,18 @@ pub enum ProtoErrorKind {
     #[error("{0}")]
     Msg(String),
 
+    /// A record type that is only allowed in the additional section was found in another section
+    #[error("record type {0} only allowed in additional section")]
+    NotInAdditionalSection(RecordType),
+
     /// No resolvers available
     #[error("no connections available")]
     NoConnections,
 
+    /// None of the name servers of a zone could be reached, or none of them answered for it
+    #[error("no reachable authority for {0}")]
+    NoReachableAuthority(crate::rr::Name),
+
     /// No error was specified
     #[error("no error specified")]
     NoError,
@@ -256,7 +291,7 @@ pub enum ProtoErrorKind {
     /// A ring error
     #[cfg(feature = "__dnssec")]
     #[error("ring error: {0}")]
-    Ring(#[from] Unspecified),
+    Ring(#[cfg_attr(feature = "std", from)] Unspecified),
 
     /// A tokio timer error
     #[error("timer error")]
@@ -322,10 +357,10 @@ pub enum ProtoErrorKind {
     #[error("an unknown quic stream was used")]
     QuinnUnknownStreamError,
 
-    /// A quic message id should always be 0
+    /// The peer violated the DoQ protocol
     #[cfg(feature = "__quic")]
-    #[error("quic messages should always be 0, got: {0}")]
-    QuicMessageIdNot0(u16),
+    #[error("DoQ protocol error: {0}")]
+    DoqProtocol(#[from] crate::quic::DoqProtocolError),
 
     /// A Rustls error occurred
     #[cfg(feature = "__tls")]
@@ -336,6 +371,11 @@ pub enum ProtoErrorKind {
     /// case.
     #[error("case of query name in response did not match")]
     QueryCaseMismatch,
+
+    /// More than one response was received over UDP for a query, with different contents, which
+    /// indicates an attempt to spoof the response.
+    #[error("received conflicting responses to a query")]
+    ConflictingResponses,
 }
 
 impl From<NoRecords> for ProtoErrorKind {
@@ -344,6 +384,14 @@ impl From<NoRecords> for ProtoErrorKind {
     }
 }
 
+// ring only implements `Error` for `Unspecified` with `std`, so it can't be a source without it
+#[cfg(all(feature = "__dnssec", not(feature = "std")))]
+impl From<Unspecified> for ProtoErrorKind {
+    fn from(e: Unspecified) -> Self {
+        Self::Ring(e)
+    }
+}
+
 /// Response where no records were found
 #[derive(Clone, Debug)]
 #[non_exhaustive]
@@ -455,7 +503,7 @@ pub struct ForwardNSData {
 #[non_exhaustive]
 pub struct ProtoError {
     /// Kind of error that occurred
-    pub kind: Box<ProtoErrorKind>,
+    kind: Kind,
     /// Backtrace to the source of the error
     #[cfg(feature = "backtrace")]
     pub backtrack: Option<ExtBacktrace>,
@@ -464,46 +512,127 @@ pub struct ProtoError {
 impl ProtoError {
     /// Get the kind of the error
     #[inline]
+    ///
+    /// Errors of the binary decoder box their kind on the first call, see [`Self::decode_error`]
+    /// to inspect them without allocating.
     pub fn kind(&self) -> &ProtoErrorKind {
-        &self.kind
+        match &self.kind {
+            Kind::Decode { error, kind } => kind.get_or_init(|| Box::new(decode_kind(*error))),
+            Kind::Boxed(kind) => kind,
+        }
+    }
+
+    /// Get the kind of the error mutably, boxing the kind of errors of the binary decoder
+    pub fn kind_mut(&mut self) -> &mut ProtoErrorKind {
+        if let Kind::Decode { error, .. } = self.kind {
+            self.kind = Kind::Boxed(Box::new(decode_kind(error)));
+        }
+
+        match &mut self.kind {
+            Kind::Boxed(kind) => kind,
+            Kind::Decode { .. } => unreachable!("the kind was boxed above"),
+        }
+    }
+
+    /// Get the error of the binary decoder that caused this error, if any
+    #[inline]
+    pub fn decode_error(&self) -> Option<DecodeError> {
+        match self.kind {
+            Kind::Decode { error, .. } => Some(error),
+            Kind::Boxed(_) => None,
+        }
+    }
+
+    /// Splits a [`ProtoErrorKind::FormError`] into the header and the error of the message
+    pub fn into_form_error(self) -> Result<(Header, Box<Self>), Self> {
+        match self.kind {
+            Kind::Boxed(kind) => match *kind {
+                ProtoErrorKind::FormError { header, error } => Ok((header, error)),
+                kind => Err(Self {
+                    kind: Kind::Boxed(Box::new(kind)),
+                    #[cfg(feature = "backtrace")]
+                    backtrack: self.backtrack,
+                }),
+            },
+            kind @ Kind::Decode { .. } => Err(Self {
+                kind,
+                #[cfg(feature = "backtrace")]
+                backtrack: self.backtrack,
+            }),
+        }
+    }
+
+    /// The kind of the error, unless it is an error of the binary decoder
+    ///
+    /// None of the kinds checked by the methods below come from the decoder, they don't need to
+    /// box the kind of decoder errors.
+    fn boxed_kind(&self) -> Option<&ProtoErrorKind> {
+        match &self.kind {
+            Kind::Decode { .. } => None,
+            Kind::Boxed(kind) => Some(kind),
+        }
     }
 
     /// If this is a ProtoErrorKind::Busy
     #[inline]
     pub fn is_busy(&self) -> bool {
-        matches!(*self.kind, ProtoErrorKind::Busy)
+        matches!(self.boxed_kind(), Some(ProtoErrorKind::Busy))
+    }
+
+    /// If this is a ProtoErrorKind::LookupQueueFull
+    #[inline]
+    pub fn is_lookup_queue_full(&self) -> bool {
+        matches!(
+            self.boxed_kind(),
+            Some(ProtoErrorKind::LookupQueueFull { .. })
+        )
     }
 
     /// Returns true if this error represents NoConnections
     #[inline]
     pub fn is_no_connections(&self) -> bool {
-        matches!(*self.kind, ProtoErrorKind::NoConnections)
+        matches!(self.boxed_kind(), Some(ProtoErrorKind::NoConnections))
+    }
+
+    /// Returns true if none of the name servers of a zone could be reached
+    #[inline]
+    pub fn is_no_reachable_authority(&self) -> bool {
+        matches!(
+            self.boxed_kind(),
+            Some(ProtoErrorKind::NoReachableAuthority(..))
+        )
     }
 
     /// Returns true if the domain does not exist
     #[inline]
     pub fn is_nx_domain(&self) -> bool {
         matches!(
-            *self.kind,
-            ProtoErrorKind::NoRecordsFound(NoRecords {
+            self.boxed_kind(),
+            Some(ProtoErrorKind::NoRecordsFound(NoRecords {
                 response_code: ResponseCode::NXDomain,
                 ..
-            })
+            }))
         )
     }
 
     /// Returns true if the error represents NoRecordsFound
     #[inline]
     pub fn is_no_records_found(&self) -> bool {
-        matches!(*self.kind, ProtoErrorKind::NoRecordsFound { .. })
+        matches!(
+            self.boxed_kind(),
+            Some(ProtoErrorKind::NoRecordsFound { .. })
+        )
     }
 
     /// Returns the SOA record, if the error contains one
     #[inline]
     pub fn into_soa(self) -> Option<Box<Record<SOA>>> {
-        match *self.kind {
-            ProtoErrorKind::NoRecordsFound(NoRecords { soa, .. }) => soa,
-            _ => None,
+        match self.kind {
+            Kind::Boxed(kind) => match *kind {
+                ProtoErrorKind::NoRecordsFound(NoRecords { soa, .. }) => soa,
+                _ => None,
+            },
+            Kind::Decode { .. } => None,
         }
     }
 
@@ -511,7 +640,7 @@ impl ProtoError {
     #[inline]
     #[cfg(feature = "std")]
     pub fn is_io(&self) -> bool {
-        matches!(*self.kind, ProtoErrorKind::Io(..))
+        matches!(self.boxed_kind(), Some(ProtoErrorKind::Io(..)))
     }
 
     #[cfg(feature = "std")]
@@ -619,32 +748,39 @@ impl ProtoError {
 
     /// Compare two errors to see if one contains a server response.
     pub fn cmp_specificity(&self, other: &Self) -> Ordering {
-        let kind = self.kind();
-        let other = other.kind();
+        let kind = self.boxed_kind();
+        let other = other.boxed_kind();
 
         match (kind, other) {
-            (ProtoErrorKind::NoRecordsFound { .. }, ProtoErrorKind::NoRecordsFound { .. }) => {
+            (
+                Some(ProtoErrorKind::NoRecordsFound { .. }),
+                Some(ProtoErrorKind::NoRecordsFound { .. }),
+            ) => {
                 return Ordering::Equal;
             }
-            (ProtoErrorKind::NoRecordsFound { .. }, _) => return Ordering::Greater,
-            (_, ProtoErrorKind::NoRecordsFound { .. }) => return Ordering::Less,
+            (Some(ProtoErrorKind::NoRecordsFound { .. }), _) => return Ordering::Greater,
+            (_, Some(ProtoErrorKind::NoRecordsFound { .. })) => return Ordering::Less,
             _ => (),
         }
 
         match (kind, other) {
             #[cfg(feature = "std")]
-            (ProtoErrorKind::Io { .. }, ProtoErrorKind::Io { .. }) => return Ordering::Equal,
+            (Some(ProtoErrorKind::Io { .. }), Some(ProtoErrorKind::Io { .. })) => {
+                return Ordering::Equal;
+            }
             #[cfg(feature = "std")]
-            (ProtoErrorKind::Io { .. }, _) => return Ordering::Greater,
+            (Some(ProtoErrorKind::Io { .. }), _) => return Ordering::Greater,
             #[cfg(feature = "std")]
-            (_, ProtoErrorKind::Io { .. }) => return Ordering::Less,
+            (_, Some(ProtoErrorKind::Io { .. })) => return Ordering::Less,
             _ => (),
         }
 
         match (kind, other) {
-            (ProtoErrorKind::Timeout, ProtoErrorKind::Timeout) => return Ordering::Equal,
-            (ProtoErrorKind::Timeout, _) => return Ordering::Greater,
-            (_, ProtoErrorKind::Timeout) => return Ordering::Less,
+            (Some(ProtoErrorKind::Timeout), Some(ProtoErrorKind::Timeout)) => {
+                return Ordering::Equal;
+            }
+            (Some(ProtoErrorKind::Timeout), _) => return Ordering::Greater,
+            (_, Some(ProtoErrorKind::Timeout)) => return Ordering::Less,
             _ => (),
         }
 
@@ -654,14 +790,18 @@ impl ProtoError {
     /// Whether the query should be retried after this error
     pub fn should_retry(&self) -> bool {
         !matches!(
-            self.kind(),
-            ProtoErrorKind::NoConnections | ProtoErrorKind::NoRecordsFound { .. }
+            self.boxed_kind(),
+            Some(
+                ProtoErrorKind::NoConnections
+                    | ProtoErrorKind::NoReachableAuthority(..)
+                    | ProtoErrorKind::NoRecordsFound { .. }
+            )
         )
     }
 
     /// Whether this error should count as an attempt
     pub fn attempted(&self) -> bool {
-        !matches!(self.kind(), ProtoErrorKind::Busy)
+        !matches!(self.boxed_kind(), Some(ProtoErrorKind::Busy))
     }
 }
 
@@ -690,7 +830,7 @@ where
         let kind: ProtoErrorKind = error.into();
 
         Self {
-            kind: Box::new(kind),
+            kind: Kind::Boxed(Box::new(kind)),
             #[cfg(feature = "backtrace")]
             backtrack: trace!(),
         }
@@ -698,20 +838,75 @@ where
 }
 
 impl From<DecodeError> for ProtoError {
-    fn from(err: DecodeError) -> Self {
-        match err {
-            DecodeError::PointerNotPriorToLabel { idx, ptr } => {
-                ProtoErrorKind::PointerNotPriorToLabel { idx, ptr }
-            }
-            DecodeError::LabelBytesTooLong(len) => ProtoErrorKind::LabelBytesTooLong(len),
-            DecodeError::UnrecognizedLabelCode(code) => ProtoErrorKind::UnrecognizedLabelCode(code),
-            DecodeError::DomainNameTooLong(len) => ProtoErrorKind::DomainNameTooLong(len),
-            DecodeError::LabelOverlapsWithOther { label, other } => {
-                ProtoErrorKind::LabelOverlapsWithOther { label, other }
-            }
-            _ => ProtoErrorKind::Msg(err.to_string()),
+    fn from(error: DecodeError) -> Self {
+        Self {
+            kind: Kind::Decode {
+                error,
+                kind: OnceBox::new(),
+            },
+            #[cfg(feature = "backtrace")]
+            backtrack: trace!(),
+        }
+    }
+}
+
+/// Storage of the kind of a [`ProtoError`]
+///
+/// Errors of the binary decoder are kept inline, so that rejecting a malformed message doesn't
+/// allocate. Their [`ProtoErrorKind`] is only boxed once it's asked for.
+enum Kind {
+    Decode {
+        error: DecodeError,
+        kind: OnceBox<ProtoErrorKind>,
+    },
+    Boxed(Box<ProtoErrorKind>),
+}
+
+impl Clone for Kind {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Decode { error, .. } => Self::Decode {
+                error: *error,
+                kind: OnceBox::new(),
+            },
+            Self::Boxed(kind) => Self::Boxed(kind.clone()),
+        }
+    }
+}
+
+impl fmt::Debug for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode { error, .. } => f.debug_tuple("Decode").field(error).finish(),
+            Self::Boxed(kind) => fmt::Debug::fmt(kind, f),
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // the messages of the decoder errors are those of their kinds
+            Self::Decode { error, .. } => fmt::Display::fmt(error, f),
+            Self::Boxed(kind) => fmt::Display::fmt(kind, f),
         }
-        .into()
+    }
+}
+
+/// The kind of a decoder error, name errors keep the kinds they had before the decoder reported
+/// them separately
+fn decode_kind(error: DecodeError) -> ProtoErrorKind {
+    match error {
+        DecodeError::PointerNotPriorToLabel { idx, ptr } => {
+            ProtoErrorKind::PointerNotPriorToLabel { idx, ptr }
+        }
+        DecodeError::LabelBytesTooLong(len) => ProtoErrorKind::LabelBytesTooLong(len),
+        DecodeError::UnrecognizedLabelCode(code) => ProtoErrorKind::UnrecognizedLabelCode(code),
+        DecodeError::DomainNameTooLong(len) => ProtoErrorKind::DomainNameTooLong(len),
+        DecodeError::LabelOverlapsWithOther { label, other } => {
+            ProtoErrorKind::LabelOverlapsWithOther { label, other }
+        }
+        _ => ProtoErrorKind::Decode(error),
     }
 }
 
@@ -747,8 +942,8 @@ impl<T> From<sync::PoisonError<T>> for ProtoError {
 #[cfg(feature = "std")]
 impl From<ProtoError> for io::Error {
     fn from(e: ProtoError) -> Self {
-        match e.kind() {
-            ProtoErrorKind::Timeout => Self::new(io::ErrorKind::TimedOut, e),
+        match e.boxed_kind() {
+            Some(ProtoErrorKind::Timeout) => Self::new(io::ErrorKind::TimedOut, e),
             _ => Self::other(e),
         }
     }
@@ -773,10 +968,12 @@ impl Clone for ProtoErrorKind {
         match *self {
             BadQueryCount(count) => BadQueryCount(count),
             Busy => Busy,
+            LookupQueueFull { queued } => LookupQueueFull { queued },
             Canceled(ref c) => Canceled(*c),
             CharacterDataTooLong { max, len } => CharacterDataTooLong { max, len },
             LabelOverlapsWithOther { label, other } => LabelOverlapsWithOther { label, other },
             DnsKeyProtocolNot3(protocol) => DnsKeyProtocolNot3(protocol),
+            Decode(err) => Decode(err),
             DomainNameTooLong(len) => DomainNameTooLong(len),
             EdnsNameNotRoot(ref found) => EdnsNameNotRoot(found.clone()),
             FormError { header, ref error } => FormError {
@@ -785,6 +982,15 @@ impl Clone for ProtoErrorKind {
             },
             IncorrectRDataLengthRead { read, len } => IncorrectRDataLengthRead { read, len },
             LabelBytesTooLong(len) => LabelBytesTooLong(len),
+            LengthExceedsRemaining {
+                field,
+                len,
+                remaining,
+            } => LengthExceedsRemaining {
+                field,
+                len,
+                remaining,
+            },
             PointerNotPriorToLabel { idx, ptr } => PointerNotPriorToLabel { idx, ptr },
             MaxBufferSizeExceeded(max) => MaxBufferSizeExceeded(max),
             MaxRecordLimitExceeded { count, record_type } => {
@@ -793,7 +999,9 @@ impl Clone for ProtoErrorKind {
             Message(msg) => Message(msg),
             Msg(ref msg) => Msg(msg.clone()),
             NoConnections => NoConnections,
+            NoReachableAuthority(ref zone) => NoReachableAuthority(zone.clone()),
             NoError => NoError,
+            NotInAdditionalSection(record_type) => NotInAdditionalSection(record_type),
             NotAllRecordsWritten { count } => NotAllRecordsWritten { count },
             NoRecordsFound(ref inner) => NoRecordsFound(inner.clone()),
             RequestRefused => RequestRefused,
@@ -834,7 +1042,7 @@ impl Clone for ProtoErrorKind {
             #[cfg(feature = "__quic")]
             QuinnWriteError(ref e) => QuinnWriteError(e.clone()),
             #[cfg(feature = "__quic")]
-            QuicMessageIdNot0(val) => QuicMessageIdNot0(val),
+            DoqProtocol(ref e) => DoqProtocol(e.clone()),
             #[cfg(feature = "__quic")]
             QuinnReadError(ref e) => QuinnReadError(e.clone()),
             #[cfg(feature = "__quic")]
@@ -848,6 +1056,46 @@ impl Clone for ProtoErrorKind {
             #[cfg(feature = "__tls")]
             RustlsError(ref e) => RustlsError(e.clone()),
             QueryCaseMismatch => QueryCaseMismatch,
+            ConflictingResponses => ConflictingResponses,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::op::Message;
+    use crate::serialize::binary::BinDecodable;
+
+    #[test]
+    fn test_decode_error_not_boxed() {
+        // the header of the message is cut short
+        let error = Message::from_bytes(&[0, 1, 0]).unwrap_err();
+        let not_boxed = |error: &ProtoError| {
+            matches!(
+                &error.kind,
+                Kind::Decode { error: DecodeError::InsufficientBytes, kind } if kind.get().is_none()
+            )
+        };
+        assert!(not_boxed(&error));
+
+        // inspecting the error doesn't box its kind either
+        assert!(matches!(
+            error.decode_error(),
+            Some(DecodeError::InsufficientBytes)
+        ));
+        assert!(error.should_retry());
+        assert!(!error.is_no_records_found());
+        assert_eq!(error.to_string(), "unexpected end of input reached");
+        let error = error.into_form_error().unwrap_err();
+        assert!(not_boxed(&error.clone()));
+        assert!(not_boxed(&error));
+
+        // until its kind is asked for
+        assert!(matches!(
+            error.kind(),
+            ProtoErrorKind::Decode(DecodeError::InsufficientBytes)
+        ));
+        assert!(!not_boxed(&error));
+    }
+}
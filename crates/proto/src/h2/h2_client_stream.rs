@@ -10,6 +10,7 @@ use alloc::string::String;
 use alloc::sync::Arc;
 use core::fmt::{self, Display};
 use core::future::Future;
+#[cfg(feature = "__tls")]
 use core::ops::DerefMut;
 use core::pin::Pin;
 use core::str::FromStr;
@@ -19,23 +20,33 @@ use std::net::SocketAddr;
 
 use bytes::{Buf, Bytes, BytesMut};
 use futures_util::future::{FutureExt, TryFutureExt};
+#[cfg(feature = "__tls")]
 use futures_util::ready;
 use futures_util::stream::Stream;
 use h2::client::{Connection, SendRequest};
 use http::header::{self, CONTENT_LENGTH};
+#[cfg(feature = "__tls")]
 use rustls::ClientConfig;
+#[cfg(feature = "__tls")]
 use rustls::pki_types::ServerName;
-use tokio::time::{error, timeout};
+use tokio::io::{AsyncRead, AsyncWrite};
+#[cfg(feature = "__tls")]
+use tokio::time::error;
+use tokio::time::timeout;
+#[cfg(feature = "__tls")]
 use tokio_rustls::{TlsConnector, client::TlsStream as TokioTlsClientStream};
 use tracing::{debug, warn};
 
 use crate::error::ProtoError;
 use crate::http::Version;
+#[cfg(feature = "__tls")]
 use crate::runtime::RuntimeProvider;
 use crate::runtime::iocompat::AsyncIoStdAsTokio;
 use crate::tcp::DnsTcpStream;
+use crate::tls::TlsConnect;
 use crate::xfer::{CONNECT_TIMEOUT, DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream};
 
+#[cfg(feature = "__tls")]
 const ALPN_H2: &[u8] = b"h2";
 
 /// A DNS client connection for DNS-over-HTTPS
@@ -61,6 +72,34 @@ impl Display for HttpsClientStream {
 }
 
 impl HttpsClientStream {
+    /// Runs the h2 `connection` in the background, sending requests through `h2`
+    fn spawn<T>(
+        h2: SendRequest<Bytes>,
+        connection: Connection<T, Bytes>,
+        name_server: SocketAddr,
+        name_server_name: Arc<str>,
+        query_path: Arc<str>,
+    ) -> Self
+    where
+        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        // TODO: hand this back for others to run rather than spawning here?
+        debug!("h2 connection established to: {}", name_server);
+        tokio::spawn(
+            connection
+                .map_err(|e| warn!("h2 connection failed: {e}"))
+                .map(|_: Result<(), ()>| ()),
+        );
+
+        Self {
+            name_server_name,
+            name_server,
+            query_path,
+            h2,
+            is_shutdown: false,
+        }
+    }
+
     async fn inner_send(
         h2: SendRequest<Bytes>,
         message: Bytes,
@@ -289,6 +328,7 @@ impl Stream for HttpsClientStream {
     }
 }
 
+#[cfg(feature = "__tls")]
 /// A HTTPS connection builder for DNS-over-HTTPS
 #[derive(Clone)]
 pub struct HttpsClientStreamBuilder<P> {
@@ -297,6 +337,7 @@ pub struct HttpsClientStreamBuilder<P> {
     bind_addr: Option<SocketAddr>,
 }
 
+#[cfg(feature = "__tls")]
 impl<P: RuntimeProvider> HttpsClientStreamBuilder<P> {
     /// Constructs a new TlsStreamBuilder with the associated ClientConfig
     pub fn with_client_config(client_config: Arc<ClientConfig>, provider: P) -> Self {
@@ -348,11 +389,13 @@ impl<P: RuntimeProvider> HttpsClientStreamBuilder<P> {
     }
 }
 
+#[cfg(feature = "__tls")]
 /// A future that resolves to an HttpsClientStream
 pub struct HttpsClientConnect<S>(HttpsClientConnectState<S>)
 where
     S: DnsTcpStream;
 
+#[cfg(feature = "__tls")]
 impl<S: DnsTcpStream> HttpsClientConnect<S> {
     /// Creates a new HttpsStream with existing connection
     pub fn new<F>(
@@ -388,6 +431,7 @@ impl<S: DnsTcpStream> HttpsClientConnect<S> {
     }
 }
 
+#[cfg(feature = "__tls")]
 impl<S> Future for HttpsClientConnect<S>
 where
     S: DnsTcpStream,
@@ -399,12 +443,14 @@ where
     }
 }
 
+#[cfg(feature = "__tls")]
 struct TlsConfig {
     client_config: Arc<ClientConfig>,
     server_name: Arc<str>,
     path: Arc<str>,
 }
 
+#[cfg(feature = "__tls")]
 #[allow(clippy::type_complexity)]
 enum HttpsClientConnectState<S>
 where
@@ -453,6 +499,7 @@ where
     Errored(Option<ProtoError>),
 }
 
+#[cfg(feature = "__tls")]
 impl<S> Future for HttpsClientConnectState<S>
 where
     S: DnsTcpStream,
@@ -530,21 +577,13 @@ where
                             .map_err(|e| ProtoError::from(format!("h2 handshake error: {e}")))
                     )?;
 
-                    // TODO: hand this back for others to run rather than spawning here?
-                    debug!("h2 connection established to: {}", name_server);
-                    tokio::spawn(
-                        connection
-                            .map_err(|e| warn!("h2 connection failed: {e}"))
-                            .map(|_: Result<(), ()>| ()),
-                    );
-
-                    Self::Connected(Some(HttpsClientStream {
-                        name_server_name: Arc::clone(name_server_name),
-                        name_server: *name_server,
-                        query_path: Arc::clone(query_path),
-                        h2: send_request,
-                        is_shutdown: false,
-                    }))
+                    Self::Connected(Some(HttpsClientStream::spawn(
+                        send_request,
+                        connection,
+                        *name_server,
+                        Arc::clone(name_server_name),
+                        Arc::clone(query_path),
+                    )))
                 }
                 Self::Connected(conn) => {
                     return Poll::Ready(Ok(conn.take().expect("cannot poll after complete")));
@@ -559,6 +598,59 @@ where
     }
 }
 
+/// Creates a new DNS over HTTPS client stream, securing the connection produced by `future` with
+/// the TLS library behind `connector`.
+///
+/// The connector should offer `h2` through ALPN, e.g. a native-tls connector built with
+/// `request_alpns(&["h2"])`, the HTTP/2 connection is established without checking the
+/// negotiated protocol.
+///
+/// # Arguments
+///
+/// * `future` - A future producing DnsTcpStream
+/// * `name_server` - IP and Port for the remote DNS resolver
+/// * `server_name` - The DNS name associated with a certificate
+/// * `path` - The HTTP endpoint where the remote DNS resolver provides service, typically `/dns-query`
+/// * `connector` - Establishes the TLS session
+pub fn https_client_connect_with_connector<C, S, F>(
+    future: F,
+    name_server: SocketAddr,
+    server_name: Arc<str>,
+    path: Arc<str>,
+    connector: C,
+) -> Pin<Box<dyn Future<Output = Result<HttpsClientStream, ProtoError>> + Send>>
+where
+    C: TlsConnect,
+    S: DnsTcpStream,
+    F: Future<Output = io::Result<S>> + Send + Unpin + 'static,
+{
+    Box::pin(async move {
+        let tcp = future.await?;
+        debug!("tcp connection established to: {}", name_server);
+
+        let Ok(tls) = timeout(CONNECT_TIMEOUT, connector.connect(&server_name, tcp)).await else {
+            return Err(format!("TLS handshake timed out after {CONNECT_TIMEOUT:?}").into());
+        };
+        let tls = tls?;
+        debug!("tls connection established to: {}", name_server);
+
+        let mut handshake = h2::client::Builder::new();
+        handshake.enable_push(false);
+        let (send_request, connection) = handshake
+            .handshake(AsyncIoStdAsTokio(tls))
+            .await
+            .map_err(|e| ProtoError::from(format!("h2 handshake error: {e}")))?;
+
+        Ok(HttpsClientStream::spawn(
+            send_request,
+            connection,
+            name_server,
+            server_name,
+            path,
+        ))
+    })
+}
+
 /// A future that resolves to
 pub struct HttpsClientResponse(
     Pin<Box<dyn Future<Output = Result<DnsResponse, ProtoError>> + Send>>,
@@ -572,7 +664,10 @@ impl Future for HttpsClientResponse {
     }
 }
 
-#[cfg(any(feature = "webpki-roots", feature = "rustls-platform-verifier"))]
+#[cfg(all(
+    feature = "__tls",
+    any(feature = "webpki-roots", feature = "rustls-platform-verifier")
+))]
 #[cfg(test)]
 mod tests {
     use alloc::string::ToString;
@@ -12,6 +12,8 @@ pub mod h2_server;
 
 pub use crate::http::error::{Error as HttpsError, Result as HttpsResult};
 
+#[cfg(feature = "__tls")]
+pub use self::h2_client_stream::{HttpsClientConnect, HttpsClientStreamBuilder};
 pub use self::h2_client_stream::{
-    HttpsClientConnect, HttpsClientResponse, HttpsClientStream, HttpsClientStreamBuilder,
+    HttpsClientResponse, HttpsClientStream, https_client_connect_with_connector,
 };
@@ -52,6 +52,8 @@ macro_rules! try_ready_stream {
     }};
 }
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
 #[cfg(any(feature = "dnssec-aws-lc-rs", feature = "dnssec-ring"))]
 pub mod dnssec;
 mod error;
@@ -63,7 +65,11 @@ pub mod h3;
 pub mod http;
 #[cfg(feature = "mdns")]
 pub mod multicast;
+#[cfg(feature = "native-tls")]
+pub mod native_tls;
 pub mod op;
+#[cfg(feature = "pcap")]
+pub mod pcap;
 #[cfg(all(feature = "__quic", feature = "tokio"))]
 pub mod quic;
 pub mod rr;
@@ -76,6 +82,8 @@ pub mod serialize;
 pub mod tcp;
 #[cfg(all(feature = "std", any(test, feature = "testing")))]
 pub mod tests;
+#[cfg(any(feature = "__tls", feature = "native-tls"))]
+pub mod tls;
 #[cfg(feature = "std")]
 pub mod udp;
 pub mod xfer;
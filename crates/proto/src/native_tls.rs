@@ -0,0 +1,131 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TLS backend for DNS over TLS using the platform's TLS library through native-tls
+//!
+//! This is OpenSSL on Linux, Security.framework on macOS and SChannel on Windows, for deployments
+//! that need a FIPS validated library or the platform's trust store. DNS over HTTPS is supported
+//! as well with the `https-native-tls` feature, DNS over QUIC and HTTP/3 require rustls.
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use std::io;
+
+use tokio::net::TcpStream as TokioTcpStream;
+use tokio_native_tls::{TlsAcceptor, TlsConnector};
+
+use crate::runtime::iocompat::{AsyncIoStdAsTokio, AsyncIoTokioAsStd};
+use crate::tcp::DnsTcpStream;
+use crate::tls::{TlsAccept, TlsConnect, TlsFuture};
+
+/// Predefined type for the native-tls client stream over a connection of type `S`
+pub type NativeTlsClientStream<S> = tokio_native_tls::TlsStream<AsyncIoStdAsTokio<S>>;
+
+/// Predefined type for the native-tls server stream
+pub type NativeTlsServerStream = tokio_native_tls::TlsStream<TokioTcpStream>;
+
+impl TlsConnect for TlsConnector {
+    type Stream<S: DnsTcpStream> = AsyncIoTokioAsStd<NativeTlsClientStream<S>>;
+
+    fn connect<S: DnsTcpStream>(&self, server_name: &str, stream: S) -> TlsFuture<Self::Stream<S>> {
+        let connector = self.clone();
+        let server_name = server_name.to_owned();
+
+        Box::pin(async move {
+            connector
+                .connect(&server_name, AsyncIoStdAsTokio(stream))
+                .await
+                .map(AsyncIoTokioAsStd)
+                .map_err(io::Error::other)
+        })
+    }
+}
+
+impl TlsAccept for TlsAcceptor {
+    type Stream = AsyncIoTokioAsStd<NativeTlsServerStream>;
+
+    fn accept(&self, stream: TokioTcpStream) -> TlsFuture<Self::Stream> {
+        let acceptor = self.clone();
+
+        Box::pin(async move {
+            acceptor
+                .accept(stream)
+                .await
+                .map(AsyncIoTokioAsStd)
+                .map_err(io::Error::other)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+    use alloc::boxed::Box;
+    use alloc::string::String;
+    use std::{env, fs};
+
+    use futures_util::StreamExt;
+    use native_tls::{Certificate, Identity};
+    use test_support::subscribe;
+    use tokio::net::{TcpListener, TcpStream as TokioTcpStream};
+
+    use super::*;
+    use crate::tls::{tls_client_connect_with_connector, tls_from_stream};
+    use crate::xfer::{DnsStreamHandle, SerialMessage};
+
+    #[tokio::test]
+    async fn test_native_tls_stream() {
+        subscribe();
+
+        let server_path = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "../..".to_owned());
+        let read = |file: &str| fs::read(format!("{server_path}/tests/test-data/{file}")).unwrap();
+
+        let identity = Identity::from_pkcs8(&read("cert.pem"), &read("cert.key")).unwrap();
+        let acceptor = TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+
+        // echo every message back to the client
+        let server = tokio::spawn(async move {
+            let (tcp, src_addr) = listener.accept().await.unwrap();
+            let tls = TlsAccept::accept(&acceptor, tcp).await.unwrap();
+            let (mut stream, mut handle) = tls_from_stream(tls, src_addr);
+            while let Some(message) = stream.next().await {
+                handle.send(message.unwrap()).unwrap();
+            }
+        });
+
+        let ca = Certificate::from_pem(&read("ca.pem")).unwrap();
+        let connector = native_tls::TlsConnector::builder()
+            .add_root_certificate(ca)
+            .build()
+            .unwrap();
+
+        let tcp = Box::pin(async move {
+            TokioTcpStream::connect(server_addr)
+                .await
+                .map(AsyncIoTokioAsStd)
+        });
+        let (stream, mut handle) = tls_client_connect_with_connector(
+            tcp,
+            server_addr,
+            String::from("ns.example.com"),
+            TlsConnector::from(connector),
+        );
+        let mut stream = stream.await.unwrap();
+
+        let sent = b"DNS over native TLS".to_vec();
+        handle
+            .send(SerialMessage::new(sent.clone(), server_addr))
+            .unwrap();
+        let received = stream.next().await.unwrap().unwrap();
+        assert_eq!(received.bytes(), sent);
+
+        server.abort();
+    }
+}
@@ -0,0 +1,355 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Rendering of a [`Message`] in the layout of `dig` output
+
+use core::fmt;
+
+use data_encoding::{HEXLOWER, HEXUPPER};
+
+use crate::op::{Edns, Message, MessageType, OpCode, ResponseCode};
+use crate::rr::Record;
+use crate::rr::rdata::opt::{EdnsCode, EdnsOption};
+
+/// Displays a [`Message`] the way `dig` prints responses
+///
+/// The header and its flags are followed by the EDNS pseudosection, if present, and then by each
+/// non-empty section with its records on tab separated lines. This is meant for human consumption,
+/// e.g. in logs or when comparing messages in tests, and is not guaranteed to be stable.
+///
+/// Created with [`Message::dig_display()`].
+///
+/// ```
+/// use std::str::FromStr;
+/// use hickory_proto::op::{Message, MessageType, OpCode, Query};
+/// use hickory_proto::rr::{Name, RecordType};
+///
+/// let mut message = Message::new(10, MessageType::Query, OpCode::Query);
+/// message.set_recursion_desired(true);
+/// message.add_query(Query::query(Name::from_str("example.com.").unwrap(), RecordType::A));
+///
+/// let output = message.dig_display().to_string();
+/// assert!(output.starts_with(";; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 10\n"));
+/// assert!(output.contains(";; flags: rd; QUERY: 1, ANSWER: 0, AUTHORITY: 0, ADDITIONAL: 0\n"));
+/// assert!(output.contains(";example.com.\tIN\tA\n"));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct DigDisplay<'a> {
+    message: &'a Message,
+}
+
+impl<'a> DigDisplay<'a> {
+    pub(crate) fn new(message: &'a Message) -> Self {
+        Self { message }
+    }
+}
+
+impl fmt::Display for DigDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = self.message;
+        let header = message.header();
+
+        writeln!(
+            f,
+            ";; ->>HEADER<<- opcode: {op_code}, status: {status}, id: {id}",
+            op_code = OpCodeMnemonic(header.op_code()),
+            status = ResponseCodeMnemonic(message.response_code()),
+            id = header.id(),
+        )?;
+
+        f.write_str(";; flags:")?;
+        let flags = [
+            (header.message_type() == MessageType::Response, "qr"),
+            (header.authoritative(), "aa"),
+            (header.truncated(), "tc"),
+            (header.recursion_desired(), "rd"),
+            (header.recursion_available(), "ra"),
+            (header.authentic_data(), "ad"),
+            (header.checking_disabled(), "cd"),
+        ];
+        for (_, flag) in flags.iter().filter(|(set, _)| *set) {
+            write!(f, " {flag}")?;
+        }
+
+        // an update reuses the sections as ZONE, PREREQUISITE, UPDATE and ADDITIONAL
+        let (names, titles) = match header.op_code() {
+            OpCode::Update => (
+                ["ZONE", "PREREQ", "UPDATE", "ADDITIONAL"],
+                ["ZONE", "PREREQUISITE", "UPDATE", "ADDITIONAL"],
+            ),
+            _ => (
+                ["QUERY", "ANSWER", "AUTHORITY", "ADDITIONAL"],
+                ["QUESTION", "ANSWER", "AUTHORITY", "ADDITIONAL"],
+            ),
+        };
+        writeln!(
+            f,
+            "; {}: {}, {}: {}, {}: {}, {}: {}",
+            names[0],
+            message.queries().len(),
+            names[1],
+            message.answers().len(),
+            names[2],
+            message.name_servers().len(),
+            names[3],
+            message.additionals().len() + usize::from(message.extensions().is_some()),
+        )?;
+
+        if let Some(edns) = message.extensions() {
+            f.write_str("\n;; OPT PSEUDOSECTION:\n")?;
+            write_edns(edns, f)?;
+        }
+
+        if !message.queries().is_empty() {
+            writeln!(f, "\n;; {} SECTION:", titles[0])?;
+            for query in message.queries() {
+                writeln!(
+                    f,
+                    ";{name}\t{class}\t{ty}",
+                    name = query.name(),
+                    class = query.query_class(),
+                    ty = query.query_type(),
+                )?;
+            }
+        }
+
+        write_section(titles[1], message.answers(), f)?;
+        write_section(titles[2], message.name_servers(), f)?;
+        write_section(titles[3], message.additionals(), f)?;
+
+        Ok(())
+    }
+}
+
+fn write_section(title: &str, records: &[Record], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(f, "\n;; {title} SECTION:")?;
+    for record in records {
+        writeln!(
+            f,
+            "{name}\t{ttl}\t{class}\t{ty}\t{rdata}",
+            name = record.name(),
+            ttl = record.ttl(),
+            class = record.dns_class(),
+            ty = record.record_type(),
+            rdata = record.data(),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_edns(edns: &Edns, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "; EDNS: version: {}, flags:", edns.version())?;
+    if edns.flags().dnssec_ok {
+        f.write_str(" do")?;
+    }
+    if edns.flags().z != 0 {
+        write!(f, " MBZ: {:#06x}", edns.flags().z)?;
+    }
+    writeln!(f, "; udp: {}", edns.max_payload())?;
+
+    for (code, option) in edns.options().as_ref() {
+        write!(f, "; {}: ", EdnsCodeMnemonic(*code))?;
+        match option {
+            #[cfg(feature = "__dnssec")]
+            EdnsOption::DAU(algorithms) => {
+                let mut iter = algorithms.iter();
+                if let Some(first) = iter.next() {
+                    write!(f, "{first}")?;
+                }
+                for algorithm in iter {
+                    write!(f, " {algorithm}")?;
+                }
+            }
+            EdnsOption::Subnet(subnet) => write!(
+                f,
+                "{}/{}/{}",
+                subnet.addr(),
+                subnet.source_prefix(),
+                subnet.scope_prefix()
+            )?,
+            EdnsOption::NSID(payload) => write_opaque(payload.as_ref(), f)?,
+            EdnsOption::Cookie(cookie) => {
+                f.write_str(&HEXLOWER.encode(cookie.client_cookie()))?;
+                if let Some(server) = cookie.server_cookie() {
+                    f.write_str(&HEXLOWER.encode(server))?;
+                }
+            }
+            EdnsOption::ExtendedDnsError(error) => {
+                let info_code = error.info_code();
+                write!(f, "{} ({info_code})", u16::from(info_code))?;
+                if !error.extra_text().is_empty() {
+                    write!(f, ": ({})", error.extra_text())?;
+                }
+            }
+            EdnsOption::Unknown(_, data) => write_opaque(data, f)?,
+        }
+        f.write_str("\n")?;
+    }
+
+    Ok(())
+}
+
+/// Writes option data in hex, followed by its printable form like `dig` does for NSID
+fn write_opaque(data: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&HEXUPPER.encode(data))?;
+    if !data.is_empty() && data.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+        f.write_str(" (\"")?;
+        for b in data {
+            write!(f, "{}", char::from(*b))?;
+        }
+        f.write_str("\")")?;
+    }
+
+    Ok(())
+}
+
+struct OpCodeMnemonic(OpCode);
+
+impl fmt::Display for OpCodeMnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            OpCode::Unknown(code) => write!(f, "RESERVED{code}"),
+            op_code => write!(f, "{op_code}"),
+        }
+    }
+}
+
+struct ResponseCodeMnemonic(ResponseCode);
+
+impl fmt::Display for ResponseCodeMnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self.0 {
+            ResponseCode::NoError => "NOERROR",
+            ResponseCode::FormErr => "FORMERR",
+            ResponseCode::ServFail => "SERVFAIL",
+            ResponseCode::NXDomain => "NXDOMAIN",
+            ResponseCode::NotImp => "NOTIMP",
+            ResponseCode::Refused => "REFUSED",
+            ResponseCode::YXDomain => "YXDOMAIN",
+            ResponseCode::YXRRSet => "YXRRSET",
+            ResponseCode::NXRRSet => "NXRRSET",
+            ResponseCode::NotAuth => "NOTAUTH",
+            ResponseCode::NotZone => "NOTZONE",
+            ResponseCode::BADVERS | ResponseCode::BADSIG => "BADVERS",
+            ResponseCode::BADKEY => "BADKEY",
+            ResponseCode::BADTIME => "BADTIME",
+            ResponseCode::BADMODE => "BADMODE",
+            ResponseCode::BADNAME => "BADNAME",
+            ResponseCode::BADALG => "BADALG",
+            ResponseCode::BADTRUNC => "BADTRUNC",
+            ResponseCode::BADCOOKIE => "BADCOOKIE",
+            ResponseCode::Unknown(code) => return write!(f, "RESERVED{code}"),
+        };
+
+        f.write_str(s)
+    }
+}
+
+struct EdnsCodeMnemonic(EdnsCode);
+
+impl fmt::Display for EdnsCodeMnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self.0 {
+            EdnsCode::LLQ => "LLQ",
+            EdnsCode::UL => "UL",
+            EdnsCode::NSID => "NSID",
+            EdnsCode::DAU => "DAU",
+            EdnsCode::DHU => "DHU",
+            EdnsCode::N3U => "N3U",
+            EdnsCode::Subnet => "CLIENT-SUBNET",
+            EdnsCode::Expire => "EXPIRE",
+            EdnsCode::Cookie => "COOKIE",
+            EdnsCode::Keepalive => "TCP-KEEPALIVE",
+            EdnsCode::Padding => "PADDING",
+            EdnsCode::Chain => "CHAIN",
+            EdnsCode::ExtendedDnsError => "EDE",
+            code @ (EdnsCode::Zero | EdnsCode::Unknown(_)) => {
+                return write!(f, "OPT={}", u16::from(code));
+            }
+        };
+
+        f.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use core::str::FromStr;
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::op::Query;
+    use crate::rr::rdata::{A, NS};
+    use crate::rr::{Name, RData, RecordType};
+
+    #[test]
+    fn test_response() {
+        let name = Name::from_str("www.example.com.").unwrap();
+
+        let mut message = Message::new(4242, MessageType::Response, OpCode::Query);
+        message
+            .set_recursion_desired(true)
+            .set_recursion_available(true)
+            .set_response_code(ResponseCode::NXDomain);
+        message.add_query(Query::query(name.clone(), RecordType::A));
+        message.add_answer(Record::from_rdata(
+            name.clone(),
+            300,
+            RData::A(A(Ipv4Addr::new(192, 0, 2, 1))),
+        ));
+        message.add_name_server(Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            3600,
+            RData::NS(NS(Name::from_str("ns.example.com.").unwrap())),
+        ));
+
+        let mut edns = Edns::new();
+        edns.set_max_payload(1232).set_dnssec_ok(true);
+        edns.options_mut()
+            .insert(EdnsOption::NSID(b"ns1".as_slice().try_into().unwrap()));
+        message.set_edns(edns);
+
+        let expected = "\
+;; ->>HEADER<<- opcode: QUERY, status: NXDOMAIN, id: 4242
+;; flags: qr rd ra; QUERY: 1, ANSWER: 1, AUTHORITY: 1, ADDITIONAL: 1
+
+;; OPT PSEUDOSECTION:
+; EDNS: version: 0, flags: do; udp: 1232
+; NSID: 6E7331 (\"ns1\")
+
+;; QUESTION SECTION:
+;www.example.com.\tIN\tA
+
+;; ANSWER SECTION:
+www.example.com.\t300\tIN\tA\t192.0.2.1
+
+;; AUTHORITY SECTION:
+example.com.\t3600\tIN\tNS\tns.example.com.
+";
+        assert_eq!(message.dig_display().to_string(), expected);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut message = Message::new(0, MessageType::Query, OpCode::Update);
+        message.add_query(Query::query(
+            Name::from_str("example.com.").unwrap(),
+            RecordType::SOA,
+        ));
+
+        let output = message.dig_display().to_string();
+        assert!(output.starts_with(";; ->>HEADER<<- opcode: UPDATE, status: NOERROR, id: 0\n"));
+        assert!(output.contains("; ZONE: 1, PREREQ: 0, UPDATE: 0, ADDITIONAL: 0\n"));
+        assert!(output.contains("\n;; ZONE SECTION:\n;example.com.\tIN\tSOA\n"));
+    }
+}
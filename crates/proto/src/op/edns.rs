@@ -20,7 +20,7 @@ use crate::{
         DNSClass, Name, RData, Record, RecordType,
         rdata::{
             OPT,
-            opt::{EdnsCode, EdnsOption},
+            opt::{ClientSubnet, CookieOption, EdnsCode, EdnsOption, NSIDPayload},
         },
     },
     serialize::binary::{BinEncodable, BinEncoder},
@@ -61,6 +61,25 @@ impl Edns {
         Self::default()
     }
 
+    /// Builder for an extended DNS object
+    ///
+    /// ```
+    /// use hickory_proto::op::Edns;
+    /// use hickory_proto::rr::rdata::opt::CookieOption;
+    ///
+    /// let edns = Edns::builder()
+    ///     .max_payload(1232)
+    ///     .dnssec_ok(true)
+    ///     .option(CookieOption::new([0x42; 8]))
+    ///     .build();
+    ///
+    /// assert_eq!(edns.max_payload(), 1232);
+    /// assert_eq!(edns.cookie().unwrap().client_cookie(), &[0x42; 8]);
+    /// ```
+    pub fn builder() -> EdnsBuilder {
+        EdnsBuilder::default()
+    }
+
     /// The high order bytes for the response code in the DNS Message
     pub fn rcode_high(&self) -> u8 {
         self.rcode_high
@@ -91,6 +110,39 @@ impl Edns {
         self.options.get(code)
     }
 
+    /// Returns the DNSSEC algorithms understood by the sender, from the DAU option
+    #[cfg(feature = "__dnssec")]
+    pub fn dau(&self) -> Option<&SupportedAlgorithms> {
+        match self.option(EdnsCode::DAU)? {
+            EdnsOption::DAU(algorithms) => Some(algorithms),
+            _ => None,
+        }
+    }
+
+    /// Returns the client subnet option
+    pub fn client_subnet(&self) -> Option<&ClientSubnet> {
+        match self.option(EdnsCode::Subnet)? {
+            EdnsOption::Subnet(subnet) => Some(subnet),
+            _ => None,
+        }
+    }
+
+    /// Returns the payload of the NSID option
+    pub fn nsid(&self) -> Option<&NSIDPayload> {
+        match self.option(EdnsCode::NSID)? {
+            EdnsOption::NSID(payload) => Some(payload),
+            _ => None,
+        }
+    }
+
+    /// Returns the cookie option
+    pub fn cookie(&self) -> Option<&CookieOption> {
+        match self.option(EdnsCode::Cookie)? {
+            EdnsOption::Cookie(cookie) => Some(cookie),
+            _ => None,
+        }
+    }
+
     /// Returns the options portion of EDNS
     pub fn options(&self) -> &OPT {
         &self.options
@@ -163,6 +215,51 @@ impl Edns {
     }
 }
 
+/// Builder for [`Edns`], see [`Edns::builder()`]
+#[derive(Debug, Default, Clone)]
+pub struct EdnsBuilder {
+    edns: Edns,
+}
+
+impl EdnsBuilder {
+    /// Sets the maximum payload which can be supported, at least 512 bytes
+    pub fn max_payload(mut self, max_payload: u16) -> Self {
+        self.edns.set_max_payload(max_payload);
+        self
+    }
+
+    /// Sets the DNSSEC OK bit
+    pub fn dnssec_ok(mut self, dnssec_ok: bool) -> Self {
+        self.edns.set_dnssec_ok(dnssec_ok);
+        self
+    }
+
+    /// Sets the EDNS version
+    pub fn version(mut self, version: u8) -> Self {
+        self.edns.set_version(version);
+        self
+    }
+
+    /// Sets the high order bits for the response code
+    pub fn rcode_high(mut self, rcode_high: u8) -> Self {
+        self.edns.set_rcode_high(rcode_high);
+        self
+    }
+
+    /// Adds an option, replacing any option with the same code
+    pub fn option(mut self, option: impl Into<EdnsOption>) -> Self {
+        let option = option.into();
+        self.edns.options.remove(EdnsCode::from(&option));
+        self.edns.options.insert(option);
+        self
+    }
+
+    /// Returns the extended DNS object
+    pub fn build(self) -> Edns {
+        self.edns
+    }
+}
+
 // FIXME: this should be a TryFrom
 impl<'a> From<&'a Record> for Edns {
     fn from(value: &'a Record) -> Self {
@@ -325,4 +422,32 @@ mod tests {
         edns.options_mut().remove(EdnsCode::DAU);
         assert!(edns.option(EdnsCode::DAU).is_none());
     }
+
+    #[test]
+    fn test_builder() {
+        let cookie = CookieOption::with_server_cookie([0x01; 8], [0x02; 8]).unwrap();
+        let edns = Edns::builder()
+            .max_payload(1232)
+            .dnssec_ok(true)
+            .version(0)
+            .option(CookieOption::new([0x01; 8]))
+            .option(cookie.clone())
+            .option(NSIDPayload::new(*b"ns1").unwrap())
+            .build();
+
+        assert_eq!(edns.max_payload(), 1232);
+        assert!(edns.flags().dnssec_ok);
+        assert_eq!(edns.cookie(), Some(&cookie));
+        assert_eq!(edns.options().get_all(EdnsCode::Cookie).len(), 1);
+        assert_eq!(edns.nsid().unwrap().as_ref(), b"ns1");
+        assert!(edns.client_subnet().is_none());
+        assert!(edns.dau().is_none());
+
+        let edns_decode = Edns::from(&Record::from(&edns));
+        assert_eq!(edns, edns_decode);
+        assert_eq!(edns_decode.cookie(), Some(&cookie));
+
+        // values lower than 512 are treated as 512
+        assert_eq!(Edns::builder().max_payload(0).build().max_payload(), 512);
+    }
 }
@@ -7,7 +7,7 @@
 
 //! Basic protocol message for DNS
 
-use alloc::{boxed::Box, fmt, vec::Vec};
+use alloc::{boxed::Box, fmt, format, vec::Vec};
 use core::{iter, mem, ops::Deref};
 
 #[cfg(feature = "serde")]
@@ -16,11 +16,15 @@ use tracing::{debug, warn};
 
 #[cfg(any(feature = "std", feature = "no-std-rand"))]
 use crate::random;
+#[cfg(feature = "std")]
+use crate::xfer::BufferPool;
 use crate::{
     error::*,
-    op::{Edns, Header, MessageType, OpCode, Query, ResponseCode},
+    op::{DigDisplay, Edns, Header, MessageType, OpCode, Query, ResponseCode},
     rr::{Record, RecordType},
-    serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, EncodeMode},
+    serialize::binary::{
+        BinDecodable, BinDecoder, BinEncodable, BinEncoder, DecodeMode, EncodeMode,
+    },
     xfer::DnsResponse,
 };
 
@@ -605,6 +609,11 @@ impl Message {
         &mut self.edns
     }
 
+    /// Returns an object that displays the message in the layout of `dig` output
+    pub fn dig_display(&self) -> DigDisplay<'_> {
+        DigDisplay::new(self)
+    }
+
     /// # Return value
     ///
     /// the max payload value as it's defined in the EDNS section.
@@ -654,6 +663,9 @@ impl Message {
     pub fn read_queries(decoder: &mut BinDecoder<'_>, count: usize) -> ProtoResult<Vec<Query>> {
         let mut queries = Vec::with_capacity(count);
         for _ in 0..count {
+            if decoder.mode() == DecodeMode::Lenient && decoder.is_empty() {
+                break;
+            }
             queries.push(Query::read(decoder)?);
         }
         Ok(queries)
@@ -688,6 +700,11 @@ impl Message {
         let mut sig = MessageSignature::default();
 
         for _ in 0..count {
+            // tolerate counts exceeding the records actually present
+            if decoder.mode() == DecodeMode::Lenient && decoder.is_empty() {
+                break;
+            }
+
             let record = Record::read(decoder)?;
 
             // There must be no additional records after a TSIG/SIG(0) record.
@@ -702,11 +719,7 @@ impl Message {
                     RecordType::OPT | RecordType::SIG | RecordType::TSIG
                 )
             {
-                return Err(format!(
-                    "record type {} only allowed in additional section",
-                    record.record_type()
-                )
-                .into());
+                return Err(ProtoErrorKind::NotInAdditionalSection(record.record_type()).into());
             } else if !is_additional {
                 records.push(record);
                 continue;
@@ -734,21 +747,53 @@ impl Message {
 
     /// Decodes a message from the buffer.
     pub fn from_vec(buffer: &[u8]) -> ProtoResult<Self> {
-        let mut decoder = BinDecoder::new(buffer);
-        Self::read(&mut decoder)
+        Self::from_vec_with_mode(buffer, DecodeMode::Normal)
+    }
+
+    /// Decodes a message from the buffer, handling non-conforming data according to `mode`
+    ///
+    /// ```
+    /// use hickory_proto::op::{Message, OpCode};
+    /// use hickory_proto::serialize::binary::DecodeMode;
+    ///
+    /// let mut buffer = Message::response(0, OpCode::Query).to_vec().unwrap();
+    /// buffer.push(0);
+    ///
+    /// assert!(Message::from_vec_with_mode(&buffer, DecodeMode::Normal).is_ok());
+    /// assert!(Message::from_vec_with_mode(&buffer, DecodeMode::Strict).is_err());
+    /// ```
+    pub fn from_vec_with_mode(buffer: &[u8], mode: DecodeMode) -> ProtoResult<Self> {
+        let mut decoder = BinDecoder::with_mode(buffer, mode);
+        let message = Self::read(&mut decoder)?;
+
+        if mode == DecodeMode::Strict && !decoder.is_empty() {
+            return Err(format!("{} trailing bytes after message", decoder.len()).into());
+        }
+
+        Ok(message)
     }
 
     /// Encodes the Message into a buffer
+    ///
+    /// With the `std` feature the message is encoded into a scratch buffer of
+    /// [`BufferPool::global`], and copied into a buffer of its exact size.
     pub fn to_vec(&self) -> Result<Vec<u8>, ProtoError> {
         // TODO: this feels like the right place to verify the max packet size of the message,
         //  will need to update the header for truncation and the lengths if we send less than the
         //  full response. This needs to conform with the EDNS settings of the server...
+        #[cfg(feature = "std")]
+        let mut buffer = BufferPool::global().get();
+        #[cfg(not(feature = "std"))]
         let mut buffer = Vec::with_capacity(512);
+
         {
             let mut encoder = BinEncoder::new(&mut buffer);
             self.emit(&mut encoder)?;
         }
 
+        #[cfg(feature = "std")]
+        return Ok(buffer.to_vec());
+        #[cfg(not(feature = "std"))]
         Ok(buffer)
     }
 
@@ -1064,6 +1109,9 @@ impl<'r> BinDecodable<'r> for Message {
         let count = header.query_count() as usize;
         let mut queries = Vec::with_capacity(count);
         for _ in 0..count {
+            if decoder.mode() == DecodeMode::Lenient && decoder.is_empty() {
+                break;
+            }
             queries.push(Query::read(decoder)?);
         }
 
@@ -1159,6 +1207,7 @@ pub enum MessageSignature {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::serialize::binary::DecodeError;
 
     #[cfg(feature = "__dnssec")]
     use crate::rr::RecordType;
@@ -1173,6 +1222,31 @@ mod tests {
     #[cfg(feature = "std")]
     use crate::std::string::ToString;
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_to_vec_exact_size() {
+        // a new thread starts without idle buffers
+        std::thread::spawn(|| {
+            let mut message = Message::query();
+            message.add_query(Query::query(
+                Name::from_ascii("www.example.com.").unwrap(),
+                crate::rr::RecordType::A,
+            ));
+
+            let buffer = message.to_vec().unwrap();
+            assert_eq!(buffer.capacity(), buffer.len());
+            assert_eq!(
+                Message::from_vec(&buffer).unwrap().queries(),
+                message.queries()
+            );
+
+            // the scratch buffer went back to the pool
+            assert_eq!(BufferPool::global().len(), 1);
+        })
+        .join()
+        .unwrap();
+    }
+
     #[test]
     fn test_emit_and_read_header() {
         let mut message = Message::response(10, OpCode::Update);
@@ -1319,6 +1393,38 @@ mod tests {
         assert_eq!(message.id(), 4_096);
     }
 
+    #[test]
+    fn test_malformed_message_errors() {
+        #[rustfmt::skip]
+        let buf: &[u8] = &[
+            0x10, 0x00, 0x81, 0x80, // id = 4096, response, op=query, rd, ra, no_error
+            0x00, 0x00, 0x00, 0x01, // 0 queries, 1 answer,
+            0x00, 0x00, 0x00, 0x00, // 0 nameservers, 0 additional record
+            0x00,                   // root
+            0x00, 0x01, 0x00, 0x01, // RecordType = A, Class = IN
+            0x00, 0x00, 0x00, 0x02, // TTL = 2 seconds
+            0x00, 0x08,             // record length = 8, but only 4 remain
+            0x5D, 0xB8, 0xD7, 0x0E,
+        ];
+
+        // malformed packets are reported with structured kinds, not formatted messages
+        let err = Message::from_bytes(buf).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ProtoErrorKind::LengthExceedsRemaining {
+                field: "rdata",
+                len: 8,
+                remaining: 4,
+            }
+        ));
+
+        let err = Message::from_bytes(&buf[..20]).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ProtoErrorKind::Decode(DecodeError::InsufficientBytes)
+        ));
+    }
+
     #[test]
     fn rdata_zero_roundtrip() {
         let buf = &[
@@ -1328,6 +1434,41 @@ mod tests {
         assert!(Message::from_bytes(buf).is_err());
     }
 
+    #[test]
+    fn test_decode_modes() {
+        let mut message = Message::response(0, OpCode::Query);
+        message.add_answer(Record::from_rdata(
+            Name::from_labels(vec!["www", "example", "com"]).unwrap(),
+            300,
+            RData::A(A::new(127, 0, 0, 1)),
+        ));
+        let bytes = message.to_vec().unwrap();
+
+        // trailing bytes are only rejected in strict mode
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Message::from_vec_with_mode(&trailing, DecodeMode::Strict).is_err());
+        assert!(Message::from_vec_with_mode(&trailing, DecodeMode::Normal).is_ok());
+        assert!(Message::from_vec_with_mode(&trailing, DecodeMode::Lenient).is_ok());
+        assert!(Message::from_vec_with_mode(&bytes, DecodeMode::Strict).is_ok());
+
+        // an answer count exceeding the records present
+        let mut bad_count = bytes.clone();
+        bad_count[7] = 2;
+        assert!(Message::from_vec_with_mode(&bad_count, DecodeMode::Normal).is_err());
+        let lenient = Message::from_vec_with_mode(&bad_count, DecodeMode::Lenient).unwrap();
+        assert_eq!(lenient.answers(), message.answers());
+
+        // an rdata length covering more than the A record
+        let mut long_rdata = bytes.clone();
+        let rdlength = long_rdata.len() - 5;
+        long_rdata[rdlength] = 5;
+        long_rdata.push(0);
+        assert!(Message::from_vec_with_mode(&long_rdata, DecodeMode::Normal).is_err());
+        let lenient = Message::from_vec_with_mode(&long_rdata, DecodeMode::Lenient).unwrap();
+        assert_eq!(lenient.answers(), message.answers());
+    }
+
     #[test]
     fn nsec_deserialization() {
         const CRASHING_MESSAGE: &[u8] = &[
@@ -17,21 +17,27 @@
 //! Operations to send with a `Client` or server, e.g. `Query`, `Message`, or `UpdateMessage` can
 //! be used together to either query or update resource records sets.
 
+mod dig;
 mod edns;
 pub mod header;
 mod lower_query;
 pub mod message;
+pub mod mutation;
 pub mod op_code;
 pub mod query;
 pub mod response_code;
+mod truncation;
 pub mod update_message;
 
-pub use self::edns::{Edns, EdnsFlags};
+pub use self::dig::DigDisplay;
+pub use self::edns::{Edns, EdnsBuilder, EdnsFlags};
 pub use self::header::Header;
 pub use self::header::MessageType;
 pub use self::message::{Message, MessageParts, MessageSignature, MessageSigner, MessageVerifier};
+pub use self::mutation::{MessageSection, Mutation, Mutator};
 pub use self::op_code::OpCode;
 pub use self::query::Query;
 pub use self::response_code::ResponseCode;
+pub use self::truncation::{Truncation, Truncator};
 pub use lower_query::LowerQuery;
 pub use update_message::UpdateMessage;
@@ -0,0 +1,473 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Structured corruption of encoded messages, e.g. for fuzzing or for hostile test servers
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{
+    error::{ProtoError, ProtoResult},
+    op::Message,
+};
+
+/// Length of the message header, which ends with the four section counts
+const HEADER_LEN: usize = 12;
+
+/// The largest offset a compression pointer can refer to
+const MAX_POINTER: u16 = 0x3FFF;
+
+/// Produces systematically corrupted variants of an encoded message
+///
+/// The message is only parsed as far as needed to locate the owner names, the RDATA of the records
+/// and the section counts, so the variants keep everything else of the original intact. Each
+/// [`Mutation`] breaks exactly one invariant of the wire format, which helps to narrow down the
+/// cause when a decoder misbehaves on one of them.
+#[derive(Clone, Debug)]
+pub struct Mutator {
+    bytes: Vec<u8>,
+    counts: [u16; 4],
+    queries: Vec<Entry>,
+    records: Vec<Entry>,
+}
+
+impl Mutator {
+    /// Creates a mutator for the encoding of a valid `message`
+    pub fn new(message: &Message) -> ProtoResult<Self> {
+        Self::from_vec(message.to_vec()?)
+    }
+
+    /// Creates a mutator for an encoded message
+    ///
+    /// Fails if the header, the questions or the records announced by the header can not be
+    /// located in `bytes`; trailing bytes are kept as is.
+    pub fn from_bytes(bytes: &[u8]) -> ProtoResult<Self> {
+        Self::from_vec(bytes.to_vec())
+    }
+
+    fn from_vec(bytes: Vec<u8>) -> ProtoResult<Self> {
+        if bytes.len() < HEADER_LEN {
+            return Err(ProtoError::from("message is shorter than its header"));
+        }
+
+        let mut counts = [0; 4];
+        for (i, count) in counts.iter_mut().enumerate() {
+            *count = read_u16(&bytes, 4 + 2 * i)?;
+        }
+
+        let mut offset = HEADER_LEN;
+        let mut queries = Vec::with_capacity(usize::from(counts[0]));
+        for _ in 0..counts[0] {
+            let name = offset..skip_name(&bytes, offset)?;
+            // QTYPE and QCLASS
+            offset = name.end + 4;
+            if offset > bytes.len() {
+                return Err(ProtoError::from(
+                    "question extends past the end of the message",
+                ));
+            }
+
+            queries.push(Entry {
+                name,
+                rdata: 0..0,
+                end: offset,
+            });
+        }
+
+        let record_count = counts[1..].iter().map(|&count| usize::from(count)).sum();
+        let mut records = Vec::with_capacity(record_count);
+        for _ in 0..record_count {
+            let name = offset..skip_name(&bytes, offset)?;
+            // TYPE, CLASS and TTL precede the RDLENGTH
+            let rdlength = read_u16(&bytes, name.end + 8)?;
+            let rdata = name.end + 10..name.end + 10 + usize::from(rdlength);
+            if rdata.end > bytes.len() {
+                return Err(ProtoError::from(
+                    "record extends past the end of the message",
+                ));
+            }
+
+            offset = rdata.end;
+            records.push(Entry {
+                name,
+                end: rdata.end,
+                rdata,
+            });
+        }
+
+        Ok(Self {
+            bytes,
+            counts,
+            queries,
+            records,
+        })
+    }
+
+    /// The encoded message without mutations
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// All mutations that apply to the message
+    ///
+    /// These are:
+    ///
+    /// - each section count off by one in both directions, and set to its maximum
+    /// - the RDATA of each record truncated, and its RDLENGTH off by one in both directions
+    /// - each owner name replaced by a compression pointer to itself, to the header and past the
+    ///   end of the message
+    /// - the message cut off in the middle of the header and before the last byte of each
+    ///   question and record
+    pub fn mutations(&self) -> Vec<Mutation> {
+        let mut mutations = Vec::new();
+
+        for section in MessageSection::ALL {
+            let count = self.counts[section as usize];
+            let mut push = |count| {
+                let mutation = Mutation::Count { section, count };
+                if !mutations.contains(&mutation) {
+                    mutations.push(mutation);
+                }
+            };
+
+            if let Some(more) = count.checked_add(1) {
+                push(more);
+            }
+            if let Some(fewer) = count.checked_sub(1) {
+                push(fewer);
+            }
+            if count != u16::MAX {
+                push(u16::MAX);
+            }
+        }
+
+        for (record, entry) in self.records.iter().enumerate() {
+            if !entry.rdata.is_empty() {
+                mutations.push(Mutation::RDataTruncated { record });
+                mutations.push(Mutation::RDataLengthTooShort { record });
+            }
+            if entry.rdata.len() < usize::from(u16::MAX) {
+                mutations.push(Mutation::RDataLengthTooLong { record });
+            }
+        }
+
+        for entry in self.queries.iter().chain(&self.records) {
+            let offset = entry.name.start;
+            let itself = u16::try_from(offset)
+                .ok()
+                .filter(|&target| target <= MAX_POINTER);
+            for target in itself.into_iter().chain([0, MAX_POINTER]) {
+                mutations.push(Mutation::Pointer { offset, target });
+            }
+        }
+
+        mutations.push(Mutation::Truncated {
+            length: HEADER_LEN / 2,
+        });
+        for entry in self.queries.iter().chain(&self.records) {
+            mutations.push(Mutation::Truncated {
+                length: entry.end - 1,
+            });
+        }
+
+        mutations
+    }
+
+    /// Applies the `mutation` to the encoded message
+    ///
+    /// Returns `None` if the mutation does not apply to this message, e.g. because it refers to
+    /// a record that the message does not have.
+    pub fn apply(&self, mutation: Mutation) -> Option<Vec<u8>> {
+        let mut bytes = self.bytes.clone();
+        match mutation {
+            Mutation::Truncated { length } => {
+                if length >= bytes.len() {
+                    return None;
+                }
+                bytes.truncate(length);
+            }
+
+            Mutation::Count { section, count } => {
+                write_u16(&mut bytes, 4 + 2 * section as usize, count);
+            }
+
+            Mutation::RDataTruncated { record } => {
+                let rdata = &self.records.get(record)?.rdata;
+                let rdlength = u16::try_from(rdata.len()).ok()?.checked_sub(1)?;
+                bytes.remove(rdata.end - 1);
+                write_u16(&mut bytes, rdata.start - 2, rdlength);
+            }
+
+            Mutation::RDataLengthTooLong { record } => {
+                let rdata = &self.records.get(record)?.rdata;
+                let rdlength = u16::try_from(rdata.len()).ok()?.checked_add(1)?;
+                write_u16(&mut bytes, rdata.start - 2, rdlength);
+            }
+
+            Mutation::RDataLengthTooShort { record } => {
+                let rdata = &self.records.get(record)?.rdata;
+                let rdlength = u16::try_from(rdata.len()).ok()?.checked_sub(1)?;
+                write_u16(&mut bytes, rdata.start - 2, rdlength);
+            }
+
+            Mutation::Pointer { offset, target } => {
+                if target > MAX_POINTER {
+                    return None;
+                }
+
+                let entry = self
+                    .queries
+                    .iter()
+                    .chain(&self.records)
+                    .find(|entry| entry.name.start == offset)?;
+                bytes.splice(entry.name.clone(), (0xC000 | target).to_be_bytes());
+            }
+        }
+
+        Some(bytes)
+    }
+
+    /// All variants of the message, in the order of [`Mutator::mutations()`]
+    pub fn variants(&self) -> impl Iterator<Item = (Mutation, Vec<u8>)> + '_ {
+        self.mutations()
+            .into_iter()
+            .filter_map(|mutation| Some((mutation, self.apply(mutation)?)))
+    }
+}
+
+/// A single corruption of an encoded message, see [`Mutator`]
+///
+/// Records are numbered in the order of the message, starting with the first record of the
+/// answer section; questions are not counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Mutation {
+    /// The message is cut off after `length` bytes
+    Truncated {
+        /// The number of bytes that are kept
+        length: usize,
+    },
+    /// The header announces `count` entries in the `section`
+    Count {
+        /// The section of the count
+        section: MessageSection,
+        /// The announced number of entries
+        count: u16,
+    },
+    /// The last byte of the RDATA of the record is removed, and the RDLENGTH adjusted to match
+    RDataTruncated {
+        /// The index of the record
+        record: usize,
+    },
+    /// The RDLENGTH of the record is one larger than its RDATA
+    RDataLengthTooLong {
+        /// The index of the record
+        record: usize,
+    },
+    /// The RDLENGTH of the record is one smaller than its RDATA
+    RDataLengthTooShort {
+        /// The index of the record
+        record: usize,
+    },
+    /// The owner name at `offset` is replaced by a compression pointer to `target`
+    ///
+    /// Compression pointers of later names are not adjusted, so they may point to a different
+    /// location than before.
+    Pointer {
+        /// The offset of the owner name of a question or record
+        offset: usize,
+        /// The offset the pointer refers to, at most `0x3FFF`
+        target: u16,
+    },
+}
+
+/// A section of a message, in the order of the header counts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageSection {
+    /// The question section
+    Query,
+    /// The answer section
+    Answer,
+    /// The authority section
+    Authority,
+    /// The additional section, including the EDNS OPT record and the message signature
+    Additional,
+}
+
+impl MessageSection {
+    const ALL: [Self; 4] = [Self::Query, Self::Answer, Self::Authority, Self::Additional];
+}
+
+/// The location of a question or a record in the encoded message
+#[derive(Clone, Debug)]
+struct Entry {
+    name: Range<usize>,
+    /// Empty for questions
+    rdata: Range<usize>,
+    end: usize,
+}
+
+/// Returns the offset after the name that starts at `offset`
+fn skip_name(bytes: &[u8], mut offset: usize) -> ProtoResult<usize> {
+    loop {
+        let Some(&len) = bytes.get(offset) else {
+            return Err(ProtoError::from("name extends past the end of the message"));
+        };
+
+        match len & 0xC0 {
+            0x00 if len == 0 => return Ok(offset + 1),
+            0x00 => offset += 1 + usize::from(len),
+            0xC0 if offset + 2 <= bytes.len() => return Ok(offset + 2),
+            0xC0 => return Err(ProtoError::from("name extends past the end of the message")),
+            _ => return Err(ProtoError::from("unsupported label type")),
+        }
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> ProtoResult<u16> {
+    match bytes.get(offset..offset + 2) {
+        Some(&[high, low]) => Ok(u16::from_be_bytes([high, low])),
+        _ => Err(ProtoError::from("message ends in the middle of a field")),
+    }
+}
+
+fn write_u16(bytes: &mut [u8], offset: usize, value: u16) {
+    bytes[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+    use core::str::FromStr;
+
+    use super::*;
+    use crate::op::{Edns, MessageType, OpCode, Query};
+    use crate::rr::rdata::{A, NS};
+    use crate::rr::{Name, RData, Record, RecordType};
+    use crate::serialize::binary::BinDecodable;
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn message() -> Message {
+        let mut message = Message::new(1, MessageType::Response, OpCode::Query);
+        message.add_query(Query::query(name("www.example.com."), RecordType::A));
+        message.add_answer(Record::from_rdata(
+            name("www.example.com."),
+            300,
+            RData::A(A(Ipv4Addr::new(192, 0, 2, 1))),
+        ));
+        message.add_name_server(Record::from_rdata(
+            name("example.com."),
+            300,
+            RData::NS(NS(name("ns.example.com."))),
+        ));
+        message.set_edns(Edns::new());
+        message
+    }
+
+    #[test]
+    fn test_mutations() {
+        let message = message();
+        let mutator = Mutator::new(&message).unwrap();
+        assert_eq!(
+            Message::from_bytes(mutator.bytes())
+                .unwrap()
+                .to_vec()
+                .unwrap(),
+            mutator.bytes()
+        );
+
+        let mutations = mutator.mutations();
+        assert!(mutations.contains(&Mutation::Count {
+            section: MessageSection::Answer,
+            count: 2,
+        }));
+        assert!(mutations.contains(&Mutation::Count {
+            section: MessageSection::Authority,
+            count: 0,
+        }));
+        // the OPT record has no RDATA
+        assert!(mutations.contains(&Mutation::RDataTruncated { record: 1 }));
+        assert!(!mutations.contains(&Mutation::RDataTruncated { record: 2 }));
+        assert!(mutations.contains(&Mutation::RDataLengthTooLong { record: 2 }));
+        assert!(mutations.contains(&Mutation::Pointer {
+            offset: HEADER_LEN,
+            target: HEADER_LEN as u16,
+        }));
+
+        // every variant differs from the original, and decoding it must not panic
+        let mut failures = 0;
+        for (mutation, bytes) in mutator.variants() {
+            assert_ne!(bytes, mutator.bytes(), "{mutation:?}");
+            if Message::from_bytes(&bytes).is_err() {
+                failures += 1;
+            }
+        }
+        assert!(failures > 0);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mutator = Mutator::new(&message()).unwrap();
+
+        let bytes = mutator
+            .apply(Mutation::Count {
+                section: MessageSection::Answer,
+                count: 2,
+            })
+            .unwrap();
+        assert!(Message::from_bytes(&bytes).is_err());
+
+        // the A record loses a byte of its address
+        let bytes = mutator
+            .apply(Mutation::RDataTruncated { record: 0 })
+            .unwrap();
+        assert_eq!(bytes.len(), mutator.bytes().len() - 1);
+        assert!(Message::from_bytes(&bytes).is_err());
+
+        let bytes = mutator
+            .apply(Mutation::Pointer {
+                offset: HEADER_LEN,
+                target: HEADER_LEN as u16,
+            })
+            .unwrap();
+        assert_eq!(
+            &bytes[HEADER_LEN..HEADER_LEN + 2],
+            &[0xC0, HEADER_LEN as u8]
+        );
+        assert!(Message::from_bytes(&bytes).is_err());
+
+        let bytes = mutator.apply(Mutation::Truncated { length: 6 }).unwrap();
+        assert_eq!(bytes, &mutator.bytes()[..6]);
+
+        assert!(
+            mutator
+                .apply(Mutation::RDataLengthTooLong { record: 3 })
+                .is_none()
+        );
+        assert!(
+            mutator
+                .apply(Mutation::Pointer {
+                    offset: HEADER_LEN + 1,
+                    target: 0,
+                })
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_invalid_message() {
+        assert!(Mutator::from_bytes(&[0; 6]).is_err());
+
+        // one question announced, but none present
+        let mut bytes = [0; HEADER_LEN];
+        bytes[5] = 1;
+        assert!(Mutator::from_bytes(&bytes).is_err());
+    }
+}
@@ -61,11 +61,12 @@ use serde::{Deserialize, Serialize};
 ///
 ///                 6-15            Reserved for future use.
 ///  ```
-#[derive(Debug, Eq, PartialEq, PartialOrd, Copy, Clone, Hash)]
+#[derive(Debug, Eq, PartialEq, PartialOrd, Copy, Clone, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[allow(dead_code)]
 pub enum ResponseCode {
     /// No Error [RFC 1035](https://tools.ietf.org/html/rfc1035)
+    #[default]
     NoError,
 
     /// Format Error [RFC 1035](https://tools.ietf.org/html/rfc1035)
@@ -185,12 +186,6 @@ impl ResponseCode {
     }
 }
 
-impl Default for ResponseCode {
-    fn default() -> Self {
-        Self::NoError
-    }
-}
-
 impl Display for ResponseCode {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
         f.write_str(self.to_str())
@@ -0,0 +1,365 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Truncation of messages to a size budget
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "__dnssec")]
+use crate::dnssec::rdata::DNSSECRData;
+#[cfg(feature = "__dnssec")]
+use crate::rr::RData;
+use crate::{
+    error::ProtoResult,
+    op::Message,
+    rr::{DNSClass, Name, Record, RecordType},
+    serialize::binary::{BinEncodable, BinEncoder},
+};
+
+/// Fits messages into a size budget, e.g. the maximum payload of a UDP response
+///
+/// Whole RRsets are dropped from the end of the additional section first, then from the
+/// authority section, and finally from the answer section, until the encoded message fits. RRSIG
+/// records are dropped together with the RRset they cover. The question, the EDNS OPT record and
+/// the message signature are always kept, and count towards the budget.
+///
+/// The TC bit is set if records were dropped from the answer or the authority section; records
+/// in the additional section are optional ([RFC 2181, section 9]), so dropping only those does
+/// not truncate the message.
+///
+/// Messages should be truncated before they are signed, as dropping records invalidates an
+/// existing signature.
+///
+/// [RFC 2181, section 9]: https://www.rfc-editor.org/rfc/rfc2181#section-9
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Truncator {
+    max_size: u16,
+}
+
+impl Truncator {
+    /// Creates a truncator for messages of at most `max_size` bytes
+    pub fn new(max_size: u16) -> Self {
+        Self { max_size }
+    }
+
+    /// The size budget, in bytes
+    pub fn max_size(&self) -> u16 {
+        self.max_size
+    }
+
+    /// Drops records from the `message` until it fits into the size budget
+    ///
+    /// If the message does not fit even without any records, all of them are dropped and the
+    /// message is returned as is otherwise; encoding it with a size limit will then fail.
+    pub fn truncate(&self, message: &mut Message) -> ProtoResult<Truncation> {
+        let mut truncation = Truncation::default();
+        if self.fits(message)? {
+            return Ok(truncation);
+        }
+
+        for section in [Section::Additional, Section::Authority, Section::Answer] {
+            let rrsets = rrsets(section.take(message));
+            let total = rrsets.iter().map(Vec::len).sum::<usize>();
+
+            // the message does not fit with all the RRsets of this section, so find the largest
+            // number of them that does; the encoded size never shrinks by adding records
+            let (mut fitting, mut too_many) = (None, rrsets.len());
+            let mut low = 0;
+            while low < too_many {
+                let mid = low + (too_many - low) / 2;
+                *section.records_mut(message) = rrsets[..mid].concat();
+                if self.fits(message)? {
+                    fitting = Some(mid);
+                    low = mid + 1;
+                } else {
+                    too_many = mid;
+                }
+            }
+
+            let kept = rrsets[..fitting.unwrap_or(0)].concat();
+            let dropped = total - kept.len();
+            *section.records_mut(message) = kept;
+            match section {
+                Section::Answer => truncation.answers = dropped,
+                Section::Authority => truncation.name_servers = dropped,
+                Section::Additional => truncation.additionals = dropped,
+            }
+
+            if fitting.is_some() {
+                break;
+            }
+        }
+
+        if truncation.answers > 0 || truncation.name_servers > 0 {
+            message.set_truncated(true);
+        }
+
+        Ok(truncation)
+    }
+
+    fn fits(&self, message: &Message) -> ProtoResult<bool> {
+        let mut buffer = Vec::with_capacity(usize::from(self.max_size));
+        let mut encoder = BinEncoder::new(&mut buffer);
+        message.emit(&mut encoder)?;
+        Ok(buffer.len() <= usize::from(self.max_size))
+    }
+}
+
+/// The records dropped by a [`Truncator`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Truncation {
+    /// Number of records dropped from the answer section
+    pub answers: usize,
+    /// Number of records dropped from the authority section
+    pub name_servers: usize,
+    /// Number of records dropped from the additional section
+    pub additionals: usize,
+}
+
+impl Truncation {
+    /// Returns `true` if any records were dropped
+    pub fn dropped_records(&self) -> bool {
+        self.answers > 0 || self.name_servers > 0 || self.additionals > 0
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Answer,
+    Authority,
+    Additional,
+}
+
+impl Section {
+    fn take(self, message: &mut Message) -> Vec<Record> {
+        match self {
+            Self::Answer => message.take_answers(),
+            Self::Authority => message.take_name_servers(),
+            Self::Additional => message.take_additionals(),
+        }
+    }
+
+    fn records_mut(self, message: &mut Message) -> &mut Vec<Record> {
+        match self {
+            Self::Answer => message.answers_mut(),
+            Self::Authority => message.name_servers_mut(),
+            Self::Additional => message.additionals_mut(),
+        }
+    }
+}
+
+/// Groups records into RRsets, in the order of their first record
+fn rrsets(records: Vec<Record>) -> Vec<Vec<Record>> {
+    let mut rrsets = Vec::<(RRsetKey, Vec<Record>)>::new();
+    for record in records {
+        let key = RRsetKey::from(&record);
+        match rrsets.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, rrset)) => rrset.push(record),
+            None => rrsets.push((key, vec![record])),
+        }
+    }
+
+    rrsets.into_iter().map(|(_, rrset)| rrset).collect()
+}
+
+#[derive(PartialEq, Eq)]
+struct RRsetKey {
+    name: Name,
+    dns_class: DNSClass,
+    /// The covered type for RRSIG records, which belong to the RRset they sign
+    record_type: RecordType,
+}
+
+impl From<&Record> for RRsetKey {
+    fn from(record: &Record) -> Self {
+        #[cfg(feature = "__dnssec")]
+        let record_type = match record.data() {
+            RData::DNSSEC(DNSSECRData::RRSIG(rrsig)) => rrsig.input().type_covered,
+            _ => record.record_type(),
+        };
+        #[cfg(not(feature = "__dnssec"))]
+        let record_type = record.record_type();
+
+        Self {
+            name: record.name().clone(),
+            dns_class: record.dns_class(),
+            record_type,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+    use core::str::FromStr;
+
+    use super::*;
+    use crate::op::{Edns, MessageType, OpCode, Query};
+    use crate::rr::RData;
+    use crate::rr::rdata::{A, NS, TXT};
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn message() -> Message {
+        let mut message = Message::new(1, MessageType::Response, OpCode::Query);
+        message.add_query(Query::query(name("www.example.com."), RecordType::A));
+
+        for i in 1..=4 {
+            message.add_answer(Record::from_rdata(
+                name("www.example.com."),
+                300,
+                RData::A(A(Ipv4Addr::new(192, 0, 2, i))),
+            ));
+        }
+
+        for ns in ["ns1.example.com.", "ns2.example.com."] {
+            message.add_name_server(Record::from_rdata(
+                name("example.com."),
+                300,
+                RData::NS(NS(name(ns))),
+            ));
+        }
+
+        for (ns, i) in [("ns1.example.com.", 1), ("ns2.example.com.", 2)] {
+            message.add_additional(Record::from_rdata(
+                name(ns),
+                300,
+                RData::A(A(Ipv4Addr::new(198, 51, 100, i))),
+            ));
+        }
+
+        message
+    }
+
+    fn encoded_len(message: &Message) -> usize {
+        message.to_vec().unwrap().len()
+    }
+
+    #[test]
+    fn test_fits() {
+        let mut message = message();
+        let len = encoded_len(&message);
+
+        let truncation = Truncator::new(len as u16).truncate(&mut message).unwrap();
+        assert!(!truncation.dropped_records());
+        assert!(!message.truncated());
+        assert_eq!(message.additionals().len(), 2);
+    }
+
+    #[test]
+    fn test_drops_additionals_first() {
+        let mut message = message();
+        let len = encoded_len(&message);
+
+        let truncation = Truncator::new(len as u16 - 1)
+            .truncate(&mut message)
+            .unwrap();
+        assert_eq!(
+            truncation,
+            Truncation {
+                answers: 0,
+                name_servers: 0,
+                additionals: 1,
+            }
+        );
+        // glue is optional
+        assert!(!message.truncated());
+        assert_eq!(message.additionals()[0].name(), &name("ns1.example.com."));
+        assert_eq!(message.name_servers().len(), 2);
+        assert!(encoded_len(&message) < len);
+    }
+
+    #[test]
+    fn test_drops_answer_rrsets_atomically() {
+        let mut message = message();
+        message.add_answer(Record::from_rdata(
+            name("www.example.com."),
+            300,
+            RData::TXT(TXT::new(vec!["a".repeat(200)])),
+        ));
+
+        let mut without_answers = message.clone();
+        without_answers.take_answers();
+        without_answers.take_name_servers();
+        without_answers.take_additionals();
+        let max_size = encoded_len(&without_answers) as u16 + 100;
+
+        let truncation = Truncator::new(max_size).truncate(&mut message).unwrap();
+        assert_eq!(truncation.additionals, 2);
+        assert_eq!(truncation.name_servers, 2);
+        assert_eq!(truncation.answers, 1);
+        assert!(message.truncated());
+
+        // all of the A records fit, the TXT record is dropped
+        assert_eq!(message.answers().len(), 4);
+        assert!(
+            message
+                .answers()
+                .iter()
+                .all(|record| record.record_type() == RecordType::A)
+        );
+
+        // the A RRset does not fit as a whole
+        let mut message = message.clone();
+        let truncation = Truncator::new(encoded_len(&without_answers) as u16 + 20)
+            .truncate(&mut message)
+            .unwrap();
+        assert_eq!(truncation.answers, 4);
+        assert!(message.answers().is_empty());
+        assert!(message.truncated());
+    }
+
+    #[test]
+    fn test_preserves_edns() {
+        let mut message = message();
+        message.set_edns(Edns::builder().max_payload(1232).build());
+        let len = encoded_len(&message);
+
+        Truncator::new(len as u16 - 1)
+            .truncate(&mut message)
+            .unwrap();
+        assert_eq!(message.max_payload(), 1232);
+        assert!(encoded_len(&message) < len);
+
+        let decoded = Message::from_vec(&message.to_vec().unwrap()).unwrap();
+        assert_eq!(decoded.extensions(), message.extensions());
+        assert_eq!(decoded.additionals().len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "__dnssec")]
+    fn test_rrsig_stays_with_rrset() {
+        use crate::serialize::binary::{BinDecoder, Restrict};
+
+        // RRSIG covering A, ECDSAP256SHA256, 3 labels, TTL 300, signed by the root
+        let rdata = [
+            0, 1, 13, 3, 0, 0, 1, 44, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3,
+        ];
+        let rrsig = RData::read(
+            &mut BinDecoder::new(&rdata),
+            RecordType::RRSIG,
+            Restrict::new(rdata.len() as u16),
+        )
+        .unwrap();
+
+        let mut records = message().take_answers();
+        records.push(Record::from_rdata(
+            name("www.example.com."),
+            300,
+            RData::TXT(TXT::new(vec!["txt".into()])),
+        ));
+        records.push(Record::from_rdata(name("www.example.com."), 300, rrsig));
+
+        let rrsets = rrsets(records);
+        assert_eq!(rrsets.len(), 2);
+        assert_eq!(rrsets[0].len(), 5);
+        assert_eq!(rrsets[0][4].record_type(), RecordType::RRSIG);
+        assert_eq!(rrsets[1][0].record_type(), RecordType::TXT);
+    }
+}
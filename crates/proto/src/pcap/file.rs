@@ -0,0 +1,345 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Readers for the pcap and pcapng capture file formats
+
+use alloc::vec::Vec;
+use core::time::Duration;
+use std::io::{self, Read};
+
+use crate::error::{ProtoError, ProtoResult};
+
+/// Captures larger than this are rejected instead of allocated, real link layers are far smaller
+const MAX_BLOCK_LEN: usize = 16 * 1024 * 1024;
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+
+const PCAPNG_OPTION_END: u16 = 0;
+const PCAPNG_OPTION_TSRESOL: u16 = 9;
+
+/// A single captured frame, still including its link layer header
+pub(super) struct Frame {
+    /// Time of capture since the UNIX epoch
+    pub(super) timestamp: Duration,
+    /// The `LINKTYPE_*` value of the interface the frame was captured on
+    pub(super) link_type: u32,
+    pub(super) data: Vec<u8>,
+}
+
+/// Reads frames from either a pcap or pcapng file, detected from the leading magic number
+pub(super) enum CaptureReader<R> {
+    Pcap(PcapReader<R>),
+    PcapNg(PcapNgReader<R>),
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub(super) fn new(mut reader: R) -> ProtoResult<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        if u32::from_le_bytes(magic) == PCAPNG_SECTION_HEADER {
+            PcapNgReader::new(reader, magic).map(Self::PcapNg)
+        } else {
+            PcapReader::new(reader, magic).map(Self::Pcap)
+        }
+    }
+
+    /// Returns the next frame, or `None` at the end of the capture
+    pub(super) fn next_frame(&mut self) -> ProtoResult<Option<Frame>> {
+        match self {
+            Self::Pcap(reader) => reader.next_frame(),
+            Self::PcapNg(reader) => reader.next_frame(),
+        }
+    }
+}
+
+/// The classic libpcap format
+pub(super) struct PcapReader<R> {
+    reader: R,
+    big_endian: bool,
+    nanos: bool,
+    link_type: u32,
+}
+
+impl<R: Read> PcapReader<R> {
+    fn new(mut reader: R, magic: [u8; 4]) -> ProtoResult<Self> {
+        let (big_endian, nanos) = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+            (PCAP_MAGIC_MICROS, _) => (false, false),
+            (PCAP_MAGIC_NANOS, _) => (false, true),
+            (_, PCAP_MAGIC_MICROS) => (true, false),
+            (_, PCAP_MAGIC_NANOS) => (true, true),
+            _ => return Err(ProtoError::from("not a pcap or pcapng capture")),
+        };
+
+        // version (4), thiszone (4), sigfigs (4), snaplen (4), network (4)
+        let mut header = [0; 20];
+        reader.read_exact(&mut header)?;
+        let link_type = read_u32(&header[16..], big_endian);
+
+        Ok(Self {
+            reader,
+            big_endian,
+            nanos,
+            link_type,
+        })
+    }
+
+    fn next_frame(&mut self) -> ProtoResult<Option<Frame>> {
+        let mut header = [0; 16];
+        if !read_exact_or_eof(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+
+        let seconds = read_u32(&header[0..], self.big_endian);
+        let fraction = read_u32(&header[4..], self.big_endian);
+        let captured_len = read_u32(&header[8..], self.big_endian) as usize;
+        if captured_len > MAX_BLOCK_LEN {
+            return Err(ProtoError::from(format!(
+                "pcap record too large: {captured_len}"
+            )));
+        }
+
+        let mut data = vec![0; captured_len];
+        self.reader.read_exact(&mut data)?;
+
+        let nanos = match self.nanos {
+            true => fraction,
+            false => fraction.saturating_mul(1_000),
+        };
+
+        Ok(Some(Frame {
+            timestamp: Duration::new(u64::from(seconds), 0)
+                + Duration::from_nanos(u64::from(nanos)),
+            link_type: self.link_type,
+            data,
+        }))
+    }
+}
+
+/// The pcapng format, <https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-03.html>
+pub(super) struct PcapNgReader<R> {
+    reader: R,
+    big_endian: bool,
+    interfaces: Vec<Interface>,
+    /// The block type already read while detecting the format
+    pending_type: Option<[u8; 4]>,
+}
+
+struct Interface {
+    link_type: u32,
+    /// Units of the timestamps per second
+    resolution: u64,
+}
+
+impl<R: Read> PcapNgReader<R> {
+    fn new(reader: R, magic: [u8; 4]) -> ProtoResult<Self> {
+        let mut this = Self {
+            reader,
+            big_endian: false,
+            interfaces: Vec::new(),
+            pending_type: Some(magic),
+        };
+
+        // the first block must be a section header, this establishes the byte order
+        match this.next_block()? {
+            Some((PCAPNG_SECTION_HEADER, _)) => Ok(this),
+            _ => Err(ProtoError::from(
+                "pcapng capture is missing its section header",
+            )),
+        }
+    }
+
+    fn next_frame(&mut self) -> ProtoResult<Option<Frame>> {
+        while let Some((block_type, body)) = self.next_block()? {
+            match block_type {
+                PCAPNG_INTERFACE_DESCRIPTION => self.interface(&body)?,
+                PCAPNG_ENHANCED_PACKET => return self.enhanced_packet(&body).map(Some),
+                PCAPNG_SIMPLE_PACKET => return self.simple_packet(&body).map(Some),
+                // name resolution, statistics, custom blocks, etc. carry no packets
+                _ => continue,
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Reads the type and body of the next block
+    fn next_block(&mut self) -> ProtoResult<Option<(u32, Vec<u8>)>> {
+        let mut block_type = [0; 4];
+        match self.pending_type.take() {
+            Some(pending) => block_type = pending,
+            None => {
+                if !read_exact_or_eof(&mut self.reader, &mut block_type)? {
+                    return Ok(None);
+                }
+            }
+        }
+
+        let mut length = [0; 4];
+        self.reader.read_exact(&mut length)?;
+
+        // the section header is palindromic, its byte order magic decides the endianness
+        let section_header = u32::from_le_bytes(block_type) == PCAPNG_SECTION_HEADER;
+        if section_header {
+            let mut byte_order = [0; 4];
+            self.reader.read_exact(&mut byte_order)?;
+            self.big_endian = match (
+                u32::from_le_bytes(byte_order),
+                u32::from_be_bytes(byte_order),
+            ) {
+                (PCAPNG_BYTE_ORDER_MAGIC, _) => false,
+                (_, PCAPNG_BYTE_ORDER_MAGIC) => true,
+                _ => return Err(ProtoError::from("invalid pcapng byte order magic")),
+            };
+
+            // interface ids are scoped to their section
+            self.interfaces.clear();
+        }
+
+        let total_len = read_u32(&length, self.big_endian) as usize;
+        if !(12..=MAX_BLOCK_LEN).contains(&total_len) || total_len % 4 != 0 {
+            return Err(ProtoError::from(format!(
+                "invalid pcapng block length: {total_len}"
+            )));
+        }
+
+        // the leading type and length, and the trailing copy of the length, frame the body
+        let consumed = if section_header { 4 } else { 0 };
+        let body_len = (total_len - 12)
+            .checked_sub(consumed)
+            .ok_or_else(|| ProtoError::from("pcapng section header too short"))?;
+        let mut body = vec![0; body_len + 4];
+        self.reader.read_exact(&mut body)?;
+        body.truncate(body_len);
+
+        Ok(Some((read_u32(&block_type, self.big_endian), body)))
+    }
+
+    fn interface(&mut self, body: &[u8]) -> ProtoResult<()> {
+        if body.len() < 8 {
+            return Err(ProtoError::from("pcapng interface description too short"));
+        }
+
+        let link_type = u32::from(read_u16(&body[0..], self.big_endian));
+        let mut resolution = 1_000_000;
+
+        let mut options = &body[8..];
+        while options.len() >= 4 {
+            let code = read_u16(&options[0..], self.big_endian);
+            let len = usize::from(read_u16(&options[2..], self.big_endian));
+            let padded = (len + 3) & !3;
+            if code == PCAPNG_OPTION_END || options.len() < 4 + padded {
+                break;
+            }
+
+            if code == PCAPNG_OPTION_TSRESOL && len == 1 {
+                let value = options[4];
+                let exponent = u32::from(value & 0x7f);
+                resolution = match value & 0x80 {
+                    0 => 10u64.checked_pow(exponent),
+                    _ => 2u64.checked_pow(exponent),
+                }
+                .ok_or_else(|| ProtoError::from("unsupported pcapng timestamp resolution"))?;
+            }
+
+            options = &options[4 + padded..];
+        }
+
+        self.interfaces.push(Interface {
+            link_type,
+            resolution,
+        });
+        Ok(())
+    }
+
+    fn enhanced_packet(&self, body: &[u8]) -> ProtoResult<Frame> {
+        if body.len() < 20 {
+            return Err(ProtoError::from("pcapng enhanced packet too short"));
+        }
+
+        let interface = self.interface_by_id(read_u32(&body[0..], self.big_endian))?;
+        let high = u64::from(read_u32(&body[4..], self.big_endian));
+        let low = u64::from(read_u32(&body[8..], self.big_endian));
+        let captured_len = read_u32(&body[12..], self.big_endian) as usize;
+
+        let data = body
+            .get(20..20 + captured_len)
+            .ok_or_else(|| ProtoError::from("pcapng enhanced packet truncated"))?;
+
+        let units = (high << 32) | low;
+        let resolution = interface.resolution;
+        let fraction = u128::from(units % resolution) * 1_000_000_000 / u128::from(resolution);
+
+        Ok(Frame {
+            timestamp: Duration::new(units / resolution, fraction as u32),
+            link_type: interface.link_type,
+            data: data.to_vec(),
+        })
+    }
+
+    fn simple_packet(&self, body: &[u8]) -> ProtoResult<Frame> {
+        if body.len() < 4 {
+            return Err(ProtoError::from("pcapng simple packet too short"));
+        }
+
+        // simple packets have no timestamp and are always from the first interface
+        let interface = self.interface_by_id(0)?;
+        let original_len = read_u32(&body[0..], self.big_endian) as usize;
+        let data = &body[4..];
+
+        Ok(Frame {
+            timestamp: Duration::ZERO,
+            link_type: interface.link_type,
+            data: data[..original_len.min(data.len())].to_vec(),
+        })
+    }
+
+    fn interface_by_id(&self, id: u32) -> ProtoResult<&Interface> {
+        self.interfaces
+            .get(id as usize)
+            .ok_or_else(|| ProtoError::from(format!("unknown pcapng interface: {id}")))
+    }
+}
+
+fn read_u16(bytes: &[u8], big_endian: bool) -> u16 {
+    let bytes = [bytes[0], bytes[1]];
+    match big_endian {
+        true => u16::from_be_bytes(bytes),
+        false => u16::from_le_bytes(bytes),
+    }
+}
+
+fn read_u32(bytes: &[u8], big_endian: bool) -> u32 {
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    match big_endian {
+        true => u32::from_be_bytes(bytes),
+        false => u32::from_le_bytes(bytes),
+    }
+}
+
+/// Fills `buf`, returning false if the reader was already at its end
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> ProtoResult<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(true)
+}
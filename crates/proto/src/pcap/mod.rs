@@ -0,0 +1,400 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Extraction of DNS messages from packet captures
+//!
+//! Both the classic pcap and the pcapng file formats are supported, with Ethernet (including VLAN
+//! tags), Linux cooked, BSD loopback and raw IP link layers. DNS over UDP is read from single
+//! datagrams, DNS over TCP is reassembled from the segments of each stream. IP fragments are not
+//! reassembled and are skipped.
+//!
+//! ```no_run
+//! use hickory_proto::pcap::DnsCapture;
+//!
+//! for captured in DnsCapture::open("dns.pcap").unwrap() {
+//!     let captured = captured.unwrap();
+//!     println!(
+//!         "{:?} {} -> {}: {}",
+//!         captured.timestamp,
+//!         captured.five_tuple.src,
+//!         captured.five_tuple.dst,
+//!         captured.message.dig_display(),
+//!     );
+//! }
+//! ```
+
+mod file;
+mod packet;
+mod tcp;
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::time::Duration;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::net::SocketAddr;
+use std::path::Path;
+
+use crate::error::{ProtoError, ProtoResult};
+use crate::op::Message;
+use crate::serialize::binary::BinDecodable;
+use crate::xfer::Protocol;
+
+use file::CaptureReader;
+use packet::Transport;
+
+/// The default port filter, DNS and mDNS
+const DEFAULT_PORTS: [u16; 2] = [53, 5353];
+
+/// A DNS message read from a capture
+#[derive(Clone, Debug)]
+pub struct CapturedMessage {
+    /// Time of capture since the UNIX epoch
+    ///
+    /// For TCP this is the time of the segment which completed the message.
+    pub timestamp: Duration,
+    /// The connection the message was sent on
+    pub five_tuple: FiveTuple,
+    /// The parsed message
+    pub message: Message,
+}
+
+/// The protocol and endpoints of a captured message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FiveTuple {
+    /// Either [`Protocol::Udp`] or [`Protocol::Tcp`]
+    pub protocol: Protocol,
+    /// The sender of the message
+    pub src: SocketAddr,
+    /// The receiver of the message
+    pub dst: SocketAddr,
+}
+
+/// An iterator over the DNS messages in a pcap or pcapng capture
+///
+/// Traffic is selected by port, by default 53 and 5353. Payloads on those ports that do not parse
+///  as DNS messages are returned as errors, so that they can be counted or skipped. An error while
+///  reading the capture itself ends the iteration.
+pub struct DnsCapture<R> {
+    reader: CaptureReader<R>,
+    ports: Vec<u16>,
+    streams: tcp::Reassembler,
+    pending: VecDeque<ProtoResult<CapturedMessage>>,
+    done: bool,
+}
+
+impl DnsCapture<BufReader<File>> {
+    /// Opens the capture file at `path`
+    pub fn open(path: impl AsRef<Path>) -> ProtoResult<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> DnsCapture<R> {
+    /// Reads a capture, the format is detected from its header
+    pub fn new(reader: R) -> ProtoResult<Self> {
+        Ok(Self {
+            reader: CaptureReader::new(reader)?,
+            ports: DEFAULT_PORTS.to_vec(),
+            streams: tcp::Reassembler::default(),
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    /// Only extract messages sent from or to one of these ports
+    pub fn with_ports(mut self, ports: impl IntoIterator<Item = u16>) -> Self {
+        self.ports = ports.into_iter().collect();
+        self
+    }
+
+    /// Reads frames until one yields messages, or the capture ends
+    fn read_frame(&mut self) -> ProtoResult<bool> {
+        let Some(frame) = self.reader.next_frame()? else {
+            return Ok(false);
+        };
+
+        let Some(segment) = packet::decode(frame.link_type, &frame.data) else {
+            return Ok(true);
+        };
+        if !self.ports.contains(&segment.src.port()) && !self.ports.contains(&segment.dst.port()) {
+            return Ok(true);
+        }
+
+        let (protocol, messages) = match &segment.transport {
+            Transport::Udp(data) => (Protocol::Udp, vec![data.to_vec()]),
+            Transport::Tcp(tcp) => (
+                Protocol::Tcp,
+                self.streams.segment(segment.src, segment.dst, tcp),
+            ),
+        };
+
+        let five_tuple = FiveTuple {
+            protocol,
+            src: segment.src,
+            dst: segment.dst,
+        };
+        self.pending.extend(messages.iter().map(|data| {
+            Message::from_bytes(data).map(|message| CapturedMessage {
+                timestamp: frame.timestamp,
+                five_tuple,
+                message,
+            })
+        }));
+
+        Ok(true)
+    }
+}
+
+impl<R: Read> Iterator for DnsCapture<R> {
+    type Item = Result<CapturedMessage, ProtoError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(message) = self.pending.pop_front() {
+                return Some(message);
+            }
+            if self.done {
+                return None;
+            }
+
+            match self.read_frame() {
+                Ok(true) => continue,
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+    use crate::op::{MessageType, OpCode, Query};
+    use crate::rr::{Name, RecordType};
+    use crate::serialize::binary::BinEncodable;
+
+    fn query(id: u16) -> Vec<u8> {
+        let mut message = Message::new(id, MessageType::Query, OpCode::Query);
+        message.add_query(Query::query(
+            Name::from_str("example.com.").unwrap(),
+            RecordType::A,
+        ));
+        message.to_bytes().unwrap()
+    }
+
+    fn ipv4(src: [u8; 4], dst: [u8; 4], protocol: u8, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x45, 0, 0, 0, 0, 0, 0x40, 0, 64, protocol, 0, 0];
+        packet[2..4].copy_from_slice(&(20 + payload.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&src);
+        packet.extend_from_slice(&dst);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn udp(src: u16, dst: u16, payload: &[u8]) -> Vec<u8> {
+        let mut datagram = Vec::new();
+        datagram.extend_from_slice(&src.to_be_bytes());
+        datagram.extend_from_slice(&dst.to_be_bytes());
+        datagram.extend_from_slice(&(8 + payload.len() as u16).to_be_bytes());
+        datagram.extend_from_slice(&[0, 0]);
+        datagram.extend_from_slice(payload);
+        datagram
+    }
+
+    fn tcp(src: u16, dst: u16, seq: u32, flags: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = Vec::new();
+        segment.extend_from_slice(&src.to_be_bytes());
+        segment.extend_from_slice(&dst.to_be_bytes());
+        segment.extend_from_slice(&seq.to_be_bytes());
+        segment.extend_from_slice(&[0, 0, 0, 0, 0x50, flags, 0xff, 0xff, 0, 0, 0, 0]);
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    fn ethernet(packet: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&0x0800u16.to_be_bytes());
+        frame.extend_from_slice(packet);
+        frame
+    }
+
+    fn pcap(link_type: u32, frames: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut file = Vec::new();
+        for value in [0xa1b2_c3d4, 0x0004_0002, 0, 0, 65535, link_type] {
+            file.extend_from_slice(&u32::to_le_bytes(value));
+        }
+        for (seconds, frame) in frames {
+            for value in [*seconds, 500_000, frame.len() as u32, frame.len() as u32] {
+                file.extend_from_slice(&value.to_le_bytes());
+            }
+            file.extend_from_slice(frame);
+        }
+        file
+    }
+
+    #[test]
+    fn test_pcap_udp() {
+        let client = [192, 0, 2, 1];
+        let server = [192, 0, 2, 53];
+        let frames = [
+            (
+                1,
+                ethernet(&ipv4(client, server, 17, &udp(4242, 53, &query(1)))),
+            ),
+            // not dns, filtered by port
+            (
+                2,
+                ethernet(&ipv4(client, server, 17, &udp(4242, 123, &[1, 2, 3]))),
+            ),
+            (
+                3,
+                ethernet(&ipv4(server, client, 17, &udp(53, 4242, &query(2)))),
+            ),
+        ];
+
+        let messages = DnsCapture::new(pcap(1, &frames).as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].message.id(), 1);
+        assert_eq!(messages[0].timestamp, Duration::from_millis(1_500));
+        assert_eq!(
+            messages[0].five_tuple,
+            FiveTuple {
+                protocol: Protocol::Udp,
+                src: SocketAddr::new(IpAddr::V4(Ipv4Addr::from(client)), 4242),
+                dst: SocketAddr::new(IpAddr::V4(Ipv4Addr::from(server)), 53),
+            }
+        );
+        assert_eq!(messages[1].message.id(), 2);
+        assert_eq!(messages[1].five_tuple.src.port(), 53);
+    }
+
+    #[test]
+    fn test_pcap_tcp_reassembly() {
+        let client = [192, 0, 2, 1];
+        let server = [192, 0, 2, 53];
+
+        // two messages in one stream, split at arbitrary points and delivered out of order
+        let mut stream = Vec::new();
+        for id in [1, 2] {
+            let message = query(id);
+            stream.extend_from_slice(&(message.len() as u16).to_be_bytes());
+            stream.extend_from_slice(&message);
+        }
+        let (first, rest) = stream.split_at(5);
+        let (second, third) = rest.split_at(30);
+
+        let isn = u32::MAX - 2;
+        let seq = |offset: usize| isn.wrapping_add(1).wrapping_add(offset as u32);
+        let segments = [
+            tcp(4242, 53, isn, 0x02, &[]),
+            tcp(4242, 53, seq(0), 0x18, first),
+            tcp(4242, 53, seq(35), 0x18, third),
+            tcp(4242, 53, seq(5), 0x18, second),
+            // a retransmission is ignored
+            tcp(4242, 53, seq(5), 0x18, second),
+            tcp(4242, 53, seq(stream.len()), 0x11, &[]),
+        ];
+        let frames = segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| (i as u32, ipv4(client, server, 6, segment)))
+            .collect::<Vec<_>>();
+
+        let messages = DnsCapture::new(pcap(101, &frames).as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].message.id(), 1);
+        assert_eq!(messages[1].message.id(), 2);
+        assert_eq!(messages[0].five_tuple.protocol, Protocol::Tcp);
+        assert_eq!(messages[0].timestamp, Duration::from_millis(3_500));
+    }
+
+    #[test]
+    fn test_pcapng_ipv6() {
+        fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+            let len = 12 + body.len() as u32;
+            let mut block = Vec::new();
+            block.extend_from_slice(&block_type.to_be_bytes());
+            block.extend_from_slice(&len.to_be_bytes());
+            block.extend_from_slice(body);
+            block.extend_from_slice(&len.to_be_bytes());
+            block
+        }
+
+        let client = Ipv6Addr::from_str("2001:db8::1").unwrap();
+        let server = Ipv6Addr::from_str("2001:db8::53").unwrap();
+        let datagram = udp(4242, 53, &query(7));
+        let mut packet = vec![0x60, 0, 0, 0];
+        packet.extend_from_slice(&(datagram.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&[17, 64]);
+        packet.extend_from_slice(&client.octets());
+        packet.extend_from_slice(&server.octets());
+        packet.extend_from_slice(&datagram);
+        while packet.len() % 4 != 0 {
+            packet.push(0);
+        }
+
+        // big endian section, with a nanosecond resolution interface
+        let mut file = block(
+            0x0a0d_0d0a,
+            &[
+                0x1a, 0x2b, 0x3c, 0x4d, 0, 1, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            ],
+        );
+        file.extend(block(
+            1,
+            &[0, 101, 0, 0, 0, 0, 0, 0, 0, 9, 0, 1, 9, 0, 0, 0, 0, 0, 0, 0],
+        ));
+        file.extend(block(4, &[0, 0, 0, 0]));
+
+        let nanos = 1_700_000_000_123_456_789u64;
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u32.to_be_bytes());
+        body.extend_from_slice(&((nanos >> 32) as u32).to_be_bytes());
+        body.extend_from_slice(&(nanos as u32).to_be_bytes());
+        body.extend_from_slice(&(40 + datagram.len() as u32).to_be_bytes());
+        body.extend_from_slice(&(40 + datagram.len() as u32).to_be_bytes());
+        body.extend_from_slice(&packet);
+        file.extend(block(6, &body));
+
+        let messages = DnsCapture::new(file.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message.id(), 7);
+        assert_eq!(messages[0].timestamp, Duration::from_nanos(nanos));
+        assert_eq!(
+            messages[0].five_tuple.src,
+            SocketAddr::new(client.into(), 4242)
+        );
+        assert_eq!(
+            messages[0].five_tuple.dst,
+            SocketAddr::new(server.into(), 53)
+        );
+    }
+
+    #[test]
+    fn test_not_a_capture() {
+        assert!(DnsCapture::new(&b"not a capture file"[..]).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Decoding of the link, network and transport layers of captured frames
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_RAW_BSD: u32 = 12;
+const LINKTYPE_LOOP: u32 = 108;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88a8;
+
+const IP_PROTO_TCP: u8 = 6;
+const IP_PROTO_UDP: u8 = 17;
+
+const IPV6_HOP_BY_HOP: u8 = 0;
+const IPV6_ROUTING: u8 = 43;
+const IPV6_FRAGMENT: u8 = 44;
+const IPV6_DESTINATION_OPTIONS: u8 = 60;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+
+/// The transport layer of a decoded frame
+pub(super) struct Segment<'a> {
+    pub(super) src: SocketAddr,
+    pub(super) dst: SocketAddr,
+    pub(super) transport: Transport<'a>,
+}
+
+pub(super) enum Transport<'a> {
+    Udp(&'a [u8]),
+    Tcp(TcpSegment<'a>),
+}
+
+pub(super) struct TcpSegment<'a> {
+    pub(super) seq: u32,
+    pub(super) syn: bool,
+    /// The stream was closed, either with a FIN or a RST
+    pub(super) end: bool,
+    pub(super) payload: &'a [u8],
+}
+
+/// Decodes a frame down to its UDP or TCP segment
+///
+/// Returns `None` for anything else, e.g. ARP, ICMP, unsupported link types, truncated headers or
+/// IP fragments, which are not reassembled.
+pub(super) fn decode(link_type: u32, frame: &[u8]) -> Option<Segment<'_>> {
+    let (ether_type, packet) = match link_type {
+        LINKTYPE_ETHERNET => ethernet(frame)?,
+        LINKTYPE_NULL | LINKTYPE_LOOP => {
+            // the address family, in host byte order for NULL, network byte order for LOOP
+            let family = frame.get(..4)?;
+            let family = match family {
+                [0, 0, 0, f] | [f, 0, 0, 0] => *f,
+                _ => return None,
+            };
+            let ether_type = match family {
+                2 => ETHERTYPE_IPV4,
+                // the values used by the various BSDs and macOS
+                24 | 28 | 30 => ETHERTYPE_IPV6,
+                _ => return None,
+            };
+            (ether_type, &frame[4..])
+        }
+        LINKTYPE_RAW | LINKTYPE_RAW_BSD => (version(frame)?, frame),
+        LINKTYPE_IPV4 => (ETHERTYPE_IPV4, frame),
+        LINKTYPE_IPV6 => (ETHERTYPE_IPV6, frame),
+        LINKTYPE_LINUX_SLL => (read_u16(frame, 14)?, frame.get(16..)?),
+        LINKTYPE_LINUX_SLL2 => (read_u16(frame, 0)?, frame.get(20..)?),
+        _ => return None,
+    };
+
+    let (src, dst, protocol, payload) = match ether_type {
+        ETHERTYPE_IPV4 => ipv4(packet)?,
+        ETHERTYPE_IPV6 => ipv6(packet)?,
+        _ => return None,
+    };
+
+    match protocol {
+        IP_PROTO_UDP => {
+            let length = usize::from(read_u16(payload, 4)?);
+            let data = payload.get(8..length.min(payload.len()))?;
+            Some(Segment {
+                src: SocketAddr::new(src, read_u16(payload, 0)?),
+                dst: SocketAddr::new(dst, read_u16(payload, 2)?),
+                transport: Transport::Udp(data),
+            })
+        }
+        IP_PROTO_TCP => {
+            let offset = usize::from(*payload.get(12)? >> 4) * 4;
+            let flags = *payload.get(13)?;
+            Some(Segment {
+                src: SocketAddr::new(src, read_u16(payload, 0)?),
+                dst: SocketAddr::new(dst, read_u16(payload, 2)?),
+                transport: Transport::Tcp(TcpSegment {
+                    seq: read_u32(payload, 4)?,
+                    syn: flags & TCP_SYN != 0,
+                    end: flags & (TCP_FIN | TCP_RST) != 0,
+                    payload: payload.get(offset..)?,
+                }),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn ethernet(frame: &[u8]) -> Option<(u16, &[u8])> {
+    let mut ether_type = read_u16(frame, 12)?;
+    let mut rest = frame.get(14..)?;
+    while matches!(ether_type, ETHERTYPE_VLAN | ETHERTYPE_QINQ) {
+        ether_type = read_u16(rest, 2)?;
+        rest = rest.get(4..)?;
+    }
+
+    Some((ether_type, rest))
+}
+
+fn version(packet: &[u8]) -> Option<u16> {
+    match packet.first()? >> 4 {
+        4 => Some(ETHERTYPE_IPV4),
+        6 => Some(ETHERTYPE_IPV6),
+        _ => None,
+    }
+}
+
+fn ipv4(packet: &[u8]) -> Option<(IpAddr, IpAddr, u8, &[u8])> {
+    let header_len = usize::from(packet.first()? & 0x0f) * 4;
+    let total_len = usize::from(read_u16(packet, 2)?);
+
+    // more fragments, or a non-zero offset
+    if read_u16(packet, 6)? & 0x3fff != 0 {
+        return None;
+    }
+
+    let src = Ipv4Addr::from(read_u32(packet, 12)?);
+    let dst = Ipv4Addr::from(read_u32(packet, 16)?);
+    let payload = packet.get(header_len..total_len.min(packet.len()))?;
+
+    Some((src.into(), dst.into(), *packet.get(9)?, payload))
+}
+
+fn ipv6(packet: &[u8]) -> Option<(IpAddr, IpAddr, u8, &[u8])> {
+    let payload_len = usize::from(read_u16(packet, 4)?);
+    let mut next_header = *packet.get(6)?;
+    let src = Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(8..24)?).ok()?);
+    let dst = Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(24..40)?).ok()?);
+
+    let mut payload = packet.get(40..(40 + payload_len).min(packet.len()))?;
+    loop {
+        match next_header {
+            IPV6_HOP_BY_HOP | IPV6_ROUTING | IPV6_DESTINATION_OPTIONS => {
+                let len = (usize::from(*payload.get(1)?) + 1) * 8;
+                next_header = *payload.first()?;
+                payload = payload.get(len..)?;
+            }
+            IPV6_FRAGMENT => {
+                // only unfragmented packets that happen to carry the header are supported
+                if read_u16(payload, 2)? & 0xfff9 != 0 {
+                    return None;
+                }
+                next_header = *payload.first()?;
+                payload = payload.get(8..)?;
+            }
+            _ => break,
+        }
+    }
+
+    Some((src.into(), dst.into(), next_header, payload))
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
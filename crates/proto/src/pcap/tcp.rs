@@ -0,0 +1,125 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Reassembly of the length prefixed DNS messages sent over TCP
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use super::packet::TcpSegment;
+
+/// Segments beyond a gap are buffered up to this many bytes per stream before the stream is dropped
+const MAX_OUT_OF_ORDER: usize = 1024 * 1024;
+
+/// Tracks the TCP streams of a capture, each direction of a connection separately
+#[derive(Default)]
+pub(super) struct Reassembler {
+    streams: HashMap<(SocketAddr, SocketAddr), Stream>,
+}
+
+#[derive(Default)]
+struct Stream {
+    /// The sequence number of the next expected byte
+    next_seq: Option<u32>,
+    /// In order data that has not yet formed a complete message
+    buffer: Vec<u8>,
+    /// Segments received ahead of `next_seq`, by their sequence number
+    out_of_order: BTreeMap<u32, Vec<u8>>,
+    out_of_order_len: usize,
+}
+
+impl Reassembler {
+    /// Adds a segment to its stream, returning the DNS messages it completed
+    pub(super) fn segment(
+        &mut self,
+        src: SocketAddr,
+        dst: SocketAddr,
+        segment: &TcpSegment<'_>,
+    ) -> Vec<Vec<u8>> {
+        let key = (src, dst);
+        let stream = self.streams.entry(key).or_default();
+
+        if segment.syn {
+            *stream = Stream {
+                next_seq: Some(segment.seq.wrapping_add(1)),
+                ..Stream::default()
+            };
+        } else if !segment.payload.is_empty() {
+            // a capture started mid-stream, assume that this segment starts a message
+            let next_seq = *stream.next_seq.get_or_insert(segment.seq);
+            stream.insert(next_seq, segment.seq, segment.payload);
+        }
+
+        let messages = stream.messages();
+        if segment.end || stream.out_of_order_len > MAX_OUT_OF_ORDER {
+            self.streams.remove(&key);
+        }
+
+        messages
+    }
+}
+
+impl Stream {
+    fn insert(&mut self, next_seq: u32, seq: u32, payload: &[u8]) {
+        // the distance from the next expected byte, negative for retransmissions
+        let offset = seq.wrapping_sub(next_seq) as i32;
+        if offset > 0 {
+            self.out_of_order_len += payload.len();
+            self.out_of_order.insert(seq, payload.to_vec());
+            return;
+        }
+
+        let skip = offset.unsigned_abs() as usize;
+        if skip >= payload.len() {
+            return;
+        }
+        self.append(&payload[skip..]);
+
+        // drain any buffered segments that are now contiguous
+        while let Some(next_seq) = self.next_seq {
+            let Some((&seq, _)) = self
+                .out_of_order
+                .iter()
+                .find(|(seq, _)| seq.wrapping_sub(next_seq) as i32 <= 0)
+            else {
+                break;
+            };
+
+            let data = self.out_of_order.remove(&seq).unwrap_or_default();
+            self.out_of_order_len -= data.len();
+            let skip = next_seq.wrapping_sub(seq) as usize;
+            if skip < data.len() {
+                self.append(&data[skip..]);
+            }
+        }
+    }
+
+    fn append(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+        self.next_seq = self.next_seq.map(|seq| seq.wrapping_add(data.len() as u32));
+    }
+
+    /// Removes all complete messages from the buffer
+    fn messages(&mut self) -> Vec<Vec<u8>> {
+        let mut messages = Vec::new();
+        let mut start = 0;
+        while let Some(len) = self.buffer.get(start..start + 2) {
+            let len = usize::from(u16::from_be_bytes([len[0], len[1]]));
+            let Some(message) = self.buffer.get(start + 2..start + 2 + len) else {
+                break;
+            };
+
+            messages.push(message.to_vec());
+            start += 2 + len;
+        }
+
+        self.buffer.drain(..start);
+        messages
+    }
+}
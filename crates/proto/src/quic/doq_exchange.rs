@@ -0,0 +1,326 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use thiserror::Error;
+
+use super::DoqErrorCode;
+use crate::{
+    op::{Edns, Message},
+    rr::{RecordType, rdata::opt::EdnsCode},
+};
+
+/// The side of a DoQ connection an exchange is on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoqRole {
+    /// The client sends a single query on each stream, and receives the responses to it
+    Client,
+    /// The server receives a single query on each stream, and sends the responses to it
+    Server,
+}
+
+/// A violation of [RFC 9250](https://www.rfc-editor.org/rfc/rfc9250.html) by the peer
+///
+/// ```text
+/// 4.3.3.  Protocol Errors
+///
+///    Other error scenarios can occur due to malformed, incomplete, or
+///    unexpected messages during a DoQ session.
+///    [...]
+///    If a peer encounters such an error condition, it is considered a
+///    fatal error.  It SHOULD forcibly abort the connection using QUIC's
+///    CONNECTION_CLOSE mechanism and SHOULD use the DoQ error code
+///    DOQ_PROTOCOL_ERROR.
+/// ```
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DoqProtocolError {
+    /// A message was received with a non-zero message ID
+    #[error("message ID must be 0, got: {0}")]
+    MessageIdNot0(u16),
+    /// The server received more than one query on a stream
+    #[error("more than one query received on a stream")]
+    MultipleQueries,
+    /// The client received more responses on a stream than expected for its query
+    #[error("unexpected additional response received on a stream")]
+    UnexpectedResponse,
+    /// The stream finished before the end of the message announced by its length field
+    #[error("stream finished in the middle of a message")]
+    IncompleteMessage,
+    /// The stream finished before the server received a query
+    #[error("stream finished without a query")]
+    MissingQuery,
+    /// The stream finished before the client received a response
+    #[error("stream finished without a response")]
+    MissingResponse,
+    /// A message contained the edns-tcp-keepalive option, which is not used with DoQ
+    #[error("edns-tcp-keepalive option received")]
+    EdnsTcpKeepalive,
+}
+
+impl DoqProtocolError {
+    /// The error code to abort the connection with
+    pub fn error_code(&self) -> DoqErrorCode {
+        DoqErrorCode::ProtocolError
+    }
+}
+
+/// The [RFC 9250](https://www.rfc-editor.org/rfc/rfc9250.html) rules for the DNS messages
+/// exchanged on a single DoQ stream
+///
+/// ```text
+/// 4.2.  Stream Mapping and Usage
+///
+///    The mapping specified here requires that the client select a separate
+///    QUIC stream for each query.  The server then uses the same stream to
+///    provide all the response messages for that query.
+/// ```
+///
+/// The exchange does no I/O, it is told about the messages sent and received on the stream, and
+/// when the stream finished, and checks them for violations of the protocol.
+#[derive(Clone, Copy, Debug)]
+pub struct DoqExchange {
+    role: DoqRole,
+    received: usize,
+    multiple_responses: bool,
+}
+
+impl DoqExchange {
+    /// A new exchange on a stream, for the `role` of this side of the connection
+    pub fn new(role: DoqRole) -> Self {
+        Self {
+            role,
+            received: 0,
+            multiple_responses: false,
+        }
+    }
+
+    /// The side of the connection this exchange is on
+    pub fn role(&self) -> DoqRole {
+        self.role
+    }
+
+    /// The number of messages received on the stream
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Prepares a message to be sent on the stream
+    ///
+    /// ```text
+    /// 4.2.1.  DNS Message IDs
+    ///
+    ///    When sending queries over a QUIC connection, the DNS Message ID MUST
+    ///    be set to 0.
+    /// ```
+    ///
+    /// Zone transfer queries sent by a client may be answered with more than one response.
+    pub fn outgoing(&mut self, message: &mut Message) {
+        message.set_id(0);
+
+        if self.role == DoqRole::Client {
+            self.multiple_responses = message
+                .queries()
+                .iter()
+                .any(|q| matches!(q.query_type(), RecordType::AXFR | RecordType::IXFR));
+        }
+    }
+
+    /// Checks an encoded message received on the stream
+    ///
+    /// Malformed messages are left to be rejected when they are decoded, the EDNS options of the
+    /// decoded message are checked with [`Self::check_edns`].
+    pub fn incoming(&mut self, message: &[u8]) -> Result<(), DoqProtocolError> {
+        match self.role {
+            DoqRole::Server if self.received > 0 => return Err(DoqProtocolError::MultipleQueries),
+            DoqRole::Client if self.received > 0 && !self.multiple_responses => {
+                return Err(DoqProtocolError::UnexpectedResponse);
+            }
+            _ => {}
+        }
+
+        if let [high, low, ..] = *message {
+            let id = u16::from_be_bytes([high, low]);
+            if id != 0 {
+                return Err(DoqProtocolError::MessageIdNot0(id));
+            }
+        }
+
+        self.received += 1;
+        Ok(())
+    }
+
+    /// Checks the EDNS options of a message received on a stream, once it has been decoded
+    ///
+    /// ```text
+    /// 5.5.2.  Idle Timeouts
+    ///
+    ///    [...] DoQ implementations MUST NOT send the edns-tcp-keepalive EDNS(0) Option [RFC7828]
+    ///    in any messages sent on a DoQ connection [...] if a server receives a query that contains
+    ///    the edns-tcp-keepalive EDNS(0) Option, it MUST treat it as a protocol error [...]
+    /// ```
+    pub fn check_edns(edns: Option<&Edns>) -> Result<(), DoqProtocolError> {
+        match edns.is_some_and(|edns| edns.option(EdnsCode::Keepalive).is_some()) {
+            true => Err(DoqProtocolError::EdnsTcpKeepalive),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks that the stream may finish, `partial` is true if it finished in the middle of a
+    /// message
+    pub fn finished(&self, partial: bool) -> Result<(), DoqProtocolError> {
+        if partial {
+            return Err(DoqProtocolError::IncompleteMessage);
+        }
+
+        match (self.role, self.received) {
+            (DoqRole::Server, 0) => Err(DoqProtocolError::MissingQuery),
+            (DoqRole::Client, 0) => Err(DoqProtocolError::MissingResponse),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks data received on the stream after all the expected messages
+    pub fn unexpected_data(&self) -> DoqProtocolError {
+        match self.role {
+            DoqRole::Server => DoqProtocolError::MultipleQueries,
+            DoqRole::Client => DoqProtocolError::UnexpectedResponse,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::op::{Edns, OpCode, Query};
+    use crate::rr::Name;
+    use crate::rr::rdata::opt::EdnsOption;
+
+    fn query(id: u16, query_type: RecordType) -> Message {
+        let mut message = Message::query();
+        message.set_id(id).add_query(Query::query(
+            Name::from_ascii("example.com.").unwrap(),
+            query_type,
+        ));
+        message
+    }
+
+    fn encode(message: &Message) -> Vec<u8> {
+        message.to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_outgoing_message_id() {
+        let mut exchange = DoqExchange::new(DoqRole::Client);
+        let mut message = query(1234, RecordType::A);
+        exchange.outgoing(&mut message);
+        assert_eq!(message.id(), 0);
+    }
+
+    #[test]
+    fn test_incoming_message_id() {
+        let mut exchange = DoqExchange::new(DoqRole::Server);
+        assert_eq!(
+            exchange.incoming(&encode(&query(1234, RecordType::A))),
+            Err(DoqProtocolError::MessageIdNot0(1234))
+        );
+        assert_eq!(exchange.received(), 0);
+
+        assert_eq!(exchange.incoming(&encode(&query(0, RecordType::A))), Ok(()));
+        assert_eq!(exchange.received(), 1);
+    }
+
+    #[test]
+    fn test_one_query_per_stream() {
+        let mut exchange = DoqExchange::new(DoqRole::Server);
+        let query = encode(&query(0, RecordType::A));
+        assert_eq!(exchange.incoming(&query), Ok(()));
+        assert_eq!(
+            exchange.incoming(&query),
+            Err(DoqProtocolError::MultipleQueries)
+        );
+        assert_eq!(
+            exchange.unexpected_data(),
+            DoqProtocolError::MultipleQueries
+        );
+    }
+
+    #[test]
+    fn test_one_response_per_query() {
+        let mut exchange = DoqExchange::new(DoqRole::Client);
+        exchange.outgoing(&mut query(0, RecordType::A));
+
+        let response = encode(&Message::response(0, OpCode::Query));
+        assert_eq!(exchange.incoming(&response), Ok(()));
+        assert_eq!(
+            exchange.incoming(&response),
+            Err(DoqProtocolError::UnexpectedResponse)
+        );
+    }
+
+    #[test]
+    fn test_zone_transfer_responses() {
+        let mut exchange = DoqExchange::new(DoqRole::Client);
+        exchange.outgoing(&mut query(0, RecordType::AXFR));
+
+        let response = encode(&Message::response(0, OpCode::Query));
+        for _ in 0..3 {
+            assert_eq!(exchange.incoming(&response), Ok(()));
+        }
+        assert_eq!(exchange.received(), 3);
+        assert_eq!(exchange.finished(false), Ok(()));
+    }
+
+    #[test]
+    fn test_edns_tcp_keepalive() {
+        let mut message = query(0, RecordType::A);
+        let mut edns = Edns::new();
+        edns.options_mut()
+            .insert(EdnsOption::Unknown(EdnsCode::Keepalive.into(), Vec::new()));
+        message.set_edns(edns);
+
+        // the option is only seen once the message is decoded
+        let mut exchange = DoqExchange::new(DoqRole::Server);
+        assert_eq!(exchange.incoming(&encode(&message)), Ok(()));
+        assert_eq!(
+            DoqExchange::check_edns(message.extensions().as_ref()),
+            Err(DoqProtocolError::EdnsTcpKeepalive)
+        );
+        assert_eq!(DoqExchange::check_edns(Some(&Edns::new())), Ok(()));
+        assert_eq!(DoqExchange::check_edns(None), Ok(()));
+    }
+
+    #[test]
+    fn test_malformed_message_is_left_to_decoding() {
+        let mut exchange = DoqExchange::new(DoqRole::Server);
+        assert_eq!(exchange.incoming(&[0, 0, 0xff]), Ok(()));
+    }
+
+    #[test]
+    fn test_finished() {
+        let exchange = DoqExchange::new(DoqRole::Server);
+        assert_eq!(
+            exchange.finished(false),
+            Err(DoqProtocolError::MissingQuery)
+        );
+        assert_eq!(
+            exchange.finished(true),
+            Err(DoqProtocolError::IncompleteMessage)
+        );
+
+        let mut exchange = DoqExchange::new(DoqRole::Client);
+        assert_eq!(
+            exchange.finished(false),
+            Err(DoqProtocolError::MissingResponse)
+        );
+        exchange
+            .incoming(&encode(&Message::response(0, OpCode::Query)))
+            .unwrap();
+        assert_eq!(exchange.finished(false), Ok(()));
+    }
+}
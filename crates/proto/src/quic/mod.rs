@@ -7,11 +7,13 @@
 
 //! QUIC protocol related components for DNS over QUIC (DoQ)
 
+mod doq_exchange;
 mod quic_client_stream;
 mod quic_config;
 mod quic_server;
 mod quic_stream;
 
+pub use self::doq_exchange::{DoqExchange, DoqProtocolError, DoqRole};
 #[cfg(feature = "__h3")]
 pub(crate) use self::quic_client_stream::connect_quic;
 pub use self::quic_client_stream::{
@@ -21,14 +21,14 @@ use quinn::{
 use tokio::time::timeout;
 
 use crate::{
-    error::ProtoError,
+    error::{ProtoError, ProtoErrorKind},
     quic::quic_stream::{DoqErrorCode, QuicStream},
     rustls::client_config,
     udp::UdpSocket,
     xfer::{CONNECT_TIMEOUT, DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream},
 };
 
-use super::{quic_config, quic_stream};
+use super::{DoqRole, quic_config, quic_stream};
 
 /// A DNS client connection for DNS-over-QUIC
 #[must_use = "futures do nothing unless polled"]
@@ -60,7 +60,7 @@ impl QuicClientStream {
 
         // RFC: The mapping specified here requires that the client selects a separate
         //  QUIC stream for each query. The server then uses the same stream to provide all the response messages for that query.
-        let mut stream = QuicStream::new(send_stream, recv_stream);
+        let mut stream = QuicStream::new(send_stream, recv_stream, DoqRole::Client);
 
         stream.send(message.into_parts().0).await?;
 
@@ -68,7 +68,15 @@ impl QuicClientStream {
         // and MUST indicate through the STREAM FIN mechanism that no further data will be sent on that stream.
         stream.finish().await?;
 
-        stream.receive().await
+        let result = stream.receive().await;
+        if let Err(error) = &result {
+            // a protocol error is fatal to the whole connection
+            if let ProtoErrorKind::DoqProtocol(error) = error.kind() {
+                connection.close(error.error_code().into(), b"Protocol error");
+            }
+        }
+
+        result
     }
 }
 
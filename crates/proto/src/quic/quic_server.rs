@@ -17,7 +17,7 @@ use rustls::version::TLS13;
 use crate::{error::ProtoError, rustls::default_provider, udp::UdpSocket};
 
 use super::{
-    quic_config,
+    DoqErrorCode, DoqRole, quic_config,
     quic_stream::{self, QuicStream},
 };
 
@@ -101,10 +101,17 @@ impl QuicStreams {
     /// Get the next bi directional stream from the client
     pub async fn next(&mut self) -> Option<Result<QuicStream, ProtoError>> {
         match self.connection.accept_bi().await {
-            Ok((send_stream, receive_stream)) => {
-                Some(Ok(QuicStream::new(send_stream, receive_stream)))
-            }
+            Ok((send_stream, receive_stream)) => Some(Ok(QuicStream::new(
+                send_stream,
+                receive_stream,
+                DoqRole::Server,
+            ))),
             Err(e) => Some(Err(e.into())),
         }
     }
+
+    /// Close the connection with the client, e.g. after it violated the DoQ protocol
+    pub fn close(&self, code: DoqErrorCode, reason: &[u8]) {
+        self.connection.close(code.into(), reason);
+    }
 }
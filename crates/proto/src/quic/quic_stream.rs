@@ -5,10 +5,13 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::io;
+
 use bytes::{Bytes, BytesMut};
-use quinn::{RecvStream, SendStream, VarInt};
+use quinn::{ReadExactError, RecvStream, SendStream, VarInt};
 use tracing::debug;
 
+use super::{DoqExchange, DoqProtocolError, DoqRole};
 use crate::{
     error::{ProtoError, ProtoErrorKind},
     op::Message,
@@ -121,13 +124,15 @@ impl From<VarInt> for DoqErrorCode {
 pub struct QuicStream {
     send_stream: SendStream,
     receive_stream: RecvStream,
+    exchange: DoqExchange,
 }
 
 impl QuicStream {
-    pub(crate) fn new(send_stream: SendStream, receive_stream: RecvStream) -> Self {
+    pub(crate) fn new(send_stream: SendStream, receive_stream: RecvStream, role: DoqRole) -> Self {
         Self {
             send_stream,
             receive_stream,
+            exchange: DoqExchange::new(role),
         }
     }
 
@@ -136,8 +141,7 @@ impl QuicStream {
         // RFC: When sending queries over a QUIC connection, the DNS Message ID MUST be set to 0.
         // The stream mapping for DoQ allows for unambiguous correlation of queries and responses,
         // so the Message ID field is not required.
-
-        message.set_id(0);
+        self.exchange.outgoing(&mut message);
 
         let bytes = Bytes::from(message.to_vec()?);
 
@@ -172,25 +176,32 @@ impl QuicStream {
     /// Receive a single packet
     pub async fn receive(&mut self) -> Result<DnsResponse, ProtoError> {
         let bytes = self.receive_bytes().await?;
-        let message = Message::from_vec(&bytes)?;
-
-        // assert that the message id is 0, this is a bad dns-over-quic packet if not
-        if message.id() != 0 {
-            self.reset(DoqErrorCode::ProtocolError)
-                .map_err(|_| debug!("stream already closed"))
-                .ok();
-            return Err(ProtoErrorKind::QuicMessageIdNot0(message.id()).into());
+        let response = DnsResponse::from_buffer(bytes.to_vec())?;
+        if let Err(error) = DoqExchange::check_edns(response.extensions().as_ref()) {
+            return Err(self.violation(error));
         }
 
-        DnsResponse::from_buffer(bytes.to_vec())
+        Ok(response)
     }
 
     // TODO: we should change the protocol handlers to work with Messages since some require things like 0 for the Message ID.
     /// Receive a single packet as raw bytes
+    ///
+    /// Messages which violate the DoQ stream rules, e.g. a second query on the stream, reset the
+    /// stream and return a [`ProtoErrorKind::DoqProtocol`] error.
     pub async fn receive_bytes(&mut self) -> Result<BytesMut, ProtoError> {
         // following above, the data should be first the length, followed by the message(s)
         let mut len = [0u8; 2];
-        self.receive_stream.read_exact(&mut len).await?;
+        match self.receive_stream.read_exact(&mut len).await {
+            Ok(()) => {}
+            Err(ReadExactError::FinishedEarly(read)) => {
+                if let Err(error) = self.exchange.finished(read > 0) {
+                    return Err(self.violation(error));
+                }
+                return Err(ReadExactError::FinishedEarly(read).into());
+            }
+            Err(e) => return Err(e.into()),
+        }
         let len = u16::from_be_bytes(len) as usize;
 
         // RFC: DoQ queries and responses are sent on QUIC streams, which in theory can carry up to
@@ -203,16 +214,55 @@ impl QuicStream {
         if let Err(e) = self.receive_stream.read_exact(&mut bytes[..len]).await {
             debug!("received bad packet len: {} bytes: {:?}", len, bytes);
 
+            if let ReadExactError::FinishedEarly(_) = e {
+                return Err(self.violation(DoqProtocolError::IncompleteMessage));
+            }
+
             self.reset(DoqErrorCode::ProtocolError)
                 .map_err(|_| debug!("stream already closed"))
                 .ok();
             return Err(e.into());
         }
 
+        if let Err(error) = self.exchange.incoming(&bytes) {
+            return Err(self.violation(error));
+        }
+
         debug!("received packet len: {} bytes: {:x?}", len, bytes);
         Ok(bytes)
     }
 
+    /// Waits for the peer to finish its side of the stream
+    ///
+    /// A server must receive exactly one query on a stream, and a client the responses to its
+    /// query, any further data is a protocol violation.
+    pub async fn finished(&mut self) -> Result<(), ProtoError> {
+        let mut buf = [0u8; 1];
+        let error = match self.receive_stream.read(&mut buf).await {
+            Ok(None) => match self.exchange.finished(false) {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            },
+            Ok(Some(_)) => self.exchange.unexpected_data(),
+            Err(e) => return Err(io::Error::from(e).into()),
+        };
+
+        Err(self.violation(error))
+    }
+
+    /// The DoQ rules for the messages on this stream
+    pub fn exchange(&self) -> &DoqExchange {
+        &self.exchange
+    }
+
+    fn violation(&mut self, error: DoqProtocolError) -> ProtoError {
+        debug!("DoQ protocol violation: {error}");
+        self.reset(error.error_code())
+            .map_err(|_| debug!("stream already closed"))
+            .ok();
+        error.into()
+    }
+
     /// Reset the sending stream due to some error
     pub fn reset(&mut self, code: DoqErrorCode) -> Result<(), ProtoError> {
         self.send_stream
@@ -29,7 +29,8 @@ use crate::error::{ProtoError, ProtoErrorKind, ProtoResult};
 use crate::rr::domain::label::{CaseInsensitive, CaseSensitive, IntoLabel, Label, LabelCmp};
 use crate::rr::domain::usage::LOCALHOST as LOCALHOST_usage;
 use crate::serialize::binary::{
-    BinDecodable, BinDecoder, BinEncodable, BinEncoder, DecodeError, NameEncoding, Restrict,
+    BinDecodable, BinDecoder, BinEncodable, BinEncoder, DecodeError, DecompressionLimit,
+    NameEncoding, Restrict,
 };
 
 /// A domain name
@@ -943,6 +944,116 @@ impl Name {
             is_fqdn: self.is_fqdn,
         }
     }
+
+    /// Returns an iterator over the labels, converted to UTF-8 from IDNA as necessary
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use hickory_proto::rr::Name;
+    ///
+    /// let name = Name::from_utf8("www.ñ.example.").unwrap();
+    /// assert_eq!(name.iter().nth(1), Some(&b"xn--ida"[..]));
+    /// assert_eq!(name.iter_utf8().collect::<Vec<_>>(), ["www", "ñ", "example"]);
+    /// ```
+    pub fn iter_utf8(&self) -> impl DoubleEndedIterator<Item = String> + '_ {
+        self.iter().map(|label| {
+            // the labels of a name are never empty nor longer than 63 bytes
+            Label::from_raw_bytes(label)
+                .expect("name contains an invalid label")
+                .to_utf8()
+        })
+    }
+
+    /// Returns the labels of this name that precede `zone`, as a relative name, or `None` if
+    /// `zone` is not a zone of this name
+    ///
+    /// The comparison is case insensitive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use hickory_proto::rr::Name;
+    ///
+    /// let name = Name::from_str("www.example.com.").unwrap();
+    /// let zone = Name::from_str("Example.COM.").unwrap();
+    ///
+    /// let relative = name.strip_suffix(&zone).unwrap();
+    /// assert_eq!(relative, Name::from_str("www").unwrap());
+    /// assert!(!relative.is_fqdn());
+    ///
+    /// assert_eq!(name.strip_suffix(&name).unwrap().num_labels(), 0);
+    /// assert_eq!(name.strip_suffix(&Name::from_str("example.net.").unwrap()), None);
+    /// ```
+    pub fn strip_suffix(&self, zone: &Self) -> Option<Self> {
+        if !zone.zone_of(self) {
+            return None;
+        }
+
+        let num_labels = self.label_ends.len() - zone.label_ends.len();
+        let mut name = Self::from_labels(self.iter().take(num_labels)).ok()?;
+        name.set_fqdn(false);
+        Some(name)
+    }
+
+    /// Returns the longest name that is a zone of both this name and `other`
+    ///
+    /// The comparison is case insensitive; the labels of the result are taken from this name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use hickory_proto::rr::Name;
+    ///
+    /// let www = Name::from_str("www.example.com.").unwrap();
+    /// let mail = Name::from_str("mail.EXAMPLE.com.").unwrap();
+    /// assert_eq!(www.common_ancestor(&mail), Name::from_str("example.com.").unwrap());
+    ///
+    /// let net = Name::from_str("example.net.").unwrap();
+    /// assert!(www.common_ancestor(&net).is_root());
+    /// ```
+    pub fn common_ancestor(&self, other: &Self) -> Self {
+        let num_labels = self
+            .iter()
+            .rev()
+            .zip(other.iter().rev())
+            .take_while(|(label, other)| label.eq_ignore_ascii_case(other))
+            .count();
+
+        self.trim_to(num_labels)
+    }
+
+    /// Returns true if this is a wildcard name which `name` is an expansion of
+    ///
+    /// A wildcard matches any name, at any depth, below the parent of its `*` label
+    /// ([RFC 4592, section 2.1.1](https://www.rfc-editor.org/rfc/rfc4592#section-2.1.1)), this
+    /// does not account for names that exist in the zone and would prevent the expansion. The
+    /// comparison is case insensitive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use hickory_proto::rr::Name;
+    ///
+    /// let wildcard = Name::from_str("*.example.com.").unwrap();
+    /// assert!(wildcard.is_wildcard_match(&Name::from_str("www.example.com.").unwrap()));
+    /// assert!(wildcard.is_wildcard_match(&Name::from_str("a.b.Example.com.").unwrap()));
+    /// assert!(!wildcard.is_wildcard_match(&Name::from_str("example.com.").unwrap()));
+    /// assert!(!wildcard.is_wildcard_match(&Name::from_str("www.example.net.").unwrap()));
+    ///
+    /// // not a wildcard
+    /// let name = Name::from_str("www.example.com.").unwrap();
+    /// assert!(!name.is_wildcard_match(&name));
+    /// ```
+    pub fn is_wildcard_match(&self, name: &Self) -> bool {
+        self.is_wildcard()
+            && self.label_ends.len() <= name.label_ends.len()
+            && self.base_name().zone_of(name)
+    }
 }
 
 impl core::fmt::Debug for Name {
@@ -1134,12 +1245,14 @@ impl Hash for Name {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.is_fqdn.hash(state);
 
-        // this needs to be CaseInsensitive like PartialEq
-        for l in self
-            .iter()
-            .map(|l| Label::from_raw_bytes(l).unwrap().to_lowercase())
-        {
-            l.hash(state);
+        // this needs to be CaseInsensitive like PartialEq, lowercase into a stack buffer to not
+        //  allocate a Label for each label
+        let mut buf = [0u8; 63];
+        for label in self.iter() {
+            let lower = &mut buf[..label.len()];
+            lower.copy_from_slice(label);
+            lower.make_ascii_lowercase();
+            lower.hash(state);
         }
     }
 }
@@ -1226,7 +1339,7 @@ impl<'r> BinDecodable<'r> for Name {
     /// This will consume the portions of the `Vec` which it is reading...
     fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
         let mut name = Self::default();
-        read_inner(decoder, &mut name, None)?;
+        read_inner(decoder, &mut name, None, 0)?;
         Ok(name)
     }
 }
@@ -1235,6 +1348,7 @@ fn read_inner(
     decoder: &mut BinDecoder<'_>,
     name: &mut Name,
     max_idx: Option<usize>,
+    jumps: u8,
 ) -> Result<(), DecodeError> {
     let mut state: LabelParseState = LabelParseState::LabelLengthOrPointer;
     let name_start = decoder.index();
@@ -1294,6 +1408,11 @@ fn read_inner(
 
                 name.extend_name(label)
                     .map_err(|_| DecodeError::DomainNameTooLong(label.len()))?;
+                if name.encoded_len() > usize::from(decoder.limits().max_name_length) {
+                    return Err(DecodeError::DecompressionLimit(
+                        DecompressionLimit::NameLength,
+                    ));
+                }
 
                 // reset to collect more data
                 LabelParseState::LabelLengthOrPointer
@@ -1320,6 +1439,12 @@ fn read_inner(
             // domain header).  A zero offset specifies the first byte of the ID field,
             // etc.
             LabelParseState::Pointer => {
+                if jumps >= decoder.limits().max_pointer_jumps {
+                    return Err(DecodeError::DecompressionLimit(
+                        DecompressionLimit::PointerJumps,
+                    ));
+                }
+
                 let pointer_location = decoder.index();
                 let location = decoder
                     .read_u16()?
@@ -1337,7 +1462,11 @@ fn read_inner(
                     })?;
 
                 let mut pointer = decoder.clone(location);
-                read_inner(&mut pointer, name, Some(name_start))?;
+                read_inner(&mut pointer, name, Some(name_start), jumps + 1)?;
+
+                // everything read through the pointer counts towards the budget of the message
+                decoder.join(&pointer);
+                decoder.expand(pointer.index() - usize::from(location))?;
 
                 // Pointers always finish the name, break like Root.
                 break;
@@ -1692,6 +1821,68 @@ mod tests {
         assert!(Name::read(&mut d).is_err());
     }
 
+    fn decompression_limit(result: ProtoResult<Name>) -> Option<DecompressionLimit> {
+        match result.unwrap_err().kind() {
+            ProtoErrorKind::Decode(DecodeError::DecompressionLimit(limit)) => Some(*limit),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_decompression_limits() {
+        use crate::serialize::binary::{DecodeMode, DecompressionLimits};
+
+        // a chain of three pointers, each pointing at the previous one
+        let bytes = [1, b'a', 0, 0xC0, 0x00, 0xC0, 0x03, 0xC0, 0x05];
+        let mut d = BinDecoder::new(&bytes);
+        d.read_slice(7).unwrap();
+        assert_eq!(Name::read(&mut d).unwrap(), Name::from_str("a.").unwrap());
+
+        let limits = DecompressionLimits {
+            max_pointer_jumps: 2,
+            ..DecompressionLimits::default()
+        };
+        let mut d = BinDecoder::with_limits(&bytes, DecodeMode::Normal, limits);
+        d.read_slice(7).unwrap();
+        assert_eq!(
+            decompression_limit(Name::read(&mut d)),
+            Some(DecompressionLimit::PointerJumps)
+        );
+
+        let limits = DecompressionLimits {
+            max_name_length: 3,
+            ..DecompressionLimits::default()
+        };
+        let mut d =
+            BinDecoder::with_limits(&[1, b'a', 0, 2, b'a', b'b', 0], DecodeMode::Normal, limits);
+        assert_eq!(Name::read(&mut d).unwrap(), Name::from_str("a.").unwrap());
+        assert_eq!(
+            decompression_limit(Name::read(&mut d)),
+            Some(DecompressionLimit::NameLength)
+        );
+
+        // the same long name over and over again
+        let mut bytes = vec![63];
+        bytes.extend(iter::repeat(b'a').take(63));
+        bytes.push(0);
+        for _ in 0..3 {
+            bytes.extend_from_slice(&[0xC0, 0x00]);
+        }
+
+        let limits = DecompressionLimits {
+            max_expansion: 130,
+            ..DecompressionLimits::default()
+        };
+        let mut d = BinDecoder::with_limits(&bytes, DecodeMode::Normal, limits);
+        for _ in 0..3 {
+            Name::read(&mut d).unwrap();
+        }
+        assert_eq!(
+            decompression_limit(Name::read(&mut d)),
+            Some(DecompressionLimit::Expansion)
+        );
+    }
+
     #[test]
     fn test_base_name() {
         let zone = Name::from_str("example.com.").unwrap();
@@ -1715,6 +1906,65 @@ mod tests {
         assert!(!zone.zone_of(&root));
     }
 
+    #[test]
+    fn test_iter_utf8() {
+        let name = Name::from_utf8("_443._tcp.ñ.example.").unwrap();
+        assert_eq!(
+            name.iter_utf8().collect::<Vec<_>>(),
+            ["_443", "_tcp", "ñ", "example"]
+        );
+        assert_eq!(name.iter_utf8().next_back().as_deref(), Some("example"));
+        assert_eq!(Name::root().iter_utf8().count(), 0);
+    }
+
+    #[test]
+    fn test_strip_suffix() {
+        let name = Name::from_str("a.b.example.com.").unwrap();
+
+        assert_eq!(
+            name.strip_suffix(&Name::from_str("example.com.").unwrap()),
+            Some(Name::from_str("a.b").unwrap())
+        );
+        assert_eq!(
+            name.strip_suffix(&Name::root()),
+            Some(Name::from_str("a.b.example.com").unwrap())
+        );
+        assert_eq!(
+            name.strip_suffix(&Name::from_str("b.example.net.").unwrap()),
+            None
+        );
+        assert_eq!(Name::root().strip_suffix(&name), None);
+    }
+
+    #[test]
+    fn test_common_ancestor() {
+        let name = Name::from_str("a.b.example.com.").unwrap();
+        let other = Name::from_str("c.B.example.com.").unwrap();
+
+        assert_eq!(
+            name.common_ancestor(&other),
+            Name::from_str("b.example.com.").unwrap()
+        );
+        assert_eq!(
+            name.common_ancestor(&Name::from_str("example.com.").unwrap()),
+            Name::from_str("example.com.").unwrap()
+        );
+        assert_eq!(name.common_ancestor(&name), name);
+        assert!(name.common_ancestor(&Name::root()).is_root());
+    }
+
+    #[test]
+    fn test_is_wildcard_match() {
+        let wildcard = Name::from_str("*.example.com.").unwrap();
+
+        assert!(wildcard.is_wildcard_match(&Name::from_str("www.example.com.").unwrap()));
+        assert!(wildcard.is_wildcard_match(&wildcard));
+        assert!(wildcard.is_wildcard_match(&Name::from_str("a.b.example.com.").unwrap()));
+        assert!(!wildcard.is_wildcard_match(&Name::from_str("example.com.").unwrap()));
+        assert!(!wildcard.is_wildcard_match(&Name::from_str("www.example.org.").unwrap()));
+        assert!(Name::from_str("*.").unwrap().is_wildcard_match(&wildcard));
+    }
+
     #[test]
     fn test_zone_of_case() {
         let zone = Name::from_ascii("examplE.cOm").unwrap();
@@ -2553,6 +2803,18 @@ mod tests {
         let hash_without_dot = hasher.finish();
         assert_ne!(with_dot, without_dot);
         assert_ne!(hash_with_dot, hash_without_dot);
+
+        // hashing is case insensitive like PartialEq
+        let mut hasher = DefaultHasher::new();
+        let upper = Name::from_utf8("WWW.Example.COM.").unwrap();
+        upper.hash(&mut hasher);
+        let hash_upper = hasher.finish();
+
+        let mut hasher = DefaultHasher::new();
+        let lower = Name::from_utf8("www.example.com.").unwrap();
+        lower.hash(&mut hasher);
+        assert_eq!(upper, lower);
+        assert_eq!(hash_upper, hasher.finish());
     }
 
     #[test]
@@ -327,3 +327,25 @@ fn test_name_lowername_roundtrip() {
     assert_eq!(relative_name, relative_rt_name);
     assert!(fqdn_rt_name != relative_rt_name);
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn test_hash() {
+    use std::collections::HashMap;
+
+    let upper = LowerName::from(Name::from_ascii("WWW.Example.COM.").unwrap());
+    let lower = LowerName::from(Name::from_ascii("www.example.com.").unwrap());
+    let other = LowerName::from(Name::from_ascii("www.example.net.").unwrap());
+
+    let mut map = HashMap::new();
+    map.insert(upper, 1);
+    assert_eq!(map.get(&lower), Some(&1));
+    assert_eq!(map.get(&other), None);
+
+    // changing the name in place changes the key it is found by
+    let mut changed = lower.clone();
+    *changed = Name::from_ascii("www.example.net.").unwrap();
+    assert_eq!(map.get(&changed), None);
+    map.insert(changed, 2);
+    assert_eq!(map.get(&other), Some(&2));
+}
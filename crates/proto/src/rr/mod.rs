@@ -19,6 +19,7 @@ pub mod resource;
 mod rr_key;
 mod rr_set;
 pub mod serial_number;
+pub mod zone_check;
 
 use core::fmt::{Debug, Display};
 
@@ -40,6 +41,7 @@ pub use self::rr_set::RrsetRecords;
 pub use lower_name::LowerName;
 pub use rr_key::RrKey;
 pub use serial_number::SerialNumber;
+pub use zone_check::{Severity, ZoneIssue, check_zone};
 
 /// RecordData that is stored in a DNS Record.
 ///
@@ -193,6 +193,116 @@ impl CAA {
     }
 }
 
+/// The decision on a certificate request for a domain, derived from its relevant CAA RRset
+///
+/// [RFC 8659, section 4](https://www.rfc-editor.org/rfc/rfc8659#section-4)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CaaAuthorization<'a> {
+    /// None of the records restricts the issuance, any issuer may issue the certificate
+    Unrestricted,
+    /// The record authorizes the issuer, whose parameters are left to be checked by the issuer
+    Authorized(&'a CAA),
+    /// The issuer must not issue the certificate
+    Denied,
+}
+
+impl CaaAuthorization<'_> {
+    /// Returns true if the issuer may issue the certificate
+    pub fn is_permitted(&self) -> bool {
+        !matches!(self, Self::Denied)
+    }
+}
+
+/// Finds the relevant CAA RRset of `domain`, walking from the domain towards the root
+///
+/// `lookup` is called with each candidate name and returns its CAA records. It must return an
+/// empty list if the name does not exist or has no CAA records, and an error if the lookup failed
+/// otherwise, which must prevent the issuance. The first non-empty RRset is the relevant one, it
+/// is returned with the name it was found at. `Ok(None)` means there is no relevant RRset and
+/// therefore no restriction.
+///
+/// The search for a wildcard domain, like `*.example.com.`, starts at the base domain.
+///
+/// [RFC 8659, section 3](https://www.rfc-editor.org/rfc/rfc8659#section-3)
+pub async fn find_relevant_rrset<F, Fut, E>(
+    domain: &Name,
+    mut lookup: F,
+) -> Result<Option<(Name, Vec<CAA>)>, E>
+where
+    F: FnMut(Name) -> Fut,
+    Fut: core::future::Future<Output = Result<Vec<CAA>, E>>,
+{
+    let mut name = match domain.is_wildcard() {
+        true => domain.base_name(),
+        false => domain.clone(),
+    };
+
+    // the root itself is never queried
+    while !name.is_root() {
+        let rrset = lookup(name.clone()).await?;
+        if !rrset.is_empty() {
+            return Ok(Some((name, rrset)));
+        }
+        name = name.base_name();
+    }
+
+    Ok(None)
+}
+
+/// Evaluates the relevant CAA RRset of a domain for a request to `issuer`
+///
+/// `issuer` is the issuer domain name of the CA, e.g. `letsencrypt.org`. For a wildcard domain
+/// the `issuewild` properties apply if there are any, otherwise the `issue` properties. Records
+/// with an unknown property tag and the issuer critical flag deny the issuance, RFC 8659, section
+/// 4.1. Records with a malformed value authorize no issuer.
+///
+/// [RFC 8659, section 4](https://www.rfc-editor.org/rfc/rfc8659#section-4)
+pub fn authorize<'a>(rrset: &'a [CAA], issuer: &Name, wildcard: bool) -> CaaAuthorization<'a> {
+    let is_tag = |caa: &CAA, tag: &str| caa.tag().eq_ignore_ascii_case(tag);
+    if rrset.iter().any(|caa| {
+        caa.issuer_critical()
+            && !["issue", "issuewild", "iodef"]
+                .iter()
+                .any(|tag| is_tag(caa, tag))
+    }) {
+        return CaaAuthorization::Denied;
+    }
+
+    let issuewild = rrset
+        .iter()
+        .filter(|caa| is_tag(caa, "issuewild"))
+        .collect::<Vec<_>>();
+    let properties = match wildcard && !issuewild.is_empty() {
+        true => issuewild,
+        false => rrset.iter().filter(|caa| is_tag(caa, "issue")).collect(),
+    };
+
+    if properties.is_empty() {
+        return CaaAuthorization::Unrestricted;
+    }
+
+    properties
+        .into_iter()
+        .find(|caa| {
+            matches!(
+                caa.value_as_issue(),
+                Ok((Some(name), _)) if name.eq_ignore_root(issuer)
+            )
+        })
+        .map_or(CaaAuthorization::Denied, CaaAuthorization::Authorized)
+}
+
+/// The URLs of the `iodef` properties of a CAA RRset, to report denied requests to
+///
+/// Records with a malformed URL are skipped.
+pub fn iodef_urls(rrset: &[CAA]) -> Vec<Url> {
+    rrset
+        .iter()
+        .filter(|caa| caa.tag().eq_ignore_ascii_case("iodef"))
+        .filter_map(|caa| caa.value_as_iodef().ok())
+        .collect()
+}
+
 enum IssueProperty {
     Issue,
     IssueWild,
@@ -1096,4 +1206,96 @@ mod tests {
             assert_eq!(original.as_slice(), &encoded);
         }
     }
+
+    fn issue(issuer: &str) -> CAA {
+        CAA::new_issue(false, Some(Name::from_ascii(issuer).unwrap()), vec![])
+    }
+
+    #[test]
+    fn test_authorize() {
+        let ca = Name::from_ascii("ca.example.net").unwrap();
+        let other = issue("other.example.net");
+
+        assert_eq!(authorize(&[], &ca, false), CaaAuthorization::Unrestricted);
+
+        let rrset = [other.clone(), issue("CA.example.net.")];
+        assert_eq!(
+            authorize(&rrset, &ca, false),
+            CaaAuthorization::Authorized(&rrset[1])
+        );
+        assert_eq!(
+            authorize(core::slice::from_ref(&other), &ca, false),
+            CaaAuthorization::Denied
+        );
+
+        // an empty issuer value forbids any issuance
+        let deny = CAA::new_issue(false, None, vec![]);
+        assert!(!authorize(&[deny], &ca, false).is_permitted());
+
+        // only iodef properties don't restrict the issuance
+        let iodef = CAA::new_iodef(false, Url::parse("mailto:security@example.com").unwrap());
+        assert_eq!(
+            authorize(core::slice::from_ref(&iodef), &ca, false),
+            CaaAuthorization::Unrestricted
+        );
+        assert_eq!(
+            iodef_urls(&[iodef, other]),
+            vec![Url::parse("mailto:security@example.com").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_authorize_wildcard() {
+        let ca = Name::from_ascii("ca.example.net").unwrap();
+        let issuewild = CAA::new_issuewild(false, Some(ca.clone()), vec![]);
+        let rrset = [issue("other.example.net"), issuewild];
+
+        // issuewild takes precedence over issue for wildcard domains only
+        assert!(authorize(&rrset, &ca, true).is_permitted());
+        assert!(!authorize(&rrset, &ca, false).is_permitted());
+
+        // issue applies to wildcard domains without issuewild properties
+        assert!(authorize(&[issue("ca.example.net")], &ca, true).is_permitted());
+    }
+
+    #[test]
+    fn test_authorize_critical() {
+        let ca = Name::from_ascii("ca.example.net").unwrap();
+        let mut unknown = issue("ca.example.net");
+        unknown.set_tag("unknown".to_string());
+
+        let rrset = [issue("ca.example.net"), unknown];
+        assert!(authorize(&rrset, &ca, false).is_permitted());
+
+        let mut rrset = rrset;
+        rrset[1].set_issuer_critical(true);
+        assert_eq!(authorize(&rrset, &ca, false), CaaAuthorization::Denied);
+    }
+
+    #[test]
+    fn test_find_relevant_rrset() {
+        let lookup = |name: Name| async move {
+            match name.to_ascii().as_str() {
+                "example.com." => Ok::<_, ProtoError>(vec![issue("ca.example.net")]),
+                "broken.example.org." => Err("SERVFAIL".into()),
+                _ => Ok(vec![]),
+            }
+        };
+        let find = |domain: &str| {
+            futures_executor::block_on(find_relevant_rrset(
+                &Name::from_ascii(domain).unwrap(),
+                lookup,
+            ))
+        };
+
+        let (name, rrset) = find("www.sub.example.com.").unwrap().unwrap();
+        assert_eq!(name, Name::from_ascii("example.com.").unwrap());
+        assert_eq!(rrset, vec![issue("ca.example.net")]);
+
+        let (name, _) = find("*.example.com.").unwrap().unwrap();
+        assert_eq!(name, Name::from_ascii("example.com.").unwrap());
+
+        assert!(find("www.example.org.").unwrap().is_none());
+        assert!(find("www.broken.example.org.").is_err());
+    }
 }
@@ -7,7 +7,6 @@
 
 //! CERT record type for storing certificates in DNS
 use alloc::string::String;
-use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::fmt;
 
@@ -477,7 +476,7 @@ impl<'r> RecordDataDecodable<'r> for CERT {
         let rdata_length = length.map(|u| u as usize).unverified(/*used only as length safely*/);
 
         if rdata_length <= 5 {
-            return Err(ProtoError::from("invalid cert_record length".to_string()));
+            return Err(ProtoError::from("invalid cert_record length"));
         }
 
         let start_idx = decoder.index();
@@ -570,6 +569,8 @@ impl fmt::Display for CERT {
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
 
+    use alloc::string::ToString;
+
     use super::*;
 
     #[test]
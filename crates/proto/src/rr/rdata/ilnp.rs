@@ -0,0 +1,491 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Identifier-Locator Network Protocol (ILNP) records, NID, L32, L64 and LP
+//!
+//! [RFC 6742, DNS Resource Records for the Identifier-Locator Network Protocol (ILNP), November 2012](https://www.rfc-editor.org/rfc/rfc6742)
+
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ProtoResult,
+    rr::{RData, RecordData, RecordType, domain::Name, rdata::a::Ipv4Addr},
+    serialize::binary::*,
+};
+
+/// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.1.1)
+///
+/// ```text
+/// 2.1.1.  NID RDATA Wire Format
+///
+///    The RDATA for a NID RR consists of:
+///       - a 16-bit Preference field
+///       - a 64-bit NodeID field
+///
+///      0                   1                   2                   3
+///      0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///     |          Preference           |                               |
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               +
+///     |                             NodeID                            |
+///     +                               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///     |                               |
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct NID {
+    preference: u16,
+    node_id: u64,
+}
+
+impl NID {
+    /// Constructs a new NID RData
+    ///
+    /// # Arguments
+    ///
+    /// * `preference` - preference of this record among the NID records of the owner, lower values are preferred
+    /// * `node_id` - the ILNP Node Identifier
+    pub fn new(preference: u16, node_id: u64) -> Self {
+        Self {
+            preference,
+            node_id,
+        }
+    }
+
+    /// The preference given to this record among the NID records of the owner, lower values are preferred
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The ILNP Node Identifier
+    pub fn node_id(&self) -> u64 {
+        self.node_id
+    }
+}
+
+impl BinEncodable for NID {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.preference)?;
+        encoder.emit_vec(&self.node_id.to_be_bytes())
+    }
+}
+
+impl<'r> BinDecodable<'r> for NID {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        Ok(Self::new(
+            decoder.read_u16()?.unverified(/*any u16 is valid*/),
+            read_u64(decoder)?,
+        ))
+    }
+}
+
+impl RecordData for NID {
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::NID(nid) => Some(nid),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::NID
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::NID(self)
+    }
+}
+
+/// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.1.2)
+///
+/// ```text
+/// 2.1.2.  NID RR Presentation Format
+///
+///    The Preference field is represented as a 16-bit unsigned integer.
+///
+///    The NodeID field is represented as four groups of 16-bit hexadecimal
+///    digits, separated by colons, e.g. "0014:4fff:ff20:ee64".
+/// ```
+impl fmt::Display for NID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{} {}", self.preference, Hex64(self.node_id))
+    }
+}
+
+/// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.2.1)
+///
+/// ```text
+/// 2.2.1.  L32 RDATA Wire Format
+///
+///    The RDATA for a L32 RR consists of:
+///       - a 16-bit Preference field
+///       - a 32-bit Locator32 field
+///
+///      0                   1                   2                   3
+///      0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///     |          Preference           |      Locator32 (16 MSBs)      |
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///     |     Locator32 (16 LSBs)       |
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct L32 {
+    preference: u16,
+    locator32: Ipv4Addr,
+}
+
+impl L32 {
+    /// Constructs a new L32 RData
+    ///
+    /// # Arguments
+    ///
+    /// * `preference` - preference of this record among the L32 records of the owner, lower values are preferred
+    /// * `locator32` - the 32-bit ILNPv4 Locator, in the form of an IPv4 address
+    pub fn new(preference: u16, locator32: Ipv4Addr) -> Self {
+        Self {
+            preference,
+            locator32,
+        }
+    }
+
+    /// The preference given to this record among the L32 records of the owner, lower values are preferred
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The 32-bit ILNPv4 Locator
+    pub fn locator32(&self) -> Ipv4Addr {
+        self.locator32
+    }
+}
+
+impl BinEncodable for L32 {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.preference)?;
+        encoder.emit_vec(&self.locator32.octets())
+    }
+}
+
+impl<'r> BinDecodable<'r> for L32 {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        Ok(Self::new(
+            decoder.read_u16()?.unverified(/*any u16 is valid*/),
+            Ipv4Addr::from(decoder.read_array::<4>()?.unverified(/*any address is valid*/)),
+        ))
+    }
+}
+
+impl RecordData for L32 {
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::L32(l32) => Some(l32),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::L32
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::L32(self)
+    }
+}
+
+/// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.2.2)
+///
+/// ```text
+/// 2.2.2.  L32 RR Presentation Format
+///
+///    The Preference field is represented as a 16-bit unsigned integer.
+///
+///    The Locator32 field is represented as an IPv4 address using the
+///    conventional "dotted-quad" notation for IPv4 addresses.
+/// ```
+impl fmt::Display for L32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{} {}", self.preference, self.locator32)
+    }
+}
+
+/// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.3.1)
+///
+/// ```text
+/// 2.3.1.  L64 RDATA Wire Format
+///
+///    The RDATA for a L64 RR consists of:
+///       - a 16-bit Preference field
+///       - a 64-bit Locator64 field
+///
+///      0                   1                   2                   3
+///      0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///     |          Preference           |                               |
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               +
+///     |                          Locator64                            |
+///     +                               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///     |                               |
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct L64 {
+    preference: u16,
+    locator64: u64,
+}
+
+impl L64 {
+    /// Constructs a new L64 RData
+    ///
+    /// # Arguments
+    ///
+    /// * `preference` - preference of this record among the L64 records of the owner, lower values are preferred
+    /// * `locator64` - the 64-bit ILNPv6 Locator
+    pub fn new(preference: u16, locator64: u64) -> Self {
+        Self {
+            preference,
+            locator64,
+        }
+    }
+
+    /// The preference given to this record among the L64 records of the owner, lower values are preferred
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The 64-bit ILNPv6 Locator
+    pub fn locator64(&self) -> u64 {
+        self.locator64
+    }
+}
+
+impl BinEncodable for L64 {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.preference)?;
+        encoder.emit_vec(&self.locator64.to_be_bytes())
+    }
+}
+
+impl<'r> BinDecodable<'r> for L64 {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        Ok(Self::new(
+            decoder.read_u16()?.unverified(/*any u16 is valid*/),
+            read_u64(decoder)?,
+        ))
+    }
+}
+
+impl RecordData for L64 {
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::L64(l64) => Some(l64),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::L64
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::L64(self)
+    }
+}
+
+/// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.3.2)
+///
+/// ```text
+/// 2.3.2.  L64 RR Presentation Format
+///
+///    The Preference field is represented as a 16-bit unsigned integer.
+///
+///    The Locator64 field is represented as four groups of 16-bit
+///    hexadecimal digits, separated by colons, e.g. "2001:0db8:1140:1000".
+/// ```
+impl fmt::Display for L64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{} {}", self.preference, Hex64(self.locator64))
+    }
+}
+
+/// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.4.1)
+///
+/// ```text
+/// 2.4.1.  LP RDATA Wire Format
+///
+///    The RDATA for a LP RR consists of:
+///       - a 16-bit Preference field
+///       - a variable-length FQDN field
+///
+///      0                   1                   2                   3
+///      0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///     |          Preference           |                               /
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               /
+///     /                              FQDN                             /
+///     /                                                               /
+///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///
+///    The FQDN field MUST NOT be compressed.
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct LP {
+    preference: u16,
+    fqdn: Name,
+}
+
+impl LP {
+    /// Constructs a new LP RData
+    ///
+    /// # Arguments
+    ///
+    /// * `preference` - preference of this record among the LP records of the owner, lower values are preferred
+    /// * `fqdn` - the name holding the L32 or L64 records of the locators
+    pub fn new(preference: u16, fqdn: Name) -> Self {
+        Self { preference, fqdn }
+    }
+
+    /// The preference given to this record among the LP records of the owner, lower values are preferred
+    pub fn preference(&self) -> u16 {
+        self.preference
+    }
+
+    /// The name holding the L32 or L64 records of the locators
+    pub fn fqdn(&self) -> &Name {
+        &self.fqdn
+    }
+}
+
+impl BinEncodable for LP {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        let mut encoder = encoder.with_rdata_behavior(RDataEncoding::Other);
+
+        encoder.emit_u16(self.preference)?;
+        self.fqdn.emit(&mut encoder)
+    }
+}
+
+impl<'r> BinDecodable<'r> for LP {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        Ok(Self::new(
+            decoder.read_u16()?.unverified(/*any u16 is valid*/),
+            Name::read(decoder)?,
+        ))
+    }
+}
+
+impl RecordData for LP {
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::LP(lp) => Some(lp),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::LP
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::LP(self)
+    }
+}
+
+/// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.4.2)
+///
+/// ```text
+/// 2.4.2.  LP RR Presentation Format
+///
+///    The Preference field is represented as a 16-bit unsigned integer.
+///
+///    The FQDN field is represented as a domain name.
+/// ```
+impl fmt::Display for LP {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{} {}", self.preference, self.fqdn)
+    }
+}
+
+fn read_u64(decoder: &mut BinDecoder<'_>) -> ProtoResult<u64> {
+    Ok(u64::from_be_bytes(
+        decoder.read_array::<8>()?.unverified(/*any u64 is valid*/),
+    ))
+}
+
+/// A 64-bit NodeID or Locator64 as four colon separated groups of hexadecimal digits
+struct Hex64(u64);
+
+impl fmt::Display for Hex64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let [a, b, c, d] = [48, 32, 16, 0].map(|shift| (self.0 >> shift) as u16);
+        write!(f, "{a:04x}:{b:04x}:{c:04x}:{d:04x}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+    use core::str::FromStr;
+
+    use super::*;
+
+    fn round_trip<R>(rdata: R, expected: &[u8])
+    where
+        R: BinEncodable + for<'r> BinDecodable<'r> + PartialEq + fmt::Debug,
+    {
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        rdata.emit(&mut encoder).unwrap();
+        assert_eq!(bytes, expected);
+
+        let mut decoder = BinDecoder::new(expected);
+        assert_eq!(R::read(&mut decoder).unwrap(), rdata);
+    }
+
+    #[test]
+    fn test_nid() {
+        let rdata = NID::new(10, 0x0014_4fff_ff20_ee64);
+        round_trip(
+            rdata,
+            &[0, 10, 0x00, 0x14, 0x4f, 0xff, 0xff, 0x20, 0xee, 0x64],
+        );
+        assert_eq!(rdata.to_string(), "10 0014:4fff:ff20:ee64");
+    }
+
+    #[test]
+    fn test_l32() {
+        let rdata = L32::new(10, Ipv4Addr::new(10, 1, 2, 0));
+        round_trip(rdata, &[0, 10, 10, 1, 2, 0]);
+        assert_eq!(rdata.to_string(), "10 10.1.2.0");
+    }
+
+    #[test]
+    fn test_l64() {
+        let rdata = L64::new(20, 0x2001_0db8_1140_1000);
+        round_trip(
+            rdata,
+            &[0, 20, 0x20, 0x01, 0x0d, 0xb8, 0x11, 0x40, 0x10, 0x00],
+        );
+        assert_eq!(rdata.to_string(), "20 2001:0db8:1140:1000");
+    }
+
+    #[test]
+    fn test_lp() {
+        let rdata = LP::new(10, Name::from_str("l64-subnet1.example.com.").unwrap());
+        let mut expected = vec![0, 10];
+        expected.extend_from_slice(b"\x0bl64-subnet1\x07example\x03com\x00");
+        round_trip(rdata.clone(), &expected);
+        assert_eq!(rdata.to_string(), "10 l64-subnet1.example.com.");
+    }
+}
@@ -17,6 +17,7 @@ pub mod cert;
 pub mod csync;
 pub mod hinfo;
 pub mod https;
+pub mod ilnp;
 pub mod mx;
 pub mod name;
 pub mod naptr;
@@ -37,6 +38,7 @@ pub use self::cert::CERT;
 pub use self::csync::CSYNC;
 pub use self::hinfo::HINFO;
 pub use self::https::HTTPS;
+pub use self::ilnp::{L32, L64, LP, NID};
 pub use self::mx::MX;
 pub use self::name::{ANAME, CNAME, NS, PTR};
 pub use self::naptr::NAPTR;
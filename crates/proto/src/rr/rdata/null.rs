@@ -103,15 +103,32 @@ impl RecordData for NULL {
 }
 
 impl fmt::Display for NULL {
+    /// NULL has no presentation format, the generic one of RFC 3597 is used
+    ///
+    /// This replaces the base64 output of earlier versions, which no zone file parser could read
+    /// back, with `\\# <length> <hex>`, e.g. `\\# 2 ABCD`.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        f.write_str(&data_encoding::BASE64.encode(&self.anything))
+        write_generic(f, &self.anything)
     }
 }
 
+/// Writes record data in the generic `\# <length> <hex>` form
+///
+/// [RFC 3597, Handling of Unknown DNS Resource Record (RR) Types](https://datatracker.ietf.org/doc/html/rfc3597#section-5)
+pub(crate) fn write_generic(f: &mut fmt::Formatter<'_>, rdata: &[u8]) -> Result<(), fmt::Error> {
+    write!(f, "\\# {}", rdata.len())?;
+    if !rdata.is_empty() {
+        write!(f, " {}", data_encoding::HEXUPPER.encode(rdata))?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
 
+    use alloc::string::ToString;
     #[cfg(feature = "std")]
     use std::println;
 
@@ -134,4 +151,26 @@ mod tests {
         let read_rdata = NULL::read_data(&mut decoder, restrict).expect("Decoding error");
         assert_eq!(rdata, read_rdata);
     }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            NULL::with(vec![0xab, 0xcd, 0x01]).to_string(),
+            "\\# 3 ABCD01"
+        );
+        assert_eq!(NULL::new().to_string(), "\\# 0");
+    }
+
+    #[test]
+    #[cfg(feature = "text-parsing")]
+    fn test_display_round_trip() {
+        use crate::rr::{RData, RecordType};
+
+        let rdata = RData::NULL(NULL::with(vec![0, 1, 2, 3, 4, 5, 6, 7]));
+        let text = rdata.to_string();
+        assert_eq!(
+            RData::from_presentation(RecordType::NULL, &text).unwrap(),
+            rdata
+        );
+    }
 }
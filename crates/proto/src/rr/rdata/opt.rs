@@ -8,7 +8,7 @@
 //! option record for passing protocol options between the client and server
 #![allow(clippy::use_self)]
 
-use alloc::vec::Vec;
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
 use core::fmt;
 #[cfg(not(feature = "std"))]
 use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
@@ -406,6 +406,9 @@ pub enum EdnsCode {
     /// [RFC 7901, CHAIN Query Requests in DNS, Optional](https://tools.ietf.org/html/rfc7901)
     Chain,
 
+    /// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914)
+    ExtendedDnsError,
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16),
 }
@@ -428,6 +431,7 @@ impl From<u16> for EdnsCode {
             11 => Self::Keepalive,
             12 => Self::Padding,
             13 => Self::Chain,
+            15 => Self::ExtendedDnsError,
             _ => Self::Unknown(value),
         }
     }
@@ -450,6 +454,7 @@ impl From<EdnsCode> for u16 {
             EdnsCode::Keepalive => 11,
             EdnsCode::Padding => 12,
             EdnsCode::Chain => 13,
+            EdnsCode::ExtendedDnsError => 15,
             EdnsCode::Unknown(value) => value,
         }
     }
@@ -474,6 +479,12 @@ pub enum EdnsOption {
     /// [RFC 5001, DNS Name Server Identifier (NSID) Option](https://tools.ietf.org/html/rfc5001)
     NSID(NSIDPayload),
 
+    /// [RFC 7873, Domain Name System (DNS) Cookies](https://tools.ietf.org/html/rfc7873)
+    Cookie(CookieOption),
+
+    /// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914)
+    ExtendedDnsError(ExtendedDnsError),
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16, Vec<u8>),
 }
@@ -486,7 +497,9 @@ impl EdnsOption {
             EdnsOption::DAU(algorithms) => algorithms.len(),
             EdnsOption::Subnet(subnet) => subnet.len(),
             EdnsOption::NSID(payload) => payload.as_ref().len() as u16, // cast safety: NSIDPayload size is constrained.
-            EdnsOption::Unknown(_, data) => data.len() as u16,          // TODO: should we verify?
+            EdnsOption::Cookie(cookie) => cookie.len(),
+            EdnsOption::ExtendedDnsError(error) => error.len(),
+            EdnsOption::Unknown(_, data) => data.len() as u16, // TODO: should we verify?
         }
     }
 
@@ -497,6 +510,8 @@ impl EdnsOption {
             EdnsOption::DAU(algorithms) => algorithms.is_empty(),
             EdnsOption::Subnet(subnet) => subnet.is_empty(),
             EdnsOption::NSID(payload) => payload.as_ref().is_empty(),
+            EdnsOption::Cookie(cookie) => cookie.is_empty(),
+            EdnsOption::ExtendedDnsError(error) => error.is_empty(),
             EdnsOption::Unknown(_, data) => data.is_empty(),
         }
     }
@@ -509,6 +524,8 @@ impl BinEncodable for EdnsOption {
             EdnsOption::DAU(algorithms) => algorithms.emit(encoder),
             EdnsOption::Subnet(subnet) => subnet.emit(encoder),
             EdnsOption::NSID(payload) => encoder.emit_vec(payload.as_ref()),
+            EdnsOption::Cookie(cookie) => cookie.emit(encoder),
+            EdnsOption::ExtendedDnsError(error) => error.emit(encoder),
             EdnsOption::Unknown(_, data) => encoder.emit_vec(data), // gah, clone needed or make a crazy api.
         }
     }
@@ -524,6 +541,8 @@ impl<'a> TryFrom<(EdnsCode, &'a [u8])> for EdnsOption {
             EdnsCode::DAU => Self::DAU(value.1.into()),
             EdnsCode::Subnet => Self::Subnet(value.1.try_into()?),
             EdnsCode::NSID => Self::NSID(value.1.try_into()?),
+            EdnsCode::Cookie => Self::Cookie(value.1.try_into()?),
+            EdnsCode::ExtendedDnsError => Self::ExtendedDnsError(value.1.try_into()?),
             _ => Self::Unknown(value.0.into(), value.1.to_vec()),
         })
     }
@@ -538,6 +557,8 @@ impl<'a> TryFrom<&'a EdnsOption> for Vec<u8> {
             EdnsOption::DAU(algorithms) => algorithms.into(),
             EdnsOption::Subnet(subnet) => subnet.try_into()?,
             EdnsOption::NSID(payload) => payload.as_ref().to_vec(),
+            EdnsOption::Cookie(cookie) => cookie.into(),
+            EdnsOption::ExtendedDnsError(error) => error.into(),
             EdnsOption::Unknown(_, data) => data.clone(), // gah, clone needed or make a crazy api.
         })
     }
@@ -550,11 +571,44 @@ impl<'a> From<&'a EdnsOption> for EdnsCode {
             EdnsOption::DAU(..) => Self::DAU,
             EdnsOption::Subnet(..) => Self::Subnet,
             EdnsOption::NSID(..) => Self::NSID,
+            EdnsOption::Cookie(..) => Self::Cookie,
+            EdnsOption::ExtendedDnsError(..) => Self::ExtendedDnsError,
             EdnsOption::Unknown(code, _) => (*code).into(),
         }
     }
 }
 
+#[cfg(feature = "__dnssec")]
+impl From<SupportedAlgorithms> for EdnsOption {
+    fn from(algorithms: SupportedAlgorithms) -> Self {
+        Self::DAU(algorithms)
+    }
+}
+
+impl From<ClientSubnet> for EdnsOption {
+    fn from(subnet: ClientSubnet) -> Self {
+        Self::Subnet(subnet)
+    }
+}
+
+impl From<NSIDPayload> for EdnsOption {
+    fn from(payload: NSIDPayload) -> Self {
+        Self::NSID(payload)
+    }
+}
+
+impl From<CookieOption> for EdnsOption {
+    fn from(cookie: CookieOption) -> Self {
+        Self::Cookie(cookie)
+    }
+}
+
+impl From<ExtendedDnsError> for EdnsOption {
+    fn from(error: ExtendedDnsError) -> Self {
+        Self::ExtendedDnsError(error)
+    }
+}
+
 /// [RFC 7871, Client Subnet, Optional](https://tools.ietf.org/html/rfc7871)
 ///
 /// ```text
@@ -820,6 +874,424 @@ impl AsRef<[u8]> for NSIDPayload {
     }
 }
 
+/// [RFC 7873, Domain Name System (DNS) Cookies](https://tools.ietf.org/html/rfc7873)
+///
+/// ```text
+/// 4.  The COOKIE OPTION
+///
+///    The COOKIE option is an EDNS(0) option [RFC6891] that can be included
+///    in the RDATA portion of an OPT RR in DNS requests and responses.  The
+///    option length varies, depending on the circumstances in which it is
+///    being used.  There are two cases, as described below.  Both use the
+///    same OPTION-CODE; they are distinguished by their length.
+///
+///    In a request sent by a client to a server when the client does not
+///    know the server's cookie, its length is 8, consisting of an 8-byte
+///    Client Cookie.
+///
+///    In a request sent by a client when a Server Cookie is known, and in
+///    all responses to such a request, the length is variable from 16 to
+///    40 bytes, consisting of an 8-byte Client Cookie followed by the
+///    variable-length (8 bytes to 32 bytes) Server Cookie.
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct CookieOption {
+    client: [u8; Self::CLIENT_COOKIE_LEN],
+    server: Option<Vec<u8>>,
+}
+
+impl CookieOption {
+    /// Length of the client cookie
+    pub const CLIENT_COOKIE_LEN: usize = 8;
+
+    /// Minimum length of the server cookie
+    pub const MIN_SERVER_COOKIE_LEN: usize = 8;
+
+    /// Maximum length of the server cookie
+    pub const MAX_SERVER_COOKIE_LEN: usize = 32;
+
+    /// Construct a cookie option with only a client cookie, as sent when the server cookie is
+    /// not known
+    pub fn new(client: [u8; Self::CLIENT_COOKIE_LEN]) -> Self {
+        Self {
+            client,
+            server: None,
+        }
+    }
+
+    /// Construct a cookie option with both the client and the server cookie
+    ///
+    /// A `ProtoError` is returned if the server cookie is not between 8 and 32 bytes long.
+    pub fn with_server_cookie(
+        client: [u8; Self::CLIENT_COOKIE_LEN],
+        server: impl Into<Vec<u8>>,
+    ) -> Result<Self, ProtoError> {
+        let server = server.into();
+        if !(Self::MIN_SERVER_COOKIE_LEN..=Self::MAX_SERVER_COOKIE_LEN).contains(&server.len()) {
+            return Err(ProtoError::from("invalid server cookie length"));
+        }
+
+        Ok(Self {
+            client,
+            server: Some(server),
+        })
+    }
+
+    /// Returns the client cookie
+    pub fn client_cookie(&self) -> &[u8; Self::CLIENT_COOKIE_LEN] {
+        &self.client
+    }
+
+    /// Returns the server cookie, if any
+    pub fn server_cookie(&self) -> Option<&[u8]> {
+        self.server.as_deref()
+    }
+
+    /// Returns the length in bytes of the cookie option
+    pub fn len(&self) -> u16 {
+        // cast safety: the server cookie is at most 32 bytes long
+        (Self::CLIENT_COOKIE_LEN + self.server.as_ref().map_or(0, Vec::len)) as u16
+    }
+
+    /// Returns `true` if the length in bytes of the cookie option is 0, which is never the case
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl BinEncodable for CookieOption {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_vec(&self.client)?;
+        if let Some(server) = &self.server {
+            encoder.emit_vec(server)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for CookieOption {
+    type Error = ProtoError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        if value.len() < Self::CLIENT_COOKIE_LEN {
+            return Err(ProtoError::from("invalid cookie option length"));
+        }
+
+        let (client, server) = value.split_at(Self::CLIENT_COOKIE_LEN);
+        let mut client_cookie = [0; Self::CLIENT_COOKIE_LEN];
+        client_cookie.copy_from_slice(client);
+
+        match server.is_empty() {
+            true => Ok(Self::new(client_cookie)),
+            false => Self::with_server_cookie(client_cookie, server),
+        }
+    }
+}
+
+impl<'a> From<&'a CookieOption> for Vec<u8> {
+    fn from(value: &'a CookieOption) -> Self {
+        let mut bytes = Self::with_capacity(value.len() as usize);
+        bytes.extend_from_slice(&value.client);
+        if let Some(server) = &value.server {
+            bytes.extend_from_slice(server);
+        }
+
+        bytes
+    }
+}
+
+/// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914)
+///
+/// ```text
+/// 2.  Extended DNS Error EDNS0 Option Format
+///
+///                                                 1   1   1   1   1   1
+///         0   1   2   3   4   5   6   7   8   9   0   1   2   3   4   5
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    0: |                            OPTION-CODE                        |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    2: |                           OPTION-LENGTH                       |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    4: | INFO-CODE                                                     |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    6: / EXTRA-TEXT ...                                                /
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///
+///    INFO-CODE:  16 bits, which is the principal contribution of this
+///       document.  This 16-bit value, encoded in network most significant
+///       bit (MSB) byte order, provides the additional context for the
+///       RESPONSE-CODE of the DNS message.
+///
+///    EXTRA-TEXT:  a variable-length, UTF-8-encoded [RFC5198] text field
+///       that may hold additional textual information.  This information is
+///       intended for human consumption (not automated parsing).
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ExtendedDnsError {
+    info_code: ExtendedDnsErrorCode,
+    extra_text: String,
+}
+
+impl ExtendedDnsError {
+    /// Construct an extended error without any extra text
+    pub fn new(info_code: ExtendedDnsErrorCode) -> Self {
+        Self {
+            info_code,
+            extra_text: String::new(),
+        }
+    }
+
+    /// Construct an extended error with extra text for human consumption
+    ///
+    /// A `ProtoError` is returned if the text is too large to be expressed as an EDNS option value.
+    pub fn with_extra_text(
+        info_code: ExtendedDnsErrorCode,
+        extra_text: impl Into<String>,
+    ) -> Result<Self, ProtoError> {
+        let extra_text = extra_text.into();
+        if extra_text.len() > (u16::MAX - 2) as usize {
+            return Err(ProtoError::from("extended DNS error text too large"));
+        }
+
+        Ok(Self {
+            info_code,
+            extra_text,
+        })
+    }
+
+    /// Returns the code of the error
+    pub fn info_code(&self) -> ExtendedDnsErrorCode {
+        self.info_code
+    }
+
+    /// Returns the extra text, which is empty if there is none
+    pub fn extra_text(&self) -> &str {
+        &self.extra_text
+    }
+
+    /// Returns the length in bytes of the extended error option
+    pub fn len(&self) -> u16 {
+        // cast safety: the extra text is constrained to fit
+        (2 + self.extra_text.len()) as u16
+    }
+
+    /// Returns `true` if the length in bytes of the extended error option is 0, which is never the
+    /// case
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl BinEncodable for ExtendedDnsError {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.info_code.into())?;
+        encoder.emit_vec(self.extra_text.as_bytes())
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ExtendedDnsError {
+    type Error = ProtoError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        if value.len() < 2 {
+            return Err(ProtoError::from("invalid extended DNS error length"));
+        }
+        let (code, text) = value.split_at(2);
+
+        // the text is only informational, so it is accepted even if it is not valid UTF-8
+        let extra_text = String::from_utf8_lossy(text);
+        Ok(Self {
+            info_code: u16::from_be_bytes([code[0], code[1]]).into(),
+            extra_text: extra_text.trim_end_matches('\0').to_owned(),
+        })
+    }
+}
+
+impl<'a> From<&'a ExtendedDnsError> for Vec<u8> {
+    fn from(value: &'a ExtendedDnsError) -> Self {
+        let mut bytes = Self::with_capacity(value.len() as usize);
+        bytes.extend_from_slice(&u16::from(value.info_code).to_be_bytes());
+        bytes.extend_from_slice(value.extra_text.as_bytes());
+        bytes
+    }
+}
+
+impl fmt::Display for ExtendedDnsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.extra_text.is_empty() {
+            true => write!(f, "{}", self.info_code),
+            false => write!(f, "{}: {}", self.info_code, self.extra_text),
+        }
+    }
+}
+
+/// The INFO-CODE of an [`ExtendedDnsError`]
+///
+/// <https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#extended-dns-error-codes>
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum ExtendedDnsErrorCode {
+    /// The error does not match any of the other codes, see the extra text
+    Other,
+    /// A DNSKEY RRset contained only unsupported DNSSEC algorithms
+    UnsupportedDnskeyAlgorithm,
+    /// A DS RRset contained only unsupported digest types
+    UnsupportedDsDigestType,
+    /// The resolver answered with stale data
+    StaleAnswer,
+    /// The answer was forged, e.g. by a policy
+    ForgedAnswer,
+    /// The DNSSEC validation ended in the indeterminate state
+    DnssecIndeterminate,
+    /// The DNSSEC validation ended in the bogus state
+    DnssecBogus,
+    /// No RRSIGs could be validated because they all expired
+    SignatureExpired,
+    /// No RRSIGs could be validated because they are not yet valid
+    SignatureNotYetValid,
+    /// A trust anchor indicates that a DNSKEY is missing
+    DnskeyMissing,
+    /// There are no RRSIGs for the DNSKEY RRset of a signed zone
+    RrsigsMissing,
+    /// No DNSKEY record has the zone key bit set
+    NoZoneKeyBitSet,
+    /// The requested data is missing, and the NSEC records are missing too
+    NsecMissing,
+    /// A cached SERVFAIL is returned
+    CachedError,
+    /// The server is not able to answer yet, e.g. while loading zones
+    NotReady,
+    /// The domain is on a blocklist of the operator
+    Blocked,
+    /// The domain is on a blocklist requested by an external entity
+    Censored,
+    /// The domain is on a blocklist requested by the client
+    Filtered,
+    /// The client is not permitted to perform the query
+    Prohibited,
+    /// The resolver answered with a stale NXDOMAIN
+    StaleNxDomainAnswer,
+    /// The server is not authoritative for the query, and recursion is not available
+    NotAuthoritative,
+    /// The requested operation or query is not supported
+    NotSupported,
+    /// None of the authoritative name servers could be reached
+    NoReachableAuthority,
+    /// An unrecoverable network error occurred
+    NetworkError,
+    /// The data of the zone is not valid
+    InvalidData,
+    /// Unknown, used to deal with unassigned codes
+    Unknown(u16),
+}
+
+impl From<u16> for ExtendedDnsErrorCode {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => Self::Other,
+            1 => Self::UnsupportedDnskeyAlgorithm,
+            2 => Self::UnsupportedDsDigestType,
+            3 => Self::StaleAnswer,
+            4 => Self::ForgedAnswer,
+            5 => Self::DnssecIndeterminate,
+            6 => Self::DnssecBogus,
+            7 => Self::SignatureExpired,
+            8 => Self::SignatureNotYetValid,
+            9 => Self::DnskeyMissing,
+            10 => Self::RrsigsMissing,
+            11 => Self::NoZoneKeyBitSet,
+            12 => Self::NsecMissing,
+            13 => Self::CachedError,
+            14 => Self::NotReady,
+            15 => Self::Blocked,
+            16 => Self::Censored,
+            17 => Self::Filtered,
+            18 => Self::Prohibited,
+            19 => Self::StaleNxDomainAnswer,
+            20 => Self::NotAuthoritative,
+            21 => Self::NotSupported,
+            22 => Self::NoReachableAuthority,
+            23 => Self::NetworkError,
+            24 => Self::InvalidData,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<ExtendedDnsErrorCode> for u16 {
+    fn from(value: ExtendedDnsErrorCode) -> Self {
+        match value {
+            ExtendedDnsErrorCode::Other => 0,
+            ExtendedDnsErrorCode::UnsupportedDnskeyAlgorithm => 1,
+            ExtendedDnsErrorCode::UnsupportedDsDigestType => 2,
+            ExtendedDnsErrorCode::StaleAnswer => 3,
+            ExtendedDnsErrorCode::ForgedAnswer => 4,
+            ExtendedDnsErrorCode::DnssecIndeterminate => 5,
+            ExtendedDnsErrorCode::DnssecBogus => 6,
+            ExtendedDnsErrorCode::SignatureExpired => 7,
+            ExtendedDnsErrorCode::SignatureNotYetValid => 8,
+            ExtendedDnsErrorCode::DnskeyMissing => 9,
+            ExtendedDnsErrorCode::RrsigsMissing => 10,
+            ExtendedDnsErrorCode::NoZoneKeyBitSet => 11,
+            ExtendedDnsErrorCode::NsecMissing => 12,
+            ExtendedDnsErrorCode::CachedError => 13,
+            ExtendedDnsErrorCode::NotReady => 14,
+            ExtendedDnsErrorCode::Blocked => 15,
+            ExtendedDnsErrorCode::Censored => 16,
+            ExtendedDnsErrorCode::Filtered => 17,
+            ExtendedDnsErrorCode::Prohibited => 18,
+            ExtendedDnsErrorCode::StaleNxDomainAnswer => 19,
+            ExtendedDnsErrorCode::NotAuthoritative => 20,
+            ExtendedDnsErrorCode::NotSupported => 21,
+            ExtendedDnsErrorCode::NoReachableAuthority => 22,
+            ExtendedDnsErrorCode::NetworkError => 23,
+            ExtendedDnsErrorCode::InvalidData => 24,
+            ExtendedDnsErrorCode::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for ExtendedDnsErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Other => "Other Error",
+            Self::UnsupportedDnskeyAlgorithm => "Unsupported DNSKEY Algorithm",
+            Self::UnsupportedDsDigestType => "Unsupported DS Digest Type",
+            Self::StaleAnswer => "Stale Answer",
+            Self::ForgedAnswer => "Forged Answer",
+            Self::DnssecIndeterminate => "DNSSEC Indeterminate",
+            Self::DnssecBogus => "DNSSEC Bogus",
+            Self::SignatureExpired => "Signature Expired",
+            Self::SignatureNotYetValid => "Signature Not Yet Valid",
+            Self::DnskeyMissing => "DNSKEY Missing",
+            Self::RrsigsMissing => "RRSIGs Missing",
+            Self::NoZoneKeyBitSet => "No Zone Key Bit Set",
+            Self::NsecMissing => "NSEC Missing",
+            Self::CachedError => "Cached Error",
+            Self::NotReady => "Not Ready",
+            Self::Blocked => "Blocked",
+            Self::Censored => "Censored",
+            Self::Filtered => "Filtered",
+            Self::Prohibited => "Prohibited",
+            Self::StaleNxDomainAnswer => "Stale NXDomain Answer",
+            Self::NotAuthoritative => "Not Authoritative",
+            Self::NotSupported => "Not Supported",
+            Self::NoReachableAuthority => "No Reachable Authority",
+            Self::NetworkError => "Network Error",
+            Self::InvalidData => "Invalid Data",
+            Self::Unknown(_) => "Unassigned",
+        };
+
+        f.write_str(s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
@@ -872,7 +1344,9 @@ mod tests {
             ),
             (
                 EdnsCode::Cookie,
-                EdnsOption::Unknown(10, vec![0x0b, 0x64, 0xb4, 0xdc, 0xd7, 0xb0, 0xcc, 0x8f]),
+                EdnsOption::Cookie(CookieOption::new([
+                    0x0b, 0x64, 0xb4, 0xdc, 0xd7, 0xb0, 0xcc, 0x8f,
+                ])),
             ),
             (EdnsCode::Keepalive, EdnsOption::Unknown(11, vec![])),
         ];
@@ -898,17 +1372,19 @@ mod tests {
         let opt = read_rdata.unwrap();
         let options = vec![
             (
-                EdnsCode::Unknown(15u16),
-                EdnsOption::Unknown(15u16, vec![0x00, 0x06]),
+                EdnsCode::ExtendedDnsError,
+                EdnsOption::ExtendedDnsError(ExtendedDnsError::new(
+                    ExtendedDnsErrorCode::DnssecBogus,
+                )),
             ),
             (
-                EdnsCode::Unknown(15u16),
-                EdnsOption::Unknown(
-                    15u16,
-                    vec![
-                        0x00, 0x09, 0x55, 0x6E, 0x6B, 0x6E, 0x6F, 0x77, 0x6E, 0x20, 0x65, 0x72,
-                        0x72, 0x6F, 0x72,
-                    ],
+                EdnsCode::ExtendedDnsError,
+                EdnsOption::ExtendedDnsError(
+                    ExtendedDnsError::with_extra_text(
+                        ExtendedDnsErrorCode::DnskeyMissing,
+                        "Unknown error",
+                    )
+                    .unwrap(),
                 ),
             ),
         ];
@@ -952,4 +1428,53 @@ mod tests {
         let payload_out = EdnsOption::try_from((EdnsCode::NSID, buf.as_ref())).unwrap();
         assert_eq!(payload_in, payload_out);
     }
+
+    #[test]
+    fn test_cookie_roundtrip() {
+        let client = [0x0b, 0x64, 0xb4, 0xdc, 0xd7, 0xb0, 0xcc, 0x8f];
+        for cookie_in in [
+            CookieOption::new(client),
+            CookieOption::with_server_cookie(client, [0x42; 16]).unwrap(),
+        ] {
+            let option_in = EdnsOption::from(cookie_in.clone());
+            let mut buf = Vec::new();
+            let mut encoder = BinEncoder::new(&mut buf);
+            option_in.emit(&mut encoder).unwrap();
+            assert_eq!(buf.len(), usize::from(option_in.len()));
+
+            let option_out = EdnsOption::try_from((EdnsCode::Cookie, buf.as_ref())).unwrap();
+            assert_eq!(option_in, option_out);
+        }
+    }
+
+    #[test]
+    fn test_extended_dns_error_roundtrip() {
+        let option_in = EdnsOption::from(
+            ExtendedDnsError::with_extra_text(
+                ExtendedDnsErrorCode::NoReachableAuthority,
+                "no name server of example.com. could be reached",
+            )
+            .unwrap(),
+        );
+        let mut buf = Vec::new();
+        let mut encoder = BinEncoder::new(&mut buf);
+        option_in.emit(&mut encoder).unwrap();
+        assert_eq!(buf.len(), usize::from(option_in.len()));
+        assert_eq!(&buf[..2], &[0x00, 22]);
+
+        let option_out = EdnsOption::try_from((EdnsCode::ExtendedDnsError, buf.as_ref())).unwrap();
+        assert_eq!(option_in, option_out);
+
+        assert!(ExtendedDnsError::try_from([0x00].as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_cookie_length() {
+        for len in [0, 7, 9, 15, 41] {
+            let bytes = vec![0x42; len];
+            assert!(CookieOption::try_from(bytes.as_slice()).is_err(), "{len}");
+        }
+
+        assert!(CookieOption::with_server_cookie([0; 8], [0; 33]).is_err());
+    }
 }
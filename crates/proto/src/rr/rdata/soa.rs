@@ -115,6 +115,11 @@ impl SOA {
         self.serial += 1; // TODO: what to do on overflow?
     }
 
+    /// Sets the serial number
+    pub fn set_serial(&mut self, serial: u32) {
+        self.serial = serial;
+    }
+
     /// ```text
     /// MNAME           The <domain-name> of the name server that was the
     ///                 original or primary source of data for this zone.
@@ -101,6 +101,74 @@ impl SSHFP {
     pub fn fingerprint(&self) -> &[u8] {
         &self.fingerprint
     }
+
+    /// Creates the record data for an OpenSSH public key line, as found in `.pub`,
+    /// `authorized_keys` and `known_hosts` files.
+    ///
+    /// Leading options or host names and the trailing comment of the line are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - the public key, e.g. `ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAI... user@host`.
+    /// * `fingerprint_type` - the digest to calculate over the key, SHA-1 or SHA-256.
+    #[cfg(feature = "__dnssec")]
+    pub fn from_openssh(line: &str, fingerprint_type: FingerprintType) -> ProtoResult<Self> {
+        use alloc::format;
+
+        use crate::dnssec::ring_like::digest;
+
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let (algorithm, key_type, key) = tokens
+            .windows(2)
+            .find_map(|window| Some((openssh_algorithm(window[0])?, window[0], window[1])))
+            .ok_or_else(|| ProtoError::from("no supported OpenSSH public key found"))?;
+
+        let key = data_encoding::BASE64
+            .decode(key.as_bytes())
+            .map_err(|e| ProtoError::from(format!("invalid OpenSSH public key: {e}")))?;
+
+        // the key blob starts with its key type as an SSH string, RFC 4253 section 6.6
+        let embedded_type = key
+            .get(..4)
+            .map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize)
+            .and_then(|len| key.get(4..)?.get(..len));
+        if embedded_type != Some(key_type.as_bytes()) {
+            return Err(
+                format!("OpenSSH public key does not match its key type {key_type}").into(),
+            );
+        }
+
+        let digest_algorithm = match fingerprint_type {
+            FingerprintType::SHA1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+            FingerprintType::SHA256 => &digest::SHA256,
+            _ => {
+                return Err(
+                    format!("unsupported SSHFP fingerprint type: {fingerprint_type:?}").into(),
+                );
+            }
+        };
+
+        Ok(Self::new(
+            algorithm,
+            fingerprint_type,
+            digest::digest(digest_algorithm, &key).as_ref().to_vec(),
+        ))
+    }
+}
+
+/// The SSHFP algorithm of an OpenSSH key type
+#[cfg(feature = "__dnssec")]
+fn openssh_algorithm(key_type: &str) -> Option<Algorithm> {
+    match key_type {
+        "ssh-rsa" => Some(Algorithm::RSA),
+        "ssh-dss" => Some(Algorithm::DSA),
+        "ecdsa-sha2-nistp256" | "ecdsa-sha2-nistp384" | "ecdsa-sha2-nistp521" => {
+            Some(Algorithm::ECDSA)
+        }
+        "ssh-ed25519" => Some(Algorithm::Ed25519),
+        "ssh-ed448" => Some(Algorithm::Ed448),
+        _ => None,
+    }
 }
 
 /// ```text
@@ -302,6 +370,9 @@ impl fmt::Display for SSHFP {
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "__dnssec")]
+    use alloc::string::ToString;
+
     use super::*;
 
     #[test]
@@ -386,4 +457,47 @@ mod tests {
             &[255, 13, 100, 110, 115, 115, 101, 99, 32, 100, 97, 110, 101],
         );
     }
+
+    #[cfg(feature = "__dnssec")]
+    const ED25519_KEY: &str = "ssh-ed25519 \
+        AAAAC3NzaC1lZDI1NTE5AAAAIJGVNlh1RrmnC0y04Qs/tx33fH7TsoYneGAvNiT2py6O user@example";
+
+    #[cfg(feature = "__dnssec")]
+    #[test]
+    fn test_from_openssh() {
+        // the expected fingerprints are the output of `ssh-keygen -r`
+        let sshfp = SSHFP::from_openssh(ED25519_KEY, FingerprintType::SHA1).unwrap();
+        assert_eq!(
+            sshfp.to_string(),
+            "4 1 7a56d695b33eaa765c93d77208af722f2d662e27"
+        );
+
+        let sshfp = SSHFP::from_openssh(ED25519_KEY, FingerprintType::SHA256).unwrap();
+        assert_eq!(
+            sshfp.to_string(),
+            "4 2 095f9f39bc5cf3e2401143a3cfb7578ce08eeee3ed1910cb5502c260237e9c34"
+        );
+
+        let ecdsa = "host.example.com,192.0.2.1 ecdsa-sha2-nistp256 \
+            AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAAAIbmlzdHAyNTYAAABBBPazgCdxKnMl2llDELgVVHHxQwTsvx1N6Pfj\
+            dznZ+ZDVTrWOT/tku7fbqaKrKYYSXtZZySMO6g4wS7i91HUeWuw=";
+        let sshfp = SSHFP::from_openssh(ecdsa, FingerprintType::SHA256).unwrap();
+        assert_eq!(
+            sshfp.to_string(),
+            "3 2 6ec2516168cce2c6bd66b6814da4eba6c0e43d0ae956a407989d2cffd5cbc7b1"
+        );
+    }
+
+    #[cfg(feature = "__dnssec")]
+    #[test]
+    fn test_from_openssh_invalid() {
+        assert!(SSHFP::from_openssh("", FingerprintType::SHA256).is_err());
+        assert!(SSHFP::from_openssh("ssh-ed25519", FingerprintType::SHA256).is_err());
+        assert!(SSHFP::from_openssh("ssh-ed25519 !!!", FingerprintType::SHA256).is_err());
+        assert!(SSHFP::from_openssh(ED25519_KEY, FingerprintType::Unassigned(3)).is_err());
+
+        // the key type of the line must match the one in the key
+        let mismatch = ED25519_KEY.replacen("ssh-ed25519", "ssh-rsa", 1);
+        assert!(SSHFP::from_openssh(&mismatch, FingerprintType::SHA256).is_err());
+    }
 }
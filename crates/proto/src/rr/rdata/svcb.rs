@@ -465,12 +465,10 @@ impl SvcParamValue {
             .read_u16()?
             .verify_unwrap(|len| *len as usize <= decoder.len())
             .map(|len| len as usize)
-            .map_err(|u| {
-                ProtoError::from(format!(
-                    "length of SvcParamValue ({}) exceeds remainder in RDATA ({})",
-                    u,
-                    decoder.len()
-                ))
+            .map_err(|u| ProtoErrorKind::LengthExceedsRemaining {
+                field: "SvcParamValue",
+                len: usize::from(u),
+                remaining: decoder.len(),
             })?;
 
         let param_data = decoder.read_slice(len)?.unverified(/*verification to be done by individual param types*/);
@@ -1115,10 +1113,11 @@ impl RecordDataDecodable<'_> for SVCB {
         let svc_priority = decoder.read_u16()?.unverified(/*any u16 is valid*/);
         let target_name = Name::read(decoder)?;
 
+        let read = decoder.index() - start_index;
         let mut remainder_len = rdata_length
             .map(|len| len as usize)
-            .checked_sub(decoder.index() - start_index)
-            .map_err(|len| format!("Bad length for RDATA of SVCB: {len}"))?
+            .checked_sub(read)
+            .map_err(|len| ProtoErrorKind::IncorrectRDataLengthRead { read, len })?
             .unverified(); // valid len
         let mut svc_params: Vec<(SvcParamKey, SvcParamValue)> = Vec::new();
 
@@ -1140,10 +1139,11 @@ impl RecordDataDecodable<'_> for SVCB {
             }
 
             svc_params.push((key, value));
+            let read = decoder.index() - start_index;
             remainder_len = rdata_length
                 .map(|len| len as usize)
-                .checked_sub(decoder.index() - start_index)
-                .map_err(|len| format!("Bad length for RDATA of SVCB: {len}"))?
+                .checked_sub(read)
+                .map_err(|len| ProtoErrorKind::IncorrectRDataLengthRead { read, len })?
                 .unverified(); // valid len
         }
 
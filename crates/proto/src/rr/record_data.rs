@@ -27,12 +27,12 @@ use crate::{
     rr::{
         RecordData, RecordDataDecodable,
         rdata::{
-            A, AAAA, ANAME, CAA, CERT, CNAME, CSYNC, HINFO, HTTPS, MX, NAPTR, NS, NULL, OPENPGPKEY,
-            OPT, PTR, SOA, SRV, SSHFP, SVCB, TLSA, TXT,
+            A, AAAA, ANAME, CAA, CERT, CNAME, CSYNC, HINFO, HTTPS, L32, L64, LP, MX, NAPTR, NID,
+            NS, NULL, OPENPGPKEY, OPT, PTR, SOA, SRV, SSHFP, SVCB, TLSA, TXT, null,
         },
         record_type::RecordType,
     },
-    serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, Restrict},
+    serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, DecodeMode, Restrict},
 };
 
 #[cfg(feature = "__dnssec")]
@@ -258,6 +258,30 @@ pub enum RData {
     /// ```
     HTTPS(HTTPS),
 
+    /// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.2.1)
+    ///
+    /// ```text
+    /// The RDATA for a L32 RR consists of a 16-bit Preference field and a
+    /// 32-bit Locator32 field.
+    /// ```
+    L32(L32),
+
+    /// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.3.1)
+    ///
+    /// ```text
+    /// The RDATA for a L64 RR consists of a 16-bit Preference field and a
+    /// 64-bit Locator64 field.
+    /// ```
+    L64(L64),
+
+    /// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.4.1)
+    ///
+    /// ```text
+    /// The RDATA for a LP RR consists of a 16-bit Preference field and a
+    /// variable-length FQDN field.
+    /// ```
+    LP(LP),
+
     /// ```text
     /// 3.3.9. MX RDATA format
     ///
@@ -394,6 +418,14 @@ pub enum RData {
     /// ```
     NAPTR(NAPTR),
 
+    /// [RFC 6742](https://www.rfc-editor.org/rfc/rfc6742#section-2.1.1)
+    ///
+    /// ```text
+    /// The RDATA for a NID RR consists of a 16-bit Preference field and a
+    /// 64-bit NodeID field.
+    /// ```
+    NID(NID),
+
     /// ```text
     /// 3.3.10. NULL RDATA format (EXPERIMENTAL)
     ///
@@ -741,8 +773,12 @@ impl RData {
             Self::CSYNC(..) => RecordType::CSYNC,
             Self::HINFO(..) => RecordType::HINFO,
             Self::HTTPS(..) => RecordType::HTTPS,
+            Self::L32(..) => RecordType::L32,
+            Self::L64(..) => RecordType::L64,
+            Self::LP(..) => RecordType::LP,
             Self::MX(..) => RecordType::MX,
             Self::NAPTR(..) => RecordType::NAPTR,
+            Self::NID(..) => RecordType::NID,
             Self::NS(..) => RecordType::NS,
             Self::NULL(..) => RecordType::NULL,
             Self::OPENPGPKEY(..) => RecordType::OPENPGPKEY,
@@ -776,6 +812,20 @@ impl RData {
         decoder: &mut BinDecoder<'_>,
         record_type: RecordType,
         length: Restrict<u16>,
+    ) -> ProtoResult<Self> {
+        // all reads of the rdata are bounded by the already validated rdata length
+        let mut section = decoder.read_section(length.unverified() as usize)?;
+        let result = Self::read_section(&mut section, record_type, length);
+
+        // names in the rdata count towards the decompression budget of the entire message
+        decoder.join(&section);
+        result
+    }
+
+    fn read_section(
+        decoder: &mut BinDecoder<'_>,
+        record_type: RecordType,
+        length: Restrict<u16>,
     ) -> ProtoResult<Self> {
         let start_idx = decoder.index();
 
@@ -819,6 +869,18 @@ impl RData {
                 trace!("reading HTTPS");
                 HTTPS::read_data(decoder, length).map(Self::HTTPS)
             }
+            RecordType::L32 => {
+                trace!("reading L32");
+                L32::read(decoder).map(Self::L32)
+            }
+            RecordType::L64 => {
+                trace!("reading L64");
+                L64::read(decoder).map(Self::L64)
+            }
+            RecordType::LP => {
+                trace!("reading LP");
+                LP::read(decoder).map(Self::LP)
+            }
             RecordType::ZERO => {
                 trace!("reading EMPTY");
                 // we should never get here, since ZERO should be 0 length, and None in the Record.
@@ -834,6 +896,10 @@ impl RData {
                 trace!("reading NAPTR");
                 NAPTR::read_data(decoder, length).map(Self::NAPTR)
             }
+            RecordType::NID => {
+                trace!("reading NID");
+                NID::read(decoder).map(Self::NID)
+            }
             RecordType::NULL => {
                 trace!("reading NULL");
                 NULL::read_data(decoder, length).map(Self::NULL)
@@ -891,6 +957,11 @@ impl RData {
 
         // we should have read rdata_length, but we did not
         let read = decoder.index() - start_idx;
+        if decoder.mode() == DecodeMode::Lenient {
+            // the section has already been consumed, unread trailing bytes are dropped
+            return result;
+        }
+
         length
             .map(|u| u as usize)
             .verify_unwrap(|rdata_length| read == *rdata_length)
@@ -986,9 +1057,13 @@ impl BinEncodable for RData {
             Self::CSYNC(csync) => csync.emit(encoder),
             Self::HINFO(hinfo) => hinfo.emit(encoder),
             Self::HTTPS(https) => https.emit(encoder),
+            Self::L32(l32) => l32.emit(encoder),
+            Self::L64(l64) => l64.emit(encoder),
+            Self::LP(lp) => lp.emit(encoder),
             Self::ZERO => Ok(()),
             Self::MX(mx) => mx.emit(encoder),
             Self::NAPTR(naptr) => naptr.emit(encoder),
+            Self::NID(nid) => nid.emit(encoder),
             Self::NULL(null) => null.emit(encoder),
             Self::OPENPGPKEY(openpgpkey) => openpgpkey.emit(encoder),
             Self::OPT(opt) => opt.emit(encoder),
@@ -1043,14 +1118,18 @@ impl fmt::Display for RData {
             Self::CSYNC(csync) => w(f, csync),
             Self::HINFO(hinfo) => w(f, hinfo),
             Self::HTTPS(https) => w(f, https),
+            Self::L32(l32) => w(f, l32),
+            Self::L64(l64) => w(f, l64),
+            Self::LP(lp) => w(f, lp),
             Self::ZERO => Ok(()),
             // to_lowercase for rfc4034 and rfc6840
             Self::MX(mx) => w(f, mx),
             Self::NAPTR(naptr) => w(f, naptr),
+            Self::NID(nid) => w(f, nid),
             Self::NULL(null) => w(f, null),
             Self::OPENPGPKEY(openpgpkey) => w(f, openpgpkey),
-            // Opt has no display representation
-            Self::OPT(_) => Err(fmt::Error),
+            // Opt has no presentation format
+            Self::OPT(opt) => null::write_generic(f, &opt.to_bytes().map_err(|_| fmt::Error)?),
             // to_lowercase for rfc4034 and rfc6840
             Self::SOA(soa) => w(f, soa),
             // to_lowercase for rfc4034 and rfc6840
@@ -1120,6 +1199,31 @@ impl From<Ipv6Addr> for RData {
     }
 }
 
+impl From<A> for RData {
+    fn from(a: A) -> Self {
+        RData::A(a)
+    }
+}
+
+impl From<AAAA> for RData {
+    fn from(aaaa: AAAA) -> Self {
+        RData::AAAA(aaaa)
+    }
+}
+
+/// Converts the data of an A or AAAA record to its address, any other data is returned as the error
+impl TryFrom<RData> for IpAddr {
+    type Error = RData;
+
+    fn try_from(rdata: RData) -> Result<Self, Self::Error> {
+        match rdata {
+            RData::A(a) => Ok(IpAddr::V4(a.0)),
+            RData::AAAA(aaaa) => Ok(IpAddr::V6(aaaa.0)),
+            rdata => Err(rdata),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
@@ -1214,6 +1318,23 @@ mod tests {
     }
 
     // TODO this test kinda sucks, shows the problem with not storing the binary parts
+    #[test]
+    fn test_ip_conversions() {
+        let v4 = Ipv4Addr::new(192, 0, 2, 1);
+        let v6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+        assert_eq!(RData::from(v4), RData::A(A(v4)));
+        assert_eq!(RData::from(v6), RData::AAAA(AAAA(v6)));
+        assert_eq!(RData::from(IpAddr::V4(v4)), RData::from(A(v4)));
+        assert_eq!(RData::from(IpAddr::V6(v6)), RData::from(AAAA(v6)));
+
+        assert_eq!(IpAddr::try_from(RData::from(v4)), Ok(IpAddr::V4(v4)));
+        assert_eq!(IpAddr::try_from(RData::from(v6)), Ok(IpAddr::V6(v6)));
+
+        let cname = RData::CNAME(CNAME(Name::from_str("www.example.com.").unwrap()));
+        assert_eq!(IpAddr::try_from(cname.clone()), Err(cname));
+    }
+
     #[test]
     fn test_order() {
         let ordered: Vec<RData> = vec![
@@ -1311,8 +1432,12 @@ mod tests {
             RData::CSYNC(..) => RecordType::CSYNC,
             RData::HINFO(..) => RecordType::HINFO,
             RData::HTTPS(..) => RecordType::HTTPS,
+            RData::L32(..) => RecordType::L32,
+            RData::L64(..) => RecordType::L64,
+            RData::LP(..) => RecordType::LP,
             RData::MX(..) => RecordType::MX,
             RData::NAPTR(..) => RecordType::NAPTR,
+            RData::NID(..) => RecordType::NID,
             RData::NS(..) => RecordType::NS,
             RData::NULL(..) => RecordType::NULL,
             RData::OPENPGPKEY(..) => RecordType::OPENPGPKEY,
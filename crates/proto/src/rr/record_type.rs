@@ -73,11 +73,19 @@ pub enum RecordType {
     //  KX,         // 36 RFC 2230 Key eXchanger record
     /// [RFC 2535](https://tools.ietf.org/html/rfc2535) and [RFC 2930](https://tools.ietf.org/html/rfc2930) Key record
     KEY,
+    /// [RFC 6742](https://tools.ietf.org/html/rfc6742) ILNP 32-bit Locator
+    L32,
+    /// [RFC 6742](https://tools.ietf.org/html/rfc6742) ILNP 64-bit Locator
+    L64,
     //  LOC,        // 29 RFC 1876 Location record
+    /// [RFC 6742](https://tools.ietf.org/html/rfc6742) ILNP Locator Pointer
+    LP,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) Mail exchange record
     MX,
     /// [RFC 3403](https://tools.ietf.org/html/rfc3403) Naming Authority Pointer
     NAPTR,
+    /// [RFC 6742](https://tools.ietf.org/html/rfc6742) ILNP Node Identifier
+    NID,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) Name server record
     NS,
     /// [RFC 4034](https://tools.ietf.org/html/rfc4034) Next-Secure record
@@ -108,7 +116,8 @@ pub enum RecordType {
     /// [RFC 9460](https://tools.ietf.org/html/rfc9460) DNS SVCB and HTTPS RRs
     SVCB,
     //  TA,         // 32768 N/A DNSSEC Trust Authorities
-    //  TKEY,       // 249 RFC 2930 Secret key record
+    /// [RFC 2930](https://tools.ietf.org/html/rfc2930) Secret key establishment
+    TKEY,
     /// [RFC 6698](https://tools.ietf.org/html/rfc6698) TLSA certificate association
     TLSA,
     /// [RFC 8945](https://tools.ietf.org/html/rfc8945) Transaction Signature
@@ -180,6 +189,7 @@ impl RecordType {
                 | Self::NSEC3PARAM
                 | Self::RRSIG
                 | Self::SIG
+                | Self::TKEY
                 | Self::TSIG
         )
     }
@@ -228,8 +238,12 @@ impl FromStr for RecordType {
             "HINFO" => Ok(Self::HINFO),
             "HTTPS" => Ok(Self::HTTPS),
             "KEY" => Ok(Self::KEY),
+            "L32" => Ok(Self::L32),
+            "L64" => Ok(Self::L64),
+            "LP" => Ok(Self::LP),
             "MX" => Ok(Self::MX),
             "NAPTR" => Ok(Self::NAPTR),
+            "NID" => Ok(Self::NID),
             "NSEC" => Ok(Self::NSEC),
             "NSEC3" => Ok(Self::NSEC3),
             "NSEC3PARAM" => Ok(Self::NSEC3PARAM),
@@ -245,9 +259,17 @@ impl FromStr for RecordType {
             "SVCB" => Ok(Self::SVCB),
             "TLSA" => Ok(Self::TLSA),
             "TXT" => Ok(Self::TXT),
+            "TKEY" => Ok(Self::TKEY),
             "TSIG" => Ok(Self::TSIG),
             "ANY" | "*" => Ok(Self::ANY),
-            _ => Err(ProtoErrorKind::UnknownRecordTypeStr(str.to_string()).into()),
+            "ZERO" => Ok(Self::ZERO),
+            // RFC 3597, the generic TYPE<code> form may be used for any type
+            _ => str
+                .strip_prefix("TYPE")
+                .filter(|code| !code.starts_with('+'))
+                .and_then(|code| code.parse::<u16>().ok())
+                .map(Self::from)
+                .ok_or_else(|| ProtoErrorKind::UnknownRecordTypeStr(str.to_string()).into()),
         }
     }
 }
@@ -281,8 +303,12 @@ impl From<u16> for RecordType {
             13 => Self::HINFO,
             65 => Self::HTTPS,
             25 => Self::KEY,
+            105 => Self::L32,
+            106 => Self::L64,
+            107 => Self::LP,
             15 => Self::MX,
             35 => Self::NAPTR,
+            104 => Self::NID,
             2 => Self::NS,
             47 => Self::NSEC,
             50 => Self::NSEC3,
@@ -298,6 +324,7 @@ impl From<u16> for RecordType {
             44 => Self::SSHFP,
             64 => Self::SVCB,
             52 => Self::TLSA,
+            249 => Self::TKEY,
             250 => Self::TSIG,
             16 => Self::TXT,
             0 => Self::ZERO,
@@ -356,9 +383,13 @@ impl From<RecordType> for &'static str {
             RecordType::HINFO => "HINFO",
             RecordType::HTTPS => "HTTPS",
             RecordType::KEY => "KEY",
+            RecordType::L32 => "L32",
+            RecordType::L64 => "L64",
+            RecordType::LP => "LP",
             RecordType::IXFR => "IXFR",
             RecordType::MX => "MX",
             RecordType::NAPTR => "NAPTR",
+            RecordType::NID => "NID",
             RecordType::NS => "NS",
             RecordType::NSEC => "NSEC",
             RecordType::NSEC3 => "NSEC3",
@@ -374,6 +405,7 @@ impl From<RecordType> for &'static str {
             RecordType::SSHFP => "SSHFP",
             RecordType::SVCB => "SVCB",
             RecordType::TLSA => "TLSA",
+            RecordType::TKEY => "TKEY",
             RecordType::TSIG => "TSIG",
             RecordType::TXT => "TXT",
             RecordType::ZERO => "ZERO",
@@ -410,9 +442,13 @@ impl From<RecordType> for u16 {
             RecordType::HINFO => 13,
             RecordType::HTTPS => 65,
             RecordType::KEY => 25,
+            RecordType::L32 => 105,
+            RecordType::L64 => 106,
+            RecordType::LP => 107,
             RecordType::IXFR => 251,
             RecordType::MX => 15,
             RecordType::NAPTR => 35,
+            RecordType::NID => 104,
             RecordType::NS => 2,
             RecordType::NSEC => 47,
             RecordType::NSEC3 => 50,
@@ -428,6 +464,7 @@ impl From<RecordType> for u16 {
             RecordType::SSHFP => 44,
             RecordType::SVCB => 64,
             RecordType::TLSA => 52,
+            RecordType::TKEY => 249,
             RecordType::TSIG => 250,
             RecordType::TXT => 16,
             RecordType::ZERO => 0,
@@ -451,8 +488,12 @@ impl Ord for RecordType {
 }
 
 impl Display for RecordType {
+    /// Unknown types are written in the generic `TYPE<code>` form of RFC 3597
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
-        f.write_str(Into::<&str>::into(*self))
+        match self {
+            Self::Unknown(code) => write!(f, "TYPE{code}"),
+            _ => f.write_str(Into::<&str>::into(*self)),
+        }
     }
 }
 
@@ -526,9 +567,13 @@ mod tests {
             "CNAME",
             "CSYNC",
             "HINFO",
+            "L32",
+            "L64",
+            "LP",
             "NULL",
             "MX",
             "NAPTR",
+            "NID",
             "NS",
             "OPENPGPKEY",
             "PTR",
@@ -553,6 +598,7 @@ mod tests {
             "NSEC3PARAM",
             "RRSIG",
             "SIG",
+            "TKEY",
             "TSIG",
         ];
         #[cfg(not(feature = "__dnssec"))]
@@ -569,6 +615,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_generic_record_type() {
+        assert_eq!("TYPE1".parse::<RecordType>().unwrap(), RecordType::A);
+        assert_eq!(
+            "TYPE65280".parse::<RecordType>().unwrap(),
+            RecordType::Unknown(65280)
+        );
+        assert_eq!(RecordType::Unknown(65280).to_string(), "TYPE65280");
+        assert!("TYPE".parse::<RecordType>().is_err());
+        assert!("TYPE+1".parse::<RecordType>().is_err());
+        assert!("TYPE65536".parse::<RecordType>().is_err());
+    }
+
     #[test]
     fn check_record_type_parse_wont_panic_with_symbols() {
         let dns_class = "a-b-c".to_ascii_uppercase().parse::<RecordType>();
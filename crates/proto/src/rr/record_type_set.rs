@@ -40,6 +40,7 @@ impl RecordTypeSet {
     }
 
     #[cfg(feature = "__dnssec")]
+    #[cfg_attr(not(any(feature = "std", feature = "no-std-rand")), expect(dead_code))]
     pub(crate) fn contains(&self, r#type: RecordType) -> bool {
         self.types.contains(&r#type)
     }
@@ -14,7 +14,7 @@ use core::{cmp::Ordering, convert::TryFrom, fmt};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    error::{ProtoError, ProtoErrorKind, ProtoResult},
+    error::{ProtoErrorKind, ProtoResult},
     rr::{Name, RData, RecordData, RecordSet, RecordType, dns_class::DNSClass},
     serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, Restrict},
 };
@@ -75,6 +75,7 @@ const MDNS_ENABLE_CACHE_FLUSH: u16 = 1 << 15;
 #[derive(Eq, Debug, Clone)]
 // TODO: make Record carry a lifetime for more efficient storage options in the future
 pub struct Record<R: RecordData = RData> {
+    /// The owner name of the record
     pub name_labels: Name,
     dns_class: DNSClass,
     ttl: u32,
@@ -471,12 +472,10 @@ impl<'r> BinDecodable<'r> for Record<RData> {
         let rd_length = decoder
             .read_u16()?
             .verify_unwrap(|u| (*u as usize) <= decoder.len())
-            .map_err(|u| {
-                ProtoError::from(format!(
-                    "rdata length too large for remaining bytes, need: {} remain: {}",
-                    u,
-                    decoder.len()
-                ))
+            .map_err(|u| ProtoErrorKind::LengthExceedsRemaining {
+                field: "rdata",
+                len: usize::from(u),
+                remaining: decoder.len(),
             })?;
 
         // this is to handle updates, RFC 2136, which uses 0 to indicate certain aspects of pre-requisites
@@ -10,16 +10,20 @@ use alloc::vec::Vec;
 use core::{iter::Chain, slice::Iter};
 use tracing::{info, warn};
 
-use crate::rr::{DNSClass, Name, RData, Record, RecordType};
+use crate::rr::{DNSClass, Name, RData, Record, RecordData, RecordType};
 
 /// Set of resource records associated to a name and type
+///
+/// # Generic type
+/// * `R` - the RecordData type of the records in this set, if unknown at runtime use the `RData`
+///   abstract enum type. Signatures are always stored as untyped `Record`s.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct RecordSet {
+pub struct RecordSet<R: RecordData = RData> {
     name: Name,
     record_type: RecordType,
     dns_class: DNSClass,
     ttl: u32,
-    records: Vec<Record>,
+    records: Vec<Record<R>>,
     rrsigs: Vec<Record>,
     serial: u32, // serial number at which this record was modified
 }
@@ -76,6 +80,113 @@ impl RecordSet {
         }
     }
 
+    /// Returns a Vec of all records in the set.
+    ///
+    /// # Arguments
+    ///
+    /// * `and_rrsigs` - if true, RRSIGs will be returned if they exist
+    #[cfg(feature = "__dnssec")]
+    pub fn records(&self, and_rrsigs: bool) -> RrsetRecords<'_> {
+        if and_rrsigs {
+            self.records_with_rrsigs()
+        } else {
+            self.records_without_rrsigs()
+        }
+    }
+
+    /// Returns a Vec of all records in the set, with RRSIGs, if present.
+    #[cfg(feature = "__dnssec")]
+    pub fn records_with_rrsigs(&self) -> RrsetRecords<'_> {
+        if self.records.is_empty() {
+            RrsetRecords::Empty
+        } else {
+            RrsetRecords::RecordsAndRrsigs(RecordsAndRrsigsIter(
+                self.records.iter().chain(self.rrsigs.iter()),
+            ))
+        }
+    }
+
+    /// Returns a Vec of all records in the set, without any RRSIGs.
+    pub fn records_without_rrsigs(&self) -> RrsetRecords<'_> {
+        if self.records.is_empty() {
+            RrsetRecords::Empty
+        } else {
+            RrsetRecords::RecordsOnly(self.records.iter())
+        }
+    }
+
+    /// Returns an iterator over the records in the set
+    #[deprecated(note = "see `records_without_rrsigs`")]
+    pub fn iter(&self) -> Iter<'_, Record> {
+        self.records.iter()
+    }
+
+    /// Converts this RecordSet into one of a more specific RecordData type
+    ///
+    /// Returns `None` if any of its records is not of type `N`.
+    pub fn try_into_typed<N: RecordData>(self) -> Option<RecordSet<N>> {
+        if self
+            .records
+            .iter()
+            .any(|r| N::try_borrow(r.data()).is_none())
+        {
+            return None;
+        }
+
+        Some(RecordSet {
+            name: self.name,
+            record_type: self.record_type,
+            dns_class: self.dns_class,
+            ttl: self.ttl,
+            records: self
+                .records
+                .into_iter()
+                .filter_map(|r| r.map(|rdata| N::try_borrow(&rdata).cloned()))
+                .collect(),
+            rrsigs: self.rrsigs,
+            serial: self.serial,
+        })
+    }
+}
+
+impl<R: RecordData> RecordSet<R> {
+    /// Creates a new Resource Record Set for records of a known `RecordData` type.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The label for the `RecordSet`
+    /// * `record_type` - `RecordType` of this `RecordSet`, all records in the `RecordSet` must be of the
+    ///   specified `RecordType`.
+    /// * `ttl` - time-to-live for the `RecordSet` in seconds.
+    ///
+    /// ```
+    /// use std::net::Ipv4Addr;
+    /// use std::str::FromStr;
+    /// use hickory_proto::rr::{Name, RecordSet, RecordType, rdata::A};
+    ///
+    /// let mut rrset = RecordSet::<A>::typed(Name::from_str("www.example.com.").unwrap(), RecordType::A, 300);
+    /// rrset.add_rdata(A(Ipv4Addr::new(192, 0, 2, 1)));
+    /// rrset.add_rdata(A(Ipv4Addr::new(192, 0, 2, 2)));
+    ///
+    /// let addresses = rrset.data().map(|a| a.0).collect::<Vec<_>>();
+    /// assert_eq!(addresses, [Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 2)]);
+    ///
+    /// // and back to a set of `RData`, e.g. to store it in an authority
+    /// let rrset = rrset.into_record_set_of_rdata();
+    /// assert_eq!(rrset.records_without_rrsigs().count(), 2);
+    /// ```
+    pub fn typed(name: Name, record_type: RecordType, ttl: u32) -> Self {
+        Self {
+            name,
+            record_type,
+            dns_class: DNSClass::IN,
+            ttl,
+            records: Vec::new(),
+            rrsigs: Vec::new(),
+            serial: 0,
+        }
+    }
+
     /// # Return value
     ///
     /// Label of the Resource Record Set
@@ -125,45 +236,14 @@ impl RecordSet {
         self.ttl
     }
 
-    /// Returns a Vec of all records in the set.
-    ///
-    /// # Arguments
-    ///
-    /// * `and_rrsigs` - if true, RRSIGs will be returned if they exist
-    #[cfg(feature = "__dnssec")]
-    pub fn records(&self, and_rrsigs: bool) -> RrsetRecords<'_> {
-        if and_rrsigs {
-            self.records_with_rrsigs()
-        } else {
-            self.records_without_rrsigs()
-        }
-    }
-
-    /// Returns a Vec of all records in the set, with RRSIGs, if present.
-    #[cfg(feature = "__dnssec")]
-    pub fn records_with_rrsigs(&self) -> RrsetRecords<'_> {
-        if self.records.is_empty() {
-            RrsetRecords::Empty
-        } else {
-            RrsetRecords::RecordsAndRrsigs(RecordsAndRrsigsIter(
-                self.records.iter().chain(self.rrsigs.iter()),
-            ))
-        }
-    }
-
-    /// Returns a Vec of all records in the set, without any RRSIGs.
-    pub fn records_without_rrsigs(&self) -> RrsetRecords<'_> {
-        if self.records.is_empty() {
-            RrsetRecords::Empty
-        } else {
-            RrsetRecords::RecordsOnly(self.records.iter())
-        }
+    /// Returns an iterator over the typed records in the set, without any RRSIGs.
+    pub fn typed_records(&self) -> Iter<'_, Record<R>> {
+        self.records.iter()
     }
 
-    /// Returns an iterator over the records in the set
-    #[deprecated(note = "see `records_without_rrsigs`")]
-    pub fn iter(&self) -> Iter<'_, Record> {
-        self.records.iter()
+    /// Returns an iterator over the record data of the records in the set
+    pub fn data(&self) -> impl Iterator<Item = &R> {
+        self.records.iter().map(Record::data)
     }
 
     /// Returns true if there are no records in this set
@@ -205,7 +285,7 @@ impl RecordSet {
     /// creates a new Record as part of this RecordSet, adding the associated RData
     ///
     /// this interface may be deprecated in the future.
-    pub fn new_record(&mut self, rdata: &RData) -> &Record {
+    pub fn new_record(&mut self, rdata: &R) -> &Record<R> {
         self.add_rdata(rdata.clone());
 
         self.records
@@ -215,7 +295,7 @@ impl RecordSet {
     }
 
     /// creates a new Record as part of this RecordSet, adding the associated RData
-    pub fn add_rdata(&mut self, rdata: RData) -> bool {
+    pub fn add_rdata(&mut self, rdata: R) -> bool {
         debug_assert_eq!(self.record_type, rdata.record_type());
 
         let record = Record::from_rdata(self.name.clone(), self.ttl, rdata);
@@ -255,7 +335,7 @@ impl RecordSet {
     /// True if the record was inserted.
     ///
     /// TODO: make a default add without serial number for basic usage
-    pub fn insert(&mut self, record: Record, serial: u32) -> bool {
+    pub fn insert(&mut self, record: Record<R>, serial: u32) -> bool {
         assert_eq!(record.name(), &self.name);
         assert_eq!(record.record_type(), self.record_type);
 
@@ -272,9 +352,10 @@ impl RecordSet {
                 assert!(self.records.len() <= 1);
 
                 if let Some(soa_record) = self.records.first() {
-                    match soa_record.data() {
+                    // the serials can only be compared on the generic RData
+                    match soa_record.data().clone().into_rdata() {
                         RData::SOA(existing_soa) => {
-                            if let RData::SOA(new_soa) = record.data() {
+                            if let RData::SOA(new_soa) = record.data().clone().into_rdata() {
                                 if new_soa.serial() <= existing_soa.serial() {
                                     info!(
                                         "update ignored serial out of data: {:?} <= {:?}",
@@ -380,7 +461,7 @@ impl RecordSet {
     /// # Return value
     ///
     /// True if a record was removed.
-    pub fn remove(&mut self, record: &Record, serial: u32) -> bool {
+    pub fn remove(&mut self, record: &Record<R>, serial: u32) -> bool {
         assert_eq!(record.name(), &self.name);
         assert!(
             record.record_type() == self.record_type || record.record_type() == RecordType::ANY
@@ -388,11 +469,9 @@ impl RecordSet {
 
         match record.record_type() {
             // never delete the last NS record
-            RecordType::NS => {
-                if self.records.len() <= 1 {
-                    info!("ignoring delete of last NS record: {:?}", record);
-                    return false;
-                }
+            RecordType::NS if self.records.len() <= 1 => {
+                info!("ignoring delete of last NS record: {:?}", record);
+                return false;
             }
             // never delete SOA
             RecordType::SOA => {
@@ -422,26 +501,43 @@ impl RecordSet {
     }
 
     /// Consumes `RecordSet` and returns its components
-    pub fn into_parts(self) -> RecordSetParts {
+    pub fn into_parts(self) -> RecordSetParts<R> {
         self.into()
     }
+
+    /// Converts this RecordSet into a generic RecordSet of RData
+    pub fn into_record_set_of_rdata(self) -> RecordSet {
+        RecordSet {
+            name: self.name,
+            record_type: self.record_type,
+            dns_class: self.dns_class,
+            ttl: self.ttl,
+            records: self
+                .records
+                .into_iter()
+                .map(Record::into_record_of_rdata)
+                .collect(),
+            rrsigs: self.rrsigs,
+            serial: self.serial,
+        }
+    }
 }
 
 /// Consumes `RecordSet` giving public access to fields of `RecordSet` so they can
 /// be destructured and taken by value
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct RecordSetParts {
+pub struct RecordSetParts<R: RecordData = RData> {
     pub name: Name,
     pub record_type: RecordType,
     pub dns_class: DNSClass,
     pub ttl: u32,
-    pub records: Vec<Record>,
+    pub records: Vec<Record<R>>,
     pub rrsigs: Vec<Record>,
     pub serial: u32, // serial number at which this record was modified,
 }
 
-impl From<RecordSet> for RecordSetParts {
-    fn from(rset: RecordSet) -> Self {
+impl<R: RecordData> From<RecordSet<R>> for RecordSetParts<R> {
+    fn from(rset: RecordSet<R>) -> Self {
         let RecordSet {
             name,
             record_type,
@@ -463,8 +559,8 @@ impl From<RecordSet> for RecordSetParts {
     }
 }
 
-impl From<Record> for RecordSet {
-    fn from(record: Record) -> Self {
+impl<R: RecordData> From<Record<R>> for RecordSet<R> {
+    fn from(record: Record<R>) -> Self {
         Self {
             name: record.name().clone(),
             record_type: record.record_type(),
@@ -548,13 +644,14 @@ impl<'r> Iterator for RrsetRecords<'r> {
 
 #[cfg(test)]
 mod test {
+    use alloc::vec::Vec;
     #[cfg(not(feature = "std"))]
     use core::net::Ipv4Addr;
     use core::str::FromStr;
     #[cfg(feature = "std")]
     use std::net::Ipv4Addr;
 
-    use crate::rr::rdata::{CNAME, NS, SOA};
+    use crate::rr::rdata::{A, CNAME, NS, SOA};
     use crate::rr::*;
 
     #[test]
@@ -780,4 +877,63 @@ mod test {
         assert!(rr_set.remove(&ns2, 0));
         assert!(!rr_set.remove(&ns1, 0));
     }
+
+    #[test]
+    fn test_typed() {
+        let name = Name::from_str("www.example.com.").unwrap();
+        let mut typed = RecordSet::<A>::typed(name.clone(), RecordType::A, 86400);
+
+        assert!(typed.add_rdata(A::new(93, 184, 216, 24)));
+        assert!(!typed.add_rdata(A::new(93, 184, 216, 24)));
+        assert!(typed.insert(
+            Record::from_rdata(name.clone(), 3600, A::new(93, 184, 216, 25)),
+            1
+        ));
+        assert_eq!(typed.ttl(), 3600);
+        assert_eq!(typed.serial(), 1);
+        assert_eq!(
+            typed.data().copied().collect::<Vec<_>>(),
+            [A::new(93, 184, 216, 24), A::new(93, 184, 216, 25)]
+        );
+
+        let untyped = typed.clone().into_record_set_of_rdata();
+        assert_eq!(untyped.records_without_rrsigs().count(), 2);
+        assert!(
+            untyped
+                .records_without_rrsigs()
+                .all(|r| matches!(r.data(), RData::A(_)))
+        );
+
+        let typed_again = untyped.clone().try_into_typed::<A>().unwrap();
+        assert_eq!(typed_again, typed);
+
+        // the records are not NS
+        assert!(untyped.try_into_typed::<NS>().is_none());
+    }
+
+    #[test]
+    #[allow(clippy::unreadable_literal)]
+    fn test_typed_soa() {
+        let name = Name::from_str("example.com.").unwrap();
+        let soa = |serial| {
+            SOA::new(
+                Name::from_str("sns.dns.icann.org.").unwrap(),
+                Name::from_str("noc.dns.icann.org.").unwrap(),
+                serial,
+                7200,
+                3600,
+                1209600,
+                3600,
+            )
+        };
+
+        let mut rr_set = RecordSet::<SOA>::typed(name, RecordType::SOA, 3600);
+        assert!(rr_set.add_rdata(soa(2015082403)));
+        assert!(!rr_set.add_rdata(soa(2015082402)));
+        assert!(rr_set.add_rdata(soa(2015082404)));
+        assert_eq!(
+            rr_set.data().map(SOA::serial).collect::<Vec<_>>(),
+            [2015082404]
+        );
+    }
 }
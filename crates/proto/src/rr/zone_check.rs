@@ -0,0 +1,436 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Semantic checks of the contents of a zone
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::rr::{LowerName, Name, Record, RecordType};
+
+/// Checks the records of the zone at `origin` for semantic errors, e.g. a CNAME next to other
+/// data, and for records which will never be served, e.g. data below a delegation point
+///
+/// The issues are returned in canonical order of their owner names; errors make the zone
+/// unusable, see [`ZoneIssue::severity()`].
+pub fn check_zone<'a>(
+    origin: &Name,
+    records: impl IntoIterator<Item = &'a Record>,
+) -> Vec<ZoneIssue> {
+    // the origin of a zone is always absolute, even if it was not configured as such
+    let mut origin = origin.clone();
+    origin.set_fqdn(true);
+    let origin = LowerName::new(&origin);
+
+    // the number of records per type, at each name
+    let mut names = BTreeMap::<LowerName, BTreeMap<RecordType, usize>>::new();
+    for record in records {
+        *names
+            .entry(LowerName::new(record.name()))
+            .or_default()
+            .entry(record.record_type())
+            .or_default() += 1;
+    }
+
+    let delegations = names
+        .iter()
+        .filter(|(name, types)| **name != origin && types.contains_key(&RecordType::NS))
+        .map(|(name, _)| name.clone())
+        .collect::<BTreeSet<_>>();
+
+    let mut issues = Vec::new();
+    match names.get(&origin) {
+        Some(types) => {
+            match types.get(&RecordType::SOA) {
+                None => issues.push(ZoneIssue::MissingSoa),
+                Some(1) => {}
+                Some(&count) => issues.push(ZoneIssue::MultipleSoa { count }),
+            }
+
+            if !types.contains_key(&RecordType::NS) {
+                issues.push(ZoneIssue::MissingApexNs);
+            }
+        }
+        None => issues.extend([ZoneIssue::MissingSoa, ZoneIssue::MissingApexNs]),
+    }
+
+    for (name, types) in &names {
+        if !origin.zone_of(name) {
+            issues.push(ZoneIssue::OutOfZone { name: name.into() });
+            continue;
+        }
+
+        if *name != origin && types.contains_key(&RecordType::SOA) {
+            issues.push(ZoneIssue::SoaOutsideApex { name: name.into() });
+        }
+
+        if let Some(&count) = types.get(&RecordType::CNAME) {
+            if count > 1 {
+                issues.push(ZoneIssue::MultipleCnames {
+                    name: name.into(),
+                    count,
+                });
+            }
+
+            issues.extend(
+                types
+                    .keys()
+                    .filter(|ty| {
+                        !matches!(ty, RecordType::CNAME | RecordType::RRSIG | RecordType::NSEC)
+                    })
+                    .map(|&record_type| ZoneIssue::CnameAndOtherData {
+                        name: name.into(),
+                        record_type,
+                    }),
+            );
+        }
+
+        let Some(cut) = highest_cut(name, &origin, &delegations) else {
+            continue;
+        };
+
+        issues.extend(
+            types
+                .keys()
+                .filter(|ty| !allowed_at_or_below_cut(name == &cut, **ty))
+                .map(|&record_type| ZoneIssue::Occluded {
+                    name: name.into(),
+                    record_type,
+                    cut: (&cut).into(),
+                }),
+        );
+    }
+
+    issues
+}
+
+/// Returns the highest delegation point at or above `name`, which occludes the records below it
+fn highest_cut(
+    name: &LowerName,
+    origin: &LowerName,
+    delegations: &BTreeSet<LowerName>,
+) -> Option<LowerName> {
+    let mut cut = None;
+    let mut current = name.clone();
+    while current != *origin && !current.is_root() {
+        if delegations.contains(&current) {
+            cut = Some(current.clone());
+        }
+
+        current = current.base_name();
+    }
+
+    cut
+}
+
+fn allowed_at_or_below_cut(at_cut: bool, record_type: RecordType) -> bool {
+    match at_cut {
+        // the delegation itself, its DS RRset and the denial of existence, as well as glue
+        true => matches!(
+            record_type,
+            RecordType::NS
+                | RecordType::DS
+                | RecordType::NSEC
+                | RecordType::RRSIG
+                | RecordType::A
+                | RecordType::AAAA
+        ),
+        // glue
+        false => matches!(record_type, RecordType::A | RecordType::AAAA),
+    }
+}
+
+/// An issue found by [`check_zone()`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ZoneIssue {
+    /// There is no SOA record at the apex of the zone
+    MissingSoa,
+    /// There is more than one SOA record at the apex of the zone
+    MultipleSoa {
+        /// The number of SOA records
+        count: usize,
+    },
+    /// There is an SOA record below the apex of the zone
+    SoaOutsideApex {
+        /// The owner of the SOA record
+        name: Name,
+    },
+    /// There is no NS RRset at the apex of the zone
+    MissingApexNs,
+    /// There is a CNAME record and data of another type at the same name
+    CnameAndOtherData {
+        /// The owner of the records
+        name: Name,
+        /// The type of the other data
+        record_type: RecordType,
+    },
+    /// There is more than one CNAME record at the same name
+    MultipleCnames {
+        /// The owner of the CNAME records
+        name: Name,
+        /// The number of CNAME records
+        count: usize,
+    },
+    /// The records are at or below a delegation point, and thus never served
+    Occluded {
+        /// The owner of the records
+        name: Name,
+        /// The type of the records
+        record_type: RecordType,
+        /// The owner of the NS RRset which occludes the records
+        cut: Name,
+    },
+    /// The records are not within the zone
+    OutOfZone {
+        /// The owner of the records
+        name: Name,
+    },
+}
+
+impl ZoneIssue {
+    /// The severity of the issue
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::MissingSoa
+            | Self::MultipleSoa { .. }
+            | Self::SoaOutsideApex { .. }
+            | Self::CnameAndOtherData { .. }
+            | Self::MultipleCnames { .. } => Severity::Error,
+            Self::MissingApexNs | Self::Occluded { .. } | Self::OutOfZone { .. } => {
+                Severity::Warning
+            }
+        }
+    }
+
+    /// Returns `true` if the issue makes the zone unusable
+    pub fn is_error(&self) -> bool {
+        self.severity() == Severity::Error
+    }
+}
+
+impl fmt::Display for ZoneIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSoa => write!(f, "no SOA record at the zone apex"),
+            Self::MultipleSoa { count } => write!(f, "{count} SOA records at the zone apex"),
+            Self::SoaOutsideApex { name } => write!(f, "{name}: SOA record below the zone apex"),
+            Self::MissingApexNs => write!(f, "no NS records at the zone apex"),
+            Self::CnameAndOtherData { name, record_type } => {
+                write!(
+                    f,
+                    "{name}: CNAME and {record_type} records at the same name"
+                )
+            }
+            Self::MultipleCnames { name, count } => write!(f, "{name}: {count} CNAME records"),
+            Self::Occluded {
+                name,
+                record_type,
+                cut,
+            } => write!(f, "{name}: {record_type} records occluded by {cut}"),
+            Self::OutOfZone { name } => write!(f, "{name}: records outside of the zone"),
+        }
+    }
+}
+
+/// The severity of a [`ZoneIssue`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The records are never served, or the zone might not be served as intended
+    Warning,
+    /// The zone is invalid
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use core::net::Ipv4Addr;
+    use core::str::FromStr;
+
+    use super::*;
+    use crate::rr::RData;
+    use crate::rr::rdata::{A, CNAME, NS, SOA, TXT};
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn record(owner: &str, rdata: RData) -> Record {
+        Record::from_rdata(name(owner), 3600, rdata)
+    }
+
+    fn soa() -> Record {
+        record(
+            "example.com.",
+            RData::SOA(SOA::new(
+                name("ns1.example.com."),
+                name("admin.example.com."),
+                1,
+                3600,
+                600,
+                86400,
+                300,
+            )),
+        )
+    }
+
+    fn ns(owner: &str, target: &str) -> Record {
+        record(owner, RData::NS(NS(name(target))))
+    }
+
+    fn a(owner: &str) -> Record {
+        record(owner, RData::A(A(Ipv4Addr::new(192, 0, 2, 1))))
+    }
+
+    fn txt(owner: &str) -> Record {
+        record(owner, RData::TXT(TXT::new(vec!["txt".into()])))
+    }
+
+    fn check(records: &[Record]) -> Vec<ZoneIssue> {
+        check_zone(&name("example.com."), records)
+    }
+
+    fn valid_zone() -> Vec<Record> {
+        vec![
+            soa(),
+            ns("example.com.", "ns1.example.com."),
+            a("ns1.example.com."),
+            a("www.example.com."),
+            record(
+                "alias.example.com.",
+                RData::CNAME(CNAME(name("www.example.com."))),
+            ),
+            // delegation with in-bailiwick glue
+            ns("sub.example.com.", "ns.sub.example.com."),
+            a("ns.sub.example.com."),
+        ]
+    }
+
+    #[test]
+    fn test_valid_zone() {
+        assert_eq!(check(&valid_zone()), []);
+        assert_eq!(check_zone(&name("example.com"), &valid_zone()), []);
+    }
+
+    #[test]
+    fn test_apex() {
+        let mut records = valid_zone();
+        records.retain(|record| record.record_type() != RecordType::NS);
+        records.push(soa());
+
+        let issues = check(&records);
+        assert_eq!(
+            issues,
+            [
+                ZoneIssue::MultipleSoa { count: 2 },
+                ZoneIssue::MissingApexNs
+            ]
+        );
+        assert!(issues[0].is_error());
+        assert!(!issues[1].is_error());
+
+        let issues = check(&[a("www.example.com.")]);
+        assert_eq!(issues, [ZoneIssue::MissingSoa, ZoneIssue::MissingApexNs]);
+    }
+
+    #[test]
+    fn test_cname_and_other_data() {
+        let mut records = valid_zone();
+        records.push(txt("alias.example.com."));
+        records.push(record(
+            "alias.example.com.",
+            RData::CNAME(CNAME(name("other.example.com."))),
+        ));
+
+        let issues = check(&records);
+        assert_eq!(
+            issues,
+            [
+                ZoneIssue::MultipleCnames {
+                    name: name("alias.example.com."),
+                    count: 2,
+                },
+                ZoneIssue::CnameAndOtherData {
+                    name: name("alias.example.com."),
+                    record_type: RecordType::TXT,
+                },
+            ]
+        );
+        assert!(issues.iter().all(ZoneIssue::is_error));
+        assert_eq!(
+            issues[1].to_string(),
+            "alias.example.com.: CNAME and TXT records at the same name"
+        );
+    }
+
+    #[test]
+    fn test_occluded() {
+        let mut records = valid_zone();
+        records.push(txt("sub.example.com."));
+        records.push(txt("deep.ns.sub.example.com."));
+        records.push(a("www.sub.example.com."));
+        // nested delegation
+        records.push(ns("deeper.sub.example.com.", "ns.sub.example.com."));
+
+        let issues = check(&records);
+        assert_eq!(
+            issues,
+            [
+                ZoneIssue::Occluded {
+                    name: name("sub.example.com."),
+                    record_type: RecordType::TXT,
+                    cut: name("sub.example.com."),
+                },
+                ZoneIssue::Occluded {
+                    name: name("deeper.sub.example.com."),
+                    record_type: RecordType::NS,
+                    cut: name("sub.example.com."),
+                },
+                ZoneIssue::Occluded {
+                    name: name("deep.ns.sub.example.com."),
+                    record_type: RecordType::TXT,
+                    cut: name("sub.example.com."),
+                },
+            ]
+        );
+        assert!(!issues.iter().any(ZoneIssue::is_error));
+    }
+
+    #[test]
+    fn test_out_of_zone() {
+        let mut records = valid_zone();
+        records.push(a("www.example.net."));
+        records.push(soa());
+        records
+            .last_mut()
+            .unwrap()
+            .set_name(name("sub2.example.com."));
+
+        assert_eq!(
+            check(&records),
+            [
+                ZoneIssue::SoaOutsideApex {
+                    name: name("sub2.example.com."),
+                },
+                ZoneIssue::OutOfZone {
+                    name: name("www.example.net."),
+                },
+            ]
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Abstractions to deal with different async runtimes.
 
 use alloc::boxed::Box;
+use alloc::string::String;
 #[cfg(feature = "__quic")]
 use alloc::sync::Arc;
 use core::future::Future;
@@ -83,7 +84,7 @@ pub mod iocompat {
             buf: &mut ReadBuf<'_>,
         ) -> Poll<io::Result<()>> {
             Pin::new(&mut self.get_mut().0)
-                .poll_read(cx, buf.initialized_mut())
+                .poll_read(cx, buf.initialize_unfilled())
                 .map_ok(|len| buf.advance(len))
         }
     }
@@ -119,6 +120,7 @@ mod tokio_runtime {
     use futures_util::FutureExt;
     #[cfg(feature = "__quic")]
     use quinn::Runtime;
+    use socket2::{Domain, Protocol, SockRef, Socket, Type};
     use tokio::net::{TcpSocket, TcpStream, UdpSocket as TokioUdpSocket};
     use tokio::task::JoinSet;
     use tokio::time::timeout;
@@ -146,13 +148,22 @@ mod tokio_runtime {
 
     /// The Tokio Runtime for async execution
     #[derive(Clone, Default)]
-    pub struct TokioRuntimeProvider(TokioHandle);
+    pub struct TokioRuntimeProvider {
+        handle: TokioHandle,
+        socket_options: Arc<SocketOptions>,
+    }
 
     impl TokioRuntimeProvider {
         /// Create a Tokio runtime
         pub fn new() -> Self {
             Self::default()
         }
+
+        /// Sets the options to apply to all TCP and UDP sockets created by this runtime
+        pub fn with_socket_options(mut self, socket_options: SocketOptions) -> Self {
+            self.socket_options = Arc::new(socket_options);
+            self
+        }
     }
 
     impl RuntimeProvider for TokioRuntimeProvider {
@@ -162,7 +173,7 @@ mod tokio_runtime {
         type Tcp = AsyncIoTokioAsStd<TcpStream>;
 
         fn create_handle(&self) -> Self::Handle {
-            self.0.clone()
+            self.handle.clone()
         }
 
         fn connect_tcp(
@@ -171,11 +182,13 @@ mod tokio_runtime {
             bind_addr: Option<SocketAddr>,
             wait_for: Option<Duration>,
         ) -> Pin<Box<dyn Send + Future<Output = io::Result<Self::Tcp>>>> {
+            let socket_options = self.socket_options.clone();
             Box::pin(async move {
                 let socket = match server_addr {
                     SocketAddr::V4(_) => TcpSocket::new_v4(),
                     SocketAddr::V6(_) => TcpSocket::new_v6(),
                 }?;
+                socket_options.apply(SockRef::from(&socket), server_addr.is_ipv6())?;
 
                 if let Some(bind_addr) = bind_addr {
                     socket.bind(bind_addr)?;
@@ -200,7 +213,22 @@ mod tokio_runtime {
             local_addr: SocketAddr,
             _server_addr: SocketAddr,
         ) -> Pin<Box<dyn Send + Future<Output = io::Result<Self::Udp>>>> {
-            Box::pin(tokio::net::UdpSocket::bind(local_addr))
+            if self.socket_options.is_empty() {
+                return Box::pin(tokio::net::UdpSocket::bind(local_addr));
+            }
+
+            let socket_options = self.socket_options.clone();
+            Box::pin(async move {
+                let socket = Socket::new(
+                    Domain::for_address(local_addr),
+                    Type::DGRAM,
+                    Some(Protocol::UDP),
+                )?;
+                socket_options.apply(SockRef::from(&socket), local_addr.is_ipv6())?;
+                socket.set_nonblocking(true)?;
+                socket.bind(&local_addr.into())?;
+                TokioUdpSocket::from_std(socket.into())
+            })
         }
 
         #[cfg(feature = "__quic")]
@@ -236,6 +264,75 @@ mod tokio_runtime {
 #[cfg(feature = "tokio")]
 pub use tokio_runtime::{TokioHandle, TokioRuntimeProvider};
 
+/// Options applied to the sockets of outgoing connections, for multi-homed hosts and policy routing
+///
+/// The options are only supported on Linux, Android and Fuchsia. Elsewhere, creating a socket
+/// with any of them set fails with [`io::ErrorKind::Unsupported`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SocketOptions {
+    /// Binds sockets to the network interface with this name (`SO_BINDTODEVICE`)
+    pub bind_device: Option<String>,
+    /// Sets the mark of the packets sent through sockets, for policy routing (`SO_MARK`)
+    pub mark: Option<u32>,
+    /// Sets the differentiated services code point of the packets sent through sockets, which is
+    /// the upper 6 bits of the `IP_TOS` or `IPV6_TCLASS` option
+    pub dscp: Option<u8>,
+}
+
+impl SocketOptions {
+    /// Returns true if no option is set
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Applies the options to `socket`, `ipv6` selects the IP level options to set
+    #[cfg(all(
+        feature = "tokio",
+        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
+    ))]
+    fn apply(&self, socket: socket2::SockRef<'_>, ipv6: bool) -> io::Result<()> {
+        if let Some(device) = &self.bind_device {
+            socket.bind_device(Some(device.as_bytes()))?;
+        }
+
+        if let Some(mark) = self.mark {
+            socket.set_mark(mark)?;
+        }
+
+        if let Some(dscp) = self.dscp {
+            if dscp > 0x3F {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("DSCP out of range: {dscp}"),
+                ));
+            }
+
+            let tos = u32::from(dscp) << 2;
+            match ipv6 {
+                true => socket.set_tclass_v6(tos)?,
+                false => socket.set_tos(tos)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(all(
+        feature = "tokio",
+        not(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))
+    ))]
+    fn apply(&self, _socket: socket2::SockRef<'_>, _ipv6: bool) -> io::Result<()> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "socket options are not supported on this platform",
+            )),
+        }
+    }
+}
+
 /// RuntimeProvider defines which async runtime that handles IO and timers.
 pub trait RuntimeProvider: Clone + Send + Sync + Unpin + 'static {
     /// Handle to the executor;
@@ -12,7 +12,8 @@ use alloc::sync::Arc;
 #[cfg(not(feature = "rustls-platform-verifier"))]
 use rustls::RootCertStore;
 use rustls::{
-    ClientConfig,
+    ClientConfig, ConfigBuilder,
+    client::WantsClientCert,
     crypto::{self, CryptoProvider},
 };
 #[cfg(feature = "rustls-platform-verifier")]
@@ -28,6 +29,12 @@ pub use self::tls_stream::{TlsStream, tls_connect, tls_connect_with_bind_addr, t
 
 /// Make a new [`ClientConfig`] with the default settings
 pub fn client_config() -> ClientConfig {
+    client_config_builder().with_no_client_auth()
+}
+
+/// Make a new [`ClientConfig`] builder which verifies servers with the default roots, leaving the
+/// client authentication to be chosen
+pub fn client_config_builder() -> ConfigBuilder<ClientConfig, WantsClientCert> {
     let builder = ClientConfig::builder_with_provider(Arc::new(default_provider()))
         .with_safe_default_protocol_versions()
         .unwrap();
@@ -43,7 +50,7 @@ pub fn client_config() -> ClientConfig {
         root_store
     });
 
-    builder.with_no_client_auth()
+    builder
 }
 
 /// Instantiate a new [`CryptoProvider`] for use with rustls
@@ -7,9 +7,10 @@
 
 //! DNS over TLS I/O stream implementation for Rustls
 
+use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
 use alloc::sync::Arc;
-use core::future::Future;
+use core::future::{self, Future};
 use core::pin::Pin;
 use std::io;
 use std::net::SocketAddr;
@@ -18,11 +19,12 @@ use rustls::ClientConfig;
 use rustls::pki_types::ServerName;
 use tokio::net::TcpStream as TokioTcpStream;
 use tokio::{self, time::timeout};
-use tokio_rustls::TlsConnector;
+use tokio_rustls::{TlsAcceptor, TlsConnector};
 
 use crate::runtime::RuntimeProvider;
 use crate::runtime::iocompat::{AsyncIoStdAsTokio, AsyncIoTokioAsStd};
 use crate::tcp::{DnsTcpStream, TcpStream};
+use crate::tls::{TlsAccept, TlsConnect, TlsFuture};
 use crate::xfer::{BufDnsStreamHandle, CONNECT_TIMEOUT, StreamReceiver};
 
 /// Predefined type for abstracting the TlsClientStream with TokioTls
@@ -34,16 +36,39 @@ pub type TokioTlsServerStream = tokio_rustls::server::TlsStream<TokioTcpStream>;
 /// Predefined type for abstracting the base I/O TlsStream with TokioTls
 pub type TlsStream<S> = TcpStream<S>;
 
-/// Initializes a TlsStream with an existing tokio_tls::TlsStream.
-///
-/// This is intended for use with a TlsListener and Incoming connections
-pub fn tls_from_stream<S: DnsTcpStream>(
-    stream: S,
-    peer_addr: SocketAddr,
-) -> (TlsStream<S>, BufDnsStreamHandle) {
-    let (message_sender, outbound_messages) = BufDnsStreamHandle::new(peer_addr);
-    let stream = TcpStream::from_stream_with_receiver(stream, peer_addr, outbound_messages);
-    (stream, message_sender)
+pub use crate::tls::tls_from_stream;
+
+impl TlsConnect for TlsConnector {
+    type Stream<S: DnsTcpStream> = AsyncIoTokioAsStd<TokioTlsClientStream<S>>;
+
+    fn connect<S: DnsTcpStream>(&self, server_name: &str, stream: S) -> TlsFuture<Self::Stream<S>> {
+        let server_name = match ServerName::try_from(server_name.to_owned()) {
+            Ok(server_name) => server_name,
+            Err(e) => {
+                return Box::pin(future::ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    e,
+                ))));
+            }
+        };
+
+        let connect = Self::connect(self, server_name, AsyncIoStdAsTokio(stream));
+        Box::pin(async move { connect.await.map(AsyncIoTokioAsStd) })
+    }
+}
+
+impl TlsAccept for TlsAcceptor {
+    type Stream = AsyncIoTokioAsStd<TokioTlsServerStream>;
+
+    fn accept(&self, stream: TokioTcpStream) -> TlsFuture<Self::Stream> {
+        let accept = Self::accept(self, stream);
+        Box::pin(async move { accept.await.map(AsyncIoTokioAsStd) })
+    }
+
+    fn is_client_authenticated(stream: &Self::Stream) -> bool {
+        // the client certificate verifier only accepts verified certificates
+        stream.0.get_ref().1.peer_certificates().is_some()
+    }
 }
 
 /// Creates a new TlsStream to the specified name_server
@@ -15,7 +15,10 @@
  */
 
 use alloc::{borrow::ToOwned, vec::Vec};
+use core::fmt;
 
+#[cfg(feature = "serde")]
+use serde::Deserialize;
 use thiserror::Error;
 
 use crate::serialize::binary::Restrict;
@@ -30,6 +33,87 @@ use crate::serialize::binary::Restrict;
 pub struct BinDecoder<'a> {
     buffer: &'a [u8],    // The entire original buffer
     remaining: &'a [u8], // The unread section of the original buffer, so that reads do not cause a bounds check at the current seek offset
+    mode: DecodeMode,
+    limits: DecompressionLimits,
+    expanded: usize, // Bytes of names read through compression pointers, over the entire message
+}
+
+/// How strictly malformed or non-conforming data is handled while decoding
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DecodeMode {
+    /// Enforce the RFCs, in addition to the checks of `Normal`, bytes following the end of a
+    ///  message are rejected
+    Strict,
+    /// Reject malformed data, but ignore bytes following the end of a message
+    #[default]
+    Normal,
+    /// Tolerate broken packets as seen in the wild, e.g. when passively analyzing traffic: section
+    ///  counts larger than the records present stop decoding at the end of the buffer, and unread
+    ///  bytes at the end of RDATA are skipped
+    Lenient,
+}
+
+/// Limits on the work done to decompress the names of a message
+///
+/// Compression pointers can only point to data prior to the name, so they cannot loop, but a
+///  crafted message can still chain many pointers, or point at the same long names over and over
+///  again, to make a small packet expand into a large amount of name data. Exceeding one of these
+///  limits fails decoding with [`DecodeError::DecompressionLimit`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+#[non_exhaustive]
+pub struct DecompressionLimits {
+    /// Maximum number of compression pointers followed while reading a single name
+    ///
+    /// A name has at most 127 labels and an encoder adds at least one label per pointer, so the
+    ///  default of 127 does not reject any sensibly compressed name.
+    pub max_pointer_jumps: u8,
+    /// Maximum length of a single name in its uncompressed wire format, names are never longer
+    ///  than 255 bytes regardless of this limit
+    pub max_name_length: u8,
+    /// Maximum number of bytes read through compression pointers for all names of a message,
+    ///  defaults to 1 MiB
+    pub max_expansion: usize,
+}
+
+impl Default for DecompressionLimits {
+    fn default() -> Self {
+        Self {
+            max_pointer_jumps: 127,
+            max_name_length: 255,
+            max_expansion: 1 << 20,
+        }
+    }
+}
+
+/// The limit of [`DecompressionLimits`] that was exceeded
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum DecompressionLimit {
+    /// Too many compression pointers were followed for a single name
+    PointerJumps,
+    /// A name was longer than permitted
+    NameLength,
+    /// Too many bytes were read through compression pointers for the message
+    Expansion,
+}
+
+impl DecompressionLimit {
+    /// Returns a short, stable identifier of the limit, e.g. for metrics labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PointerJumps => "pointer_jumps",
+            Self::NameLength => "name_length",
+            Self::Expansion => "expansion",
+        }
+    }
+}
+
+impl fmt::Display for DecompressionLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 pub(crate) type DecodeResult<T> = Result<T, DecodeError>;
@@ -76,6 +160,10 @@ pub enum DecodeError {
         /// Start of the other label
         other: usize,
     },
+
+    /// A limit on the decompression of names was exceeded
+    #[error("name decompression exceeded the {0} limit")]
+    DecompressionLimit(DecompressionLimit),
 }
 
 impl<'a> BinDecoder<'a> {
@@ -85,12 +173,64 @@ impl<'a> BinDecoder<'a> {
     ///
     /// * `buffer` - buffer from which all data will be read
     pub fn new(buffer: &'a [u8]) -> Self {
+        Self::with_mode(buffer, DecodeMode::Normal)
+    }
+
+    /// Creates a new BinDecoder with the specified mode
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - buffer from which all data will be read
+    /// * `mode` - how strictly non-conforming data is handled
+    pub fn with_mode(buffer: &'a [u8], mode: DecodeMode) -> Self {
+        Self::with_limits(buffer, mode, DecompressionLimits::default())
+    }
+
+    /// Creates a new BinDecoder with the specified mode and limits on name decompression
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - buffer from which all data will be read
+    /// * `mode` - how strictly non-conforming data is handled
+    /// * `limits` - bounds on the work done to decompress the names of the message in `buffer`
+    pub fn with_limits(buffer: &'a [u8], mode: DecodeMode, limits: DecompressionLimits) -> Self {
         BinDecoder {
             buffer,
             remaining: buffer,
+            mode,
+            limits,
+            expanded: 0,
         }
     }
 
+    /// Returns the current decoding mode
+    pub fn mode(&self) -> DecodeMode {
+        self.mode
+    }
+
+    /// Returns the limits on name decompression
+    pub fn limits(&self) -> DecompressionLimits {
+        self.limits
+    }
+
+    /// Accounts `len` bytes read through a compression pointer to the expansion budget
+    pub(crate) fn expand(&mut self, len: usize) -> DecodeResult<()> {
+        self.expanded += len;
+        if self.expanded > self.limits.max_expansion {
+            return Err(DecodeError::DecompressionLimit(
+                DecompressionLimit::Expansion,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Takes over the expansion budget used by `other`, which was derived from this decoder with
+    ///  [`Self::clone`] or [`Self::read_section`]
+    pub(crate) fn join(&mut self, other: &Self) {
+        self.expanded = self.expanded.max(other.expanded);
+    }
+
     /// Pop one byte from the buffer
     pub fn pop(&mut self) -> DecodeResult<Restrict<u8>> {
         if let Some((first, remaining)) = self.remaining.split_first() {
@@ -136,6 +276,9 @@ impl<'a> BinDecoder<'a> {
         BinDecoder {
             buffer: self.buffer,
             remaining: &self.buffer[index_at as usize..],
+            mode: self.mode,
+            limits: self.limits,
+            expanded: self.expanded,
         }
     }
 
@@ -187,6 +330,56 @@ impl<'a> BinDecoder<'a> {
         Ok(Restrict::new(read))
     }
 
+    /// Splits off a decoder over the next `len` bytes, advancing this decoder past them
+    ///
+    /// The returned decoder can not read beyond the section, so a length that was validated once,
+    ///  e.g. the RDLENGTH of a record, bounds every read of the section without further checks.
+    ///  Indexes remain relative to the start of the original buffer, and data prior to the section
+    ///  stays reachable through [`Self::clone`], as is needed to follow name compression pointers.
+    ///
+    /// ```
+    /// use hickory_proto::serialize::binary::BinDecoder;
+    ///
+    /// let deadbeef = b"deadbeef";
+    /// let mut decoder = BinDecoder::new(deadbeef);
+    /// decoder.read_slice(2).unwrap();
+    ///
+    /// let mut section = decoder.read_section(4).unwrap();
+    /// assert_eq!(decoder.index(), 6);
+    /// assert_eq!(section.index(), 2);
+    /// assert_eq!(section.read_slice(4).unwrap().unverified(), b"adbe");
+    /// assert!(section.read_u8().is_err());
+    /// ```
+    pub fn read_section(&mut self, len: usize) -> DecodeResult<Self> {
+        let end = self.index() + len;
+        let section = self.read_slice(len)?.unverified(/*bounded by the section*/);
+
+        Ok(BinDecoder {
+            buffer: &self.buffer[..end],
+            remaining: section,
+            mode: self.mode,
+            limits: self.limits,
+            expanded: self.expanded,
+        })
+    }
+
+    /// Reads a fixed size array out of the buffer, without allocating
+    ///
+    /// # Returns
+    ///
+    /// The array of the next `N` bytes, otherwise an error
+    pub fn read_array<const N: usize>(&mut self) -> DecodeResult<Restrict<[u8; N]>> {
+        if N > self.remaining.len() {
+            return Err(DecodeError::InsufficientBytes);
+        }
+        let (read, remaining) = self.remaining.split_at(N);
+        self.remaining = remaining;
+
+        let mut array = [0u8; N];
+        array.copy_from_slice(read);
+        Ok(Restrict::new(array))
+    }
+
     /// Reads a slice from a previous index to the current
     pub fn slice_from(&self, index: usize) -> DecodeResult<&'a [u8]> {
         if index > self.index() {
@@ -210,9 +403,11 @@ impl<'a> BinDecoder<'a> {
     ///
     /// Return the u16 from the buffer
     pub fn read_u16(&mut self) -> DecodeResult<Restrict<u16>> {
-        Ok(self
-            .read_slice(2)?
-            .map(|s| u16::from_be_bytes([s[0], s[1]])))
+        let [a, b, remaining @ ..] = self.remaining else {
+            return Err(DecodeError::InsufficientBytes);
+        };
+        self.remaining = remaining;
+        Ok(Restrict::new(u16::from_be_bytes([*a, *b])))
     }
 
     /// Reads the next four bytes into i32.
@@ -224,10 +419,7 @@ impl<'a> BinDecoder<'a> {
     ///
     /// Return the i32 from the buffer
     pub fn read_i32(&mut self) -> DecodeResult<Restrict<i32>> {
-        Ok(self.read_slice(4)?.map(|s| {
-            assert!(s.len() == 4);
-            i32::from_be_bytes([s[0], s[1], s[2], s[3]])
-        }))
+        Ok(self.read_u32()?.map(|u| u as i32))
     }
 
     /// Reads the next four bytes into u32.
@@ -239,10 +431,11 @@ impl<'a> BinDecoder<'a> {
     ///
     /// Return the u32 from the buffer
     pub fn read_u32(&mut self) -> DecodeResult<Restrict<u32>> {
-        Ok(self.read_slice(4)?.map(|s| {
-            assert!(s.len() == 4);
-            u32::from_be_bytes([s[0], s[1], s[2], s[3]])
-        }))
+        let [a, b, c, d, remaining @ ..] = self.remaining else {
+            return Err(DecodeError::InsufficientBytes);
+        };
+        self.remaining = remaining;
+        Ok(Restrict::new(u32::from_be_bytes([*a, *b, *c, *d])))
     }
 }
 
@@ -288,4 +481,66 @@ mod tests {
         // this should fail
         assert!(decoder.slice_from(10).is_err());
     }
+
+    #[test]
+    fn test_read_integers() {
+        let bytes = [0xFF, 0xFF, 0xFF, 0xFE, 0x01, 0x02, 0x03];
+        let mut decoder = BinDecoder::new(&bytes);
+
+        assert_eq!(decoder.read_i32().unwrap().unverified(), -2);
+        assert_eq!(decoder.read_u16().unwrap().unverified(), 0x0102);
+        assert!(decoder.read_u16().is_err());
+        assert_eq!(decoder.index(), 6);
+        assert!(decoder.read_u32().is_err());
+        assert_eq!(decoder.read_array::<1>().unwrap().unverified(), [0x03]);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_read_section() {
+        let deadbeef = b"deadbeef";
+        let mut decoder = BinDecoder::new(deadbeef);
+        decoder.read_slice(4).expect("failed to read dead");
+
+        let mut section = decoder.read_section(2).expect("failed to split section");
+        assert_eq!(decoder.index(), 6);
+        assert_eq!(section.len(), 2);
+        assert!(section.read_u32().is_err());
+        assert_eq!(
+            section.read_u16().unwrap().unverified(),
+            u16::from_be_bytes(*b"be")
+        );
+        assert!(section.is_empty());
+
+        // prior data is still reachable for compression pointers
+        let mut prior = section.clone(0);
+        assert_eq!(&prior.read_slice(6).unwrap().unverified(), b"deadbe");
+        assert!(prior.is_empty());
+
+        assert!(decoder.read_section(3).is_err());
+    }
+
+    #[test]
+    fn test_expansion_budget() {
+        let limits = DecompressionLimits {
+            max_expansion: 8,
+            ..DecompressionLimits::default()
+        };
+        let mut decoder = BinDecoder::with_limits(b"deadbeef", DecodeMode::Normal, limits);
+
+        let mut section = decoder.read_section(4).expect("failed to split section");
+        section.expand(6).expect("within budget");
+        decoder.join(&section);
+
+        let mut pointer = decoder.clone(0);
+        pointer.expand(2).expect("within budget");
+        assert!(matches!(
+            pointer.expand(1),
+            Err(DecodeError::DecompressionLimit(
+                DecompressionLimit::Expansion
+            ))
+        ));
+        decoder.join(&pointer);
+        assert!(decoder.expand(0).is_err());
+    }
 }
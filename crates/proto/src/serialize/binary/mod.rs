@@ -13,7 +13,9 @@ mod restrict;
 
 use alloc::vec::Vec;
 
-pub use self::decoder::{BinDecoder, DecodeError};
+pub use self::decoder::{
+    BinDecoder, DecodeError, DecodeMode, DecompressionLimit, DecompressionLimits,
+};
 pub use self::encoder::{BinEncoder, EncodeMode, ModalEncoder, NameEncoding, RDataEncoding};
 pub use self::restrict::{Restrict, RestrictedMath, Verified};
 
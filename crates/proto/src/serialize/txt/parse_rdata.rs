@@ -55,6 +55,29 @@ pub trait RDataParser: Sized {
     }
 }
 
+impl RData {
+    /// Parse the RData of the given record type from its zone file presentation format
+    ///
+    /// Every record type also accepts the generic `\# <length> <hex>` encoding of
+    /// [RFC 3597](https://datatracker.ietf.org/doc/html/rfc3597#section-5), which is the only
+    /// form available for types without a type-specific presentation format, e.g. NULL or
+    /// unknown types. Names in the record data must be fully qualified.
+    ///
+    /// ```
+    /// use hickory_proto::rr::{RData, RecordType};
+    ///
+    /// let a = RData::from_presentation(RecordType::A, "192.0.2.1").unwrap();
+    /// assert_eq!(a, RData::from_presentation(RecordType::A, r"\# 4 C0000201").unwrap());
+    /// assert_eq!(a.to_string(), "192.0.2.1");
+    ///
+    /// let unknown = RData::from_presentation(RecordType::Unknown(65280), r"\# 2 ABCD").unwrap();
+    /// assert_eq!(unknown.to_string(), r"\# 2 ABCD");
+    /// ```
+    pub fn from_presentation(record_type: RecordType, text: &str) -> ParseResult<Self> {
+        Self::try_from_str(record_type, text)
+    }
+}
+
 #[warn(clippy::wildcard_enum_match_arm)] // make sure all cases are handled
 impl RDataParser for RData {
     /// Parse the RData from a set of Tokens
@@ -63,6 +86,12 @@ impl RDataParser for RData {
         tokens: I,
         origin: Option<&Name>,
     ) -> ParseResult<Self> {
+        let mut tokens = tokens.peekable();
+        if tokens.peek() == Some(&generic::GENERIC_MARKER) {
+            // the wire decoding rejects the meta types
+            return generic::parse(record_type, tokens);
+        }
+
         let rdata = match record_type {
             RecordType::A => Self::A(a::parse(tokens)?),
             RecordType::AAAA => Self::AAAA(aaaa::parse(tokens)?),
@@ -76,16 +105,14 @@ impl RDataParser for RData {
             RecordType::HINFO => Self::HINFO(hinfo::parse(tokens)?),
             RecordType::HTTPS => svcb::parse(tokens).map(HTTPS).map(Self::HTTPS)?,
             RecordType::IXFR => return Err(ParseError::from("parsing IXFR doesn't make sense")),
+            RecordType::L32 => Self::L32(ilnp::parse_l32(tokens)?),
+            RecordType::L64 => Self::L64(ilnp::parse_l64(tokens)?),
+            RecordType::LP => Self::LP(ilnp::parse_lp(tokens, origin)?),
             RecordType::MX => Self::MX(mx::parse(tokens, origin)?),
             RecordType::NAPTR => Self::NAPTR(naptr::parse(tokens, origin)?),
-            RecordType::NULL => {
-                return Err(ParseError::from(ParseErrorKind::Message(
-                    "parse is not implemented for NULL record",
-                )));
-            }
+            RecordType::NID => Self::NID(ilnp::parse_nid(tokens)?),
             RecordType::NS => Self::NS(NS(name::parse(tokens, origin)?)),
             RecordType::OPENPGPKEY => Self::OPENPGPKEY(openpgpkey::parse(tokens)?),
-            RecordType::OPT => return Err(ParseError::from("parsing OPT doesn't make sense")),
             RecordType::PTR => Self::PTR(PTR(name::parse(tokens, origin)?)),
             RecordType::SOA => Self::SOA(soa::parse(tokens, origin)?),
             RecordType::SRV => Self::SRV(srv::parse(tokens, origin)?),
@@ -93,38 +120,54 @@ impl RDataParser for RData {
             RecordType::SVCB => svcb::parse(tokens).map(Self::SVCB)?,
             RecordType::TLSA => Self::TLSA(tlsa::parse(tokens)?),
             RecordType::TXT => Self::TXT(txt::parse(tokens)?),
-            RecordType::SIG => return Err(ParseError::from("parsing SIG doesn't make sense")),
-            RecordType::DNSKEY => {
-                return Err(ParseError::from("DNSKEY should be dynamically generated"));
-            }
+            #[cfg(feature = "__dnssec")]
             RecordType::CDNSKEY => {
-                return Err(ParseError::from("CDNSKEY should be dynamically generated"));
+                Self::DNSSEC(DNSSECRData::CDNSKEY(dnskey::parse_cdnskey(tokens)?))
             }
-            RecordType::KEY => return Err(ParseError::from("KEY should be dynamically generated")),
+            #[cfg(feature = "__dnssec")]
+            RecordType::CDS => Self::DNSSEC(DNSSECRData::CDS(ds::parse_cds(tokens)?)),
+            #[cfg(feature = "__dnssec")]
+            RecordType::DNSKEY => Self::DNSSEC(DNSSECRData::DNSKEY(dnskey::parse(tokens)?)),
             #[cfg(feature = "__dnssec")]
             RecordType::DS => Self::DNSSEC(DNSSECRData::DS(ds::parse(tokens)?)),
-            #[cfg(not(feature = "__dnssec"))]
-            RecordType::DS => return Err(ParseError::from("DS should be dynamically generated")),
-            RecordType::CDS => return Err(ParseError::from("CDS should be dynamically generated")),
-            RecordType::NSEC => {
-                return Err(ParseError::from("NSEC should be dynamically generated"));
-            }
-            RecordType::NSEC3 => {
-                return Err(ParseError::from("NSEC3 should be dynamically generated"));
-            }
+            #[cfg(feature = "__dnssec")]
+            RecordType::KEY => Self::DNSSEC(DNSSECRData::KEY(dnskey::parse_key(tokens)?)),
+            #[cfg(feature = "__dnssec")]
+            RecordType::NSEC => Self::DNSSEC(DNSSECRData::NSEC(nsec::parse(tokens, origin)?)),
+            #[cfg(feature = "__dnssec")]
+            RecordType::NSEC3 => Self::DNSSEC(DNSSECRData::NSEC3(nsec3::parse(tokens)?)),
+            #[cfg(feature = "__dnssec")]
             RecordType::NSEC3PARAM => {
-                return Err(ParseError::from(
-                    "NSEC3PARAM should be dynamically generated",
-                ));
+                Self::DNSSEC(DNSSECRData::NSEC3PARAM(nsec3::parse_param(tokens)?))
+            }
+            #[cfg(feature = "__dnssec")]
+            RecordType::RRSIG => Self::DNSSEC(DNSSECRData::RRSIG(rrsig::parse(tokens, origin)?)),
+            #[cfg(feature = "__dnssec")]
+            RecordType::SIG => Self::DNSSEC(DNSSECRData::SIG(rrsig::parse_sig(tokens, origin)?)),
+            #[cfg(not(feature = "__dnssec"))]
+            r @ (RecordType::CDNSKEY
+            | RecordType::CDS
+            | RecordType::DNSKEY
+            | RecordType::DS
+            | RecordType::KEY
+            | RecordType::NSEC
+            | RecordType::NSEC3
+            | RecordType::NSEC3PARAM
+            | RecordType::RRSIG
+            | RecordType::SIG) => {
+                return Err(ParseError::from(ParseErrorKind::Msg(format!(
+                    "{r} requires the dnssec feature, or the generic \\# encoding"
+                ))));
             }
-            RecordType::RRSIG => {
-                return Err(ParseError::from("RRSIG should be dynamically generated"));
+            r @ (RecordType::NULL | RecordType::OPT | RecordType::TKEY | RecordType::TSIG) => {
+                return Err(ParseError::from(ParseErrorKind::Msg(format!(
+                    "{r} can only be parsed from the generic \\# encoding"
+                ))));
             }
-            RecordType::TSIG => return Err(ParseError::from("TSIG is only used during AXFR")),
             #[allow(deprecated)]
             RecordType::ZERO => Self::ZERO,
             r @ RecordType::Unknown(..) => {
-                // TODO: add a way to associate generic record types to the zone
+                // only the generic encoding is available for these
                 return Err(ParseError::from(ParseErrorKind::UnsupportedRecordType(r)));
             }
         };
@@ -137,6 +180,8 @@ impl RDataParser for RData {
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
 
+    use alloc::string::ToString;
+
     use super::*;
     #[cfg(feature = "__dnssec")]
     use crate::dnssec::rdata::DS;
@@ -291,9 +336,10 @@ mod tests {
             RecordType::NSEC3,
             RecordType::NSEC3PARAM,
             RecordType::RRSIG,
+            RecordType::SIG,
         ];
 
-        let tokens = ["test"];
+        let tokens = ["test", "test"];
 
         let name = Name::from_str("example.com.").unwrap();
 
@@ -302,4 +348,85 @@ mod tests {
             assert!(result.is_err());
         }
     }
+
+    #[test]
+    fn test_generic() {
+        let a = RData::from_presentation(RecordType::A, r"\# 4 C0000201").unwrap();
+        assert_eq!(a, RData::A("192.0.2.1".parse().unwrap()));
+
+        for (record_type, text) in [
+            (RecordType::Unknown(65280), r"\# 4 0A000001"),
+            (RecordType::NULL, r"\# 3 010203"),
+            (RecordType::NULL, r"\# 0"),
+            (RecordType::OPT, r"\# 0"),
+            (RecordType::OPT, r"\# 6 000C00020102"),
+        ] {
+            let rdata = RData::from_presentation(record_type, text).unwrap();
+            assert_eq!(rdata.record_type(), record_type);
+            assert_eq!(rdata.to_string(), text);
+        }
+
+        assert!(RData::from_presentation(RecordType::ANY, r"\# 0").is_err());
+        assert!(RData::from_presentation(RecordType::Unknown(65280), "0A000001").is_err());
+        assert!(RData::from_presentation(RecordType::NULL, "AQID").is_err());
+    }
+
+    #[cfg(feature = "__dnssec")]
+    #[test]
+    fn test_dnssec_round_trip() {
+        use crate::dnssec::rdata::{TSIG, tsig::TsigAlgorithm};
+
+        for (record_type, text) in [
+            (
+                RecordType::DNSKEY,
+                "257 3 ECDSAP256SHA256 mdsswUyr3DPW132mOi8V9xESWE8jTo0d xCjjnopKl+GqJxpVXckHAeF+KkxLbxIL fDLUT0rAK9iUzy1L53eKGQ==",
+            ),
+            (RecordType::CDNSKEY, "0 3 0 AA=="),
+            (RecordType::KEY, "512 3 15 aGVsbG8="),
+            (
+                RecordType::DS,
+                "60485 RSASHA1 1 2BB183AF5F22588179A53B0A 98631FAD1A292118",
+            ),
+            (RecordType::CDS, "0 0 0 00"),
+            (
+                RecordType::NSEC,
+                "host.example.com. A MX RRSIG NSEC TYPE1234",
+            ),
+            (
+                RecordType::NSEC3,
+                "1 1 12 AABBCCDD 2T7B4G4VSA5SMI47K61MV5BV1A22BOJR NS SOA MX RRSIG DNSKEY NSEC3PARAM",
+            ),
+            (RecordType::NSEC3PARAM, "1 0 12 -"),
+            (
+                RecordType::RRSIG,
+                "A 13 3 3600 20250101000000 20241201000000 2642 example.com. aGVsbG8gd29ybGQ=",
+            ),
+            (
+                RecordType::SIG,
+                "TYPE0 15 0 0 1700000300 1700000000 12345 . aGVsbG8=",
+            ),
+        ] {
+            let rdata = RData::from_presentation(record_type, text).unwrap();
+            let displayed = rdata.to_string();
+            assert_eq!(
+                RData::from_presentation(record_type, &displayed).unwrap(),
+                rdata,
+                "{displayed}"
+            );
+        }
+
+        let tsig = RData::DNSSEC(DNSSECRData::TSIG(TSIG::new(
+            TsigAlgorithm::HmacSha256,
+            0,
+            300,
+            vec![0, 1, 2, 3],
+            0,
+            None,
+            vec![],
+        )));
+        assert_eq!(
+            RData::from_presentation(RecordType::TSIG, &tsig.to_string()).unwrap(),
+            tsig
+        );
+    }
 }
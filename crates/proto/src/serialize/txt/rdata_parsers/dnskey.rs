@@ -1,20 +1,102 @@
+//! Parser for DNSKEY, CDNSKEY and KEY text form
+
 use alloc::string::String;
+use alloc::vec::Vec;
 use core::str::FromStr as _;
 
+use crate::dnssec::rdata::cdnskey::CDNSKEY;
 use crate::dnssec::rdata::dnskey::DNSKEY;
+#[allow(deprecated)]
+use crate::dnssec::rdata::key::{KEY, KeyTrust, KeyUsage, Protocol, UpdateScope};
 use crate::dnssec::{Algorithm, PublicKeyBuf};
 use crate::serialize::txt::{ParseError, ParseErrorKind, ParseResult};
 
-pub(crate) fn parse<'i>(mut tokens: impl Iterator<Item = &'i str>) -> ParseResult<DNSKEY> {
-    let flags_str = tokens
-        .next()
-        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("flags not present")))?;
-    let protocol_str = tokens
-        .next()
-        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("protocol not present")))?;
-    let algorithm_str = tokens
-        .next()
-        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("algorithm not present")))?;
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, Resource Records for the DNS Security Extensions](https://datatracker.ietf.org/doc/html/rfc4034#section-2.2)
+/// ```text
+/// 2.2.  The DNSKEY RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Flag field MUST be represented as an unsigned decimal integer.
+///    Given the currently defined flags, the possible values are: 0, 256,
+///    and 257.
+///
+///    The Protocol Field MUST be represented as an unsigned decimal integer
+///    with a value of 3.
+///
+///    The Algorithm field MUST be represented either as an unsigned decimal
+///    integer or as an algorithm mnemonic as specified in Appendix A.1.
+///
+///    The Public Key field MUST be represented as a Base64 encoding of the
+///    Public Key.  Whitespace is allowed within the Base64 text.  For a
+///    definition of Base64 encoding, see [RFC3548].
+/// ```
+pub(crate) fn parse<'i>(tokens: impl Iterator<Item = &'i str>) -> ParseResult<DNSKEY> {
+    let (flags, algorithm, public_key) = parse_fields(tokens)?;
+    Ok(DNSKEY::with_flags(
+        flags,
+        PublicKeyBuf::new(public_key, algorithm),
+    ))
+}
+
+/// Parse the CDNSKEY RData from a set of Tokens, the format is that of DNSKEY
+///
+/// An algorithm of `0` requests the deletion of the DNSKEY RRset, see [RFC 8078](https://datatracker.ietf.org/doc/html/rfc8078#section-4).
+pub(crate) fn parse_cdnskey<'i>(tokens: impl Iterator<Item = &'i str>) -> ParseResult<CDNSKEY> {
+    let (flags, algorithm, public_key) = parse_fields(tokens)?;
+    let algorithm = match algorithm {
+        Algorithm::Unknown(0) => None,
+        algorithm => Some(algorithm),
+    };
+
+    Ok(CDNSKEY::with_flags(flags, algorithm, public_key))
+}
+
+/// Parse the KEY RData from a set of Tokens, the format is that of DNSKEY
+///
+/// [RFC 2535](https://tools.ietf.org/html/rfc2535#section-7.1) allows any protocol value, and the
+///  public key may be omitted when the key trust is "no key".
+#[allow(deprecated)]
+pub(crate) fn parse_key<'i>(mut tokens: impl Iterator<Item = &'i str>) -> ParseResult<KEY> {
+    let flags = next_field(&mut tokens, "flags")?;
+    let protocol = next_field(&mut tokens, "protocol")?;
+    let flags = u16::from_str(flags)?;
+    let protocol = u8::from_str(protocol)?;
+
+    // same restrictions as in the binary form
+    if flags & 0b0010_1100_1111_0000 != 0 {
+        return Err(ParseError::from(ParseErrorKind::Message(
+            "flag 2, 4-5, and 8-11 are reserved, must be zero",
+        )));
+    }
+    if flags & 0b0001_0000_0000_0000 != 0 {
+        return Err(ParseError::from(ParseErrorKind::Message(
+            "extended flags currently not supported",
+        )));
+    }
+
+    let algorithm = algorithm(next_field(&mut tokens, "algorithm")?)?;
+    let public_key_str: String = tokens.collect();
+    let public_key = data_encoding::BASE64.decode(public_key_str.as_bytes())?;
+
+    Ok(KEY::new(
+        KeyTrust::from(flags),
+        KeyUsage::from(flags),
+        UpdateScope::from(flags),
+        Protocol::from(protocol),
+        algorithm,
+        public_key,
+    ))
+}
+
+fn parse_fields<'i>(
+    mut tokens: impl Iterator<Item = &'i str>,
+) -> ParseResult<(u16, Algorithm, Vec<u8>)> {
+    let flags_str = next_field(&mut tokens, "flags")?;
+    let protocol_str = next_field(&mut tokens, "protocol")?;
+    let algorithm_str = next_field(&mut tokens, "algorithm")?;
 
     let flags = u16::from_str(flags_str)?;
 
@@ -26,7 +108,7 @@ pub(crate) fn parse<'i>(mut tokens: impl Iterator<Item = &'i str>) -> ParseResul
         )));
     }
 
-    let algorithm = Algorithm::from_u8(algorithm_str.parse()?);
+    let algorithm = algorithm(algorithm_str)?;
 
     let public_key_str: String = tokens.collect();
     if public_key_str.is_empty() {
@@ -36,11 +118,43 @@ pub(crate) fn parse<'i>(mut tokens: impl Iterator<Item = &'i str>) -> ParseResul
     }
 
     let public_key = data_encoding::BASE64.decode(public_key_str.as_bytes())?;
+    Ok((flags, algorithm, public_key))
+}
 
-    Ok(DNSKEY::with_flags(
-        flags,
-        PublicKeyBuf::new(public_key, algorithm),
-    ))
+fn next_field<'i>(
+    tokens: &mut impl Iterator<Item = &'i str>,
+    field: &'static str,
+) -> ParseResult<&'i str> {
+    tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Msg(format!("{field} not present"))))
+}
+
+/// Parse a DNSSEC algorithm, either as a number or as one of the mnemonics of
+///  [RFC 4034, Appendix A.1](https://datatracker.ietf.org/doc/html/rfc4034#appendix-A.1) and its updates
+pub(crate) fn algorithm(token: &str) -> ParseResult<Algorithm> {
+    let value = match token.to_ascii_uppercase().as_str() {
+        "RSAMD5" => 1,
+        "DH" => 2,
+        "DSA" => 3,
+        "ECC" => 4,
+        "RSASHA1" => 5,
+        "DSA-NSEC3-SHA1" => 6,
+        "RSASHA1-NSEC3-SHA1" => 7,
+        "RSASHA256" => 8,
+        "RSASHA512" => 10,
+        "ECC-GOST" => 12,
+        "ECDSAP256SHA256" => 13,
+        "ECDSAP384SHA384" => 14,
+        "ED25519" => 15,
+        "ED448" => 16,
+        "INDIRECT" => 252,
+        "PRIVATEDNS" => 253,
+        "PRIVATEOID" => 254,
+        _ => token.parse()?,
+    };
+
+    Ok(Algorithm::from_u8(value))
 }
 
 #[cfg(test)]
@@ -1,9 +1,11 @@
-//! Parser for DS text form
+//! Parser for DS and CDS text form
 
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::str::FromStr;
 
+use super::dnskey::algorithm;
+use crate::dnssec::rdata::cds::CDS;
 use crate::dnssec::rdata::ds::DS;
 use crate::dnssec::{Algorithm, DigestType};
 use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
@@ -28,8 +30,27 @@ use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
 ///    hexadecimal digits.  Whitespace is allowed within the hexadecimal
 ///    text.
 /// ```
-#[allow(deprecated)]
-pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<DS> {
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<DS> {
+    let (tag, algorithm, digest_type, digest) = parse_fields(tokens)?;
+    Ok(DS::new(tag, algorithm, digest_type, digest))
+}
+
+/// Parse the CDS RData from a set of Tokens, the format is that of DS
+///
+/// An algorithm of `0` requests the deletion of the DS RRset, see [RFC 8078](https://datatracker.ietf.org/doc/html/rfc8078#section-4).
+pub(crate) fn parse_cds<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<CDS> {
+    let (tag, algorithm, digest_type, digest) = parse_fields(tokens)?;
+    let algorithm = match algorithm {
+        Algorithm::Unknown(0) => None,
+        algorithm => Some(algorithm),
+    };
+
+    Ok(CDS::new(tag, algorithm, digest_type, digest))
+}
+
+fn parse_fields<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+) -> ParseResult<(u16, Algorithm, DigestType, Vec<u8>)> {
     let tag_str: &str = tokens
         .next()
         .ok_or_else(|| ParseError::from(ParseErrorKind::Message("key tag not present")))?;
@@ -40,18 +61,7 @@ pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResu
         .next()
         .ok_or_else(|| ParseError::from(ParseErrorKind::Message("digest type not present")))?;
     let tag: u16 = tag_str.parse()?;
-    let algorithm = match algorithm_str {
-        // Mnemonics from Appendix A.1.
-        "RSAMD5" => Algorithm::Unknown(1),
-        "DH" => Algorithm::Unknown(2),
-        "DSA" => Algorithm::Unknown(3),
-        "ECC" => Algorithm::Unknown(4),
-        "RSASHA1" => Algorithm::RSASHA1,
-        "INDIRECT" => Algorithm::Unknown(252),
-        "PRIVATEDNS" => Algorithm::Unknown(253),
-        "PRIVATEOID" => Algorithm::Unknown(254),
-        _ => Algorithm::from_u8(algorithm_str.parse()?),
-    };
+    let algorithm = algorithm(algorithm_str)?;
     let digest_type = DigestType::from(u8::from_str(digest_type_str)?);
     let digest_str: String = tokens.collect();
     if digest_str.is_empty() {
@@ -72,7 +82,7 @@ pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResu
         let byte = u8::from_str_radix(byte_str, 16)?;
         digest.push(byte);
     }
-    Ok(DS::new(tag, algorithm, digest_type, digest))
+    Ok((tag, algorithm, digest_type, digest))
 }
 
 #[cfg(test)]
@@ -0,0 +1,103 @@
+//! Parser for the generic RDATA text form of RFC 3597
+
+use alloc::string::String;
+
+use crate::rr::{RData, RecordType};
+use crate::serialize::binary::{BinDecoder, Restrict};
+use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
+
+/// The token introducing the generic encoding
+pub(crate) const GENERIC_MARKER: &str = "\\#";
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 3597, Handling of Unknown DNS Resource Record (RR) Types](https://datatracker.ietf.org/doc/html/rfc3597#section-5)
+/// ```text
+/// 5.  Text Representation
+///
+///    The RDATA section of an RR of unknown type is represented as a
+///    sequence of white space separated words as follows:
+///
+///       The special token \# (a backslash immediately followed by a hash
+///       sign), which identifies the RDATA as having the generic encoding
+///       defined herein rather than a traditional type-specific encoding.
+///
+///       An unsigned decimal integer specifying the RDATA length in octets.
+///
+///       Zero or more words of hexadecimal data encoding the actual RDATA
+///       field, each containing an even number of hexadecimal digits.
+///
+///    ...
+///
+///    An implementation MAY also choose to represent some RRs of known type
+///    using the above generic representations for the type, class and/or
+///    RDATA, which carries the benefit of making the resulting master file
+///    portable to servers where these types are unknown.
+/// ```
+///
+/// The data is decoded as the wire format of `record_type`, so known types are checked for
+///  validity as if they had been received in a message.
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(
+    record_type: RecordType,
+    mut tokens: I,
+) -> ParseResult<RData> {
+    if tokens.next() != Some(GENERIC_MARKER) {
+        return Err(ParseError::from(ParseErrorKind::Message(
+            "generic rdata must start with \\#",
+        )));
+    }
+
+    let length: u16 = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("rdata length not present")))?
+        .parse()?;
+
+    let data: String = tokens.collect();
+    let data = data_encoding::HEXUPPER_PERMISSIVE.decode(data.as_bytes())?;
+    if data.len() != usize::from(length) {
+        return Err(ParseError::from(ParseErrorKind::Msg(format!(
+            "rdata length {length} does not match the {} octets of data",
+            data.len()
+        ))));
+    }
+
+    let mut decoder = BinDecoder::new(&data);
+    Ok(RData::read(
+        &mut decoder,
+        record_type,
+        Restrict::new(length),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rr::rdata::NULL;
+
+    #[test]
+    fn test_parsing() {
+        assert_eq!(
+            parse(RecordType::A, "\\# 4 0A000001".split(' ')).unwrap(),
+            RData::A("10.0.0.1".parse().unwrap())
+        );
+        assert_eq!(
+            parse(RecordType::Unknown(65280), "\\# 3 abcd 01".split(' ')).unwrap(),
+            RData::Unknown {
+                code: RecordType::Unknown(65280),
+                rdata: NULL::with(vec![0xab, 0xcd, 0x01]),
+            }
+        );
+        assert_eq!(
+            parse(RecordType::NULL, "\\# 0".split(' ')).unwrap(),
+            RData::NULL(NULL::new())
+        );
+    }
+
+    #[test]
+    fn test_parsing_fails() {
+        assert!(parse(RecordType::A, "4 0A000001".split(' ')).is_err());
+        assert!(parse(RecordType::A, "\\# 5 0A000001".split(' ')).is_err());
+        assert!(parse(RecordType::A, "\\# 3 0A0000".split(' ')).is_err());
+        assert!(parse(RecordType::A, "\\# 4 0A00000".split(' ')).is_err());
+    }
+}
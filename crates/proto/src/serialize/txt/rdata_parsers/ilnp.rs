@@ -0,0 +1,164 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Parsers for the ILNP records, NID, L32, L64 and LP
+
+use alloc::string::ToString;
+use std::net::Ipv4Addr;
+
+use crate::{
+    rr::{
+        domain::Name,
+        rdata::{L32, L64, LP, NID},
+    },
+    serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult},
+};
+
+/// Parse the RData of a NID record from a set of Tokens
+///
+/// ```text
+/// host1.example.com. IN NID 10 0014:4fff:ff20:ee64
+/// ```
+pub(crate) fn parse_nid<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<NID> {
+    let preference = preference(&mut tokens)?;
+    let node_id = hex64(&mut tokens, "node id")?;
+    end(tokens, NID::new(preference, node_id))
+}
+
+/// Parse the RData of a L32 record from a set of Tokens
+///
+/// ```text
+/// host1.example.com. IN L32 10 10.1.2.0
+/// ```
+pub(crate) fn parse_l32<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<L32> {
+    let preference = preference(&mut tokens)?;
+    let locator32: Ipv4Addr = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("locator32".to_string())))
+        .and_then(|s| s.parse().map_err(Into::into))?;
+    end(tokens, L32::new(preference, locator32))
+}
+
+/// Parse the RData of a L64 record from a set of Tokens
+///
+/// ```text
+/// host1.example.com. IN L64 10 2001:0db8:1140:1000
+/// ```
+pub(crate) fn parse_l64<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<L64> {
+    let preference = preference(&mut tokens)?;
+    let locator64 = hex64(&mut tokens, "locator64")?;
+    end(tokens, L64::new(preference, locator64))
+}
+
+/// Parse the RData of a LP record from a set of Tokens
+///
+/// ```text
+/// host1.example.com. IN LP 10 l64-subnet1.example.com.
+/// ```
+pub(crate) fn parse_lp<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<LP> {
+    let preference = preference(&mut tokens)?;
+    let fqdn = tokens
+        .next()
+        .ok_or_else(|| ParseErrorKind::MissingToken("fqdn".to_string()).into())
+        .and_then(|s| Name::parse(s, origin).map_err(ParseError::from))?;
+    end(tokens, LP::new(preference, fqdn))
+}
+
+fn preference<'i>(tokens: &mut impl Iterator<Item = &'i str>) -> ParseResult<u16> {
+    tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("preference".to_string())))
+        .and_then(|s| s.parse().map_err(Into::into))
+}
+
+/// Four colon separated groups of up to four hexadecimal digits
+fn hex64<'i>(tokens: &mut impl Iterator<Item = &'i str>, field: &str) -> ParseResult<u64> {
+    let token = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken(field.to_string())))?;
+    let invalid = || ParseError::from(ParseErrorKind::Msg(format!("invalid {field}: {token}")));
+
+    let mut groups = 0;
+    let mut value = 0u64;
+    for group in token.split(':') {
+        if group.is_empty() || group.len() > 4 || groups == 4 {
+            return Err(invalid());
+        }
+        let group = u16::from_str_radix(group, 16).map_err(|_| invalid())?;
+        value = value << 16 | u64::from(group);
+        groups += 1;
+    }
+
+    match groups {
+        4 => Ok(value),
+        _ => Err(invalid()),
+    }
+}
+
+fn end<'i, T>(mut tokens: impl Iterator<Item = &'i str>, rdata: T) -> ParseResult<T> {
+    match tokens.next() {
+        None => Ok(rdata),
+        Some(token) => Err(ParseErrorKind::Msg(format!("unexpected token: {token}")).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_nid() {
+        assert_eq!(
+            parse_nid(["10", "0014:4fff:ff20:ee64"].into_iter()).unwrap(),
+            NID::new(10, 0x0014_4fff_ff20_ee64)
+        );
+        assert_eq!(
+            parse_nid(["20", "14:4FFF:0:1"].into_iter()).unwrap(),
+            NID::new(20, 0x0014_4fff_0000_0001)
+        );
+
+        assert!(parse_nid(["10"].into_iter()).is_err());
+        assert!(parse_nid(["10", "0014:4fff:ff20"].into_iter()).is_err());
+        assert!(parse_nid(["10", "0014:4fff:ff20:ee64:0001"].into_iter()).is_err());
+        assert!(parse_nid(["10", "0014:4fff::ee64"].into_iter()).is_err());
+        assert!(parse_nid(["10", "00014:4fff:ff20:ee64"].into_iter()).is_err());
+        assert!(parse_nid(["10", "0014:4fff:ff20:ee64", "1"].into_iter()).is_err());
+    }
+
+    #[test]
+    fn test_parse_l32() {
+        assert_eq!(
+            parse_l32(["10", "10.1.2.0"].into_iter()).unwrap(),
+            L32::new(10, Ipv4Addr::new(10, 1, 2, 0))
+        );
+        assert!(parse_l32(["10", "2001:db8::"].into_iter()).is_err());
+    }
+
+    #[test]
+    fn test_parse_l64() {
+        assert_eq!(
+            parse_l64(["10", "2001:0db8:1140:1000"].into_iter()).unwrap(),
+            L64::new(10, 0x2001_0db8_1140_1000)
+        );
+        assert!(parse_l64(["-1", "2001:0db8:1140:1000"].into_iter()).is_err());
+    }
+
+    #[test]
+    fn test_parse_lp() {
+        let origin = Name::from_str("example.com.").unwrap();
+        assert_eq!(
+            parse_lp(["10", "l64-subnet1"].into_iter(), Some(&origin)).unwrap(),
+            LP::new(10, Name::from_str("l64-subnet1.example.com.").unwrap())
+        );
+        assert!(parse_lp(["10"].into_iter(), None).is_err());
+    }
+}
@@ -28,11 +28,19 @@ pub(crate) mod csync;
 pub(crate) mod dnskey;
 #[cfg(feature = "__dnssec")]
 pub(crate) mod ds;
+pub(crate) mod generic;
 pub(crate) mod hinfo;
+pub(crate) mod ilnp;
 pub(crate) mod mx;
 pub(crate) mod name;
 pub(crate) mod naptr;
+#[cfg(feature = "__dnssec")]
+pub(crate) mod nsec;
+#[cfg(feature = "__dnssec")]
+pub(crate) mod nsec3;
 pub(crate) mod openpgpkey;
+#[cfg(feature = "__dnssec")]
+pub(crate) mod rrsig;
 pub(crate) mod soa;
 pub(crate) mod srv;
 pub(crate) mod sshfp;
@@ -0,0 +1,75 @@
+//! Parser for NSEC text form
+
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+use crate::dnssec::rdata::nsec::NSEC;
+use crate::rr::{Name, RecordType};
+use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, Resource Records for the DNS Security Extensions](https://datatracker.ietf.org/doc/html/rfc4034#section-4.2)
+/// ```text
+/// 4.2.  The NSEC RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Next Domain field is represented as a domain name.
+///
+///    The Type Bit Maps field is represented as a sequence of RR type
+///    mnemonics.  When the mnemonic is not known, the TYPE representation
+///    as described in [RFC3597], Section 5, MUST be used.
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<NSEC> {
+    let next_domain_name = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("next domain name not present")))
+        .and_then(|token| Name::parse(token, origin).map_err(ParseError::from))?;
+
+    Ok(NSEC::new(next_domain_name, type_bit_maps(tokens)?))
+}
+
+/// Parse a sequence of RR type mnemonics
+pub(super) fn type_bit_maps<'i, I: Iterator<Item = &'i str>>(
+    tokens: I,
+) -> ParseResult<Vec<RecordType>> {
+    tokens.map(record_type).collect()
+}
+
+/// Parse an RR type mnemonic, or its generic `TYPE<code>` form
+pub(super) fn record_type(token: &str) -> ParseResult<RecordType> {
+    RecordType::from_str(&token.to_ascii_uppercase()).map_err(ParseError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing() {
+        let origin = Name::from_str("example.").unwrap();
+        assert_eq!(
+            parse("host.example. A MX RRSIG NSEC TYPE1234".split(' '), None).unwrap(),
+            NSEC::new(
+                Name::from_str("host.example.").unwrap(),
+                [
+                    RecordType::A,
+                    RecordType::MX,
+                    RecordType::RRSIG,
+                    RecordType::NSEC,
+                    RecordType::Unknown(1234),
+                ]
+            )
+        );
+        assert_eq!(
+            parse("host aaaa".split(' '), Some(&origin)).unwrap(),
+            NSEC::new(Name::from_str("host.example.").unwrap(), [RecordType::AAAA])
+        );
+        assert!(parse(core::iter::empty(), None).is_err());
+        assert!(parse("host.example. NOTATYPE".split(' '), None).is_err());
+    }
+}
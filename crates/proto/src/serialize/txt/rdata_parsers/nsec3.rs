@@ -0,0 +1,163 @@
+//! Parser for NSEC3 and NSEC3PARAM text form
+
+use alloc::vec::Vec;
+
+use super::nsec::type_bit_maps;
+use crate::dnssec::Nsec3HashAlgorithm;
+use crate::dnssec::rdata::nsec3::NSEC3;
+use crate::dnssec::rdata::nsec3param::NSEC3PARAM;
+use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 5155, NSEC3](https://datatracker.ietf.org/doc/html/rfc5155#section-3.3)
+/// ```text
+/// 3.3.  Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    o  The Hash Algorithm field is represented as an unsigned decimal
+///       integer.  The value has a maximum of 255.
+///
+///    o  The Flags field is represented as an unsigned decimal integer.
+///       The value has a maximum of 255.
+///
+///    o  The Iterations field is represented as an unsigned decimal
+///       integer.  The value is between 0 and 65535, inclusive.
+///
+///    o  The Salt Length field is not represented.
+///
+///    o  The Salt field is represented as a sequence of case-insensitive
+///       hexadecimal digits.  Whitespace is not allowed within the
+///       sequence.  The Salt field is represented as "-" (without the
+///       quotes) when the Salt Length field has a value of 0.
+///
+///    o  The Hash Length field is not represented.
+///
+///    o  The Next Hashed Owner Name field is represented as an unpadded
+///       sequence of case-insensitive base32 digits, without whitespace.
+///
+///    o  The Type Bit Maps field is represented as a sequence of RR type
+///       mnemonics.
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<NSEC3> {
+    let (hash_algorithm, opt_out, iterations, salt) = parse_params(&mut tokens)?;
+
+    let next_hashed_owner_name = tokens.next().ok_or_else(|| {
+        ParseError::from(ParseErrorKind::Message(
+            "next hashed owner name not present",
+        ))
+    })?;
+    let next_hashed_owner_name = data_encoding::BASE32_DNSSEC
+        .decode(next_hashed_owner_name.to_ascii_lowercase().as_bytes())?;
+
+    Ok(NSEC3::new(
+        hash_algorithm,
+        opt_out,
+        iterations,
+        salt,
+        next_hashed_owner_name,
+        type_bit_maps(tokens)?,
+    ))
+}
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 5155, NSEC3](https://datatracker.ietf.org/doc/html/rfc5155#section-4.3)
+/// ```text
+/// 4.3.  Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    o  The Hash Algorithm field is represented as an unsigned decimal
+///       integer.  The value has a maximum of 255.
+///
+///    o  The Flags field is represented as an unsigned decimal integer.
+///       The value has a maximum value of 255.
+///
+///    o  The Iterations field is represented as an unsigned decimal
+///       integer.  The value is between 0 and 65535, inclusive.
+///
+///    o  The Salt Length field is not represented.
+///
+///    o  The Salt field is represented as a sequence of case-insensitive
+///       hexadecimal digits.  Whitespace is not allowed within the
+///       sequence.  This field is represented as "-" (without the quotes)
+///       when the Salt Length field is zero.
+/// ```
+pub(crate) fn parse_param<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+) -> ParseResult<NSEC3PARAM> {
+    let (hash_algorithm, opt_out, iterations, salt) = parse_params(&mut tokens)?;
+    if tokens.next().is_some() {
+        return Err(ParseError::from(ParseErrorKind::Message(
+            "too many fields for NSEC3PARAM",
+        )));
+    }
+
+    Ok(NSEC3PARAM::new(hash_algorithm, opt_out, iterations, salt))
+}
+
+fn parse_params<'i, I: Iterator<Item = &'i str>>(
+    tokens: &mut I,
+) -> ParseResult<(Nsec3HashAlgorithm, bool, u16, Vec<u8>)> {
+    let mut next = |field: &'static str| {
+        tokens
+            .next()
+            .ok_or_else(|| ParseError::from(ParseErrorKind::Msg(format!("{field} not present"))))
+    };
+
+    let hash_algorithm = Nsec3HashAlgorithm::from_u8(next("hash algorithm")?.parse()?)?;
+    let flags: u8 = next("flags")?.parse()?;
+    if flags & 0b1111_1110 != 0 {
+        return Err(ParseError::from(ParseErrorKind::Msg(format!(
+            "unrecognized NSEC3 flags: {flags}"
+        ))));
+    }
+    let iterations: u16 = next("iterations")?.parse()?;
+    let salt = match next("salt")? {
+        "-" => Vec::new(),
+        salt => data_encoding::HEXUPPER_PERMISSIVE.decode(salt.as_bytes())?,
+    };
+
+    Ok((hash_algorithm, flags & 0b0000_0001 != 0, iterations, salt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rr::RecordType;
+
+    #[test]
+    fn test_parsing() {
+        assert_eq!(
+            parse("1 1 12 aabbccdd 2vptu5timamqttgl4luu9kg21e0aor3s A RRSIG".split(' ')).unwrap(),
+            NSEC3::new(
+                Nsec3HashAlgorithm::SHA1,
+                true,
+                12,
+                vec![0xaa, 0xbb, 0xcc, 0xdd],
+                data_encoding::BASE32_DNSSEC
+                    .decode(b"2vptu5timamqttgl4luu9kg21e0aor3s")
+                    .unwrap(),
+                [RecordType::A, RecordType::RRSIG],
+            )
+        );
+        assert!(parse("1 2 12 - 2vptu5timamqttgl4luu9kg21e0aor3s".split(' ')).is_err());
+        assert!(parse("1 0 12 -".split(' ')).is_err());
+    }
+
+    #[test]
+    fn test_parsing_param() {
+        assert_eq!(
+            parse_param("1 0 0 -".split(' ')).unwrap(),
+            NSEC3PARAM::new(Nsec3HashAlgorithm::SHA1, false, 0, vec![])
+        );
+        assert_eq!(
+            parse_param("1 0 10 AABB".split(' ')).unwrap(),
+            NSEC3PARAM::new(Nsec3HashAlgorithm::SHA1, false, 10, vec![0xaa, 0xbb])
+        );
+        assert!(parse_param("2 0 0 -".split(' ')).is_err());
+        assert!(parse_param("1 0 0 - extra".split(' ')).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Parser for RRSIG and SIG text form
+
+use alloc::string::String;
+
+use time::{Date, Month, PrimitiveDateTime, Time};
+
+use super::dnskey::algorithm;
+use super::nsec::record_type;
+use crate::dnssec::rdata::{RRSIG, SIG, SigInput};
+use crate::rr::{Name, SerialNumber};
+use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, Resource Records for the DNS Security Extensions](https://datatracker.ietf.org/doc/html/rfc4034#section-3.2)
+/// ```text
+/// 3.2.  The RRSIG RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Type Covered field is represented as an RR type mnemonic.  When
+///    the mnemonic is not known, the TYPE representation as described in
+///    [RFC3597], Section 5, MUST be used.
+///
+///    The Algorithm field value MUST be represented either as an unsigned
+///    decimal integer or as an algorithm mnemonic, as specified in Appendix
+///    A.1.
+///
+///    The Labels field value MUST be represented as an unsigned decimal
+///    integer.
+///
+///    The Original TTL field value MUST be represented as an unsigned
+///    decimal integer.
+///
+///    The Signature Expiration Time and Inception Time field values MUST be
+///    represented either as an unsigned decimal integer indicating seconds
+///    since 1 January 1970 00:00:00 UTC, or in the form YYYYMMDDHHmmSS in
+///    UTC, where:
+///
+///       YYYY is the year (0001-9999, but see Section 3.1.5);
+///       MM is the month number (01-12);
+///       DD is the day of the month (01-31);
+///       HH is the hour, in 24 hour notation (00-23);
+///       mm is the minute (00-59); and
+///       SS is the second (00-59).
+///
+///    Note that it is always possible to distinguish between these two
+///    formats because the YYYYMMDDHHmmSS format will always be exactly 14
+///    digits, while the decimal representation of a 32-bit unsigned integer
+///    can never be longer than 10 digits.
+///
+///    The Key Tag field MUST be represented as an unsigned decimal integer.
+///
+///    The Signer's Name field value MUST be represented as a domain name.
+///
+///    The Signature field is represented as a Base64 encoding of the
+///    signature.  Whitespace is allowed within the Base64 text.  See
+///    Section 3.1.8.
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(
+    tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<RRSIG> {
+    parse_sig(tokens, origin).map(RRSIG::from_sig)
+}
+
+/// Parse the SIG RData from a set of Tokens, the format is that of RRSIG
+pub(crate) fn parse_sig<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<SIG> {
+    let mut next = |field: &'static str| {
+        tokens
+            .next()
+            .ok_or_else(|| ParseError::from(ParseErrorKind::Msg(format!("{field} not present"))))
+    };
+
+    let type_covered = record_type(next("type covered")?)?;
+    let algorithm = algorithm(next("algorithm")?)?;
+    let num_labels: u8 = next("labels")?.parse()?;
+    let original_ttl: u32 = next("original ttl")?.parse()?;
+    let sig_expiration = timestamp(next("signature expiration")?)?;
+    let sig_inception = timestamp(next("signature inception")?)?;
+    let key_tag: u16 = next("key tag")?.parse()?;
+    let signer_name = Name::parse(next("signer's name")?, origin)?;
+
+    let sig: String = tokens.collect();
+    let sig = data_encoding::BASE64.decode(sig.as_bytes())?;
+
+    Ok(SIG {
+        input: SigInput {
+            type_covered,
+            algorithm,
+            num_labels,
+            original_ttl,
+            sig_expiration,
+            sig_inception,
+            key_tag,
+            signer_name,
+        },
+        sig,
+    })
+}
+
+/// Parse a signature timestamp, either in seconds or as YYYYMMDDHHmmSS
+fn timestamp(token: &str) -> ParseResult<SerialNumber> {
+    if token.len() != 14 {
+        return Ok(SerialNumber(token.parse()?));
+    }
+
+    let invalid = || ParseError::from(ParseErrorKind::Msg(format!("invalid timestamp: {token}")));
+    if !token.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let field = |range: core::ops::Range<usize>| token[range].parse::<u8>();
+    let year: i32 = token[0..4].parse()?;
+    let month = Month::try_from(field(4..6)?).map_err(|_| invalid())?;
+    let date = Date::from_calendar_date(year, month, field(6..8)?).map_err(|_| invalid())?;
+    let time =
+        Time::from_hms(field(8..10)?, field(10..12)?, field(12..14)?).map_err(|_| invalid())?;
+
+    // the field is a serial number, later dates wrap around, RFC 4034 section 3.1.5
+    let seconds = PrimitiveDateTime::new(date, time)
+        .assume_utc()
+        .unix_timestamp();
+    u64::try_from(seconds)
+        .map(|seconds| SerialNumber(seconds as u32))
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use super::*;
+    use crate::dnssec::Algorithm;
+    use crate::rr::RecordType;
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_parsing() {
+        let rrsig = parse(
+            "A 5 3 86400 20030322173103 20030220173103 2642 example. oJB1W6WNGv+ldvQ3WDG0MQkg5IEhjRip8WTr PYGv07h108dUKGMeDPKijVCHX3DDKdfb+v6o B9wfuh3DTJXUAfI/M0zmO/zz8bW0Rznl8O3t GNazPwQKkRN20XPXV6nwwfoXmJQbsLNrLfkG J5D6fwFm8nN+6pBzeDQfsS3Ap3o="
+                .split(' '),
+            None,
+        )
+        .unwrap();
+
+        let input = rrsig.input();
+        assert_eq!(input.type_covered, RecordType::A);
+        assert_eq!(input.algorithm, Algorithm::RSASHA1);
+        assert_eq!(input.num_labels, 3);
+        assert_eq!(input.original_ttl, 86400);
+        assert_eq!(input.sig_expiration, SerialNumber(1048354263));
+        assert_eq!(input.sig_inception, SerialNumber(1045762263));
+        assert_eq!(input.key_tag, 2642);
+        assert_eq!(input.signer_name, Name::from_str("example.").unwrap());
+        assert_eq!(rrsig.sig().len(), 128);
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp("0").unwrap(), SerialNumber(0));
+        assert_eq!(timestamp("1048354263").unwrap(), SerialNumber(1048354263));
+        assert_eq!(timestamp("19700101000000").unwrap(), SerialNumber(0));
+        assert_eq!(timestamp("21060207062816").unwrap(), SerialNumber(0));
+        assert!(timestamp("20031322173103").is_err());
+        assert!(timestamp("2003032217310a").is_err());
+        assert!(timestamp("19691231235959").is_err());
+        assert!(timestamp("4294967296").is_err());
+    }
+}
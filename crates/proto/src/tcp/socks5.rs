@@ -0,0 +1,364 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TCP connections through a SOCKS5 proxy, see [RFC 1928](https://tools.ietf.org/html/rfc1928)
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::future::{Future, poll_fn};
+use core::pin::Pin;
+use core::time::Duration;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+
+use futures_io::{AsyncRead, AsyncWrite};
+use futures_util::future;
+use tracing::debug;
+
+use crate::runtime::{RuntimeProvider, Time};
+use crate::xfer::CONNECT_TIMEOUT;
+
+/// A runtime which makes all TCP connections through a SOCKS5 proxy, e.g. Tor
+///
+/// This applies to DNS over TCP, TLS and HTTPS. UDP is not supported through the proxy, binding a
+/// UDP socket fails, so only name servers using the other protocols should be configured.
+///
+/// Connections are made to the address of the name server, unless a name was registered for it
+/// with [`Socks5RuntimeProvider::with_target_name`]. The proxy then resolves that name, which
+/// allows connecting to `.onion` services, which have no address.
+#[derive(Clone)]
+pub struct Socks5RuntimeProvider<P> {
+    provider: P,
+    proxy: SocketAddr,
+    credentials: Option<Arc<(String, String)>>,
+    target_names: Arc<HashMap<SocketAddr, String>>,
+}
+
+impl<P: RuntimeProvider> Socks5RuntimeProvider<P> {
+    /// Connect through the SOCKS5 proxy at `proxy`, using `provider` for the connection to it
+    pub fn new(provider: P, proxy: SocketAddr) -> Self {
+        Self {
+            provider,
+            proxy,
+            credentials: None,
+            target_names: Arc::default(),
+        }
+    }
+
+    /// Authenticate to the proxy with a username and password, see [RFC 1929](https://tools.ietf.org/html/rfc1929)
+    ///
+    /// Tor isolates the circuits of connections with different credentials.
+    pub fn with_credentials(mut self, username: String, password: String) -> Self {
+        self.credentials = Some(Arc::new((username, password)));
+        self
+    }
+
+    /// Connect to the host `name` instead of to `addr`, keeping the port of `addr`
+    ///
+    /// The name is resolved by the proxy, `addr` can be any placeholder address which is then
+    /// configured for the name server.
+    pub fn with_target_name(mut self, addr: SocketAddr, name: String) -> Self {
+        Arc::make_mut(&mut self.target_names).insert(addr, name);
+        self
+    }
+}
+
+impl<P: RuntimeProvider> RuntimeProvider for Socks5RuntimeProvider<P> {
+    type Handle = P::Handle;
+    type Timer = P::Timer;
+    type Udp = P::Udp;
+    type Tcp = P::Tcp;
+
+    fn create_handle(&self) -> Self::Handle {
+        self.provider.create_handle()
+    }
+
+    fn connect_tcp(
+        &self,
+        server_addr: SocketAddr,
+        bind_addr: Option<SocketAddr>,
+        timeout: Option<Duration>,
+    ) -> Pin<Box<dyn Send + Future<Output = io::Result<Self::Tcp>>>> {
+        let connect = self.provider.connect_tcp(self.proxy, bind_addr, timeout);
+        let target = match self.target_names.get(&server_addr) {
+            Some(name) => Target::Name(name.clone(), server_addr.port()),
+            None => Target::Addr(server_addr),
+        };
+        let credentials = self.credentials.clone();
+        let timeout = timeout.unwrap_or(CONNECT_TIMEOUT);
+
+        Box::pin(async move {
+            let stream = connect.await?;
+            debug!("connecting through SOCKS5 proxy to {target:?}");
+            let handshake = handshake(stream, target, credentials);
+            P::Timer::timeout(timeout, handshake).await?
+        })
+    }
+
+    fn bind_udp(
+        &self,
+        _local_addr: SocketAddr,
+        _server_addr: SocketAddr,
+    ) -> Pin<Box<dyn Send + Future<Output = io::Result<Self::Udp>>>> {
+        Box::pin(future::ready(Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "UDP is not supported through a SOCKS5 proxy",
+        ))))
+    }
+}
+
+#[derive(Debug)]
+enum Target {
+    Addr(SocketAddr),
+    Name(String, u16),
+}
+
+const VERSION: u8 = 5;
+const NO_AUTHENTICATION: u8 = 0;
+const USERNAME_PASSWORD: u8 = 2;
+const NO_ACCEPTABLE_METHOD: u8 = 0xFF;
+const CONNECT: u8 = 1;
+const IPV4: u8 = 1;
+const DOMAIN_NAME: u8 = 3;
+const IPV6: u8 = 4;
+
+/// Negotiates a connection to `target` on a stream to the proxy
+async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    target: Target,
+    credentials: Option<Arc<(String, String)>>,
+) -> io::Result<S> {
+    let method = match credentials {
+        Some(_) => USERNAME_PASSWORD,
+        None => NO_AUTHENTICATION,
+    };
+    write_all(&mut stream, &[VERSION, 1, method]).await?;
+
+    let mut reply = [0; 2];
+    read_exact(&mut stream, &mut reply).await?;
+    match reply {
+        [VERSION, m] if m == method => {}
+        [VERSION, NO_ACCEPTABLE_METHOD] => {
+            return Err(proxy_error("no acceptable authentication method"));
+        }
+        _ => return Err(proxy_error("unexpected method selection")),
+    }
+
+    if let Some(credentials) = credentials {
+        let (username, password) = &*credentials;
+        let mut request = vec![1];
+        push_length_prefixed(&mut request, username.as_bytes())?;
+        push_length_prefixed(&mut request, password.as_bytes())?;
+        write_all(&mut stream, &request).await?;
+
+        let mut reply = [0; 2];
+        read_exact(&mut stream, &mut reply).await?;
+        if reply[1] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "SOCKS5 proxy authentication failed",
+            ));
+        }
+    }
+
+    let mut request = vec![VERSION, CONNECT, 0];
+    match &target {
+        Target::Addr(addr) => {
+            match addr.ip() {
+                IpAddr::V4(ip) => {
+                    request.push(IPV4);
+                    request.extend_from_slice(&ip.octets());
+                }
+                IpAddr::V6(ip) => {
+                    request.push(IPV6);
+                    request.extend_from_slice(&ip.octets());
+                }
+            }
+            request.extend_from_slice(&addr.port().to_be_bytes());
+        }
+        Target::Name(name, port) => {
+            request.push(DOMAIN_NAME);
+            push_length_prefixed(&mut request, name.as_bytes())?;
+            request.extend_from_slice(&port.to_be_bytes());
+        }
+    }
+    write_all(&mut stream, &request).await?;
+
+    let mut reply = [0; 4];
+    read_exact(&mut stream, &mut reply).await?;
+    if reply[0] != VERSION {
+        return Err(proxy_error("unexpected reply version"));
+    }
+
+    if reply[1] != 0 {
+        return Err(reply_error(reply[1]));
+    }
+
+    // the bound address is of no use for DNS, but must be consumed
+    let len = match reply[3] {
+        IPV4 => 4,
+        IPV6 => 16,
+        DOMAIN_NAME => {
+            let mut len = [0; 1];
+            read_exact(&mut stream, &mut len).await?;
+            usize::from(len[0])
+        }
+        _ => return Err(proxy_error("unexpected bound address type")),
+    };
+    let mut bound = vec![0; len + 2];
+    read_exact(&mut stream, &mut bound).await?;
+
+    Ok(stream)
+}
+
+fn push_length_prefixed(buf: &mut Vec<u8>, bytes: &[u8]) -> io::Result<()> {
+    let len = u8::try_from(bytes.len()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "SOCKS5 field longer than 255 bytes",
+        )
+    })?;
+
+    buf.push(len);
+    buf.extend_from_slice(bytes);
+    Ok(())
+}
+
+fn proxy_error(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("SOCKS5 proxy: {msg}"))
+}
+
+fn reply_error(reply: u8) -> io::Error {
+    let (kind, msg) = match reply {
+        2 => (io::ErrorKind::PermissionDenied, "connection not allowed"),
+        3 => (io::ErrorKind::Other, "network unreachable"),
+        4 => (io::ErrorKind::Other, "host unreachable"),
+        5 => (io::ErrorKind::ConnectionRefused, "connection refused"),
+        6 => (io::ErrorKind::TimedOut, "TTL expired"),
+        7 => (io::ErrorKind::Unsupported, "command not supported"),
+        8 => (io::ErrorKind::Unsupported, "address type not supported"),
+        _ => (io::ErrorKind::Other, "general failure"),
+    };
+
+    io::Error::new(kind, format!("SOCKS5 proxy: {msg}"))
+}
+
+async fn write_all<S: AsyncWrite + Unpin>(stream: &mut S, mut buf: &[u8]) -> io::Result<()> {
+    while !buf.is_empty() {
+        let wrote = poll_fn(|cx| Pin::new(&mut *stream).poll_write(cx, buf)).await?;
+        if wrote == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        buf = &buf[wrote..];
+    }
+
+    poll_fn(|cx| Pin::new(&mut *stream).poll_flush(cx)).await
+}
+
+async fn read_exact<S: AsyncRead + Unpin>(stream: &mut S, mut buf: &mut [u8]) -> io::Result<()> {
+    while !buf.is_empty() {
+        let read = poll_fn(|cx| Pin::new(&mut *stream).poll_read(cx, buf)).await?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf = &mut buf[read..];
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use alloc::string::ToString;
+    use std::net::Ipv4Addr;
+
+    use test_support::subscribe;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::runtime::TokioRuntimeProvider;
+
+    /// Accepts one connection, expecting a CONNECT to `example.onion:853`, and replies with `reply`
+    async fn proxy(credentials: bool, reply: u8) -> SocketAddr {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let method = if credentials { 2 } else { 0 };
+
+            let mut greeting = [0; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 1, method]);
+            stream.write_all(&[5, method]).await.unwrap();
+
+            if credentials {
+                let mut auth = [0; 11];
+                stream.read_exact(&mut auth).await.unwrap();
+                assert_eq!(&auth, b"\x01\x04user\x04pass");
+                stream.write_all(&[1, 0]).await.unwrap();
+            }
+
+            let mut request = [0; 20];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request, b"\x05\x01\x00\x03\x0dexample.onion\x03\x55");
+
+            stream
+                .write_all(&[5, reply, 0, 1, 127, 0, 0, 1, 0, 0])
+                .await
+                .unwrap();
+            stream.write_all(b"dns").await.unwrap();
+        });
+
+        addr
+    }
+
+    fn target() -> SocketAddr {
+        SocketAddr::from(([192, 0, 2, 1], 853))
+    }
+
+    #[tokio::test]
+    async fn test_connect_to_name() {
+        subscribe();
+        let proxy = proxy(false, 0).await;
+        let provider = Socks5RuntimeProvider::new(TokioRuntimeProvider::new(), proxy)
+            .with_target_name(target(), "example.onion".to_string());
+
+        let mut stream = provider.connect_tcp(target(), None, None).await.unwrap();
+        let mut buf = [0; 3];
+        read_exact(&mut stream, &mut buf).await.unwrap();
+        assert_eq!(&buf, b"dns");
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_credentials() {
+        subscribe();
+        let proxy = proxy(true, 0).await;
+        let provider = Socks5RuntimeProvider::new(TokioRuntimeProvider::new(), proxy)
+            .with_credentials("user".to_string(), "pass".to_string())
+            .with_target_name(target(), "example.onion".to_string());
+
+        provider.connect_tcp(target(), None, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_refused() {
+        subscribe();
+        let proxy = proxy(false, 5).await;
+        let provider = Socks5RuntimeProvider::new(TokioRuntimeProvider::new(), proxy)
+            .with_target_name(target(), "example.onion".to_string());
+
+        let Err(error) = provider.connect_tcp(target(), None, None).await else {
+            panic!("expected the connection to be refused");
+        };
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+    }
+}
@@ -193,24 +193,34 @@ impl<S: DnsTcpStream> Stream for TcpStream<S> {
         let mut socket = Pin::new(socket);
         let mut outbound_messages = Pin::new(outbound_messages);
 
-        // this will not accept incoming data while there is data to send
-        //  makes this self throttling.
-        // TODO: it might be interesting to try and split the sending and receiving futures.
-        loop {
+        // a blocked write must not hold back reading, otherwise pipelined requests could deadlock
+        //  with a peer that is itself waiting for its responses to be read, see RFC 7766.
+        'send: loop {
             // in the case we are sending, send it all?
             if send_state.is_some() {
                 // sending...
                 match send_state {
                     Some(WriteTcpState::LenBytes { pos, length, .. }) => {
-                        let wrote = ready!(socket.as_mut().poll_write(cx, &length[*pos..]))?;
+                        let Poll::Ready(wrote) = socket.as_mut().poll_write(cx, &length[*pos..])
+                        else {
+                            break 'send;
+                        };
+                        let wrote = wrote?;
                         *pos += wrote;
                     }
                     Some(WriteTcpState::Bytes { pos, bytes }) => {
-                        let wrote = ready!(socket.as_mut().poll_write(cx, &bytes[*pos..]))?;
+                        let Poll::Ready(wrote) = socket.as_mut().poll_write(cx, &bytes[*pos..])
+                        else {
+                            break 'send;
+                        };
+                        let wrote = wrote?;
                         *pos += wrote;
                     }
                     Some(WriteTcpState::Flushing) => {
-                        ready!(socket.as_mut().poll_flush(cx))?;
+                        let Poll::Ready(flushed) = socket.as_mut().poll_flush(cx) else {
+                            break 'send;
+                        };
+                        flushed?;
                     }
                     _ => (),
                 }
@@ -274,10 +284,10 @@ impl<S: DnsTcpStream> Stream for TcpStream<S> {
                     }
                     // now we get to drop through to the receives...
                     // TODO: should we also return None if there are no more messages to send?
-                    Poll::Pending => break,
+                    Poll::Pending => break 'send,
                     Poll::Ready(None) => {
                         debug!("no messages to send");
-                        break;
+                        break 'send;
                     }
                 }
             }
@@ -318,8 +328,7 @@ impl<S: DnsTcpStream> Stream for TcpStream<S> {
                     } else {
                         let length = u16::from_be_bytes(*bytes);
                         debug!("got length: {}", length);
-                        let mut bytes = vec![0; length as usize];
-                        bytes.resize(length as usize, 0);
+                        let bytes = vec![0; length as usize];
 
                         debug!("move ReadTcpState::Bytes: {}", bytes.len());
                         Some(ReadTcpState::Bytes { pos: 0, bytes })
@@ -383,12 +392,18 @@ impl<S: DnsTcpStream> Stream for TcpStream<S> {
 #[cfg(test)]
 #[cfg(feature = "tokio")]
 mod tests {
-    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use core::time::Duration;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
+    use futures_util::StreamExt;
     use test_support::subscribe;
+    use tokio::io::AsyncWriteExt;
 
+    use super::TcpStream;
     use crate::runtime::TokioRuntimeProvider;
+    use crate::runtime::iocompat::AsyncIoTokioAsStd;
     use crate::tests::tcp_stream_test;
+    use crate::xfer::{DnsStreamHandle, SerialMessage};
 
     #[tokio::test]
     async fn test_tcp_stream_ipv4() {
@@ -405,4 +420,29 @@ mod tests {
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn test_tcp_stream_reads_while_write_blocked() {
+        subscribe();
+        let peer = SocketAddr::from((Ipv4Addr::LOCALHOST, 53));
+
+        // the buffer is too small for the request, the peer never reads it
+        let (client, mut server) = tokio::io::duplex(64);
+        let (mut stream, mut handle) = TcpStream::from_stream(AsyncIoTokioAsStd(client), peer);
+
+        handle
+            .send(SerialMessage::new(vec![0xAB; 1024], peer))
+            .expect("send failed");
+        server
+            .write_all(&[0, 4, 1, 2, 3, 4])
+            .await
+            .expect("write failed");
+
+        let response = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("read blocked by pending write")
+            .expect("stream closed")
+            .expect("read failed");
+        assert_eq!(response.bytes(), &[1, 2, 3, 4]);
+    }
 }
@@ -4,6 +4,7 @@
 
 mod tcp;
 mod udp;
+pub mod vectors;
 
 pub use self::tcp::tcp_client_stream_test;
 pub use self::tcp::tcp_stream_test;
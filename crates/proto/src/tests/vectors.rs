@@ -0,0 +1,227 @@
+//! Wire format test vectors, round-tripped through the message decoder and encoder
+//!
+//! A vector file holds a single DNS message as hex, with `#` comments, after a line stating what
+//! is expected of it:
+//!
+//! ```text
+//! # a query for www.example.com. A
+//! expect: canonical
+//! 1234 0100 0001 0000 0000 0000
+//! 03 777777 07 6578616d706c65 03 636f6d 00 0001 0001
+//! ```
+//!
+//! See [`Expectation`] for the checks applied to each kind of vector.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use data_encoding::HEXLOWER_PERMISSIVE;
+
+use crate::error::{ProtoError, ProtoResult};
+use crate::op::Message;
+use crate::serialize::binary::{BinDecodable, BinEncodable};
+
+/// The extension of vector files in a corpus directory
+pub const VECTOR_EXTENSION: &str = "vector";
+
+/// What decoding and re-encoding a vector is expected to produce
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expectation {
+    /// The message is encoded exactly as the encoder would, re-encoding it must produce the same
+    /// bytes
+    Canonical,
+    /// The message decodes, but is encoded differently than the encoder would, e.g. without name
+    /// compression. Re-encoding it must produce a canonical message with the same content.
+    Decodes,
+    /// The message is malformed, e.g. contains a compression loop, and must fail to decode
+    Rejected,
+}
+
+/// A DNS message in wire format, with the expected result of round-tripping it
+#[derive(Clone, Debug)]
+pub struct WireVector {
+    /// Name of the vector, the file stem for vectors loaded from a corpus
+    pub name: String,
+    /// What round-tripping the message is expected to produce
+    pub expect: Expectation,
+    /// The message in wire format
+    pub bytes: Vec<u8>,
+}
+
+impl WireVector {
+    /// Parses a vector from its text format
+    pub fn parse(name: impl Into<String>, text: &str) -> ProtoResult<Self> {
+        let name = name.into();
+        let mut expect = None;
+        let mut hex = String::new();
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(value) = line.strip_prefix("expect:") {
+                expect = Some(match value.trim() {
+                    "canonical" => Expectation::Canonical,
+                    "decodes" => Expectation::Decodes,
+                    "rejected" => Expectation::Rejected,
+                    other => {
+                        return Err(format!("{name}: unknown expectation: {other}").into());
+                    }
+                });
+                continue;
+            }
+
+            hex.extend(line.chars().filter(|c| !c.is_whitespace()));
+        }
+
+        let expect = expect.ok_or_else(|| ProtoError::from(format!("{name}: missing expect")))?;
+        let bytes = HEXLOWER_PERMISSIVE
+            .decode(hex.as_bytes())
+            .map_err(|e| ProtoError::from(format!("{name}: invalid hex: {e}")))?;
+
+        Ok(Self {
+            name,
+            expect,
+            bytes,
+        })
+    }
+
+    /// Reads a vector from a file, named after the file stem
+    pub fn read(path: &Path) -> ProtoResult<Self> {
+        let text = fs::read_to_string(path)?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self::parse(name, &text)
+    }
+
+    /// Decodes and re-encodes the message, checking the result against the expectation
+    pub fn round_trip(&self) -> ProtoResult<()> {
+        let name = &self.name;
+        let decoded = Message::from_bytes(&self.bytes);
+
+        let message = match (self.expect, decoded) {
+            (Expectation::Rejected, Ok(_)) => {
+                return Err(format!("{name}: malformed message was decoded").into());
+            }
+            (Expectation::Rejected, Err(_)) => return Ok(()),
+            (_, Err(e)) => return Err(format!("{name}: failed to decode: {e}").into()),
+            (_, Ok(message)) => message,
+        };
+
+        let encoded = message
+            .to_bytes()
+            .map_err(|e| ProtoError::from(format!("{name}: failed to encode: {e}")))?;
+
+        if self.expect == Expectation::Canonical {
+            if encoded != self.bytes {
+                return Err(format!(
+                    "{name}: re-encoded message differs at byte {}",
+                    first_difference(&self.bytes, &encoded)
+                )
+                .into());
+            }
+            return Ok(());
+        }
+
+        // a non-canonical message must have the same content once re-encoded, and the re-encoded
+        // message must itself be canonical
+        let reencoded = Message::from_bytes(&encoded)
+            .map_err(|e| ProtoError::from(format!("{name}: failed to decode re-encoded: {e}")))?;
+        if reencoded != message {
+            return Err(format!("{name}: re-encoded message has different content").into());
+        }
+        if reencoded.to_bytes()? != encoded {
+            return Err(format!("{name}: re-encoded message is not canonical").into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Loads all the vectors of a corpus directory, ordered by file name
+pub fn load_vectors(dir: &Path) -> ProtoResult<Vec<WireVector>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == VECTOR_EXTENSION));
+    paths.sort();
+
+    paths.iter().map(|path| WireVector::read(path)).collect()
+}
+
+/// Round-trips all the vectors of a corpus directory, returning the failures
+pub fn check_corpus(dir: &Path) -> ProtoResult<Vec<ProtoError>> {
+    let vectors = load_vectors(dir)?;
+    if vectors.is_empty() {
+        return Err(format!("no vectors in {}", dir.display()).into());
+    }
+
+    Ok(vectors
+        .iter()
+        .filter_map(|vector| vector.round_trip().err())
+        .collect())
+}
+
+fn first_difference(expected: &[u8], actual: &[u8]) -> usize {
+    expected
+        .iter()
+        .zip(actual)
+        .position(|(e, a)| e != a)
+        .unwrap_or_else(|| expected.len().min(actual.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_corpus() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/test-data/wire");
+        let failures = check_corpus(&dir).unwrap();
+        assert!(failures.is_empty(), "failed vectors: {failures:#?}");
+    }
+
+    #[test]
+    fn test_parse() {
+        let vector = WireVector::parse(
+            "query",
+            "# comment\nexpect: canonical\n0000 0100 # header\n0000 0000 0000 0000\n",
+        )
+        .unwrap();
+        assert_eq!(vector.expect, Expectation::Canonical);
+        assert_eq!(vector.bytes.len(), 12);
+        vector.round_trip().unwrap();
+
+        assert!(WireVector::parse("missing", "0000").is_err());
+        assert!(WireVector::parse("bad", "expect: maybe\n").is_err());
+        assert!(WireVector::parse("odd", "expect: canonical\n000").is_err());
+    }
+
+    #[test]
+    fn test_expectations_are_checked() {
+        let header = vec![0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mismatch = |expect| WireVector {
+            name: "header".into(),
+            expect,
+            bytes: header.clone(),
+        };
+        assert!(mismatch(Expectation::Rejected).round_trip().is_err());
+
+        let truncated = WireVector {
+            name: "truncated".into(),
+            expect: Expectation::Canonical,
+            bytes: header[..6].to_vec(),
+        };
+        assert!(truncated.round_trip().is_err());
+    }
+}
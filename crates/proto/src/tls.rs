@@ -0,0 +1,130 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TLS backend abstraction for DNS over TLS and DNS over HTTPS
+//!
+//! The [`TlsConnect`] and [`TlsAccept`] traits are implemented for the connectors and acceptors
+//! of each TLS library supported by the crate, selected through features:
+//!
+//! * `tls-ring` or `tls-aws-lc-rs`: `tokio_rustls::TlsConnector` and `tokio_rustls::TlsAcceptor`
+//! * `native-tls`: `tokio_native_tls::TlsConnector` and `tokio_native_tls::TlsAcceptor`, using
+//!   OpenSSL, Security.framework or SChannel depending on the platform
+//!
+//! Implementing them for other libraries allows DNS over TLS on top of those, and DNS over HTTPS
+//! through `h2::https_client_connect_with_connector` with the `https-native-tls` feature.
+//!
+//! DNS over QUIC and HTTP/3, as well as the connections made by `hickory-resolver`, are always
+//! established with rustls.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::future::Future;
+use core::pin::Pin;
+use std::io;
+use std::net::SocketAddr;
+
+use tokio::net::TcpStream as TokioTcpStream;
+use tokio::time::timeout;
+
+use crate::error::ProtoError;
+use crate::tcp::{DnsTcpStream, TcpClientStream, TcpStream};
+use crate::xfer::{BufDnsStreamHandle, CONNECT_TIMEOUT};
+
+/// A future resolving to an established TLS stream
+pub type TlsFuture<S> = Pin<Box<dyn Future<Output = io::Result<S>> + Send>>;
+
+/// Establishes the client side of TLS connections
+pub trait TlsConnect: Clone + Send + Sync + 'static {
+    /// The TLS stream over a connection of type `S`
+    type Stream<S: DnsTcpStream>: DnsTcpStream;
+
+    /// Performs the TLS handshake over `stream`, authenticating the server as `server_name`
+    fn connect<S: DnsTcpStream>(&self, server_name: &str, stream: S) -> TlsFuture<Self::Stream<S>>;
+}
+
+/// Establishes the server side of TLS connections
+pub trait TlsAccept: Clone + Send + Sync + 'static {
+    /// The TLS stream over an accepted connection
+    type Stream: DnsTcpStream;
+
+    /// Performs the TLS handshake over the accepted `stream`
+    fn accept(&self, stream: TokioTcpStream) -> TlsFuture<Self::Stream>;
+
+    /// Returns true if the client of `stream` authenticated with a verified certificate
+    fn is_client_authenticated(_stream: &Self::Stream) -> bool {
+        false
+    }
+}
+
+/// Type of the DNS over TLS client stream established with the connector `C`
+pub type TlsConnectorClientStream<C, S> = TcpClientStream<<C as TlsConnect>::Stream<S>>;
+
+/// Initializes a TlsStream with an existing TLS stream.
+///
+/// This is intended for use with a TlsListener and Incoming connections
+pub fn tls_from_stream<S: DnsTcpStream>(
+    stream: S,
+    peer_addr: SocketAddr,
+) -> (TcpStream<S>, BufDnsStreamHandle) {
+    let (message_sender, outbound_messages) = BufDnsStreamHandle::new(peer_addr);
+    let stream = TcpStream::from_stream_with_receiver(stream, peer_addr, outbound_messages);
+    (stream, message_sender)
+}
+
+/// Creates a new DNS over TLS client stream, securing the connection produced by `future` with
+/// the TLS library behind `connector`.
+///
+/// # Arguments
+///
+/// * `future` - A future producing DnsTcpStream
+/// * `name_server` - IP and Port for the remote DNS resolver
+/// * `server_name` - The DNS name associated with a certificate
+/// * `connector` - Establishes the TLS session
+#[allow(clippy::type_complexity)]
+pub fn tls_client_connect_with_connector<C, S, F>(
+    future: F,
+    name_server: SocketAddr,
+    server_name: String,
+    connector: C,
+) -> (
+    Pin<
+        Box<dyn Future<Output = Result<TlsConnectorClientStream<C, S>, ProtoError>> + Send + Unpin>,
+    >,
+    BufDnsStreamHandle,
+)
+where
+    C: TlsConnect,
+    S: DnsTcpStream,
+    F: Future<Output = io::Result<S>> + Send + Unpin + 'static,
+{
+    let (message_sender, outbound_messages) = BufDnsStreamHandle::new(name_server);
+
+    let stream = Box::pin(async move {
+        let stream = future.await?;
+        let stream = match timeout(CONNECT_TIMEOUT, connector.connect(&server_name, stream)).await {
+            Ok(Ok(s)) => s,
+            Ok(Err(e)) => {
+                return Err(ProtoError::from(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    format!("tls error: {e}"),
+                )));
+            }
+            Err(_) => {
+                return Err(ProtoError::from(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("TLS handshake timed out after {CONNECT_TIMEOUT:?}"),
+                )));
+            }
+        };
+
+        Ok(TcpClientStream::from_stream(
+            TcpStream::from_stream_with_receiver(stream, name_server, outbound_messages),
+        ))
+    });
+
+    (Box::pin(stream), message_sender)
+}
@@ -9,6 +9,7 @@ use alloc::boxed::Box;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::fmt::{self, Display};
+use core::ops::RangeInclusive;
 use core::pin::Pin;
 use core::task::{Context, Poll};
 use core::time::Duration;
@@ -16,14 +17,15 @@ use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use futures_util::{future::Future, stream::Stream};
+use futures_util::{future::Future, future::poll_fn, stream::Stream};
 use tracing::{debug, trace, warn};
 
 use crate::error::{ProtoError, ProtoErrorKind};
 use crate::op::{Message, MessageSigner, MessageVerifier, Query};
 use crate::runtime::{RuntimeProvider, Time};
-use crate::udp::udp_stream::NextRandomUdpSocket;
+use crate::udp::udp_stream::{DEFAULT_LOCAL_PORT_RANGE, NextRandomUdpSocket};
 use crate::udp::{DnsUdpSocket, MAX_RECEIVE_BUFFER_SIZE};
+use crate::xfer::BufferPool;
 use crate::xfer::{DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream, SerialMessage};
 
 /// A builder to create a UDP client stream.
@@ -35,6 +37,7 @@ pub struct UdpClientStreamBuilder<P> {
     signer: Option<Arc<dyn MessageSigner>>,
     bind_addr: Option<SocketAddr>,
     avoid_local_ports: Arc<HashSet<u16>>,
+    local_port_range: RangeInclusive<u16>,
     os_port_selection: bool,
     provider: P,
 }
@@ -54,6 +57,7 @@ impl<P> UdpClientStreamBuilder<P> {
             signer,
             bind_addr: self.bind_addr,
             avoid_local_ports: self.avoid_local_ports,
+            local_port_range: self.local_port_range,
             os_port_selection: self.os_port_selection,
             provider: self.provider,
         }
@@ -75,6 +79,15 @@ impl<P> UdpClientStreamBuilder<P> {
         self
     }
 
+    /// Configures the range of local UDP ports to pick from when making outgoing connections
+    ///
+    /// Defaults to 1024-65535, as recommended by RFC 6056. This has no effect if a port is set in
+    /// the bind address, or if the OS picks the port.
+    pub fn with_local_port_range(mut self, local_port_range: RangeInclusive<u16>) -> Self {
+        self.local_port_range = local_port_range;
+        self
+    }
+
     /// Configures that OS should provide the ephemeral port, not the Hickory DNS
     pub fn with_os_port_selection(mut self, os_port_selection: bool) -> Self {
         self.os_port_selection = os_port_selection;
@@ -91,6 +104,7 @@ impl<P> UdpClientStreamBuilder<P> {
             signer: self.signer,
             bind_addr: self.bind_addr,
             avoid_local_ports: self.avoid_local_ports.clone(),
+            local_port_range: self.local_port_range,
             os_port_selection: self.os_port_selection,
             provider: self.provider,
         }
@@ -110,6 +124,7 @@ pub struct UdpClientStream<P> {
     signer: Option<Arc<dyn MessageSigner>>,
     bind_addr: Option<SocketAddr>,
     avoid_local_ports: Arc<HashSet<u16>>,
+    local_port_range: RangeInclusive<u16>,
     os_port_selection: bool,
     provider: P,
 }
@@ -123,6 +138,7 @@ impl<P: RuntimeProvider> UdpClientStream<P> {
             signer: None,
             bind_addr: None,
             avoid_local_ports: Arc::default(),
+            local_port_range: DEFAULT_LOCAL_PORT_RANGE,
             os_port_selection: false,
             provider,
         }
@@ -142,6 +158,11 @@ impl<P: RuntimeProvider> DnsRequestSender for UdpClientStream<P> {
         }
 
         let case_randomization = request.options().case_randomization;
+        // the window must leave time to receive the response before the request times out
+        let duplicate_response_window = request
+            .options()
+            .duplicate_response_window
+            .map(|window| window.min(self.timeout / 2));
         let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
             Ok(now) => now.as_secs(),
             Err(_) => return ProtoError::from("Current time is before the Unix epoch.").into(),
@@ -151,9 +172,9 @@ impl<P: RuntimeProvider> DnsRequestSender for UdpClientStream<P> {
         let now = now as u32;
 
         let mut verifier = None;
-        if let Some(signer) = &self.signer {
+        if let Some(signer) = request.signer().or(self.signer.as_ref()).cloned() {
             if signer.should_sign_message(&request) {
-                match request.finalize(&**signer, now) {
+                match request.finalize(&*signer, now) {
                     Ok(answer_verifier) => verifier = answer_verifier,
                     Err(e) => {
                         debug!("could not sign message: {}", e);
@@ -186,6 +207,7 @@ impl<P: RuntimeProvider> DnsRequestSender for UdpClientStream<P> {
         let addr = message.addr();
         let bind_addr = self.bind_addr;
         let avoid_local_ports = self.avoid_local_ports.clone();
+        let local_port_range = self.local_port_range.clone();
         let os_port_selection = self.os_port_selection;
 
         P::Timer::timeout::<Pin<Box<dyn Future<Output = Result<DnsResponse, ProtoError>> + Send>>>(
@@ -195,17 +217,19 @@ impl<P: RuntimeProvider> DnsRequestSender for UdpClientStream<P> {
                     addr,
                     bind_addr,
                     avoid_local_ports,
+                    local_port_range,
                     os_port_selection,
                     provider,
                 )
                 .await?;
-                send_serial_message_inner(
+                send_serial_message_inner::<_, P::Timer>(
                     message,
                     message_id,
                     verifier,
                     socket,
                     recv_buf_size,
                     case_randomization,
+                    duplicate_response_window,
                     request.original_query(),
                 )
                 .await
@@ -244,6 +268,7 @@ pub struct UdpClientConnect<P> {
     signer: Option<Arc<dyn MessageSigner>>,
     bind_addr: Option<SocketAddr>,
     avoid_local_ports: Arc<HashSet<u16>>,
+    local_port_range: RangeInclusive<u16>,
     os_port_selection: bool,
     provider: P,
 }
@@ -260,19 +285,22 @@ impl<P: RuntimeProvider> Future for UdpClientConnect<P> {
             signer: self.signer.take(),
             bind_addr: self.bind_addr,
             avoid_local_ports: self.avoid_local_ports.clone(),
+            local_port_range: self.local_port_range.clone(),
             os_port_selection: self.os_port_selection,
             provider: self.provider.clone(),
         }))
     }
 }
 
-async fn send_serial_message_inner<S: DnsUdpSocket + Send>(
+#[allow(clippy::too_many_arguments)]
+async fn send_serial_message_inner<S: DnsUdpSocket + Send, T: Time>(
     msg: SerialMessage,
     msg_id: u16,
     verifier: Option<MessageVerifier>,
     socket: S,
     recv_buf_size: usize,
     case_randomization: bool,
+    duplicate_response_window: Option<Duration>,
     original_query: Option<&Query>,
 ) -> Result<DnsResponse, ProtoError> {
     let bytes = msg.bytes();
@@ -289,127 +317,189 @@ async fn send_serial_message_inner<S: DnsUdpSocket + Send>(
 
     // Create the receive buffer.
     trace!("creating UDP receive buffer with size {recv_buf_size}");
-    let mut recv_buf = vec![0; recv_buf_size];
+    let mut recv_buf = BufferPool::global().get();
+    recv_buf.resize(recv_buf_size, 0);
 
     // TODO: limit the max number of attempted messages? this relies on a timeout to die...
-    loop {
+    let response = loop {
         let (len, src) = socket.recv_from(&mut recv_buf).await?;
+        if let Some(response) = check_response(
+            &msg,
+            msg_id,
+            &recv_buf[0..len],
+            src,
+            case_randomization,
+            original_query,
+        )? {
+            break response;
+        }
+    };
+
+    // A spoofed response racing the genuine one may be accepted first, so keep listening for a
+    // little while: a second, different, response to the same query means one of them is forged,
+    // and as there is no telling which, both are discarded.
+    if let Some(window) = duplicate_response_window {
+        let mut deadline = Box::pin(T::delay_for(window));
+        loop {
+            let received = poll_fn(|cx| match socket.poll_recv_from(cx, &mut recv_buf) {
+                Poll::Ready(result) => Poll::Ready(Some(result)),
+                Poll::Pending => deadline.as_mut().poll(cx).map(|()| None),
+            })
+            .await;
+            let Some((len, src)) = received.transpose()? else {
+                break;
+            };
 
-        // Copy the slice of read bytes.
-        let response_bytes = &recv_buf[0..len];
-        let response_buffer = Vec::from(response_bytes);
-
-        // compare expected src to received packet
-        let request_target = msg.addr();
-
-        // Comparing the IP and Port directly as internal information about the link is stored with the IpAddr, see https://github.com/hickory-dns/hickory-dns/issues/2081
-        if src.ip() != request_target.ip() || src.port() != request_target.port() {
-            warn!(
-                "ignoring response from {} because it does not match name_server: {}.",
-                src, request_target,
+            let duplicate = check_response(
+                &msg,
+                msg_id,
+                &recv_buf[0..len],
+                src,
+                case_randomization,
+                original_query,
             );
-
-            // await an answer from the correct NameServer
-            continue;
+            if let Ok(Some(duplicate)) = duplicate {
+                if duplicate.as_buffer() != response.as_buffer() {
+                    warn!(
+                        security_event = "conflicting_responses",
+                        "received conflicting responses for message id: {msg_id} from server {src}, discarding them"
+                    );
+                    return Err(ProtoErrorKind::ConflictingResponses.into());
+                }
+            }
         }
+    }
 
-        let mut response = match DnsResponse::from_buffer(response_buffer) {
-            Ok(response) => response,
-            Err(e) => {
-                // on errors deserializing, continue
-                warn!("dropped malformed message waiting for id: {msg_id} err: {e}");
-                continue;
-            }
-        };
+    debug!("received message id: {}", response.id());
+    if let Some(mut verifier) = verifier {
+        verifier(response.as_buffer())
+    } else {
+        Ok(response)
+    }
+}
 
-        // Validate the message id in the response matches the value chosen for the query.
-        if msg_id != response.id() {
-            // on wrong id, attempted poison?
-            warn!(
-                "expected message id: {} got: {}, dropped",
-                msg_id,
-                response.id()
-            );
+/// Checks that a message received from `src` is a response to the request `msg`, returns `None`
+/// if it should be ignored
+fn check_response(
+    msg: &SerialMessage,
+    msg_id: u16,
+    response_bytes: &[u8],
+    src: SocketAddr,
+    case_randomization: bool,
+    original_query: Option<&Query>,
+) -> Result<Option<DnsResponse>, ProtoError> {
+    // Copy the slice of read bytes.
+    let response_buffer = Vec::from(response_bytes);
+
+    // compare expected src to received packet
+    let request_target = msg.addr();
+
+    // Comparing the IP and Port directly as internal information about the link is stored with the IpAddr, see https://github.com/hickory-dns/hickory-dns/issues/2081
+    if src.ip() != request_target.ip() || src.port() != request_target.port() {
+        warn!(
+            "ignoring response from {} because it does not match name_server: {}.",
+            src, request_target,
+        );
 
-            continue;
-        }
+        // await an answer from the correct NameServer
+        return Ok(None);
+    }
 
-        // Validate the returned query name.
-        //
-        // This currently checks that each response query name was present in the original query, but not that
-        // every original question is present.
-        //
-        // References:
-        //
-        // RFC 1035 7.3:
-        //
-        // The next step is to match the response to a current resolver request.
-        // The recommended strategy is to do a preliminary matching using the ID
-        // field in the domain header, and then to verify that the question section
-        // corresponds to the information currently desired.
-        //
-        // RFC 1035 7.4:
-        //
-        // In general, we expect a resolver to cache all data which it receives in
-        // responses since it may be useful in answering future client requests.
-        // However, there are several types of data which should not be cached:
-        //
-        // ...
-        //
-        //  - RR data in responses of dubious reliability.  When a resolver
-        // receives unsolicited responses or RR data other than that
-        // requested, it should discard it without caching it.
-        let request_message = Message::from_vec(msg.bytes())?;
-        let request_queries = request_message.queries();
-        let response_queries = response.queries_mut();
-
-        let question_matches = response_queries
-            .iter()
-            .all(|elem| request_queries.contains(elem));
-        if case_randomization
-            && question_matches
-            && !response_queries.iter().all(|elem| {
-                request_queries
-                    .iter()
-                    .any(|req_q| req_q == elem && req_q.name().eq_case(elem.name()))
-            })
-        {
-            warn!(
-                "case of question section did not match: we expected '{request_queries:?}', but received '{response_queries:?}' from server {src}"
-            );
-            return Err(ProtoErrorKind::QueryCaseMismatch.into());
-        }
-        if !question_matches {
-            warn!(
-                "detected forged question section: we expected '{request_queries:?}', but received '{response_queries:?}' from server {src}"
-            );
-            continue;
+    let mut response = match DnsResponse::from_buffer(response_buffer) {
+        Ok(response) => response,
+        Err(e) => {
+            // on errors deserializing, continue
+            warn!("dropped malformed message waiting for id: {msg_id} err: {e}");
+            return Ok(None);
         }
+    };
+
+    // Validate the message id in the response matches the value chosen for the query.
+    if msg_id != response.id() {
+        // on wrong id, attempted poison?
+        warn!(
+            "expected message id: {} got: {}, dropped",
+            msg_id,
+            response.id()
+        );
 
-        // overwrite the query with the original query if case randomization may have been used
-        if case_randomization {
-            if let Some(original_query) = original_query {
-                for response_query in response_queries.iter_mut() {
-                    if response_query == original_query {
-                        *response_query = original_query.clone();
-                    }
+        return Ok(None);
+    }
+
+    // Validate the returned query name.
+    //
+    // This currently checks that each response query name was present in the original query, but not that
+    // every original question is present.
+    //
+    // References:
+    //
+    // RFC 1035 7.3:
+    //
+    // The next step is to match the response to a current resolver request.
+    // The recommended strategy is to do a preliminary matching using the ID
+    // field in the domain header, and then to verify that the question section
+    // corresponds to the information currently desired.
+    //
+    // RFC 1035 7.4:
+    //
+    // In general, we expect a resolver to cache all data which it receives in
+    // responses since it may be useful in answering future client requests.
+    // However, there are several types of data which should not be cached:
+    //
+    // ...
+    //
+    //  - RR data in responses of dubious reliability.  When a resolver
+    // receives unsolicited responses or RR data other than that
+    // requested, it should discard it without caching it.
+    let request_message = Message::from_vec(msg.bytes())?;
+    let request_queries = request_message.queries();
+    let response_queries = response.queries_mut();
+
+    let question_matches = response_queries
+        .iter()
+        .all(|elem| request_queries.contains(elem));
+    if case_randomization
+        && question_matches
+        && !response_queries.iter().all(|elem| {
+            request_queries
+                .iter()
+                .any(|req_q| req_q == elem && req_q.name().eq_case(elem.name()))
+        })
+    {
+        warn!(
+            "case of question section did not match: we expected '{request_queries:?}', but received '{response_queries:?}' from server {src}"
+        );
+        return Err(ProtoErrorKind::QueryCaseMismatch.into());
+    }
+    if !question_matches {
+        warn!(
+            "detected forged question section: we expected '{request_queries:?}', but received '{response_queries:?}' from server {src}"
+        );
+        return Ok(None);
+    }
+
+    // overwrite the query with the original query if case randomization may have been used
+    if case_randomization {
+        if let Some(original_query) = original_query {
+            for response_query in response_queries.iter_mut() {
+                if response_query == original_query {
+                    *response_query = original_query.clone();
                 }
             }
         }
-
-        debug!("received message id: {}", response.id());
-        if let Some(mut verifier) = verifier {
-            return verifier(response_bytes);
-        } else {
-            return Ok(response);
-        }
     }
+
+    Ok(Some(response))
 }
 
 #[cfg(test)]
 #[cfg(feature = "tokio")]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
+    use super::*;
+    use crate::op::OpCode;
+    use crate::rr::{Name, RData, Record, RecordType, rdata::A};
+    use crate::xfer::{DnsRequestOptions, FirstAnswer};
     use crate::{runtime::TokioRuntimeProvider, tests::udp_client_stream_test};
     use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
     use test_support::subscribe;
@@ -427,4 +517,70 @@ mod tests {
         let provider = TokioRuntimeProvider::new();
         udp_client_stream_test(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), provider).await;
     }
+
+    /// Sends a query to a server answering it with a response for each of `addrs`
+    async fn duplicate_responses(addrs: Vec<Ipv4Addr>) -> Result<DnsResponse, ProtoError> {
+        let server = tokio::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let name = Name::from_ascii("www.example.com.").unwrap();
+
+        let server_name = name.clone();
+        tokio::spawn(async move {
+            let mut buf = [0; 512];
+            let (len, src) = server.recv_from(&mut buf).await.unwrap();
+            let request = Message::from_vec(&buf[..len]).unwrap();
+            for addr in addrs {
+                let mut response = Message::response(request.id(), OpCode::Query);
+                response.add_queries(request.queries().to_vec());
+                response.add_answer(Record::from_rdata(
+                    server_name.clone(),
+                    300,
+                    RData::A(A::from(addr)),
+                ));
+                server
+                    .send_to(&response.to_vec().unwrap(), src)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let mut stream = UdpClientStream::builder(server_addr, TokioRuntimeProvider::new())
+            .with_timeout(Some(Duration::from_secs(5)))
+            .build()
+            .await
+            .unwrap();
+
+        let mut query = Message::query();
+        query.add_query(Query::query(name, RecordType::A));
+        let options = DnsRequestOptions {
+            duplicate_response_window: Some(Duration::from_millis(200)),
+            ..DnsRequestOptions::default()
+        };
+        stream
+            .send_message(DnsRequest::new(query, options))
+            .first_answer()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_responses() {
+        subscribe();
+        let error = duplicate_responses(vec![
+            Ipv4Addr::new(192, 0, 2, 1),
+            Ipv4Addr::new(192, 0, 2, 2),
+        ])
+        .await
+        .unwrap_err();
+        assert!(matches!(error.kind(), ProtoErrorKind::ConflictingResponses));
+    }
+
+    #[tokio::test]
+    async fn test_identical_duplicate_responses() {
+        subscribe();
+        let addr = Ipv4Addr::new(192, 0, 2, 1);
+        let response = duplicate_responses(vec![addr, addr]).await.unwrap();
+        assert_eq!(response.answers()[0].data(), &RData::A(A::from(addr)));
+    }
 }
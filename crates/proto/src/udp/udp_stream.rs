@@ -8,6 +8,7 @@
 use alloc::boxed::Box;
 use alloc::sync::Arc;
 use core::future::poll_fn;
+use core::ops::RangeInclusive;
 use core::pin::Pin;
 use core::task::{Context, Poll};
 use std::collections::HashSet;
@@ -21,7 +22,7 @@ use tracing::{debug, trace, warn};
 
 use crate::runtime::{RuntimeProvider, Time};
 use crate::udp::MAX_RECEIVE_BUFFER_SIZE;
-use crate::xfer::{BufDnsStreamHandle, SerialMessage, StreamReceiver};
+use crate::xfer::{BufDnsStreamHandle, BufferPool, SerialMessage, StreamReceiver};
 
 /// Trait for DnsUdpSocket
 #[async_trait]
@@ -125,6 +126,7 @@ impl<P: RuntimeProvider> UdpStream<P> {
             remote_addr,
             bind_addr,
             avoid_local_ports.unwrap_or_default(),
+            DEFAULT_LOCAL_PORT_RANGE,
             os_port_selection,
             provider,
         );
@@ -214,10 +216,11 @@ impl<P: RuntimeProvider> Stream for UdpStream<P> {
         // receive all inbound messages
 
         // TODO: this should match edns settings
-        let mut buf = [0u8; MAX_RECEIVE_BUFFER_SIZE];
+        let mut buf = BufferPool::global().get();
+        buf.resize(MAX_RECEIVE_BUFFER_SIZE, 0);
         let (len, src) = ready!(socket.poll_recv_from(cx, &mut buf))?;
 
-        let serial_message = SerialMessage::new(buf.iter().take(len).cloned().collect(), src);
+        let serial_message = SerialMessage::new(buf[..len].to_vec(), src);
         Poll::Ready(Some(Ok(serial_message)))
     }
 }
@@ -232,6 +235,7 @@ pub(crate) struct NextRandomUdpSocket<P: RuntimeProvider> {
     #[allow(clippy::type_complexity)]
     future: Option<Pin<Box<dyn Send + Future<Output = io::Result<P::Udp>>>>>,
     avoid_local_ports: Arc<HashSet<u16>>,
+    local_port_range: RangeInclusive<u16>,
     os_port_selection: bool,
 }
 
@@ -244,6 +248,7 @@ impl<P: RuntimeProvider> NextRandomUdpSocket<P> {
         name_server: SocketAddr,
         bind_addr: Option<SocketAddr>,
         avoid_local_ports: Arc<HashSet<u16>>,
+        local_port_range: RangeInclusive<u16>,
         os_port_selection: bool,
         provider: P,
     ) -> Self {
@@ -262,6 +267,7 @@ impl<P: RuntimeProvider> NextRandomUdpSocket<P> {
             attempted: 0,
             future: None,
             avoid_local_ports,
+            local_port_range,
             os_port_selection,
         }
     }
@@ -304,13 +310,20 @@ impl<P: RuntimeProvider> Future for NextRandomUdpSocket<P> {
                     let mut bind_addr = this.bind_address;
 
                     if !this.os_port_selection && bind_addr.port() == 0 {
+                        if this.local_port_range.is_empty() {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "empty local port range",
+                            )));
+                        }
+
                         while this.attempted < ATTEMPT_RANDOM {
                             // Per RFC 6056 Section 3.2:
                             //
                             // As mentioned in Section 2.1, the dynamic ports consist of the range
                             // 49152-65535.  However, ephemeral port selection algorithms should use
-                            // the whole range 1024-65535.
-                            let port = rand::random_range(1024..=u16::MAX);
+                            // the whole range 1024-65535, which is the default.
+                            let port = rand::random_range(this.local_port_range.clone());
                             if this.avoid_local_ports.contains(&port) {
                                 // Count this against the total number of attempts to pick a port.
                                 // RFC 6056 Section 3.3.2 notes that this algorithm should find a
@@ -338,6 +351,9 @@ impl<P: RuntimeProvider> Future for NextRandomUdpSocket<P> {
 
 const ATTEMPT_RANDOM: usize = 10;
 
+/// The range of local ports to pick from for outgoing queries, unless configured otherwise
+pub(crate) const DEFAULT_LOCAL_PORT_RANGE: RangeInclusive<u16> = 1024..=u16::MAX;
+
 #[cfg(feature = "tokio")]
 #[async_trait]
 impl UdpSocket for tokio::net::UdpSocket {
@@ -398,10 +414,13 @@ impl DnsUdpSocket for tokio::net::UdpSocket {
 #[cfg(test)]
 #[cfg(feature = "tokio")]
 mod tests {
-    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use alloc::sync::Arc;
+    use std::io;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
     use test_support::subscribe;
 
+    use super::NextRandomUdpSocket;
     use crate::{
         runtime::TokioRuntimeProvider,
         tests::{next_random_socket_test, udp_stream_test},
@@ -427,4 +446,60 @@ mod tests {
         let provider = TokioRuntimeProvider::new();
         udp_stream_test(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), provider).await;
     }
+
+    #[tokio::test]
+    async fn test_next_random_socket_port_range() {
+        subscribe();
+        let name_server = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 52);
+        let socket = NextRandomUdpSocket::new(
+            name_server,
+            None,
+            Arc::default(),
+            50000..=50100,
+            false,
+            TokioRuntimeProvider::new(),
+        )
+        .await
+        .expect("failed to bind socket");
+
+        let port = socket.local_addr().unwrap().port();
+        assert!((50000..=50100).contains(&port), "port: {port}");
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let error = NextRandomUdpSocket::new(
+            name_server,
+            None,
+            Arc::default(),
+            2000..=1000,
+            false,
+            TokioRuntimeProvider::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_next_random_socket_options() {
+        subscribe();
+        let socket_options = crate::runtime::SocketOptions {
+            dscp: Some(46),
+            ..Default::default()
+        };
+        let provider = TokioRuntimeProvider::new().with_socket_options(socket_options);
+
+        let socket = NextRandomUdpSocket::new(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 52),
+            None,
+            Arc::default(),
+            super::DEFAULT_LOCAL_PORT_RANGE,
+            false,
+            provider,
+        )
+        .await
+        .expect("failed to bind socket");
+
+        assert_eq!(socket2::SockRef::from(&socket).tos().unwrap(), 46 << 2);
+    }
 }
@@ -0,0 +1,260 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A pool of reusable byte buffers shared by the transports and the message encoder
+
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::udp::MAX_RECEIVE_BUFFER_SIZE;
+
+/// Default number of idle buffers retained by each thread
+pub const DEFAULT_MAX_POOLED_BUFFERS: usize = 64;
+
+/// Default capacity of scratch buffers handed out by [`BufferPool::get`]
+pub const DEFAULT_BUFFER_CAPACITY: usize = MAX_RECEIVE_BUFFER_SIZE;
+
+/// Buffers larger than this are never retained, a DNS message can not exceed it (plus the TCP length prefix)
+const MAX_RETAINED_CAPACITY: usize = u16::MAX as usize + 2;
+
+static GLOBAL_POOL: BufferPool = BufferPool {
+    max_buffers: AtomicUsize::new(DEFAULT_MAX_POOLED_BUFFERS),
+    buffer_capacity: AtomicUsize::new(DEFAULT_BUFFER_CAPACITY),
+};
+
+std::thread_local! {
+    static IDLE_BUFFERS: RefCell<IdleBuffers> = const { RefCell::new(IdleBuffers::new()) };
+}
+
+/// A pool of byte buffers, used to avoid allocating and freeing a buffer for every message
+///
+/// The UDP transports as well as [`crate::op::Message::to_vec`] draw scratch buffers from the
+/// [`BufferPool::global`] pool. Buffers are only handed out as a [`PooledBuffer`], which returns
+/// them to the pool when dropped, so that pooled buffers with a large capacity are never held
+/// beyond the use of a scratch buffer.
+///
+/// Every thread keeps its own idle buffers, so taking and returning a buffer never waits on
+/// another thread. The limits are shared by all threads.
+#[derive(Debug)]
+pub struct BufferPool {
+    max_buffers: AtomicUsize,
+    buffer_capacity: AtomicUsize,
+}
+
+impl BufferPool {
+    /// The process wide pool shared by all transports
+    pub fn global() -> &'static Self {
+        &GLOBAL_POOL
+    }
+
+    /// Get a scratch buffer with at least [`Self::buffer_capacity`] bytes of capacity
+    ///
+    /// The buffer is empty, and is returned to the pool when dropped.
+    pub fn get(&self) -> PooledBuffer<'_> {
+        PooledBuffer {
+            buffer: self.take(self.buffer_capacity()),
+            pool: self,
+        }
+    }
+
+    /// Take an empty buffer with at least `min_capacity` bytes of capacity out of the pool
+    ///
+    /// If no idle buffer of this thread is large enough, a new one is allocated.
+    fn take(&self, min_capacity: usize) -> Vec<u8> {
+        IDLE_BUFFERS
+            .try_with(|idle| idle.borrow_mut().take(min_capacity))
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| Vec::with_capacity(min_capacity))
+    }
+
+    /// Return a buffer to the pool of the current thread
+    ///
+    /// The buffer is cleared. It is dropped instead if the pool is full, or if its capacity is zero
+    /// or larger than any DNS message.
+    fn recycle(&self, buffer: Vec<u8>) {
+        let max_buffers = self.max_buffers();
+        // the buffer is dropped if the thread is shutting down
+        let _ = IDLE_BUFFERS.try_with(|idle| idle.borrow_mut().recycle(buffer, max_buffers));
+    }
+
+    /// The maximum number of idle buffers retained by each thread
+    pub fn max_buffers(&self) -> usize {
+        self.max_buffers.load(Ordering::Relaxed)
+    }
+
+    /// Set the maximum number of idle buffers retained by each thread
+    ///
+    /// Lowering the limit releases the idle buffers above it, those of the current thread at once
+    /// and those of other threads when they next return a buffer. `0` disables pooling.
+    pub fn set_max_buffers(&self, max_buffers: usize) {
+        self.max_buffers.store(max_buffers, Ordering::Relaxed);
+        let _ = IDLE_BUFFERS.try_with(|idle| idle.borrow_mut().buffers.truncate(max_buffers));
+    }
+
+    /// The capacity of scratch buffers handed out by [`Self::get`]
+    pub fn buffer_capacity(&self) -> usize {
+        self.buffer_capacity.load(Ordering::Relaxed)
+    }
+
+    /// Set the capacity of scratch buffers handed out by [`Self::get`]
+    pub fn set_buffer_capacity(&self, buffer_capacity: usize) {
+        self.buffer_capacity.store(
+            buffer_capacity.min(MAX_RETAINED_CAPACITY),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// The number of idle buffers currently held for the current thread
+    pub fn len(&self) -> usize {
+        IDLE_BUFFERS
+            .try_with(|idle| idle.borrow().buffers.len())
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the current thread holds no idle buffers
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The idle buffers of one thread
+#[derive(Debug)]
+struct IdleBuffers {
+    buffers: Vec<Vec<u8>>,
+}
+
+impl IdleBuffers {
+    const fn new() -> Self {
+        Self {
+            buffers: Vec::new(),
+        }
+    }
+
+    fn take(&mut self, min_capacity: usize) -> Option<Vec<u8>> {
+        let idx = self
+            .buffers
+            .iter()
+            .position(|b| b.capacity() >= min_capacity)?;
+        Some(self.buffers.swap_remove(idx))
+    }
+
+    fn recycle(&mut self, mut buffer: Vec<u8>, max_buffers: usize) {
+        let capacity = buffer.capacity();
+        if capacity == 0 || capacity > MAX_RETAINED_CAPACITY {
+            return;
+        }
+
+        self.buffers.truncate(max_buffers);
+        if self.buffers.len() < max_buffers {
+            buffer.clear();
+            self.buffers.push(buffer);
+        }
+    }
+}
+
+/// A buffer borrowed from a [`BufferPool`], returned to the pool on drop
+#[derive(Debug)]
+pub struct PooledBuffer<'p> {
+    buffer: Vec<u8>,
+    pool: &'p BufferPool,
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        self.pool.recycle(core::mem::take(&mut self.buffer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_reuse() {
+        // a new thread starts without idle buffers
+        thread::spawn(|| {
+            let pool = BufferPool::global();
+            assert!(pool.is_empty());
+
+            let mut buf = pool.get();
+            assert!(buf.capacity() >= pool.buffer_capacity());
+            buf.extend_from_slice(b"hello");
+            let ptr = buf.as_ptr();
+            drop(buf);
+            assert_eq!(pool.len(), 1);
+
+            let buf = pool.get();
+            assert!(buf.is_empty());
+            assert_eq!(buf.as_ptr(), ptr);
+            assert!(pool.is_empty());
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_per_thread() {
+        thread::spawn(|| {
+            BufferPool::global().recycle(Vec::with_capacity(512));
+            assert_eq!(BufferPool::global().len(), 1);
+        })
+        .join()
+        .unwrap();
+
+        // the buffer returned on the other thread is not seen here
+        thread::spawn(|| assert!(BufferPool::global().is_empty()))
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_take_capacity() {
+        let mut idle = IdleBuffers::new();
+        idle.recycle(Vec::with_capacity(16), 4);
+        idle.recycle(Vec::with_capacity(1024), 4);
+
+        assert!(idle.take(100).unwrap().capacity() >= 1024);
+        assert_eq!(idle.buffers.len(), 1);
+        assert!(idle.take(100).is_none());
+        assert_eq!(idle.buffers.len(), 1);
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut idle = IdleBuffers::new();
+        idle.recycle(Vec::with_capacity(512), 1);
+        idle.recycle(Vec::with_capacity(512), 1);
+        assert_eq!(idle.buffers.len(), 1);
+
+        idle.recycle(Vec::with_capacity(MAX_RETAINED_CAPACITY + 1), 2);
+        idle.recycle(Vec::new(), 2);
+        assert_eq!(idle.buffers.len(), 1);
+
+        // a lowered limit releases the buffers above it
+        idle.recycle(Vec::with_capacity(512), 0);
+        assert!(idle.buffers.is_empty());
+    }
+}
@@ -18,7 +18,7 @@ use futures_util::future::FutureExt;
 use futures_util::stream::{Peekable, Stream, StreamExt};
 use tracing::debug;
 
-#[cfg(all(feature = "__https", feature = "tokio"))]
+#[cfg(all(feature = "__https", feature = "__tls", feature = "tokio"))]
 use crate::h2::{HttpsClientConnect, HttpsClientStream};
 #[cfg(all(feature = "__h3", feature = "tokio"))]
 use crate::h3::{H3ClientConnect, H3ClientStream};
@@ -78,7 +78,7 @@ pub enum Connecting<R: RuntimeProvider> {
             TokioTime,
         >,
     ),
-    #[cfg(all(feature = "__https", feature = "tokio"))]
+    #[cfg(all(feature = "__https", feature = "__tls", feature = "tokio"))]
     Https(DnsExchangeConnect<HttpsClientConnect<R::Tcp>, HttpsClientStream, TokioTime>),
     #[cfg(all(feature = "__quic", feature = "tokio"))]
     Quic(DnsExchangeConnect<QuicClientConnect, QuicClientStream, TokioTime>),
@@ -66,7 +66,7 @@ pub trait DnsHandle: 'static + Clone + Send + Sync + Unpin {
     #[cfg(any(feature = "std", feature = "no-std-rand"))]
     fn lookup(&self, query: Query, options: DnsRequestOptions) -> Self::Response {
         debug!("querying: {} {:?}", query.name(), query.query_type());
-        self.send(build_request(query, options))
+        self.send(DnsRequest::from_query(query, options))
     }
 
     /// A *classic* DNS query
@@ -81,32 +81,35 @@ pub trait DnsHandle: 'static + Clone + Send + Sync + Unpin {
     fn lookup(&self, query: Query, options: DnsRequestOptions) -> Self::Response;
 }
 
-#[cfg_attr(not(any(feature = "std", feature = "no-std-rand")), expect(unused_mut))]
-#[cfg(any(feature = "std", feature = "no-std-rand"))]
-fn build_request(mut query: Query, options: DnsRequestOptions) -> DnsRequest {
-    // build the message
-    let mut message = Message::query();
-    let mut original_query = None;
-
-    #[cfg(feature = "std")]
-    if options.case_randomization {
-        original_query = Some(query.clone());
-        query.name.randomize_label_case();
-    }
+impl DnsRequest {
+    /// Builds a request for `query`, as sent by [`DnsHandle::lookup`]
+    #[cfg_attr(not(feature = "std"), expect(unused_mut))]
+    #[cfg(any(feature = "std", feature = "no-std-rand"))]
+    pub fn from_query(mut query: Query, options: DnsRequestOptions) -> Self {
+        // build the message
+        let mut message = Message::query();
+        let mut original_query = None;
 
-    message
-        .add_query(query)
-        .set_recursion_desired(options.recursion_desired);
+        #[cfg(feature = "std")]
+        if options.case_randomization {
+            original_query = Some(query.clone());
+            query.name.randomize_label_case();
+        }
 
-    // Extended dns
-    if options.use_edns {
         message
-            .extensions_mut()
-            .get_or_insert_with(Edns::new)
-            .set_max_payload(MAX_PAYLOAD_LEN)
-            .set_version(0)
-            .set_dnssec_ok(options.edns_set_dnssec_ok);
+            .add_query(query)
+            .set_recursion_desired(options.recursion_desired);
+
+        // Extended dns
+        if options.use_edns {
+            message
+                .extensions_mut()
+                .get_or_insert_with(Edns::new)
+                .set_max_payload(MAX_PAYLOAD_LEN)
+                .set_version(0)
+                .set_dnssec_ok(options.edns_set_dnssec_ok);
+        }
+
+        Self::new(message, options).with_original_query(original_query)
     }
-
-    DnsRequest::new(message, options).with_original_query(original_query)
 }
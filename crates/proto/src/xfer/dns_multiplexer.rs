@@ -94,6 +94,10 @@ impl ActiveRequest {
 /// This Client is generic and capable of wrapping UDP, TCP, and other underlying DNS protocol
 ///  implementations. This should be used for underlying protocols that do not natively support
 ///  multiplexed sessions.
+///
+/// Requests are pipelined, any number of them, up to the configured maximum, may be outstanding
+///  at the same time. Responses are matched to their requests by ID, in whichever order they
+///  arrive, as described in [RFC 7766](https://tools.ietf.org/html/rfc7766#section-6.2.1).
 #[must_use = "futures do nothing unless polled"]
 pub struct DnsMultiplexer<S>
 where
@@ -103,6 +107,7 @@ where
     timeout_duration: Duration,
     stream_handle: BufDnsStreamHandle,
     active_requests: HashMap<u16, ActiveRequest>,
+    max_active_requests: usize,
     signer: Option<Arc<dyn MessageSigner>>,
     is_shutdown: bool,
 }
@@ -154,6 +159,7 @@ where
             stream,
             stream_handle: Some(stream_handle),
             timeout_duration,
+            max_active_requests: CHANNEL_BUFFER_SIZE,
             signer,
         }
     }
@@ -224,9 +230,25 @@ where
     stream: F,
     stream_handle: Option<BufDnsStreamHandle>,
     timeout_duration: Duration,
+    max_active_requests: usize,
     signer: Option<Arc<dyn MessageSigner>>,
 }
 
+impl<F, S> DnsMultiplexerConnect<F, S>
+where
+    F: Future<Output = Result<S, ProtoError>> + Send + Unpin + 'static,
+    S: Stream<Item = Result<SerialMessage, ProtoError>> + Unpin,
+{
+    /// Sets the maximum number of requests which may be outstanding on the connection at once
+    ///
+    /// Further requests fail with [`ProtoErrorKind::Busy`] until outstanding requests are
+    /// finished, i.e. their responses were dropped or they timed out. The default is 32.
+    pub fn with_max_active_requests(mut self, max_active_requests: usize) -> Self {
+        self.max_active_requests = max_active_requests;
+        self
+    }
+}
+
 impl<F, S> Future for DnsMultiplexerConnect<F, S>
 where
     F: Future<Output = Result<S, ProtoError>> + Send + Unpin + 'static,
@@ -245,6 +267,7 @@ where
                 .take()
                 .expect("must not poll after complete"),
             active_requests: HashMap::new(),
+            max_active_requests: self.max_active_requests,
             signer: self.signer.clone(),
             is_shutdown: false,
         }))
@@ -269,7 +292,7 @@ where
             panic!("can not send messages after stream is shutdown")
         }
 
-        if self.active_requests.len() > CHANNEL_BUFFER_SIZE {
+        if self.active_requests.len() >= self.max_active_requests {
             return ProtoError::from(ProtoErrorKind::Busy).into();
         }
 
@@ -278,6 +301,7 @@ where
             Err(e) => return e.into(),
         };
 
+        let signer = request.signer().or(self.signer.as_ref()).cloned();
         let (mut request, _) = request.into_parts();
         request.set_id(query_id);
 
@@ -290,7 +314,7 @@ where
         let now = now as u32;
 
         let mut verifier = None;
-        if let Some(signer) = &self.signer {
+        if let Some(signer) = &signer {
             if signer.should_sign_message(&request) {
                 match request.finalize(signer.borrow(), now) {
                     Ok(answer_verifier) => verifier = answer_verifier,
@@ -444,7 +468,7 @@ mod test {
     use crate::rr::{DNSClass, Name, RData, Record};
     use crate::serialize::binary::BinEncodable;
     use crate::xfer::StreamReceiver;
-    use crate::xfer::{DnsClientStream, DnsRequestOptions};
+    use crate::xfer::{DnsClientStream, DnsRequestOptions, FirstAnswer};
 
     struct MockClientStream {
         messages: Vec<Message>,
@@ -705,4 +729,162 @@ mod test {
             axfr_response().len()
         );
     }
+
+    /// Answers once `pending` requests have been received, most recent first
+    struct PipelinedMockStream {
+        addr: SocketAddr,
+        pending: usize,
+        requests: Vec<Message>,
+        receiver: StreamReceiver,
+    }
+
+    impl fmt::Display for PipelinedMockStream {
+        fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+            write!(formatter, "PipelinedMockStream")
+        }
+    }
+
+    impl Stream for PipelinedMockStream {
+        type Item = Result<SerialMessage, ProtoError>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            while self.pending > 0 {
+                let serial = ready!(self.receiver.poll_next_unpin(cx)).unwrap();
+                self.requests.push(serial.to_message().unwrap());
+                self.pending -= 1;
+            }
+
+            match self.requests.pop() {
+                Some(request) => {
+                    let name = request.queries()[0].name().clone();
+                    let mut response = request.to_response();
+                    response.add_answer(Record::from_rdata(
+                        name,
+                        86400,
+                        RData::A(Ipv4Addr::new(127, 0, 0, request.id() as u8).into()),
+                    ));
+                    Poll::Ready(Some(Ok(SerialMessage::new(
+                        response.to_bytes().unwrap(),
+                        self.addr,
+                    ))))
+                }
+                None => Poll::Pending,
+            }
+        }
+    }
+
+    impl DnsClientStream for PipelinedMockStream {
+        type Time = crate::runtime::TokioTime;
+
+        fn name_server_addr(&self) -> SocketAddr {
+            self.addr
+        }
+    }
+
+    fn pipelined_multiplexer(
+        pending: usize,
+        max_active_requests: usize,
+    ) -> DnsMultiplexerConnect<
+        future::Ready<Result<PipelinedMockStream, ProtoError>>,
+        PipelinedMockStream,
+    > {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 1234));
+        let (handler, receiver) = BufDnsStreamHandle::new(addr);
+        let stream = PipelinedMockStream {
+            addr,
+            pending,
+            requests: Vec::new(),
+            receiver,
+        };
+
+        DnsMultiplexer::with_timeout(future::ok(stream), handler, Duration::from_secs(5), None)
+            .with_max_active_requests(max_active_requests)
+    }
+
+    fn a_query(name: &str) -> DnsRequest {
+        let mut msg = Message::query();
+        msg.add_query(Query::query(Name::from_ascii(name).unwrap(), RecordType::A));
+        DnsRequest::new(msg, DnsRequestOptions::default())
+    }
+
+    #[tokio::test]
+    async fn test_multiplexer_pipelined_out_of_order() {
+        subscribe();
+        let names = ["a.example.com.", "b.example.com.", "c.example.com."];
+        let mut multiplexer = pipelined_multiplexer(names.len(), names.len())
+            .await
+            .unwrap();
+
+        let responses = names
+            .iter()
+            .map(|name| multiplexer.send_message(a_query(name)).first_answer())
+            .collect::<Vec<_>>();
+        assert_eq!(multiplexer.active_requests.len(), names.len());
+
+        let responses = tokio::select! {
+            _ = multiplexer.next() => {
+                // polling multiplexer to make it run
+                panic!("should never end")
+            },
+            r = future::try_join_all(responses) => r.unwrap(),
+        };
+
+        for (name, response) in names.iter().zip(responses) {
+            assert_eq!(response.queries()[0].name().to_ascii(), *name);
+            assert_eq!(response.answers().len(), 1);
+        }
+
+        // the requests are finished once their response streams are dropped
+        assert_eq!(multiplexer.active_requests.len(), names.len());
+        future::poll_fn(|cx| {
+            let _ = multiplexer.poll_next_unpin(cx);
+            Poll::Ready(())
+        })
+        .await;
+        assert!(multiplexer.active_requests.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_multiplexer_max_active_requests() {
+        subscribe();
+        let mut multiplexer = pipelined_multiplexer(2, 2).await.unwrap();
+
+        let _first = multiplexer.send_message(a_query("a.example.com."));
+        let _second = multiplexer.send_message(a_query("b.example.com."));
+        let third = multiplexer
+            .send_message(a_query("c.example.com."))
+            .try_collect::<Vec<_>>()
+            .await;
+
+        assert!(matches!(third.unwrap_err().kind(), ProtoErrorKind::Busy));
+    }
+
+    #[tokio::test]
+    async fn test_multiplexer_default_max_active_requests() {
+        subscribe();
+        let addr = SocketAddr::from(([127, 0, 0, 1], 1234));
+        let (handler, receiver) = BufDnsStreamHandle::new(addr);
+        let stream = PipelinedMockStream {
+            addr,
+            pending: usize::MAX,
+            requests: Vec::new(),
+            receiver,
+        };
+        let mut multiplexer =
+            DnsMultiplexer::with_timeout(future::ok(stream), handler, Duration::from_secs(5), None)
+                .await
+                .unwrap();
+
+        // exactly the default of 32 requests may be outstanding
+        let _outstanding = (0..CHANNEL_BUFFER_SIZE)
+            .map(|_| multiplexer.send_message(a_query("a.example.com.")))
+            .collect::<Vec<_>>();
+        assert_eq!(multiplexer.active_requests.len(), 32);
+
+        let busy = multiplexer
+            .send_message(a_query("b.example.com."))
+            .try_collect::<Vec<_>>()
+            .await;
+        assert!(matches!(busy.unwrap_err().kind(), ProtoErrorKind::Busy));
+    }
 }
@@ -7,12 +7,16 @@
 
 //! `DnsRequest` wraps a `Message` and associates a set of `DnsRequestOptions` for specifying different transfer options.
 
+use alloc::sync::Arc;
 use core::ops::{Deref, DerefMut};
 
-use crate::op::{Message, Query};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::op::{Message, MessageSigner, Query};
 
 /// A set of options for expressing options to how requests should be treated
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub struct DnsRequestOptions {
     /// When true, the underlying DNS protocols will not return on the first response received.
@@ -34,6 +38,16 @@ pub struct DnsRequestOptions {
     /// Randomize case of query name, and check that the response matches, for spoofing resistance.
     #[cfg(feature = "std")]
     pub case_randomization: bool,
+    /// How long to keep listening for further responses over UDP once a response was accepted.
+    ///
+    /// A second response to the same query with different contents is a sign of a spoofing
+    /// attempt, in which case both are discarded and the request fails with
+    /// [`ProtoErrorKind::ConflictingResponses`](crate::error::ProtoErrorKind::ConflictingResponses).
+    /// The response is delayed by this duration, `None`, the default, disables the check.
+    #[cfg(feature = "std")]
+    pub duplicate_response_window: Option<core::time::Duration>,
+    /// How to handle a response with the TC (truncation) bit set
+    pub truncation_policy: TruncationPolicy,
 }
 
 impl Default for DnsRequestOptions {
@@ -47,19 +61,39 @@ impl Default for DnsRequestOptions {
             recursion_desired: true,
             #[cfg(feature = "std")]
             case_randomization: false,
+            #[cfg(feature = "std")]
+            duplicate_response_window: None,
+            truncation_policy: TruncationPolicy::default(),
         }
     }
 }
 
+/// The handling of truncated responses, those with the TC bit set
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[non_exhaustive]
+pub enum TruncationPolicy {
+    /// Retry the request over TCP. This is the default.
+    #[default]
+    RetryTcp,
+    /// Return the truncated response as the answer
+    ReturnTruncated,
+    /// Retry the request over UDP, advertising a larger EDNS payload size, and only retry over
+    /// TCP if the response is still truncated
+    RetryLargerEdns,
+}
+
 /// A DNS request object
 ///
 /// This wraps a DNS Message for requests. It also has request options associated for controlling certain features of the DNS protocol handlers.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct DnsRequest {
     message: Message,
     options: DnsRequestOptions,
     /// If case randomization was replied to the request, this holds the original query.
     original_query: Option<Query>,
+    /// Signs this request instead of the signer of the connection, if any.
+    signer: Option<Arc<dyn MessageSigner>>,
 }
 
 impl DnsRequest {
@@ -69,6 +103,7 @@ impl DnsRequest {
             message,
             options,
             original_query: None,
+            signer: None,
         }
     }
 
@@ -78,6 +113,14 @@ impl DnsRequest {
         self
     }
 
+    /// Sign the request with `signer`, instead of the signer configured for the connection
+    ///
+    /// The request is signed when it is sent, after its final ID has been assigned.
+    pub fn with_signer(mut self, signer: Option<Arc<dyn MessageSigner>>) -> Self {
+        self.signer = signer;
+        self
+    }
+
     /// Get the set of request options associated with this request
     pub fn options(&self) -> &DnsRequestOptions {
         &self.options
@@ -92,8 +135,24 @@ impl DnsRequest {
     pub fn original_query(&self) -> Option<&Query> {
         self.original_query.as_ref()
     }
+
+    /// Get the signer of this request, if it overrides the one of the connection
+    pub fn signer(&self) -> Option<&Arc<dyn MessageSigner>> {
+        self.signer.as_ref()
+    }
 }
 
+// the signer is not part of the request's identity
+impl PartialEq for DnsRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.message == other.message
+            && self.options == other.options
+            && self.original_query == other.original_query
+    }
+}
+
+impl Eq for DnsRequest {}
+
 impl Deref for DnsRequest {
     type Target = Message;
     fn deref(&self) -> &Self::Target {
@@ -31,6 +31,8 @@ use crate::error::{ProtoError, ProtoErrorKind};
 #[cfg(feature = "std")]
 use crate::runtime::Time;
 
+#[cfg(feature = "std")]
+pub mod buffer_pool;
 #[cfg(feature = "std")]
 mod dns_exchange;
 pub mod dns_handle;
@@ -41,6 +43,8 @@ pub mod dns_response;
 pub mod retry_dns_handle;
 mod serial_message;
 
+#[cfg(feature = "std")]
+pub use self::buffer_pool::{BufferPool, PooledBuffer};
 #[cfg(feature = "std")]
 pub use self::dns_exchange::{
     Connecting, DnsExchange, DnsExchangeBackground, DnsExchangeConnect, DnsExchangeSend,
@@ -48,7 +52,7 @@ pub use self::dns_exchange::{
 pub use self::dns_handle::{DnsHandle, DnsStreamHandle};
 #[cfg(feature = "std")]
 pub use self::dns_multiplexer::{DnsMultiplexer, DnsMultiplexerConnect};
-pub use self::dns_request::{DnsRequest, DnsRequestOptions};
+pub use self::dns_request::{DnsRequest, DnsRequestOptions, TruncationPolicy};
 pub use self::dns_response::DnsResponse;
 #[cfg(feature = "std")]
 pub use self::dns_response::DnsResponseStream;
@@ -341,7 +345,7 @@ pub enum Protocol {
     /// TCP can be used for large queries, but not all NameServers support it
     Tcp,
     /// Tls for DNS over TLS
-    #[cfg(feature = "__tls")]
+    #[cfg(any(feature = "__tls", feature = "native-tls"))]
     Tls,
     /// Https for DNS over HTTPS
     #[cfg(feature = "__https")]
@@ -370,7 +374,7 @@ impl Protocol {
         match self {
             Self::Udp => false,
             Self::Tcp => false,
-            #[cfg(feature = "__tls")]
+            #[cfg(any(feature = "__tls", feature = "native-tls"))]
             Self::Tls => true,
             #[cfg(feature = "__https")]
             Self::Https => true,
@@ -387,7 +391,7 @@ impl fmt::Display for Protocol {
         f.write_str(match self {
             Self::Udp => "udp",
             Self::Tcp => "tcp",
-            #[cfg(feature = "__tls")]
+            #[cfg(any(feature = "__tls", feature = "native-tls"))]
             Self::Tls => "tls",
             #[cfg(feature = "__https")]
             Self::Https => "https",
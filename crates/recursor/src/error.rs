@@ -114,6 +114,14 @@ impl Error {
         matches!(proto_error.kind(), ProtoErrorKind::Timeout)
     }
 
+    /// Returns true if none of the name servers of a zone could answer
+    pub fn is_no_reachable_authority(&self) -> bool {
+        match &*self.kind {
+            ErrorKind::Proto(proto) => proto.is_no_reachable_authority(),
+            _ => false,
+        }
+    }
+
     /// Returns the SOA record, if the error contains one
     pub fn into_soa(self) -> Option<Box<Record<SOA>>> {
         match *self.kind {
@@ -30,6 +30,8 @@ mod error;
 mod recursor;
 mod recursor_dns_handle;
 pub(crate) mod recursor_pool;
+mod server_failures;
+mod trace;
 
 #[cfg(feature = "__dnssec")]
 use std::sync::Arc;
@@ -46,6 +48,7 @@ use proto::{
 };
 pub use recursor::{Recursor, RecursorBuilder};
 use resolver::Name;
+pub use trace::{Delegation, DelegationSecurity, TraceStep};
 use tracing::warn;
 
 /// `Recursor`'s DNSSEC policy
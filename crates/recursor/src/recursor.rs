@@ -9,13 +9,13 @@ use std::{
     collections::HashSet,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     sync::{Arc, atomic::AtomicU8},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use ipnet::IpNet;
 
 use crate::{
-    DnssecPolicy, Error,
+    DnssecPolicy, Error, TraceStep,
     proto::{
         op::{Message, Query},
         runtime::TokioRuntimeProvider,
@@ -53,6 +53,7 @@ pub struct RecursorBuilder<P: ConnectionProvider> {
     avoid_local_udp_ports: HashSet<u16>,
     ttl_config: TtlConfig,
     case_randomization: bool,
+    server_damping: Duration,
     conn_provider: P,
 }
 
@@ -125,6 +126,17 @@ impl<P: ConnectionProvider> RecursorBuilder<P> {
         self
     }
 
+    /// Sets how long a name server is avoided for a zone after it failed to answer for it
+    ///
+    /// Servers which time out, refuse queries, or answer with a referral away from the zone they
+    /// were delegated for are not queried again for that zone until this long after their last
+    /// failure, unless all the other servers of the zone failed as well. A duration of zero, the
+    /// default, disables this.
+    pub fn server_damping(mut self, damping: Duration) -> Self {
+        self.server_damping = damping;
+        self
+    }
+
     /// Construct a new recursor using the list of root zone name server addresses
     ///
     /// # Panics
@@ -166,10 +178,33 @@ impl<P: ConnectionProvider> Recursor<P> {
             avoid_local_udp_ports: HashSet::new(),
             ttl_config: TtlConfig::default(),
             case_randomization: false,
+            server_damping: Duration::ZERO,
             conn_provider,
         }
     }
 
+    /// Follows the delegations from the roots to the name servers of `query`, like `dig +trace`
+    ///
+    /// Returns the response of the name servers of each zone, starting with the roots. The
+    /// responses of all but the last step are referrals to a child zone, along with the DNSSEC
+    /// status of the delegation. The queries of the steps are always sent, bypassing the cache,
+    /// but the addresses of name servers without glue may be resolved from the cache.
+    pub async fn trace(
+        &self,
+        query: Query,
+        request_time: Instant,
+    ) -> Result<Vec<TraceStep>, Error> {
+        if !query.name().is_fqdn() {
+            return Err(Error::from("query's domain name must be fully qualified"));
+        }
+
+        match &self.mode {
+            RecursorMode::NonValidating { handle } => handle.trace(query, request_time).await,
+            #[cfg(feature = "__dnssec")]
+            RecursorMode::Validating { recursor, .. } => recursor.trace(query, request_time).await,
+        }
+    }
+
     /// Whether the recursive resolver is a validating resolver
     pub fn is_validating(&self) -> bool {
         // matching on `NonValidating` to avoid conditional compilation (`#[cfg]`)
@@ -188,6 +223,7 @@ impl<P: ConnectionProvider> Recursor<P> {
             avoid_local_udp_ports,
             ttl_config,
             case_randomization,
+            server_damping,
             conn_provider,
         } = builder;
 
@@ -203,6 +239,7 @@ impl<P: ConnectionProvider> Recursor<P> {
             Arc::new(avoid_local_udp_ports),
             ttl_config,
             case_randomization,
+            server_damping,
             conn_provider,
         );
 
@@ -229,6 +266,7 @@ impl<P: ConnectionProvider> Recursor<P> {
 
                 RecursorMode::Validating {
                     response_cache,
+                    recursor: Box::new(handle.clone()),
                     handle: DnssecDnsHandle::with_trust_anchor(handle, trust_anchor)
                         .nsec3_iteration_limits(
                             nsec3_soft_iteration_limit,
@@ -429,6 +467,7 @@ impl<P: ConnectionProvider> Recursor<P> {
             RecursorMode::Validating {
                 handle,
                 response_cache,
+                ..
             } => {
                 if let Some(Ok(response)) = response_cache.get(&query, request_time) {
                     let none_indeterminate = response
@@ -514,6 +553,8 @@ enum RecursorMode<P: ConnectionProvider> {
         handle: DnssecDnsHandle<RecursorDnsHandle<P>>,
         // This is a handle to the response cache in `RecursorDnsHandle`, not a whole separate cache.
         response_cache: ResponseCache,
+        // The handle without validation, sharing the caches of `handle`
+        recursor: Box<RecursorDnsHandle<P>>,
     },
 }
 
@@ -5,7 +5,7 @@ use std::{
         Arc,
         atomic::{AtomicU8, Ordering},
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use async_recursion::async_recursion;
@@ -17,10 +17,10 @@ use prefix_trie::PrefixSet;
 use tracing::{debug, info, trace, warn};
 
 use crate::{
-    Error, ErrorKind,
+    Delegation, DelegationSecurity, Error, ErrorKind, TraceStep,
     proto::{
         ProtoErrorKind,
-        op::{Message, Query},
+        op::{Message, Query, ResponseCode},
         rr::{
             RData,
             RData::CNAME,
@@ -30,15 +30,15 @@ use crate::{
     },
     recursor_pool::RecursorPool,
     resolver::{
-        Name, ResponseCache, TtlConfig,
-        config::{NameServerConfig, ResolverOpts},
-        name_server::{ConnectionProvider, NameServerPool},
+        Name, ResponseCache, TtlConfig, config::ResolverOpts, name_server::ConnectionProvider,
     },
+    server_failures::ServerFailures,
 };
 
 #[derive(Clone)]
 pub(crate) struct RecursorDnsHandle<P: ConnectionProvider> {
     roots: RecursorPool<P>,
+    root_addrs: Arc<[IpAddr]>,
     name_server_cache: Arc<Mutex<LruCache<Name, RecursorPool<P>>>>,
     response_cache: ResponseCache,
     recursion_limit: Option<u8>,
@@ -50,6 +50,7 @@ pub(crate) struct RecursorDnsHandle<P: ConnectionProvider> {
     allow_server_v6: PrefixSet<Ipv6Net>,
     avoid_local_udp_ports: Arc<HashSet<u16>>,
     case_randomization: bool,
+    server_failures: ServerFailures,
     conn_provider: P,
 }
 
@@ -67,22 +68,25 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
         avoid_local_udp_ports: Arc<HashSet<u16>>,
         ttl_config: TtlConfig,
         case_randomization: bool,
+        server_damping: Duration,
         conn_provider: P,
     ) -> Self {
         assert!(!roots.is_empty(), "roots must not be empty");
-        let servers = roots
-            .iter()
-            .copied()
-            .map(NameServerConfig::udp_and_tcp)
-            .collect::<Vec<_>>();
 
         debug!(
             "Using cache sizes {}/{}",
             ns_cache_size, response_cache_size
         );
+        let root_addrs = Arc::from(roots);
+        let server_failures = ServerFailures::new(ns_cache_size, server_damping);
         let opts = recursor_opts(avoid_local_udp_ports.clone(), case_randomization);
-        let roots = NameServerPool::from_config(&servers, Arc::new(opts), conn_provider.clone());
-        let roots = RecursorPool::from(Name::root(), roots);
+        let roots = RecursorPool::new(
+            Name::root(),
+            roots,
+            Arc::new(opts),
+            conn_provider.clone(),
+            server_failures.clone(),
+        );
         let name_server_cache = Arc::new(Mutex::new(LruCache::new(ns_cache_size)));
         let response_cache = ResponseCache::new(response_cache_size, ttl_config);
 
@@ -118,6 +122,7 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
 
         Self {
             roots,
+            root_addrs,
             name_server_cache,
             response_cache,
             recursion_limit,
@@ -129,6 +134,7 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
             allow_server_v6,
             avoid_local_udp_ports,
             case_randomization,
+            server_failures,
             conn_provider,
         }
     }
@@ -200,6 +206,8 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
             // Handle the short circuit case for when we receive NXDOMAIN on a parent name, per RFC
             // 8020.
             Err(e) if e.is_nx_domain() => return Err(e),
+            // None of the name servers of an enclosing zone could be reached.
+            Err(e) if e.is_no_reachable_authority() => return Err(e),
             Err(e) => return Err(Error::from(format!("no nameserver found for {zone}: {e}"))),
         };
 
@@ -408,7 +416,7 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
             Err(e) if e.is_nx_domain() => return Err(e),
             // Short-circuit on timeouts. Requesting a longer name from the same pool would likely
             // encounter them again.
-            Err(e) if e.is_timeout() => return Err(e),
+            Err(e) if e.is_timeout() || e.is_no_reachable_authority() => return Err(e),
             // The name `zone` is not a zone cut. Return the same pool of name servers again, but do
             // not cache it. If this was recursively called by `ns_pool_for_zone()`, the outer call
             // will try again with one more label added to the iterative query name.
@@ -458,7 +466,7 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
 
             match glue_ips.get(&ns_data.0) {
                 Some(glue) if !glue.is_empty() => {
-                    config_group.extend(glue.iter().copied().map(NameServerConfig::udp_and_tcp));
+                    config_group.extend(glue.iter().copied());
                 }
                 _ => {
                     debug!("glue not found for {ns_data}");
@@ -487,12 +495,7 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
         }
 
         // now construct a namesever pool based off the NS and glue records
-        let ns = NameServerPool::from_config(
-            &config_group,
-            Arc::new(self.recursor_opts()),
-            self.conn_provider.clone(),
-        );
-        let ns = RecursorPool::from(zone.clone(), ns);
+        let ns = self.pool(zone.clone(), &config_group);
 
         // store in cache for future usage
         debug!("found nameservers for {zone}");
@@ -500,6 +503,150 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
         Ok((depth, ns))
     }
 
+    /// Follows the delegations from the roots to the name servers of `query`
+    pub(crate) async fn trace(
+        &self,
+        query: Query,
+        request_time: Instant,
+    ) -> Result<Vec<TraceStep>, Error> {
+        let mut steps = Vec::new();
+        let mut pool = self.roots.clone();
+        let mut servers = self.root_addrs.to_vec();
+
+        loop {
+            let depth = u8::try_from(steps.len()).unwrap_or(u8::MAX);
+            Error::recursion_exceeded(self.ns_recursion_limit, depth, query.name())?;
+
+            // always request DNSSEC records, for the DS records of referrals
+            let response = pool.lookup(query.clone(), true).await?.into_message();
+
+            let Some(zone) = referral_zone(pool.zone(), query.name(), &response) else {
+                steps.push(TraceStep {
+                    zone: pool.zone().clone(),
+                    servers,
+                    response,
+                    delegation: None,
+                });
+                return Ok(steps);
+            };
+
+            let name_servers = response
+                .name_servers()
+                .iter()
+                .filter(|record| record.name() == &zone)
+                .filter_map(|record| record.data().as_ns())
+                .map(|ns| ns.0.clone())
+                .collect::<Vec<_>>();
+
+            let mut glue_ips = HashMap::new();
+            self.add_glue_to_map(&mut glue_ips, response.additionals().iter());
+            let mut next_servers = name_servers
+                .iter()
+                .filter_map(|name| glue_ips.get(name))
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>();
+
+            // without glue, resolve the addresses of the name servers until one has any
+            for name in &name_servers {
+                if !next_servers.is_empty() {
+                    break;
+                }
+
+                for record_type in [RecordType::A, RecordType::AAAA] {
+                    let query = Query::query(name.clone(), record_type);
+                    match self
+                        .resolve(query, request_time, false, 0, Arc::new(AtomicU8::new(0)))
+                        .await
+                    {
+                        Ok(response) => next_servers.extend(
+                            response
+                                .answers()
+                                .iter()
+                                .filter_map(|record| record.data().ip_addr())
+                                .filter(|ip| !self.matches_nameserver_filter(*ip)),
+                        ),
+                        Err(e) => debug!("failed to resolve name server {name}: {e}"),
+                    }
+                }
+            }
+
+            if next_servers.is_empty() {
+                return Err(Error::from(format!(
+                    "no addresses found for the name servers of {zone}"
+                )));
+            }
+
+            let next_pool = self.pool(zone.clone(), &next_servers);
+
+            let security = self.delegation_security(&zone, &response, &next_pool).await;
+            steps.push(TraceStep {
+                zone: pool.zone().clone(),
+                servers,
+                response,
+                delegation: Some(Delegation {
+                    zone,
+                    name_servers,
+                    security,
+                }),
+            });
+
+            pool = next_pool;
+            servers = next_servers;
+        }
+    }
+
+    /// Checks the DS records of a referral to `zone` against the DNSKEYs of `zone`
+    #[cfg_attr(not(feature = "__dnssec"), allow(unused_variables))]
+    async fn delegation_security(
+        &self,
+        zone: &Name,
+        referral: &Message,
+        pool: &RecursorPool<P>,
+    ) -> DelegationSecurity {
+        let ds_records = referral
+            .name_servers()
+            .iter()
+            .filter(|record| record.record_type() == RecordType::DS && record.name() == zone)
+            .collect::<Vec<_>>();
+        if ds_records.is_empty() {
+            return DelegationSecurity::Insecure;
+        }
+
+        #[cfg(feature = "__dnssec")]
+        {
+            let query = Query::query(zone.clone(), RecordType::DNSKEY);
+            let dnskeys = match pool.lookup(query, true).await {
+                Ok(response) => response.into_message(),
+                Err(e) => {
+                    debug!("failed to look up the DNSKEYs of {zone}: {e}");
+                    return DelegationSecurity::Unverified;
+                }
+            };
+
+            for ds in ds_records
+                .iter()
+                .filter_map(|record| record.data().as_dnssec()?.as_ds())
+            {
+                let covered = dnskeys
+                    .answers()
+                    .iter()
+                    .filter_map(|record| record.data().as_dnssec()?.as_dnskey())
+                    .any(|dnskey| ds.covers(zone, dnskey).unwrap_or(false));
+                if covered {
+                    return DelegationSecurity::Secure {
+                        key_tag: ds.key_tag(),
+                    };
+                }
+            }
+
+            DelegationSecurity::Bogus
+        }
+
+        #[cfg(not(feature = "__dnssec"))]
+        DelegationSecurity::Unverified
+    }
+
     /// Helper function to add IP addresses from any A or AAAA records to a map indexed by record
     /// name.
     fn add_glue_to_map<'a>(
@@ -551,7 +698,7 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
         request_time: Instant,
         nameserver_pool: RecursorPool<P>,
         nameservers: I,
-        config: &mut Vec<NameServerConfig>,
+        config: &mut Vec<IpAddr>,
     ) -> Result<u8, Error> {
         let mut pool_queries = vec![];
 
@@ -598,7 +745,7 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
                             } else {
                                 Some(ip)
                             }
-                        }).map(NameServerConfig::udp_and_tcp));
+                        }));
                 }
                 Err(e) => {
                     warn!("append_ips_from_lookup: resolution failed failed: {e}");
@@ -609,11 +756,40 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
         Ok(depth)
     }
 
+    /// Builds a pool of the name `servers` of `zone`
+    fn pool(&self, zone: Name, servers: &[IpAddr]) -> RecursorPool<P> {
+        RecursorPool::new(
+            zone,
+            servers,
+            Arc::new(self.recursor_opts()),
+            self.conn_provider.clone(),
+            self.server_failures.clone(),
+        )
+    }
+
     fn recursor_opts(&self) -> ResolverOpts {
         recursor_opts(self.avoid_local_udp_ports.clone(), self.case_randomization)
     }
 }
 
+/// Returns the child zone of `zone` which `response` refers to, if it is a referral towards `name`
+fn referral_zone(zone: &Name, name: &Name, response: &Message) -> Option<Name> {
+    if response.response_code() != ResponseCode::NoError
+        || response.authoritative()
+        || !response.answers().is_empty()
+    {
+        return None;
+    }
+
+    response
+        .name_servers()
+        .iter()
+        .filter(|record| record.record_type() == RecordType::NS)
+        .map(Record::name)
+        .find(|child| *child != zone && zone.zone_of(child) && child.zone_of(name))
+        .cloned()
+}
+
 fn recursor_opts(
     avoid_local_udp_ports: Arc<HashSet<u16>>,
     case_randomization: bool,
@@ -644,13 +820,20 @@ mod tests {
         collections::HashSet,
         net::{IpAddr, Ipv4Addr},
         sync::Arc,
+        time::Duration,
     };
 
     use ipnet::IpNet;
 
+    use super::referral_zone;
     use crate::{
-        proto::runtime::TokioRuntimeProvider, recursor_dns_handle::RecursorDnsHandle,
-        resolver::TtlConfig,
+        proto::{
+            op::{Message, OpCode},
+            rr::{RData, Record, rdata::NS},
+            runtime::TokioRuntimeProvider,
+        },
+        recursor_dns_handle::RecursorDnsHandle,
+        resolver::{Name, TtlConfig},
     };
 
     #[test]
@@ -674,6 +857,7 @@ mod tests {
             Arc::new(HashSet::new()),
             TtlConfig::default(),
             false,
+            Duration::from_secs(60),
             TokioRuntimeProvider::default(),
         );
 
@@ -691,4 +875,22 @@ mod tests {
             assert!(!recursor.matches_nameserver_filter(IpAddr::from(addr)));
         }
     }
+
+    #[test]
+    fn test_referral_zone() {
+        let root = Name::root();
+        let com = Name::from_ascii("com.").unwrap();
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        let ns = Name::from_ascii("a.gtld-servers.net.").unwrap();
+
+        let mut referral = Message::response(0, OpCode::Query);
+        referral.add_name_server(Record::from_rdata(com.clone(), 86400, RData::NS(NS(ns))));
+        assert_eq!(referral_zone(&root, &name, &referral), Some(com.clone()));
+
+        // a referral to a zone which is not closer to the name is ignored
+        assert_eq!(referral_zone(&com, &name, &referral), None);
+
+        referral.set_authoritative(true);
+        assert_eq!(referral_zone(&root, &name, &referral), None);
+    }
 }
@@ -7,22 +7,29 @@
 
 use std::{
     collections::HashMap,
+    net::IpAddr,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::Instant,
 };
 
-use futures_util::{Future, FutureExt, StreamExt, future::Shared};
+use futures_util::{Future, FutureExt, future::Shared};
 use hickory_resolver::name_server::NameServerPool;
 use parking_lot::Mutex;
-use tracing::info;
+use tracing::{debug, info, warn};
 
 use crate::proto::{
-    DnsHandle, ProtoError,
-    op::Query,
-    xfer::{DnsRequestOptions, DnsResponse},
+    NoRecords, ProtoError, ProtoErrorKind,
+    op::{Query, ResponseCode},
+    xfer::{DnsRequest, DnsRequestOptions, DnsResponse},
 };
-use crate::resolver::{Name, name_server::ConnectionProvider};
+use crate::resolver::{
+    Name,
+    config::{NameServerConfig, ResolverOpts},
+    name_server::ConnectionProvider,
+};
+use crate::server_failures::{FailureKind, ServerFailures};
 
 #[allow(clippy::type_complexity)]
 #[derive(Clone)]
@@ -41,18 +48,47 @@ impl Future for SharedLookup {
     }
 }
 
+/// The name servers of a zone
+///
+/// All servers are queried through one pool, which orders them by their response times and sends
+/// concurrent requests. The server each result is received from is tracked in the shared
+/// [`ServerFailures`], and when damping is enabled, servers which failed for the zone are only
+/// queried once the others failed as well, until their damping window has passed.
 #[derive(Clone)]
 pub(crate) struct RecursorPool<P: ConnectionProvider> {
     zone: Name,
+    servers: Arc<[IpAddr]>,
     ns: NameServerPool<P>,
+    failures: ServerFailures,
     active_requests: Arc<Mutex<HashMap<Query, SharedLookup>>>,
 }
 
 impl<P: ConnectionProvider> RecursorPool<P> {
-    pub(crate) fn from(zone: Name, ns: NameServerPool<P>) -> Self {
+    pub(crate) fn new(
+        zone: Name,
+        servers: &[IpAddr],
+        options: Arc<ResolverOpts>,
+        conn_provider: P,
+        failures: ServerFailures,
+    ) -> Self {
+        let mut addrs = Vec::<IpAddr>::with_capacity(servers.len());
+        for ip in servers {
+            if !addrs.contains(ip) {
+                addrs.push(*ip);
+            }
+        }
+
+        let configs = addrs
+            .iter()
+            .map(|ip| NameServerConfig::udp_and_tcp(*ip))
+            .collect::<Vec<_>>();
+        let ns = NameServerPool::from_config(&configs, options, conn_provider);
+
         Self {
             zone,
+            servers: Arc::from(addrs),
             ns,
+            failures,
             active_requests: Arc::new(Mutex::new(HashMap::default())),
         }
     }
@@ -66,8 +102,6 @@ impl<P: ConnectionProvider> RecursorPool<P> {
         query: Query,
         security_aware: bool,
     ) -> Result<DnsResponse, ProtoError> {
-        let ns = self.ns.clone();
-
         let query_cpy = query.clone();
         let case_randomization = self.ns.options().case_randomization;
 
@@ -90,12 +124,17 @@ impl<P: ConnectionProvider> RecursorPool<P> {
                 options.recursion_desired = false;
 
                 // convert the lookup into a shared future
-                let lookup = ns
-                    .lookup(query_cpy, options)
-                    .into_future()
-                    .map(|(next, _)| next)
-                    .boxed()
-                    .shared();
+                let lookup = send_to_servers(
+                    self.zone.clone(),
+                    self.servers.clone(),
+                    self.ns.clone(),
+                    self.failures.clone(),
+                    query_cpy,
+                    options,
+                )
+                .map(Some)
+                .boxed()
+                .shared();
 
                 SharedLookup(lookup)
             })
@@ -109,3 +148,148 @@ impl<P: ConnectionProvider> RecursorPool<P> {
         result
     }
 }
+
+/// Sends `query` to the name `servers` of `zone` through the pool `ns`
+///
+/// Servers which recently failed for `zone` are only queried as a last resort, after all the
+/// other servers failed. If none of the servers could be reached, this returns a
+/// [`ProtoErrorKind::NoReachableAuthority`] error.
+async fn send_to_servers<P: ConnectionProvider>(
+    zone: Name,
+    servers: Arc<[IpAddr]>,
+    ns: NameServerPool<P>,
+    failures: ServerFailures,
+    query: Query,
+    options: DnsRequestOptions,
+) -> Result<DnsResponse, ProtoError> {
+    let now = Instant::now();
+    let (damped, healthy) =
+        servers.iter().copied().partition::<Vec<IpAddr>, _>(|ip| {
+            match failures.damped(&zone, *ip, now) {
+                Some(kind) => {
+                    debug!("avoiding {ip} for {zone} after recent {kind}");
+                    true
+                }
+                None => false,
+            }
+        });
+
+    let tiers = match (damped.is_empty(), healthy.is_empty()) {
+        (true, _) | (_, true) => vec![ns],
+        (false, false) => vec![ns.without(&damped), ns.without(&healthy)],
+    };
+
+    let mut result = Err(ProtoErrorKind::NoReachableAuthority(zone.clone()).into());
+    for (i, ns) in tiers.into_iter().enumerate() {
+        if i > 0 {
+            debug!("retrying the name servers of {zone} which recently failed");
+        }
+
+        let (server, next) = ns
+            .send_attributed(DnsRequest::from_query(query.clone(), options))
+            .await;
+        let Some(server) = server else {
+            // none of the servers could be reached
+            continue;
+        };
+
+        result = next;
+        match result.as_ref().err().and_then(|e| failure_kind(&zone, e)) {
+            Some(kind) => {
+                warn!("name server {server} failed for {zone}: {kind}");
+                failures.record(&zone, server, kind, Instant::now());
+            }
+            None => {
+                failures.clear(&zone, server);
+                return result;
+            }
+        }
+    }
+
+    result
+}
+
+/// Classifies the error returned by a name server of `zone`, if it shows the server is unusable
+fn failure_kind(zone: &Name, error: &ProtoError) -> Option<FailureKind> {
+    match error.kind() {
+        ProtoErrorKind::Timeout
+        | ProtoErrorKind::Io(_)
+        | ProtoErrorKind::NoConnections
+        | ProtoErrorKind::Busy => Some(FailureKind::Timeout),
+        ProtoErrorKind::NoRecordsFound(NoRecords {
+            response_code: ResponseCode::Refused,
+            ..
+        }) => Some(FailureKind::Refused),
+        // a referral to a zone which is not within the zone the server was delegated for
+        ProtoErrorKind::NoRecordsFound(NoRecords {
+            ns: Some(referrals),
+            response_code: ResponseCode::NoError,
+            ..
+        }) if !referrals.iter().any(|fwd| zone.zone_of(fwd.ns.name())) => Some(FailureKind::Lame),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::proto::{
+        ForwardNSData,
+        rr::{RData, Record, RecordType, rdata::NS},
+    };
+
+    fn referral(owner: &str) -> ProtoError {
+        let owner = Name::from_ascii(owner).unwrap();
+        let ns = Record::from_rdata(
+            owner,
+            86400,
+            RData::NS(NS(Name::from_ascii("ns.example.").unwrap())),
+        );
+
+        let mut no_records = NoRecords::new(
+            Query::query(Name::from_ascii("www.example.com.").unwrap(), RecordType::A),
+            ResponseCode::NoError,
+        );
+        no_records.ns = Some(Arc::from([ForwardNSData {
+            ns,
+            glue: Arc::from([]),
+        }]));
+        no_records.into()
+    }
+
+    #[test]
+    fn test_failure_kind() {
+        let zone = Name::from_ascii("example.com.").unwrap();
+
+        assert_eq!(
+            failure_kind(&zone, &ProtoErrorKind::Timeout.into()),
+            Some(FailureKind::Timeout)
+        );
+        assert_eq!(
+            failure_kind(&zone, &ProtoErrorKind::NoConnections.into()),
+            Some(FailureKind::Timeout)
+        );
+
+        let mut refused = NoRecords::new(
+            Query::query(zone.clone(), RecordType::NS),
+            ResponseCode::Refused,
+        );
+        assert_eq!(
+            failure_kind(&zone, &refused.clone().into()),
+            Some(FailureKind::Refused)
+        );
+        refused.response_code = ResponseCode::NXDomain;
+        assert_eq!(failure_kind(&zone, &refused.into()), None);
+
+        // upward and sideways referrals are lame, delegations to child zones are not
+        assert_eq!(failure_kind(&zone, &referral(".")), Some(FailureKind::Lame));
+        assert_eq!(
+            failure_kind(&zone, &referral("example.net.")),
+            Some(FailureKind::Lame)
+        );
+        assert_eq!(failure_kind(&zone, &referral("sub.example.com.")), None);
+        assert_eq!(failure_kind(&Name::root(), &referral("com.")), None);
+    }
+}
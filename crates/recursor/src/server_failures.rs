@@ -0,0 +1,148 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::{
+    fmt,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use lru_cache::LruCache;
+use parking_lot::Mutex;
+use tracing::debug;
+
+use crate::resolver::Name;
+
+/// The way in which a name server failed to answer for a zone it was delegated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FailureKind {
+    /// The server is not authoritative for the zone, and answered with a referral to another zone
+    Lame,
+    /// The server did not answer, or could not be connected to
+    Timeout,
+    /// The server refused to answer for the zone
+    Refused,
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Lame => "lame delegation",
+            Self::Timeout => "timeout",
+            Self::Refused => "refused",
+        })
+    }
+}
+
+/// Name servers which recently failed for a zone, shared between all the pools of a recursor
+///
+/// Servers are avoided for the zone they failed for until the damping window has passed since
+/// their last failure, at which point they are given another chance.
+#[allow(clippy::type_complexity)]
+#[derive(Clone)]
+pub(crate) struct ServerFailures {
+    damping: Duration,
+    failures: Arc<Mutex<LruCache<(Name, IpAddr), (FailureKind, Instant)>>>,
+}
+
+impl ServerFailures {
+    pub(crate) fn new(capacity: usize, damping: Duration) -> Self {
+        Self {
+            damping,
+            failures: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// Records a failure of `server` for `zone` at `now`
+    pub(crate) fn record(&self, zone: &Name, server: IpAddr, kind: FailureKind, now: Instant) {
+        if self.damping.is_zero() {
+            return;
+        }
+
+        debug!("avoiding {server} for {zone} after {kind}");
+        self.failures
+            .lock()
+            .insert((zone.clone(), server), (kind, now));
+    }
+
+    /// Forgets past failures of `server` for `zone`, after it answered
+    pub(crate) fn clear(&self, zone: &Name, server: IpAddr) {
+        self.failures.lock().remove(&(zone.clone(), server));
+    }
+
+    /// Returns the recent failure of `server` for `zone`, if it should still be avoided at `now`
+    pub(crate) fn damped(&self, zone: &Name, server: IpAddr, now: Instant) -> Option<FailureKind> {
+        let mut failures = self.failures.lock();
+        let key = (zone.clone(), server);
+        let &mut (kind, at) = failures.get_mut(&key)?;
+        if now.saturating_duration_since(at) < self.damping {
+            return Some(kind);
+        }
+
+        failures.remove(&key);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damping_window() {
+        let failures = ServerFailures::new(8, Duration::from_secs(60));
+        let zone = Name::from_ascii("example.com.").unwrap();
+        let other = Name::from_ascii("example.net.").unwrap();
+        let server = IpAddr::from([192, 0, 2, 1]);
+        let now = Instant::now();
+
+        assert_eq!(failures.damped(&zone, server, now), None);
+
+        failures.record(&zone, server, FailureKind::Refused, now);
+        assert_eq!(
+            failures.damped(&zone, server, now + Duration::from_secs(59)),
+            Some(FailureKind::Refused)
+        );
+        // failures only apply to the zone the server failed for
+        assert_eq!(failures.damped(&other, server, now), None);
+        assert_eq!(
+            failures.damped(&zone, IpAddr::from([192, 0, 2, 2]), now),
+            None
+        );
+
+        assert_eq!(
+            failures.damped(&zone, server, now + Duration::from_secs(60)),
+            None
+        );
+        // expired failures are forgotten
+        assert_eq!(failures.damped(&zone, server, now), None);
+    }
+
+    #[test]
+    fn test_clear() {
+        let failures = ServerFailures::new(8, Duration::from_secs(60));
+        let zone = Name::from_ascii("example.com.").unwrap();
+        let server = IpAddr::from([192, 0, 2, 1]);
+        let now = Instant::now();
+
+        failures.record(&zone, server, FailureKind::Lame, now);
+        failures.clear(&zone, server);
+        assert_eq!(failures.damped(&zone, server, now), None);
+    }
+
+    #[test]
+    fn test_no_damping() {
+        let failures = ServerFailures::new(8, Duration::ZERO);
+        let zone = Name::from_ascii("example.com.").unwrap();
+        let server = IpAddr::from([192, 0, 2, 1]);
+        let now = Instant::now();
+
+        failures.record(&zone, server, FailureKind::Timeout, now);
+        assert_eq!(failures.damped(&zone, server, now), None);
+    }
+}
@@ -0,0 +1,49 @@
+//! The steps of following delegations from the roots, see [`Recursor::trace`](crate::Recursor::trace)
+
+use std::net::IpAddr;
+
+use crate::{proto::op::Message, resolver::Name};
+
+/// The response of the name servers of one zone, while following the delegations to a name
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct TraceStep {
+    /// The zone whose name servers were queried
+    pub zone: Name,
+    /// The addresses of the name servers which were queried
+    pub servers: Vec<IpAddr>,
+    /// The response of the name servers, a referral for all but the last step
+    pub response: Message,
+    /// The delegation to the zone of the next step, if the response is a referral
+    pub delegation: Option<Delegation>,
+}
+
+/// A delegation from a zone to a child zone
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Delegation {
+    /// The child zone
+    pub zone: Name,
+    /// The name servers of the child zone, as listed in the parent zone
+    pub name_servers: Vec<Name>,
+    /// Whether the delegation is secured by DNSSEC
+    pub security: DelegationSecurity,
+}
+
+/// The DNSSEC status of a [`Delegation`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DelegationSecurity {
+    /// The parent zone has no DS records for the child zone
+    Insecure,
+    /// A DS record of the parent zone matches a DNSKEY of the child zone
+    Secure {
+        /// The key tag of the matching DS record
+        key_tag: u16,
+    },
+    /// None of the DS records of the parent zone match a DNSKEY of the child zone
+    Bogus,
+    /// The parent zone has DS records, but they could not be checked, either because the
+    /// DNSKEYs of the child zone could not be looked up or DNSSEC support is not enabled
+    Unverified,
+}
@@ -0,0 +1,32 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+fn main() {
+    #[cfg(feature = "ffi")]
+    ffi_header();
+}
+
+/// Writes the C header for the `ffi` module to `OUT_DIR/hickory_resolver.h`
+#[cfg(feature = "ffi")]
+fn ffi_header() {
+    use std::{env, path::PathBuf};
+
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("set by cargo"));
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("set by cargo"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("failed to read cbindgen.toml");
+
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("failed to generate the C header")
+        .write_to_file(out_dir.join("hickory_resolver.h"));
+}
@@ -4,7 +4,7 @@
 use {
     hickory_resolver::{
         Resolver,
-        config::{CLOUDFLARE, GOOGLE, ResolverConfig},
+        config::{GOOGLE, ResolverConfig},
         name_server::ConnectionProvider,
         proto::runtime::{RuntimeProvider, TokioHandle, TokioTime, iocompat::AsyncIoTokioAsStd},
     },
@@ -101,6 +101,8 @@ async fn run() {
 
     #[cfg(feature = "__https")]
     {
+        use hickory_resolver::config::CLOUDFLARE;
+
         let resolver2 = Resolver::builder_with_config(
             ResolverConfig::https(&CLOUDFLARE),
             PrintProvider::default(),
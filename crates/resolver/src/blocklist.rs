@@ -0,0 +1,549 @@
+//! Domain blocklists, loaded from hosts-format and adblock-style lists
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock, Weak};
+use std::thread;
+use std::time::Duration;
+
+use futures_channel::oneshot;
+use futures_util::future;
+use futures_util::stream::{Stream, once};
+use tracing::{debug, warn};
+
+use crate::proto::ProtoError;
+use crate::proto::op::{Message, ResponseCode};
+use crate::proto::rr::{Name, RData, Record, RecordType};
+use crate::proto::runtime::{RuntimeProvider, Spawn, Time};
+use crate::proto::xfer::{DnsHandle, DnsRequest, DnsResponse};
+
+/// How queries for blocked names are answered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BlockAction {
+    /// Answer with NXDOMAIN, as if the name did not exist. This is the default.
+    #[default]
+    NxDomain,
+    /// Answer address queries with the unspecified address, `0.0.0.0` or `::`
+    Unspecified,
+    /// Answer address queries with these addresses, e.g. of a server explaining the block
+    Sinkhole(Ipv4Addr, Ipv6Addr),
+}
+
+/// A source of blocklist rules
+#[derive(Clone, Debug)]
+pub enum BlocklistSource {
+    /// A list read from a file, which is read again on every refresh
+    File(PathBuf),
+    /// A list given inline
+    Text(String),
+}
+
+impl BlocklistSource {
+    fn read(&self) -> io::Result<String> {
+        match self {
+            Self::File(path) => fs::read_to_string(path),
+            Self::Text(text) => Ok(text.clone()),
+        }
+    }
+}
+
+/// A set of domain blocklists
+///
+/// The lists may be in hosts-format, e.g. `0.0.0.0 ads.example.com`, hold a domain per line, or
+/// use the adblock syntax:
+///
+/// * `ads.example.com` blocks only that name, as do hosts-format entries
+/// * `*.example.com` blocks all subdomains of `example.com`
+/// * `||example.com^` blocks `example.com` and all its subdomains
+/// * `@@||example.com^` is an exception, the name and its subdomains are never blocked, even if
+///   they appear in a list. Exceptions also apply to the other forms above.
+///
+/// Comments start with `#` or `!`, a `#` only at the start of a line or after whitespace. Adblock
+/// rules with modifiers, e.g. `||example.com^$third-party`, cosmetic rules, e.g.
+/// `example.com##.banner`, and rules which are not about a domain are ignored.
+///
+/// The blocklist is used in a resolver by [`ResolverBuilder::with_blocklist`](crate::ResolverBuilder::with_blocklist).
+#[derive(Debug)]
+pub struct Blocklist {
+    sources: Arc<[BlocklistSource]>,
+    rules: RwLock<Rules>,
+    /// Counts the reloads, so that resolvers know to drop answers cached under previous rules
+    generation: AtomicUsize,
+    action: BlockAction,
+    ttl: u32,
+}
+
+impl Blocklist {
+    /// Loads the blocklist from `sources`, failing if one of them can't be read
+    pub fn new(sources: Vec<BlocklistSource>) -> io::Result<Self> {
+        let rules = Rules::load(&sources)?;
+        Ok(Self {
+            sources: sources.into(),
+            rules: RwLock::new(rules),
+            generation: AtomicUsize::new(0),
+            action: BlockAction::default(),
+            ttl: 300,
+        })
+    }
+
+    /// Sets how queries for blocked names are answered
+    pub fn with_action(mut self, action: BlockAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Sets the TTL of the answers to blocked names, defaults to 300 seconds
+    pub fn with_ttl(mut self, ttl: u32) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Reads all sources again, replacing the rules if all of them could be read
+    ///
+    /// Resolvers using the blocklist clear their caches on their next lookup after a reload.
+    pub fn reload(&self) -> io::Result<()> {
+        let rules = Rules::load(&self.sources)?;
+        self.replace(rules);
+        Ok(())
+    }
+
+    /// Like [`Self::reload`], but reads the sources on a separate thread
+    async fn reload_in_background(&self) -> io::Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let sources = self.sources.clone();
+        thread::Builder::new()
+            .name("blocklist-reload".to_string())
+            .spawn(move || {
+                let _ = tx.send(Rules::load(&sources));
+            })?;
+
+        let rules = rx
+            .await
+            .map_err(|_| io::Error::other("blocklist reload thread panicked"))??;
+        self.replace(rules);
+        Ok(())
+    }
+
+    fn replace(&self, rules: Rules) {
+        *self.rules.write().expect("blocklist lock poisoned") = rules;
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// The number of reloads so far
+    pub(crate) fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Reloads the blocklist every `interval`, for as long as it is in use
+    ///
+    /// The reloads are spawned on the runtime of `provider`, with the sources read on a separate
+    /// thread. A failed reload keeps the previous rules.
+    pub fn refresh_every<P: RuntimeProvider>(self: &Arc<Self>, provider: &P, interval: Duration) {
+        let blocklist = Arc::downgrade(self);
+        provider
+            .create_handle()
+            .spawn_bg(refresh::<P>(blocklist, interval));
+    }
+
+    /// Returns true if queries for `name` are blocked
+    pub fn is_blocked(&self, name: &Name) -> bool {
+        let rules = self.rules.read().expect("blocklist lock poisoned");
+        !rules.allowed.matches(name) && rules.blocked.matches(name)
+    }
+
+    /// Builds the answer to `request`, if its query is for a blocked name
+    fn answer(&self, request: &DnsRequest) -> Option<Result<DnsResponse, ProtoError>> {
+        let query = request.queries().first()?;
+        if !self.is_blocked(query.name()) {
+            return None;
+        }
+
+        debug!("blocked query for {}", query.name());
+        let mut message = Message::to_response(request);
+        message.set_recursion_available(true);
+
+        let rdata = match (self.action, query.query_type()) {
+            (BlockAction::NxDomain, _) => {
+                message.set_response_code(ResponseCode::NXDomain);
+                None
+            }
+            (BlockAction::Unspecified, RecordType::A) => Some(IpAddr::from(Ipv4Addr::UNSPECIFIED)),
+            (BlockAction::Unspecified, RecordType::AAAA) => {
+                Some(IpAddr::from(Ipv6Addr::UNSPECIFIED))
+            }
+            (BlockAction::Sinkhole(ipv4, _), RecordType::A) => Some(IpAddr::from(ipv4)),
+            (BlockAction::Sinkhole(_, ipv6), RecordType::AAAA) => Some(IpAddr::from(ipv6)),
+            // other types have no data
+            _ => None,
+        };
+
+        if let Some(ip) = rdata {
+            message.add_answer(Record::from_rdata(
+                query.name().clone(),
+                self.ttl,
+                RData::from(ip),
+            ));
+        }
+
+        Some(DnsResponse::from_message(message))
+    }
+}
+
+async fn refresh<P: RuntimeProvider>(
+    blocklist: Weak<Blocklist>,
+    interval: Duration,
+) -> Result<(), ProtoError> {
+    loop {
+        P::Timer::delay_for(interval).await;
+        let Some(blocklist) = blocklist.upgrade() else {
+            return Ok(());
+        };
+
+        if let Err(error) = blocklist.reload_in_background().await {
+            warn!(%error, "failed to reload blocklist");
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Rules {
+    blocked: RuleSet,
+    allowed: RuleSet,
+}
+
+impl Rules {
+    fn load(sources: &[BlocklistSource]) -> io::Result<Self> {
+        let mut rules = Self::default();
+        for source in sources {
+            rules.parse(&source.read()?);
+        }
+
+        Ok(rules)
+    }
+
+    fn parse(&mut self, text: &str) {
+        for line in text.lines() {
+            // comments of hosts files and of adblock lists
+            let line = strip_comment(line).trim();
+            if line.is_empty() || line.starts_with('!') || line.starts_with('[') {
+                continue;
+            }
+
+            // adblock cosmetic rules hide parts of pages, they don't block the domain
+            if ["##", "#@#", "#?#", "#$#"]
+                .iter()
+                .any(|marker| line.contains(marker))
+            {
+                continue;
+            }
+
+            if let Some(rule) = line.strip_prefix("@@") {
+                self.allowed.add(rule);
+                continue;
+            }
+
+            // hosts-format, any names after the address
+            let mut fields = line.split_whitespace();
+            let first = fields.next().unwrap_or_default();
+            if IpAddr::from_str(first).is_ok() {
+                for host in fields {
+                    if !is_local_host(host) {
+                        self.blocked.add(host);
+                    }
+                }
+            } else {
+                self.blocked.add(line);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct RuleSet {
+    /// Names which match exactly
+    names: HashSet<Name>,
+    /// Names which match with all their subdomains
+    domains: HashSet<Name>,
+    /// Names whose subdomains match, but not the name itself
+    subdomains: HashSet<Name>,
+}
+
+impl RuleSet {
+    fn add(&mut self, rule: &str) {
+        let (set, domain) = if let Some(domain) = rule.strip_prefix("||") {
+            let Some(domain) = domain.strip_suffix('^') else {
+                // a rule on URLs rather than domains
+                return;
+            };
+            (&mut self.domains, domain)
+        } else if let Some(domain) = rule.strip_prefix("*.") {
+            (&mut self.subdomains, domain)
+        } else {
+            (&mut self.names, rule)
+        };
+
+        match parse_name(domain) {
+            Some(name) => {
+                set.insert(name);
+            }
+            None => debug!("ignoring blocklist rule: {rule}"),
+        }
+    }
+
+    fn matches(&self, name: &Name) -> bool {
+        let mut name = name.clone();
+        name.set_fqdn(true);
+        if self.names.contains(&name) {
+            return true;
+        }
+
+        let mut depth = 0;
+        loop {
+            if self.domains.contains(&name) || (depth > 0 && self.subdomains.contains(&name)) {
+                return true;
+            }
+
+            if name.is_root() {
+                return false;
+            }
+
+            name = name.base_name();
+            depth += 1;
+        }
+    }
+}
+
+/// Strips a `#` comment, which starts a line or follows whitespace
+fn strip_comment(line: &str) -> &str {
+    let mut token_start = true;
+    for (i, c) in line.char_indices() {
+        if c == '#' && token_start {
+            return &line[..i];
+        }
+        token_start = c.is_whitespace();
+    }
+
+    line
+}
+
+fn parse_name(domain: &str) -> Option<Name> {
+    if domain.is_empty() || domain.contains(['/', '$', '*', '^', ':']) {
+        return None;
+    }
+
+    let mut name = Name::from_str(domain).ok()?;
+    name.set_fqdn(true);
+    Some(name)
+}
+
+/// Hosts-format lists commonly map the names of the host itself
+fn is_local_host(host: &str) -> bool {
+    matches!(
+        host,
+        "localhost"
+            | "localhost.localdomain"
+            | "local"
+            | "broadcasthost"
+            | "ip6-localhost"
+            | "ip6-loopback"
+            | "ip6-localnet"
+            | "ip6-mcastprefix"
+            | "ip6-allnodes"
+            | "ip6-allrouters"
+            | "ip6-allhosts"
+            | "0.0.0.0"
+    )
+}
+
+/// A [`DnsHandle`] which answers queries for blocked names itself, and passes on all others
+#[derive(Clone)]
+pub struct BlocklistDnsHandle<H> {
+    handle: H,
+    blocklist: Option<Arc<Blocklist>>,
+}
+
+impl<H: DnsHandle> BlocklistDnsHandle<H> {
+    /// Blocks the queries to `handle` for the names in `blocklist`, if any
+    pub fn new(handle: H, blocklist: Option<Arc<Blocklist>>) -> Self {
+        Self { handle, blocklist }
+    }
+}
+
+impl<H: DnsHandle> DnsHandle for BlocklistDnsHandle<H> {
+    type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send>>;
+
+    fn is_verifying_dnssec(&self) -> bool {
+        self.handle.is_verifying_dnssec()
+    }
+
+    fn send(&self, request: DnsRequest) -> Self::Response {
+        if let Some(response) = self.blocklist.as_ref().and_then(|b| b.answer(&request)) {
+            return Box::pin(once(future::ready(response)));
+        }
+
+        Box::pin(self.handle.send(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_executor::block_on;
+
+    use super::*;
+    use crate::proto::op::Query;
+    use crate::proto::xfer::{DnsRequestOptions, FirstAnswer};
+
+    const LIST: &str = "\
+[Adblock Plus 2.0]
+! an adblock comment
+# a hosts comment
+0.0.0.0 localhost
+0.0.0.0 ads.example.com tracker.example.com # trailing comment
+127.0.0.1 metrics.example.org
+plain.example.net
+*.wild.example.net
+||adblock.example.com^
+||example.info^$third-party
+||example.biz/path^
+@@||good.adblock.example.com^
+cosmetic.example.com##.banner
+cosmetic.example.org#@#.banner
+anchor.example.com#top
+";
+
+    fn blocklist() -> Blocklist {
+        Blocklist::new(vec![BlocklistSource::Text(LIST.to_string())]).unwrap()
+    }
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    #[test]
+    fn test_matching() {
+        let blocklist = blocklist();
+
+        for blocked in [
+            "ads.example.com.",
+            "TRACKER.example.com",
+            "metrics.example.org.",
+            "plain.example.net.",
+            "a.wild.example.net.",
+            "a.b.wild.example.net.",
+            "adblock.example.com.",
+            "www.adblock.example.com.",
+        ] {
+            assert!(blocklist.is_blocked(&name(blocked)), "{blocked}");
+        }
+
+        for allowed in [
+            "localhost.",
+            "example.com.",
+            "www.ads.example.com.",
+            "www.plain.example.net.",
+            "wild.example.net.",
+            "good.adblock.example.com.",
+            "www.good.adblock.example.com.",
+            "example.info.",
+            "example.biz.",
+            "cosmetic.example.com.",
+            "cosmetic.example.org.",
+            "anchor.example.com.",
+        ] {
+            assert!(!blocklist.is_blocked(&name(allowed)), "{allowed}");
+        }
+    }
+
+    #[test]
+    fn test_reload() {
+        let path = std::env::temp_dir().join(format!("blocklist-{}.txt", std::process::id()));
+        fs::write(&path, "ads.example.com\n").unwrap();
+
+        let blocklist = Blocklist::new(vec![BlocklistSource::File(path.clone())]).unwrap();
+        assert!(blocklist.is_blocked(&name("ads.example.com.")));
+
+        fs::write(&path, "tracker.example.com\n").unwrap();
+        blocklist.reload().unwrap();
+        assert!(!blocklist.is_blocked(&name("ads.example.com.")));
+        assert!(blocklist.is_blocked(&name("tracker.example.com.")));
+        assert_eq!(blocklist.generation(), 1);
+
+        fs::write(&path, "metrics.example.org\n").unwrap();
+        block_on(blocklist.reload_in_background()).unwrap();
+        assert!(!blocklist.is_blocked(&name("tracker.example.com.")));
+        assert!(blocklist.is_blocked(&name("metrics.example.org.")));
+        assert_eq!(blocklist.generation(), 2);
+
+        // the rules are kept if a source can't be read
+        fs::remove_file(&path).unwrap();
+        assert!(blocklist.reload().is_err());
+        assert!(block_on(blocklist.reload_in_background()).is_err());
+        assert!(blocklist.is_blocked(&name("metrics.example.org.")));
+        assert_eq!(blocklist.generation(), 2);
+    }
+
+    #[derive(Clone)]
+    struct Unreachable;
+
+    impl DnsHandle for Unreachable {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send>>;
+
+        fn send(&self, _: DnsRequest) -> Self::Response {
+            Box::pin(once(future::ready(Err(ProtoError::from("not blocked")))))
+        }
+    }
+
+    fn lookup(blocklist: Blocklist, query: Query) -> Result<DnsResponse, ProtoError> {
+        let handle = BlocklistDnsHandle::new(Unreachable, Some(Arc::new(blocklist)));
+        block_on(
+            handle
+                .lookup(query, DnsRequestOptions::default())
+                .first_answer(),
+        )
+    }
+
+    #[test]
+    fn test_actions() {
+        let query = Query::query(name("ads.example.com."), RecordType::A);
+        let response = lookup(blocklist(), query.clone()).unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert!(response.answers().is_empty());
+
+        let response = lookup(
+            blocklist().with_action(BlockAction::Unspecified),
+            query.clone(),
+        )
+        .unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(
+            response.answers()[0].data(),
+            &RData::from(IpAddr::from(Ipv4Addr::UNSPECIFIED))
+        );
+
+        let sinkhole = BlockAction::Sinkhole(Ipv4Addr::new(192, 0, 2, 1), Ipv6Addr::LOCALHOST);
+        let aaaa = Query::query(name("ads.example.com."), RecordType::AAAA);
+        let response = lookup(blocklist().with_action(sinkhole).with_ttl(60), aaaa).unwrap();
+        assert_eq!(response.answers()[0].ttl(), 60);
+        assert_eq!(
+            response.answers()[0].data(),
+            &RData::from(IpAddr::from(Ipv6Addr::LOCALHOST))
+        );
+
+        // other types have no data
+        let mx = Query::query(name("ads.example.com."), RecordType::MX);
+        let response = lookup(blocklist().with_action(sinkhole), mx).unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert!(response.answers().is_empty());
+
+        // names which are not blocked are passed on
+        let other = Query::query(name("www.example.com."), RecordType::A);
+        assert!(lookup(blocklist(), other).is_err());
+    }
+}
@@ -2,20 +2,23 @@
 
 use std::{
     collections::HashMap,
+    mem,
     ops::RangeInclusive,
     sync::Arc,
     time::{Duration, Instant},
 };
 
-use moka::{Expiry, sync::Cache};
+use moka::{Expiry, policy::EvictionPolicy, sync::Cache};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Deserializer};
 
-use crate::config;
+use crate::config::{self, CacheEvictionPolicy};
 use crate::proto::{
-    NoRecords, ProtoError, ProtoErrorKind,
+    ForwardNSData, NoRecords, ProtoError, ProtoErrorKind,
     op::{Message, Query},
-    rr::RecordType,
+    rr::{Record, RecordType},
+    serialize::binary::{BinEncodable, BinEncoder},
+    xfer::BufferPool,
 };
 
 /// A cache for DNS responses.
@@ -33,11 +36,42 @@ impl ResponseCache {
     /// * `capacity` - size in number of cached responses
     /// * `ttl_config` - minimum and maximum TTLs for cached records
     pub fn new(capacity: u64, ttl_config: TtlConfig) -> Self {
-        Self {
-            cache: Cache::builder()
-                .max_capacity(capacity)
-                .expire_after(EntryExpiry)
+        Self::with_options(capacity, None, CacheEvictionPolicy::default(), ttl_config)
+    }
+
+    /// Construct a new response cache, optionally bounded by the memory used by its entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - size in number of cached responses, ignored if `max_memory` is set
+    /// * `max_memory` - optional limit in bytes on the estimated size of all cached responses
+    /// * `eviction_policy` - the policy for admitting responses to, and evicting them from, the cache
+    /// * `ttl_config` - minimum and maximum TTLs for cached records
+    pub fn with_options(
+        capacity: u64,
+        max_memory: Option<u64>,
+        eviction_policy: CacheEvictionPolicy,
+        ttl_config: TtlConfig,
+    ) -> Self {
+        let builder = Cache::builder()
+            .eviction_policy(match eviction_policy {
+                CacheEvictionPolicy::TinyLfu => EvictionPolicy::tiny_lfu(),
+                CacheEvictionPolicy::Lru => EvictionPolicy::lru(),
+            })
+            .expire_after(EntryExpiry);
+
+        let cache = match max_memory {
+            Some(max_memory) => builder
+                .max_capacity(max_memory)
+                .weigher(|query: &Query, entry: &Entry| {
+                    u32::try_from(entry_size(query, &entry.result)).unwrap_or(u32::MAX)
+                })
                 .build(),
+            None => builder.max_capacity(capacity).build(),
+        };
+
+        Self {
+            cache,
             ttl_config: Arc::new(ttl_config),
         }
     }
@@ -92,11 +126,84 @@ impl ResponseCache {
         Some(entry.updated_ttl(now))
     }
 
+    /// The number of cached responses.
+    ///
+    /// Insertions and evictions are applied asynchronously, so this is an approximation.
+    pub fn entry_count(&self) -> u64 {
+        self.cache.entry_count()
+    }
+
+    /// The estimated memory in bytes used by the cached responses.
+    ///
+    /// Only tracked if the cache was constructed with a `max_memory` limit, otherwise this is the
+    /// number of cached responses. Like [`Self::entry_count`], this is an approximation.
+    pub fn memory_usage(&self) -> u64 {
+        self.cache.weighted_size()
+    }
+
     pub(crate) fn clear(&self) {
         self.cache.invalidate_all();
     }
 }
 
+/// Estimates the memory in bytes used by a cache entry for the query and result.
+///
+/// This accounts for the fixed size of the entry and each of its records, plus the encoded size of
+/// the owner names and record data, which is approximately their size on the heap.
+fn entry_size(query: &Query, result: &Result<Message, ProtoError>) -> usize {
+    let mut buffer = BufferPool::global().get();
+    let mut size = mem::size_of::<Query>() + mem::size_of::<Entry>() + query.name().len();
+    size += mem::size_of::<Result<Message, ProtoError>>();
+
+    match result {
+        Ok(message) => {
+            size += message
+                .queries()
+                .iter()
+                .map(|q| q.name().len())
+                .sum::<usize>();
+            size += records_size(message.all_sections(), &mut buffer);
+            if let Some(edns) = message.extensions() {
+                size += mem::size_of_val(edns);
+            }
+        }
+        Err(e) => {
+            size += mem::size_of::<ProtoErrorKind>();
+            if let ProtoErrorKind::NoRecordsFound(no_records) = e.kind() {
+                size += mem::size_of::<NoRecords>() + mem::size_of::<Query>();
+                size += no_records.query.name().len();
+                if let Some(soa) = &no_records.soa {
+                    size += mem::size_of_val(&**soa) + soa.name().len();
+                    size += soa.data().mname().len() + soa.data().rname().len();
+                }
+                for ForwardNSData { ns, glue } in no_records.ns.iter().flat_map(|ns| ns.iter()) {
+                    size += mem::size_of::<ForwardNSData>();
+                    size += records_size(Some(ns).into_iter().chain(glue.iter()), &mut buffer);
+                }
+                if let Some(authorities) = &no_records.authorities {
+                    size += records_size(authorities.iter(), &mut buffer);
+                }
+            }
+        }
+    }
+
+    size
+}
+
+/// Estimates the memory in bytes used by the records, `buffer` is used as scratch space.
+fn records_size<'a>(records: impl Iterator<Item = &'a Record>, buffer: &mut Vec<u8>) -> usize {
+    records
+        .map(|record| {
+            buffer.clear();
+            let rdata_len = match record.data().emit(&mut BinEncoder::new(buffer)) {
+                Ok(()) => buffer.len(),
+                Err(_) => 0,
+            };
+            mem::size_of::<Record>() + record.name().len() + rdata_len
+        })
+        .sum()
+}
+
 /// An entry in the response cache.
 ///
 /// This contains the response itself (or an error), the time it was received, and the time at which
@@ -133,7 +240,7 @@ impl Entry {
                 if let ProtoErrorKind::NoRecordsFound(NoRecords {
                     negative_ttl: Some(ttl),
                     ..
-                }) = e.kind.as_mut()
+                }) = e.kind_mut()
                 {
                     *ttl = ttl.saturating_sub(elapsed);
                 }
@@ -691,6 +798,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_memory_limit() {
+        let now = Instant::now();
+
+        let txt_response = |name: &Name, len: usize| {
+            let mut message = Message::response(0, OpCode::Query);
+            message.add_answer(Record::from_rdata(
+                name.clone(),
+                60,
+                RData::TXT(TXT::new(vec!["x".repeat(250); len / 250])),
+            ));
+            message
+        };
+
+        let cache = ResponseCache::with_options(
+            u64::MAX,
+            Some(16 * 1024),
+            CacheEvictionPolicy::Lru,
+            TtlConfig::default(),
+        );
+
+        // a single response larger than the limit is never admitted
+        let name = Name::from_str("large.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::TXT);
+        cache.insert(query.clone(), Ok(txt_response(&name, 20 * 1024)), now);
+        cache.cache.run_pending_tasks();
+        assert!(cache.get(&query, now).is_none());
+
+        let small = entry_size(&query, &Ok(txt_response(&name, 250)));
+        let large = entry_size(&query, &Ok(txt_response(&name, 2500)));
+        assert!(large >= small + 2250);
+
+        for i in 0..64 {
+            let name = Name::from_str(&format!("{i}.example.com.")).unwrap();
+            let query = Query::query(name.clone(), RecordType::TXT);
+            cache.insert(query, Ok(txt_response(&name, 1000)), now);
+        }
+        cache.cache.run_pending_tasks();
+
+        assert!(cache.memory_usage() <= 16 * 1024);
+        assert!(cache.entry_count() < 64);
+        assert!(cache.entry_count() > 0);
+    }
+
+    #[test]
+    fn test_unweighted_without_memory_limit() {
+        let now = Instant::now();
+        let cache = ResponseCache::new(8, TtlConfig::default());
+
+        for i in 0..4 {
+            let name = Name::from_str(&format!("{i}.example.com.")).unwrap();
+            let query = Query::query(name.clone(), RecordType::A);
+            let mut message = Message::response(0, OpCode::Query);
+            message.add_answer(Record::from_rdata(name, 60, RData::A(A::new(192, 0, 2, 1))));
+            cache.insert(query, Ok(message), now);
+        }
+        cache.cache.run_pending_tasks();
+
+        // entries are not sized, every entry counts as one
+        assert_eq!(cache.memory_usage(), 4);
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn ttl_config_deserialize_errors() {
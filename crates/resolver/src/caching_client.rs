@@ -9,12 +9,18 @@
 
 use std::{
     borrow::Cow,
+    collections::HashMap,
+    fmt,
     future::Future,
     pin::Pin,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
+use futures_util::{FutureExt, future::Shared};
 use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tracing::{Instrument, Span, debug_span, field};
 
 use crate::{
     cache::{MAX_TTL, ResponseCache, TtlConfig},
@@ -40,6 +46,16 @@ static LOCALHOST: Lazy<RData> =
 static LOCALHOST_V4: Lazy<RData> = Lazy::new(|| RData::A(A::new(127, 0, 0, 1)));
 static LOCALHOST_V6: Lazy<RData> = Lazy::new(|| RData::AAAA(AAAA::new(0, 0, 0, 0, 0, 0, 0, 1)));
 
+/// The span of a lookup through the cache, recording whether it was answered from the cache
+fn lookup_span(query: &Query) -> Span {
+    debug_span!(
+        "cache_lookup",
+        name = %query.name(),
+        record_type = %query.query_type(),
+        cache_hit = field::Empty,
+    )
+}
+
 /// Counts the depth of CNAME query resolutions.
 #[derive(Default, Clone, Copy)]
 struct DepthTracker {
@@ -60,6 +76,71 @@ impl DepthTracker {
     const MAX_QUERY_DEPTH: u8 = 8; // arbitrarily chosen number...
 }
 
+/// A lookup in progress, whose result is shared by all the lookups of the same query
+type SharedLookup = Shared<Pin<Box<dyn Future<Output = Result<Lookup, ProtoError>> + Send>>>;
+
+/// A lookup in progress, along with a count of the lookups waiting for it
+#[derive(Clone)]
+struct InFlightLookup {
+    waiters: Arc<()>,
+    lookup: SharedLookup,
+}
+
+/// The lookups in progress, keyed by their query and request options
+///
+/// Concurrent lookups of the same query wait for a single upstream query, instead of each sending
+/// their own. This matters most when a popular record expires from the cache, where every lookup
+/// would otherwise miss the cache until the first of them completes. Lookups with different
+/// options, e.g. one requesting DNSSEC records, are not shared.
+#[derive(Clone, Default)]
+struct InFlight(Arc<Mutex<HashMap<(Query, DnsRequestOptions), InFlightLookup>>>);
+
+impl fmt::Debug for InFlight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InFlight")
+            .field(&self.0.lock().len())
+            .finish()
+    }
+}
+
+/// Waits for a shared lookup, and removes it from the lookups in progress once it is no longer
+/// needed
+struct InFlightGuard {
+    in_flight: InFlight,
+    key: (Query, DnsRequestOptions),
+    lookup: InFlightLookup,
+    done: bool,
+}
+
+impl InFlightGuard {
+    /// Marks the lookup as completed, its result is now cached
+    fn complete(&mut self) {
+        self.done = true;
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.0.lock();
+        let Some(current) = in_flight.get(&self.key) else {
+            return;
+        };
+
+        if !Arc::ptr_eq(&current.waiters, &self.lookup.waiters) {
+            return;
+        }
+
+        // the result is in the cache once the lookup completed; otherwise keep the lookup going
+        // while other lookups still wait for it, besides this one and the map
+        if self.done || Arc::strong_count(&self.lookup.waiters) <= 2 {
+            let removed = in_flight.remove(&self.key);
+            // the lookup may hold other clients, drop it without holding the lock
+            drop(in_flight);
+            drop(removed);
+        }
+    }
+}
+
 // TODO: need to consider this storage type as it compares to Authority in server...
 //       should it just be an variation on Authority?
 #[derive(Clone, Debug)]
@@ -71,6 +152,7 @@ where
     cache: ResponseCache,
     client: C,
     preserve_intermediates: bool,
+    in_flight: InFlight,
 }
 
 impl<C> CachingClient<C>
@@ -95,22 +177,54 @@ where
             cache,
             client,
             preserve_intermediates,
+            in_flight: InFlight::default(),
         }
     }
 
     /// Perform a lookup against this caching client, looking first in the cache for a result
+    ///
+    /// Concurrent lookups of the same query with the same options share the result of a single
+    /// lookup.
     pub fn lookup(
         &self,
         query: Query,
         options: DnsRequestOptions,
     ) -> Pin<Box<dyn Future<Output = Result<Lookup, ProtoError>> + Send>> {
-        Box::pin(Self::inner_lookup(
-            query,
-            options,
-            self.clone(),
-            vec![],
-            DepthTracker::default(),
-        ))
+        let lookup = self
+            .in_flight
+            .0
+            .lock()
+            .entry((query.clone(), options))
+            .or_insert_with(|| {
+                let span = lookup_span(&query);
+                let lookup: Pin<Box<dyn Future<Output = _> + Send>> = Box::pin(
+                    Self::inner_lookup(
+                        query.clone(),
+                        options,
+                        self.clone(),
+                        vec![],
+                        DepthTracker::default(),
+                    )
+                    .instrument(span),
+                );
+                InFlightLookup {
+                    waiters: Arc::new(()),
+                    lookup: lookup.shared(),
+                }
+            })
+            .clone();
+
+        let mut guard = InFlightGuard {
+            in_flight: self.in_flight.clone(),
+            key: (query, options),
+            lookup,
+            done: false,
+        };
+        Box::pin(async move {
+            let result = (&mut guard.lookup.lookup).await;
+            guard.complete();
+            result
+        })
     }
 
     async fn inner_lookup(
@@ -162,7 +276,9 @@ where
 
         let is_dnssec = client.client.is_verifying_dnssec();
 
-        if let Some(cached_lookup) = client.lookup_from_cache(&query) {
+        let cached_lookup = client.lookup_from_cache(&query);
+        Span::current().record("cache_hit", cached_lookup.is_some());
+        if let Some(cached_lookup) = cached_lookup {
             return cached_lookup;
         };
 
@@ -361,13 +477,16 @@ where
         if was_cname && !depth.is_exhausted() {
             let next_query = Query::query(search_name, query.query_type());
             Ok(Records::CnameChain {
-                next: Box::pin(Self::inner_lookup(
-                    next_query,
-                    options,
-                    client.clone(),
-                    preserved_records,
-                    depth.nest(),
-                )),
+                next: Box::pin(
+                    Self::inner_lookup(
+                        next_query.clone(),
+                        options,
+                        client.clone(),
+                        preserved_records,
+                        depth.nest(),
+                    )
+                    .instrument(lookup_span(&next_query)),
+                ),
             })
         } else {
             // TODO: review See https://tools.ietf.org/html/rfc2308 for NoData section
@@ -510,6 +629,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_concurrent_lookups_are_coalesced() {
+        subscribe();
+        // without a cache, the second lookup could only be answered by the first
+        let client = CachingClient::new(0, mock(vec![v4_message()]), false);
+        let query = Query::query(Name::root(), RecordType::A);
+
+        let first = client.lookup(query.clone(), DnsRequestOptions::default());
+        let second = client.lookup(query.clone(), DnsRequestOptions::default());
+        let (first, second) = block_on(futures_util::future::join(first, second));
+
+        for lookup in [first, second] {
+            assert_eq!(
+                lookup.unwrap().iter().cloned().collect::<Vec<_>>(),
+                vec![RData::A(A::new(127, 0, 0, 1))]
+            );
+        }
+        assert!(client.in_flight.0.lock().is_empty());
+
+        // once completed, lookups of the same query are sent again
+        assert!(block_on(client.lookup(query, DnsRequestOptions::default())).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_lookups_with_different_options() {
+        subscribe();
+        let client = CachingClient::new(0, mock(vec![v4_message(), v4_message()]), false);
+        let query = Query::query(Name::root(), RecordType::A);
+
+        let mut dnssec_ok = DnsRequestOptions::default();
+        dnssec_ok.use_edns = true;
+        dnssec_ok.edns_set_dnssec_ok = true;
+
+        // a lookup requesting DNSSEC records does not wait for one which does not
+        let first = client.lookup(query.clone(), DnsRequestOptions::default());
+        let second = client.lookup(query.clone(), dnssec_ok);
+        assert_eq!(client.in_flight.0.lock().len(), 2);
+
+        let (first, second) = block_on(futures_util::future::join(first, second));
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        assert!(client.in_flight.0.lock().is_empty());
+
+        // each lookup sent its own query
+        assert!(block_on(client.lookup(query, DnsRequestOptions::default())).is_err());
+    }
+
+    #[test]
+    fn test_dropped_lookup_is_removed() {
+        subscribe();
+        let client = CachingClient::new(0, mock(vec![v4_message()]), false);
+        let query = Query::query(Name::root(), RecordType::A);
+
+        let first = client.lookup(query.clone(), DnsRequestOptions::default());
+        let second = client.lookup(query.clone(), DnsRequestOptions::default());
+        drop(first);
+        assert_eq!(client.in_flight.0.lock().len(), 1);
+        drop(second);
+        assert!(client.in_flight.0.lock().is_empty());
+    }
+
     #[test]
     fn test_no_cache_insert() {
         subscribe();
@@ -10,6 +10,7 @@
 
 use std::collections::HashSet;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
@@ -22,7 +23,7 @@ use crate::proto::http::DEFAULT_DNS_QUERY_PATH;
 use crate::proto::rr::Name;
 #[cfg(feature = "__tls")]
 use crate::proto::rustls::client_config;
-use crate::proto::xfer::Protocol;
+use crate::proto::xfer::{Protocol, TruncationPolicy};
 
 /// Configuration for the upstream nameservers to use for resolution
 #[derive(Clone, Debug, Default)]
@@ -36,6 +37,9 @@ pub struct ResolverConfig {
     search: Vec<Name>,
     // nameservers to use for resolution.
     name_servers: Vec<NameServerConfig>,
+    // nameservers to use instead for the names within some domains
+    #[cfg_attr(feature = "serde", serde(default))]
+    domain_name_servers: Vec<DomainNameServers>,
 }
 
 impl ResolverConfig {
@@ -48,6 +52,7 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.udp_and_tcp().collect(),
+            domain_name_servers: Vec::new(),
         }
     }
 
@@ -61,6 +66,22 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.tls().collect(),
+            domain_name_servers: Vec::new(),
+        }
+    }
+
+    /// Create a new `ResolverConfig` from [`ServerGroup`] configuration.
+    ///
+    /// Connects via TLS, and falls back to UDP and TCP if that fails, see
+    /// [`PrivacyProfile::Opportunistic`].
+    #[cfg(feature = "__tls")]
+    pub fn opportunistic_tls(config: &ServerGroup<'_>) -> Self {
+        Self {
+            // TODO: this should get the hostname and use the basename as the default
+            domain: None,
+            search: vec![],
+            name_servers: config.opportunistic_tls().collect(),
+            domain_name_servers: Vec::new(),
         }
     }
 
@@ -74,6 +95,7 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.https().collect(),
+            domain_name_servers: Vec::new(),
         }
     }
 
@@ -87,6 +109,7 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.quic().collect(),
+            domain_name_servers: Vec::new(),
         }
     }
 
@@ -100,6 +123,7 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.h3().collect(),
+            domain_name_servers: Vec::new(),
         }
     }
 
@@ -119,10 +143,12 @@ impl ResolverConfig {
             domain,
             search,
             name_servers,
+            domain_name_servers: Vec::new(),
         }
     }
 
-    /// Take the `domain`, `search`, and `name_servers` from the config.
+    /// Take the `domain`, `search`, and `name_servers` from the config, dropping the name servers of
+    /// specific domains.
     pub fn into_parts(self) -> (Option<Name>, Vec<Name>, Vec<NameServerConfig>) {
         (self.domain, self.search, self.name_servers)
     }
@@ -162,6 +188,40 @@ impl ResolverConfig {
     pub fn name_servers(&self) -> &[NameServerConfig] {
         &self.name_servers
     }
+
+    /// Use `name_servers` instead of the other name servers for the names within `domain`
+    ///
+    /// The name servers of the most specific domain containing a name are used.
+    pub fn add_domain_name_servers(&mut self, domain: Name, name_servers: Vec<NameServerConfig>) {
+        self.domain_name_servers
+            .push(DomainNameServers::new(domain, name_servers));
+    }
+
+    /// Returns the name servers used for the names within specific domains
+    pub fn domain_name_servers(&self) -> &[DomainNameServers] {
+        &self.domain_name_servers
+    }
+}
+
+/// The name servers for the names within a domain, see [`ResolverConfig::add_domain_name_servers`]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct DomainNameServers {
+    /// The domain, including all names below it
+    pub domain: Name,
+    /// The name servers for the names within `domain`
+    pub name_servers: Vec<NameServerConfig>,
+}
+
+impl DomainNameServers {
+    /// Creates the name servers for the names within `domain`
+    pub fn new(domain: Name, name_servers: Vec<NameServerConfig>) -> Self {
+        Self {
+            domain,
+            name_servers,
+        }
+    }
 }
 
 /// Configuration for the NameServer
@@ -188,6 +248,11 @@ pub struct NameServerConfig {
     pub trust_negative_responses: bool,
     /// Connection protocols configured for this server.
     pub connections: Vec<ConnectionConfig>,
+    /// Which of the `connections` may be used, depending on whether they are encrypted.
+    ///
+    /// Defaults to [`PrivacyProfile::Any`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub privacy: PrivacyProfile,
 }
 
 impl NameServerConfig {
@@ -197,6 +262,7 @@ impl NameServerConfig {
             ip,
             trust_negative_responses: true,
             connections: vec![ConnectionConfig::udp(), ConnectionConfig::tcp()],
+            privacy: PrivacyProfile::Any,
         }
     }
 
@@ -206,6 +272,7 @@ impl NameServerConfig {
             ip,
             trust_negative_responses: true,
             connections: vec![ConnectionConfig::udp()],
+            privacy: PrivacyProfile::Any,
         }
     }
 
@@ -215,6 +282,7 @@ impl NameServerConfig {
             ip,
             trust_negative_responses: true,
             connections: vec![ConnectionConfig::tcp()],
+            privacy: PrivacyProfile::Any,
         }
     }
 
@@ -225,6 +293,7 @@ impl NameServerConfig {
             ip,
             trust_negative_responses: true,
             connections: vec![ConnectionConfig::tls(server_name)],
+            privacy: PrivacyProfile::Any,
         }
     }
 
@@ -235,6 +304,7 @@ impl NameServerConfig {
             ip,
             trust_negative_responses: true,
             connections: vec![ConnectionConfig::https(server_name, path)],
+            privacy: PrivacyProfile::Any,
         }
     }
 
@@ -245,6 +315,7 @@ impl NameServerConfig {
             ip,
             trust_negative_responses: true,
             connections: vec![ConnectionConfig::quic(server_name)],
+            privacy: PrivacyProfile::Any,
         }
     }
 
@@ -255,6 +326,7 @@ impl NameServerConfig {
             ip,
             trust_negative_responses: true,
             connections: vec![ConnectionConfig::h3(server_name, path)],
+            privacy: PrivacyProfile::Any,
         }
     }
 
@@ -268,6 +340,7 @@ impl NameServerConfig {
             ip,
             trust_negative_responses,
             connections,
+            privacy: PrivacyProfile::Any,
         }
     }
 }
@@ -277,6 +350,43 @@ fn default_trust_negative_responses() -> bool {
     true
 }
 
+/// Restricts the use of the unencrypted connections of a name server
+///
+/// Encrypted connections are TLS, HTTPS, QUIC and HTTP/3; the certificates presented by the
+/// server are validated with the verifier of `ResolverOpts::tls_config`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum PrivacyProfile {
+    /// All connections are used, whether they are encrypted or not
+    #[default]
+    Any,
+    /// Encrypted connections are tried first, unencrypted ones are only used once all other
+    /// connections of the resolver failed for a request
+    ///
+    /// Answers received over the unencrypted connections are counted as privacy downgrades, see
+    /// `NameServerPool::privacy_stats()`.
+    Opportunistic,
+    /// Only encrypted connections are used, unencrypted ones are ignored
+    Strict,
+}
+
+impl PrivacyProfile {
+    /// Returns true if a connection with the `protocol` may be used at all
+    pub fn allows(self, protocol: Protocol) -> bool {
+        self != Self::Strict || protocol.is_encrypted()
+    }
+
+    /// Returns true if a connection with the `protocol` is only used after the encrypted
+    /// connections failed
+    pub fn is_fallback(self, protocol: Protocol) -> bool {
+        self == Self::Opportunistic && !protocol.is_encrypted()
+    }
+}
+
 /// Configuration for a connection to a nameserver
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
@@ -288,6 +398,11 @@ pub struct ConnectionConfig {
     pub protocol: ProtocolConfig,
     /// The client address (IP and port) to use for connecting to the server
     pub bind_addr: Option<SocketAddr>,
+    /// The TLS client configuration for encrypted connections to the server, overriding
+    /// [`ResolverOpts::tls_config`], e.g. to trust a private CA or to authenticate the client
+    #[cfg(feature = "__tls")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub tls_config: Option<Arc<rustls::ClientConfig>>,
 }
 
 impl ConnectionConfig {
@@ -338,6 +453,8 @@ impl ConnectionConfig {
             port: protocol.default_port(),
             protocol,
             bind_addr: None,
+            #[cfg(feature = "__tls")]
+            tls_config: None,
         }
     }
 }
@@ -360,6 +477,8 @@ impl<'de> serde::Deserialize<'de> for ConnectionConfig {
             port: parts.port.unwrap_or_else(|| parts.protocol.default_port()),
             protocol: parts.protocol,
             bind_addr: parts.bind_addr,
+            #[cfg(feature = "__tls")]
+            tls_config: None,
         })
     }
 }
@@ -437,6 +556,21 @@ impl ProtocolConfig {
             ProtocolConfig::H3 { .. } => 443,
         }
     }
+
+    /// The server name used in the TLS handshake, for encrypted protocols
+    pub fn server_name(&self) -> Option<&Arc<str>> {
+        match self {
+            ProtocolConfig::Udp | ProtocolConfig::Tcp => None,
+            #[cfg(feature = "__tls")]
+            ProtocolConfig::Tls { server_name } => Some(server_name),
+            #[cfg(feature = "__https")]
+            ProtocolConfig::Https { server_name, .. } => Some(server_name),
+            #[cfg(feature = "__quic")]
+            ProtocolConfig::Quic { server_name } => Some(server_name),
+            #[cfg(feature = "__h3")]
+            ProtocolConfig::H3 { server_name, .. } => Some(server_name),
+        }
+    }
 }
 
 /// Configuration for the Resolver
@@ -454,6 +588,17 @@ pub struct ResolverOpts {
     ///  would never be assumed to be a TLD, and would always be appended to either the search
     #[cfg_attr(feature = "serde", serde(default = "default_ndots"))]
     pub ndots: usize,
+    /// The order in which names that are not fully qualified are tried as is and with the search
+    ///  domains appended. Defaults to [`SearchOrder::Ndots`], the system configuration of each
+    ///  platform selects the order of its own resolver
+    pub search_order: SearchOrder,
+    /// Never query names that consist of a single label as is
+    ///
+    /// By default a single-label name like `printer` that is not found within any of the search
+    ///  domains is finally looked up as `printer.`, which sends it to the root servers and may
+    ///  resolve it to a TLD. Enabling this restricts single-label names to the search domains,
+    ///  except for `localhost`.
+    pub avoid_single_label_queries: bool,
     /// Specify the timeout for a request. Defaults to 5 seconds
     #[cfg_attr(feature = "serde", serde(default = "default_timeout"))]
     pub timeout: Duration,
@@ -471,9 +616,29 @@ pub struct ResolverOpts {
     pub validate: bool,
     /// The ip_strategy for the Resolver to use when lookup Ipv4 or Ipv6 addresses
     pub ip_strategy: LookupIpStrategy,
+    /// Strategies that replace `ip_strategy` for the names within some domains, e.g. those of an
+    ///  internal network that only has IPv4 addresses
+    ///
+    /// The override of the most specific domain applies. Names that are not fully qualified are
+    ///  matched as given, before any search domain is appended.
+    pub ip_strategy_overrides: Vec<IpStrategyOverride>,
+    /// Only look up the addresses of the families that the host has a route for, like
+    ///  `AI_ADDRCONFIG` of `getaddrinfo`
+    ///
+    /// The routes are checked on every lookup, without sending any packets. This neither changes
+    ///  the `Ipv4Only` and `Ipv6Only` strategies, nor any strategy if no family is reachable at
+    ///  all. Disabled by default.
+    pub filter_unreachable_families: bool,
     /// Cache size is in number of responses (some responses can be large)
     #[cfg_attr(feature = "serde", serde(default = "default_cache_size"))]
     pub cache_size: u64,
+    /// Optional limit, in bytes, on the estimated memory used by cached responses
+    ///
+    /// If this is set, the cache is bounded by the size of its responses instead of by
+    /// `cache_size`, and responses larger than the limit are not cached at all.
+    pub cache_max_memory: Option<u64>,
+    /// The policy used to admit responses to, and evict them from, a full cache
+    pub cache_eviction_policy: CacheEvictionPolicy,
     /// Check /etc/hosts file before dns requery (only works for unix like OS)
     pub use_hosts_file: ResolveHosts,
     /// Optional minimum TTL for positive responses.
@@ -502,11 +667,29 @@ pub struct ResolverOpts {
     /// to a number of servers in parallel. Defaults to 2; 0 or 1 will execute requests serially.
     #[cfg_attr(feature = "serde", serde(default = "default_num_concurrent_reqs"))]
     pub num_concurrent_reqs: usize,
+    /// Optional limit on the number of lookups the resolver performs concurrently
+    ///
+    /// Lookups beyond the limit wait for an earlier one to complete, up to `max_queued_lookups`,
+    /// after which they fail immediately with [`ProtoErrorKind::LookupQueueFull`]. This bounds
+    /// the memory and upstream load of a burst of lookups. Defaults to no limit.
+    ///
+    /// [`ProtoErrorKind::LookupQueueFull`]: crate::proto::ProtoErrorKind::LookupQueueFull
+    pub max_outstanding_lookups: Option<usize>,
+    /// Number of lookups which may wait for one of the `max_outstanding_lookups` to complete
+    ///
+    /// Defaults to 0, failing every lookup beyond the limit. This is ignored if
+    /// `max_outstanding_lookups` is not set.
+    pub max_queued_lookups: usize,
     /// Preserve all intermediate records in the lookup response, such as CNAME records
     #[cfg_attr(feature = "serde", serde(default = "default_preserve_intermediates"))]
     pub preserve_intermediates: bool,
     /// Try queries over TCP if they fail over UDP.
     pub try_tcp_on_error: bool,
+    /// How to handle truncated responses received over UDP.
+    ///
+    /// Defaults to [`TruncationPolicy::RetryTcp`]. This can be overridden for a single query
+    /// through its `DnsRequestOptions`.
+    pub truncation_policy: TruncationPolicy,
     /// The server ordering strategy that the resolver should use.
     pub server_ordering_strategy: ServerOrderingStrategy,
     /// Request upstream recursive resolvers to not perform any recursion.
@@ -516,6 +699,12 @@ pub struct ResolverOpts {
     pub recursion_desired: bool,
     /// Local UDP ports to avoid when making outgoing queries
     pub avoid_local_udp_ports: Arc<HashSet<u16>>,
+    /// The range of local UDP ports to pick from when making outgoing queries
+    ///
+    /// Defaults to 1024-65535. This is ignored if os_port_selection is true, or if a port is set
+    /// in the bind address of the connection.
+    #[cfg_attr(feature = "serde", serde(default = "default_local_udp_port_range"))]
+    pub local_udp_port_range: RangeInclusive<u16>,
     /// Request UDP bind ephemeral ports directly from the OS
     ///
     /// Boolean parameter to specify whether to use the operating system's standard UDP port
@@ -542,6 +731,12 @@ pub struct ResolverOpts {
     /// This implements the mechanism described in
     /// [draft-vixie-dnsext-dns0x20-00](https://datatracker.ietf.org/doc/html/draft-vixie-dnsext-dns0x20-00).
     pub case_randomization: bool,
+    /// How long to keep listening for further responses over UDP once a response was received.
+    ///
+    /// A different response to the same query is a sign of a spoofing attempt: both responses are
+    /// discarded and the query is retried over TCP. This delays each UDP response by the window,
+    /// which is capped to half the timeout. Disabled by default.
+    pub duplicate_response_window: Option<Duration>,
     /// Path to a DNSSEC trust anchor file.
     ///
     /// If this is provided, `validate` will automatically be set to `true`, enabling DNSSEC validation.
@@ -555,6 +750,8 @@ impl Default for ResolverOpts {
     fn default() -> Self {
         Self {
             ndots: default_ndots(),
+            search_order: SearchOrder::default(),
+            avoid_single_label_queries: false,
             timeout: default_timeout(),
             attempts: default_attempts(),
             check_names: default_check_names(),
@@ -562,30 +759,54 @@ impl Default for ResolverOpts {
             #[cfg(feature = "__dnssec")]
             validate: false,
             ip_strategy: LookupIpStrategy::default(),
+            ip_strategy_overrides: Vec::new(),
+            filter_unreachable_families: false,
             cache_size: default_cache_size(),
+            cache_max_memory: None,
+            cache_eviction_policy: CacheEvictionPolicy::default(),
             use_hosts_file: ResolveHosts::default(),
             positive_min_ttl: None,
             negative_min_ttl: None,
             positive_max_ttl: None,
             negative_max_ttl: None,
             num_concurrent_reqs: default_num_concurrent_reqs(),
+            max_outstanding_lookups: None,
+            max_queued_lookups: 0,
 
             // Defaults to `true` to match the behavior of dig and nslookup.
             preserve_intermediates: default_preserve_intermediates(),
 
             try_tcp_on_error: false,
+            truncation_policy: TruncationPolicy::default(),
             server_ordering_strategy: ServerOrderingStrategy::default(),
             recursion_desired: default_recursion_desired(),
             avoid_local_udp_ports: Arc::default(),
+            local_udp_port_range: default_local_udp_port_range(),
             os_port_selection: false,
             #[cfg(feature = "__tls")]
             tls_config: client_config(),
             case_randomization: false,
+            duplicate_response_window: None,
             trust_anchor: None,
         }
     }
 }
 
+impl ResolverOpts {
+    /// Returns the strategy for looking up the addresses of `name`, see `ip_strategy_overrides`
+    pub(crate) fn ip_strategy_for(&self, name: &Name) -> LookupIpStrategy {
+        self.ip_strategy_overrides
+            .iter()
+            .filter(|ip_override| ip_override.domain.zone_of(name))
+            .max_by_key(|ip_override| ip_override.domain.num_labels())
+            .map_or(self.ip_strategy, |ip_override| ip_override.strategy)
+    }
+}
+
+fn default_local_udp_port_range() -> RangeInclusive<u16> {
+    1024..=u16::MAX
+}
+
 fn default_ndots() -> usize {
     1
 }
@@ -632,6 +853,23 @@ pub enum LookupIpStrategy {
     Ipv6thenIpv4,
     /// Query for Ipv4 if that fails, query for Ipv6 (default)
     Ipv4thenIpv6,
+    /// Query for A and AAAA in parallel and alternate between the Ipv6 and Ipv4 addresses,
+    ///  starting with Ipv6, as recommended for connection attempts by RFC 8305 section 4
+    Ipv6AndIpv4Interleaved,
+}
+
+impl LookupIpStrategy {
+    /// Restricts the strategy to the address families that are reachable
+    ///
+    /// Strategies for a single family are kept, as is any strategy if neither family is reachable.
+    pub(crate) fn restrict_to(self, ipv4: bool, ipv6: bool) -> Self {
+        match (self, ipv4, ipv6) {
+            (Self::Ipv4Only | Self::Ipv6Only, _, _) => self,
+            (_, true, false) => Self::Ipv4Only,
+            (_, false, true) => Self::Ipv6Only,
+            _ => self,
+        }
+    }
 }
 
 impl Default for LookupIpStrategy {
@@ -641,6 +879,45 @@ impl Default for LookupIpStrategy {
     }
 }
 
+/// A lookup ip strategy for the names within a domain, see [`ResolverOpts::ip_strategy_overrides`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct IpStrategyOverride {
+    /// The domain, including all names below it
+    pub domain: Name,
+    /// The strategy for the names within `domain`
+    pub strategy: LookupIpStrategy,
+}
+
+impl IpStrategyOverride {
+    /// Creates an override of the strategy for the names within `domain`
+    pub fn new(domain: Name, strategy: LookupIpStrategy) -> Self {
+        Self { domain, strategy }
+    }
+}
+
+/// The order in which a name that is not fully qualified is tried as is and within the search
+/// domains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum SearchOrder {
+    /// Names with at least `ndots` dots are tried as is before the search domains, others after
+    /// them, like resolv.conf on Unix and macOS
+    Ndots,
+    /// Names with a dot are only tried as is and single-label names only within the search
+    /// domains, like on Windows. `ndots` is ignored
+    Windows,
+}
+
+impl Default for SearchOrder {
+    /// Returns [`SearchOrder::Ndots`] as the default.
+    fn default() -> Self {
+        Self::Ndots
+    }
+}
+
 /// The strategy for establishing the query order of name servers in a pool.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -664,6 +941,22 @@ impl Default for ServerOrderingStrategy {
     }
 }
 
+/// The admission and eviction policy of the response cache.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum CacheEvictionPolicy {
+    /// Responses are only admitted to a full cache if they are estimated to be requested more
+    /// frequently than the least recently used entry they would replace. This is the default.
+    ///
+    /// The frequency of queries, including those which missed the cache, is tracked in a
+    /// Count-Min Sketch, so that a burst of one-off queries can not flush popular responses.
+    #[default]
+    TinyLfu,
+    /// Responses are always admitted, evicting the least recently used entries.
+    Lru,
+}
+
 /// Whether the system hosts file should be respected by the resolver.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -773,6 +1066,28 @@ impl<'a> ServerGroup<'a> {
         })
     }
 
+    /// Create an iterator with `NameServerConfig` for each IP address in the group.
+    ///
+    /// The TLS connection is preferred over the UDP and TCP connections, see
+    /// [`PrivacyProfile::Opportunistic`].
+    #[cfg(feature = "__tls")]
+    pub fn opportunistic_tls(&self) -> impl Iterator<Item = NameServerConfig> + 'a {
+        let this = *self;
+        self.ips.iter().map(move |&ip| {
+            let mut config = NameServerConfig::new(
+                ip,
+                true,
+                vec![
+                    ConnectionConfig::tls(Arc::from(this.server_name)),
+                    ConnectionConfig::udp(),
+                    ConnectionConfig::tcp(),
+                ],
+            );
+            config.privacy = PrivacyProfile::Opportunistic;
+            config
+        })
+    }
+
     /// Create an iterator with `NameServerConfig` for each IP address in the group.
     #[cfg(feature = "__https")]
     pub fn https(&self) -> impl Iterator<Item = NameServerConfig> + 'a {
@@ -833,6 +1148,7 @@ mod tests {
         assert_eq!(code.attempts, json.attempts);
         assert_eq!(code.check_names, json.check_names);
         assert_eq!(code.edns0, json.edns0);
+        #[cfg(feature = "__dnssec")]
         assert_eq!(code.validate, json.validate);
         assert_eq!(code.ip_strategy, json.ip_strategy);
         assert_eq!(code.cache_size, json.cache_size);
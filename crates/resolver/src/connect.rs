@@ -0,0 +1,426 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Connection candidates for HTTPS origins, based on HTTPS (SVCB) records
+//!
+//! See [`Resolver::connection_candidates`](crate::Resolver::connection_candidates). This
+//!  implements the client side of [RFC 9460](https://datatracker.ietf.org/doc/html/rfc9460): it
+//!  follows AliasMode records, orders the ServiceMode endpoints by priority, resolves their
+//!  addresses and computes the ALPN protocols they support.
+
+use std::net::{IpAddr, SocketAddr};
+
+use crate::proto::rr::rdata::svcb::{EchConfigList, SVCB, SvcParamKey, SvcParamValue};
+use crate::proto::rr::{Name, RData, Record};
+
+/// Maximum number of AliasMode records that are followed before giving up
+pub(crate) const MAX_ALIAS_CHAIN: usize = 8;
+
+/// The ALPN protocol that HTTPS endpoints support unless they set `no-default-alpn`
+const DEFAULT_ALPN: &str = "http/1.1";
+
+/// An address to connect to for an HTTPS origin, see
+/// [`Resolver::connection_candidates`](crate::Resolver::connection_candidates)
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConnectionCandidate {
+    /// The address to connect to
+    pub addr: SocketAddr,
+    /// The name of the service endpoint, i.e. the effective TargetName of its HTTPS record
+    ///
+    /// TLS connections must still use the origin's name for SNI and certificate validation.
+    pub target: Name,
+    /// The SvcPriority of the HTTPS record, `None` for a connection without HTTPS records
+    pub priority: Option<u16>,
+    /// The ALPN protocols the endpoint supports, most preferred first
+    ///
+    /// Empty for a connection without HTTPS records, in which case the client uses its defaults.
+    pub alpn: Vec<String>,
+    /// The Encrypted Client Hello configurations of the endpoint
+    pub ech_config_list: Option<EchConfigList>,
+}
+
+/// The contents of an HTTPS RRset
+#[derive(Debug, PartialEq)]
+pub(crate) enum HttpsRecords {
+    /// No usable records, the origin is connected to without them
+    None,
+    /// The records of the given name have to be used instead
+    Alias(Name),
+    /// The compatible ServiceMode endpoints, ordered by priority
+    Service(Vec<ServiceEndpoint>),
+}
+
+impl HttpsRecords {
+    /// Classifies the HTTPS records of a lookup
+    pub(crate) fn from_records(records: &[Record], default_port: u16) -> Self {
+        let https = records
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::HTTPS(https) => Some((record.name(), &https.0)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // if an RRset contains a record in AliasMode, ServiceMode records must be ignored
+        if let Some((_, alias)) = https.iter().find(|(_, svcb)| svcb.svc_priority() == 0) {
+            // a TargetName of "." indicates that the service is not available; this is advisory,
+            // so fall back to connecting to the origin directly
+            return if alias.target_name().is_root() {
+                Self::None
+            } else {
+                Self::Alias(alias.target_name().clone())
+            };
+        }
+
+        let mut endpoints = https
+            .into_iter()
+            .filter_map(|(owner, svcb)| ServiceEndpoint::new(owner, svcb, default_port))
+            .collect::<Vec<_>>();
+        if endpoints.is_empty() {
+            return Self::None;
+        }
+
+        // the sort is stable, so endpoints of the same priority keep the order of the response
+        endpoints.sort_by_key(|endpoint| endpoint.priority);
+        Self::Service(endpoints)
+    }
+}
+
+/// A ServiceMode endpoint that this client is compatible with
+#[derive(Debug, PartialEq)]
+pub(crate) struct ServiceEndpoint {
+    priority: u16,
+    pub(crate) target: Name,
+    port: u16,
+    alpn: Vec<String>,
+    ech_config_list: Option<EchConfigList>,
+    hints: Vec<IpAddr>,
+}
+
+impl ServiceEndpoint {
+    /// Returns `None` if the record is incompatible, i.e. it has mandatory keys that are not
+    /// supported or no ALPN protocols at all
+    fn new(owner: &Name, svcb: &SVCB, default_port: u16) -> Option<Self> {
+        // for ServiceMode records, a TargetName of "." stands for the owner name
+        let target = if svcb.target_name().is_root() {
+            owner.clone()
+        } else {
+            svcb.target_name().clone()
+        };
+
+        let mut endpoint = Self {
+            priority: svcb.svc_priority(),
+            target,
+            port: default_port,
+            alpn: Vec::new(),
+            ech_config_list: None,
+            hints: Vec::new(),
+        };
+
+        let mut default_alpn = true;
+        for (_, value) in svcb.svc_params() {
+            match value {
+                SvcParamValue::Mandatory(mandatory) => {
+                    if mandatory.0.iter().any(|key| !is_supported(*key)) {
+                        return None;
+                    }
+                }
+                SvcParamValue::Alpn(alpn) => endpoint.alpn.extend(alpn.0.iter().cloned()),
+                SvcParamValue::NoDefaultAlpn => default_alpn = false,
+                SvcParamValue::Port(port) => endpoint.port = *port,
+                SvcParamValue::Ipv4Hint(hint) => endpoint
+                    .hints
+                    .extend(hint.0.iter().map(|a| IpAddr::V4(a.0))),
+                SvcParamValue::Ipv6Hint(hint) => endpoint
+                    .hints
+                    .extend(hint.0.iter().map(|aaaa| IpAddr::V6(aaaa.0))),
+                SvcParamValue::EchConfigList(ech) => endpoint.ech_config_list = Some(ech.clone()),
+                SvcParamValue::Unknown(_) => {}
+            }
+        }
+
+        if default_alpn && !endpoint.alpn.iter().any(|alpn| alpn == DEFAULT_ALPN) {
+            endpoint.alpn.push(DEFAULT_ALPN.to_owned());
+        }
+
+        if endpoint.alpn.is_empty() {
+            return None;
+        }
+
+        Some(endpoint)
+    }
+}
+
+fn is_supported(key: SvcParamKey) -> bool {
+    matches!(
+        key,
+        SvcParamKey::Mandatory
+            | SvcParamKey::Alpn
+            | SvcParamKey::NoDefaultAlpn
+            | SvcParamKey::Port
+            | SvcParamKey::Ipv4Hint
+            | SvcParamKey::EchConfigList
+            | SvcParamKey::Ipv6Hint
+    )
+}
+
+/// Builds the connection candidates from the endpoints and their resolved addresses
+///
+/// `addresses` contains the addresses of every endpoint, in the same order; if it is empty, the
+/// address hints of the endpoint are used instead. `fallback` are the addresses of the origin,
+/// which are connected to last, unless every endpoint supports ECH: falling back to a
+/// connection without HTTPS records would then defeat ECH.
+pub(crate) fn candidates(
+    endpoints: Vec<ServiceEndpoint>,
+    addresses: Vec<Vec<IpAddr>>,
+    origin: &Name,
+    port: u16,
+    fallback: Vec<IpAddr>,
+) -> Vec<ConnectionCandidate> {
+    let ech_required = !endpoints.is_empty()
+        && endpoints
+            .iter()
+            .all(|endpoint| endpoint.ech_config_list.is_some());
+
+    let mut candidates = Vec::new();
+    for (endpoint, addresses) in endpoints.into_iter().zip(addresses) {
+        let addresses = if addresses.is_empty() {
+            endpoint.hints
+        } else {
+            addresses
+        };
+
+        for ip in addresses {
+            candidates.push(ConnectionCandidate {
+                addr: SocketAddr::new(ip, endpoint.port),
+                target: endpoint.target.clone(),
+                priority: Some(endpoint.priority),
+                alpn: endpoint.alpn.clone(),
+                ech_config_list: endpoint.ech_config_list.clone(),
+            });
+        }
+    }
+
+    if !ech_required {
+        for ip in fallback {
+            let addr = SocketAddr::new(ip, port);
+            if candidates.iter().any(|candidate| candidate.addr == addr) {
+                continue;
+            }
+
+            candidates.push(ConnectionCandidate {
+                addr,
+                target: origin.clone(),
+                priority: None,
+                alpn: Vec::new(),
+                ech_config_list: None,
+            });
+        }
+    }
+
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::str::FromStr;
+
+    use crate::proto::rr::rdata::svcb::{Alpn, IpHint, Mandatory};
+    use crate::proto::rr::rdata::{A, AAAA, HTTPS};
+
+    use super::*;
+
+    fn https(owner: &str, priority: u16, target: &str, params: Vec<SvcParamValue>) -> Record {
+        let params = params
+            .into_iter()
+            .map(|value| {
+                let key = match &value {
+                    SvcParamValue::Mandatory(_) => SvcParamKey::Mandatory,
+                    SvcParamValue::Alpn(_) => SvcParamKey::Alpn,
+                    SvcParamValue::NoDefaultAlpn => SvcParamKey::NoDefaultAlpn,
+                    SvcParamValue::Port(_) => SvcParamKey::Port,
+                    SvcParamValue::Ipv4Hint(_) => SvcParamKey::Ipv4Hint,
+                    SvcParamValue::EchConfigList(_) => SvcParamKey::EchConfigList,
+                    SvcParamValue::Ipv6Hint(_) => SvcParamKey::Ipv6Hint,
+                    SvcParamValue::Unknown(_) => SvcParamKey::Key(65280),
+                };
+                (key, value)
+            })
+            .collect();
+
+        Record::from_rdata(
+            Name::from_str(owner).unwrap(),
+            300,
+            RData::HTTPS(HTTPS(SVCB::new(
+                priority,
+                Name::from_str(target).unwrap(),
+                params,
+            ))),
+        )
+    }
+
+    fn alpn(protocols: &[&str]) -> SvcParamValue {
+        SvcParamValue::Alpn(Alpn(protocols.iter().map(|p| p.to_string()).collect()))
+    }
+
+    fn endpoints(records: &[Record]) -> Vec<ServiceEndpoint> {
+        match HttpsRecords::from_records(records, 443) {
+            HttpsRecords::Service(endpoints) => endpoints,
+            other => panic!("expected ServiceMode records: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_alias_mode() {
+        let records = [
+            https("example.com.", 0, "svc.example.net.", vec![]),
+            https("example.com.", 1, ".", vec![]),
+        ];
+        assert_eq!(
+            HttpsRecords::from_records(&records, 443),
+            HttpsRecords::Alias(Name::from_str("svc.example.net.").unwrap())
+        );
+
+        let records = [https("example.com.", 0, ".", vec![])];
+        assert_eq!(
+            HttpsRecords::from_records(&records, 443),
+            HttpsRecords::None
+        );
+        assert_eq!(HttpsRecords::from_records(&[], 443), HttpsRecords::None);
+    }
+
+    #[test]
+    fn test_service_mode() {
+        let records = [
+            https("example.com.", 2, ".", vec![]),
+            https(
+                "example.com.",
+                1,
+                "svc.example.net.",
+                vec![alpn(&["h3", "h2"]), SvcParamValue::Port(8443)],
+            ),
+            https(
+                "example.com.",
+                3,
+                ".",
+                vec![alpn(&["h2"]), SvcParamValue::NoDefaultAlpn],
+            ),
+        ];
+
+        let endpoints = endpoints(&records);
+        assert_eq!(
+            endpoints
+                .iter()
+                .map(|endpoint| (
+                    endpoint.priority,
+                    endpoint.target.to_string(),
+                    endpoint.port
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, "svc.example.net.".to_string(), 8443),
+                (2, "example.com.".to_string(), 443),
+                (3, "example.com.".to_string(), 443),
+            ]
+        );
+        assert_eq!(endpoints[0].alpn, vec!["h3", "h2", "http/1.1"]);
+        assert_eq!(endpoints[1].alpn, vec!["http/1.1"]);
+        assert_eq!(endpoints[2].alpn, vec!["h2"]);
+    }
+
+    #[test]
+    fn test_incompatible_records() {
+        let records = [
+            https(
+                "example.com.",
+                1,
+                ".",
+                vec![SvcParamValue::Mandatory(Mandatory(vec![SvcParamKey::Key(
+                    65280,
+                )]))],
+            ),
+            https("example.com.", 2, ".", vec![SvcParamValue::NoDefaultAlpn]),
+        ];
+        assert_eq!(
+            HttpsRecords::from_records(&records, 443),
+            HttpsRecords::None
+        );
+    }
+
+    #[test]
+    fn test_candidates() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let hint = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let records = [
+            https("example.com.", 1, "svc.example.net.", vec![alpn(&["h2"])]),
+            https(
+                "example.com.",
+                2,
+                ".",
+                vec![SvcParamValue::Ipv6Hint(IpHint(vec![AAAA(Ipv6Addr::new(
+                    0x2001, 0xdb8, 0, 0, 0, 0, 0, 1,
+                ))]))],
+            ),
+        ];
+
+        let candidates = candidates(
+            endpoints(&records),
+            vec![vec![v4], vec![]],
+            &origin,
+            443,
+            vec![v4, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))],
+        );
+        assert_eq!(
+            candidates
+                .iter()
+                .map(|candidate| (candidate.addr, candidate.priority))
+                .collect::<Vec<_>>(),
+            vec![
+                (SocketAddr::new(v4, 443), Some(1)),
+                (SocketAddr::new(hint, 443), Some(2)),
+                (
+                    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)), 443),
+                    None
+                ),
+            ]
+        );
+        assert_eq!(candidates[0].alpn, vec!["h2", "http/1.1"]);
+        assert!(candidates[2].alpn.is_empty());
+    }
+
+    #[test]
+    fn test_ech_disables_fallback() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let records = [https(
+            "example.com.",
+            1,
+            ".",
+            vec![
+                SvcParamValue::Ipv4Hint(IpHint(vec![A(Ipv4Addr::new(192, 0, 2, 1))])),
+                SvcParamValue::EchConfigList(EchConfigList(vec![0, 1, 2])),
+            ],
+        )];
+
+        let candidates = candidates(
+            endpoints(&records),
+            vec![vec![]],
+            &origin,
+            443,
+            vec![IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))],
+        );
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].addr, SocketAddr::new(ip, 443));
+        assert_eq!(
+            candidates[0].ech_config_list,
+            Some(EchConfigList(vec![0, 1, 2]))
+        );
+    }
+}
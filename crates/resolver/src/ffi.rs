@@ -0,0 +1,450 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A C compatible API, for embedding the resolver in applications not written in Rust
+//!
+//! The declarations are generated with cbindgen into `hickory_resolver.h` in the `OUT_DIR` of the
+//! build. To link against them, build this crate as a static or dynamic library, e.g. with
+//! `cargo rustc -p hickory-resolver --features ffi --crate-type staticlib`.
+//!
+//! A resolver is created with [`hickory_resolver_new`] or [`hickory_resolver_from_config`], and
+//! owns the threads on which its lookups run. Lookups return immediately, their callback is
+//! called exactly once with the result from one of those threads, unless the resolver is freed
+//! before the lookup completes.
+//!
+//! ```c
+//! void on_ips(void *context, HickoryStatus status, const HickoryIpAddr *addrs, size_t len) {
+//!     printf("%s: %zu addresses\n", hickory_status_str(status), len);
+//! }
+//!
+//! HickoryResolver *resolver = NULL;
+//! if (hickory_resolver_new(&resolver) == HICKORY_STATUS_OK) {
+//!     hickory_resolver_lookup_ip(resolver, "www.example.com.", on_ips, NULL);
+//! }
+//! ```
+
+use std::ffi::{CStr, c_char, c_void};
+use std::net::IpAddr;
+use std::ptr;
+
+use tokio::runtime::{self, Runtime};
+
+use crate::TokioResolver;
+use crate::proto::ProtoError;
+use crate::proto::rr::{Name, RecordType};
+#[cfg(unix)]
+use crate::proto::runtime::TokioRuntimeProvider;
+
+/// The result of a call, or of a lookup passed to its callback
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HickoryStatus {
+    /// The call or lookup succeeded
+    Ok = 0,
+    /// A required pointer was null, or a string was not valid UTF-8
+    InvalidArgument = 1,
+    /// The configuration could not be read or parsed, or is not supported on this platform
+    InvalidConfig = 2,
+    /// The name to look up is not a valid domain name
+    InvalidName = 3,
+    /// The name does not exist, or has no records of the requested type
+    NoRecords = 4,
+    /// The lookup failed, e.g. because no name server responded in time
+    LookupFailed = 5,
+    /// The threads to run the lookups on could not be started
+    Runtime = 6,
+}
+
+impl From<&ProtoError> for HickoryStatus {
+    fn from(error: &ProtoError) -> Self {
+        match error.is_no_records_found() {
+            true => Self::NoRecords,
+            false => Self::LookupFailed,
+        }
+    }
+}
+
+/// An IPv4 or IPv6 address
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HickoryIpAddr {
+    /// The IP version of the address, either `4` or `6`
+    pub version: u8,
+    /// The address in network byte order, IPv4 addresses only use the first four bytes
+    pub octets: [u8; 16],
+}
+
+impl From<IpAddr> for HickoryIpAddr {
+    fn from(ip: IpAddr) -> Self {
+        let mut octets = [0; 16];
+        let version = match ip {
+            IpAddr::V4(ip) => {
+                octets[..4].copy_from_slice(&ip.octets());
+                4
+            }
+            IpAddr::V6(ip) => {
+                octets = ip.octets();
+                6
+            }
+        };
+
+        Self { version, octets }
+    }
+}
+
+/// Called with the result of [`hickory_resolver_lookup_ip`]
+///
+/// The addresses are only valid for the duration of the call, `addrs` is null unless the status
+/// is `HICKORY_STATUS_OK`.
+pub type HickoryLookupIpCallback = Option<
+    unsafe extern "C" fn(
+        context: *mut c_void,
+        status: HickoryStatus,
+        addrs: *const HickoryIpAddr,
+        len: usize,
+    ),
+>;
+
+/// Called with the result of [`hickory_resolver_lookup`]
+///
+/// Each record is the presentation format of its data, e.g. `10 mail.example.com.` for an MX
+/// record. The strings are only valid for the duration of the call, `records` is null unless the
+/// status is `HICKORY_STATUS_OK`.
+pub type HickoryLookupCallback = Option<
+    unsafe extern "C" fn(
+        context: *mut c_void,
+        status: HickoryStatus,
+        records: *const *const c_char,
+        len: usize,
+    ),
+>;
+
+/// A resolver and the threads its lookups run on
+pub struct HickoryResolver {
+    runtime: Runtime,
+    resolver: TokioResolver,
+}
+
+impl HickoryResolver {
+    fn build(
+        build: impl FnOnce() -> Result<TokioResolver, HickoryStatus>,
+    ) -> Result<Box<Self>, HickoryStatus> {
+        let runtime = runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|_| HickoryStatus::Runtime)?;
+
+        // the connections of the resolver are bound to the runtime it is created in
+        let resolver = {
+            let _guard = runtime.enter();
+            build()?
+        };
+
+        Ok(Box::new(Self { runtime, resolver }))
+    }
+}
+
+/// The caller provided context passed back to a callback
+struct Context(*mut c_void);
+
+// the caller is responsible for the context being usable from the resolver's threads
+unsafe impl Send for Context {}
+
+impl Context {
+    fn get(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// Creates a resolver with the system configuration
+///
+/// This uses `/etc/resolv.conf` on Unix OSes and the registry on Windows.
+///
+/// # Safety
+///
+/// `resolver` must be valid for writes. On success the new resolver is written to it, which must
+/// be released with [`hickory_resolver_free`].
+#[no_mangle]
+pub unsafe extern "C" fn hickory_resolver_new(
+    resolver: *mut *mut HickoryResolver,
+) -> HickoryStatus {
+    if resolver.is_null() {
+        return HickoryStatus::InvalidArgument;
+    }
+
+    let built = HickoryResolver::build(|| {
+        #[cfg(any(unix, target_os = "windows"))]
+        let builder = TokioResolver::builder_tokio().map_err(|_| HickoryStatus::InvalidConfig);
+        #[cfg(not(any(unix, target_os = "windows")))]
+        let builder = Err(HickoryStatus::InvalidConfig);
+
+        builder.map(|builder| builder.build())
+    });
+
+    write_resolver(resolver, built)
+}
+
+/// Creates a resolver from a configuration in the format of `resolv.conf`
+///
+/// This is only supported on Unix OSes, elsewhere `HICKORY_STATUS_INVALID_CONFIG` is returned.
+///
+/// # Safety
+///
+/// `config` must be a valid, nul terminated string, and `resolver` must be valid for writes. On
+/// success the new resolver is written to it, which must be released with
+/// [`hickory_resolver_free`].
+#[no_mangle]
+pub unsafe extern "C" fn hickory_resolver_from_config(
+    config: *const c_char,
+    resolver: *mut *mut HickoryResolver,
+) -> HickoryStatus {
+    let Some(config) = str_arg(config) else {
+        return HickoryStatus::InvalidArgument;
+    };
+    if resolver.is_null() {
+        return HickoryStatus::InvalidArgument;
+    }
+
+    let built = HickoryResolver::build(|| {
+        #[cfg(unix)]
+        let parsed = crate::system_conf::parse_resolv_conf(config)
+            .map_err(|_| HickoryStatus::InvalidConfig)
+            .map(|(config, options)| {
+                TokioResolver::builder_with_config(config, TokioRuntimeProvider::default())
+                    .with_options(options)
+                    .build()
+            });
+        #[cfg(not(unix))]
+        let parsed = {
+            let _ = config;
+            Err(HickoryStatus::InvalidConfig)
+        };
+
+        parsed
+    });
+
+    write_resolver(resolver, built)
+}
+
+/// Releases a resolver created by [`hickory_resolver_new`] or [`hickory_resolver_from_config`]
+///
+/// The callbacks of lookups that are still in progress are not called. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `resolver` must be null or a resolver that has not been released yet. It may be called from
+/// within a callback.
+#[no_mangle]
+pub unsafe extern "C" fn hickory_resolver_free(resolver: *mut HickoryResolver) {
+    if resolver.is_null() {
+        return;
+    }
+
+    let resolver = Box::from_raw(resolver);
+    // unlike dropping it, this does not block and is allowed on the runtime's own threads
+    resolver.runtime.shutdown_background();
+}
+
+/// Looks up the IPv4 and IPv6 addresses of a host, as configured by the resolver options
+///
+/// If `host` is an IP address, it is returned as is.
+///
+/// # Safety
+///
+/// `resolver` must be a resolver that has not been released, and `host` a valid, nul terminated
+/// string. `context` is passed to `callback` as is, from another thread.
+#[no_mangle]
+pub unsafe extern "C" fn hickory_resolver_lookup_ip(
+    resolver: *const HickoryResolver,
+    host: *const c_char,
+    callback: HickoryLookupIpCallback,
+    context: *mut c_void,
+) -> HickoryStatus {
+    let (Some(resolver), Some(host), Some(callback)) = (resolver.as_ref(), str_arg(host), callback)
+    else {
+        return HickoryStatus::InvalidArgument;
+    };
+
+    let context = Context(context);
+    let lookup = resolver.resolver.clone();
+    let host = host.to_owned();
+    resolver.runtime.spawn(async move {
+        match lookup.lookup_ip(host).await {
+            Ok(ips) => {
+                let addrs = ips.iter().map(HickoryIpAddr::from).collect::<Vec<_>>();
+                callback(
+                    context.get(),
+                    HickoryStatus::Ok,
+                    addrs.as_ptr(),
+                    addrs.len(),
+                );
+            }
+            Err(e) => callback(context.get(), HickoryStatus::from(&e), ptr::null(), 0),
+        }
+    });
+
+    HickoryStatus::Ok
+}
+
+/// Looks up the records of a type for a name
+///
+/// # Safety
+///
+/// `resolver` must be a resolver that has not been released, and `name` a valid, nul terminated
+/// string. `context` is passed to `callback` as is, from another thread.
+#[no_mangle]
+pub unsafe extern "C" fn hickory_resolver_lookup(
+    resolver: *const HickoryResolver,
+    name: *const c_char,
+    record_type: u16,
+    callback: HickoryLookupCallback,
+    context: *mut c_void,
+) -> HickoryStatus {
+    let (Some(resolver), Some(name), Some(callback)) = (resolver.as_ref(), str_arg(name), callback)
+    else {
+        return HickoryStatus::InvalidArgument;
+    };
+    let Ok(name) = Name::from_utf8(name) else {
+        return HickoryStatus::InvalidName;
+    };
+
+    let context = Context(context);
+    let lookup = resolver.resolver.clone();
+    resolver.runtime.spawn(async move {
+        match lookup.lookup(name, RecordType::from(record_type)).await {
+            Ok(records) => {
+                // interior nul bytes can only come from TXT and similar records, those are cut off
+                let records = records
+                    .iter()
+                    .map(|rdata| {
+                        let mut text = rdata.to_string().into_bytes();
+                        text.truncate(text.iter().position(|&b| b == 0).unwrap_or(text.len()));
+                        text.push(0);
+                        text
+                    })
+                    .collect::<Vec<_>>();
+                let pointers = records
+                    .iter()
+                    .map(|text| text.as_ptr().cast::<c_char>())
+                    .collect::<Vec<_>>();
+
+                callback(
+                    context.get(),
+                    HickoryStatus::Ok,
+                    pointers.as_ptr(),
+                    pointers.len(),
+                );
+            }
+            Err(e) => callback(context.get(), HickoryStatus::from(&e), ptr::null(), 0),
+        }
+    });
+
+    HickoryStatus::Ok
+}
+
+/// Returns a static, nul terminated description of a status
+#[no_mangle]
+pub extern "C" fn hickory_status_str(status: HickoryStatus) -> *const c_char {
+    let description: &'static str = match status {
+        HickoryStatus::Ok => "ok\0",
+        HickoryStatus::InvalidArgument => "invalid argument\0",
+        HickoryStatus::InvalidConfig => "invalid configuration\0",
+        HickoryStatus::InvalidName => "invalid name\0",
+        HickoryStatus::NoRecords => "no records found\0",
+        HickoryStatus::LookupFailed => "lookup failed\0",
+        HickoryStatus::Runtime => "failed to start runtime\0",
+    };
+
+    description.as_ptr().cast()
+}
+
+unsafe fn str_arg<'a>(string: *const c_char) -> Option<&'a str> {
+    match string.is_null() {
+        true => None,
+        false => CStr::from_ptr(string).to_str().ok(),
+    }
+}
+
+unsafe fn write_resolver(
+    resolver: *mut *mut HickoryResolver,
+    built: Result<Box<HickoryResolver>, HickoryStatus>,
+) -> HickoryStatus {
+    match built {
+        Ok(built) => {
+            resolver.write(Box::into_raw(built));
+            HickoryStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::sync::mpsc::{self, Sender};
+
+    use super::*;
+
+    type Received = (HickoryStatus, Vec<HickoryIpAddr>);
+
+    unsafe extern "C" fn send_ips(
+        context: *mut c_void,
+        status: HickoryStatus,
+        addrs: *const HickoryIpAddr,
+        len: usize,
+    ) {
+        let sender = &*context.cast::<Sender<Received>>();
+        let addrs = match addrs.is_null() {
+            true => Vec::new(),
+            false => std::slice::from_raw_parts(addrs, len).to_vec(),
+        };
+        sender.send((status, addrs)).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_lookup_ip_literal() {
+        let config = CString::new("nameserver 127.0.0.1\n").unwrap();
+        let mut resolver = ptr::null_mut();
+        let status = unsafe { hickory_resolver_from_config(config.as_ptr(), &mut resolver) };
+        assert_eq!(status, HickoryStatus::Ok);
+
+        let (sender, receiver) = mpsc::channel::<Received>();
+        let context = &sender as *const Sender<Received> as *mut c_void;
+        let host = CString::new("192.0.2.1").unwrap();
+        let status =
+            unsafe { hickory_resolver_lookup_ip(resolver, host.as_ptr(), Some(send_ips), context) };
+        assert_eq!(status, HickoryStatus::Ok);
+
+        let (status, addrs) = receiver.recv().unwrap();
+        assert_eq!(status, HickoryStatus::Ok);
+        assert_eq!(addrs, [HickoryIpAddr::from(IpAddr::from([192, 0, 2, 1]))]);
+
+        unsafe { hickory_resolver_free(resolver) };
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let mut resolver = ptr::null_mut();
+        let status = unsafe { hickory_resolver_from_config(ptr::null(), &mut resolver) };
+        assert_eq!(status, HickoryStatus::InvalidArgument);
+        assert!(resolver.is_null());
+
+        let host = CString::new("www.example.com.").unwrap();
+        let status = unsafe {
+            hickory_resolver_lookup_ip(ptr::null(), host.as_ptr(), Some(send_ips), ptr::null_mut())
+        };
+        assert_eq!(status, HickoryStatus::InvalidArgument);
+
+        let status = unsafe {
+            CStr::from_ptr(hickory_status_str(HickoryStatus::NoRecords))
+                .to_str()
+                .unwrap()
+        };
+        assert_eq!(status, "no records found");
+    }
+}
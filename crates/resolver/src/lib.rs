@@ -198,10 +198,15 @@ pub use proto::rr::{IntoName, Name};
 #[cfg(feature = "tokio")]
 use proto::runtime::TokioRuntimeProvider;
 
+pub mod blocklist;
 pub mod caching_client;
 pub mod config;
+pub mod connect;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod hosts;
 pub use hosts::Hosts;
+mod limiter;
 pub mod lookup;
 pub mod lookup_ip;
 // TODO: consider #[doc(hidden)]
@@ -0,0 +1,240 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Admission control for the lookups of a resolver
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+use parking_lot::Mutex;
+use tracing::debug;
+
+use crate::proto::{ProtoError, ProtoErrorKind};
+
+/// Bounds the number of lookups in flight, queueing a bounded number of further lookups until
+/// earlier ones complete, and shedding the rest.
+#[derive(Clone)]
+pub(crate) struct LookupLimiter {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    max_outstanding: usize,
+    max_queued: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    outstanding: usize,
+    queue: VecDeque<Arc<Waiter>>,
+}
+
+#[derive(Default)]
+struct Waiter {
+    admitted: Mutex<(bool, Option<Waker>)>,
+}
+
+impl LookupLimiter {
+    /// A limiter allowing `max_outstanding` lookups in flight, with up to `max_queued` further
+    /// lookups waiting for one of them to complete
+    pub(crate) fn new(max_outstanding: usize, max_queued: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                max_outstanding: max_outstanding.max(1),
+                max_queued,
+                state: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Waits for the lookup to be admitted, failing fast if the queue is full
+    pub(crate) fn acquire(&self) -> Acquire {
+        let mut state = self.inner.state.lock();
+        if state.outstanding < self.inner.max_outstanding && state.queue.is_empty() {
+            state.outstanding += 1;
+            return Acquire(AcquireState::Ready(Some(Ok(LookupPermit(self.clone())))));
+        }
+
+        let queued = state.queue.len();
+        if queued >= self.inner.max_queued {
+            debug!(
+                "shedding lookup, {} outstanding and {queued} queued",
+                state.outstanding
+            );
+            let error = ProtoErrorKind::LookupQueueFull { queued }.into();
+            return Acquire(AcquireState::Ready(Some(Err(error))));
+        }
+
+        let waiter = Arc::new(Waiter::default());
+        state.queue.push_back(waiter.clone());
+        Acquire(AcquireState::Queued {
+            limiter: self.clone(),
+            waiter,
+        })
+    }
+
+    /// The number of lookups in flight
+    #[cfg(test)]
+    pub(crate) fn outstanding(&self) -> usize {
+        self.inner.state.lock().outstanding
+    }
+
+    /// The number of lookups waiting to be admitted
+    #[cfg(test)]
+    pub(crate) fn queued(&self) -> usize {
+        self.inner.state.lock().queue.len()
+    }
+
+    /// Hands the slot of a completed lookup to the next queued one, if any
+    fn release(&self) {
+        let mut state = self.inner.state.lock();
+        match state.queue.pop_front() {
+            Some(waiter) => {
+                let mut admitted = waiter.admitted.lock();
+                admitted.0 = true;
+                if let Some(waker) = admitted.1.take() {
+                    waker.wake();
+                }
+            }
+            None => state.outstanding -= 1,
+        }
+    }
+}
+
+/// Admission of a lookup by a [`LookupLimiter`], released when dropped
+pub(crate) struct LookupPermit(LookupLimiter);
+
+impl Drop for LookupPermit {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+/// Future returned by [`LookupLimiter::acquire`]
+pub(crate) struct Acquire(AcquireState);
+
+enum AcquireState {
+    Ready(Option<Result<LookupPermit, ProtoError>>),
+    Queued {
+        limiter: LookupLimiter,
+        waiter: Arc<Waiter>,
+    },
+    Done,
+}
+
+impl Future for Acquire {
+    type Output = Result<LookupPermit, ProtoError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.0 {
+            AcquireState::Ready(result) => {
+                Poll::Ready(result.take().expect("polled after completion"))
+            }
+            AcquireState::Queued { waiter, .. } => {
+                let mut admitted = waiter.admitted.lock();
+                if !admitted.0 {
+                    admitted.1 = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+                drop(admitted);
+
+                let AcquireState::Queued { limiter, .. } =
+                    mem::replace(&mut self.0, AcquireState::Done)
+                else {
+                    unreachable!();
+                };
+                Poll::Ready(Ok(LookupPermit(limiter)))
+            }
+            AcquireState::Done => panic!("polled after completion"),
+        }
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        let AcquireState::Queued { limiter, waiter } = &self.0 else {
+            return;
+        };
+
+        // a cancelled lookup leaves the queue, or passes on the slot it was already handed
+        let mut state = limiter.inner.state.lock();
+        if let Some(index) = state.queue.iter().position(|w| Arc::ptr_eq(w, waiter)) {
+            state.queue.remove(index);
+            return;
+        }
+        drop(state);
+
+        if waiter.admitted.lock().0 {
+            limiter.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn test_outstanding_limit() {
+        let limiter = LookupLimiter::new(2, 1);
+
+        let first = limiter.acquire().now_or_never().unwrap().unwrap();
+        let _second = limiter.acquire().now_or_never().unwrap().unwrap();
+        assert_eq!(limiter.outstanding(), 2);
+
+        let mut third = limiter.acquire();
+        assert!((&mut third).now_or_never().is_none());
+        assert_eq!(limiter.queued(), 1);
+
+        // the queue is full, further lookups are shed
+        let error = limiter.acquire().now_or_never().unwrap().err().unwrap();
+        assert!(error.is_lookup_queue_full());
+
+        drop(first);
+        assert_eq!(limiter.queued(), 0);
+        let third = third.now_or_never().unwrap().unwrap();
+        assert_eq!(limiter.outstanding(), 2);
+
+        drop(third);
+        assert_eq!(limiter.outstanding(), 1);
+    }
+
+    #[test]
+    fn test_cancelled_while_queued() {
+        let limiter = LookupLimiter::new(1, 2);
+
+        let first = limiter.acquire().now_or_never().unwrap().unwrap();
+        let mut second = limiter.acquire();
+        assert!((&mut second).now_or_never().is_none());
+        drop(second);
+        assert_eq!(limiter.queued(), 0);
+
+        drop(first);
+        assert_eq!(limiter.outstanding(), 0);
+    }
+
+    #[test]
+    fn test_cancelled_after_admission() {
+        let limiter = LookupLimiter::new(1, 1);
+
+        let first = limiter.acquire().now_or_never().unwrap().unwrap();
+        let second = limiter.acquire();
+        drop(first);
+        assert_eq!(limiter.outstanding(), 1);
+
+        // the slot handed to the dropped lookup is released
+        drop(second);
+        assert_eq!(limiter.outstanding(), 0);
+    }
+}
@@ -22,7 +22,7 @@ use crate::{
     lookup_ip::LookupIpIter,
     proto::{
         op::Query,
-        rr::{RData, Record, rdata},
+        rr::{Name, RData, Record, RecordType, rdata},
     },
 };
 
@@ -116,6 +116,49 @@ impl Lookup {
         self.records.as_ref()
     }
 
+    /// Interprets the records as a chain of aliases from the query name to the canonical name
+    ///
+    /// The CNAME records are followed in order, regardless of their order in the lookup; records
+    /// which are not part of the chain are ignored. For CNAME queries, no aliases are followed.
+    pub fn alias_chain(&self) -> AliasChain<'_> {
+        let query_type = self.query.query_type();
+        let mut aliases = Vec::new();
+        let mut canonical_name = self.query.name();
+
+        // there can't be more hops than CNAME records, which also breaks loops
+        while query_type != RecordType::CNAME && aliases.len() < self.records.len() {
+            let Some((alias, target)) = self.records.iter().find_map(|record| {
+                match (record.name() == canonical_name, record.data()) {
+                    (true, RData::CNAME(target)) => Some((record, &target.0)),
+                    _ => None,
+                }
+            }) else {
+                break;
+            };
+
+            aliases.push(alias);
+            canonical_name = target;
+        }
+
+        let records = self
+            .records
+            .iter()
+            .filter(|record| {
+                record.name() == canonical_name
+                    && match query_type {
+                        RecordType::ANY => record.record_type() != RecordType::CNAME,
+                        _ => record.record_type() == query_type,
+                    }
+            })
+            .collect();
+
+        AliasChain {
+            aliases,
+            canonical_name,
+            records,
+        }
+    }
+
     /// Clones the inner vec, appends the other vec
     pub(crate) fn append(&self, other: Self) -> Self {
         let mut records = Vec::with_capacity(self.len() + other.len());
@@ -136,6 +179,41 @@ impl Lookup {
     }
 }
 
+/// The aliases of a query name, and the records at its canonical name
+///
+/// See [`Lookup::alias_chain()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AliasChain<'a> {
+    aliases: Vec<&'a Record>,
+    canonical_name: &'a Name,
+    records: Vec<&'a Record>,
+}
+
+impl<'a> AliasChain<'a> {
+    /// The CNAME records followed from the query name, in order
+    ///
+    /// The first record is owned by the query name, each following one by the target of the
+    /// previous one.
+    pub fn aliases(&self) -> &[&'a Record] {
+        &self.aliases
+    }
+
+    /// The name at the end of the chain, which is the query name if it is not an alias
+    pub fn canonical_name(&self) -> &'a Name {
+        self.canonical_name
+    }
+
+    /// The records of the queried type at the canonical name
+    pub fn records(&self) -> &[&'a Record] {
+        &self.records
+    }
+
+    /// Returns true if the query name is an alias
+    pub fn is_alias(&self) -> bool {
+        !self.aliases.is_empty()
+    }
+}
+
 /// Borrowed view of set of [`RData`]s returned from a Lookup
 pub struct LookupIter<'a>(Iter<'a, Record>);
 
@@ -302,7 +380,6 @@ impl<'i, T: RecordData + 'i> Iterator for TypedLookupIter<'i, T> {
 mod tests {
     use std::str::FromStr;
 
-    #[cfg(feature = "__dnssec")]
     use crate::proto::op::Query;
     use crate::proto::rr::rdata::A;
     use crate::proto::rr::{Name, RData, Record};
@@ -330,6 +407,67 @@ mod tests {
         assert_eq!(lookup.next(), None);
     }
 
+    #[test]
+    fn test_alias_chain() {
+        use crate::proto::rr::rdata::CNAME;
+
+        let name = |name| Name::from_str(name).unwrap();
+        let cname =
+            |owner, target| Record::from_rdata(name(owner), 80, RData::CNAME(CNAME(name(target))));
+        let a = Record::from_rdata(name("c.example.com."), 80, RData::A(A::new(127, 0, 0, 1)));
+        let unrelated = Record::from_rdata(
+            name("other.example.com."),
+            80,
+            RData::A(A::new(127, 0, 0, 2)),
+        );
+
+        // out of order, as e.g. assembled from the cache
+        let records = Arc::from([
+            a.clone(),
+            cname("b.example.com.", "c.example.com."),
+            unrelated,
+            cname("WWW.example.com.", "b.example.com."),
+        ]);
+        let lookup = Lookup::new_with_max_ttl(
+            Query::query(name("www.example.com."), RecordType::A),
+            records,
+        );
+
+        let chain = lookup.alias_chain();
+        assert!(chain.is_alias());
+        assert_eq!(
+            chain.aliases(),
+            [
+                &cname("WWW.example.com.", "b.example.com."),
+                &cname("b.example.com.", "c.example.com."),
+            ]
+        );
+        assert_eq!(chain.canonical_name(), &name("c.example.com."));
+        assert_eq!(chain.records(), [&a]);
+
+        // the CNAME itself is the answer
+        let lookup = Lookup::new_with_max_ttl(
+            Query::query(name("b.example.com."), RecordType::CNAME),
+            Arc::from([cname("b.example.com.", "c.example.com.")]),
+        );
+        let chain = lookup.alias_chain();
+        assert!(!chain.is_alias());
+        assert_eq!(chain.canonical_name(), &name("b.example.com."));
+        assert_eq!(chain.records().len(), 1);
+
+        // loops end once every record was followed
+        let lookup = Lookup::new_with_max_ttl(
+            Query::query(name("b.example.com."), RecordType::A),
+            Arc::from([
+                cname("b.example.com.", "c.example.com."),
+                cname("c.example.com.", "b.example.com."),
+            ]),
+        );
+        let chain = lookup.alias_chain();
+        assert_eq!(chain.aliases().len(), 2);
+        assert!(chain.records().is_empty());
+    }
+
     #[test]
     #[cfg(feature = "__dnssec")]
     fn test_dnssec_lookup() {
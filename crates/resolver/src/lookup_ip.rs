@@ -9,8 +9,9 @@
 //!
 //! At it's heart LookupIp uses Lookup for performing all lookups. It is unlike other standard lookups in that there are customizations around A and AAAA resolutions.
 
+use std::cmp::min;
 use std::future::Future;
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -210,6 +211,7 @@ impl<C: DnsHandle> LookupContext<C> {
             LookupIpStrategy::Ipv4AndIpv6 => self.ipv4_and_ipv6(name).await,
             LookupIpStrategy::Ipv6thenIpv4 => self.ipv6_then_ipv4(name).await,
             LookupIpStrategy::Ipv4thenIpv6 => self.ipv4_then_ipv6(name).await,
+            LookupIpStrategy::Ipv6AndIpv4Interleaved => self.ipv6_and_ipv4_interleaved(name).await,
         }
     }
 
@@ -265,6 +267,33 @@ impl<C: DnsHandle> LookupContext<C> {
         }
     }
 
+    /// queries for AAAA and A in parallel and alternates between their addresses
+    async fn ipv6_and_ipv4_interleaved(&self, name: Name) -> Result<Lookup, ProtoError> {
+        let (ipv6, ipv4) = future::join(
+            self.hosts_lookup(Query::query(name.clone(), RecordType::AAAA)),
+            self.hosts_lookup(Query::query(name, RecordType::A)),
+        )
+        .await;
+
+        match (ipv6, ipv4) {
+            (Ok(ipv6), Ok(ipv4)) => Ok(interleave(&ipv6, &ipv4)),
+            (Ok(ips), Err(e)) | (Err(e), Ok(ips)) => {
+                debug!(
+                    "one of ipv6 or ipv4 lookup failed in ipv6_and_ipv4_interleaved strategy: {}",
+                    e
+                );
+                Ok(ips)
+            }
+            (Err(e1), Err(e2)) => {
+                debug!(
+                    "both of ipv6 or ipv4 lookup failed in ipv6_and_ipv4_interleaved strategy e1: {}, e2: {}",
+                    e1, e2
+                );
+                Err(e1)
+            }
+        }
+    }
+
     /// queries only for AAAA and on no results queries for A
     async fn ipv6_then_ipv4(&self, name: Name) -> Result<Lookup, ProtoError> {
         self.rt_then_swap(name, RecordType::AAAA, RecordType::A)
@@ -314,6 +343,65 @@ impl<C: DnsHandle> LookupContext<C> {
     }
 }
 
+/// Alternates between the addresses of `first` and `second`, starting with `first`
+///
+/// Records other than addresses, like the CNAME records leading to them, precede the addresses.
+fn interleave(first: &Lookup, second: &Lookup) -> Lookup {
+    let is_ip = |record: &&Record| matches!(record.data(), RData::A(_) | RData::AAAA(_));
+
+    let mut records = first
+        .records()
+        .iter()
+        .chain(second.records())
+        .filter(|record| !is_ip(record))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut first_ips = first.records().iter().filter(is_ip);
+    let mut second_ips = second.records().iter().filter(is_ip);
+    loop {
+        match (first_ips.next(), second_ips.next()) {
+            (None, None) => break,
+            (first_ip, second_ip) => records.extend(first_ip.into_iter().chain(second_ip).cloned()),
+        }
+    }
+
+    let valid_until = min(first.valid_until(), second.valid_until());
+    Lookup::new_with_deadline(first.query().clone(), Arc::from(records), valid_until)
+}
+
+/// Returns whether the host has a route to global Ipv4 and Ipv6 addresses
+///
+/// This connects UDP sockets, which only selects a route and a source address, nothing is sent.
+pub(crate) fn reachable_families() -> (bool, bool) {
+    (
+        has_route(Ipv4Addr::new(192, 0, 2, 1).into()),
+        has_route(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).into()),
+    )
+}
+
+fn has_route(destination: IpAddr) -> bool {
+    let bind_addr: IpAddr = match destination {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+
+    let Ok(socket) = UdpSocket::bind(SocketAddr::new(bind_addr, 0)) else {
+        return false;
+    };
+    if socket.connect(SocketAddr::new(destination, 53)).is_err() {
+        return false;
+    }
+
+    socket.local_addr().is_ok_and(|local| match local.ip() {
+        IpAddr::V4(ip) => !ip.is_unspecified() && !ip.is_loopback(),
+        // a link-local source address can not reach global addresses
+        IpAddr::V6(ip) => {
+            !ip.is_unspecified() && !ip.is_loopback() && ip.segments()[0] & 0xffc0 != 0xfe80
+        }
+    })
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
@@ -582,4 +670,132 @@ pub(crate) mod tests {
             vec![Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)]
         );
     }
+
+    #[test]
+    fn test_ipv6_and_ipv4_interleaved_strategy() {
+        subscribe();
+
+        let mut cx = LookupContext {
+            client: CachingClient::new(0, mock(vec![v4_message(), v6_message()]), false),
+            options: DnsRequestOptions::default(),
+            hosts: Arc::new(Hosts::default()),
+        };
+
+        assert_eq!(
+            block_on(cx.ipv6_and_ipv4_interleaved(Name::root()))
+                .unwrap()
+                .iter()
+                .map(|r| r.ip_addr().unwrap())
+                .collect::<Vec<IpAddr>>(),
+            vec![
+                IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+            ]
+        );
+
+        // error, then only ipv4 available
+        cx.client = CachingClient::new(0, mock(vec![v4_message(), error()]), false);
+        assert_eq!(
+            block_on(cx.ipv6_and_ipv4_interleaved(Name::root()))
+                .unwrap()
+                .iter()
+                .map(|r| r.ip_addr().unwrap())
+                .collect::<Vec<IpAddr>>(),
+            vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]
+        );
+    }
+
+    #[test]
+    fn test_interleave() {
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        let target = Name::from_ascii("example.net.").unwrap();
+        let cname = Record::from_rdata(
+            name.clone(),
+            300,
+            RData::CNAME(crate::proto::rr::rdata::CNAME(target.clone())),
+        );
+
+        let mut ipv6 = vec![cname];
+        ipv6.extend((1..=3).map(|i| {
+            Record::from_rdata(
+                target.clone(),
+                300,
+                RData::AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, i).into()),
+            )
+        }));
+        let ipv4 = vec![Record::from_rdata(
+            target,
+            300,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1).into()),
+        )];
+
+        let ipv6 = Lookup::new_with_max_ttl(
+            Query::query(name.clone(), RecordType::AAAA),
+            Arc::from(ipv6),
+        );
+        let ipv4 = Lookup::new_with_max_ttl(Query::query(name, RecordType::A), Arc::from(ipv4));
+
+        let lookup = interleave(&ipv6, &ipv4);
+        assert_eq!(lookup.records()[0].record_type(), RecordType::CNAME);
+        assert_eq!(
+            LookupIp::from(lookup).iter().collect::<Vec<_>>(),
+            vec![
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2)),
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ip_strategy_for() {
+        use crate::config::{IpStrategyOverride, ResolverOpts};
+
+        let options = ResolverOpts {
+            ip_strategy_overrides: vec![
+                IpStrategyOverride::new(
+                    Name::from_ascii("corp.example.").unwrap(),
+                    LookupIpStrategy::Ipv4Only,
+                ),
+                IpStrategyOverride::new(
+                    Name::from_ascii("v6.corp.example.").unwrap(),
+                    LookupIpStrategy::Ipv6Only,
+                ),
+            ],
+            ..ResolverOpts::default()
+        };
+
+        let strategy_for = |name| options.ip_strategy_for(&Name::from_ascii(name).unwrap());
+        assert_eq!(strategy_for("www.example."), LookupIpStrategy::Ipv4thenIpv6);
+        assert_eq!(
+            strategy_for("www.corp.example."),
+            LookupIpStrategy::Ipv4Only
+        );
+        assert_eq!(
+            strategy_for("www.v6.corp.example."),
+            LookupIpStrategy::Ipv6Only
+        );
+    }
+
+    #[test]
+    fn test_restrict_to_reachable_families() {
+        let strategy = LookupIpStrategy::Ipv6AndIpv4Interleaved;
+        assert_eq!(strategy.restrict_to(true, true), strategy);
+        assert_eq!(strategy.restrict_to(false, false), strategy);
+        assert_eq!(
+            strategy.restrict_to(true, false),
+            LookupIpStrategy::Ipv4Only
+        );
+        assert_eq!(
+            strategy.restrict_to(false, true),
+            LookupIpStrategy::Ipv6Only
+        );
+
+        // a single family is never replaced by the other one
+        assert_eq!(
+            LookupIpStrategy::Ipv6Only.restrict_to(true, false),
+            LookupIpStrategy::Ipv6Only
+        );
+    }
 }
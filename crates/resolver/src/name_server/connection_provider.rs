@@ -120,6 +120,8 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
         options: &ResolverOpts,
     ) -> Result<Self::FutureConn, io::Error> {
         let remote_addr = SocketAddr::new(ip, config.port);
+        #[cfg(feature = "__tls")]
+        let tls_config = config.tls_config.as_deref().unwrap_or(&options.tls_config);
         let dns_connect = match (&config.protocol, self.quic_binder()) {
             (ProtocolConfig::Udp, _) => {
                 let provider_handle = self.clone();
@@ -127,6 +129,7 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
                     .with_timeout(Some(options.timeout))
                     .with_os_port_selection(options.os_port_selection)
                     .avoid_local_ports(options.avoid_local_udp_ports.clone())
+                    .with_local_port_range(options.local_udp_port_range.clone())
                     .with_bind_addr(config.bind_addr)
                     .build();
                 let exchange = DnsExchange::connect(stream);
@@ -157,7 +160,7 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
                     ));
                 };
 
-                let mut tls_config = options.tls_config.clone();
+                let mut tls_config = tls_config.clone();
                 // The port (853) of DOT is for dns dedicated, SNI is unnecessary. (ISP block by the SNI name)
                 tls_config.enable_sni = false;
 
@@ -176,7 +179,7 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
             (ProtocolConfig::Https { server_name, path }, _) => {
                 Connecting::Https(DnsExchange::connect(HttpsClientConnect::new(
                     self.connect_tcp(remote_addr, None, None),
-                    Arc::new(options.tls_config.clone()),
+                    Arc::new(tls_config.clone()),
                     remote_addr,
                     server_name.clone(),
                     path.clone(),
@@ -191,7 +194,7 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
 
                 Connecting::Quic(DnsExchange::connect(
                     QuicClientStream::builder()
-                        .crypto_config(options.tls_config.clone())
+                        .crypto_config(tls_config.clone())
                         .build_with_future(
                             binder.bind_quic(bind_addr, remote_addr)?,
                             remote_addr,
@@ -215,7 +218,7 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
 
                 Connecting::H3(DnsExchange::connect(
                     H3ClientStream::builder()
-                        .crypto_config(options.tls_config.clone())
+                        .crypto_config(tls_config.clone())
                         .disable_grease(*disable_grease)
                         .build_with_future(
                             binder.bind_quic(bind_addr, remote_addr)?,
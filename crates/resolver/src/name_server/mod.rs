@@ -13,4 +13,4 @@ pub use connection_provider::ConnectionProvider;
 mod name_server;
 pub use name_server::NameServer;
 mod name_server_pool;
-pub use name_server_pool::NameServerPool;
+pub use name_server_pool::{NameServerPool, PrivacyStats};
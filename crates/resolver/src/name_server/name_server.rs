@@ -20,7 +20,7 @@ use parking_lot::Mutex as SyncMutex;
 use tokio::time::{Duration, Instant};
 use tracing::debug;
 
-use crate::config::{ConnectionConfig, NameServerConfig, ResolverOpts};
+use crate::config::{ConnectionConfig, NameServerConfig, PrivacyProfile, ResolverOpts};
 use crate::name_server::connection_provider::ConnectionProvider;
 use crate::proto::{
     NoRecords, ProtoError, ProtoErrorKind,
@@ -88,6 +88,10 @@ impl<P: ConnectionProvider> NameServer<P> {
         self.inner.stats.decayed_srtt()
     }
 
+    pub(super) fn ip(&self) -> IpAddr {
+        self.inner.ip
+    }
+
     pub(super) fn protocol(&self) -> Protocol {
         self.inner.config.protocol.to_protocol()
     }
@@ -95,6 +99,11 @@ impl<P: ConnectionProvider> NameServer<P> {
     pub(super) fn trust_negative_responses(&self) -> bool {
         self.inner.trust_negative_responses
     }
+
+    /// Returns true if this connection is only used after all other connections failed
+    pub(super) fn is_privacy_fallback(&self) -> bool {
+        self.inner.privacy.is_fallback(self.protocol())
+    }
 }
 
 impl<P: ConnectionProvider> DnsHandle for NameServer<P> {
@@ -137,6 +146,7 @@ struct NameServerState<P: ConnectionProvider> {
     status: AtomicU8,
     stats: NameServerStats,
     trust_negative_responses: bool,
+    privacy: PrivacyProfile,
     connection_provider: P,
 }
 
@@ -156,6 +166,7 @@ impl<P: ConnectionProvider> NameServerState<P> {
             status: AtomicU8::new(Status::Init.into()),
             stats: NameServerStats::default(),
             trust_negative_responses: server_config.trust_negative_responses,
+            privacy: server_config.privacy,
             connection_provider,
         }
     }
@@ -564,7 +575,10 @@ mod tests {
                 port: server_addr.port(),
                 protocol: ProtocolConfig::Udp,
                 bind_addr: None,
+                #[cfg(feature = "__tls")]
+                tls_config: None,
             }],
+            privacy: PrivacyProfile::Any,
         };
 
         let resolver_opts = ResolverOpts {
@@ -7,31 +7,38 @@
 
 use std::cmp::Ordering;
 use std::collections::VecDeque;
+use std::iter;
+use std::net::IpAddr;
 use std::pin::Pin;
 use std::sync::{
     Arc,
-    atomic::{AtomicUsize, Ordering as AtomicOrdering},
+    atomic::{AtomicU64, AtomicUsize, Ordering as AtomicOrdering},
 };
 use std::time::Duration;
 
 use futures_util::future::FutureExt;
 use futures_util::stream::{FuturesUnordered, Stream, StreamExt, once};
 use hickory_proto::NoRecords;
-use hickory_proto::op::ResponseCode;
+use hickory_proto::op::{Edns, ResponseCode};
+use hickory_proto::rr::Name;
 use smallvec::SmallVec;
-use tracing::debug;
+use tracing::{Instrument, debug, debug_span, warn};
 
 use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts, ServerOrderingStrategy};
 use crate::name_server::connection_provider::ConnectionProvider;
 use crate::name_server::name_server::NameServer;
 use crate::proto::runtime::{RuntimeProvider, Time};
-use crate::proto::xfer::{DnsHandle, DnsRequest, DnsResponse, FirstAnswer, Protocol};
+use crate::proto::xfer::{
+    DnsHandle, DnsRequest, DnsResponse, FirstAnswer, Protocol, TruncationPolicy,
+};
 use crate::proto::{ProtoError, ProtoErrorKind};
 
 /// Abstract interface for mocking purpose
 #[derive(Clone)]
 pub struct NameServerPool<P: ConnectionProvider> {
     state: Arc<PoolState<P>>,
+    // the name servers for the names within specific domains
+    domains: Arc<[(Name, Arc<PoolState<P>>)]>,
 }
 
 impl<P: ConnectionProvider> NameServerPool<P> {
@@ -40,7 +47,21 @@ impl<P: ConnectionProvider> NameServerPool<P> {
         options: Arc<ResolverOpts>,
         conn_provider: P,
     ) -> Self {
-        Self::from_config(config.name_servers(), options, conn_provider)
+        let mut pool = Self::from_config(
+            config.name_servers(),
+            options.clone(),
+            conn_provider.clone(),
+        );
+        pool.domains = config
+            .domain_name_servers()
+            .iter()
+            .map(|domain| {
+                let domain_pool =
+                    Self::from_config(&domain.name_servers, options.clone(), conn_provider.clone());
+                (domain.domain.clone(), domain_pool.state)
+            })
+            .collect();
+        pool
     }
 
     /// Construct a NameServerPool from a set of name server configs
@@ -52,6 +73,15 @@ impl<P: ConnectionProvider> NameServerPool<P> {
         let mut servers = Vec::with_capacity(name_servers.len());
         for server in name_servers {
             for conn in &server.connections {
+                let protocol = conn.protocol.to_protocol();
+                if !server.privacy.allows(protocol) {
+                    warn!(
+                        server = %server.ip, %protocol,
+                        "ignoring unencrypted connection of a name server with a strict privacy profile"
+                    );
+                    continue;
+                }
+
                 servers.push(NameServer::new(
                     server,
                     conn.clone(),
@@ -68,6 +98,7 @@ impl<P: ConnectionProvider> NameServerPool<P> {
     pub fn from_nameservers(servers: Vec<NameServer<P>>, options: Arc<ResolverOpts>) -> Self {
         Self {
             state: Arc::new(PoolState::new(servers, options)),
+            domains: Arc::new([]),
         }
     }
 
@@ -75,16 +106,101 @@ impl<P: ConnectionProvider> NameServerPool<P> {
     pub fn options(&self) -> &ResolverOpts {
         &self.state.options
     }
+
+    /// Returns the number of privacy downgrades and outages since the pool was created
+    pub fn privacy_stats(&self) -> PrivacyStats {
+        let count = |counter: fn(&PoolState<P>) -> &AtomicU64| {
+            self.states()
+                .map(|state| counter(state).load(AtomicOrdering::Relaxed))
+                .sum()
+        };
+
+        PrivacyStats {
+            downgrades: count(|state| &state.downgrades),
+            outages: count(|state| &state.outages),
+        }
+    }
+
+    /// Returns the number of requests which received conflicting responses over UDP, a sign of
+    /// spoofing attempts, since the pool was created
+    ///
+    /// See [`ResolverOpts::duplicate_response_window`].
+    pub fn conflicting_responses(&self) -> u64 {
+        self.states()
+            .map(|state| state.conflicting_responses.load(AtomicOrdering::Relaxed))
+            .sum()
+    }
+
+    /// Returns the name servers of this pool, followed by those of specific domains
+    fn states(&self) -> impl Iterator<Item = &PoolState<P>> {
+        iter::once(&*self.state).chain(self.domains.iter().map(|(_, state)| &**state))
+    }
+
+    /// Returns a pool of the name servers of this pool, except the ones at the `excluded` addresses
+    ///
+    /// The name servers, along with their connections and statistics, are shared with this pool.
+    #[doc(hidden)]
+    pub fn without(&self, excluded: &[IpAddr]) -> Self {
+        let servers = self
+            .state
+            .servers
+            .iter()
+            .filter(|ns| !excluded.contains(&ns.ip()))
+            .cloned()
+            .collect();
+
+        Self {
+            domains: self.domains.clone(),
+            ..Self::from_nameservers(servers, self.state.options.clone())
+        }
+    }
+
+    /// Sends `request`, returning the address of the name server the result was received from
+    ///
+    /// The address is `None` when no name server could be reached.
+    #[doc(hidden)]
+    pub async fn send_attributed(
+        &self,
+        request: DnsRequest,
+    ) -> (Option<IpAddr>, Result<DnsResponse, ProtoError>) {
+        self.state_for(&request).try_send(request).await
+    }
+
+    /// Returns the name servers for the name of `request`, those of the most specific domain
+    /// containing it if there are any
+    fn state_for(&self, request: &DnsRequest) -> &Arc<PoolState<P>> {
+        let Some(query) = request.queries().first() else {
+            return &self.state;
+        };
+
+        self.domains
+            .iter()
+            .filter(|(domain, _)| domain.zone_of(query.name()))
+            .max_by_key(|(domain, _)| domain.num_labels())
+            .map_or(&self.state, |(_, state)| state)
+    }
+}
+
+/// Failure accounting of a [`NameServerPool`], with respect to the privacy of requests
+///
+/// See [`PrivacyProfile`](crate::config::PrivacyProfile) for how connections are selected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrivacyStats {
+    /// Number of requests answered over an unencrypted connection of an opportunistic name
+    /// server, after all other connections failed
+    pub downgrades: u64,
+    /// Number of requests for which all connections failed
+    pub outages: u64,
 }
 
 impl<P: ConnectionProvider> DnsHandle for NameServerPool<P> {
     type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send>>;
 
     fn send(&self, request: DnsRequest) -> Self::Response {
-        let state = self.state.clone();
+        let state = self.state_for(&request).clone();
         Box::pin(once(async move {
             debug!("sending request: {:?}", request.queries());
-            state.try_send(request).await
+            state.try_send(request).await.1
         }))
     }
 }
@@ -93,6 +209,9 @@ struct PoolState<P: ConnectionProvider> {
     servers: Vec<NameServer<P>>,
     options: Arc<ResolverOpts>,
     next: AtomicUsize,
+    downgrades: AtomicU64,
+    outages: AtomicU64,
+    conflicting_responses: AtomicU64,
 }
 
 impl<P: ConnectionProvider> PoolState<P> {
@@ -107,10 +226,16 @@ impl<P: ConnectionProvider> PoolState<P> {
             servers,
             options,
             next: AtomicUsize::new(0),
+            downgrades: AtomicU64::new(0),
+            outages: AtomicU64::new(0),
+            conflicting_responses: AtomicU64::new(0),
         }
     }
 
-    async fn try_send(&self, request: DnsRequest) -> Result<DnsResponse, ProtoError> {
+    async fn try_send(
+        &self,
+        mut request: DnsRequest,
+    ) -> (Option<IpAddr>, Result<DnsResponse, ProtoError>) {
         let mut conns = self.servers.clone();
         match self.options.server_ordering_strategy {
             // select the highest priority connection
@@ -151,7 +276,11 @@ impl<P: ConnectionProvider> PoolState<P> {
         // TODO: more principled handling of timeouts. Currently, timeouts appear to be handled mostly
         // close to the connection, which means the top level resolution might take substantially longer
         // to fire than the timeout configured in `ResolverOpts`.
-        let mut conns = VecDeque::from(conns);
+        // unencrypted connections of opportunistic name servers are kept until all others failed
+        let (conns, mut fallback) = conns
+            .into_iter()
+            .partition::<VecDeque<_>, _>(|conn| !conn.is_privacy_fallback());
+        let mut conns = conns;
         let mut backoff = Duration::from_millis(20);
         let mut busy = SmallVec::<[NameServer<P>; 2]>::new();
         let mut err = ProtoError::from(ProtoErrorKind::NoConnections);
@@ -183,28 +312,67 @@ impl<P: ConnectionProvider> PoolState<P> {
                     backoff *= 2;
                     continue;
                 }
-                return Err(err);
+
+                if !fallback.is_empty() {
+                    debug!("all other connections failed, falling back to unencrypted connections");
+                    conns.extend(
+                        fallback
+                            .drain(..)
+                            .filter(|ns| !(skip_udp && ns.protocol() == Protocol::Udp)),
+                    );
+                    continue;
+                }
+
+                self.outages.fetch_add(1, AtomicOrdering::Relaxed);
+                return (None, Err(err));
             }
 
             let mut requests = par_conns
                 .into_iter()
                 .map(|conn| {
+                    let span =
+                        debug_span!("upstream", server = %conn.ip(), protocol = %conn.protocol());
                     conn.send(request.clone())
                         .first_answer()
-                        .map(|result| result.map_err(|e| (conn, e)))
+                        .instrument(span)
+                        .map(|result| (conn, result))
                 })
                 .collect::<FuturesUnordered<_>>();
 
-            while let Some(result) = requests.next().await {
-                let (conn, e) = match result {
+            let mut enlarged = false;
+            while let Some((conn, result)) = requests.next().await {
+                let e = match result {
                     Ok(response) if response.truncated() => {
-                        debug!("truncated response received, retrying over TCP");
-                        skip_udp = true;
+                        let policy = request.options().truncation_policy;
+                        match policy {
+                            TruncationPolicy::ReturnTruncated => {
+                                self.record_answer(&conn);
+                                return (Some(conn.ip()), Ok(response));
+                            }
+                            TruncationPolicy::RetryLargerEdns
+                                if conn.protocol() == Protocol::Udp
+                                    && (enlarged || enlarge_edns_payload(&mut request)) =>
+                            {
+                                debug!(
+                                    "truncated response received, retrying with a larger EDNS payload"
+                                );
+                                enlarged = true;
+                                conns.push_front(conn);
+                            }
+                            _ => {
+                                debug!("truncated response received, retrying over TCP");
+                                skip_udp = true;
+                            }
+                        }
+
                         err = ProtoError::from("received truncated response");
                         continue;
                     }
-                    Ok(response) => return Ok(response),
-                    Err((conn, e)) => (conn, e),
+                    Ok(response) => {
+                        self.record_answer(&conn);
+                        return (Some(conn.ip()), Ok(response));
+                    }
+                    Err(e) => e,
                 };
 
                 use ProtoErrorKind::*;
@@ -212,6 +380,14 @@ impl<P: ConnectionProvider> PoolState<P> {
                     // We assume the response is spoofed, so ignore it and avoid UDP server for this
                     // request to try and avoid further spoofing.
                     QueryCaseMismatch => skip_udp = true,
+                    // Likewise when different responses were received for the request, both of
+                    // them are discarded.
+                    ConflictingResponses => {
+                        debug!("conflicting responses received, retrying over TCP");
+                        self.conflicting_responses
+                            .fetch_add(1, AtomicOrdering::Relaxed);
+                        skip_udp = true;
+                    }
                     // If the server is busy, try it again later if necessary.
                     Busy => busy.push(conn),
                     // If the connection failed, try another one.
@@ -222,7 +398,10 @@ impl<P: ConnectionProvider> PoolState<P> {
                         response_code: ResponseCode::NXDomain,
                         ..
                     }) if !conn.trust_negative_responses() => {}
-                    _ => return Err(e),
+                    _ => {
+                        self.record_answer(&conn);
+                        return (Some(conn.ip()), Err(e));
+                    }
                 }
 
                 if err.cmp_specificity(&e) == Ordering::Less {
@@ -231,8 +410,30 @@ impl<P: ConnectionProvider> PoolState<P> {
             }
         }
     }
+
+    /// Accounts for the final result of a request, received over `conn`
+    fn record_answer(&self, conn: &NameServer<P>) {
+        if conn.is_privacy_fallback() {
+            warn!(server = %conn.ip(), protocol = %conn.protocol(), "privacy downgrade");
+            self.downgrades.fetch_add(1, AtomicOrdering::Relaxed);
+        }
+    }
+}
+
+/// Raises the EDNS payload size advertised by `request`, returns false if it was already as large
+fn enlarge_edns_payload(request: &mut DnsRequest) -> bool {
+    let edns = request.extensions_mut().get_or_insert_with(Edns::new);
+    if edns.max_payload() >= LARGE_EDNS_PAYLOAD_LEN {
+        return false;
+    }
+
+    edns.set_max_payload(LARGE_EDNS_PAYLOAD_LEN);
+    true
 }
 
+// the largest payload size commonly supported over UDP, see RFC 6891 section 6.2.5
+const LARGE_EDNS_PAYLOAD_LEN: u16 = 4096;
+
 #[cfg(test)]
 #[cfg(feature = "tokio")]
 mod tests {
@@ -244,7 +445,7 @@ mod tests {
 
     use super::*;
     use crate::config::NameServerConfig;
-    use crate::proto::op::Query;
+    use crate::proto::op::{Message, Query};
     use crate::proto::rr::{Name, RecordType};
     use crate::proto::runtime::TokioRuntimeProvider;
     use crate::proto::xfer::{DnsHandle, DnsRequestOptions};
@@ -360,6 +561,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_domain_name_servers() {
+        let mut config = ResolverConfig::default();
+        config.add_name_server(NameServerConfig::udp(IpAddr::from([192, 0, 2, 1])));
+        config.add_domain_name_servers(
+            Name::from_str("corp.example.").unwrap(),
+            vec![NameServerConfig::udp(IpAddr::from([192, 0, 2, 2]))],
+        );
+        config.add_domain_name_servers(
+            Name::from_str("lab.corp.example.").unwrap(),
+            vec![NameServerConfig::udp(IpAddr::from([192, 0, 2, 3]))],
+        );
+
+        let pool = NameServerPool::tokio_from_config(
+            &config,
+            Arc::new(ResolverOpts::default()),
+            TokioRuntimeProvider::new(),
+        );
+
+        let name_server = |name: &str| {
+            let mut message = Message::query();
+            message.add_query(Query::query(Name::from_str(name).unwrap(), RecordType::A));
+            let request = DnsRequest::new(message, DnsRequestOptions::default());
+            pool.state_for(&request).servers[0].ip()
+        };
+
+        // the most specific domain wins, names outside of all domains use the other name servers
+        assert_eq!(name_server("www.example."), IpAddr::from([192, 0, 2, 1]));
+        assert_eq!(name_server("corp.example."), IpAddr::from([192, 0, 2, 2]));
+        assert_eq!(
+            name_server("WWW.Corp.Example."),
+            IpAddr::from([192, 0, 2, 2])
+        );
+        assert_eq!(
+            name_server("www.lab.corp.example."),
+            IpAddr::from([192, 0, 2, 3])
+        );
+        assert_eq!(
+            name_server("notcorp.example."),
+            IpAddr::from([192, 0, 2, 1])
+        );
+
+        assert_eq!(pool.privacy_stats(), PrivacyStats::default());
+    }
+
     impl NameServerPool<TokioRuntimeProvider> {
         pub(crate) fn tokio_from_config(
             config: &ResolverConfig,
@@ -10,19 +10,23 @@ use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::task::{Context, Poll};
 
 use futures_util::{FutureExt, Stream, future};
 use hickory_proto::rr::rdata;
-use tracing::debug;
+use tracing::{Instrument, debug, debug_span};
 
+use crate::blocklist::{Blocklist, BlocklistDnsHandle};
 use crate::cache::{MAX_TTL, ResponseCache, TtlConfig};
 use crate::caching_client::CachingClient;
-use crate::config::{ResolveHosts, ResolverConfig, ResolverOpts};
+use crate::config::{ResolveHosts, ResolverConfig, ResolverOpts, SearchOrder};
+use crate::connect::{self, ConnectionCandidate, HttpsRecords, MAX_ALIAS_CHAIN};
 use crate::hosts::Hosts;
+use crate::limiter::{LookupLimiter, LookupPermit};
 use crate::lookup::{Lookup, TypedLookup};
-use crate::lookup_ip::{LookupIp, LookupIpFuture};
-use crate::name_server::{ConnectionProvider, NameServerPool};
+use crate::lookup_ip::{LookupIp, LookupIpFuture, reachable_families};
+use crate::name_server::{ConnectionProvider, NameServerPool, PrivacyStats};
 #[cfg(feature = "__dnssec")]
 use crate::proto::dnssec::{DnssecDnsHandle, TrustAnchors};
 use crate::proto::op::Query;
@@ -82,8 +86,13 @@ impl TokioResolver {
 pub struct Resolver<P: ConnectionProvider> {
     config: ResolverConfig,
     options: Arc<ResolverOpts>,
-    client_cache: CachingClient<LookupEither<P>>,
+    client_cache: CachingClient<BlocklistDnsHandle<LookupEither<P>>>,
     hosts: Arc<Hosts>,
+    pool: NameServerPool<P>,
+    limiter: Option<LookupLimiter>,
+    blocklist: Option<Arc<Blocklist>>,
+    /// The blocklist generation the cached answers were resolved under
+    blocklist_generation: Arc<AtomicUsize>,
 }
 
 impl<R: ConnectionProvider> Resolver<R> {
@@ -108,12 +117,15 @@ impl<R: ConnectionProvider> Resolver<R> {
             config,
             options: ResolverOpts::default(),
             provider,
+            blocklist: None,
             #[cfg(feature = "__dnssec")]
             trust_anchor: None,
             #[cfg(feature = "__dnssec")]
             nsec3_soft_iteration_limit: None,
             #[cfg(feature = "__dnssec")]
             nsec3_hard_iteration_limit: None,
+            #[cfg(feature = "__dnssec")]
+            dnssec_verification_concurrency: None,
         }
     }
 
@@ -152,6 +164,9 @@ impl<R: ConnectionProvider> Resolver<R> {
     where
         L: From<Lookup> + Send + Sync + 'static,
     {
+        self.sync_blocklist();
+        let _permit = self.admit().await?;
+        let span = debug_span!("lookup", name = %name, record_type = %record_type);
         let names = self.build_names(name);
         LookupFuture::lookup_with_hosts(
             names,
@@ -160,6 +175,7 @@ impl<R: ConnectionProvider> Resolver<R> {
             self.client_cache.clone(),
             self.hosts.clone(),
         )
+        .instrument(span)
         .await
         .map(L::from)
     }
@@ -204,18 +220,120 @@ impl<R: ConnectionProvider> Resolver<R> {
             (Err(err), None) => return Err(err),
         };
 
+        self.sync_blocklist();
+        let _permit = self.admit().await?;
+        let mut strategy = self.options.ip_strategy_for(&name);
+        if self.options.filter_unreachable_families {
+            let (ipv4, ipv6) = reachable_families();
+            strategy = strategy.restrict_to(ipv4, ipv6);
+        }
+
+        let span = debug_span!("lookup_ip", name = %name, strategy = ?strategy);
         let names = self.build_names(name);
         let hosts = self.hosts.clone();
 
         LookupIpFuture::lookup(
             names,
-            self.options.ip_strategy,
+            strategy,
             self.client_cache.clone(),
             self.request_options(),
             hosts,
             finally_ip_addr.map(Record::into_data),
         )
+        .instrument(span)
+        .await
+    }
+
+    /// Returns the addresses to connect to for an HTTPS origin, most preferred first
+    ///
+    /// This resolves the HTTPS (SVCB) records of the origin, following AliasMode records, and the
+    /// addresses of its ServiceMode endpoints, see [RFC 9460](https://datatracker.ietf.org/doc/html/rfc9460).
+    /// Every candidate carries the ALPN protocols and the Encrypted Client Hello configurations to
+    /// use for it. The addresses of the origin itself are added after those of the endpoints, as
+    /// the connection without HTTPS records, unless every endpoint supports ECH.
+    ///
+    /// Endpoints with the same priority are returned in the order of the response; clients should
+    /// shuffle them to balance the load.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - the host of the origin
+    /// * `port` - the port of the origin, which endpoints use unless their records specify one
+    pub async fn connection_candidates(
+        &self,
+        host: impl IntoName,
+        port: u16,
+    ) -> Result<Vec<ConnectionCandidate>, ProtoError> {
+        let origin = host.into_name()?;
+
+        // RFC 9460 section 9.1: origins on other ports than 443 use a port prefix
+        let mut name = match port {
+            443 => origin.clone(),
+            _ => Name::from_ascii(format!("_{port}._https"))?.append_domain(&origin)?,
+        };
+
+        let mut endpoints = Vec::new();
+        for _ in 0..MAX_ALIAS_CHAIN {
+            let records = match self.lookup(name.clone(), RecordType::HTTPS).await {
+                Ok(lookup) => HttpsRecords::from_records(lookup.records(), port),
+                Err(e) if e.is_no_records_found() || e.is_nx_domain() => HttpsRecords::None,
+                Err(e) => return Err(e),
+            };
+
+            match records {
+                HttpsRecords::None => break,
+                HttpsRecords::Alias(target) => name = target,
+                HttpsRecords::Service(service) => {
+                    endpoints = service;
+                    break;
+                }
+            }
+        }
+
+        let addresses = future::join_all(
+            endpoints
+                .iter()
+                .map(|endpoint| self.lookup_ip(endpoint.target.clone())),
+        )
         .await
+        .into_iter()
+        .map(|lookup| lookup.map_or_else(|_| Vec::new(), |lookup| lookup.iter().collect()))
+        .collect();
+
+        let fallback = match self.lookup_ip(origin.clone()).await {
+            Ok(lookup) => lookup.iter().collect(),
+            // the endpoints might still be reachable
+            Err(e) if !endpoints.is_empty() => {
+                debug!("failed to resolve {origin}: {e}");
+                Vec::new()
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(connect::candidates(
+            endpoints, addresses, &origin, port, fallback,
+        ))
+    }
+
+    /// Waits for a lookup to be admitted under `max_outstanding_lookups`
+    async fn admit(&self) -> Result<Option<LookupPermit>, ProtoError> {
+        match &self.limiter {
+            Some(limiter) => limiter.acquire().await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Clears the cache if the blocklist was reloaded since the last lookup, as the cached answers
+    /// may be for names the blocklist now blocks or allows
+    fn sync_blocklist(&self) {
+        let Some(blocklist) = &self.blocklist else {
+            return;
+        };
+
+        let generation = blocklist.generation();
+        if self.blocklist_generation.swap(generation, Ordering::AcqRel) != generation {
+            self.client_cache.clear_cache();
+        }
     }
 
     fn build_names(&self, name: Name) -> Vec<Name> {
@@ -238,51 +356,63 @@ impl<R: ConnectionProvider> Resolver<R> {
             let mut names =
                 Vec::<Name>::with_capacity(1 /*FQDN*/ + 1 /*DOMAIN*/ + self.config.search().len());
 
-            // if not meeting ndots, we always do the raw name in the final lookup, or it's a localhost...
-            let raw_name_first: bool =
-                name.num_labels() as usize > self.options.ndots || name.is_localhost();
+            // single-label names queried as is end up at the root zone
+            let single_label = name.num_labels() < 2 && !name.is_localhost();
+            let (raw_name_first, use_search) = match self.options.search_order {
+                // if not meeting ndots, we always do the raw name in the final lookup, or it's a localhost...
+                SearchOrder::Ndots => (
+                    name.num_labels() as usize > self.options.ndots || name.is_localhost(),
+                    true,
+                ),
+                SearchOrder::Windows => (!single_label, single_label),
+            };
+            let query_raw_name = !single_label
+                || !self.options.avoid_single_label_queries
+                    && self.options.search_order != SearchOrder::Windows;
 
             // if not meeting ndots, we always do the raw name in the final lookup
-            if !raw_name_first {
+            if query_raw_name && !raw_name_first {
                 let mut fqdn = name.clone();
                 fqdn.set_fqdn(true);
                 names.push(fqdn);
             }
 
-            for search in self.config.search().iter().rev() {
-                let name_search = name.clone().append_domain(search);
+            if use_search {
+                for search in self.config.search().iter().rev() {
+                    let name_search = name.clone().append_domain(search);
 
-                match name_search {
-                    Ok(name_search) => {
-                        if !names.contains(&name_search) {
-                            names.push(name_search);
+                    match name_search {
+                        Ok(name_search) => {
+                            if !names.contains(&name_search) {
+                                names.push(name_search);
+                            }
                         }
+                        Err(e) => debug!(
+                            "Not adding {} to {} for search due to error: {}",
+                            search, name, e
+                        ),
                     }
-                    Err(e) => debug!(
-                        "Not adding {} to {} for search due to error: {}",
-                        search, name, e
-                    ),
                 }
-            }
 
-            if let Some(domain) = self.config.domain() {
-                let name_search = name.clone().append_domain(domain);
+                if let Some(domain) = self.config.domain() {
+                    let name_search = name.clone().append_domain(domain);
 
-                match name_search {
-                    Ok(name_search) => {
-                        if !names.contains(&name_search) {
-                            names.push(name_search);
+                    match name_search {
+                        Ok(name_search) => {
+                            if !names.contains(&name_search) {
+                                names.push(name_search);
+                            }
                         }
+                        Err(e) => debug!(
+                            "Not adding {} to {} for search due to error: {}",
+                            domain, name, e
+                        ),
                     }
-                    Err(e) => debug!(
-                        "Not adding {} to {} for search due to error: {}",
-                        domain, name, e
-                    ),
                 }
             }
 
             // this is the direct name lookup
-            if raw_name_first {
+            if query_raw_name && raw_name_first {
                 // adding the name as though it's an FQDN for lookup
                 let mut fqdn = name.clone();
                 fqdn.set_fqdn(true);
@@ -315,6 +445,8 @@ impl<R: ConnectionProvider> Resolver<R> {
         request_opts.recursion_desired = self.options.recursion_desired;
         request_opts.use_edns = self.options.edns0;
         request_opts.case_randomization = self.options.case_randomization;
+        request_opts.duplicate_response_window = self.options.duplicate_response_window;
+        request_opts.truncation_policy = self.options.truncation_policy;
 
         request_opts
     }
@@ -328,6 +460,16 @@ impl<R: ConnectionProvider> Resolver<R> {
     pub fn options(&self) -> &ResolverOpts {
         &self.options
     }
+
+    /// Returns the number of privacy downgrades and outages of the upstream name servers.
+    pub fn privacy_stats(&self) -> PrivacyStats {
+        self.pool.privacy_stats()
+    }
+
+    /// Returns the number of requests which received conflicting responses over UDP.
+    pub fn conflicting_responses(&self) -> u64 {
+        self.pool.conflicting_responses()
+    }
 }
 
 impl<P: ConnectionProvider> fmt::Debug for Resolver<P> {
@@ -371,6 +513,7 @@ pub struct ResolverBuilder<P> {
     config: ResolverConfig,
     options: ResolverOpts,
     provider: P,
+    blocklist: Option<Arc<Blocklist>>,
 
     #[cfg(feature = "__dnssec")]
     trust_anchor: Option<Arc<TrustAnchors>>,
@@ -378,6 +521,8 @@ pub struct ResolverBuilder<P> {
     nsec3_soft_iteration_limit: Option<u16>,
     #[cfg(feature = "__dnssec")]
     nsec3_hard_iteration_limit: Option<u16>,
+    #[cfg(feature = "__dnssec")]
+    dnssec_verification_concurrency: Option<usize>,
 }
 
 impl<P: ConnectionProvider> ResolverBuilder<P> {
@@ -399,6 +544,14 @@ impl<P: ConnectionProvider> ResolverBuilder<P> {
         &mut self.options
     }
 
+    /// Answers queries for the names in `blocklist` without sending them to the name servers.
+    ///
+    /// See [`Blocklist`] for the supported list formats and how blocked names are answered.
+    pub fn with_blocklist(mut self, blocklist: Arc<Blocklist>) -> Self {
+        self.blocklist = Some(blocklist);
+        self
+    }
+
     /// Set the DNSSEC trust anchors to be used by the resolver.
     #[cfg(feature = "__dnssec")]
     pub fn with_trust_anchor(mut self, trust_anchor: Arc<TrustAnchors>) -> Self {
@@ -422,6 +575,15 @@ impl<P: ConnectionProvider> ResolverBuilder<P> {
         self
     }
 
+    /// Set the number of RRsets of a response whose DNSSEC signatures are verified concurrently.
+    ///
+    /// Defaults to 8, `1` verifies them serially.
+    #[cfg(feature = "__dnssec")]
+    pub fn dnssec_verification_concurrency(mut self, limit: usize) -> Self {
+        self.dnssec_verification_concurrency = Some(limit);
+        self
+    }
+
     /// Construct the resolver.
     pub fn build(self) -> Resolver<P> {
         #[cfg_attr(not(feature = "__dnssec"), allow(unused_mut))]
@@ -429,12 +591,15 @@ impl<P: ConnectionProvider> ResolverBuilder<P> {
             config,
             mut options,
             provider,
+            blocklist,
             #[cfg(feature = "__dnssec")]
             trust_anchor,
             #[cfg(feature = "__dnssec")]
             nsec3_soft_iteration_limit,
             #[cfg(feature = "__dnssec")]
             nsec3_hard_iteration_limit,
+            #[cfg(feature = "__dnssec")]
+            dnssec_verification_concurrency,
         } = self;
 
         #[cfg(feature = "__dnssec")]
@@ -444,35 +609,57 @@ impl<P: ConnectionProvider> ResolverBuilder<P> {
 
         let options = Arc::new(options);
         let pool = NameServerPool::from_config_with_provider(&config, options.clone(), provider);
-        let client = RetryDnsHandle::new(pool, options.attempts);
+        let client = RetryDnsHandle::new(pool.clone(), options.attempts);
 
         #[cfg(feature = "__dnssec")]
         let either = if options.validate {
             let trust_anchor = trust_anchor.unwrap_or_else(|| Arc::new(TrustAnchors::default()));
 
-            LookupEither::Secure(
-                DnssecDnsHandle::with_trust_anchor(client, trust_anchor)
-                    .nsec3_iteration_limits(nsec3_soft_iteration_limit, nsec3_hard_iteration_limit),
-            )
+            let mut handle = DnssecDnsHandle::with_trust_anchor(client, trust_anchor)
+                .nsec3_iteration_limits(nsec3_soft_iteration_limit, nsec3_hard_iteration_limit);
+            if let Some(limit) = dnssec_verification_concurrency {
+                handle = handle.verification_concurrency(limit);
+            }
+
+            LookupEither::Secure(handle)
         } else {
             LookupEither::Retry(client)
         };
         #[cfg(not(feature = "__dnssec"))]
         let either = LookupEither::Retry(client);
 
-        let cache = ResponseCache::new(options.cache_size, TtlConfig::from_opts(&options));
-        let client_cache = CachingClient::with_cache(cache, either, options.preserve_intermediates);
+        let cache = ResponseCache::with_options(
+            options.cache_size,
+            options.cache_max_memory,
+            options.cache_eviction_policy,
+            TtlConfig::from_opts(&options),
+        );
+        let blocklist_generation = Arc::new(AtomicUsize::new(
+            blocklist
+                .as_ref()
+                .map_or(0, |blocklist| blocklist.generation()),
+        ));
+        let handle = BlocklistDnsHandle::new(either, blocklist.clone());
+        let client_cache = CachingClient::with_cache(cache, handle, options.preserve_intermediates);
 
         let hosts = Arc::new(match options.use_hosts_file {
             ResolveHosts::Always | ResolveHosts::Auto => Hosts::from_system().unwrap_or_default(),
             ResolveHosts::Never => Hosts::default(),
         });
 
+        let limiter = options
+            .max_outstanding_lookups
+            .map(|max| LookupLimiter::new(max, options.max_queued_lookups));
+
         Resolver {
             config,
             options,
             client_cache,
             hosts,
+            pool,
+            limiter,
+            blocklist,
+            blocklist_generation,
         }
     }
 }
@@ -1102,7 +1289,7 @@ mod tests {
     #[cfg(feature = "__dnssec")]
     use super::testing::{sec_lookup_fails_test, sec_lookup_test};
     use super::*;
-    use crate::config::{CLOUDFLARE, GOOGLE, ResolverConfig, ResolverOpts};
+    use crate::config::{CLOUDFLARE, GOOGLE, ResolverConfig, ResolverOpts, SearchOrder};
     use crate::proto::op::Message;
     use crate::proto::rr::rdata::A;
     use crate::proto::xfer::{DnsRequest, DnsResponse};
@@ -1293,6 +1480,88 @@ mod tests {
         assert_eq!(name_list.first(), Some(&fqdn));
     }
 
+    #[test]
+    fn test_build_names_search_order() {
+        use std::str::FromStr;
+
+        let handle = TokioRuntimeProvider::default();
+        let mut config = ResolverConfig::udp_and_tcp(&GOOGLE);
+        config.add_search(Name::from_ascii("example.com.").unwrap());
+        let mut builder = Resolver::builder_with_config(config, handle);
+        builder.options_mut().ndots = 2;
+        let resolver = builder.build();
+
+        // names are tried from the back
+        let name = Name::from_str("www").unwrap();
+        assert_eq!(
+            resolver.build_names(name),
+            vec![
+                Name::from_str("www.").unwrap(),
+                Name::from_str("www.example.com.").unwrap(),
+            ]
+        );
+        let name = Name::from_str("www.example.net").unwrap();
+        assert_eq!(
+            resolver.build_names(name),
+            vec![
+                Name::from_str("www.example.net.example.com.").unwrap(),
+                Name::from_str("www.example.net.").unwrap(),
+            ]
+        );
+
+        let handle = TokioRuntimeProvider::default();
+        let mut config = ResolverConfig::udp_and_tcp(&GOOGLE);
+        config.add_search(Name::from_ascii("example.com.").unwrap());
+        let mut builder = Resolver::builder_with_config(config, handle);
+        builder.options_mut().search_order = SearchOrder::Windows;
+        let resolver = builder.build();
+
+        let name = Name::from_str("www").unwrap();
+        assert_eq!(
+            resolver.build_names(name),
+            vec![Name::from_str("www.example.com.").unwrap()]
+        );
+        let name = Name::from_str("www.example").unwrap();
+        assert_eq!(
+            resolver.build_names(name),
+            vec![Name::from_str("www.example.").unwrap()]
+        );
+        let name = Name::from_str("localhost").unwrap();
+        assert_eq!(
+            resolver.build_names(name),
+            vec![Name::from_str("localhost.").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_build_names_avoid_single_label_queries() {
+        use std::str::FromStr;
+
+        let handle = TokioRuntimeProvider::default();
+        let mut builder =
+            Resolver::builder_with_config(ResolverConfig::udp_and_tcp(&GOOGLE), handle);
+        builder.options_mut().avoid_single_label_queries = true;
+        let resolver = builder.build();
+
+        assert!(
+            resolver
+                .build_names(Name::from_str("www").unwrap())
+                .is_empty()
+        );
+        assert_eq!(
+            resolver.build_names(Name::from_str("www.example").unwrap()),
+            vec![Name::from_str("www.example.").unwrap()]
+        );
+        assert_eq!(
+            resolver.build_names(Name::from_str("localhost").unwrap()),
+            vec![Name::from_str("localhost.").unwrap()]
+        );
+        assert_eq!(
+            resolver.build_names(Name::from_str("www.").unwrap()).len(),
+            1
+        );
+    }
+
     #[test]
     fn test_build_names_onion() {
         let handle = TokioRuntimeProvider::default();
@@ -0,0 +1,217 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! System configuration loading for macOS
+//!
+//! The configuration is read from the dynamic store of the SystemConfiguration framework, which
+//!  is what `scutil --dns` reports. `/etc/resolv.conf` is only a partial copy of it, which lacks
+//!  e.g. the supplemental resolvers used by VPNs for their domains.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use system_configuration::core_foundation::array::CFArray;
+use system_configuration::core_foundation::base::{CFType, TCFType};
+use system_configuration::core_foundation::dictionary::CFDictionary;
+use system_configuration::core_foundation::string::CFString;
+use system_configuration::dynamic_store::{SCDynamicStore, SCDynamicStoreBuilder};
+use tracing::debug;
+
+use super::unix;
+use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use crate::proto::ProtoError;
+use crate::proto::rr::Name;
+
+const STORE_NAME: &str = "hickory-resolver";
+const GLOBAL_DNS_KEY: &str = "State:/Network/Global/DNS";
+const SERVICE_DNS_PATTERN: &str = "State:/Network/Service/[^/]+/DNS";
+
+const SERVER_ADDRESSES: &str = "ServerAddresses";
+const SEARCH_DOMAINS: &str = "SearchDomains";
+const DOMAIN_NAME: &str = "DomainName";
+const SUPPLEMENTAL_MATCH_DOMAINS: &str = "SupplementalMatchDomains";
+const INTERFACE_NAME: &str = "InterfaceName";
+
+/// Name servers that are only used for names within some domains, e.g. those of a VPN
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct SupplementalConfig {
+    /// The domains for which `config` should be used
+    pub match_domains: Vec<Name>,
+    /// The network interface through which the name servers are reached, if known
+    pub interface: Option<String>,
+    /// The name servers, domain and search domains to use for names within `match_domains`
+    pub config: ResolverConfig,
+}
+
+/// Reads the global resolver configuration from the dynamic store
+///
+/// Falls back to `/etc/resolv.conf` if the dynamic store has no global name servers, for
+///  example when no network service is active. The dynamic store has no resolver options, so
+///  these are always read from `/etc/resolv.conf`, as on other Unix platforms.
+///
+/// The name servers of the supplemental resolvers are added for their match domains, see
+///  [`ResolverConfig::add_domain_name_servers`], so that e.g. the names of a VPN are only sent to
+///  the name servers of the VPN.
+pub fn read_system_conf() -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    let store = SCDynamicStoreBuilder::new(STORE_NAME).build();
+    let (mut config, options) =
+        match dictionary(&store, GLOBAL_DNS_KEY).and_then(|dns| resolver_config(&dns)) {
+            Some(config) => {
+                let options = match unix::read_resolv_conf_options(unix::RESOLV_CONF_PATH) {
+                    Ok(options) => options,
+                    Err(error) => {
+                        debug!(%error, "using the default resolver options");
+                        ResolverOpts::default()
+                    }
+                };
+                (config, options)
+            }
+            None => unix::read_resolv_conf(unix::RESOLV_CONF_PATH)?,
+        };
+
+    for supplemental in supplemental_conf(&store) {
+        for domain in supplemental.match_domains {
+            config.add_domain_name_servers(domain, supplemental.config.name_servers().to_vec());
+        }
+    }
+
+    Ok((config, options))
+}
+
+/// Reads the supplemental resolver configurations of all network services from the dynamic store
+///
+/// These are the resolvers that `scutil --dns` lists with a `domain` and that only answer for the
+///  names within their match domains, e.g. those configured by VPN clients. Their name servers are
+///  already part of the configuration returned by [`read_system_conf`].
+pub fn read_supplemental_conf() -> Result<Vec<SupplementalConfig>, ProtoError> {
+    let store = SCDynamicStoreBuilder::new(STORE_NAME).build();
+    Ok(supplemental_conf(&store))
+}
+
+fn supplemental_conf(store: &SCDynamicStore) -> Vec<SupplementalConfig> {
+    let Some(keys) = store.get_keys(SERVICE_DNS_PATTERN) else {
+        return Vec::new();
+    };
+
+    let mut supplemental = Vec::new();
+    for key in keys.iter() {
+        let Some(dns) = dictionary(store, &key.to_string()) else {
+            continue;
+        };
+
+        // an empty match domain makes the resolver a default one, which the global resolver
+        // configuration already covers
+        let match_domains = strings(&dns, SUPPLEMENTAL_MATCH_DOMAINS)
+            .iter()
+            .filter_map(|domain| Name::from_str(domain).ok())
+            .filter(|domain| !domain.is_root())
+            .collect::<Vec<_>>();
+        if match_domains.is_empty() {
+            continue;
+        }
+
+        let Some(config) = resolver_config(&dns) else {
+            continue;
+        };
+
+        supplemental.push(SupplementalConfig {
+            match_domains,
+            interface: string(&dns, INTERFACE_NAME),
+            config,
+        });
+    }
+
+    supplemental
+}
+
+/// Converts a DNS dictionary of the dynamic store, `None` if it has no usable name servers
+fn resolver_config(dns: &CFDictionary<CFString, CFType>) -> Option<ResolverConfig> {
+    let name_servers = strings(dns, SERVER_ADDRESSES)
+        .iter()
+        .filter_map(|address| parse_address(address))
+        .map(|ip| {
+            let mut config = NameServerConfig::udp_and_tcp(ip);
+            config.trust_negative_responses = false;
+            config
+        })
+        .collect::<Vec<_>>();
+    if name_servers.is_empty() {
+        return None;
+    }
+
+    // Ignore invalid domains, like the resolv.conf parser does
+    let domain = string(dns, DOMAIN_NAME).and_then(|domain| Name::from_str(&domain).ok());
+    let search = strings(dns, SEARCH_DOMAINS)
+        .iter()
+        .filter_map(|domain| Name::from_str(domain).ok())
+        .collect();
+
+    Some(ResolverConfig::from_parts(domain, search, name_servers))
+}
+
+/// Parses a server address, which may carry a scope like `fe80::1%en0`
+fn parse_address(address: &str) -> Option<IpAddr> {
+    let address = address
+        .split_once('%')
+        .map_or(address, |(address, _)| address);
+    IpAddr::from_str(address).ok()
+}
+
+fn dictionary(store: &SCDynamicStore, key: &str) -> Option<CFDictionary<CFString, CFType>> {
+    let dictionary = store.get(key)?.downcast_into::<CFDictionary>()?;
+
+    // SAFETY: the keys of the dictionaries in the dynamic store are strings and their values are
+    // property list objects
+    Some(unsafe { CFDictionary::wrap_under_get_rule(dictionary.as_concrete_TypeRef()) })
+}
+
+fn string(dictionary: &CFDictionary<CFString, CFType>, key: &'static str) -> Option<String> {
+    dictionary
+        .find(CFString::from_static_string(key))?
+        .downcast::<CFString>()
+        .map(|string| string.to_string())
+}
+
+fn strings(dictionary: &CFDictionary<CFString, CFType>, key: &'static str) -> Vec<String> {
+    let Some(array) = dictionary
+        .find(CFString::from_static_string(key))
+        .and_then(|value| value.downcast::<CFArray>())
+    else {
+        return Vec::new();
+    };
+
+    array
+        .iter()
+        .filter_map(|item| {
+            // SAFETY: the items of a property list array are property list objects
+            let item = unsafe { CFType::wrap_under_get_rule(*item) };
+            item.downcast::<CFString>()
+        })
+        .map(|string| string.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(
+            parse_address("192.0.2.1"),
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)))
+        );
+        assert_eq!(
+            parse_address("fe80::1%en0"),
+            Some(IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)))
+        );
+        assert_eq!(parse_address("ns.example.com"), None);
+    }
+}
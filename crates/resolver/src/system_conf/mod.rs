@@ -9,7 +9,9 @@
 //!
 //! This module is responsible for parsing and returning the configuration from
 //!  the host system. It will read from the default location on each operating
-//!  system, e.g. most Unixes have this written to `/etc/resolv.conf`
+//!  system, e.g. most Unixes have this written to `/etc/resolv.conf`, macOS keeps
+//!  it in the SystemConfiguration dynamic store and Windows in the registry and
+//!  the adapter addresses.
 #![allow(missing_docs)]
 
 #[cfg(unix)]
@@ -18,7 +20,19 @@ mod unix;
 
 #[cfg(unix)]
 #[cfg(feature = "system-config")]
-pub use self::unix::{parse_resolv_conf, read_system_conf};
+pub use self::unix::parse_resolv_conf;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+#[cfg(feature = "system-config")]
+pub use self::unix::read_system_conf;
+
+#[cfg(target_os = "macos")]
+#[cfg(feature = "system-config")]
+mod macos;
+
+#[cfg(target_os = "macos")]
+#[cfg(feature = "system-config")]
+pub use self::macos::{SupplementalConfig, read_supplemental_conf, read_system_conf};
 
 #[cfg(windows)]
 #[cfg(feature = "system-config")]
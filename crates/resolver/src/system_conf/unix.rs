@@ -18,27 +18,47 @@ use std::path::Path;
 use std::str::FromStr;
 use std::time::Duration;
 
-use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts, ServerOrderingStrategy};
 use crate::proto::ProtoError;
 use crate::proto::rr::Name;
 
+#[cfg(not(target_os = "macos"))]
 pub fn read_system_conf() -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
-    read_resolv_conf("/etc/resolv.conf")
+    read_resolv_conf(RESOLV_CONF_PATH)
 }
 
-fn read_resolv_conf<P: AsRef<Path>>(path: P) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
-    let mut data = String::new();
-    let mut file = File::open(path)?;
-    file.read_to_string(&mut data)?;
-    parse_resolv_conf(&data)
+pub(super) const RESOLV_CONF_PATH: &str = "/etc/resolv.conf";
+
+pub(super) fn read_resolv_conf<P: AsRef<Path>>(
+    path: P,
+) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
+    into_resolver_config(read_config(path)?)
+}
+
+/// Reads only the options of a resolv.conf file, which may list no name servers
+#[cfg(target_os = "macos")]
+pub(super) fn read_resolv_conf_options<P: AsRef<Path>>(
+    path: P,
+) -> Result<ResolverOpts, ProtoError> {
+    Ok(into_resolver_opts(&read_config(path)?))
 }
 
 pub fn parse_resolv_conf<T: AsRef<[u8]>>(
     data: T,
 ) -> Result<(ResolverConfig, ResolverOpts), ProtoError> {
-    let parsed_conf = resolv_conf::Config::parse(&data)
-        .map_err(|e| io::Error::other(format!("Error parsing resolv.conf: {e}")))?;
-    into_resolver_config(parsed_conf)
+    into_resolver_config(parse_config(data)?)
+}
+
+fn read_config<P: AsRef<Path>>(path: P) -> Result<resolv_conf::Config, ProtoError> {
+    let mut data = String::new();
+    let mut file = File::open(path)?;
+    file.read_to_string(&mut data)?;
+    parse_config(&data)
+}
+
+fn parse_config<T: AsRef<[u8]>>(data: T) -> Result<resolv_conf::Config, ProtoError> {
+    Ok(resolv_conf::Config::parse(&data)
+        .map_err(|e| io::Error::other(format!("Error parsing resolv.conf: {e}")))?)
 }
 
 // TODO: use a custom parsing error type maybe?
@@ -85,16 +105,23 @@ fn into_resolver_config(
     }
 
     let config = ResolverConfig::from_parts(domain, search, nameservers);
+    Ok((config, into_resolver_opts(&parsed_config)))
+}
+
+fn into_resolver_opts(parsed_config: &resolv_conf::Config) -> ResolverOpts {
+    let server_ordering_strategy = match parsed_config.rotate {
+        true => ServerOrderingStrategy::RoundRobin,
+        false => ServerOrderingStrategy::default(),
+    };
 
-    let options = ResolverOpts {
+    ResolverOpts {
         ndots: parsed_config.ndots as usize,
         timeout: Duration::from_secs(u64::from(parsed_config.timeout)),
         attempts: parsed_config.attempts as usize,
         edns0: parsed_config.edns0,
+        server_ordering_strategy,
         ..ResolverOpts::default()
-    };
-
-    Ok((config, options))
+    }
 }
 
 #[cfg(test)]
@@ -176,6 +203,23 @@ mod tests {
         is_default_opts(parsed.1);
     }
 
+    #[test]
+    fn test_options() {
+        let parsed = parse_resolv_conf(
+            "options ndots:3 timeout:1 attempts:4 rotate edns0\nnameserver 127.0.0.1",
+        )
+        .expect("failed");
+        let opts = parsed.1;
+        assert_eq!(opts.ndots, 3);
+        assert_eq!(opts.timeout, Duration::from_secs(1));
+        assert_eq!(opts.attempts, 4);
+        assert!(opts.edns0);
+        assert_eq!(
+            opts.server_ordering_strategy,
+            ServerOrderingStrategy::RoundRobin
+        );
+    }
+
     #[test]
     fn test_read_resolv_conf() {
         read_resolv_conf(format!("{}/resolv.conf-simple", tests_dir())).expect("simple failed");
@@ -188,5 +232,9 @@ mod tests {
         assert_eq!(opts.ndots, 1);
         assert_eq!(opts.timeout, Duration::from_secs(5));
         assert_eq!(opts.attempts, 2);
+        assert_eq!(
+            opts.server_ordering_strategy,
+            ServerOrderingStrategy::QueryStatistics
+        );
     }
 }
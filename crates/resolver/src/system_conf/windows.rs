@@ -13,7 +13,7 @@ use std::str::FromStr;
 use ipconfig::computer::{get_domain, get_search_list};
 use ipconfig::get_adapters;
 
-use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts, SearchOrder};
 use crate::proto::ProtoError;
 use crate::proto::rr::Name;
 
@@ -50,9 +50,12 @@ pub fn read_system_conf() -> Result<(ResolverConfig, ResolverOpts), ProtoError>
         None => Name::root(),
     };
 
+    let mut options = ResolverOpts::default();
+    options.search_order = SearchOrder::Windows;
+
     Ok((
         ResolverConfig::from_parts(Some(domain), search_list, name_servers),
-        ResolverOpts::default(),
+        options,
     ))
 }
 
@@ -0,0 +1,132 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Certificate signing requests, placeholder certificates and the expiration of certificates
+
+use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair, PKCS_ECDSA_P256_SHA256};
+use rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer};
+use time::OffsetDateTime;
+use x509_cert::Certificate;
+use x509_cert::der::Decode;
+
+use super::AcmeError;
+use crate::proto::rr::Name;
+
+/// Generates a P-256 key for a certificate
+pub(super) fn generate_key() -> Result<KeyPair, AcmeError> {
+    Ok(KeyPair::generate_for(&PKCS_ECDSA_P256_SHA256)?)
+}
+
+/// The PKCS#8 encoding of `key`
+pub(super) fn pkcs8(key: &KeyPair) -> PrivatePkcs8KeyDer<'static> {
+    PrivatePkcs8KeyDer::from(key.serialize_der())
+}
+
+/// Encodes a PKCS#10 certificate signing request for `domains`, signed with `key`
+pub(super) fn certificate_request(domains: &[Name], key: &KeyPair) -> Result<Vec<u8>, AcmeError> {
+    let request = params(domains)?.serialize_request(key)?;
+    Ok(request.der().to_vec())
+}
+
+/// Encodes a self-signed certificate for `domains`, valid from `not_before` until `not_after`
+pub(super) fn self_signed_certificate(
+    domains: &[Name],
+    key: &KeyPair,
+    not_before: OffsetDateTime,
+    not_after: OffsetDateTime,
+) -> Result<CertificateDer<'static>, AcmeError> {
+    let mut params = params(domains)?;
+    params.not_before = not_before;
+    params.not_after = not_after;
+    Ok(params.self_signed(key)?.der().clone())
+}
+
+/// Reads the end of the validity period of the DER encoded X.509 `certificate`
+pub(super) fn not_after(certificate: &[u8]) -> Result<OffsetDateTime, AcmeError> {
+    let certificate = Certificate::from_der(certificate)
+        .map_err(|err| AcmeError::Msg(format!("failed to parse certificate: {err}")))?;
+    let not_after = certificate
+        .tbs_certificate
+        .validity
+        .not_after
+        .to_unix_duration();
+
+    OffsetDateTime::from_unix_timestamp(not_after.as_secs() as i64)
+        .map_err(|err| AcmeError::Msg(format!("invalid expiration of certificate: {err}")))
+}
+
+/// The first domain is the common name, all of them are alternative names
+fn params(domains: &[Name]) -> Result<CertificateParams, AcmeError> {
+    let names = domains
+        .iter()
+        .map(|name| {
+            let name = name.to_ascii();
+            name.strip_suffix('.').unwrap_or(&name).to_owned()
+        })
+        .collect::<Vec<_>>();
+
+    let mut distinguished_name = DistinguishedName::new();
+    if let Some(common_name) = names.first() {
+        distinguished_name.push(DnType::CommonName, common_name.as_str());
+    }
+
+    let mut params = CertificateParams::new(names)?;
+    params.distinguished_name = distinguished_name;
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rustls::pki_types::PrivateKeyDer;
+    use rustls::sign::CertifiedKey;
+
+    use super::*;
+    use crate::proto::rustls::default_provider;
+
+    #[test]
+    fn test_self_signed_certificate() {
+        let key = generate_key().unwrap();
+
+        let domains = [
+            Name::from_ascii("ns.example.com.").unwrap(),
+            Name::from_ascii("*.example.com.").unwrap(),
+        ];
+        let not_before = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let expires = not_before + Duration::from_secs(7 * 24 * 60 * 60);
+        let certificate = self_signed_certificate(&domains, &key, not_before, expires).unwrap();
+
+        assert_eq!(not_after(&certificate).unwrap(), expires);
+
+        // rustls checks that the certificate parses and belongs to the key
+        CertifiedKey::from_der(
+            vec![certificate],
+            PrivateKeyDer::Pkcs8(pkcs8(&key)),
+            &default_provider(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_certificate_request() {
+        let key = generate_key().unwrap();
+        let domains = [Name::from_ascii("ns.example.com.").unwrap()];
+        let request = certificate_request(&domains, &key).unwrap();
+
+        let request = x509_cert::request::CertReq::from_der(&request).unwrap();
+        assert_eq!(
+            request.info.subject.to_string(),
+            "CN=ns.example.com".to_string()
+        );
+    }
+
+    #[test]
+    fn test_not_after_of_malformed_certificate() {
+        assert!(not_after(&[0x30, 0x03, 0x02, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,443 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The requests of RFC 8555, signed with the account key
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use data_encoding::BASE64URL_NOPAD;
+use http::{HeaderMap, Method, Response, header};
+use rustls::ClientConfig;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tokio::time::sleep;
+use tracing::debug;
+
+use super::{AcmeError, http::request};
+use crate::proto::dnssec::crypto::{Digest, EcdsaSigningKey};
+use crate::proto::dnssec::{DigestType, PublicKey, SigningKey, TBS};
+use crate::proto::rr::Name;
+
+/// Number of times the state of an order or authorization is checked before giving up
+const POLL_ATTEMPTS: usize = 30;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A session with the ACME server, keeping track of the anti-replay nonce
+pub(super) struct AcmeClient<'a> {
+    client_config: &'a Arc<ClientConfig>,
+    key: &'a EcdsaSigningKey,
+    directory: Directory,
+    nonce: Option<String>,
+    account: Option<String>,
+}
+
+impl<'a> AcmeClient<'a> {
+    /// Fetches the directory of the ACME server at `directory_url`
+    pub(super) async fn new(
+        client_config: &'a Arc<ClientConfig>,
+        key: &'a EcdsaSigningKey,
+        directory_url: &str,
+    ) -> Result<Self, AcmeError> {
+        let response = request(client_config, Method::GET, directory_url, None).await?;
+        let directory = parse(response)?.1;
+
+        Ok(Self {
+            client_config,
+            key,
+            directory,
+            nonce: None,
+            account: None,
+        })
+    }
+
+    /// Creates the account of the key, or looks up the existing one
+    pub(super) async fn register(&mut self, contacts: &[String]) -> Result<(), AcmeError> {
+        let payload = json!({
+            "termsOfServiceAgreed": true,
+            "contact": contacts,
+        });
+
+        let url = self.directory.new_account.clone();
+        let (headers, _) = self.post::<Value>(&url, Some(payload)).await?;
+        self.account = Some(location(&headers)?);
+        Ok(())
+    }
+
+    /// Places an order for a certificate for `domains`, returning its url
+    pub(super) async fn new_order(
+        &mut self,
+        domains: &[Name],
+    ) -> Result<(String, Order), AcmeError> {
+        let identifiers = domains
+            .iter()
+            .map(|domain| json!({ "type": "dns", "value": identifier(domain) }))
+            .collect::<Vec<_>>();
+
+        let url = self.directory.new_order.clone();
+        let (headers, order) = self
+            .post(&url, Some(json!({ "identifiers": identifiers })))
+            .await?;
+        Ok((location(&headers)?, order))
+    }
+
+    /// Fetches the authorization at `url`
+    pub(super) async fn authorization(&mut self, url: &str) -> Result<Authorization, AcmeError> {
+        Ok(self.post(url, None).await?.1)
+    }
+
+    /// Tells the server that the challenge at `url` is ready to be validated
+    pub(super) async fn respond(&mut self, url: &str) -> Result<(), AcmeError> {
+        self.post::<Value>(url, Some(json!({}))).await?;
+        Ok(())
+    }
+
+    /// Waits until the authorization at `url` is no longer pending
+    pub(super) async fn poll_authorization(
+        &mut self,
+        url: &str,
+    ) -> Result<Authorization, AcmeError> {
+        for _ in 0..POLL_ATTEMPTS {
+            let authorization = self.authorization(url).await?;
+            if authorization.status != Status::Pending {
+                return Ok(authorization);
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+
+        Err(AcmeError::Msg(format!(
+            "authorization {url} is still pending"
+        )))
+    }
+
+    /// Waits until the order at `url` is neither pending nor processing
+    pub(super) async fn poll_order(&mut self, url: &str) -> Result<Order, AcmeError> {
+        for _ in 0..POLL_ATTEMPTS {
+            let order = self.post::<Order>(url, None).await?.1;
+            if !matches!(order.status, Status::Pending | Status::Processing) {
+                return Ok(order);
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+
+        Err(AcmeError::Msg(format!("order {url} is still processing")))
+    }
+
+    /// Requests the certificate for the DER encoded certificate signing request `csr`
+    pub(super) async fn finalize(&mut self, order: &Order, csr: &[u8]) -> Result<(), AcmeError> {
+        let payload = json!({ "csr": BASE64URL_NOPAD.encode(csr) });
+        self.post::<Value>(&order.finalize, Some(payload)).await?;
+        Ok(())
+    }
+
+    /// Downloads the PEM encoded certificate chain at `url`
+    pub(super) async fn certificate(&mut self, url: &str) -> Result<String, AcmeError> {
+        let response = self.signed_request(url, None).await?;
+        String::from_utf8(response.into_body().to_vec())
+            .map_err(|_| AcmeError::Msg("certificate is not valid PEM".to_string()))
+    }
+
+    /// The TXT record content answering the DNS-01 challenge with `token`
+    pub(super) fn dns01_value(&self, token: &str) -> Result<String, AcmeError> {
+        let key_authorization = format!("{token}.{}", self.thumbprint()?);
+        let digest = Digest::new(key_authorization.as_bytes(), DigestType::SHA256)?;
+        Ok(BASE64URL_NOPAD.encode(digest.as_ref()))
+    }
+
+    /// RFC 7638 thumbprint of the account key
+    fn thumbprint(&self) -> Result<String, AcmeError> {
+        let (x, y) = self.coordinates()?;
+        // the members in lexicographic order and without whitespace
+        let jwk = format!(r#"{{"crv":"P-256","kty":"EC","x":"{x}","y":"{y}"}}"#);
+        let digest = Digest::new(jwk.as_bytes(), DigestType::SHA256)?;
+        Ok(BASE64URL_NOPAD.encode(digest.as_ref()))
+    }
+
+    fn coordinates(&self) -> Result<(String, String), AcmeError> {
+        let public_key = self.key.to_public_key()?;
+        let (x, y) = public_key.public_bytes().split_at(32);
+        Ok((BASE64URL_NOPAD.encode(x), BASE64URL_NOPAD.encode(y)))
+    }
+
+    /// Sends `payload` to `url`, or fetches `url` if there is no payload
+    async fn post<T: DeserializeOwned>(
+        &mut self,
+        url: &str,
+        payload: Option<Value>,
+    ) -> Result<(HeaderMap, T), AcmeError> {
+        let response = self.signed_request(url, payload).await?;
+        parse(response)
+    }
+
+    async fn signed_request(
+        &mut self,
+        url: &str,
+        payload: Option<Value>,
+    ) -> Result<Response<Bytes>, AcmeError> {
+        // a nonce which expired is rejected with a fresh one, which is tried once
+        let mut retried = false;
+        loop {
+            let body = self.sign(url, payload.as_ref()).await?;
+            let response = request(self.client_config, Method::POST, url, Some(body)).await?;
+            self.nonce = nonce(response.headers());
+
+            if response.status().is_success() {
+                return Ok(response);
+            }
+
+            let error = problem(response);
+            match error {
+                AcmeError::Problem { ref kind, .. }
+                    if !retried && kind == "urn:ietf:params:acme:error:badNonce" =>
+                {
+                    debug!("retrying ACME request with a new nonce");
+                    retried = true;
+                }
+                error => return Err(error),
+            }
+        }
+    }
+
+    /// Creates the flattened JSON Web Signature for `payload`, RFC 8555, section 6.2
+    async fn sign(&mut self, url: &str, payload: Option<&Value>) -> Result<Vec<u8>, AcmeError> {
+        let nonce = match self.nonce.take() {
+            Some(nonce) => nonce,
+            None => self.new_nonce().await?,
+        };
+
+        let mut protected = json!({
+            "alg": "ES256",
+            "nonce": nonce,
+            "url": url,
+        });
+        match &self.account {
+            Some(account) => protected["kid"] = json!(account),
+            None => {
+                let (x, y) = self.coordinates()?;
+                protected["jwk"] = json!({ "crv": "P-256", "kty": "EC", "x": x, "y": y });
+            }
+        }
+
+        let protected = BASE64URL_NOPAD.encode(&serde_json::to_vec(&protected)?);
+        // an empty payload marks a POST-as-GET request
+        let payload = match payload {
+            Some(payload) => BASE64URL_NOPAD.encode(&serde_json::to_vec(payload)?),
+            None => String::new(),
+        };
+
+        let signing_input = format!("{protected}.{payload}");
+        let signature = self.key.sign(&TBS::from(signing_input.as_bytes()))?;
+
+        Ok(serde_json::to_vec(&json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": BASE64URL_NOPAD.encode(&signature),
+        }))?)
+    }
+
+    async fn new_nonce(&self) -> Result<String, AcmeError> {
+        let response = request(
+            self.client_config,
+            Method::HEAD,
+            &self.directory.new_nonce,
+            None,
+        )
+        .await?;
+
+        nonce(response.headers())
+            .ok_or_else(|| AcmeError::Msg("no nonce returned by the ACME server".to_string()))
+    }
+}
+
+/// The value of the ACME identifier of `domain`
+pub(super) fn identifier(domain: &Name) -> String {
+    let domain = domain.to_ascii();
+    match domain.strip_suffix('.') {
+        Some(domain) => domain.to_string(),
+        None => domain,
+    }
+}
+
+fn parse<T: DeserializeOwned>(response: Response<Bytes>) -> Result<(HeaderMap, T), AcmeError> {
+    if !response.status().is_success() {
+        return Err(problem(response));
+    }
+
+    let (parts, body) = response.into_parts();
+    Ok((parts.headers, serde_json::from_slice(&body)?))
+}
+
+fn problem(response: Response<Bytes>) -> AcmeError {
+    let status = response.status();
+    match serde_json::from_slice::<Problem>(response.body()) {
+        Ok(problem) => AcmeError::Problem {
+            status: status.as_u16(),
+            kind: problem.kind,
+            detail: problem.detail.unwrap_or_default(),
+        },
+        Err(_) => AcmeError::Problem {
+            status: status.as_u16(),
+            kind: String::new(),
+            detail: status.canonical_reason().unwrap_or_default().to_string(),
+        },
+    }
+}
+
+fn nonce(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("replay-nonce")
+        .and_then(|nonce| nonce.to_str().ok())
+        .map(str::to_string)
+}
+
+fn location(headers: &HeaderMap) -> Result<String, AcmeError> {
+    headers
+        .get(header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .map(str::to_string)
+        .ok_or_else(|| AcmeError::Msg("no location returned by the ACME server".to_string()))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+/// The state of a certificate order
+#[derive(Deserialize)]
+pub(super) struct Order {
+    pub(super) status: Status,
+    pub(super) authorizations: Vec<String>,
+    pub(super) finalize: String,
+    pub(super) certificate: Option<String>,
+}
+
+/// The challenges proving the control over an identifier of an order
+#[derive(Deserialize)]
+pub(super) struct Authorization {
+    pub(super) status: Status,
+    pub(super) identifier: Identifier,
+    pub(super) challenges: Vec<Challenge>,
+}
+
+#[derive(Deserialize)]
+pub(super) struct Identifier {
+    pub(super) value: String,
+}
+
+#[derive(Deserialize)]
+pub(super) struct Challenge {
+    #[serde(rename = "type")]
+    pub(super) kind: String,
+    pub(super) url: String,
+    #[serde(default)]
+    pub(super) token: String,
+}
+
+/// The status of orders, authorizations and challenges
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum Status {
+    Pending,
+    Ready,
+    Processing,
+    Valid,
+    Invalid,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct Problem {
+    #[serde(rename = "type")]
+    kind: String,
+    detail: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::dnssec::Algorithm;
+
+    fn client<'a>(
+        client_config: &'a Arc<ClientConfig>,
+        key: &'a EcdsaSigningKey,
+    ) -> AcmeClient<'a> {
+        AcmeClient {
+            client_config,
+            key,
+            directory: Directory {
+                new_nonce: String::new(),
+                new_account: String::new(),
+                new_order: String::new(),
+            },
+            nonce: Some("nonce".to_string()),
+            account: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign() {
+        let pkcs8 = EcdsaSigningKey::generate_pkcs8(Algorithm::ECDSAP256SHA256).unwrap();
+        let key = EcdsaSigningKey::from_pkcs8(&pkcs8, Algorithm::ECDSAP256SHA256).unwrap();
+        let client_config = Arc::new(crate::proto::rustls::client_config());
+        let mut client = client(&client_config, &key);
+
+        let url = "https://acme.example.com/new-account";
+        let body = client.sign(url, Some(&json!({}))).await.unwrap();
+        let jws = serde_json::from_slice::<Value>(&body).unwrap();
+        let protected = jws["protected"].as_str().unwrap();
+        let payload = jws["payload"].as_str().unwrap();
+        let signature = BASE64URL_NOPAD
+            .decode(jws["signature"].as_str().unwrap().as_bytes())
+            .unwrap();
+
+        key.to_public_key()
+            .unwrap()
+            .verify(format!("{protected}.{payload}").as_bytes(), &signature)
+            .unwrap();
+
+        // the account is identified by its key until it is registered
+        let header = BASE64URL_NOPAD.decode(protected.as_bytes()).unwrap();
+        let header = serde_json::from_slice::<Value>(&header).unwrap();
+        assert_eq!(header["nonce"], "nonce");
+        assert_eq!(header["url"], url);
+        assert_eq!(header["jwk"]["crv"], "P-256");
+        assert!(header.get("kid").is_none());
+    }
+
+    #[test]
+    fn test_dns01_value() {
+        let pkcs8 = EcdsaSigningKey::generate_pkcs8(Algorithm::ECDSAP256SHA256).unwrap();
+        let key = EcdsaSigningKey::from_pkcs8(&pkcs8, Algorithm::ECDSAP256SHA256).unwrap();
+        let client_config = Arc::new(crate::proto::rustls::client_config());
+        let client = client(&client_config, &key);
+
+        // unpadded base64 of SHA-256 digests
+        assert_eq!(client.thumbprint().unwrap().len(), 43);
+        let value = client.dns01_value("token").unwrap();
+        assert_eq!(value.len(), 43);
+        assert_ne!(value, client.dns01_value("other").unwrap());
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(
+            identifier(&Name::from_ascii("*.example.com.").unwrap()),
+            "*.example.com"
+        );
+        assert_eq!(
+            identifier(&Name::from_ascii("example.com").unwrap()),
+            "example.com"
+        );
+    }
+}
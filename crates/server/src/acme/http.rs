@@ -0,0 +1,98 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A minimal HTTP/1.1 client for the requests to the ACME server
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::{Method, Request, Response, Uri, header};
+use http_body_util::{BodyExt, Full};
+use hyper::client::conn::http1;
+use hyper_util::rt::TokioIo;
+use rustls::ClientConfig;
+use rustls::pki_types::ServerName;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+use tracing::debug;
+
+use super::AcmeError;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends a request for `url`, with `body` as the JSON Web Signature content if present
+///
+/// A new connection is used for every request, as only a handful are needed for each certificate.
+pub(super) async fn request(
+    client_config: &Arc<ClientConfig>,
+    method: Method,
+    url: &str,
+    body: Option<Vec<u8>>,
+) -> Result<Response<Bytes>, AcmeError> {
+    let uri = url.parse::<Uri>().map_err(http::Error::from)?;
+    let (Some(host), Some(authority)) = (uri.host(), uri.authority()) else {
+        return Err(AcmeError::Msg(format!("no host in url: {url}")));
+    };
+
+    let https = match uri.scheme_str() {
+        Some("https") => true,
+        // only local test servers are reached without TLS
+        Some("http") if cfg!(feature = "testing") => false,
+        _ => return Err(AcmeError::Msg(format!("unsupported scheme in url: {url}"))),
+    };
+    let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri.path_and_query().map_or("/", |path| path.as_str()))
+        .header(header::HOST, authority.as_str())
+        .header(
+            header::USER_AGENT,
+            concat!("hickory-dns/", env!("CARGO_PKG_VERSION")),
+        );
+    if body.is_some() {
+        request = request.header(header::CONTENT_TYPE, "application/jose+json");
+    }
+    let request = request.body(Full::new(Bytes::from(body.unwrap_or_default())))?;
+
+    let response = async {
+        let stream = TcpStream::connect((host, port)).await?;
+        if !https {
+            return send(stream, request).await;
+        }
+
+        let server_name = ServerName::try_from(host.to_owned())
+            .map_err(|err| AcmeError::Msg(format!("invalid host in url {url}: {err}")))?;
+        let stream = TlsConnector::from(client_config.clone())
+            .connect(server_name, stream)
+            .await?;
+        send(stream, request).await
+    };
+
+    timeout(REQUEST_TIMEOUT, response)
+        .await
+        .map_err(|_| AcmeError::Msg(format!("request to {url} timed out")))?
+}
+
+async fn send<S>(stream: S, request: Request<Full<Bytes>>) -> Result<Response<Bytes>, AcmeError>
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let (mut sender, connection) = http1::handshake(TokioIo::new(stream)).await?;
+    tokio::spawn(async move {
+        if let Err(err) = connection.await {
+            debug!("ACME connection failed: {err}");
+        }
+    });
+
+    let (parts, body) = sender.send_request(request).await?.into_parts();
+    let body = body.collect().await?.to_bytes();
+    Ok(Response::from_parts(parts, body))
+}
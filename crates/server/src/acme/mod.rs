@@ -0,0 +1,636 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Certificates for the TLS based listeners obtained and renewed with ACME, RFC 8555
+//!
+//! Control over the domains is proven with DNS-01 challenges answered by the server itself, which
+//! must be authoritative for the domains. [`Acme::challenge_authorities`] provides a zone for the
+//! `_acme-challenge` name of each domain, which is registered in the
+//! [`Catalog`](crate::authority::Catalog) next to the zones of the domains.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), hickory_server::acme::AcmeError> {
+//! use std::sync::Arc;
+//!
+//! use hickory_server::acme::{Acme, LETS_ENCRYPT_DIRECTORY};
+//! use hickory_server::authority::Catalog;
+//! use hickory_server::proto::rr::Name;
+//! use hickory_server::server::ReloadableCertResolver;
+//!
+//! let domains = vec![Name::from_ascii("ns.example.com.").unwrap()];
+//! let acme = Acme::builder(LETS_ENCRYPT_DIRECTORY, domains)
+//!     .with_contacts(vec!["mailto:hostmaster@example.com".to_string()])
+//!     .build()?;
+//!
+//! let mut catalog = Catalog::new();
+//! for (name, authority) in acme.challenge_authorities() {
+//!     catalog.upsert(name, vec![authority]);
+//! }
+//!
+//! // a placeholder is presented until the first certificate is issued
+//! let tls_cert = Arc::new(ReloadableCertResolver::new(acme.initial_certificate()?));
+//! tokio::spawn(acme.run(tls_cert.clone()));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use data_encoding::BASE64;
+use rustls::ClientConfig;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, pem::PemObject};
+use rustls::sign::CertifiedKey;
+use thiserror::Error;
+use time::OffsetDateTime;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::authority::{Authority, AxfrPolicy, ZoneType};
+use crate::proto::ProtoError;
+use crate::proto::dnssec::crypto::EcdsaSigningKey;
+use crate::proto::dnssec::{Algorithm, DnsSecError};
+use crate::proto::rr::rdata::{SOA, TXT};
+use crate::proto::rr::{LowerName, Name, RData, Record, RecordType, RrKey};
+use crate::proto::rustls::{client_config, default_provider};
+use crate::server::ReloadableCertResolver;
+use crate::store::in_memory::InMemoryAuthority;
+
+mod certificate;
+mod client;
+use client::{AcmeClient, Status};
+mod http;
+
+/// The directory of the Let's Encrypt production environment
+pub const LETS_ENCRYPT_DIRECTORY: &str = "https://acme-v02.api.letsencrypt.org/directory";
+
+/// The directory of the Let's Encrypt staging environment, for testing without rate limits
+pub const LETS_ENCRYPT_STAGING_DIRECTORY: &str =
+    "https://acme-staging-v02.api.letsencrypt.org/directory";
+
+/// Validation servers must see a new challenge on a retry, not a cached one
+const CHALLENGE_TTL: u32 = 0;
+
+/// Wait after a failed attempt to obtain a certificate
+const RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Validity of the self-signed certificate presented until the first one is issued
+const PLACEHOLDER_VALIDITY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Obtains and renews the certificate for a set of domains from an ACME server
+pub struct Acme {
+    directory_url: String,
+    domains: Vec<Name>,
+    contacts: Vec<String>,
+    renew_before: Duration,
+    client_config: Arc<ClientConfig>,
+    cache: Option<(PathBuf, PathBuf)>,
+    account_key: EcdsaSigningKey,
+    challenges: Vec<Arc<InMemoryAuthority>>,
+}
+
+impl Acme {
+    /// Returns a builder for a client of the ACME server with the directory at `directory_url`,
+    /// ordering certificates for `domains`
+    pub fn builder(directory_url: impl Into<String>, domains: Vec<Name>) -> AcmeBuilder {
+        AcmeBuilder {
+            directory_url: directory_url.into(),
+            domains,
+            contacts: Vec::new(),
+            renew_before: Duration::from_secs(30 * 24 * 60 * 60),
+            client_config: None,
+            cache: None,
+            account_key: None,
+        }
+    }
+
+    /// The zones answering the DNS-01 challenges, to be added to the catalog of the server
+    pub fn challenge_authorities(
+        &self,
+    ) -> impl Iterator<Item = (LowerName, Arc<dyn Authority>)> + '_ {
+        self.challenges.iter().map(|authority| {
+            let authority = authority.clone();
+            (authority.origin().clone(), authority as Arc<dyn Authority>)
+        })
+    }
+
+    /// The certificate to present until the next renewal
+    ///
+    /// This is the cached certificate if there is one, otherwise a self-signed certificate which
+    /// is replaced as soon as [`Self::run`] obtains one.
+    pub fn initial_certificate(&self) -> Result<CertifiedKey, AcmeError> {
+        if let Some((cert_path, key_path)) = &self.cache {
+            if cert_path.exists() && key_path.exists() {
+                info!(
+                    "loading cached ACME certificate from {}",
+                    cert_path.display()
+                );
+                let chain = fs::read(cert_path)?;
+                let key = fs::read(key_path)?;
+                return certified_key(&chain, &key);
+            }
+        }
+
+        info!("using a self-signed certificate until one is obtained with ACME");
+        let key = certificate::generate_key()?;
+        let now = OffsetDateTime::now_utc();
+        let certificate = certificate::self_signed_certificate(
+            &self.domains,
+            &key,
+            now,
+            now + PLACEHOLDER_VALIDITY,
+        )?;
+
+        Ok(CertifiedKey::from_der(
+            vec![certificate],
+            PrivateKeyDer::Pkcs8(certificate::pkcs8(&key)),
+            &default_provider(),
+        )?)
+    }
+
+    /// Renews the certificate presented by `tls_cert` before it expires, forever
+    ///
+    /// Failed attempts are logged and retried after an hour.
+    pub async fn run(self, tls_cert: Arc<ReloadableCertResolver>) {
+        loop {
+            let certified_key = tls_cert.current();
+            let not_after = certified_key
+                .end_entity_cert()
+                .map_err(AcmeError::from)
+                .and_then(|cert| certificate::not_after(cert));
+            let renew_at = match not_after {
+                Ok(not_after) => not_after - self.renew_before,
+                Err(err) => {
+                    warn!("failed to read expiration of the TLS certificate: {err}");
+                    OffsetDateTime::now_utc()
+                }
+            };
+
+            let wait = renew_at - OffsetDateTime::now_utc();
+            if wait.is_positive() {
+                info!("renewing the TLS certificate with ACME at {renew_at}");
+                sleep(wait.unsigned_abs()).await;
+            }
+
+            match self.obtain().await {
+                Ok(certified_key) => tls_cert.replace(certified_key),
+                Err(err) => {
+                    warn!("failed to obtain a TLS certificate with ACME: {err}");
+                    sleep(RETRY_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// Orders a new certificate, writing it to the cache if configured
+    async fn obtain(&self) -> Result<CertifiedKey, AcmeError> {
+        info!("ordering TLS certificate from {}", self.directory_url);
+        let mut client =
+            AcmeClient::new(&self.client_config, &self.account_key, &self.directory_url).await?;
+        client.register(&self.contacts).await?;
+        let (order_url, order) = client.new_order(&self.domains).await?;
+
+        let mut published = Vec::new();
+        let result = async {
+            for url in &order.authorizations {
+                let authorization = client.authorization(url).await?;
+                if authorization.status == Status::Valid {
+                    continue;
+                }
+
+                let challenge = authorization
+                    .challenges
+                    .iter()
+                    .find(|challenge| challenge.kind == "dns-01")
+                    .ok_or_else(|| {
+                        AcmeError::Msg(format!(
+                            "no dns-01 challenge offered for {}",
+                            authorization.identifier.value
+                        ))
+                    })?;
+
+                let name = challenge_name(&Name::from_ascii(&authorization.identifier.value)?)?;
+                let zone = self.challenge_zone(&name)?;
+                let txt = TXT::new(vec![client.dns01_value(&challenge.token)?]);
+                let record = Record::from_rdata(name, CHALLENGE_TTL, RData::TXT(txt));
+                zone.upsert(record.clone(), zone.serial().await).await;
+                published.push((zone, record));
+
+                client.respond(&challenge.url).await?;
+                let authorization = client.poll_authorization(url).await?;
+                if authorization.status != Status::Valid {
+                    return Err(AcmeError::Msg(format!(
+                        "authorization of {} failed: {:?}",
+                        authorization.identifier.value, authorization.status
+                    )));
+                }
+            }
+
+            let order = client.poll_order(&order_url).await?;
+            if order.status != Status::Ready {
+                return Err(AcmeError::Msg(format!("order failed: {:?}", order.status)));
+            }
+
+            let key = certificate::generate_key()?;
+            client
+                .finalize(
+                    &order,
+                    &certificate::certificate_request(&self.domains, &key)?,
+                )
+                .await?;
+
+            let order = client.poll_order(&order_url).await?;
+            let Some(url) = order.certificate.filter(|_| order.status == Status::Valid) else {
+                return Err(AcmeError::Msg(format!("order failed: {:?}", order.status)));
+            };
+
+            Ok((client.certificate(&url).await?, certificate::pkcs8(&key)))
+        }
+        .await;
+
+        for (zone, record) in published {
+            let key = RrKey::new(LowerName::from(record.name()), RecordType::TXT);
+            zone.records_mut().await.remove(&key);
+        }
+
+        let (chain, pkcs8) = result?;
+        let key = pem("PRIVATE KEY", pkcs8.secret_pkcs8_der());
+        let certified_key = certified_key(chain.as_bytes(), key.as_bytes())?;
+        info!("obtained TLS certificate with ACME");
+
+        // the certificate is still presented if it can't be cached
+        if let Some((cert_path, key_path)) = &self.cache {
+            if let Err(err) =
+                fs::write(cert_path, &chain).and_then(|()| write_private_key(key_path, &key))
+            {
+                warn!(
+                    "failed to cache ACME certificate in {}: {err}",
+                    cert_path.display()
+                );
+            }
+        }
+
+        Ok(certified_key)
+    }
+
+    fn challenge_zone(&self, name: &Name) -> Result<Arc<InMemoryAuthority>, AcmeError> {
+        let name = LowerName::from(name);
+        self.challenges
+            .iter()
+            .find(|authority| *authority.origin() == name)
+            .cloned()
+            .ok_or_else(|| AcmeError::Msg(format!("no challenge zone for {name}")))
+    }
+}
+
+/// Builder for [`Acme`]
+///
+/// Created by [`Acme::builder`].
+pub struct AcmeBuilder {
+    directory_url: String,
+    domains: Vec<Name>,
+    contacts: Vec<String>,
+    renew_before: Duration,
+    client_config: Option<Arc<ClientConfig>>,
+    cache: Option<(PathBuf, PathBuf)>,
+    account_key: Option<PathBuf>,
+}
+
+impl AcmeBuilder {
+    /// Sets the contact URLs of the account, e.g. `mailto:hostmaster@example.com`
+    pub fn with_contacts(mut self, contacts: Vec<String>) -> Self {
+        self.contacts = contacts;
+        self
+    }
+
+    /// Sets how long before the expiration the certificate is renewed, 30 days by default
+    pub fn with_renew_before(mut self, renew_before: Duration) -> Self {
+        self.renew_before = renew_before;
+        self
+    }
+
+    /// Sets the TLS configuration for the connections to the ACME server
+    ///
+    /// By default the roots enabled through the `webpki-roots` or `rustls-platform-verifier`
+    /// features of hickory-proto are trusted.
+    pub fn with_client_config(mut self, client_config: Arc<ClientConfig>) -> Self {
+        self.client_config = Some(client_config);
+        self
+    }
+
+    /// Sets the PEM files the certificate chain and private key are stored in, so that they are
+    /// reused after a restart instead of ordering a new certificate
+    pub fn with_cache(mut self, cert_path: PathBuf, key_path: PathBuf) -> Self {
+        self.cache = Some((cert_path, key_path));
+        self
+    }
+
+    /// Sets the PEM file the PKCS#8 key of the account is stored in, so that the account is reused
+    /// after a restart instead of registering a new one
+    ///
+    /// The key is generated and written to the file if it doesn't exist yet.
+    pub fn with_account_key(mut self, path: PathBuf) -> Self {
+        self.account_key = Some(path);
+        self
+    }
+
+    /// Loads or generates the account key and creates the challenge zones
+    ///
+    /// The directory must be an `https://` URL, plain HTTP is only allowed with the `testing`
+    /// feature, for local test servers.
+    pub fn build(self) -> Result<Acme, AcmeError> {
+        if self.domains.is_empty() {
+            return Err(AcmeError::Msg(
+                "no domains to order a certificate for".to_string(),
+            ));
+        }
+
+        if !self.directory_url.starts_with("https://") && !cfg!(feature = "testing") {
+            return Err(AcmeError::Msg(format!(
+                "ACME directory is not an https URL: {}",
+                self.directory_url
+            )));
+        }
+
+        let mut challenges = BTreeMap::new();
+        for domain in &self.domains {
+            let name = challenge_name(domain)?;
+            challenges
+                .entry(LowerName::from(&name))
+                .or_insert(challenge_authority(name)?);
+        }
+
+        Ok(Acme {
+            directory_url: self.directory_url,
+            domains: self.domains,
+            contacts: self.contacts,
+            renew_before: self.renew_before,
+            client_config: self
+                .client_config
+                .unwrap_or_else(|| Arc::new(client_config())),
+            cache: self.cache,
+            account_key: account_key(self.account_key.as_deref())?,
+            challenges: challenges.into_values().map(Arc::new).collect(),
+        })
+    }
+}
+
+/// The name of the TXT record for the DNS-01 challenge of `domain`, shared by a wildcard domain
+/// and its base domain
+fn challenge_name(domain: &Name) -> Result<Name, AcmeError> {
+    let domain = match domain.is_wildcard() {
+        true => domain.base_name(),
+        false => domain.clone(),
+    };
+
+    let mut name = Name::from_ascii("_acme-challenge")?.append_domain(&domain)?;
+    name.set_fqdn(true);
+    Ok(name)
+}
+
+/// An empty zone at `origin`, for the TXT records of the challenges
+fn challenge_authority(origin: Name) -> Result<InMemoryAuthority, AcmeError> {
+    let mut authority = InMemoryAuthority::empty(
+        origin.clone(),
+        ZoneType::Primary,
+        AxfrPolicy::Deny,
+        #[cfg(feature = "__dnssec")]
+        None,
+    );
+
+    let soa = SOA::new(
+        origin.clone(),
+        origin.clone(),
+        1,
+        3600,
+        600,
+        86400,
+        CHALLENGE_TTL,
+    );
+    if !authority.upsert_mut(
+        Record::from_rdata(origin, CHALLENGE_TTL, RData::SOA(soa)),
+        1,
+    ) {
+        return Err(AcmeError::Msg(
+            "failed to create challenge zone".to_string(),
+        ));
+    }
+
+    Ok(authority)
+}
+
+/// Reads the account key from `path`, or generates it and writes it to `path`
+fn account_key(path: Option<&Path>) -> Result<EcdsaSigningKey, AcmeError> {
+    let pkcs8 = match path {
+        Some(path) if path.exists() => PrivatePkcs8KeyDer::from_pem_file(path).map_err(|err| {
+            AcmeError::Msg(format!(
+                "failed to read ACME account key from {}: {err}",
+                path.display()
+            ))
+        })?,
+        _ => {
+            let pkcs8 = EcdsaSigningKey::generate_pkcs8(Algorithm::ECDSAP256SHA256)?;
+            if let Some(path) = path {
+                info!("writing new ACME account key to {}", path.display());
+                write_private_key(path, &pem("PRIVATE KEY", pkcs8.secret_pkcs8_der()))?;
+            }
+            pkcs8
+        }
+    };
+
+    Ok(EcdsaSigningKey::from_pkcs8(
+        &pkcs8,
+        Algorithm::ECDSAP256SHA256,
+    )?)
+}
+
+/// Writes a PEM encoded private key, readable only by the owner on Unix
+fn write_private_key(path: &Path, pem: &str) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(path)?.write_all(pem.as_bytes())
+}
+
+fn certified_key(chain: &[u8], key: &[u8]) -> Result<CertifiedKey, AcmeError> {
+    let chain = CertificateDer::pem_slice_iter(chain)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| AcmeError::Msg(format!("failed to parse certificate chain: {err}")))?;
+    let key = PrivateKeyDer::from_pem_slice(key)
+        .map_err(|err| AcmeError::Msg(format!("failed to parse private key: {err}")))?;
+
+    Ok(CertifiedKey::from_der(chain, key, &default_provider())?)
+}
+
+fn pem(label: &str, der: &[u8]) -> String {
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in BASE64.encode(der).as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {label}-----\n"));
+    pem
+}
+
+/// Errors while obtaining a certificate with ACME
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum AcmeError {
+    /// An error with an arbitrary message
+    #[error("{0}")]
+    Msg(String),
+
+    /// The ACME server rejected a request, RFC 8555, section 6.7
+    #[error("ACME server returned {status} {kind}: {detail}")]
+    Problem {
+        /// The HTTP status code
+        status: u16,
+        /// The type of the problem, e.g. `urn:ietf:params:acme:error:rejectedIdentifier`
+        kind: String,
+        /// A description of the problem
+        detail: String,
+    },
+
+    // foreign
+    /// An error generating a key, certificate or certificate signing request
+    #[error("certificate error: {0}")]
+    Certificate(#[from] rcgen::Error),
+
+    /// An error while signing or hashing
+    #[error("dnssec error: {0}")]
+    DnsSec(#[from] DnsSecError),
+
+    /// An error building an HTTP request
+    #[error("http error: {0}")]
+    Http(#[from] ::http::Error),
+
+    /// An error on the HTTP connection
+    #[error("hyper error: {0}")]
+    Hyper(#[from] hyper::Error),
+
+    /// An error reading the cached certificate, or on a connection
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    /// An error decoding a response of the ACME server
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// An error got returned by the hickory-proto crate
+    #[error("proto error: {0}")]
+    Proto(#[from] ProtoError),
+
+    /// An error loading the certificate
+    #[error("tls error: {0}")]
+    Tls(#[from] rustls::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::dnssec::{PublicKey, SigningKey};
+
+    #[test]
+    fn test_challenge_name() {
+        let name = |name: &str| Name::from_ascii(name).unwrap();
+
+        assert_eq!(
+            challenge_name(&name("ns.example.com.")).unwrap(),
+            name("_acme-challenge.ns.example.com.")
+        );
+        assert_eq!(
+            challenge_name(&name("*.example.com.")).unwrap(),
+            name("_acme-challenge.example.com.")
+        );
+    }
+
+    #[test]
+    fn test_challenge_authorities() {
+        let domains = ["example.com.", "*.example.com.", "ns.example.com."]
+            .iter()
+            .map(|name| Name::from_ascii(name).unwrap())
+            .collect();
+        let acme = Acme::builder(LETS_ENCRYPT_STAGING_DIRECTORY, domains)
+            .build()
+            .unwrap();
+
+        let zones = acme
+            .challenge_authorities()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            zones,
+            [
+                "_acme-challenge.example.com.",
+                "_acme-challenge.ns.example.com."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_initial_certificate() {
+        let domains = vec![Name::from_ascii("ns.example.com.").unwrap()];
+        let acme = Acme::builder(LETS_ENCRYPT_STAGING_DIRECTORY, domains)
+            .build()
+            .unwrap();
+
+        let certified_key = acme.initial_certificate().unwrap();
+        let not_after = certificate::not_after(certified_key.end_entity_cert().unwrap()).unwrap();
+        assert!(not_after > OffsetDateTime::now_utc());
+    }
+
+    #[test]
+    #[cfg(not(feature = "testing"))]
+    fn test_http_directory() {
+        let domains = vec![Name::from_ascii("ns.example.com.").unwrap()];
+        assert!(
+            Acme::builder("http://localhost:14000/dir", domains)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_account_key_is_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("account.pem");
+        let domains = vec![Name::from_ascii("ns.example.com.").unwrap()];
+        let build = || {
+            Acme::builder(LETS_ENCRYPT_STAGING_DIRECTORY, domains.clone())
+                .with_account_key(path.clone())
+                .build()
+                .unwrap()
+        };
+
+        let first = build().account_key.to_public_key().unwrap();
+        let second = build().account_key.to_public_key().unwrap();
+        assert_eq!(first.public_bytes(), second.public_bytes());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_pem_round_trip() {
+        let pkcs8 = EcdsaSigningKey::generate_pkcs8(Algorithm::ECDSAP256SHA256).unwrap();
+        let pem = pem("PRIVATE KEY", pkcs8.secret_pkcs8_der());
+
+        let decoded = PrivatePkcs8KeyDer::from_pem_slice(pem.as_bytes()).unwrap();
+        assert_eq!(decoded.secret_pkcs8_der(), pkcs8.secret_pkcs8_der());
+    }
+}
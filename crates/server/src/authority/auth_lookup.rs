@@ -15,10 +15,11 @@ use crate::proto::rr::{LowerName, Record, RecordSet, RecordType, RrsetRecords};
 use crate::resolver::lookup::{Lookup, LookupRecordIter};
 
 /// The result of a lookup on an Authority
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[non_exhaustive]
 pub enum AuthLookup {
     /// No records
+    #[default]
     Empty,
     // TODO: change the result of a lookup to a set of chained iterators...
     /// Records
@@ -33,6 +34,13 @@ pub enum AuthLookup {
     Resolved(Lookup),
     /// Soa only differs from Records in that the lifetime on the name is from the authority, and not the query
     SOA(LookupRecords),
+    /// A referral to the name servers of a zone delegated from the authority
+    Referral {
+        /// The NS records of the delegation point, followed by its DS records if requested
+        ns: LookupRecords,
+        /// The addresses of the name servers within the zone of the authority
+        glue: Option<LookupRecords>,
+    },
     /// An axfr starts with soa, chained to all the records, then another soa...
     AXFR {
         /// The first SOA record in an AXFR response
@@ -96,12 +104,6 @@ impl From<Lookup> for AuthLookup {
     }
 }
 
-impl Default for AuthLookup {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 impl<'a> IntoIterator for &'a AuthLookup {
     type Item = &'a Record;
     type IntoIter = AuthLookupIter<'a>;
@@ -110,9 +112,9 @@ impl<'a> IntoIterator for &'a AuthLookup {
         match self {
             AuthLookup::Empty => AuthLookupIter::Empty,
             // TODO: what about the additionals? is IntoIterator a bad idea?
-            AuthLookup::Records { answers: r, .. } | AuthLookup::SOA(r) => {
-                AuthLookupIter::Records(r.into_iter())
-            }
+            AuthLookup::Records { answers: r, .. }
+            | AuthLookup::SOA(r)
+            | AuthLookup::Referral { ns: r, .. } => AuthLookupIter::Records(r.into_iter()),
             #[cfg(feature = "resolver")]
             AuthLookup::Resolved(lookup) => AuthLookupIter::Resolved(lookup.record_iter()),
             AuthLookup::AXFR {
@@ -267,9 +269,10 @@ impl<'r> Iterator for AnyRecordsIter<'r> {
 }
 
 /// The result of a lookup
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum LookupRecords {
     /// The empty set of records
+    #[default]
     Empty,
     /// The associate records
     Records {
@@ -314,12 +317,6 @@ impl LookupRecords {
     }
 }
 
-impl Default for LookupRecords {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 impl<'a> IntoIterator for &'a LookupRecords {
     type Item = &'a Record;
     type IntoIter = LookupRecordsIter<'a>;
@@ -8,14 +8,16 @@
 //! All authority related types
 
 use cfg_if::cfg_if;
+use ipnet::IpNet;
 use serde::Deserialize;
 use std::fmt;
+use time::OffsetDateTime;
 
 use crate::{
     authority::{AuthLookup, LookupError, UpdateResult, ZoneType},
     proto::{
         op::message::ResponseSigner,
-        rr::{LowerName, RecordSet, RecordType, RrsetRecords},
+        rr::{LowerName, RecordSet, RecordType, RrsetRecords, SerialNumber},
     },
     server::Request,
 };
@@ -85,6 +87,18 @@ pub trait Authority: Send + Sync {
         false
     }
 
+    /// Which optional records are added to responses from this zone
+    fn minimal_responses(&self) -> MinimalResponses {
+        MinimalResponses::No
+    }
+
+    /// Networks of the secondaries which must transfer this zone over TLS (XoT, RFC 9103)
+    ///
+    /// Zone transfers requested by these secondaries over any other protocol are refused.
+    fn xot_required(&self) -> &[IpNet] {
+        &[]
+    }
+
     /// Perform a dynamic update of a zone
     async fn update(
         &self,
@@ -258,6 +272,106 @@ pub enum AxfrPolicy {
     AllowSigned,
 }
 
+/// MinimalResponses describes which optional records are added to responses
+///
+/// The records required by a query are always returned: the answer, the SOA record of negative
+/// responses and the DNSSEC records proving either. By default, all optional records are added.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum MinimalResponses {
+    /// Add the NS records of the zone to answers for its SOA record, and the records of the
+    /// targets of e.g. MX, NS and SRV records to the additional section.
+    #[default]
+    No,
+    /// Leave the NS records out of the authority section of positive answers.
+    NoAuth,
+    /// Leave the NS records out of positive answers, as well as the additional records, unless
+    /// the answer is an alias and they continue its chain.
+    Yes,
+}
+
+impl MinimalResponses {
+    /// Returns true if the NS records of the zone are left out of positive answers
+    pub fn omits_authority(self) -> bool {
+        matches!(self, Self::NoAuth | Self::Yes)
+    }
+
+    /// Returns true if additional records are left out, unless they continue an alias chain
+    pub fn omits_additionals(self) -> bool {
+        matches!(self, Self::Yes)
+    }
+}
+
+/// SerialPolicy describes how the SOA serial of a zone is advanced after it changed, i.e. after
+/// dynamic updates and when the zone is re-signed
+///
+/// The new serial is always greater than the previous one in serial number arithmetic
+/// ([RFC 1982]): if the policy would not advance it, e.g. after several changes within the same
+/// second, the previous serial is incremented by one instead.
+///
+/// [RFC 1982]: https://www.rfc-editor.org/rfc/rfc1982
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum SerialPolicy {
+    /// Increment the serial by one
+    #[default]
+    Increment,
+    /// Set the serial to the current time, in seconds since the Unix epoch
+    UnixTime,
+    /// Set the serial to the current date in the `YYYYMMDDnn` format, where `nn` counts the
+    /// changes on that day
+    Date,
+}
+
+impl SerialPolicy {
+    /// Returns the serial following `serial`, for a change of the zone at `now`
+    pub fn next(self, serial: u32, now: OffsetDateTime) -> u32 {
+        let candidate = match self {
+            Self::Increment => serial.wrapping_add(1),
+            Self::UnixTime => now.unix_timestamp() as u32,
+            Self::Date => {
+                let date = now.date();
+                (date.year() as u32 * 10_000
+                    + u32::from(u8::from(date.month())) * 100
+                    + u32::from(date.day()))
+                    * 100
+            }
+        };
+
+        match SerialNumber::from(candidate) > SerialNumber::from(serial) {
+            true => candidate,
+            false => serial.wrapping_add(1),
+        }
+    }
+}
+
+/// TtlPolicy bounds the TTLs of the records served from a zone, or forwarded from its upstreams
+///
+/// TTLs under the minimum, including zero TTLs, are raised to it, and TTLs over the maximum are
+/// capped to it. The maximum wins if it is lower than the minimum. By default, TTLs are served
+/// unchanged.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TtlPolicy {
+    /// The minimum TTL in seconds
+    #[serde(default)]
+    pub min_ttl: Option<u32>,
+    /// The maximum TTL in seconds
+    #[serde(default)]
+    pub max_ttl: Option<u32>,
+}
+
+impl TtlPolicy {
+    /// Returns `ttl` within the bounds of the policy
+    pub fn apply(self, ttl: u32) -> u32 {
+        let ttl = self.min_ttl.map_or(ttl, |min_ttl| ttl.max(min_ttl));
+        self.max_ttl.map_or(ttl, |max_ttl| ttl.min(max_ttl))
+    }
+
+    /// Returns true if the policy leaves all TTLs unchanged
+    pub fn is_unbounded(self) -> bool {
+        self.min_ttl.is_none() && self.max_ttl.is_none()
+    }
+}
+
 /// Result of a Lookup in the Catalog and Authority
 ///
 /// * **All authorities should default to using LookupControlFlow::Continue to wrap their responses.**
@@ -446,3 +560,52 @@ impl Nsec3QueryInfo<'_> {
         Ok(LowerName::new(&zone.prepend_label(label)?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serial_policy() {
+        // 2025-03-07 12:00 UTC
+        let now = OffsetDateTime::from_unix_timestamp(1_741_348_800).unwrap();
+
+        assert_eq!(SerialPolicy::Increment.next(41, now), 42);
+        assert_eq!(SerialPolicy::Increment.next(u32::MAX, now), 0);
+
+        assert_eq!(SerialPolicy::UnixTime.next(1, now), 1_741_348_800);
+        // several changes within the same second
+        assert_eq!(
+            SerialPolicy::UnixTime.next(1_741_348_800, now),
+            1_741_348_801
+        );
+
+        assert_eq!(SerialPolicy::Date.next(2025030199, now), 2025030700);
+        assert_eq!(SerialPolicy::Date.next(2025030700, now), 2025030701);
+        // the serial never goes backwards, e.g. after switching from the UnixTime policy
+        assert_eq!(SerialPolicy::Date.next(2025030800, now), 2025030801);
+    }
+
+    #[test]
+    fn test_ttl_policy() {
+        let policy = TtlPolicy::default();
+        assert!(policy.is_unbounded());
+        assert_eq!(policy.apply(0), 0);
+        assert_eq!(policy.apply(u32::MAX), u32::MAX);
+
+        let policy = TtlPolicy {
+            min_ttl: Some(30),
+            max_ttl: Some(86_400),
+        };
+        assert_eq!(policy.apply(0), 30);
+        assert_eq!(policy.apply(300), 300);
+        assert_eq!(policy.apply(604_800), 86_400);
+
+        // the maximum wins over a greater minimum
+        let policy = TtlPolicy {
+            min_ttl: Some(600),
+            max_ttl: Some(60),
+        };
+        assert_eq!(policy.apply(0), 60);
+    }
+}
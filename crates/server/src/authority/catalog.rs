@@ -9,24 +9,33 @@
 //  should be the only "front-end" for lookups, where if that misses, then we go to the catalog
 //  then, if requested, do a recursive lookup... i.e. the catalog would only point to files.
 use std::{borrow::Borrow, collections::HashMap, io, sync::Arc};
+#[cfg(feature = "metrics")]
+use std::{sync::Once, time::Instant};
 
 use cfg_if::cfg_if;
-use tracing::{debug, error, info, trace, warn};
+#[cfg(feature = "metrics")]
+use metrics::{Unit, describe_histogram, histogram};
+use tracing::{Instrument, Span, debug, debug_span, error, field, info, trace, warn};
 
+#[cfg(feature = "metrics")]
+use crate::server::metrics::response_code_label;
 #[cfg(feature = "__dnssec")]
 use crate::{authority::Nsec3QueryInfo, dnssec::NxProofKind};
 use crate::{
     authority::{
         AuthLookup, Authority, DnssecSummary, LookupControlFlow, LookupError, LookupOptions,
-        LookupRecords, MessageResponseBuilder, ZoneType,
+        LookupRecords, MessageResponseBuilder, QueryStatistics, ZoneType,
     },
     proto::{
         op::{Edns, Header, LowerQuery, MessageType, OpCode, ResponseCode},
         rr::{
             LowerName, RecordSet, RecordType,
-            rdata::opt::{EdnsCode, EdnsOption, NSIDPayload},
+            rdata::opt::{
+                EdnsCode, EdnsOption, ExtendedDnsError, ExtendedDnsErrorCode, NSIDPayload,
+            },
         },
         serialize::binary::{BinEncoder, EncodeMode},
+        xfer::Protocol,
     },
     server::{Request, RequestHandler, RequestInfo, ResponseHandler, ResponseInfo},
 };
@@ -37,6 +46,7 @@ use crate::{proto::ProtoErrorKind, recursor::ErrorKind};
 #[derive(Default)]
 pub struct Catalog {
     nsid_payload: Option<NSIDPayload>,
+    statistics: Option<Arc<QueryStatistics>>,
     authorities: HashMap<LowerName, Vec<Arc<dyn Authority>>>,
 }
 
@@ -59,14 +69,14 @@ impl RequestHandler for Catalog {
 
         // check if it's edns
         if let Some(req_edns) = request.edns() {
-            let mut resp_edns: Edns = Edns::new();
-
             // check our version against the request
             // TODO: what version are we?
             let our_version = 0;
-            resp_edns.set_dnssec_ok(true);
-            resp_edns.set_max_payload(req_edns.max_payload().max(512));
-            resp_edns.set_version(our_version);
+            let mut resp_edns = Edns::builder()
+                .dnssec_ok(true)
+                .max_payload(req_edns.max_payload())
+                .version(our_version)
+                .build();
 
             if req_edns.version() > our_version {
                 warn!(
@@ -169,6 +179,7 @@ impl Catalog {
         Self {
             authorities: HashMap::new(),
             nsid_payload: None,
+            statistics: None,
         }
     }
 
@@ -204,6 +215,23 @@ impl Catalog {
         self.nsid_payload.as_ref()
     }
 
+    /// Collect per-zone query statistics for queries answered by this catalog
+    ///
+    /// The statistics are shared so that they can be reported elsewhere, e.g. by a management
+    /// endpoint. Set to `None` to disable collecting statistics.
+    ///
+    /// By default, no statistics are collected.
+    pub fn set_statistics(&mut self, statistics: Option<Arc<QueryStatistics>>) {
+        self.statistics = statistics
+    }
+
+    /// Return the query statistics that are collected (if enabled)
+    ///
+    /// See `set_statistics()` for more information.
+    pub fn statistics(&self) -> Option<&Arc<QueryStatistics>> {
+        self.statistics.as_ref()
+    }
+
     /// Update the zone given the Update request.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
@@ -279,6 +307,7 @@ impl Catalog {
         if let Some(authorities) = self.find(request_info.query.name()) {
             #[allow(clippy::never_loop)]
             for authority in authorities {
+                Span::current().record("zone", field::display(authority.origin()));
                 let (response_code, signer) = match authority.zone_type() {
                     ZoneType::Secondary => {
                         error!("secondary forwarding for update not yet implemented");
@@ -303,17 +332,20 @@ impl Catalog {
                 let mut response = response.build_no_records(response_header);
 
                 if let Some(signer) = signer {
-                    let mut tbs_response_buf = Vec::with_capacity(512);
-                    let mut encoder =
-                        BinEncoder::with_mode(&mut tbs_response_buf, EncodeMode::Normal);
-                    let mut response_header =
-                        Header::new(update.id(), MessageType::Response, OpCode::Update);
-                    response_header.set_response_code(response_code);
-                    let tbs_response =
-                        MessageResponseBuilder::new(update.raw_queries(), response_edns)
-                            .build_no_records(response_header);
-                    tbs_response.destructive_emit(&mut encoder)?;
-                    response.set_signature(signer.sign(&tbs_response_buf)?);
+                    let signature = debug_span!("sign").in_scope(|| {
+                        let mut tbs_response_buf = Vec::with_capacity(512);
+                        let mut encoder =
+                            BinEncoder::with_mode(&mut tbs_response_buf, EncodeMode::Normal);
+                        let mut response_header =
+                            Header::new(update.id(), MessageType::Response, OpCode::Update);
+                        response_header.set_response_code(response_code);
+                        let tbs_response =
+                            MessageResponseBuilder::new(update.raw_queries(), response_edns)
+                                .build_no_records(response_header);
+                        tbs_response.destructive_emit(&mut encoder)?;
+                        signer.sign(&tbs_response_buf)
+                    })?;
+                    response.set_signature(signature);
                 }
 
                 return response_handle.send_response(response).await;
@@ -376,6 +408,8 @@ impl Catalog {
             }
         };
 
+        #[cfg(feature = "metrics")]
+        let start = Instant::now();
         let result = lookup(
             request_info.clone(),
             authorities,
@@ -387,14 +421,32 @@ impl Catalog {
         )
         .await;
 
-        match result {
+        let info = match result {
             Ok(lookup) => lookup,
             Err(_e) => ResponseInfo::serve_failed(request),
+        };
+
+        // failed lookups are answered with SERVFAIL, and are recorded as such
+        #[cfg(feature = "metrics")]
+        if let Some(authority) = authorities.first() {
+            record_request_duration(authority.origin(), info.response_code(), start);
+        }
+
+        if let (Some(statistics), Some(authority)) = (&self.statistics, authorities.first()) {
+            statistics.record(
+                authority.origin(),
+                request_info.query.name(),
+                request_info.query.query_type(),
+                info.response_code(),
+                request.src().ip(),
+            );
         }
+
+        info
     }
 
     /// Recursively searches the catalog for a matching authority
-    pub fn find(&self, name: &LowerName) -> Option<&Vec<Arc<(dyn Authority + 'static)>>> {
+    pub fn find(&self, name: &LowerName) -> Option<&Vec<Arc<dyn Authority + 'static>>> {
         debug!("searching authorities for: {name}");
         self.authorities.get(name).or_else(|| {
             if !name.is_root() {
@@ -431,9 +483,34 @@ async fn lookup<R: ResponseHandler + Unpin>(
             origin = authority.origin(),
         );
 
+        // RFC 9103, secondaries which must use XoT are refused transfers over other protocols
+        if matches!(query.query_type(), RecordType::AXFR | RecordType::IXFR)
+            && !is_tls(request.protocol())
+            && authority
+                .xot_required()
+                .iter()
+                .any(|network| network.contains(&request.src().ip()))
+        {
+            warn!(
+                src = %request.src(),
+                protocol = %request.protocol(),
+                "refusing zone transfer of {origin} outside of TLS",
+                origin = authority.origin(),
+            );
+            let response = MessageResponseBuilder::new(request.raw_queries(), response_edns)
+                .error_msg(request.header(), ResponseCode::Refused);
+            return response_handle
+                .send_response(response)
+                .await
+                .map_err(LookupError::Io);
+        }
+
         // Wait so we can determine if we need to fire a request to the next authority in a chained
         // configuration if the current authority declines to answer.
-        let (mut result, mut signer) = authority.search(request, lookup_options).await;
+        let (mut result, mut signer) = authority
+            .search(request, lookup_options)
+            .instrument(debug_span!("authority_lookup", zone = %authority.origin()))
+            .await;
 
         if let LookupControlFlow::Skip = result {
             trace!("catalog::lookup::authority did not handle request");
@@ -467,6 +544,7 @@ async fn lookup<R: ResponseHandler + Unpin>(
         } else {
             trace!("catalog::lookup::authority did handle request with break");
         }
+        Span::current().record("zone", field::display(authority.origin()));
 
         // We no longer need the context from LookupControlFlow, so decompose into a standard Result
         // to clean up the rest of the match conditions
@@ -475,6 +553,27 @@ async fn lookup<R: ResponseHandler + Unpin>(
             return Err(LookupError::ResponseCode(ResponseCode::ServFail));
         };
 
+        // RFC 8914 sections 4.23 and 4.24, report failures to reach the authoritative servers, and
+        // lookups abandoned at the request deadline
+        let mut response_edns = response_edns;
+        if let (Err(e), Some(response_edns)) = (&result, &mut response_edns) {
+            let info_code = if e.is_no_reachable_authority() {
+                Some(ExtendedDnsErrorCode::NoReachableAuthority)
+            } else if e.is_timeout() {
+                Some(ExtendedDnsErrorCode::NetworkError)
+            } else {
+                None
+            };
+
+            if let Some(info_code) = info_code {
+                response_edns
+                    .options_mut()
+                    .insert(EdnsOption::ExtendedDnsError(ExtendedDnsError::new(
+                        info_code,
+                    )));
+            }
+        }
+
         let (response_header, sections) = build_response(
             result,
             &**authority,
@@ -495,18 +594,21 @@ async fn lookup<R: ResponseHandler + Unpin>(
             );
 
         if let Some(signer) = signer {
-            let mut tbs_response_buf = Vec::with_capacity(512);
-            let mut encoder = BinEncoder::with_mode(&mut tbs_response_buf, EncodeMode::Normal);
-            let tbs_response = MessageResponseBuilder::new(request.raw_queries(), response_edns)
-                .build(
-                    response_header,
-                    sections.answers.iter(),
-                    sections.ns.iter(),
-                    sections.soa.iter(),
-                    sections.additionals.iter(),
-                );
-            tbs_response.destructive_emit(&mut encoder)?;
-            message_response.set_signature(signer.sign(&tbs_response_buf)?);
+            let signature = debug_span!("sign").in_scope(|| {
+                let mut tbs_response_buf = Vec::with_capacity(512);
+                let mut encoder = BinEncoder::with_mode(&mut tbs_response_buf, EncodeMode::Normal);
+                let tbs_response =
+                    MessageResponseBuilder::new(request.raw_queries(), response_edns).build(
+                        response_header,
+                        sections.answers.iter(),
+                        sections.ns.iter(),
+                        sections.soa.iter(),
+                        sections.additionals.iter(),
+                    );
+                tbs_response.destructive_emit(&mut encoder)?;
+                signer.sign(&tbs_response_buf)
+            })?;
+            message_response.set_signature(signature);
         }
 
         match response_handle.send_response(message_response).await {
@@ -522,6 +624,42 @@ async fn lookup<R: ResponseHandler + Unpin>(
     Err(LookupError::ResponseCode(ResponseCode::ServFail))
 }
 
+/// Whether requests received over `protocol` were protected by TLS, as required by XoT
+#[cfg_attr(
+    not(any(feature = "__tls", feature = "native-tls")),
+    allow(unused_variables)
+)]
+fn is_tls(protocol: Protocol) -> bool {
+    cfg_if! {
+        if #[cfg(any(feature = "__tls", feature = "native-tls"))] {
+            protocol == Protocol::Tls
+        } else {
+            false
+        }
+    }
+}
+
+/// Records how long it took to answer a request from `zone`
+#[cfg(feature = "metrics")]
+fn record_request_duration(zone: &LowerName, response_code: ResponseCode, start: Instant) {
+    static DESCRIBE: Once = Once::new();
+    let request_duration_name = "hickory_request_duration_seconds";
+    DESCRIBE.call_once(|| {
+        describe_histogram!(
+            request_duration_name,
+            Unit::Seconds,
+            "duration of dns requests answered from a zone"
+        );
+    });
+
+    histogram!(
+        request_duration_name,
+        "zone" => zone.to_string(),
+        "code" => response_code_label(response_code),
+    )
+    .record(start.elapsed());
+}
+
 #[cfg_attr(not(feature = "__dnssec"), allow(unused_variables))]
 fn lookup_options_for_edns(edns: Option<&Edns>) -> LookupOptions {
     let edns = match edns {
@@ -595,6 +733,17 @@ async fn build_authoritative_response(
     // On Errors, the transition depends on the type of error.
 
     let answers = match response {
+        // the delegated zone is authoritative for the name, not this one
+        Ok(AuthLookup::Referral { ns, glue }) => {
+            response_header.set_response_code(ResponseCode::NoError);
+            response_header.set_authoritative(false);
+            return LookupSections {
+                answers: AuthLookup::default(),
+                ns: AuthLookup::from(ns),
+                soa: AuthLookup::default(),
+                additionals: glue.map(AuthLookup::from).unwrap_or_default(),
+            };
+        }
         Ok(records) => {
             response_header.set_response_code(ResponseCode::NoError);
             response_header.set_authoritative(true);
@@ -621,9 +770,10 @@ async fn build_authoritative_response(
         }
     };
 
-    let (ns, soa) = if answers.is_some() {
+    let minimal_responses = authority.minimal_responses();
+    let (ns, soa) = if let Some(answers) = &answers {
         // SOA queries should return the NS records as well.
-        if query.query_type().is_soa() {
+        if query.query_type().is_soa() && !minimal_responses.omits_authority() {
             // This was a successful authoritative lookup for SOA:
             //   get the NS records as well.
 
@@ -641,34 +791,36 @@ async fn build_authoritative_response(
         } else {
             #[cfg(feature = "__dnssec")]
             {
-                if let Some(NxProofKind::Nsec3 {
-                    algorithm,
-                    salt,
-                    iterations,
-                    opt_out: _,
-                }) = authority.nx_proof_kind()
-                {
-                    // This unwrap will not panic as we know that `answers` is `Some`.
-                    let has_wildcard_match =
-                        answers.as_ref().unwrap().iter().any(|rr| {
-                            rr.record_type() == RecordType::RRSIG && rr.name().is_wildcard()
-                        });
-
-                    match authority
-                        .get_nsec3_records(
-                            Nsec3QueryInfo {
-                                qname: query.name(),
-                                qtype: query.query_type(),
-                                has_wildcard_match,
-                                algorithm: *algorithm,
-                                salt,
-                                iterations: *iterations,
-                            },
-                            lookup_options,
-                        )
-                        .await
-                        .map_result()
-                    {
+                let has_wildcard_match = answers
+                    .iter()
+                    .any(|rr| rr.record_type() == RecordType::RRSIG && rr.name().is_wildcard());
+
+                let future = match authority.nx_proof_kind() {
+                    Some(NxProofKind::Nsec3 {
+                        algorithm,
+                        salt,
+                        iterations,
+                        opt_out: _,
+                    }) => Some(authority.get_nsec3_records(
+                        Nsec3QueryInfo {
+                            qname: query.name(),
+                            qtype: query.query_type(),
+                            has_wildcard_match,
+                            algorithm: *algorithm,
+                            salt,
+                            iterations: *iterations,
+                        },
+                        lookup_options,
+                    )),
+                    // a wildcard expansion has to be proven by the NSEC record covering the name
+                    Some(NxProofKind::Nsec) if has_wildcard_match => {
+                        Some(authority.get_nsec_records(query.name(), lookup_options))
+                    }
+                    _ => None,
+                };
+
+                if let Some(future) = future {
+                    match future.await.map_result() {
                         // run the soa lookup
                         Some(Ok(nsecs)) => (Some(nsecs), None),
                         Some(Err(error)) => {
@@ -688,7 +840,10 @@ async fn build_authoritative_response(
                 }
             }
             #[cfg(not(feature = "__dnssec"))]
-            (None, None)
+            {
+                let _ = answers;
+                (None, None)
+            }
         }
     } else {
         let nsecs = if lookup_options.dnssec_ok() {
@@ -762,6 +917,15 @@ async fn build_authoritative_response(
     // everything is done, return results.
     let (answers, additionals) = match answers {
         Some(mut answers) => match answers.take_additionals() {
+            // if the answer is an alias, the additional records continue its chain
+            Some(_)
+                if minimal_responses.omits_additionals()
+                    && !answers
+                        .iter()
+                        .any(|record| record.record_type() == RecordType::CNAME) =>
+            {
+                (answers, AuthLookup::default())
+            }
             Some(additionals) => (
                 answers,
                 AuthLookup::Records {
@@ -897,6 +1061,13 @@ async fn build_forwarded_response(
                 (Answer::Normal(AuthLookup::default()), AuthLookup::default())
             }
         },
+        // An external authority answering with a response code of its own choosing, e.g. an
+        // intercept rule answering REFUSED, gets that code passed through to the client. Other
+        // errors, like those of upstream resolution, are reported as SERVFAIL.
+        Err(LookupError::ResponseCode(response_code)) => {
+            response_header.set_response_code(response_code);
+            (Answer::Normal(AuthLookup::default()), AuthLookup::default())
+        }
         Err(e) => {
             response_header.set_response_code(ResponseCode::ServFail);
             debug!(error = ?e, "error resolving");
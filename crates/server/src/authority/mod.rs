@@ -26,16 +26,21 @@ mod authority;
 mod catalog;
 pub(crate) mod message_request;
 mod message_response;
+mod statistics;
 
 pub use self::auth_lookup::{
     AnyRecords, AuthLookup, AuthLookupIter, LookupRecords, LookupRecordsIter,
 };
-pub use self::authority::{Authority, AxfrPolicy, LookupControlFlow, LookupOptions};
+pub use self::authority::{
+    Authority, AxfrPolicy, LookupControlFlow, LookupOptions, MinimalResponses, SerialPolicy,
+    TtlPolicy,
+};
 #[cfg(feature = "__dnssec")]
 pub use self::authority::{DnssecAuthority, Nsec3QueryInfo};
 pub use self::catalog::Catalog;
 pub use self::message_request::{MessageRequest, Queries, UpdateRequest};
 pub use self::message_response::{MessageResponse, MessageResponseBuilder};
+pub use self::statistics::{QueryStatistics, ZoneReport};
 
 /// Result of an Update operation
 pub type UpdateResult<T> = Result<T, ResponseCode>;
@@ -60,6 +65,9 @@ pub enum LookupError {
     /// An underlying IO error occurred
     #[error("io error: {0}")]
     Io(io::Error),
+    /// The lookup was abandoned after exceeding the request deadline
+    #[error("lookup timed out")]
+    Timeout,
 }
 
 impl LookupError {
@@ -89,6 +97,16 @@ impl LookupError {
         }
     }
 
+    /// Returns true if none of the name servers of a zone could be reached
+    pub fn is_no_reachable_authority(&self) -> bool {
+        match self {
+            Self::ProtoError(e) => e.is_no_reachable_authority(),
+            #[cfg(feature = "recursor")]
+            Self::RecursiveError(e) => e.is_no_reachable_authority(),
+            _ => false,
+        }
+    }
+
     /// Returns the SOA record, if the error contains one
     pub fn into_soa(self) -> Option<Box<Record<SOA>>> {
         match self {
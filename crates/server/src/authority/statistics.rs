@@ -0,0 +1,338 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Per-zone query statistics
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    hash::Hash,
+    net::IpAddr,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::proto::{
+    op::ResponseCode,
+    rr::{LowerName, RecordType},
+};
+
+/// Number of candidates tracked for every entry of a top-N report
+///
+/// Tracking more candidates than are reported makes the estimated counts of the reported entries
+/// more accurate.
+const CANDIDATES_PER_ENTRY: usize = 8;
+
+/// Per-zone query statistics of a [`Catalog`](super::Catalog)
+///
+/// Every query is counted by type and response code. In addition, one in `sample_rate` queries
+/// is sampled to estimate the clients and names that are queried the most, which helps to spot
+/// abuse such as random subdomain attacks or reflection without external log processing.
+pub struct QueryStatistics {
+    top_n: usize,
+    sample_rate: u64,
+    queries: AtomicU64,
+    zones: Mutex<HashMap<LowerName, ZoneStatistics>>,
+}
+
+impl QueryStatistics {
+    /// Creates new, empty statistics
+    ///
+    /// # Arguments
+    ///
+    /// * `top_n` - number of clients and names to report for each zone
+    /// * `sample_rate` - one in this many queries is sampled for the top clients and names, `0`
+    ///   is treated as `1`
+    pub fn new(top_n: usize, sample_rate: u32) -> Self {
+        Self {
+            top_n,
+            sample_rate: u64::from(sample_rate.max(1)),
+            queries: AtomicU64::new(0),
+            zones: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a query that was answered from `zone`
+    pub fn record(
+        &self,
+        zone: &LowerName,
+        name: &LowerName,
+        query_type: RecordType,
+        response_code: ResponseCode,
+        src: IpAddr,
+    ) {
+        let sampled = self.queries.fetch_add(1, Ordering::Relaxed) % self.sample_rate == 0;
+
+        let mut zones = self.zones.lock().expect("query statistics lock poisoned");
+        let statistics = zones
+            .entry(zone.clone())
+            .or_insert_with(|| ZoneStatistics::new(self.top_n * CANDIDATES_PER_ENTRY));
+
+        *statistics
+            .queries
+            .entry((query_type, response_code))
+            .or_default() += 1;
+
+        if sampled {
+            statistics.clients.insert(src);
+            statistics.names.insert(name.clone());
+        }
+    }
+
+    /// Returns a report for every zone that received queries, ordered by zone name
+    pub fn report(&self) -> Vec<ZoneReport> {
+        let zones = self.zones.lock().expect("query statistics lock poisoned");
+        let mut reports = zones
+            .iter()
+            .map(|(zone, statistics)| ZoneReport {
+                zone: zone.clone(),
+                queries: statistics
+                    .queries
+                    .iter()
+                    .map(|(&(query_type, response_code), &count)| {
+                        ((query_type, u16::from(response_code)), count)
+                    })
+                    .collect(),
+                top_clients: statistics.clients.top(self.top_n),
+                top_names: statistics.names.top(self.top_n),
+            })
+            .collect::<Vec<_>>();
+
+        reports.sort_by(|a, b| a.zone.cmp(&b.zone));
+        reports
+    }
+
+    /// Discards all statistics collected so far
+    pub fn reset(&self) {
+        self.zones
+            .lock()
+            .expect("query statistics lock poisoned")
+            .clear();
+    }
+}
+
+struct ZoneStatistics {
+    queries: HashMap<(RecordType, ResponseCode), u64>,
+    clients: TopTalkers<IpAddr>,
+    names: TopTalkers<LowerName>,
+}
+
+impl ZoneStatistics {
+    fn new(capacity: usize) -> Self {
+        Self {
+            queries: HashMap::new(),
+            clients: TopTalkers::new(capacity),
+            names: TopTalkers::new(capacity),
+        }
+    }
+}
+
+/// Estimates the most frequent keys within a bounded amount of memory
+///
+/// This is the Space-Saving algorithm: once `capacity` keys are tracked, a new key replaces the
+/// least frequent one and inherits its count. Counts may therefore be overestimated, but any key
+/// that occurs more often than `1 / capacity` of the time is guaranteed to be tracked.
+struct TopTalkers<K> {
+    capacity: usize,
+    counts: HashMap<K, u64>,
+}
+
+impl<K: Clone + Eq + Hash + Ord> TopTalkers<K> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            counts: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, key: K) {
+        if let Some(count) = self.counts.get_mut(&key) {
+            *count += 1;
+            return;
+        }
+
+        if self.counts.len() < self.capacity {
+            self.counts.insert(key, 1);
+            return;
+        }
+
+        let Some((least, &count)) = self.counts.iter().min_by_key(|(_, count)| **count) else {
+            // capacity is zero
+            return;
+        };
+
+        let least = least.clone();
+        self.counts.remove(&least);
+        self.counts.insert(key, count + 1);
+    }
+
+    fn top(&self, n: usize) -> Vec<(K, u64)> {
+        let mut top = self
+            .counts
+            .iter()
+            .map(|(key, count)| (key.clone(), *count))
+            .collect::<Vec<_>>();
+
+        top.sort_by(|(a_key, a_count), (b_key, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_key.cmp(b_key))
+        });
+        top.truncate(n);
+        top
+    }
+}
+
+/// Query statistics of a single zone, see [`QueryStatistics::report`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ZoneReport {
+    /// The zone the queries were answered from
+    pub zone: LowerName,
+    /// Number of queries by query type and response code
+    ///
+    /// Response codes are stored as their numeric value, which also covers extended response codes.
+    pub queries: BTreeMap<(RecordType, u16), u64>,
+    /// Clients that sent the most sampled queries with their estimated number of sampled queries,
+    /// most active first
+    pub top_clients: Vec<(IpAddr, u64)>,
+    /// Names that were queried the most with their estimated number of sampled queries, most
+    /// queried first
+    pub top_names: Vec<(LowerName, u64)>,
+}
+
+impl ZoneReport {
+    /// Total number of queries answered from the zone
+    pub fn total(&self) -> u64 {
+        self.queries.values().sum()
+    }
+}
+
+impl fmt::Display for ZoneReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "zone {} queries {}", self.zone, self.total())?;
+        for (&(query_type, response_code), count) in &self.queries {
+            writeln!(
+                f,
+                "  {query_type} {} {count}",
+                <ResponseCode as From<u16>>::from(response_code)
+            )?;
+        }
+
+        writeln!(f, "  top clients")?;
+        for (client, count) in &self.top_clients {
+            writeln!(f, "    {client} {count}")?;
+        }
+
+        writeln!(f, "  top names")?;
+        for (name, count) in &self.top_names {
+            writeln!(f, "    {name} {count}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_counts_by_zone() {
+        let statistics = QueryStatistics::new(2, 1);
+        let example = LowerName::from_str("example.com.").unwrap();
+        let other = LowerName::from_str("example.net.").unwrap();
+        let www = LowerName::from_str("www.example.com.").unwrap();
+        let client = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+
+        statistics.record(
+            &other,
+            &other,
+            RecordType::SOA,
+            ResponseCode::NoError,
+            client,
+        );
+        for _ in 0..3 {
+            statistics.record(&example, &www, RecordType::A, ResponseCode::NoError, client);
+        }
+        statistics.record(
+            &example,
+            &www,
+            RecordType::MX,
+            ResponseCode::NoError,
+            client,
+        );
+        statistics.record(
+            &example,
+            &example,
+            RecordType::A,
+            ResponseCode::NXDomain,
+            client,
+        );
+
+        let report = statistics.report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].zone, example);
+        assert_eq!(report[0].total(), 5);
+        assert_eq!(report[0].queries[&(RecordType::A, 0)], 3);
+        assert_eq!(report[0].queries[&(RecordType::MX, 0)], 1);
+        assert_eq!(report[0].queries[&(RecordType::A, 3)], 1);
+        assert_eq!(report[0].top_clients, vec![(client, 5)]);
+        assert_eq!(report[0].top_names, vec![(www, 4), (example, 1)]);
+        assert_eq!(report[1].zone, other);
+        assert_eq!(report[1].total(), 1);
+
+        statistics.reset();
+        assert!(statistics.report().is_empty());
+    }
+
+    #[test]
+    fn test_sampling() {
+        let statistics = QueryStatistics::new(1, 10);
+        let zone = LowerName::from_str("example.com.").unwrap();
+        let client = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+
+        for _ in 0..100 {
+            statistics.record(&zone, &zone, RecordType::A, ResponseCode::NoError, client);
+        }
+
+        let report = statistics.report();
+        assert_eq!(report[0].total(), 100);
+        assert_eq!(report[0].top_clients, vec![(client, 10)]);
+    }
+
+    #[test]
+    fn test_top_talkers() {
+        let mut top = TopTalkers::new(3);
+
+        // a flood of distinct keys must not push out a frequent one
+        for key in 100..200 {
+            top.insert(key);
+            top.insert(1);
+        }
+
+        let top = top.top(1);
+        assert_eq!(top[0].0, 1);
+        assert!(top[0].1 >= 100);
+    }
+
+    #[test]
+    fn test_display() {
+        let statistics = QueryStatistics::new(1, 1);
+        let zone = LowerName::from_str("example.com.").unwrap();
+        let client = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        statistics.record(&zone, &zone, RecordType::A, ResponseCode::Refused, client);
+
+        assert_eq!(
+            statistics.report()[0].to_string(),
+            "zone example.com. queries 1\n  A Query Refused 1\n  top clients\n    192.0.2.1 1\n  top names\n    example.com. 1\n"
+        );
+    }
+}
@@ -55,6 +55,8 @@ pub use hickory_recursor as recursor;
 pub use hickory_resolver as resolver;
 
 mod access;
+#[cfg(feature = "__acme")]
+pub mod acme;
 pub mod authority;
 mod error;
 pub use error::{ConfigError, ConfigErrorKind, PersistenceError, PersistenceErrorKind};
@@ -10,32 +10,33 @@ use std::{io, net::SocketAddr, sync::Arc, time::Duration};
 use bytes::Bytes;
 use futures_util::lock::Mutex;
 use h2::server;
-use rustls::server::ResolvesServerCert;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     net::TcpListener,
     task::JoinSet,
     time::timeout,
 };
-use tokio_rustls::TlsAcceptor;
-use tracing::{debug, error, warn};
+use tracing::{debug, debug_span, error, warn};
 
 use super::{
     ResponseInfo, ServerContext, is_unrecoverable_socket_error, reap_tasks,
     request_handler::RequestHandler,
     response_handler::{ResponseHandler, encode_fallback_servfail_response},
-    sanitize_src_address, tls_server_config,
+    sanitize_src_address,
 };
 use crate::{
     authority::MessageResponse,
-    proto::{ProtoError, h2::h2_server, http::Version, rr::Record, xfer::Protocol},
+    proto::{
+        ProtoError, h2::h2_server, http::Version, rr::Record, runtime::iocompat::AsyncIoStdAsTokio,
+        tls::TlsAccept, xfer::Protocol,
+    },
 };
 
 pub(super) async fn handle_h2(
     listener: TcpListener,
     // TODO: need to set a timeout between requests.
     handshake_timeout: Duration,
-    server_cert_resolver: Arc<dyn ResolvesServerCert>,
+    tls_acceptor: impl TlsAccept,
     dns_hostname: Option<String>,
     http_endpoint: String,
     cx: Arc<ServerContext<impl RequestHandler>>,
@@ -44,8 +45,6 @@ pub(super) async fn handle_h2(
     let http_endpoint: Arc<str> = Arc::from(http_endpoint);
     debug!("registered https: {listener:?}");
 
-    let tls_acceptor = TlsAcceptor::from(Arc::new(tls_server_config(b"h2", server_cert_resolver)?));
-
     let mut inner_join_set = JoinSet::new();
     loop {
         let shutdown = cx.shutdown.clone();
@@ -96,7 +95,14 @@ pub(super) async fn handle_h2(
             };
             debug!("accepted HTTPS request from: {src_addr}");
 
-            h2_handler(tls_stream, src_addr, dns_hostname, http_endpoint, cx).await;
+            h2_handler(
+                AsyncIoStdAsTokio(tls_stream),
+                src_addr,
+                dns_hostname,
+                http_endpoint,
+                cx,
+            )
+            .await;
         });
 
         reap_tasks(&mut inner_join_set);
@@ -193,13 +199,13 @@ impl ResponseHandler for HttpsResponseHandle {
         let id = response.header().id();
         let mut bytes = Vec::with_capacity(512);
         // mut block
-        let info = {
+        let info = debug_span!("encode").in_scope(|| {
             let mut encoder = BinEncoder::new(&mut bytes);
             response.destructive_emit(&mut encoder).or_else(|error| {
                 error!(%error, "error encoding message");
                 encode_fallback_servfail_response(id, &mut bytes)
-            })?
-        };
+            })
+        })?;
         let bytes = Bytes::from(bytes);
         let response = response::new(Version::Http2, bytes.len())?;
 
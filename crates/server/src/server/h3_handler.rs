@@ -13,7 +13,7 @@ use h3::server::RequestStream;
 use h3_quinn::BidiStream;
 use rustls::server::ResolvesServerCert;
 use tokio::{net, task::JoinSet};
-use tracing::{debug, error, warn};
+use tracing::{debug, debug_span, error, warn};
 
 use super::{
     ResponseInfo, ServerContext, reap_tasks,
@@ -177,13 +177,13 @@ impl ResponseHandler for H3ResponseHandle {
         let id = response.header().id();
         let mut bytes = Vec::with_capacity(512);
         // mut block
-        let info = {
+        let info = debug_span!("encode").in_scope(|| {
             let mut encoder = BinEncoder::new(&mut bytes);
             response.destructive_emit(&mut encoder).or_else(|error| {
                 error!(%error, "error encoding message");
                 encode_fallback_servfail_response(id, &mut bytes)
-            })?
-        };
+            })
+        })?;
         let bytes = Bytes::from(bytes);
         let response = response::new(Version::Http3, bytes.len())?;
 
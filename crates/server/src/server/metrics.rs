@@ -1,5 +1,7 @@
 use hickory_proto::op::{Header, OpCode, ResponseCode};
+use hickory_proto::serialize::binary::{DecodeError, DecompressionLimit};
 use hickory_proto::xfer::Protocol;
+use hickory_proto::{ProtoError, ProtoErrorKind};
 use metrics::{Counter, Unit, counter, describe_counter};
 
 #[derive(Clone)]
@@ -83,9 +85,9 @@ impl FlagMetrics {
 pub(super) struct ProtocolMetrics {
     udp: Counter,
     tcp: Counter,
-    #[cfg(feature = "__tls")]
+    #[cfg(any(feature = "__tls", feature = "native-tls"))]
     tls: Counter,
-    #[cfg(feature = "__https")]
+    #[cfg(feature = "__h2")]
     https: Counter,
     #[cfg(feature = "__quic")]
     quic: Counter,
@@ -108,9 +110,9 @@ impl Default for ProtocolMetrics {
                 new
             },
             tcp: counter!(request_protocols_name, key => "tcp"),
-            #[cfg(feature = "__tls")]
+            #[cfg(any(feature = "__tls", feature = "native-tls"))]
             tls: counter!(request_protocols_name, key => "tls"),
-            #[cfg(feature = "__https")]
+            #[cfg(feature = "__h2")]
             https: counter!(request_protocols_name, key => "https"),
             #[cfg(feature = "__quic")]
             quic: counter!(request_protocols_name, key => "quic"),
@@ -125,9 +127,9 @@ impl ProtocolMetrics {
         match proto {
             Protocol::Udp => self.udp.increment(1),
             Protocol::Tcp => self.tcp.increment(1),
-            #[cfg(feature = "__tls")]
+            #[cfg(any(feature = "__tls", feature = "native-tls"))]
             Protocol::Tls => self.tls.increment(1),
-            #[cfg(feature = "__https")]
+            #[cfg(feature = "__h2")]
             Protocol::Https => self.https.increment(1),
             #[cfg(feature = "__quic")]
             Protocol::Quic => self.quic.increment(1),
@@ -138,6 +140,57 @@ impl ProtocolMetrics {
     }
 }
 
+/// Counts the requests that were dropped because decompressing their names exceeded a limit
+#[derive(Clone)]
+pub(super) struct DecompressionLimitMetrics {
+    pointer_jumps: Counter,
+    name_length: Counter,
+    expansion: Counter,
+}
+
+impl Default for DecompressionLimitMetrics {
+    fn default() -> Self {
+        let decompression_limits_name = "hickory_request_decompression_limits_total";
+        let key = "limit";
+        Self {
+            pointer_jumps: {
+                let new = counter!(decompression_limits_name, key => DecompressionLimit::PointerJumps.as_str());
+                describe_counter!(
+                    decompression_limits_name,
+                    Unit::Count,
+                    "number of dns requests exceeding a name decompression limit"
+                );
+                new
+            },
+            name_length: counter!(decompression_limits_name, key => DecompressionLimit::NameLength.as_str()),
+            expansion: counter!(decompression_limits_name, key => DecompressionLimit::Expansion.as_str()),
+        }
+    }
+}
+
+impl DecompressionLimitMetrics {
+    /// Increments the counter of the limit that caused `error`, if any
+    pub(super) fn increment(&self, error: &ProtoError) {
+        match decompression_limit(error) {
+            Some(DecompressionLimit::PointerJumps) => self.pointer_jumps.increment(1),
+            Some(DecompressionLimit::NameLength) => self.name_length.increment(1),
+            Some(DecompressionLimit::Expansion) => self.expansion.increment(1),
+            _ => {}
+        }
+    }
+}
+
+fn decompression_limit(error: &ProtoError) -> Option<DecompressionLimit> {
+    match error.decode_error() {
+        Some(DecodeError::DecompressionLimit(limit)) => Some(limit),
+        Some(_) => None,
+        None => match error.kind() {
+            ProtoErrorKind::FormError { error, .. } => decompression_limit(error),
+            _ => None,
+        },
+    }
+}
+
 #[derive(Clone)]
 pub(super) struct OpCodeMetrics {
     query: Counter,
@@ -212,7 +265,7 @@ impl Default for ResponseCodeMetrics {
         let key = "code";
         Self {
             no_error: {
-                let new = counter!(response_codes_name, "code" => "no_error");
+                let new = counter!(response_codes_name, key => response_code_label(ResponseCode::NoError));
                 describe_counter!(
                     response_codes_name,
                     Unit::Count,
@@ -220,26 +273,26 @@ impl Default for ResponseCodeMetrics {
                 );
                 new
             },
-            form_error: counter!(response_codes_name, key => "form_error"),
-            serv_fail: counter!(response_codes_name, key => "serv_fail"),
-            nx_domain: counter!(response_codes_name, key => "nx_domain"),
-            not_imp: counter!(response_codes_name, key => "not_imp"),
-            refused: counter!(response_codes_name, key => "refused"),
-            yx_domain: counter!(response_codes_name, key => "yx_domain"),
-            yx_rrset: counter!(response_codes_name, key => "yx_rrset"),
-            nx_rrset: counter!(response_codes_name, key => "nx_rrset"),
-            not_auth: counter!(response_codes_name, key => "not_auth"),
-            not_zone: counter!(response_codes_name, key => "not_zone"),
-            bad_vers: counter!(response_codes_name, key => "bad_vers"),
-            bad_sig: counter!(response_codes_name, key => "bad_sig"),
-            bad_key: counter!(response_codes_name, key => "bad_key"),
-            bad_time: counter!(response_codes_name, key => "bad_time"),
-            bad_mode: counter!(response_codes_name, key => "bad_mode"),
-            bad_name: counter!(response_codes_name, key => "bad_name"),
-            bad_alg: counter!(response_codes_name, key => "bad_alg"),
-            bad_trunc: counter!(response_codes_name, key => "bad_trunc"),
-            bad_cookie: counter!(response_codes_name, key => "bad_cookie"),
-            unknown: counter!(response_codes_name, key => "unknown"),
+            form_error: counter!(response_codes_name, key => response_code_label(ResponseCode::FormErr)),
+            serv_fail: counter!(response_codes_name, key => response_code_label(ResponseCode::ServFail)),
+            nx_domain: counter!(response_codes_name, key => response_code_label(ResponseCode::NXDomain)),
+            not_imp: counter!(response_codes_name, key => response_code_label(ResponseCode::NotImp)),
+            refused: counter!(response_codes_name, key => response_code_label(ResponseCode::Refused)),
+            yx_domain: counter!(response_codes_name, key => response_code_label(ResponseCode::YXDomain)),
+            yx_rrset: counter!(response_codes_name, key => response_code_label(ResponseCode::YXRRSet)),
+            nx_rrset: counter!(response_codes_name, key => response_code_label(ResponseCode::NXRRSet)),
+            not_auth: counter!(response_codes_name, key => response_code_label(ResponseCode::NotAuth)),
+            not_zone: counter!(response_codes_name, key => response_code_label(ResponseCode::NotZone)),
+            bad_vers: counter!(response_codes_name, key => response_code_label(ResponseCode::BADVERS)),
+            bad_sig: counter!(response_codes_name, key => response_code_label(ResponseCode::BADSIG)),
+            bad_key: counter!(response_codes_name, key => response_code_label(ResponseCode::BADKEY)),
+            bad_time: counter!(response_codes_name, key => response_code_label(ResponseCode::BADTIME)),
+            bad_mode: counter!(response_codes_name, key => response_code_label(ResponseCode::BADMODE)),
+            bad_name: counter!(response_codes_name, key => response_code_label(ResponseCode::BADNAME)),
+            bad_alg: counter!(response_codes_name, key => response_code_label(ResponseCode::BADALG)),
+            bad_trunc: counter!(response_codes_name, key => response_code_label(ResponseCode::BADTRUNC)),
+            bad_cookie: counter!(response_codes_name, key => response_code_label(ResponseCode::BADCOOKIE)),
+            unknown: counter!(response_codes_name, key => response_code_label(ResponseCode::Unknown(0))),
         }
     }
 }
@@ -271,3 +324,30 @@ impl ResponseCodeMetrics {
         }
     }
 }
+
+/// The label of a response code, as used by the response code counters
+pub(crate) fn response_code_label(response_code: ResponseCode) -> &'static str {
+    match response_code {
+        ResponseCode::NoError => "no_error",
+        ResponseCode::FormErr => "form_error",
+        ResponseCode::ServFail => "serv_fail",
+        ResponseCode::NXDomain => "nx_domain",
+        ResponseCode::NotImp => "not_imp",
+        ResponseCode::Refused => "refused",
+        ResponseCode::YXDomain => "yx_domain",
+        ResponseCode::YXRRSet => "yx_rrset",
+        ResponseCode::NXRRSet => "nx_rrset",
+        ResponseCode::NotAuth => "not_auth",
+        ResponseCode::NotZone => "not_zone",
+        ResponseCode::BADVERS => "bad_vers",
+        ResponseCode::BADSIG => "bad_sig",
+        ResponseCode::BADKEY => "bad_key",
+        ResponseCode::BADTIME => "bad_time",
+        ResponseCode::BADMODE => "bad_mode",
+        ResponseCode::BADNAME => "bad_name",
+        ResponseCode::BADALG => "bad_alg",
+        ResponseCode::BADTRUNC => "bad_trunc",
+        ResponseCode::BADCOOKIE => "bad_cookie",
+        ResponseCode::Unknown(_) => "unknown",
+    }
+}
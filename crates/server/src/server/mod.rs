@@ -14,22 +14,27 @@ use std::{
     time::Duration,
 };
 
-#[cfg(feature = "__tls")]
-use crate::proto::rustls::tls_from_stream;
+#[cfg(any(feature = "__tls", feature = "native-tls"))]
+use crate::proto::tls::{TlsAccept, tls_from_stream};
 use bytes::Bytes;
 use futures_util::{FutureExt, StreamExt};
 use hickory_proto::ProtoErrorKind;
 use ipnet::IpNet;
 #[cfg(feature = "__tls")]
-use rustls::{ServerConfig, server::ResolvesServerCert};
-#[cfg(feature = "__tls")]
+use rustls::{
+    ServerConfig,
+    server::{ResolvesServerCert, danger::ClientCertVerifier},
+};
+#[cfg(any(feature = "__tls", feature = "native-tls"))]
 use tokio::time::timeout;
 use tokio::{net, task::JoinSet};
 #[cfg(feature = "__tls")]
 use tokio_rustls::TlsAcceptor;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, info, warn};
+use tracing::{Instrument, Span, debug, debug_span, field, info, warn};
 
+#[cfg(feature = "__quic")]
+use crate::proto::quic::DoqExchange;
 #[cfg(feature = "__tls")]
 use crate::proto::rustls::default_provider;
 use crate::{
@@ -40,27 +45,31 @@ use crate::{
         op::{Header, LowerQuery, MessageType, ResponseCode},
         rr::Record,
         runtime::{TokioRuntimeProvider, iocompat::AsyncIoTokioAsStd},
-        serialize::binary::{BinDecodable, BinDecoder},
+        serialize::binary::{BinDecodable, BinDecoder, DecodeMode, DecompressionLimits},
         tcp::TcpStream,
         udp::UdpStream,
         xfer::{Protocol, SerialMessage},
     },
 };
 
-#[cfg(feature = "__https")]
+#[cfg(feature = "__h2")]
 mod h2_handler;
 #[cfg(feature = "__h3")]
 mod h3_handler;
 #[cfg(feature = "__quic")]
 mod quic_handler;
+#[cfg(feature = "__tls")]
+mod reloadable_cert;
+#[cfg(feature = "__tls")]
+pub use reloadable_cert::ReloadableCertResolver;
 mod request_handler;
 pub use request_handler::{Request, RequestHandler, RequestInfo, ResponseInfo};
 mod response_handler;
 pub use response_handler::{ResponseHandle, ResponseHandler};
 #[cfg(feature = "metrics")]
-mod metrics;
+pub(crate) mod metrics;
 #[cfg(feature = "metrics")]
-use metrics::ResponseHandlerMetrics;
+use metrics::{DecompressionLimitMetrics, ResponseHandlerMetrics};
 mod timeout_stream;
 pub use timeout_stream::TimeoutStream;
 
@@ -88,11 +97,28 @@ impl<T: RequestHandler> Server<T> {
                 handler,
                 access,
                 shutdown: CancellationToken::new(),
+                decompression_limits: DecompressionLimits::default(),
+                #[cfg(feature = "metrics")]
+                decompression_metrics: DecompressionLimitMetrics::default(),
             }),
             join_set: JoinSet::new(),
         }
     }
 
+    /// Sets the limits on the decompression of names in incoming requests
+    ///
+    /// Requests exceeding them are answered with `FORMERR`.
+    ///
+    /// # Panics
+    ///
+    /// If called after a socket or listener was registered.
+    pub fn with_decompression_limits(mut self, limits: DecompressionLimits) -> Self {
+        Arc::get_mut(&mut self.context)
+            .expect("decompression limits must be set before registering sockets")
+            .decompression_limits = limits;
+        self
+    }
+
     /// Register a UDP socket. Should be bound before calling this function.
     pub fn register_socket(&mut self, socket: net::UdpSocket) {
         self.join_set
@@ -135,12 +161,40 @@ impl<T: RequestHandler> Server<T> {
         listener: net::TcpListener,
         handshake_timeout: Duration,
         tls_config: Arc<ServerConfig>,
+    ) -> io::Result<()> {
+        self.register_tls_listener_with_acceptor(
+            listener,
+            handshake_timeout,
+            TlsAcceptor::from(tls_config),
+        )
+    }
+
+    /// Register a TlsListener to the Server, performing the TLS handshakes with any of the TLS
+    /// backends supported by `hickory-proto`, e.g. rustls or native-tls. The TlsListener should
+    /// already be bound to either an IPv6 or an IPv4 address.
+    ///
+    /// To make the server more resilient to DOS issues, there is a timeout. Care should be taken
+    ///  to not make this too low depending on use cases.
+    ///
+    /// # Arguments
+    /// * `listener` - a bound TCP (needs to be on a different port from standard TCP connections) socket
+    /// * `handshake_timeout` - timeout duration of the TLS handshake and of incoming requests,
+    ///   any connection that does not send requests within this time period will be closed.
+    /// * `acceptor` - performs the server side of the TLS handshake, e.g. a
+    ///   `tokio_native_tls::TlsAcceptor` with the `native-tls` feature
+    #[cfg(any(feature = "__tls", feature = "native-tls"))]
+    pub fn register_tls_listener_with_acceptor(
+        &mut self,
+        listener: net::TcpListener,
+        handshake_timeout: Duration,
+        acceptor: impl TlsAccept,
     ) -> io::Result<()> {
         self.join_set.spawn(handle_tls(
             listener,
-            tls_config,
+            acceptor,
             handshake_timeout,
             self.context.clone(),
+            false,
         ));
         Ok(())
     }
@@ -169,6 +223,46 @@ impl<T: RequestHandler> Server<T> {
         Self::register_tls_listener_with_tls_config(self, listener, timeout, Arc::new(config))
     }
 
+    /// Register a TlsListener to the Server, which authenticates clients by their certificates
+    /// (mutual TLS). The TlsListener should already be bound to either an IPv6 or an IPv4 address.
+    ///
+    /// This is intended for zone transfers over TLS (XoT, RFC 9103), where the primary may
+    /// authenticate its secondaries by their certificates. Clients without a certificate may still
+    /// send queries, but their zone transfer (AXFR and IXFR) requests are refused, so the
+    /// verifier should allow unauthenticated clients.
+    ///
+    /// # Arguments
+    /// * `listener` - a bound TCP (needs to be on a different port from standard TCP connections) socket
+    /// * `timeout` - timeout duration of the TLS handshake and of incoming requests
+    /// * `server_cert_resolver` - resolver for the certificate and key used to announce to clients
+    /// * `client_cert_verifier` - verifies the certificates of clients, e.g. a
+    ///   `rustls::server::WebPkiClientVerifier` built from the CAs of the secondaries, allowing
+    ///   unauthenticated clients
+    #[cfg(feature = "__tls")]
+    pub fn register_tls_listener_with_client_auth(
+        &mut self,
+        listener: net::TcpListener,
+        timeout: Duration,
+        server_cert_resolver: Arc<dyn ResolvesServerCert>,
+        client_cert_verifier: Arc<dyn ClientCertVerifier>,
+    ) -> io::Result<()> {
+        let mut config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| io::Error::other(format!("error creating TLS acceptor: {e}")))?
+            .with_client_cert_verifier(client_cert_verifier)
+            .with_cert_resolver(server_cert_resolver);
+
+        config.alpn_protocols = vec![b"dot".to_vec()];
+        self.join_set.spawn(handle_tls(
+            listener,
+            TlsAcceptor::from(Arc::new(config)),
+            timeout,
+            self.context.clone(),
+            true,
+        ));
+        Ok(())
+    }
+
     /// Register a TcpListener for HTTPS (h2) to the Server for supporting DoH (DNS-over-HTTPS). The TcpListener should already be bound to either an
     /// IPv6 or an IPv4 address.
     ///
@@ -193,11 +287,46 @@ impl<T: RequestHandler> Server<T> {
         server_cert_resolver: Arc<dyn ResolvesServerCert>,
         dns_hostname: Option<String>,
         http_endpoint: String,
+    ) -> io::Result<()> {
+        let config = tls_server_config(b"h2", server_cert_resolver)?;
+        self.register_https_listener_with_acceptor(
+            listener,
+            handshake_timeout,
+            TlsAcceptor::from(Arc::new(config)),
+            dns_hostname,
+            http_endpoint,
+        )
+    }
+
+    /// Register a TcpListener for HTTPS (h2) to the Server for supporting DoH (DNS-over-HTTPS),
+    /// performing the TLS handshakes with any of the TLS backends supported by `hickory-proto`,
+    /// e.g. rustls or native-tls. The TcpListener should already be bound to either an IPv6 or an
+    /// IPv4 address.
+    ///
+    /// The acceptor should select `h2` through ALPN, which native-tls can't do on the server side:
+    /// clients then have to use HTTP/2 without having negotiated it.
+    ///
+    /// # Arguments
+    /// * `listener` - a bound TCP (needs to be on a different port from standard TCP connections) socket
+    /// * `handshake_timeout` - timeout duration of the TLS handshake
+    /// * `acceptor` - performs the server side of the TLS handshake, e.g. a
+    ///   `tokio_native_tls::TlsAcceptor` with the `https-native-tls` feature
+    /// * `dns_hostname` - the DNS hostname of the H2 server.
+    /// * `http_endpoint` - the HTTP endpoint of the H2 server.
+    #[cfg(feature = "__h2")]
+    pub fn register_https_listener_with_acceptor(
+        &mut self,
+        listener: net::TcpListener,
+        // TODO: need to set a timeout between requests.
+        handshake_timeout: Duration,
+        acceptor: impl TlsAccept,
+        dns_hostname: Option<String>,
+        http_endpoint: String,
     ) -> io::Result<()> {
         self.join_set.spawn(h2_handler::handle_h2(
             listener,
             handshake_timeout,
-            server_cert_resolver,
+            acceptor,
             dns_hostname,
             http_endpoint,
             self.context.clone(),
@@ -439,15 +568,15 @@ async fn handle_tcp(
     }
 }
 
-#[cfg(feature = "__tls")]
-async fn handle_tls(
+#[cfg(any(feature = "__tls", feature = "native-tls"))]
+async fn handle_tls<A: TlsAccept>(
     listener: net::TcpListener,
-    tls_config: Arc<ServerConfig>,
+    tls_acceptor: A,
     handshake_timeout: Duration,
     cx: Arc<ServerContext<impl RequestHandler>>,
+    transfers_require_auth: bool,
 ) -> Result<(), ProtoError> {
     debug!(?listener, "registered tls");
-    let tls_acceptor = TlsAcceptor::from(tls_config);
 
     let mut inner_join_set = JoinSet::new();
     loop {
@@ -491,13 +620,14 @@ async fn handle_tls(
             };
 
             let tls_stream = match tls_stream {
-                Ok(tls_stream) => AsyncIoTokioAsStd(tls_stream),
+                Ok(tls_stream) => tls_stream,
                 Err(error) => {
                     debug!(%src_addr, %error, "tls handshake error");
                     return;
                 }
             };
             debug!(%src_addr, "accepted TLS request");
+            let authenticated = A::is_client_authenticated(&tls_stream);
             let (buf_stream, stream_handle) = tls_from_stream(tls_stream, src_addr);
             let mut timeout_stream = TimeoutStream::new(buf_stream, handshake_timeout);
             while let Some(message) = timeout_stream.next().await {
@@ -514,6 +644,25 @@ async fn handle_tls(
                     }
                 };
 
+                if transfers_require_auth && !authenticated {
+                    if let Some((header, queries)) = zone_transfer_request(message.bytes(), cx.decompression_limits) {
+                        warn!(%src_addr, "refusing zone transfer from a TLS client without a certificate");
+                        let response_handle =
+                            ResponseHandle::new(src_addr, stream_handle.clone(), Protocol::Tls);
+                        error_response_handler(
+                            Protocol::Tls,
+                            src_addr,
+                            header,
+                            queries,
+                            ResponseCode::Refused,
+                            Box::new(ProtoErrorKind::RequestRefused.into()),
+                            response_handle,
+                        )
+                        .await;
+                        continue;
+                    }
+                }
+
                 cx.handle_raw_request(message, Protocol::Tls, stream_handle.clone())
                     .await;
             }
@@ -529,6 +678,21 @@ async fn handle_tls(
     }
 }
 
+/// Decodes the header and queries of `message`, if it is a zone transfer (AXFR or IXFR) request
+#[cfg(any(feature = "__tls", feature = "native-tls"))]
+fn zone_transfer_request(message: &[u8], limits: DecompressionLimits) -> Option<(Header, Queries)> {
+    use crate::proto::rr::RecordType;
+
+    let mut decoder = BinDecoder::with_limits(message, DecodeMode::Normal, limits);
+    let header = Header::read(&mut decoder).ok()?;
+    let queries = Queries::read(&mut decoder, header.query_count() as usize).ok()?;
+    queries
+        .queries()
+        .iter()
+        .any(|query| matches!(query.query_type(), RecordType::AXFR | RecordType::IXFR))
+        .then_some((header, queries))
+}
+
 /// Reap finished tasks from a `JoinSet`, without awaiting or blocking.
 fn reap_tasks(join_set: &mut JoinSet<()>) {
     while FutureExt::now_or_never(join_set.join_next())
@@ -590,6 +754,7 @@ impl<R: ResponseHandler> ResponseHandler for ReportingResponseHandler<R> {
         let authority_count = response_info.name_server_count();
         let additional_count = response_info.additional_count();
         let response_code = response_info.response_code();
+        Span::current().record("response_code", field::display(response_code));
 
         info!(
             "request:{id} src:{proto}://{addr}#{port} {op} qflags:{qflags} response:{code:?} rr:{answers}/{authorities}/{additionals} rflags:{rflags}",
@@ -643,6 +808,9 @@ struct ServerContext<T> {
     handler: T,
     access: AccessControl,
     shutdown: CancellationToken,
+    decompression_limits: DecompressionLimits,
+    #[cfg(feature = "metrics")]
+    decompression_metrics: DecompressionLimitMetrics,
 }
 
 impl<T: RequestHandler> ServerContext<T> {
@@ -666,7 +834,52 @@ impl<T: RequestHandler> ServerContext<T> {
         protocol: Protocol,
         response_handler: impl ResponseHandler,
     ) {
-        let mut decoder = BinDecoder::new(&message_bytes);
+        let result = self
+            .checked_request(message_bytes, src_addr, protocol, response_handler)
+            .await;
+
+        if let Err(error) = result {
+            debug!(%error, %src_addr, "request violated the protocol");
+        }
+    }
+
+    /// Handles a request, failing if it violates the rules of the connection's protocol
+    ///
+    /// The connection is to be closed on failure.
+    async fn checked_request(
+        &self,
+        message_bytes: Bytes,
+        src_addr: SocketAddr,
+        protocol: Protocol,
+        response_handler: impl ResponseHandler,
+    ) -> Result<(), ProtoError> {
+        // the catalog records the zone, and the reporter the response code
+        let span = debug_span!(
+            "request",
+            %protocol,
+            src = %src_addr,
+            id = field::Empty,
+            zone = field::Empty,
+            response_code = field::Empty,
+        );
+
+        self.process_request(message_bytes, src_addr, protocol, response_handler)
+            .instrument(span)
+            .await
+    }
+
+    async fn process_request(
+        &self,
+        message_bytes: Bytes,
+        src_addr: SocketAddr,
+        protocol: Protocol,
+        response_handler: impl ResponseHandler,
+    ) -> Result<(), ProtoError> {
+        let mut decoder = BinDecoder::with_limits(
+            &message_bytes,
+            DecodeMode::Normal,
+            self.decompression_limits,
+        );
         if !self.access.allow(src_addr.ip()) {
             info!(
                 "request:Refused src:{proto}://{addr}#{port}",
@@ -679,7 +892,7 @@ impl<T: RequestHandler> ServerContext<T> {
                 // This will only fail if the message is less than twelve bytes long. Such messages are
                 // definitely not valid DNS queries, so it should be fine to return without sending a
                 // response.
-                return;
+                return Ok(());
             };
             let queries = match Queries::read(&mut decoder, header.query_count() as usize) {
                 Ok(queries) => queries,
@@ -696,54 +909,65 @@ impl<T: RequestHandler> ServerContext<T> {
             )
             .await;
 
-            return;
+            return Ok(());
         }
 
         // Attempt to decode the message
-        let request = match MessageRequest::read(&mut decoder) {
+        let result = debug_span!("decode").in_scope(|| MessageRequest::read(&mut decoder));
+        #[cfg(feature = "metrics")]
+        if let Err(error) = &result {
+            self.decompression_metrics.increment(error);
+        }
+
+        let request = match result {
             Ok(message) => Request {
                 message,
                 raw: message_bytes,
                 src: src_addr,
                 protocol,
             },
-            Err(ProtoError { kind, .. }) if kind.as_form_error().is_some() => {
-                // We failed to parse the request due to some issue in the message, but the header is available, so we can respond
-                let (header, error) = kind
-                    .into_form_error()
-                    .expect("as form_error already confirmed this is a FormError");
-                let queries = Queries::empty();
-
-                error_response_handler(
-                    protocol,
-                    src_addr,
-                    header,
-                    queries,
-                    ResponseCode::FormErr,
-                    error,
-                    response_handler,
-                )
-                .await;
+            Err(error) => match error.into_form_error() {
+                Ok((header, error)) => {
+                    // We failed to parse the request due to some issue in the message, but the header is available, so we can respond
+                    let queries = Queries::empty();
+
+                    error_response_handler(
+                        protocol,
+                        src_addr,
+                        header,
+                        queries,
+                        ResponseCode::FormErr,
+                        error,
+                        response_handler,
+                    )
+                    .await;
 
-                return;
-            }
-            Err(error) => {
-                info!(
-                    "request:Failed src:{proto}://{addr}#{port} error:{error}",
-                    proto = protocol,
-                    addr = src_addr.ip(),
-                    port = src_addr.port(),
-                );
-                return;
-            }
+                    return Ok(());
+                }
+                Err(error) => {
+                    info!(
+                        "request:Failed src:{proto}://{addr}#{port} error:{error}",
+                        proto = protocol,
+                        addr = src_addr.ip(),
+                        port = src_addr.port(),
+                    );
+                    return Ok(());
+                }
+            },
         };
 
         if request.message.message_type() == MessageType::Response {
             // Don't process response messages to avoid DoS attacks from reflection.
-            return;
+            return Ok(());
+        }
+
+        #[cfg(feature = "__quic")]
+        if protocol == Protocol::Quic {
+            DoqExchange::check_edns(request.message.edns())?;
         }
 
         let id = request.message.id();
+        Span::current().record("id", id);
         let qflags = request.message.header().flags();
         let qop_code = request.message.op_code();
         let message_type = request.message.message_type();
@@ -785,6 +1009,7 @@ impl<T: RequestHandler> ServerContext<T> {
         };
 
         self.handler.handle_request(&request, reporter).await;
+        Ok(())
     }
 }
 
@@ -1088,6 +1313,157 @@ mod tests {
         Arc::new(SingleCertAndKey::from(certified_key))
     }
 
+    #[tokio::test]
+    async fn test_decompression_limits() {
+        use crate::proto::{
+            op::{Message, Query},
+            rr::{Name, RecordType},
+            serialize::binary::BinEncodable,
+        };
+
+        subscribe();
+
+        let mut message = Message::query();
+        message.add_query(Query::query(
+            Name::from_ascii("www.example.com.").unwrap(),
+            RecordType::A,
+        ));
+        let request = message.to_bytes().unwrap();
+
+        // the name is 17 bytes long in wire format
+        let mut tightened = DecompressionLimits::default();
+        tightened.max_name_length = 16;
+
+        for (limits, response_code) in [
+            (DecompressionLimits::default(), ResponseCode::Refused),
+            (tightened, ResponseCode::FormErr),
+        ] {
+            let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let server_addr = socket.local_addr().unwrap();
+            let mut server = Server::new(Catalog::new()).with_decompression_limits(limits);
+            server.register_socket(socket);
+
+            let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            client.send_to(&request, server_addr).await.unwrap();
+            let mut buf = [0; 512];
+            let (len, _) = timeout(Duration::from_secs(5), client.recv_from(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+            let response = Message::from_bytes(&buf[..len]).unwrap();
+            assert_eq!(response.response_code(), response_code);
+
+            server.shutdown_gracefully().await.unwrap();
+        }
+    }
+
+    #[cfg(feature = "https-native-tls")]
+    #[tokio::test]
+    async fn test_https_native_tls() {
+        use std::{env, fs};
+
+        use native_tls::Identity;
+
+        use crate::proto::{
+            h2::https_client_connect_with_connector,
+            op::{Message, Query},
+            rr::{Name, RecordType},
+            xfer::{DnsRequest, DnsRequestOptions, DnsRequestSender},
+        };
+
+        subscribe();
+
+        let server_path = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "../..".to_owned());
+        let read = |file: &str| fs::read(format!("{server_path}/tests/test-data/{file}")).unwrap();
+
+        let identity = Identity::from_pkcs8(&read("cert.pem"), &read("cert.key")).unwrap();
+        let acceptor =
+            tokio_native_tls::TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        let mut server = Server::new(Catalog::new());
+        server
+            .register_https_listener_with_acceptor(
+                listener,
+                Duration::from_secs(1),
+                acceptor,
+                None,
+                "/dns-query".into(),
+            )
+            .unwrap();
+
+        let connector = native_tls::TlsConnector::builder()
+            .request_alpns(&["h2"])
+            // verification is up to native-tls, this only exercises DNS over HTTPS on top of it
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let tcp = Box::pin(async move {
+            net::TcpStream::connect(server_addr)
+                .await
+                .map(AsyncIoTokioAsStd)
+        });
+        let mut client = https_client_connect_with_connector(
+            tcp,
+            server_addr,
+            Arc::from("ns.example.com"),
+            Arc::from("/dns-query"),
+            tokio_native_tls::TlsConnector::from(connector),
+        )
+        .await
+        .unwrap();
+
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        let mut message = Message::query();
+        message.add_query(Query::query(name.clone(), RecordType::A));
+        let response = client
+            .send_message(DnsRequest::new(message, DnsRequestOptions::default()))
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+
+        // the empty catalog isn't authoritative for the name
+        assert_eq!(response.message_type(), MessageType::Response);
+        assert_eq!(response.response_code(), ResponseCode::Refused);
+        assert_eq!(response.queries()[0].name(), &name);
+
+        server.shutdown_gracefully().await.unwrap();
+    }
+
+    #[cfg(feature = "__tls")]
+    #[test]
+    fn test_zone_transfer_request() {
+        use crate::proto::{
+            op::{Message, Query},
+            rr::{Name, RecordType},
+            serialize::binary::BinEncodable,
+        };
+
+        let request = |query_type| {
+            let mut message = Message::query();
+            message.add_query(Query::query(
+                Name::from_ascii("example.com.").unwrap(),
+                query_type,
+            ));
+            message.to_bytes().unwrap()
+        };
+
+        for query_type in [RecordType::AXFR, RecordType::IXFR] {
+            let (header, queries) =
+                zone_transfer_request(&request(query_type), DecompressionLimits::default())
+                    .unwrap();
+            assert_eq!(header.query_count(), 1);
+            assert_eq!(queries.queries()[0].query_type(), query_type);
+        }
+        assert!(
+            zone_transfer_request(&request(RecordType::SOA), DecompressionLimits::default())
+                .is_none()
+        );
+        assert!(zone_transfer_request(&[0; 4], DecompressionLimits::default()).is_none());
+    }
+
     #[test]
     fn task_reap_on_empty_joinset() {
         let mut joinset = JoinSet::new();
@@ -11,7 +11,7 @@ use bytes::Bytes;
 use futures_util::lock::Mutex;
 use rustls::server::ResolvesServerCert;
 use tokio::{net, task::JoinSet};
-use tracing::{debug, error, warn};
+use tracing::{debug, debug_span, error, warn};
 
 use super::{
     ResponseInfo, ServerContext, reap_tasks,
@@ -22,7 +22,7 @@ use super::{
 use crate::{
     authority::MessageResponse,
     proto::{
-        ProtoError,
+        ProtoError, ProtoErrorKind,
         quic::{DoqErrorCode, QuicServer, QuicStream, QuicStreams},
         rr::Record,
         xfer::Protocol,
@@ -115,7 +115,15 @@ pub(crate) async fn quic_handler(
             },
         };
 
-        let request = request_stream.receive_bytes().await?;
+        // the client must send a single query on the stream, and then finish it, violations of
+        // this are fatal to the connection
+        let request = match request_stream.receive_bytes().await {
+            Ok(request) => match request_stream.finished().await {
+                Ok(()) => request,
+                Err(err) => return Err(close_on_violation(&quic_streams, err)),
+            },
+            Err(err) => return Err(close_on_violation(&quic_streams, err)),
+        };
 
         debug!(
             "Received bytes {} from {src_addr} {request:?}",
@@ -125,8 +133,12 @@ pub(crate) async fn quic_handler(
         let stream = Arc::new(Mutex::new(request_stream));
         let responder = QuicResponseHandle(stream.clone());
 
-        cx.handle_request(request.freeze(), src_addr, Protocol::Quic, responder)
+        let result = cx
+            .checked_request(request.freeze(), src_addr, Protocol::Quic, responder)
             .await;
+        if let Err(err) = result {
+            return Err(close_on_violation(&quic_streams, err));
+        }
 
         max_requests -= 1;
         if max_requests == 0 {
@@ -141,6 +153,15 @@ pub(crate) async fn quic_handler(
     Ok(())
 }
 
+/// Closes the connection with DOQ_PROTOCOL_ERROR if `error` is a DoQ protocol violation
+fn close_on_violation(quic_streams: &QuicStreams, error: ProtoError) -> ProtoError {
+    if let ProtoErrorKind::DoqProtocol(violation) = error.kind() {
+        quic_streams.close(violation.error_code(), b"Protocol error");
+    }
+
+    error
+}
+
 #[derive(Clone)]
 struct QuicResponseHandle(Arc<Mutex<QuicStream>>);
 
@@ -165,13 +186,13 @@ impl ResponseHandler for QuicResponseHandle {
 
         let id = response.header().id();
         let mut bytes = Vec::with_capacity(512);
-        let info = {
+        let info = debug_span!("encode").in_scope(|| {
             let mut encoder = BinEncoder::new(&mut bytes);
             response.destructive_emit(&mut encoder).or_else(|error| {
                 error!(%error, "error encoding message");
                 encode_fallback_servfail_response(id, &mut bytes)
-            })?
-        };
+            })
+        })?;
         let bytes = Bytes::from(bytes);
 
         debug!("sending quic response: {}", bytes.len());
@@ -0,0 +1,82 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::sync::{Arc, RwLock};
+
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use tracing::info;
+
+/// A certificate and key for the TLS based listeners which can be replaced while the server runs
+///
+/// The certificate is picked for each TLS handshake, so after [`Self::replace`] new connections of
+/// the DoT, DoH, DoQ and DoH3 listeners sharing this resolver present the new certificate, while
+/// established connections are left untouched.
+#[derive(Debug)]
+pub struct ReloadableCertResolver {
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadableCertResolver {
+    /// Creates a resolver presenting `certified_key` until it is replaced
+    pub fn new(certified_key: CertifiedKey) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(certified_key)),
+        }
+    }
+
+    /// Presents `certified_key` in all following TLS handshakes
+    pub fn replace(&self, certified_key: CertifiedKey) {
+        info!("replacing the TLS certificate");
+        *self.current.write().expect("TLS certificate lock poisoned") = Arc::new(certified_key);
+    }
+
+    /// The certificate and key currently presented
+    pub fn current(&self) -> Arc<CertifiedKey> {
+        self.current
+            .read()
+            .expect("TLS certificate lock poisoned")
+            .clone()
+    }
+}
+
+impl ResolvesServerCert for ReloadableCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+
+    use super::*;
+    use crate::proto::rustls::default_provider;
+
+    fn certified_key() -> CertifiedKey {
+        let path = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "../..".to_owned());
+        let cert_chain = CertificateDer::pem_file_iter(format!("{path}/tests/test-data/cert.pem"))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let key = PrivateKeyDer::from_pem_file(format!("{path}/tests/test-data/cert.key")).unwrap();
+
+        CertifiedKey::from_der(cert_chain, key, &default_provider()).unwrap()
+    }
+
+    #[test]
+    fn test_replace() {
+        let resolver = ReloadableCertResolver::new(certified_key());
+        let initial = resolver.current();
+        assert!(Arc::ptr_eq(&initial, &resolver.current()));
+
+        resolver.replace(certified_key());
+        assert!(!Arc::ptr_eq(&initial, &resolver.current()));
+    }
+}
@@ -7,13 +7,13 @@
 
 use std::{io, net::SocketAddr};
 
-use tracing::{debug, error, trace};
+use tracing::{debug, debug_span, error, trace};
 
 use crate::{
     authority::MessageResponse,
     proto::{
         BufDnsStreamHandle, DnsStreamHandle, ProtoError,
-        op::{Header, MessageType, OpCode, ResponseCode},
+        op::{Header, Message, MessageType, OpCode, ResponseCode, Truncator, message},
         rr::Record,
         serialize::binary::BinEncodable,
         serialize::binary::BinEncoder,
@@ -109,23 +109,31 @@ impl ResponseHandler for ResponseHandle {
             "sending response",
         );
         let mut buffer = Vec::with_capacity(512);
-        let encode_result = {
-            let mut encoder = BinEncoder::new(&mut buffer);
-
-            // Set an appropriate maximum on the encoder.
+        let info = debug_span!("encode").in_scope(|| {
             let max_size = self.max_size_for_response(&response);
             trace!(
                 "setting response max size: {max_size} for protocol: {:?}",
                 self.protocol
             );
-            encoder.set_max_size(max_size);
 
-            response.destructive_emit(&mut encoder)
-        };
+            let encode_result = {
+                let mut encoder = BinEncoder::new(&mut buffer);
+                response.destructive_emit(&mut encoder)
+            };
+
+            // drop whole RRsets from the end of the message, rather than whatever records happen
+            //  to exceed the maximum size
+            let encode_result = match encode_result {
+                Ok(_) if buffer.len() > usize::from(max_size) => {
+                    truncate_response(&mut buffer, max_size)
+                }
+                result => result,
+            };
 
-        let info = encode_result.or_else(|error| {
-            error!(%error, "error encoding message");
-            encode_fallback_servfail_response(id, &mut buffer)
+            encode_result.or_else(|error| {
+                error!(%error, "error encoding message");
+                encode_fallback_servfail_response(id, &mut buffer)
+            })
         })?;
 
         self.stream_handle
@@ -136,6 +144,36 @@ impl ResponseHandler for ResponseHandle {
     }
 }
 
+/// Truncates the response encoded in the buffer to `max_size` bytes, and returns a matching
+/// ResponseInfo.
+fn truncate_response(buffer: &mut Vec<u8>, max_size: u16) -> Result<ResponseInfo, ProtoError> {
+    let mut message = Message::from_vec(buffer)?;
+    let truncation = Truncator::new(max_size).truncate(&mut message)?;
+    debug!(
+        max_size,
+        answers = truncation.answers,
+        name_servers = truncation.name_servers,
+        additionals = truncation.additionals,
+        "truncated response",
+    );
+
+    buffer.clear();
+    let mut encoder = BinEncoder::new(buffer);
+    encoder.set_max_size(max_size);
+    let header = message::emit_message_parts(
+        message.header(),
+        &mut message.queries().iter(),
+        &mut message.answers().iter(),
+        &mut message.name_servers().iter(),
+        &mut message.additionals().iter(),
+        message.extensions().as_ref(),
+        message.signature(),
+        &mut encoder,
+    )?;
+
+    Ok(ResponseInfo::from(header))
+}
+
 /// Clears the buffer, encodes a SERVFAIL response in it, and returns a matching
 /// ResponseInfo.
 pub(crate) fn encode_fallback_servfail_response(
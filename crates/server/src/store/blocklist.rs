@@ -14,9 +14,11 @@ use std::{
     fs::File,
     io,
     io::{Error, Read},
+    mem,
     net::{Ipv4Addr, Ipv6Addr},
     path::Path,
     str::FromStr,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
@@ -39,6 +41,7 @@ use crate::{
     },
     resolver::lookup::Lookup,
     server::Request,
+    store::memory::{MemoryBudget, ZoneMemory},
 };
 
 // TODO:
@@ -74,6 +77,7 @@ pub struct BlocklistAuthority {
     ttl: u32,
     block_message: Option<String>,
     consult_action: BlocklistConsultAction,
+    memory: Option<ZoneMemory>,
 }
 
 impl BlocklistAuthority {
@@ -95,6 +99,7 @@ impl BlocklistAuthority {
             ttl: config.ttl,
             block_message: config.block_message.clone(),
             consult_action: config.consult_action,
+            memory: None,
         };
 
         let base_dir = match base_dir {
@@ -205,40 +210,64 @@ impl BlocklistAuthority {
         let mut contents = String::new();
 
         handle.read_to_string(&mut contents)?;
-        for mut entry in contents.lines() {
-            // Strip comments
-            if let Some((item, _)) = entry.split_once('#') {
-                entry = item.trim();
+        let mut names = parse_entries(&contents)
+            .into_iter()
+            .map(LowerName::from)
+            .filter(|name| !self.blocklist.contains_key(name))
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        let reserved = names.iter().map(entry_size).sum();
+        if let Some(memory) = &self.memory {
+            if !memory.try_reserve(&self.origin, reserved) {
+                return Err(Error::other(format!(
+                    "memory limit of blocklist {} reached, {} entries not added",
+                    self.origin,
+                    names.len()
+                )));
             }
+        }
 
-            if entry.is_empty() {
-                continue;
-            }
-
-            let name = match entry.split_once(' ') {
-                Some((ip, domain)) if ip.trim() == "0.0.0.0" && !domain.trim().is_empty() => domain,
-                Some(_) => {
-                    warn!("invalid blocklist entry '{entry}'; skipping entry");
-                    continue;
-                }
-                None => entry,
-            };
-
-            let Ok(mut name) = LowerName::from_str(name) else {
-                warn!("unable to derive LowerName for blocklist entry '{name}'; skipping entry");
-                continue;
-            };
-
+        for name in names {
             trace!("inserting blocklist entry {name}");
 
             // The boolean value is not significant; only the key is used.
-            name.set_fqdn(true);
             self.blocklist.insert(name, true);
         }
 
+        if let Some(memory) = &self.memory {
+            memory.settle(reserved, memory.usage() + reserved);
+        }
+
         Ok(())
     }
 
+    /// Limit the memory used by the entries of the block lists
+    ///
+    /// This enables the accounting of the memory of the entries already added. Block lists added
+    /// later that would exceed `max_memory` bytes, or the memory left of the `budget` shared with
+    /// other zones, are not added. Returns an error if the entries already exceed one of the limits.
+    pub fn set_memory_limits(
+        &mut self,
+        max_memory: Option<u64>,
+        budget: Option<Arc<MemoryBudget>>,
+    ) -> Result<(), String> {
+        let usage = self.blocklist.keys().map(entry_size).sum();
+
+        // return the memory of the previous accounting to its budget first
+        self.memory = None;
+        self.memory = Some(ZoneMemory::new(&self.origin, usage, max_memory, budget)?);
+        Ok(())
+    }
+
+    /// The estimated memory in bytes used by the entries of the block lists
+    ///
+    /// `None` unless the memory is accounted for, see [`Self::set_memory_limits`].
+    pub fn memory_usage(&self) -> Option<u64> {
+        self.memory.as_ref().map(ZoneMemory::usage)
+    }
+
     /// Build a wildcard match list for a given host
     fn wildcards(&self, host: &Name) -> Vec<LowerName> {
         host.iter()
@@ -432,6 +461,47 @@ impl Authority for BlocklistAuthority {
     }
 }
 
+/// Estimates the memory in bytes used by an entry of the block list
+fn entry_size(name: &LowerName) -> u64 {
+    (mem::size_of::<(LowerName, bool)>() + name.len()) as u64
+}
+
+/// Parses the entries of a block list, see [`BlocklistAuthority::add`] for the format
+///
+/// Invalid entries are logged and skipped. The returned names are fully qualified.
+pub(crate) fn parse_entries(contents: &str) -> Vec<Name> {
+    let mut names = Vec::new();
+    for mut entry in contents.lines() {
+        // Strip comments
+        if let Some((item, _)) = entry.split_once('#') {
+            entry = item.trim();
+        }
+
+        if entry.is_empty() {
+            continue;
+        }
+
+        let name = match entry.split_once(' ') {
+            Some((ip, domain)) if ip.trim() == "0.0.0.0" && !domain.trim().is_empty() => domain,
+            Some(_) => {
+                warn!("invalid blocklist entry '{entry}'; skipping entry");
+                continue;
+            }
+            None => entry,
+        };
+
+        let Ok(mut name) = Name::from_str(name) else {
+            warn!("unable to derive LowerName for blocklist entry '{name}'; skipping entry");
+            continue;
+        };
+
+        name.set_fqdn(true);
+        names.push(name);
+    }
+
+    names
+}
+
 /// Consult action enum.  Controls how consult lookups are handled.
 #[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
 pub enum BlocklistConsultAction {
@@ -688,6 +758,49 @@ mod test {
         .await;
     }
 
+    #[test]
+    fn test_blocklist_memory_limit() {
+        subscribe();
+        let config = BlocklistConfig {
+            min_wildcard_depth: 2,
+            wildcard_match: false,
+            lists: vec!["default/blocklist.txt".to_string()],
+            sinkhole_ipv4: None,
+            sinkhole_ipv6: None,
+            block_message: None,
+            ttl: 86_400,
+            consult_action: BlocklistConsultAction::Disabled,
+        };
+
+        let mut blocklist = BlocklistAuthority::try_from_config(
+            Name::root(),
+            &config,
+            Some(Path::new("../../tests/test-data/test_configs/")),
+        )
+        .unwrap();
+        assert!(blocklist.memory_usage().is_none());
+
+        blocklist.set_memory_limits(Some(1), None).unwrap_err();
+        blocklist.set_memory_limits(None, None).unwrap();
+        let usage = blocklist.memory_usage().unwrap();
+        assert!(usage > 0);
+
+        // a single new entry does not fit
+        blocklist.set_memory_limits(Some(usage + 1), None).unwrap();
+        blocklist.add("new.example.com\n".as_bytes()).unwrap_err();
+        assert_eq!(blocklist.memory_usage(), Some(usage));
+        assert!(!blocklist.is_blocked(&LowerName::from_str("new.example.com.").unwrap()));
+
+        // entries already on the block list use no additional memory
+        blocklist.add("foo.com\n".as_bytes()).unwrap();
+        assert_eq!(blocklist.memory_usage(), Some(usage));
+
+        blocklist.set_memory_limits(Some(usage * 2), None).unwrap();
+        blocklist.add("new.example.com\n".as_bytes()).unwrap();
+        assert!(blocklist.memory_usage().unwrap() > usage);
+        assert!(blocklist.is_blocked(&LowerName::from_str("new.example.com.").unwrap()));
+    }
+
     async fn basic_test(
         ao: &Arc<dyn Authority>,
         query: &'static str,
@@ -12,13 +12,15 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use ipnet::IpNet;
 use serde::Deserialize;
 
 #[cfg(feature = "metrics")]
 use crate::store::metrics::StoreMetrics;
 use crate::{
     authority::{
-        AuthLookup, Authority, AxfrPolicy, LookupControlFlow, LookupOptions, UpdateResult, ZoneType,
+        AuthLookup, Authority, AxfrPolicy, LookupControlFlow, LookupOptions, MinimalResponses,
+        UpdateResult, ZoneType,
     },
     proto::{
         op::message::ResponseSigner,
@@ -131,6 +133,16 @@ impl Authority for FileAuthority {
         self.in_memory.axfr_policy()
     }
 
+    /// Which optional records are added to responses from this zone
+    fn minimal_responses(&self) -> MinimalResponses {
+        self.in_memory.minimal_responses()
+    }
+
+    /// Networks of the secondaries which must transfer this zone over TLS
+    fn xot_required(&self) -> &[IpNet] {
+        self.in_memory.xot_required()
+    }
+
     /// Perform a dynamic update of a zone
     async fn update(
         &self,
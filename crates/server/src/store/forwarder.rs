@@ -10,12 +10,23 @@
 //! Forwarding resolver related types
 
 use std::io;
-#[cfg(feature = "__dnssec")]
+#[cfg(feature = "__tls")]
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "__tls")]
+use rustls::{
+    ClientConfig, RootCertStore,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+};
 
 use serde::Deserialize;
-use tracing::{debug, info};
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
 
+#[cfg(feature = "__tls")]
+use crate::proto::rustls::{client_config_builder, default_provider};
 #[cfg(feature = "metrics")]
 use crate::store::metrics::QueryStoreMetrics;
 #[cfg(feature = "__dnssec")]
@@ -23,16 +34,17 @@ use crate::{authority::Nsec3QueryInfo, dnssec::NxProofKind, proto::dnssec::Trust
 use crate::{
     authority::{
         AuthLookup, Authority, AxfrPolicy, LookupControlFlow, LookupError, LookupOptions,
-        UpdateResult, ZoneType,
+        TtlPolicy, UpdateResult, ZoneType,
     },
     proto::{
         op::{ResponseCode, message::ResponseSigner},
-        rr::{LowerName, Name, RecordType},
+        rr::{LowerName, Name, Record, RecordType},
         runtime::TokioRuntimeProvider,
     },
     resolver::{
         Resolver,
         config::{NameServerConfig, ResolveHosts, ResolverConfig, ResolverOpts},
+        lookup::Lookup,
         name_server::ConnectionProvider,
     },
     server::Request,
@@ -101,7 +113,31 @@ impl<P: ConnectionProvider> ForwardAuthorityBuilder<P> {
         } = self;
         info!(%origin, "loading forwarder config");
 
-        let name_servers = config.name_servers;
+        #[cfg_attr(not(feature = "__tls"), allow(unused_mut))]
+        let mut name_servers = config.name_servers;
+        #[cfg(feature = "__tls")]
+        for tls in &config.tls {
+            let tls_config = Arc::new(tls.client_config()?);
+            let connections = name_servers
+                .iter_mut()
+                .flat_map(|ns| &mut ns.connections)
+                .filter(|conn| conn.protocol.server_name().map(|n| &**n) == Some(&*tls.server_name))
+                .collect::<Vec<_>>();
+
+            if connections.is_empty() {
+                return Err(format!(
+                    "no encrypted upstream connections to {} for its TLS settings",
+                    tls.server_name
+                ));
+            }
+
+            for conn in connections {
+                conn.tls_config = Some(tls_config.clone());
+            }
+        }
+
+        let request_timeout = config.request_timeout.map(Duration::from_secs);
+        let ttl_policy = config.ttl_policy;
         let mut options = config.options.unwrap_or_default();
 
         // See RFC 1034, Section 4.3.2:
@@ -116,7 +152,7 @@ impl<P: ConnectionProvider> ForwardAuthorityBuilder<P> {
         // preserve_intermediates enables when set to true, and disables
         // when set to false. So we set it to true.
         if !options.preserve_intermediates {
-            tracing::warn!(
+            warn!(
                 "preserve_intermediates set to false, which is invalid \
                 for a forwarder; switching to true"
             );
@@ -153,6 +189,8 @@ impl<P: ConnectionProvider> ForwardAuthorityBuilder<P> {
         Ok(ForwardAuthority {
             origin: origin.into(),
             resolver,
+            request_timeout,
+            ttl_policy,
             #[cfg(feature = "metrics")]
             metrics: QueryStoreMetrics::new("forwarder"),
         })
@@ -165,6 +203,8 @@ impl<P: ConnectionProvider> ForwardAuthorityBuilder<P> {
 pub struct ForwardAuthority<P: ConnectionProvider = TokioRuntimeProvider> {
     origin: LowerName,
     resolver: Resolver<P>,
+    request_timeout: Option<Duration>,
+    ttl_policy: TtlPolicy,
     #[cfg(feature = "metrics")]
     metrics: QueryStoreMetrics,
 }
@@ -178,6 +218,10 @@ impl<P: ConnectionProvider> ForwardAuthority<P> {
         let forward_config = ForwardConfig {
             name_servers: resolver_config.name_servers().to_owned(),
             options: Some(options),
+            request_timeout: None,
+            ttl_policy: TtlPolicy::default(),
+            #[cfg(feature = "__tls")]
+            tls: Vec::new(),
         };
         let mut builder = Self::builder_with_config(forward_config, runtime);
         if let Some(domain) = resolver_config.domain() {
@@ -199,6 +243,24 @@ impl<P: ConnectionProvider> ForwardAuthority<P> {
             trust_anchor: None,
         }
     }
+
+    /// Applies the TTL policy to the records of an upstream lookup
+    fn bound_ttls(&self, lookup: Lookup) -> Lookup {
+        if self.ttl_policy.is_unbounded() {
+            return lookup;
+        }
+
+        let records = lookup
+            .records()
+            .iter()
+            .map(|record| {
+                let mut record = record.clone();
+                record.set_ttl(self.ttl_policy.apply(record.ttl()));
+                record
+            })
+            .collect::<Arc<[Record]>>();
+        Lookup::new_with_deadline(lookup.query().clone(), records, lookup.valid_until())
+    }
 }
 
 impl ForwardAuthority<TokioRuntimeProvider> {
@@ -265,12 +327,26 @@ impl<P: ConnectionProvider> Authority for ForwardAuthority<P> {
         let mut name: Name = name.clone().into();
         name.set_fqdn(false);
 
-        use LookupControlFlow::*;
-        let lookup = match self.resolver.lookup(name, rtype).await {
-            Ok(lookup) => Continue(Ok(AuthLookup::from(lookup))),
-            Err(e) => Continue(Err(LookupError::from(e))),
+        // abandon the upstream lookup once the request deadline has passed, rather than letting
+        // the lookups of client retries stack up
+        let result = match self.request_timeout {
+            Some(deadline) => match timeout(deadline, self.resolver.lookup(name, rtype)).await {
+                Ok(result) => result.map_err(LookupError::from),
+                Err(_) => {
+                    warn!("forwarded lookup exceeded the request timeout of {deadline:?}");
+                    Err(LookupError::Timeout)
+                }
+            },
+            None => self
+                .resolver
+                .lookup(name, rtype)
+                .await
+                .map_err(LookupError::from),
         };
 
+        use LookupControlFlow::*;
+        let lookup = Continue(result.map(|lookup| AuthLookup::from(self.bound_ttls(lookup))));
+
         #[cfg(feature = "metrics")]
         self.metrics.increment_lookup(&lookup);
 
@@ -335,4 +411,86 @@ pub struct ForwardConfig {
     pub name_servers: Vec<NameServerConfig>,
     /// Resolver options
     pub options: Option<ResolverOpts>,
+    /// Deadline in seconds for answering a forwarded request, after which the upstream lookup is
+    /// abandoned and SERVFAIL is returned
+    ///
+    /// This bounds the time spent on a request across all the upstream attempts configured in the
+    /// resolver options.
+    #[serde(default)]
+    pub request_timeout: Option<u64>,
+    /// Bounds of the TTLs of the records forwarded from the upstream name servers, e.g. to cap
+    /// huge TTLs or raise zero TTLs
+    ///
+    /// This applies to the answers sent to clients, whereas the TTL bounds of the resolver
+    /// options only apply to the cache of the forwarder.
+    #[serde(default)]
+    pub ttl_policy: TtlPolicy,
+    /// TLS settings of encrypted upstreams, which are otherwise verified with the default roots
+    #[cfg(feature = "__tls")]
+    #[serde(default)]
+    pub tls: Vec<ForwardTlsConfig>,
+}
+
+/// TLS settings for the encrypted connections to an upstream name server
+#[cfg(feature = "__tls")]
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ForwardTlsConfig {
+    /// The server name of the TLS, HTTPS or QUIC connections to the upstream these settings apply to
+    pub server_name: String,
+    /// Path to the PEM encoded CA certificates to verify the upstream with, instead of the default
+    /// roots
+    pub ca_file: Option<PathBuf>,
+    /// Path to the PEM encoded certificate chain to authenticate to the upstream with
+    pub client_cert: Option<PathBuf>,
+    /// Path to the PEM encoded private key of `client_cert`
+    pub client_key: Option<PathBuf>,
+}
+
+#[cfg(feature = "__tls")]
+impl ForwardTlsConfig {
+    /// Builds the TLS client configuration for the connections to the upstream
+    pub fn client_config(&self) -> Result<ClientConfig, String> {
+        let builder = match &self.ca_file {
+            Some(ca_file) => {
+                let mut roots = RootCertStore::empty();
+                for cert in read_certs(ca_file)? {
+                    roots.add(cert).map_err(|e| {
+                        format!("invalid CA certificate in {}: {e}", ca_file.display())
+                    })?;
+                }
+
+                ClientConfig::builder_with_provider(Arc::new(default_provider()))
+                    .with_safe_default_protocol_versions()
+                    .map_err(|e| format!("failed to configure TLS: {e}"))?
+                    .with_root_certificates(roots)
+            }
+            None => client_config_builder(),
+        };
+
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                let key = PrivateKeyDer::from_pem_file(key)
+                    .map_err(|e| format!("failed to read key from {}: {e}", key.display()))?;
+                builder
+                    .with_client_auth_cert(read_certs(cert)?, key)
+                    .map_err(|e| {
+                        format!("invalid client certificate for {}: {e}", self.server_name)
+                    })
+            }
+            (None, None) => Ok(builder.with_no_client_auth()),
+            _ => Err(format!(
+                "both client_cert and client_key must be set for {}",
+                self.server_name
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "__tls")]
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    CertificateDer::pem_file_iter(path)
+        .map_err(|e| format!("failed to read certificates from {}: {e}", path.display()))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("failed to parse certificates from {}: {e}", path.display()))
 }
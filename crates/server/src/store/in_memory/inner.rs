@@ -1,12 +1,9 @@
 #[cfg(feature = "__dnssec")]
 use std::collections::{BTreeSet, HashMap, hash_map::Entry};
-use std::{
-    collections::{BTreeMap, HashSet},
-    sync::Arc,
-};
+use std::{collections::HashSet, sync::Arc};
 
 use cfg_if::cfg_if;
-#[cfg(feature = "__dnssec")]
+#[cfg(any(feature = "__dnssec", feature = "sqlite", feature = "blocklist"))]
 use time::OffsetDateTime;
 #[cfg(feature = "__dnssec")]
 use tracing::debug;
@@ -25,9 +22,13 @@ use crate::{
     },
 };
 
-use super::maybe_next_name;
+#[cfg(feature = "__dnssec")]
+use super::NameMatch;
+use super::{ZoneTree, maybe_next_name};
+#[cfg(any(feature = "__dnssec", feature = "sqlite", feature = "blocklist"))]
+use crate::authority::SerialPolicy;
 use crate::{
-    authority::LookupOptions,
+    authority::{AuthLookup, LookupOptions, LookupRecords},
     proto::rr::{
         DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey, rdata::SOA,
     },
@@ -35,7 +36,7 @@ use crate::{
 
 #[derive(Default)]
 pub(super) struct InnerInMemory {
-    pub(super) records: BTreeMap<RrKey, Arc<RecordSet>>,
+    pub(super) records: ZoneTree,
     // Private key mapped to the Record of the DNSKey
     //  TODO: these private_keys should be stored securely. Ideally, we have keys only stored per
     //   server instance, but that requires requesting updates from the parent zone, which may or
@@ -97,10 +98,8 @@ impl InnerInMemory {
             records.push(cover);
         }
 
-        let wildcard_match = {
-            let wildcard = qname.clone().into_wildcard();
-            self.records.keys().any(|rr_key| rr_key.name == wildcard)
-        };
+        // the wildcard at the closest encloser exists, but does not own the type
+        let wildcard_match = matches!(self.records.match_name(qname), NameMatch::Wildcard { .. });
 
         if wildcard_match {
             let wildcard_at_closest_encloser = next_closer_name.into_wildcard();
@@ -124,37 +123,78 @@ impl InnerInMemory {
         Ok(records)
     }
 
+    /// Selects the NSEC records proving a negative or wildcard answer for `name`, see RFC 4035
+    /// section 3.1.3
+    ///
+    /// * for a name that exists, the NSEC record matching it, which lists the types it owns
+    /// * for an empty non-terminal, the NSEC record covering it
+    /// * for a name synthesized from a wildcard, the NSEC record covering the name, and the one
+    ///   matching the wildcard
+    /// * for a name that does not exist, the NSEC records covering the name and the wildcard at
+    ///   its closest encloser
     #[cfg(feature = "__dnssec")]
-    pub(super) fn closest_nsec(&self, name: &LowerName) -> Option<Arc<RecordSet>> {
-        for rr_set in self.records.values().rev() {
-            if rr_set.record_type() != RecordType::NSEC {
-                continue;
-            }
+    pub(super) fn nsec_proof(&self, name: &LowerName) -> Vec<Arc<RecordSet>> {
+        let matching_nsec = |name: LowerName| {
+            self.records
+                .get(&RrKey::new(name, RecordType::NSEC))
+                .cloned()
+        };
 
-            if *name < rr_set.name().into() {
-                continue;
+        // the wildcard child of the closest encloser, the source of synthesis
+        let wildcard = |closest_encloser: LowerName| {
+            closest_encloser
+                .prepend_label("*")
+                .ok()
+                .map(LowerName::from)
+        };
+
+        let mut proofs = match self.records.match_name(name) {
+            NameMatch::Exact => {
+                vec![matching_nsec(name.clone()).or_else(|| self.closest_nsec(name))]
             }
+            NameMatch::EmptyNonTerminal => vec![self.closest_nsec(name)],
+            NameMatch::Wildcard { closest_encloser } => vec![
+                self.closest_nsec(name),
+                wildcard(closest_encloser).and_then(matching_nsec),
+            ],
+            NameMatch::NxDomain { closest_encloser } => vec![
+                self.closest_nsec(name),
+                wildcard(closest_encloser).and_then(|wildcard| self.closest_nsec(&wildcard)),
+            ],
+        }
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        proofs.dedup();
+        proofs
+    }
 
-            // there should only be one record
-            let Some(record) = rr_set.records(false).next() else {
-                continue;
-            };
+    #[cfg(feature = "__dnssec")]
+    pub(super) fn closest_nsec(&self, name: &LowerName) -> Option<Arc<RecordSet>> {
+        // only the records at or before the name need to be searched
+        self.records
+            .rfind_at_or_before(name, |_, rr_set| {
+                if rr_set.record_type() != RecordType::NSEC {
+                    return false;
+                }
 
-            let RData::DNSSEC(DNSSECRData::NSEC(nsec)) = record.data() else {
-                continue;
-            };
+                // there should only be one record
+                let Some(record) = rr_set.records(false).next() else {
+                    return false;
+                };
 
-            let next_domain_name = nsec.next_domain_name();
-            // the search name is less than the next NSEC record
-            if *name < next_domain_name.into() ||
-                // this is the last record, and wraps to the beginning of the zone
-                next_domain_name < rr_set.name()
-            {
-                return Some(rr_set.clone());
-            }
-        }
+                let RData::DNSSEC(DNSSECRData::NSEC(nsec)) = record.data() else {
+                    return false;
+                };
 
-        None
+                let next_domain_name = nsec.next_domain_name();
+                // the search name is less than the next NSEC record
+                *name < next_domain_name.into() ||
+                    // this is the last record, and wraps to the beginning of the zone
+                    next_domain_name < rr_set.name()
+            })
+            .map(|(_, rr_set)| rr_set.clone())
     }
 
     fn inner_soa(&self, origin: &LowerName) -> Option<&SOA> {
@@ -196,89 +236,64 @@ impl InnerInMemory {
         record_type: RecordType,
         lookup_options: LookupOptions,
     ) -> Option<Arc<RecordSet>> {
-        // this range covers all the records for any of the RecordTypes at a given label.
-        let start_range_key = RrKey::new(name.clone(), RecordType::Unknown(u16::MIN));
-        let end_range_key = RrKey::new(name.clone(), RecordType::Unknown(u16::MAX));
-
         fn aname_covers_type(key_type: RecordType, query_type: RecordType) -> bool {
             (query_type == RecordType::A || query_type == RecordType::AAAA)
                 && key_type == RecordType::ANAME
         }
 
-        let lookup = self
-            .records
-            .range(&start_range_key..&end_range_key)
+        // a single descent of the tree finds the name, or the wildcards of its ancestors
+        let (rrset, wildcard) = self.records.find_with_wildcard(name, |key_type| {
             // remember CNAME can be the only record at a particular label
-            .find(|(key, _)| {
-                key.record_type == record_type
-                    || key.record_type == RecordType::CNAME
-                    || aname_covers_type(key.record_type, record_type)
-            })
-            .map(|(_key, rr_set)| rr_set);
+            key_type == record_type
+                || key_type == RecordType::CNAME
+                || aname_covers_type(key_type, record_type)
+        })?;
 
-        // TODO: maybe unwrap this recursion.
-        match lookup {
-            None => self.inner_lookup_wildcard(name, record_type, lookup_options),
-            l => l.cloned(),
+        match wildcard {
+            false => Some(rrset.clone()),
+            true => Some(Self::expand_wildcard(name, rrset, lookup_options)),
         }
     }
 
-    fn inner_lookup_wildcard(
-        &self,
+    /// Returns a copy of a record set found at a wildcard, owned by the query name
+    #[cfg_attr(not(feature = "__dnssec"), allow(unused_variables))]
+    fn expand_wildcard(
         name: &LowerName,
-        record_type: RecordType,
+        rrset: &RecordSet,
         lookup_options: LookupOptions,
-    ) -> Option<Arc<RecordSet>> {
-        // if this is a wildcard or a root, both should break continued lookups
-        if name.is_wildcard() || name.is_root() {
-            return None;
-        }
-
-        let mut wildcard = name.clone().into_wildcard();
-        loop {
-            let Some(rrset) = self.inner_lookup(&wildcard, record_type, lookup_options) else {
-                let parent = wildcard.base_name();
-                if parent.is_root() {
-                    return None;
-                }
-
-                wildcard = parent.into_wildcard();
-                continue;
-            };
-
-            // we need to change the name to the query name in the result set since this was a wildcard
-            let mut new_answer =
-                RecordSet::with_ttl(Name::from(name), rrset.record_type(), rrset.ttl());
-
-            #[allow(clippy::needless_late_init)]
-            let records;
-            #[allow(clippy::needless_late_init)]
-            let _rrsigs: Vec<&Record>;
-            cfg_if! {
-                if #[cfg(feature = "__dnssec")] {
-                    let (records_tmp, rrsigs_tmp) = rrset
-                        .records(lookup_options.dnssec_ok())
-                        .partition(|r| r.record_type() != RecordType::RRSIG);
-                    records = records_tmp;
-                    _rrsigs = rrsigs_tmp;
-                } else {
-                    let (records_tmp, rrsigs_tmp) = (rrset.records_without_rrsigs(), Vec::with_capacity(0));
-                    records = records_tmp;
-                    _rrsigs = rrsigs_tmp;
-                }
-            };
-
-            for record in records {
-                new_answer.add_rdata(record.data().clone());
+    ) -> Arc<RecordSet> {
+        // we need to change the name to the query name in the result set since this was a wildcard
+        let mut new_answer =
+            RecordSet::with_ttl(Name::from(name), rrset.record_type(), rrset.ttl());
+
+        #[allow(clippy::needless_late_init)]
+        let records;
+        #[allow(clippy::needless_late_init)]
+        let _rrsigs: Vec<&Record>;
+        cfg_if! {
+            if #[cfg(feature = "__dnssec")] {
+                let (records_tmp, rrsigs_tmp) = rrset
+                    .records(lookup_options.dnssec_ok())
+                    .partition(|r| r.record_type() != RecordType::RRSIG);
+                records = records_tmp;
+                _rrsigs = rrsigs_tmp;
+            } else {
+                let (records_tmp, rrsigs_tmp) = (rrset.records_without_rrsigs(), Vec::with_capacity(0));
+                records = records_tmp;
+                _rrsigs = rrsigs_tmp;
             }
+        };
 
-            #[cfg(feature = "__dnssec")]
-            for rrsig in _rrsigs {
-                new_answer.insert_rrsig(rrsig.clone())
-            }
+        for record in records {
+            new_answer.add_rdata(record.data().clone());
+        }
 
-            return Some(Arc::new(new_answer));
+        #[cfg(feature = "__dnssec")]
+        for rrsig in _rrsigs {
+            new_answer.insert_rrsig(rrsig.clone())
         }
+
+        Arc::new(new_answer)
     }
 
     /// Search for additional records to include in the response
@@ -351,8 +366,69 @@ impl InnerInMemory {
         }
     }
 
-    #[cfg(any(feature = "__dnssec", feature = "sqlite"))]
-    pub(super) fn increment_soa_serial(&mut self, origin: &LowerName, dns_class: DNSClass) -> u32 {
+    /// Returns a referral if `name` is at or below a delegation point of the zone
+    ///
+    /// The glue holds the addresses of the name servers within the zone, addresses of name servers
+    /// outside of the zone are never included, those must be resolved from their own zones.
+    ///
+    /// # Arguments
+    ///
+    /// * origin - the origin of the zone
+    /// * name - the name being looked up
+    /// * query_type - the type of the query, DS records at the delegation point are answered
+    ///   by the zone itself
+    /// * lookup_options - Query-related lookup options (e.g., DNSSEC DO bit, supported hash
+    ///   algorithms, etc.)
+    pub(super) fn referral(
+        &self,
+        origin: &LowerName,
+        name: &LowerName,
+        query_type: RecordType,
+        lookup_options: LookupOptions,
+    ) -> Option<AuthLookup> {
+        let ns = self.records.find_delegation(origin, name)?;
+        let cut = LowerName::from(ns.name());
+        if query_type == RecordType::DS && &cut == name {
+            return None;
+        }
+
+        let mut delegation = vec![ns.clone()];
+        if lookup_options.dnssec_ok() {
+            if let Some(ds) = self.records.get(&RrKey::new(cut, RecordType::DS)) {
+                delegation.push(ds.clone());
+            }
+        }
+
+        let mut glue = Vec::new();
+        let targets = ns
+            .records_without_rrsigs()
+            .filter_map(|record| record.data().as_ns())
+            .map(|ns| LowerName::from(&ns.0))
+            .filter(|target| origin.zone_of(target));
+        for target in targets {
+            for record_type in [RecordType::A, RecordType::AAAA] {
+                if let Some(addresses) = self.records.get(&RrKey::new(target.clone(), record_type))
+                {
+                    if !glue.contains(addresses) {
+                        glue.push(addresses.clone());
+                    }
+                }
+            }
+        }
+
+        Some(AuthLookup::Referral {
+            ns: LookupRecords::many(lookup_options, delegation),
+            glue: (!glue.is_empty()).then(|| LookupRecords::many(lookup_options, glue)),
+        })
+    }
+
+    #[cfg(any(feature = "__dnssec", feature = "sqlite", feature = "blocklist"))]
+    pub(super) fn increment_soa_serial(
+        &mut self,
+        origin: &LowerName,
+        dns_class: DNSClass,
+        serial_policy: SerialPolicy,
+    ) -> u32 {
         // we'll remove the SOA and then replace it
         let rr_key = RrKey::new(origin.clone(), RecordType::SOA);
         let record = self
@@ -369,7 +445,7 @@ impl InnerInMemory {
         };
 
         let serial = if let RData::SOA(soa_rdata) = record.data_mut() {
-            soa_rdata.increment_serial();
+            soa_rdata.set_serial(serial_policy.next(soa_rdata.serial(), OffsetDateTime::now_utc()));
             soa_rdata.serial()
         } else {
             panic!("This was not an SOA record"); // valid panic, never should happen
@@ -429,12 +505,10 @@ impl InnerInMemory {
         }
 
         // check that CNAME and ANAME is either not already present, or no other records are if it's a CNAME
-        let start_range_key = RrKey::new(record.name().into(), RecordType::Unknown(u16::MIN));
-        let end_range_key = RrKey::new(record.name().into(), RecordType::Unknown(u16::MAX));
-
+        let name = LowerName::from(record.name());
         let multiple_records_at_label_disallowed = self
             .records
-            .range(&start_range_key..&end_range_key)
+            .rrsets_at(&name)
             // remember CNAME can be the only record at a particular label
             .any(|(key, _)| {
                 !is_nsec(record.record_type(), key.record_type)
@@ -450,8 +524,8 @@ impl InnerInMemory {
             return false;
         }
 
-        let rr_key = RrKey::new(record.name().into(), record.record_type());
-        let records: &mut Arc<RecordSet> = self.records.entry(rr_key).or_insert_with(|| {
+        let rr_key = RrKey::new(name, record.record_type());
+        let records: &mut Arc<RecordSet> = self.records.get_or_insert_with(rr_key, || {
             Arc::new(RecordSet::new(
                 record.name().clone(),
                 record.record_type(),
@@ -476,6 +550,7 @@ impl InnerInMemory {
         origin: &LowerName,
         dns_class: DNSClass,
         nx_proof_kind: Option<&NxProofKind>,
+        serial_policy: SerialPolicy,
     ) -> DnsSecResult<()> {
         // TODO: only call nsec_zone after adds/deletes
         // needs to be called before incrementing the soa serial, to make sure IXFR works properly
@@ -492,7 +567,7 @@ impl InnerInMemory {
 
         // need to resign any records at the current serial number and bump the number.
         // first bump the serial number on the SOA, so that it is resigned with the new serial.
-        self.increment_soa_serial(origin, dns_class);
+        self.increment_soa_serial(origin, dns_class, serial_policy);
 
         // TODO: should we auto sign here? or maybe up a level...
         self.sign_zone(origin, dns_class)
@@ -784,22 +859,21 @@ impl InnerInMemory {
         info: &Nsec3QueryInfo<'_>,
     ) -> Result<Option<Arc<RecordSet>>, ProtoError> {
         let owner_name = info.get_hashed_owner_name(name, zone)?;
-        let records = self
-            .records
-            .values()
-            .filter(|rr_set| rr_set.record_type() == RecordType::NSEC3);
 
         // Find the record with the largest owner name such that its owner name is before the
         // hashed QNAME. If this record exist, it already covers QNAME. Otherwise, the QNAME
         // preceeds all the existing NSEC3 records' owner names, meaning that it is covered by
         // the NSEC3 record with the largest owner name.
-        Ok(records
-            .clone()
-            .filter(|rr_set| rr_set.record_type() == RecordType::NSEC3)
-            .filter(|rr_set| rr_set.name() < &*owner_name)
-            .max_by_key(|rr_set| rr_set.name())
-            .or_else(|| records.max_by_key(|rr_set| rr_set.name()))
-            .cloned())
+        Ok(self
+            .records
+            .rfind_at_or_before(&owner_name, |key, _| {
+                key.record_type == RecordType::NSEC3 && key.name != owner_name
+            })
+            .or_else(|| {
+                self.records
+                    .rfind(|key, _| key.record_type == RecordType::NSEC3)
+            })
+            .map(|(_, rr_set)| rr_set.clone()))
     }
 
     /// Return the next closer name and the record that matches the closest encloser of a given name.
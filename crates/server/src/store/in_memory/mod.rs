@@ -9,29 +9,35 @@
 
 use std::{
     collections::BTreeMap,
-    fs,
+    fs, mem,
+    net::IpAddr,
     ops::{Deref, DerefMut},
     path::Path,
     sync::Arc,
 };
 
+use ipnet::IpNet;
 use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
-#[cfg(feature = "__dnssec")]
-use tracing::warn;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::{
     authority::{
         AnyRecords, AuthLookup, Authority, AxfrPolicy, LookupControlFlow, LookupError,
-        LookupOptions, LookupRecords, UpdateResult, ZoneType,
+        LookupOptions, LookupRecords, MinimalResponses, SerialPolicy, TtlPolicy, UpdateResult,
+        ZoneType,
     },
     proto::{
         op::ResponseCode,
         op::message::ResponseSigner,
-        rr::{DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey, rdata::SOA},
+        rr::{
+            DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey,
+            rdata::{A, AAAA, SOA},
+            zone_check::check_zone,
+        },
         serialize::txt::Parser,
     },
     server::Request,
+    store::memory::{MemoryBudget, MemoryReservation, ZoneMemory, record_size, rrset_size},
 };
 #[cfg(feature = "__dnssec")]
 use crate::{
@@ -45,6 +51,10 @@ use crate::{
 
 mod inner;
 use inner::InnerInMemory;
+mod tree;
+pub use tree::{NameMatch, ZoneTree};
+mod zone_writer;
+pub use zone_writer::ZoneWriter;
 
 /// InMemoryAuthority is responsible for storing the resource records for a particular zone.
 ///
@@ -55,6 +65,11 @@ pub struct InMemoryAuthority {
     class: DNSClass,
     zone_type: ZoneType,
     axfr_policy: AxfrPolicy,
+    minimal_responses: MinimalResponses,
+    serial_policy: SerialPolicy,
+    ttl_policy: TtlPolicy,
+    xot_required: Vec<IpNet>,
+    memory: Option<ZoneMemory>,
     inner: RwLock<InnerInMemory>,
     #[cfg(feature = "__dnssec")]
     nx_proof_kind: Option<NxProofKind>,
@@ -101,6 +116,20 @@ impl InMemoryAuthority {
             .map(SOA::serial)
             .ok_or_else(|| format!("SOA record must be present: {origin}"))?;
 
+        let (errors, warnings) = check_zone(
+            &origin,
+            records.values().flat_map(RecordSet::records_without_rrsigs),
+        )
+        .into_iter()
+        .partition::<Vec<_>, _>(|issue| issue.is_error());
+        for issue in warnings {
+            warn!(%origin, %issue, "zone check");
+        }
+        if !errors.is_empty() {
+            let errors = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+            return Err(format!("invalid zone {origin}: {}", errors.join("; ")));
+        }
+
         let iter = records.into_values();
 
         // add soa to the records
@@ -136,6 +165,11 @@ impl InMemoryAuthority {
             class: DNSClass::IN,
             zone_type,
             axfr_policy,
+            minimal_responses: MinimalResponses::default(),
+            serial_policy: SerialPolicy::default(),
+            ttl_policy: TtlPolicy::default(),
+            xot_required: Vec::new(),
+            memory: None,
             inner: RwLock::new(InnerInMemory::default()),
 
             #[cfg(feature = "__dnssec")]
@@ -154,6 +188,121 @@ impl InMemoryAuthority {
         self.axfr_policy = policy;
     }
 
+    /// Set which optional records are added to responses
+    pub fn set_minimal_responses(&mut self, minimal_responses: MinimalResponses) {
+        self.minimal_responses = minimal_responses;
+    }
+
+    /// Set the networks of the secondaries which must transfer the zone over TLS
+    pub fn set_xot_required(&mut self, xot_required: Vec<IpNet>) {
+        self.xot_required = xot_required;
+    }
+
+    /// Set how the SOA serial is advanced after dynamic updates and when the zone is re-signed
+    pub fn set_serial_policy(&mut self, serial_policy: SerialPolicy) {
+        self.serial_policy = serial_policy;
+    }
+
+    /// Set the bounds of the TTLs of the records in the zone
+    ///
+    /// The TTLs of the records already in the zone are adjusted, as are those of the records
+    /// inserted later on, e.g. by dynamic updates.
+    pub fn set_ttl_policy(&mut self, ttl_policy: TtlPolicy) {
+        self.ttl_policy = ttl_policy;
+        if ttl_policy.is_unbounded() {
+            return;
+        }
+
+        for rrset in self.inner.get_mut().records.values_mut() {
+            let ttl = ttl_policy.apply(rrset.ttl());
+            if ttl != rrset.ttl() {
+                Arc::make_mut(rrset).set_ttl(ttl);
+            }
+        }
+    }
+
+    /// Limit the memory used by the records of the zone
+    ///
+    /// This enables the accounting of the memory of the zone, starting with the records already in
+    /// it, so it should be called once the zone is loaded and signed. Dynamic updates that would
+    /// exceed `max_memory` bytes, or the memory left of the `budget` shared with other zones, are
+    /// refused. Returns an error if the zone already exceeds one of the limits.
+    pub fn set_memory_limits(
+        &mut self,
+        max_memory: Option<u64>,
+        budget: Option<Arc<MemoryBudget>>,
+    ) -> Result<(), String> {
+        let mut buffer = Vec::new();
+        let usage = self
+            .inner
+            .get_mut()
+            .records
+            .values()
+            .map(|rrset| rrset_size(rrset, &mut buffer))
+            .sum();
+
+        // return the memory of the previous accounting to its budget first
+        self.memory = None;
+        self.memory = Some(ZoneMemory::new(&self.origin, usage, max_memory, budget)?);
+        Ok(())
+    }
+
+    /// The estimated memory in bytes used by the records of the zone
+    ///
+    /// `None` unless the memory is accounted for, see [`Self::set_memory_limits`].
+    pub fn memory_usage(&self) -> Option<u64> {
+        self.memory.as_ref().map(ZoneMemory::usage)
+    }
+
+    /// Reserves the memory for the records added by a dynamic update before it is applied
+    ///
+    /// Returns `Err(ResponseCode::Refused)` if the update could exceed the memory limits of the
+    /// zone, and `Ok(None)` if the memory of the zone is not accounted for. The reservation must
+    /// be passed to [`Self::settle_memory`] once the update is applied, whether it succeeded or not.
+    pub fn reserve_memory(&self, records: &[Record]) -> UpdateResult<Option<MemoryReservation>> {
+        let Some(memory) = &self.memory else {
+            return Ok(None);
+        };
+
+        let mut buffer = Vec::new();
+        let reserved = records
+            .iter()
+            .filter(|record| record.dns_class() == self.class)
+            .map(|record| {
+                record_size(record, &mut buffer)
+                    + (mem::size_of::<RrKey>() + mem::size_of::<RecordSet>()) as u64
+            })
+            .sum();
+
+        match memory.try_reserve(&self.origin, reserved) {
+            true => Ok(Some(MemoryReservation { reserved })),
+            false => Err(ResponseCode::Refused),
+        }
+    }
+
+    /// Accounts for the actual change of the memory of the zone made by an update
+    ///
+    /// The zone is measured while holding its write lock, so that the updates settled one after
+    /// another each account for the zone as all previous ones left it. Signing changes records
+    /// that were not part of the update, which is why the whole zone is measured.
+    pub async fn settle_memory(&self, reservation: Option<MemoryReservation>) {
+        let Some(memory) = &self.memory else {
+            return;
+        };
+
+        let inner = self.inner.write().await;
+        let mut buffer = Vec::new();
+        let measured = inner
+            .records
+            .values()
+            .map(|rrset| rrset_size(rrset, &mut buffer))
+            .sum();
+        memory.settle(
+            reservation.map_or(0, |reservation| reservation.reserved),
+            measured,
+        );
+    }
+
     /// Clears all records (including SOA, etc)
     pub fn clear(&mut self) {
         self.inner.get_mut().records.clear()
@@ -166,19 +315,17 @@ impl InMemoryAuthority {
     }
 
     /// Get all the records
-    pub async fn records(&self) -> impl Deref<Target = BTreeMap<RrKey, Arc<RecordSet>>> + '_ {
+    pub async fn records(&self) -> impl Deref<Target = ZoneTree> + '_ {
         RwLockReadGuard::map(self.inner.read().await, |i| &i.records)
     }
 
     /// Get a mutable reference to the records
-    pub async fn records_mut(
-        &self,
-    ) -> impl DerefMut<Target = BTreeMap<RrKey, Arc<RecordSet>>> + '_ {
+    pub async fn records_mut(&self) -> impl DerefMut<Target = ZoneTree> + '_ {
         RwLockWriteGuard::map(self.inner.write().await, |i| &mut i.records)
     }
 
     /// Get a mutable reference to the records
-    pub fn records_get_mut(&mut self) -> &mut BTreeMap<RrKey, Arc<RecordSet>> {
+    pub fn records_get_mut(&mut self) -> &mut ZoneTree {
         &mut self.inner.get_mut().records
     }
 
@@ -192,12 +339,23 @@ impl InMemoryAuthority {
         self.inner.read().await.serial(self.origin())
     }
 
-    #[cfg(any(feature = "__dnssec", feature = "sqlite"))]
+    /// Serializes the zone to a normalized zone file, see [`ZoneWriter`]
+    ///
+    /// The SOA minimum TTL is written as the `$TTL` default.
+    pub async fn write_zone_file(&self, out: &mut impl std::fmt::Write) -> std::fmt::Result {
+        let inner = self.inner.read().await;
+        let origin = Name::from(self.origin());
+        ZoneWriter::new(&origin)
+            .default_ttl(inner.minimum_ttl(self.origin()))
+            .write(out, inner.records.values().map(|rrset| &**rrset))
+    }
+
+    #[cfg(any(feature = "__dnssec", feature = "sqlite", feature = "blocklist"))]
     pub(crate) async fn increment_soa_serial(&self) -> u32 {
         self.inner
             .write()
             .await
-            .increment_soa_serial(self.origin(), self.class)
+            .increment_soa_serial(self.origin(), self.class, self.serial_policy)
     }
 
     /// Inserts or updates a `Record` depending on its existence in the authority.
@@ -212,15 +370,78 @@ impl InMemoryAuthority {
     /// # Return value
     ///
     /// true if the value was inserted, false otherwise
-    pub async fn upsert(&self, record: Record, serial: u32) -> bool {
+    pub async fn upsert(&self, mut record: Record, serial: u32) -> bool {
+        record.set_ttl(self.ttl_policy.apply(record.ttl()));
         self.inner.write().await.upsert(record, serial, self.class)
     }
 
     /// Non-async version of upsert when behind a mutable reference.
-    pub fn upsert_mut(&mut self, record: Record, serial: u32) -> bool {
+    pub fn upsert_mut(&mut self, mut record: Record, serial: u32) -> bool {
+        record.set_ttl(self.ttl_policy.apply(record.ttl()));
         self.inner.get_mut().upsert(record, serial, self.class)
     }
 
+    /// Registers an address of a name server within the zone
+    ///
+    /// The addresses of the name servers of a delegation are added to referrals as glue. Only
+    /// names within the zone are accepted, addresses of other name servers are never served.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the name server, e.g. from the NS records of a delegation.
+    /// * `address` - An IPv4 or IPv6 address of the name server.
+    /// * `ttl` - The TTL of the address record.
+    ///
+    /// # Return value
+    ///
+    /// true if the address was added, false otherwise
+    pub async fn add_glue(&self, name: Name, address: IpAddr, ttl: u32) -> bool {
+        Self::inner_add_glue(
+            &mut *self.inner.write().await,
+            name,
+            address,
+            ttl,
+            &self.origin,
+            self.class,
+        )
+    }
+
+    /// Non-async version of add_glue when behind a mutable reference.
+    pub fn add_glue_mut(&mut self, name: Name, address: IpAddr, ttl: u32) -> bool {
+        let Self {
+            origin,
+            inner,
+            class,
+            ..
+        } = self;
+
+        Self::inner_add_glue(inner.get_mut(), name, address, ttl, origin, *class)
+    }
+
+    fn inner_add_glue(
+        inner: &mut InnerInMemory,
+        name: Name,
+        address: IpAddr,
+        ttl: u32,
+        origin: &LowerName,
+        dns_class: DNSClass,
+    ) -> bool {
+        if !origin.zone_of(&LowerName::from(&name)) {
+            warn!(%name, %origin, "glue is not within the zone");
+            return false;
+        }
+
+        let rdata = match address {
+            IpAddr::V4(address) => RData::A(A::from(address)),
+            IpAddr::V6(address) => RData::AAAA(AAAA::from(address)),
+        };
+        let mut record = Record::from_rdata(name, ttl, rdata);
+        record.set_dns_class(dns_class);
+
+        let serial = inner.serial(origin);
+        inner.upsert(record, serial, dns_class)
+    }
+
     /// Add a (Sig0) key that is authorized to perform updates against this authority
     #[cfg(feature = "__dnssec")]
     fn inner_add_update_auth_key(
@@ -303,9 +524,12 @@ impl InMemoryAuthority {
     #[cfg(feature = "__dnssec")]
     pub fn secure_zone_mut(&mut self) -> DnsSecResult<()> {
         let Self { origin, inner, .. } = self;
-        inner
-            .get_mut()
-            .secure_zone_mut(origin, self.class, self.nx_proof_kind.as_ref())
+        inner.get_mut().secure_zone_mut(
+            origin,
+            self.class,
+            self.nx_proof_kind.as_ref(),
+            self.serial_policy,
+        )
     }
 
     /// (Re)generates the nsec records, increments the serial number and signs the zone
@@ -327,6 +551,16 @@ impl Authority for InMemoryAuthority {
         self.axfr_policy
     }
 
+    /// Which optional records are added to responses from this zone
+    fn minimal_responses(&self) -> MinimalResponses {
+        self.minimal_responses
+    }
+
+    /// Networks of the secondaries which must transfer this zone over TLS
+    fn xot_required(&self) -> &[IpNet] {
+        &self.xot_required
+    }
+
     /// Takes the UpdateMessage, extracts the Records, and applies the changes to the record set.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
@@ -515,20 +749,17 @@ impl Authority for InMemoryAuthority {
         let answers = match answer {
             Some(rr_set) => LookupRecords::new(lookup_options, rr_set),
             None => {
-                return Continue(Err(
-                    if inner
-                        .records
-                        .keys()
-                        .any(|key| key.name() == name || name.zone_of(key.name()))
-                    {
+                if !self.origin().zone_of(name) {
+                    return Continue(Err(LookupError::from(ResponseCode::Refused)));
+                }
+
+                // names synthesized from a wildcard exist, even if the wildcard lacks the type
+                return Continue(Err(match inner.records.match_name(name) {
+                    NameMatch::Exact | NameMatch::EmptyNonTerminal | NameMatch::Wildcard { .. } => {
                         LookupError::NameExists
-                    } else {
-                        LookupError::from(match self.origin().zone_of(name) {
-                            true => ResponseCode::NXDomain,
-                            false => ResponseCode::Refused,
-                        })
-                    },
-                ));
+                    }
+                    NameMatch::NxDomain { .. } => LookupError::from(ResponseCode::NXDomain),
+                }));
             }
         };
 
@@ -617,10 +848,23 @@ impl Authority for InMemoryAuthority {
                 )
             }
             // A standard Lookup path
-            _ => (
-                self.lookup(lookup_name, record_type, lookup_options).await,
-                None,
-            ),
+            _ => {
+                // names at or below a delegation point are answered with a referral
+                let referral = self.inner.read().await.referral(
+                    self.origin(),
+                    lookup_name,
+                    record_type,
+                    lookup_options,
+                );
+                if let Some(referral) = referral {
+                    return (LookupControlFlow::Continue(Ok(referral)), None);
+                }
+
+                (
+                    self.lookup(lookup_name, record_type, lookup_options).await,
+                    None,
+                )
+            }
         }
     }
 
@@ -628,8 +872,8 @@ impl Authority for InMemoryAuthority {
     ///
     /// # Arguments
     ///
-    /// * `name` - given this name (i.e. the lookup name), return the NSEC record that is less than
-    ///            this
+    /// * `name` - given this name (i.e. the lookup name), return the NSEC records proving that it,
+    ///            or the queried type, does not exist, or which prove a wildcard expansion
     /// * `lookup_options` - Query-related lookup options (e.g., DNSSEC DO bit, supported hash
     ///                      algorithms, etc.)
     #[cfg(feature = "__dnssec")]
@@ -640,48 +884,7 @@ impl Authority for InMemoryAuthority {
     ) -> LookupControlFlow<AuthLookup> {
         let inner = self.inner.read().await;
 
-        // TODO: need a BorrowdRrKey
-        let rr_key = RrKey::new(name.clone(), RecordType::NSEC);
-        let no_data = inner
-            .records
-            .get(&rr_key)
-            .map(|rr_set| LookupRecords::new(lookup_options, rr_set.clone()));
-
-        if let Some(no_data) = no_data {
-            return LookupControlFlow::Continue(Ok(no_data.into()));
-        }
-
-        let closest_proof = inner.closest_nsec(name);
-
-        // we need the wildcard proof, but make sure that it's still part of the zone.
-        let wildcard = name.base_name();
-        let origin = self.origin();
-        let wildcard = if origin.zone_of(&wildcard) {
-            wildcard
-        } else {
-            origin.clone()
-        };
-
-        // don't duplicate the record...
-        let wildcard_proof = if wildcard != *name {
-            inner.closest_nsec(&wildcard)
-        } else {
-            None
-        };
-
-        let proofs = match (closest_proof, wildcard_proof) {
-            (Some(closest_proof), Some(wildcard_proof)) => {
-                // dedup with the wildcard proof
-                if wildcard_proof != closest_proof {
-                    vec![wildcard_proof, closest_proof]
-                } else {
-                    vec![closest_proof]
-                }
-            }
-            (None, Some(proof)) | (Some(proof), None) => vec![proof],
-            (None, None) => vec![],
-        };
-
+        let proofs = inner.nsec_proof(name);
         LookupControlFlow::Continue(Ok(LookupRecords::many(lookup_options, proofs).into()))
     }
 
@@ -739,7 +942,12 @@ impl DnssecAuthority for InMemoryAuthority {
     async fn secure_zone(&self) -> DnsSecResult<()> {
         let mut inner = self.inner.write().await;
 
-        inner.secure_zone_mut(self.origin(), self.class, self.nx_proof_kind.as_ref())
+        inner.secure_zone_mut(
+            self.origin(),
+            self.class,
+            self.nx_proof_kind.as_ref(),
+            self.serial_policy,
+        )
     }
 }
 
@@ -817,3 +1025,192 @@ pub(crate) fn zone_from_path(
     debug!("zone: {records:#?}");
     Ok(records)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn new_authority(zone: &str) -> Result<InMemoryAuthority, String> {
+        let origin = Name::from_str("example.com.").unwrap();
+        let records = Parser::new(zone, None, Some(origin.clone()))
+            .parse()
+            .unwrap()
+            .1;
+
+        InMemoryAuthority::new(
+            origin,
+            records,
+            ZoneType::Primary,
+            AxfrPolicy::Deny,
+            #[cfg(feature = "__dnssec")]
+            None,
+        )
+    }
+
+    #[test]
+    fn test_zone_check() {
+        const ZONE: &str = r#"$ORIGIN example.com.
+$TTL 3600
+@               IN  SOA   ns.example.com. admin.example.com. ( 2024010101 3600 600 86400 300 )
+@               IN  NS    ns.example.com.
+ns              IN  A     192.0.2.2
+www             IN  CNAME ns.example.com.
+"#;
+        assert!(new_authority(ZONE).is_ok());
+
+        // records below a delegation point are only warned about
+        let occluded = format!("{ZONE}sub IN NS ns.example.net.\nsub IN TXT occluded\n");
+        assert!(new_authority(&occluded).is_ok());
+
+        let invalid = format!("{ZONE}www IN TXT \"next to a CNAME\"\n");
+        assert_eq!(
+            new_authority(&invalid).err().unwrap(),
+            "invalid zone example.com.: www.example.com.: CNAME and TXT records at the same name"
+        );
+    }
+
+    #[test]
+    fn test_ttl_policy() {
+        let mut authority = new_authority(
+            r#"$ORIGIN example.com.
+$TTL 604800
+@               IN  SOA   ns.example.com. admin.example.com. ( 2024010101 3600 600 86400 300 )
+@               IN  NS    ns.example.com.
+ns              IN  A     192.0.2.2
+zero          0 IN  A     192.0.2.3
+"#,
+        )
+        .unwrap();
+
+        authority.set_ttl_policy(TtlPolicy {
+            min_ttl: Some(60),
+            max_ttl: Some(86_400),
+        });
+        let ttl = |authority: &mut InMemoryAuthority, name: &str| {
+            let key = RrKey::new(Name::from_str(name).unwrap().into(), RecordType::A);
+            let rrset = authority.inner.get_mut().records.get(&key).unwrap().clone();
+            (
+                rrset.ttl(),
+                rrset.records_without_rrsigs().next().unwrap().ttl(),
+            )
+        };
+        assert_eq!(ttl(&mut authority, "ns.example.com."), (86_400, 86_400));
+        assert_eq!(ttl(&mut authority, "zero.example.com."), (60, 60));
+
+        // records added later on are bounded as well
+        let record = Record::from_rdata(
+            Name::from_str("new.example.com.").unwrap(),
+            0,
+            RData::A(A::new(192, 0, 2, 4)),
+        );
+        assert!(authority.upsert_mut(record, 2024010102));
+        assert_eq!(ttl(&mut authority, "new.example.com."), (60, 60));
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_and_empty_non_terminal() {
+        let authority = new_authority(
+            r#"$ORIGIN example.com.
+$TTL 3600
+@               IN  SOA   ns.example.com. admin.example.com. ( 2024010101 3600 600 86400 300 )
+@               IN  NS    ns.example.com.
+ns              IN  A     192.0.2.2
+*               IN  TXT   "wildcard"
+a.b             IN  A     192.0.2.3
+"#,
+        )
+        .unwrap();
+
+        let lookup = |name: &str, record_type| {
+            let name = LowerName::from(Name::from_str(name).unwrap());
+            let authority = &authority;
+            async move {
+                match authority
+                    .lookup(&name, record_type, LookupOptions::default())
+                    .await
+                {
+                    LookupControlFlow::Continue(Ok(lookup)) => Ok(lookup
+                        .iter()
+                        .map(|r| r.name().to_string())
+                        .collect::<Vec<_>>()),
+                    LookupControlFlow::Continue(Err(error)) => Err(error),
+                    _ => panic!("unexpected lookup control flow"),
+                }
+            }
+        };
+
+        let answer = lookup("x.example.com.", RecordType::TXT).await.unwrap();
+        assert_eq!(answer, ["x.example.com."]);
+
+        // the wildcard exists but does not own the type
+        let error = lookup("x.example.com.", RecordType::A).await.unwrap_err();
+        assert!(error.is_name_exists());
+
+        // the empty non-terminal exists and is not expanded
+        let error = lookup("b.example.com.", RecordType::TXT).await.unwrap_err();
+        assert!(error.is_name_exists());
+
+        // existing names are not expanded
+        let error = lookup("ns.example.com.", RecordType::TXT)
+            .await
+            .unwrap_err();
+        assert!(error.is_name_exists());
+
+        // the closest encloser `b.example.com.` has no wildcard
+        let error = lookup("x.b.example.com.", RecordType::TXT)
+            .await
+            .unwrap_err();
+        assert!(error.is_nx_domain());
+    }
+
+    #[cfg(feature = "__dnssec")]
+    #[test]
+    fn test_nsec_proof() {
+        let mut authority = new_authority(
+            r#"$ORIGIN example.com.
+$TTL 3600
+@               IN  SOA   ns.example.com. admin.example.com. ( 2024010101 3600 600 86400 300 )
+@               IN  NS    ns.example.com.
+@               IN  NSEC  *.example.com. NS SOA NSEC
+*               IN  TXT   "wildcard"
+*               IN  NSEC  a.b.example.com. TXT NSEC
+a.b             IN  A     192.0.2.3
+a.b             IN  NSEC  ns.example.com. A NSEC
+ns              IN  A     192.0.2.2
+ns              IN  NSEC  example.com. A NSEC
+"#,
+        )
+        .unwrap();
+
+        let inner = authority.inner.get_mut();
+        let proof = |name: &str| {
+            inner
+                .nsec_proof(&Name::from_str(name).unwrap().into())
+                .iter()
+                .map(|rrset| rrset.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // no data, the NSEC record at the name
+        assert_eq!(proof("ns.example.com."), ["ns.example.com."]);
+        // empty non-terminal, the NSEC record covering the name
+        assert_eq!(proof("b.example.com."), ["*.example.com."]);
+        // wildcard, the NSEC record covering the name and the one at the wildcard
+        assert_eq!(
+            proof("x.example.com."),
+            ["ns.example.com.", "*.example.com."]
+        );
+        // name error, the NSEC records covering the name and the wildcard at the closest encloser
+        assert_eq!(
+            proof("x.b.example.com."),
+            ["a.b.example.com.", "*.example.com."]
+        );
+        assert_eq!(
+            proof("x.ns.example.com."),
+            ["ns.example.com."],
+            "the NSEC record at the closest encloser covers both names"
+        );
+    }
+}
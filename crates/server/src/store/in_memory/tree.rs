@@ -0,0 +1,696 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Label tree storage for the record sets of a zone
+
+use std::{collections::BTreeMap, ops::Bound, sync::Arc};
+
+use crate::proto::rr::{LowerName, RecordSet, RecordType, RrKey};
+
+/// The record sets of a zone, stored in a tree with one node per label
+///
+/// Lookups descend the tree label by label from the root, so finding a name, its closest
+/// existing ancestor and the wildcards of its ancestors is done in a single pass. Iteration
+/// yields the record sets in the same order as a `BTreeMap<RrKey, _>` would, i.e. in canonical
+/// DNS name order (RFC 4034, section 6.1) and by record type within a name.
+#[derive(Clone, Debug, Default)]
+pub struct ZoneTree {
+    /// Relative names sort before all fully qualified names
+    relative: Node,
+    fqdn: Node,
+    len: usize,
+}
+
+/// How a name is matched by the names of a zone, see RFC 4592 section 3.3.1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NameMatch {
+    /// The name owns record sets
+    Exact,
+    /// The name owns no record sets, but names below it do
+    EmptyNonTerminal,
+    /// The name does not exist, and is synthesized from the wildcard child of its closest encloser
+    Wildcard {
+        /// The longest existing ancestor of the name
+        closest_encloser: LowerName,
+    },
+    /// The name does not exist, and there is no wildcard at its closest encloser
+    NxDomain {
+        /// The longest existing ancestor of the name
+        closest_encloser: LowerName,
+    },
+}
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    /// Record sets owned by the name of this node, sorted by record type
+    rrsets: Vec<(RrKey, Arc<RecordSet>)>,
+    /// Children by lowercase label, byte order of lowercase labels is the canonical order
+    children: BTreeMap<Box<[u8]>, Self>,
+}
+
+impl ZoneTree {
+    /// Returns an empty tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of record sets in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the tree contains no record sets
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all record sets
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns the record set stored for the key
+    pub fn get(&self, key: &RrKey) -> Option<&Arc<RecordSet>> {
+        let node = self.node(&key.name)?;
+        node.position(key.record_type)
+            .ok()
+            .map(|idx| &node.rrsets[idx].1)
+    }
+
+    /// Returns a mutable reference to the record set stored for the key
+    pub fn get_mut(&mut self, key: &RrKey) -> Option<&mut Arc<RecordSet>> {
+        let mut node = self.root_mut(&key.name);
+        for label in key.name.iter().rev() {
+            node = node.children.get_mut(label)?;
+        }
+
+        let idx = node.position(key.record_type).ok()?;
+        Some(&mut node.rrsets[idx].1)
+    }
+
+    /// Returns true if the key has a record set in the tree
+    pub fn contains_key(&self, key: &RrKey) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts a record set, returning the record set previously stored for the key
+    pub fn insert(&mut self, key: RrKey, rrset: Arc<RecordSet>) -> Option<Arc<RecordSet>> {
+        let node = self.node_or_insert(&key.name);
+        match node.position(key.record_type) {
+            Ok(idx) => Some(std::mem::replace(&mut node.rrsets[idx].1, rrset)),
+            Err(idx) => {
+                node.rrsets.insert(idx, (key, rrset));
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// Returns the record set stored for the key, inserting the result of `f` if there is none
+    pub fn get_or_insert_with(
+        &mut self,
+        key: RrKey,
+        f: impl FnOnce() -> Arc<RecordSet>,
+    ) -> &mut Arc<RecordSet> {
+        let mut inserted = false;
+        let node = match key.name.is_fqdn() {
+            true => &mut self.fqdn,
+            false => &mut self.relative,
+        };
+        let node = key.name.iter().rev().fold(node, |node, label| {
+            node.children.entry(label.into()).or_default()
+        });
+
+        let idx = match node.position(key.record_type) {
+            Ok(idx) => idx,
+            Err(idx) => {
+                node.rrsets.insert(idx, (key, f()));
+                inserted = true;
+                idx
+            }
+        };
+
+        if inserted {
+            self.len += 1;
+        }
+        &mut node.rrsets[idx].1
+    }
+
+    /// Removes the record set stored for the key, pruning nodes that no longer lead to any records
+    pub fn remove(&mut self, key: &RrKey) -> Option<Arc<RecordSet>> {
+        let labels = key.name.iter().rev().collect::<Vec<_>>();
+        let removed = self.root_mut(&key.name).remove(&labels, key.record_type);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Returns true if the name owns records, or is an empty non-terminal with records below it
+    pub fn name_exists(&self, name: &LowerName) -> bool {
+        self.node(name).is_some()
+    }
+
+    /// Iterates over the record sets owned by the name, ordered by record type
+    pub fn rrsets_at(
+        &self,
+        name: &LowerName,
+    ) -> impl DoubleEndedIterator<Item = (&RrKey, &Arc<RecordSet>)> {
+        self.node(name)
+            .map(|node| node.rrsets.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|(key, rrset)| (key, rrset))
+    }
+
+    /// Classifies how `name` is matched by the names in the tree, following RFC 4592
+    pub fn match_name(&self, name: &LowerName) -> NameMatch {
+        let path = self.path(name);
+        let depth = path.len() - 1;
+        let node = path[depth];
+
+        // `num_labels` does not count a leading wildcard label
+        if depth == name.iter().count() {
+            return match node.rrsets.is_empty() {
+                false => NameMatch::Exact,
+                true => NameMatch::EmptyNonTerminal,
+            };
+        }
+
+        let mut closest_encloser = name.base_name();
+        while closest_encloser.iter().count() > depth {
+            closest_encloser = closest_encloser.base_name();
+        }
+
+        match !name.is_wildcard() && node.children.contains_key(&b"*"[..]) {
+            true => NameMatch::Wildcard { closest_encloser },
+            false => NameMatch::NxDomain { closest_encloser },
+        }
+    }
+
+    /// Finds the record set at `name` accepted by `matches`, or otherwise at the wildcard child of
+    /// its closest encloser
+    ///
+    /// The boolean in the result is true if the record set was found at a wildcard. As in
+    /// RFC 4592, a wildcard only applies to names that do not exist, so names owning other record
+    /// sets and empty non-terminals are never expanded. Wildcard names themselves are never
+    /// expanded either.
+    pub fn find_with_wildcard(
+        &self,
+        name: &LowerName,
+        mut matches: impl FnMut(RecordType) -> bool,
+    ) -> Option<(&Arc<RecordSet>, bool)> {
+        let path = self.path(name);
+        let depth = path.len() - 1;
+
+        let (node, wildcard) = if depth == name.iter().count() {
+            (path[depth], false)
+        } else if name.is_wildcard() {
+            return None;
+        } else {
+            (path[depth].children.get(&b"*"[..])?, true)
+        };
+
+        node.rrsets
+            .iter()
+            .find(|(key, _)| matches(key.record_type))
+            .map(|(_, rrset)| (rrset, wildcard))
+    }
+
+    /// Finds the NS record set of the highest delegation point below `origin` at or above `name`
+    ///
+    /// The records at and below such a delegation point belong to the child zone, only its NS, DS
+    /// and glue records are held by the zone of `origin`.
+    pub fn find_delegation(&self, origin: &LowerName, name: &LowerName) -> Option<&Arc<RecordSet>> {
+        if !origin.zone_of(name) {
+            return None;
+        }
+
+        let origin_labels = origin.num_labels() as usize;
+        let mut node = self.root(name);
+        for (depth, label) in name.iter().rev().enumerate() {
+            node = node.children.get(label)?;
+            if depth < origin_labels {
+                continue;
+            }
+
+            if let Ok(idx) = node.position(RecordType::NS) {
+                return Some(&node.rrsets[idx].1);
+            }
+        }
+
+        None
+    }
+
+    /// Visits the record sets in reverse canonical order, starting at the last record set whose
+    /// owner name is less than or equal to `name`, returning the first one accepted by `f`
+    ///
+    /// This only descends into the parts of the tree preceding `name`, e.g. to find the NSEC record
+    /// covering a name.
+    pub fn rfind_at_or_before(
+        &self,
+        name: &LowerName,
+        mut f: impl FnMut(&RrKey, &Arc<RecordSet>) -> bool,
+    ) -> Option<(&RrKey, &Arc<RecordSet>)> {
+        let labels = name.iter().rev().collect::<Vec<_>>();
+        let root = self.root(name);
+
+        let mut path = Vec::with_capacity(labels.len() + 1);
+        path.push(root);
+        for label in &labels {
+            match path[path.len() - 1].children.get(*label) {
+                Some(node) => path.push(node),
+                None => break,
+            }
+        }
+
+        for (depth, node) in path.iter().enumerate().rev() {
+            if let Some(label) = labels.get(depth) {
+                let lesser = node
+                    .children
+                    .range::<[u8], _>((Bound::Unbounded, Bound::Excluded(*label)));
+                for (_, child) in lesser.rev() {
+                    if let Some(found) = child.rfind(&mut f) {
+                        return Some(found);
+                    }
+                }
+            }
+
+            if let Some(found) = node.rfind_rrsets(&mut f) {
+                return Some(found);
+            }
+        }
+
+        // all relative names sort before fully qualified ones
+        match name.is_fqdn() {
+            true => self.relative.rfind(&mut f),
+            false => None,
+        }
+    }
+
+    /// Returns the last record set in canonical order for which `f` returns true
+    pub fn rfind(
+        &self,
+        mut f: impl FnMut(&RrKey, &Arc<RecordSet>) -> bool,
+    ) -> Option<(&RrKey, &Arc<RecordSet>)> {
+        self.fqdn
+            .rfind(&mut f)
+            .or_else(|| self.relative.rfind(&mut f))
+    }
+
+    /// Iterates over the keys and record sets in canonical order
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&RrKey, &Arc<RecordSet>)> + ExactSizeIterator {
+        let mut entries = Vec::with_capacity(self.len);
+        self.relative.collect(&mut entries);
+        self.fqdn.collect(&mut entries);
+        entries.into_iter()
+    }
+
+    /// Iterates over the keys in canonical order
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &RrKey> + ExactSizeIterator {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Iterates over the record sets in canonical order
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &Arc<RecordSet>> + ExactSizeIterator {
+        self.iter().map(|(_, rrset)| rrset)
+    }
+
+    /// Iterates mutably over the record sets in canonical order
+    pub fn values_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut Arc<RecordSet>> + ExactSizeIterator {
+        let mut entries = Vec::with_capacity(self.len);
+        self.relative.collect_mut(&mut entries);
+        self.fqdn.collect_mut(&mut entries);
+        entries.into_iter()
+    }
+
+    fn root(&self, name: &LowerName) -> &Node {
+        match name.is_fqdn() {
+            true => &self.fqdn,
+            false => &self.relative,
+        }
+    }
+
+    fn root_mut(&mut self, name: &LowerName) -> &mut Node {
+        match name.is_fqdn() {
+            true => &mut self.fqdn,
+            false => &mut self.relative,
+        }
+    }
+
+    /// Returns the nodes from the root down to the closest encloser of `name`, or to `name` itself
+    /// if it exists
+    fn path(&self, name: &LowerName) -> Vec<&Node> {
+        let mut path = Vec::with_capacity(name.num_labels() as usize + 1);
+        let mut node = self.root(name);
+        path.push(node);
+
+        for label in name.iter().rev() {
+            match node.children.get(label) {
+                Some(child) => node = child,
+                None => break,
+            }
+            path.push(node);
+        }
+
+        path
+    }
+
+    fn node(&self, name: &LowerName) -> Option<&Node> {
+        name.iter()
+            .rev()
+            .try_fold(self.root(name), |node, label| node.children.get(label))
+    }
+
+    fn node_or_insert(&mut self, name: &LowerName) -> &mut Node {
+        name.iter().rev().fold(self.root_mut(name), |node, label| {
+            node.children.entry(label.into()).or_default()
+        })
+    }
+}
+
+impl Node {
+    fn position(&self, record_type: RecordType) -> Result<usize, usize> {
+        self.rrsets
+            .binary_search_by(|(key, _)| key.record_type.cmp(&record_type))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rrsets.is_empty() && self.children.is_empty()
+    }
+
+    fn remove(&mut self, labels: &[&[u8]], record_type: RecordType) -> Option<Arc<RecordSet>> {
+        let Some((label, rest)) = labels.split_first() else {
+            let idx = self.position(record_type).ok()?;
+            return Some(self.rrsets.remove(idx).1);
+        };
+
+        let child = self.children.get_mut(*label)?;
+        let removed = child.remove(rest, record_type);
+        if child.is_empty() {
+            self.children.remove(*label);
+        }
+        removed
+    }
+
+    /// Reverse pre-order search of this subtree
+    fn rfind<'a>(
+        &'a self,
+        f: &mut impl FnMut(&RrKey, &Arc<RecordSet>) -> bool,
+    ) -> Option<(&'a RrKey, &'a Arc<RecordSet>)> {
+        for child in self.children.values().rev() {
+            if let Some(found) = child.rfind(f) {
+                return Some(found);
+            }
+        }
+
+        self.rfind_rrsets(f)
+    }
+
+    fn rfind_rrsets<'a>(
+        &'a self,
+        f: &mut impl FnMut(&RrKey, &Arc<RecordSet>) -> bool,
+    ) -> Option<(&'a RrKey, &'a Arc<RecordSet>)> {
+        self.rrsets
+            .iter()
+            .rev()
+            .find(|(key, rrset)| f(key, rrset))
+            .map(|(key, rrset)| (key, rrset))
+    }
+
+    fn collect<'a>(&'a self, entries: &mut Vec<(&'a RrKey, &'a Arc<RecordSet>)>) {
+        entries.extend(self.rrsets.iter().map(|(key, rrset)| (key, rrset)));
+        for child in self.children.values() {
+            child.collect(entries);
+        }
+    }
+
+    fn collect_mut<'a>(&'a mut self, entries: &mut Vec<&'a mut Arc<RecordSet>>) {
+        entries.extend(self.rrsets.iter_mut().map(|(_, rrset)| rrset));
+        for child in self.children.values_mut() {
+            child.collect_mut(entries);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a ZoneTree {
+    type Item = (&'a RrKey, &'a Arc<RecordSet>);
+    type IntoIter = std::vec::IntoIter<(&'a RrKey, &'a Arc<RecordSet>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut entries = Vec::with_capacity(self.len);
+        self.relative.collect(&mut entries);
+        self.fqdn.collect(&mut entries);
+        entries.into_iter()
+    }
+}
+
+impl FromIterator<(RrKey, Arc<RecordSet>)> for ZoneTree {
+    fn from_iter<T: IntoIterator<Item = (RrKey, Arc<RecordSet>)>>(iter: T) -> Self {
+        let mut tree = Self::new();
+        for (key, rrset) in iter {
+            tree.insert(key, rrset);
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::proto::rr::Name;
+
+    fn key(name: &str, record_type: RecordType) -> RrKey {
+        RrKey::new(LowerName::from_str(name).unwrap(), record_type)
+    }
+
+    fn rrset(name: &str, record_type: RecordType) -> Arc<RecordSet> {
+        Arc::new(RecordSet::new(
+            Name::from_str(name).unwrap(),
+            record_type,
+            0,
+        ))
+    }
+
+    fn tree(entries: &[(&str, RecordType)]) -> ZoneTree {
+        entries
+            .iter()
+            .map(|&(name, rtype)| (key(name, rtype), rrset(name, rtype)))
+            .collect()
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        let entries = [
+            ("z.example.", RecordType::A),
+            ("example.", RecordType::SOA),
+            ("*.z.example.", RecordType::A),
+            ("a.example.", RecordType::NS),
+            ("example.", RecordType::NS),
+            ("Z.a.example.", RecordType::TXT),
+            ("yljkjljk.a.example.", RecordType::A),
+            ("zABC.a.EXAMPLE.", RecordType::A),
+            ("_.z.example.", RecordType::A),
+            ("0.z.example.", RecordType::A),
+            ("relative", RecordType::A),
+        ];
+        let tree = tree(&entries);
+        assert_eq!(tree.len(), entries.len());
+
+        let mut expected = entries
+            .iter()
+            .map(|&(name, rtype)| key(name, rtype))
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        assert_eq!(tree.keys().cloned().collect::<Vec<_>>(), expected);
+        assert_eq!(
+            tree.keys().rev().cloned().collect::<Vec<_>>(),
+            expected.iter().rev().cloned().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut tree = tree(&[("www.example.com.", RecordType::A)]);
+        assert!(tree.name_exists(&LowerName::from_str("example.com.").unwrap()));
+        assert!(tree.contains_key(&key("WWW.example.com.", RecordType::A)));
+        assert!(!tree.contains_key(&key("www.example.com", RecordType::A)));
+
+        let previous = tree.insert(
+            key("www.example.com.", RecordType::A),
+            rrset("www.example.com.", RecordType::A),
+        );
+        assert!(previous.is_some());
+        assert_eq!(tree.len(), 1);
+
+        assert!(
+            tree.remove(&key("www.example.com.", RecordType::AAAA))
+                .is_none()
+        );
+        assert!(
+            tree.remove(&key("www.example.com.", RecordType::A))
+                .is_some()
+        );
+        assert!(tree.is_empty());
+
+        // empty non-terminals are pruned
+        assert!(!tree.name_exists(&LowerName::from_str("example.com.").unwrap()));
+    }
+
+    #[test]
+    fn test_find_with_wildcard() {
+        let tree = tree(&[
+            ("example.com.", RecordType::SOA),
+            ("*.example.com.", RecordType::TXT),
+            ("www.example.com.", RecordType::A),
+            ("*.b.example.com.", RecordType::A),
+            ("c.b.example.com.", RecordType::MX),
+        ]);
+        let find = |name: &str, rtype: RecordType| {
+            tree.find_with_wildcard(&LowerName::from_str(name).unwrap(), |t| t == rtype)
+                .map(|(rrset, wildcard)| (rrset.name().to_string(), wildcard))
+        };
+
+        assert_eq!(
+            find("www.example.com.", RecordType::A),
+            Some(("www.example.com.".to_string(), false))
+        );
+        // existing names and empty non-terminals are not expanded
+        assert_eq!(find("www.example.com.", RecordType::TXT), None);
+        assert_eq!(find("b.example.com.", RecordType::TXT), None);
+        assert_eq!(
+            find("a.b.example.com.", RecordType::A),
+            Some(("*.b.example.com.".to_string(), true))
+        );
+        assert_eq!(
+            find("a.www.example.com.", RecordType::TXT),
+            None,
+            "only the wildcard at the closest encloser applies"
+        );
+        assert_eq!(find("a.c.b.example.com.", RecordType::A), None);
+        assert_eq!(
+            find("a.example.com.", RecordType::TXT),
+            Some(("*.example.com.".to_string(), true))
+        );
+        assert_eq!(
+            find("*.example.com.", RecordType::TXT),
+            Some(("*.example.com.".to_string(), false))
+        );
+        assert_eq!(find("*.c.example.com.", RecordType::TXT), None);
+        assert_eq!(find("example.net.", RecordType::TXT), None);
+    }
+
+    #[test]
+    fn test_match_name() {
+        let tree = tree(&[
+            ("example.com.", RecordType::SOA),
+            ("*.example.com.", RecordType::TXT),
+            ("c.b.example.com.", RecordType::MX),
+        ]);
+        let name = |name: &str| LowerName::from_str(name).unwrap();
+        let match_name = |n: &str| tree.match_name(&name(n));
+
+        assert_eq!(match_name("c.b.example.com."), NameMatch::Exact);
+        assert_eq!(match_name("*.example.com."), NameMatch::Exact);
+        assert_eq!(match_name("b.example.com."), NameMatch::EmptyNonTerminal);
+        assert_eq!(
+            match_name("a.example.com."),
+            NameMatch::Wildcard {
+                closest_encloser: name("example.com.")
+            }
+        );
+        assert_eq!(
+            match_name("a.b.example.com."),
+            NameMatch::NxDomain {
+                closest_encloser: name("b.example.com.")
+            }
+        );
+        assert_eq!(
+            match_name("x.a.c.b.example.com."),
+            NameMatch::NxDomain {
+                closest_encloser: name("c.b.example.com.")
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_delegation() {
+        let tree = tree(&[
+            ("example.com.", RecordType::NS),
+            ("sub.example.com.", RecordType::NS),
+            ("ns.sub.example.com.", RecordType::A),
+            ("deeper.sub.example.com.", RecordType::NS),
+        ]);
+        let origin = LowerName::from_str("example.com.").unwrap();
+        let find = |name: &str| {
+            tree.find_delegation(&origin, &LowerName::from_str(name).unwrap())
+                .map(|rrset| rrset.name().to_string())
+        };
+
+        // the apex NS records are not a delegation
+        assert_eq!(find("example.com."), None);
+        assert_eq!(find("www.example.com."), None);
+        assert_eq!(
+            find("sub.example.com."),
+            Some("sub.example.com.".to_string())
+        );
+        assert_eq!(
+            find("www.deeper.sub.example.com."),
+            Some("sub.example.com.".to_string())
+        );
+        assert_eq!(find("sub.example.net."), None);
+    }
+
+    #[test]
+    fn test_rfind_at_or_before() {
+        let tree = tree(&[
+            ("example.", RecordType::NSEC),
+            ("a.example.", RecordType::NSEC),
+            ("yljkjljk.a.example.", RecordType::NSEC),
+            ("z.example.", RecordType::A),
+            ("z.example.", RecordType::NSEC),
+        ]);
+        let rfind = |name: &str| {
+            tree.rfind_at_or_before(&LowerName::from_str(name).unwrap(), |key, _| {
+                key.record_type == RecordType::NSEC
+            })
+            .map(|(key, _)| key.name.to_string())
+        };
+
+        assert_eq!(rfind("b.example."), Some("yljkjljk.a.example.".to_string()));
+        assert_eq!(rfind("a.example."), Some("a.example.".to_string()));
+        assert_eq!(rfind("0.a.example."), Some("a.example.".to_string()));
+        assert_eq!(rfind("zz.z.example."), Some("z.example.".to_string()));
+        assert_eq!(rfind("example."), Some("example.".to_string()));
+        assert_eq!(rfind("com."), None);
+
+        // matches a reverse scan over all keys
+        for name in [
+            "b.example.",
+            "zzz.example.",
+            "a.a.example.",
+            "zz.a.example.",
+        ] {
+            let name = LowerName::from_str(name).unwrap();
+            let expected = tree
+                .keys()
+                .rev()
+                .find(|key| key.name <= name && key.record_type == RecordType::NSEC);
+            let found = tree
+                .rfind_at_or_before(&name, |key, _| key.record_type == RecordType::NSEC)
+                .map(|(key, _)| key);
+            assert_eq!(found, expected);
+        }
+    }
+}
@@ -0,0 +1,379 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Serialization of zones to the zone file format
+
+use std::fmt::{self, Write};
+
+#[cfg(feature = "__dnssec")]
+use time::OffsetDateTime;
+
+use crate::proto::rr::{Name, RData, Record, RecordSet, RecordType};
+#[cfg(feature = "__dnssec")]
+use crate::proto::{
+    dnssec::{
+        PublicKey,
+        rdata::{DNSSECRData, SIG},
+    },
+    rr::SerialNumber,
+};
+
+/// Number of base64 characters per line for keys and signatures written over multiple lines
+#[cfg(feature = "__dnssec")]
+const BASE64_LINE_LEN: usize = 44;
+
+/// Writes zones as normalized zone files, see [RFC 1035, section 5](https://tools.ietf.org/html/rfc1035#section-5)
+///
+/// The output starts with the `$ORIGIN` (and optionally `$TTL`) directives, followed by every
+/// record of the zone, one per line:
+///
+/// * names are written relative to the origin, with `@` for the origin itself,
+/// * record sets are sorted in canonical name order, the SOA first, then by record type,
+/// * each record set is followed by its RRSIGs,
+/// * DNSKEY and RRSIG records are split over multiple lines, in the style of BIND.
+///
+/// The TTL and class are written for every record, so the output does not depend on the
+/// previous lines, and can be read back with [`crate::proto::serialize::txt::Parser`].
+pub struct ZoneWriter<'a> {
+    origin: &'a Name,
+    default_ttl: Option<u32>,
+}
+
+impl<'a> ZoneWriter<'a> {
+    /// Creates a writer for the zone of the given origin
+    pub fn new(origin: &'a Name) -> Self {
+        Self {
+            origin,
+            default_ttl: None,
+        }
+    }
+
+    /// Writes a `$TTL` directive with this default TTL
+    pub fn default_ttl(mut self, ttl: u32) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    /// Writes the record sets as a zone file
+    pub fn write<'r>(
+        &self,
+        out: &mut impl Write,
+        rrsets: impl IntoIterator<Item = &'r RecordSet>,
+    ) -> fmt::Result {
+        let mut rrsets = rrsets.into_iter().collect::<Vec<_>>();
+        rrsets.sort_by(|a, b| {
+            a.name()
+                .cmp(b.name())
+                .then_with(|| type_order(a.record_type()).cmp(&type_order(b.record_type())))
+        });
+
+        writeln!(out, "$ORIGIN {}", fqdn(self.origin))?;
+        if let Some(ttl) = self.default_ttl {
+            writeln!(out, "$TTL {ttl}")?;
+        }
+
+        let mut previous = None;
+        for rrset in rrsets {
+            // separate the names, to ease reading
+            if previous.is_some_and(|name| name != rrset.name()) {
+                writeln!(out)?;
+            }
+            previous = Some(rrset.name());
+
+            for record in rrset.records_without_rrsigs() {
+                self.write_record(out, record)?;
+            }
+            for rrsig in rrset.rrsigs() {
+                self.write_record(out, rrsig)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the record sets as a zone file to a `String`
+    pub fn to_zone_string<'r>(&self, rrsets: impl IntoIterator<Item = &'r RecordSet>) -> String {
+        let mut out = String::new();
+        self.write(&mut out, rrsets)
+            .expect("writing to a String can not fail");
+        out
+    }
+
+    fn write_record(&self, out: &mut impl Write, record: &Record) -> fmt::Result {
+        write!(
+            out,
+            "{owner}\t{ttl}\t{class}\t{ty}\t",
+            owner = self.owner(record.name()),
+            ttl = record.ttl(),
+            class = record.dns_class(),
+            ty = record.record_type(),
+        )?;
+
+        #[cfg(feature = "__dnssec")]
+        if let RData::DNSSEC(rdata) = record.data() {
+            match rdata {
+                DNSSECRData::DNSKEY(_) | DNSSECRData::CDNSKEY(_) | DNSSECRData::KEY(_) => {
+                    return write_key(out, rdata);
+                }
+                DNSSECRData::RRSIG(rrsig) => return write_sig(out, rrsig),
+                DNSSECRData::SIG(sig) => return write_sig(out, sig),
+                _ => {}
+            }
+        }
+
+        match record.data() {
+            // the Display of these is not in presentation format, the strings need quoting
+            RData::TXT(txt) => {
+                for (i, data) in txt.txt_data().iter().enumerate() {
+                    if i > 0 {
+                        out.write_char(' ')?;
+                    }
+                    write_character_string(out, data)?;
+                }
+                writeln!(out)
+            }
+            RData::HINFO(hinfo) => {
+                write_character_string(out, hinfo.cpu())?;
+                out.write_char(' ')?;
+                write_character_string(out, hinfo.os())?;
+                writeln!(out)
+            }
+            rdata => writeln!(out, "{rdata}"),
+        }
+    }
+
+    /// The owner name, relative to the origin if it is within the zone
+    fn owner(&self, name: &Name) -> String {
+        if name == self.origin {
+            return "@".to_string();
+        }
+
+        if !self.origin.zone_of(name) {
+            return fqdn(name);
+        }
+
+        let relative = name.iter().count() - self.origin.iter().count();
+        match Name::from_labels(name.iter().take(relative)) {
+            Ok(mut relative) => {
+                relative.set_fqdn(false);
+                relative.to_string()
+            }
+            Err(_) => fqdn(name),
+        }
+    }
+}
+
+fn fqdn(name: &Name) -> String {
+    let mut name = name.clone();
+    name.set_fqdn(true);
+    name.to_string()
+}
+
+/// Writes a quoted `<character-string>`, see [RFC 1035, section 5.1](https://tools.ietf.org/html/rfc1035#section-5.1)
+fn write_character_string(out: &mut impl Write, data: &[u8]) -> fmt::Result {
+    out.write_char('"')?;
+    for &b in data {
+        match b {
+            b'"' | b'\\' => write!(out, "\\{}", char::from(b))?,
+            b' '..=b'~' => out.write_char(char::from(b))?,
+            _ => write!(out, "\\{b:03}")?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// The SOA always comes first at the apex
+fn type_order(record_type: RecordType) -> (bool, u16) {
+    (record_type != RecordType::SOA, u16::from(record_type))
+}
+
+/// Writes DNSKEY, CDNSKEY and KEY record data with the key split over multiple lines
+#[cfg(feature = "__dnssec")]
+fn write_key(out: &mut impl Write, rdata: &DNSSECRData) -> fmt::Result {
+    let text = rdata.to_string();
+    let mut fields = text.splitn(4, ' ');
+    let (Some(flags), Some(protocol), Some(algorithm), Some(key)) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return writeln!(out, "{text}");
+    };
+
+    write!(out, "{flags} {protocol} {algorithm} (")?;
+    write_base64(out, key)?;
+    write!(out, " )")?;
+
+    if let DNSSECRData::DNSKEY(dnskey) = rdata {
+        let role = match dnskey.secure_entry_point() {
+            true => "KSK",
+            false => "ZSK",
+        };
+        write!(
+            out,
+            " ; {role}; alg = {alg}",
+            alg = dnskey.public_key().algorithm()
+        )?;
+        if let Ok(key_tag) = dnskey.calculate_key_tag() {
+            write!(out, " ; key id = {key_tag}")?;
+        }
+    }
+
+    writeln!(out)
+}
+
+/// Writes RRSIG and SIG record data over multiple lines, with readable timestamps
+#[cfg(feature = "__dnssec")]
+fn write_sig(out: &mut impl Write, sig: &SIG) -> fmt::Result {
+    let input = sig.input();
+    write!(
+        out,
+        "{ty} {alg} {labels} {ttl} (\n\t\t\t\t\t{expiration} {inception} {tag} {signer}",
+        ty = input.type_covered,
+        alg = u8::from(input.algorithm),
+        labels = input.num_labels,
+        ttl = input.original_ttl,
+        expiration = timestamp(input.sig_expiration),
+        inception = timestamp(input.sig_inception),
+        tag = input.key_tag,
+        signer = input.signer_name,
+    )?;
+    write_base64(out, &data_encoding::BASE64.encode(sig.sig()))?;
+    writeln!(out, " )")
+}
+
+#[cfg(feature = "__dnssec")]
+fn write_base64(out: &mut impl Write, base64: &str) -> fmt::Result {
+    // base64 is ascii, so splitting on any byte index is safe
+    for line in base64.as_bytes().chunks(BASE64_LINE_LEN) {
+        write!(out, "\n\t\t\t\t\t")?;
+        for &c in line {
+            out.write_char(char::from(c))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats a signature timestamp as YYYYMMDDHHmmSS, see [RFC 4034, section 3.2](https://tools.ietf.org/html/rfc4034#section-3.2)
+#[cfg(feature = "__dnssec")]
+fn timestamp(serial: SerialNumber) -> String {
+    match OffsetDateTime::from_unix_timestamp(i64::from(serial.get())) {
+        Ok(time) => format!(
+            "{:04}{:02}{:02}{:02}{:02}{:02}",
+            time.year(),
+            u8::from(time.month()),
+            time.day(),
+            time.hour(),
+            time.minute(),
+            time.second()
+        ),
+        Err(_) => serial.get().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, str::FromStr};
+
+    use super::*;
+    use crate::proto::{
+        rr::{RrKey, rdata::TXT},
+        serialize::txt::Parser,
+    };
+
+    const ZONE: &str = r#"$ORIGIN example.com.
+$TTL 3600
+@               IN  SOA   ns.example.com. admin.example.com. ( 2024010101 3600 600 86400 300 )
+@               IN  NS    ns.example.com.
+@               IN  NS    ns.example.net.
+ZZZ             IN  A     192.0.2.3
+www     60      IN  A     192.0.2.1
+www             IN  TXT   "hello world" "with \"quotes\" \\ and \009tab"
+www             IN  HINFO "a cpu" os
+*.wild          IN  AAAA  2001:db8::1
+ns              IN  A     192.0.2.2
+mail            IN  MX    10 mx.example.net.
+"#;
+
+    fn parse(zone: &str) -> BTreeMap<RrKey, RecordSet> {
+        let origin = Name::from_str("example.com.").unwrap();
+        Parser::new(zone, None, Some(origin)).parse().unwrap().1
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let records = parse(ZONE);
+
+        let written = ZoneWriter::new(&origin)
+            .default_ttl(3600)
+            .to_zone_string(records.values());
+        assert_eq!(parse(&written), records, "{written}");
+    }
+
+    #[test]
+    fn test_normalized() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let records = parse(ZONE);
+
+        let written = ZoneWriter::new(&origin)
+            .default_ttl(300)
+            .to_zone_string(records.values().rev());
+
+        let mut lines = written.lines();
+        assert_eq!(lines.next(), Some("$ORIGIN example.com."));
+        assert_eq!(lines.next(), Some("$TTL 300"));
+        let owners = lines
+            .filter(|line| !line.is_empty())
+            .map(|line| line.split('\t').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            owners,
+            [
+                "@", "@", "@", "mail", "ns", "*.wild", "www", "www", "www", "zzz"
+            ]
+        );
+        assert!(written.starts_with("$ORIGIN example.com.\n$TTL 300\n@\t86400\tIN\tSOA\t"));
+        assert!(written.contains("\nwww\t60\tIN\tA\t192.0.2.1\n"));
+        assert!(
+            written.contains("\tTXT\t\"hello world\" \"with \\\"quotes\\\" \\\\ and \\009tab\"\n")
+        );
+    }
+
+    #[test]
+    fn test_out_of_zone() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let mut rrset = RecordSet::new(Name::from_str("example.net.").unwrap(), RecordType::TXT, 0);
+        rrset.add_rdata(RData::TXT(TXT::new(vec!["a".to_string()])));
+
+        assert_eq!(
+            ZoneWriter::new(&origin).to_zone_string([&rrset]),
+            "$ORIGIN example.com.\nexample.net.\t0\tIN\tTXT\t\"a\"\n"
+        );
+    }
+
+    #[cfg(feature = "__dnssec")]
+    #[test]
+    fn test_dnssec() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let zone = format!(
+            "{ZONE}@ 3600 IN DNSKEY 257 3 13 mdsswUyr3DPW132mOi8V9xESWE8jTo0dxCjjnopKl+GqJxpVXckHAeF+KkxLbxILfDLUT0rAK9iUzy1L53eKGQ==
+www 3600 IN RRSIG A 13 3 60 20250101000000 1733011200 2642 example.com. oJB1W6WNGv+ldvQ3WDG0MQkg5IEhjRip8WTrPYGv07h108dUKGMeDPKijVCHX3DDKdfb+v6oB9wfuh3DTJXUAfI/M0zmO/zz8bW0Rznl8O3t
+"
+        );
+        let records = parse(&zone);
+
+        let written = ZoneWriter::new(&origin).to_zone_string(records.values());
+        assert_eq!(parse(&written), records, "{written}");
+        assert!(written.contains(
+            "@\t3600\tIN\tDNSKEY\t257 3 13 (\n\t\t\t\t\tmdsswUyr3DPW132mOi8V9xESWE8jTo0dxCjjnopKl+Gq\n"
+        ));
+        assert!(written.contains(" ) ; KSK; alg = ECDSAP256SHA256 ; key id = "));
+        assert!(written.contains(
+            "www\t3600\tIN\tRRSIG\tA 13 3 60 (\n\t\t\t\t\t20250101000000 20241201000000 2642 example.com.\n"
+        ));
+    }
+}
@@ -0,0 +1,452 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Rule based interception of queries with canned responses
+
+#![cfg(feature = "intercept")]
+
+use std::{
+    io,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
+use serde::Deserialize;
+use tracing::{debug, info};
+
+#[cfg(feature = "__dnssec")]
+use crate::{authority::Nsec3QueryInfo, dnssec::NxProofKind};
+use crate::{
+    authority::{
+        AuthLookup, Authority, AxfrPolicy, LookupControlFlow, LookupError, LookupOptions,
+        UpdateResult, ZoneType,
+    },
+    proto::{
+        op::{Query, ResponseCode, message::ResponseSigner},
+        rr::{LowerName, Name, RData, Record, RecordType},
+        serialize::txt::RDataParser,
+    },
+    resolver::lookup::Lookup,
+    server::Request,
+};
+
+/// A conditional authority that answers the queries matching its rules with a fixed response
+/// code or fixed records, e.g. to redirect the clients of a captive portal or to stub out names
+/// in a lab.
+///
+/// Like the [`BlocklistAuthority`](super::blocklist::BlocklistAuthority), it is meant to be
+/// chained before a forwarding or recursive resolver: queries that match no rule are skipped and
+/// answered by the next authority of the zone. Refer to
+/// tests/test-data/test_configs/chained_intercept.toml for an example of this configuration.
+pub struct InterceptAuthority {
+    origin: LowerName,
+    rules: Vec<InterceptRule>,
+    ttl: u32,
+}
+
+impl InterceptAuthority {
+    /// Read the Authority for the origin from the specified configuration
+    pub fn try_from_config(origin: Name, config: &InterceptConfig) -> Result<Self, String> {
+        info!("loading intercept config: {origin}");
+
+        let rules = config
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                InterceptRule::try_from_config(rule)
+                    .map_err(|e| format!("invalid intercept rule #{}: {e}", index + 1))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            origin: origin.into(),
+            rules,
+            ttl: config.ttl,
+        })
+    }
+
+    /// The response of the first rule matching the query, `None` if no rule matches
+    fn intercept(
+        &self,
+        name: &LowerName,
+        rtype: RecordType,
+    ) -> Option<Result<Lookup, LookupError>> {
+        let rule = self.rules.iter().find(|rule| rule.matches(name, rtype))?;
+        info!("intercepting query {name} {rtype}");
+
+        let records = match &rule.response {
+            InterceptResponse::ResponseCode(ResponseCode::NoError) => vec![],
+            InterceptResponse::ResponseCode(response_code) => {
+                return Some(Err(LookupError::ResponseCode(*response_code)));
+            }
+            InterceptResponse::Records(rdatas) => rdatas
+                .iter()
+                .filter(|rdata| {
+                    let record_type = rdata.record_type();
+                    record_type == rtype || record_type == RecordType::CNAME || rtype.is_any()
+                })
+                .map(|rdata| Record::from_rdata(Name::from(name), self.ttl, rdata.clone()))
+                .collect::<Vec<_>>(),
+        };
+
+        Some(Ok(Lookup::new_with_deadline(
+            Query::query(name.into(), rtype),
+            records.into(),
+            Instant::now() + Duration::from_secs(u64::from(self.ttl)),
+        )))
+    }
+}
+
+#[async_trait::async_trait]
+impl Authority for InterceptAuthority {
+    fn zone_type(&self) -> ZoneType {
+        ZoneType::External
+    }
+
+    fn axfr_policy(&self) -> AxfrPolicy {
+        AxfrPolicy::Deny
+    }
+
+    async fn update(
+        &self,
+        _update: &Request,
+    ) -> (UpdateResult<bool>, Option<Box<dyn ResponseSigner>>) {
+        (Err(ResponseCode::NotImp), None)
+    }
+
+    fn origin(&self) -> &LowerName {
+        &self.origin
+    }
+
+    /// Match the query against the rules.  This will return LookupControlFlow::Break with the
+    /// response of the first matching rule, or LookupControlFlow::Skip if no rule matches.
+    async fn lookup(
+        &self,
+        name: &LowerName,
+        rtype: RecordType,
+        _lookup_options: LookupOptions,
+    ) -> LookupControlFlow<AuthLookup> {
+        match self.intercept(name, rtype) {
+            Some(Ok(lookup)) => LookupControlFlow::Break(Ok(AuthLookup::from(lookup))),
+            Some(Err(e)) => LookupControlFlow::Break(Err(e)),
+            None => {
+                debug!("query '{name}' matches no intercept rule; returning Skip...");
+                LookupControlFlow::Skip
+            }
+        }
+    }
+
+    async fn search(
+        &self,
+        request: &Request,
+        lookup_options: LookupOptions,
+    ) -> (
+        LookupControlFlow<AuthLookup>,
+        Option<Box<dyn ResponseSigner>>,
+    ) {
+        let request_info = match request.request_info() {
+            Ok(info) => info,
+            Err(e) => return (LookupControlFlow::Break(Err(LookupError::from(e))), None),
+        };
+        (
+            self.lookup(
+                request_info.query.name(),
+                request_info.query.query_type(),
+                lookup_options,
+            )
+            .await,
+            None,
+        )
+    }
+
+    async fn get_nsec_records(
+        &self,
+        _name: &LowerName,
+        _lookup_options: LookupOptions,
+    ) -> LookupControlFlow<AuthLookup> {
+        LookupControlFlow::Continue(Err(LookupError::from(io::Error::other(
+            "getting NSEC records is unimplemented for the intercept authority",
+        ))))
+    }
+
+    #[cfg(feature = "__dnssec")]
+    async fn get_nsec3_records(
+        &self,
+        _info: Nsec3QueryInfo<'_>,
+        _lookup_options: LookupOptions,
+    ) -> LookupControlFlow<AuthLookup> {
+        LookupControlFlow::Continue(Err(LookupError::from(io::Error::other(
+            "getting NSEC3 records is unimplemented for the intercept authority",
+        ))))
+    }
+
+    #[cfg(feature = "__dnssec")]
+    fn nx_proof_kind(&self) -> Option<&NxProofKind> {
+        None
+    }
+}
+
+struct InterceptRule {
+    suffix: Option<LowerName>,
+    regex: Option<Regex>,
+    types: Vec<RecordType>,
+    response: InterceptResponse,
+}
+
+impl InterceptRule {
+    fn try_from_config(config: &InterceptRuleConfig) -> Result<Self, String> {
+        let suffix = config
+            .suffix
+            .as_deref()
+            .map(|suffix| {
+                Name::from_str(suffix)
+                    .map(|mut name| {
+                        name.set_fqdn(true);
+                        LowerName::from(name)
+                    })
+                    .map_err(|e| format!("invalid suffix '{suffix}': {e}"))
+            })
+            .transpose()?;
+
+        let regex = config
+            .regex
+            .as_deref()
+            .map(|regex| Regex::new(regex).map_err(|e| format!("invalid regex '{regex}': {e}")))
+            .transpose()?;
+
+        let response = match (config.rcode, config.records.is_empty()) {
+            (Some(response_code), true) => InterceptResponse::ResponseCode(response_code),
+            (None, false) => InterceptResponse::Records(
+                config
+                    .records
+                    .iter()
+                    .map(|record| parse_rdata(record))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => return Err("exactly one of rcode and records must be specified".to_string()),
+        };
+
+        Ok(Self {
+            suffix,
+            regex,
+            types: config.types.clone(),
+            response,
+        })
+    }
+
+    fn matches(&self, name: &LowerName, rtype: RecordType) -> bool {
+        if !self.types.is_empty() && !self.types.contains(&rtype) {
+            return false;
+        }
+
+        if let Some(suffix) = &self.suffix {
+            if !suffix.zone_of(name) {
+                return false;
+            }
+        }
+
+        if let Some(regex) = &self.regex {
+            let name = name.to_string();
+            if !regex.is_match(name.strip_suffix('.').unwrap_or(&name)) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Parses a record of a rule, the record type followed by the record data, e.g. `A 192.0.2.1`
+fn parse_rdata(record: &str) -> Result<RData, String> {
+    let (record_type, rdata) = record
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("invalid record '{record}': expected a type and data"))?;
+    let record_type =
+        RecordType::from_str(record_type).map_err(|e| format!("invalid record '{record}': {e}"))?;
+
+    RData::try_from_str(record_type, rdata.trim())
+        .map_err(|e| format!("invalid record '{record}': {e}"))
+}
+
+enum InterceptResponse {
+    ResponseCode(ResponseCode),
+    Records(Vec<RData>),
+}
+
+/// Configuration for the intercept authority
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct InterceptConfig {
+    /// The rules, the first rule matching a query determines the response
+    pub rules: Vec<InterceptRuleConfig>,
+
+    /// TTL of the records of the responses, in seconds.  Defaults to 60 seconds.
+    pub ttl: u32,
+}
+
+impl Default for InterceptConfig {
+    fn default() -> Self {
+        Self {
+            rules: vec![],
+            ttl: 60,
+        }
+    }
+}
+
+/// Configuration of an intercept rule
+///
+/// A rule matches the queries that satisfy all of `suffix`, `regex` and `types` that are
+/// specified; a rule without any of them matches every query.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct InterceptRuleConfig {
+    /// Matches the name and the names below it, e.g. `example.com` matches `example.com` and
+    /// `www.example.com`
+    pub suffix: Option<String>,
+
+    /// Matches the names that this regular expression matches. The name is matched in lowercase
+    /// and without the trailing dot, e.g. `^captive\.` matches `captive.example.com`
+    pub regex: Option<String>,
+
+    /// Matches the queries for these record types, all record types if empty
+    pub types: Vec<RecordType>,
+
+    /// Answer with this response code, e.g. "NXDomain" or "Refused", and without records.
+    /// Mutually exclusive with `records`
+    pub rcode: Option<ResponseCode>,
+
+    /// Answer with these records, owned by the query name, as the record type followed by the
+    /// record data, e.g. `A 192.0.2.1`. The records of the queried type, and CNAME records, are
+    /// returned; other queries are answered with no records. Mutually exclusive with `rcode`
+    pub records: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::proto::rr::rdata::A;
+
+    fn authority(rules: Vec<InterceptRuleConfig>) -> InterceptAuthority {
+        let config = InterceptConfig {
+            rules,
+            ..InterceptConfig::default()
+        };
+        InterceptAuthority::try_from_config(Name::root(), &config).unwrap()
+    }
+
+    async fn lookup(
+        authority: &InterceptAuthority,
+        name: &str,
+        rtype: RecordType,
+    ) -> LookupControlFlow<AuthLookup> {
+        authority
+            .lookup(
+                &LowerName::from_str(name).unwrap(),
+                rtype,
+                LookupOptions::default(),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_intercept_records() {
+        let authority = authority(vec![InterceptRuleConfig {
+            regex: Some(r"^captive\.".to_string()),
+            records: vec!["A 192.0.2.1".to_string(), "AAAA 2001:db8::1".to_string()],
+            ..InterceptRuleConfig::default()
+        }]);
+
+        let LookupControlFlow::Break(Ok(answer)) =
+            lookup(&authority, "captive.example.com.", RecordType::A).await
+        else {
+            panic!("expected intercepted answer");
+        };
+        let rdatas = answer.iter().map(|r| r.data().clone()).collect::<Vec<_>>();
+        assert_eq!(rdatas, vec![RData::A(A(Ipv4Addr::new(192, 0, 2, 1)))]);
+        assert!(answer.iter().all(|r| r.ttl() == 60));
+
+        let LookupControlFlow::Break(Ok(answer)) =
+            lookup(&authority, "captive.example.com.", RecordType::TXT).await
+        else {
+            panic!("expected intercepted answer");
+        };
+        assert_eq!(answer.iter().count(), 0);
+
+        assert!(matches!(
+            lookup(&authority, "www.captive.example.com.", RecordType::A).await,
+            LookupControlFlow::Skip
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_intercept_response_code() {
+        let authority = authority(vec![
+            InterceptRuleConfig {
+                suffix: Some("ads.example.com".to_string()),
+                types: vec![RecordType::A, RecordType::AAAA],
+                rcode: Some(ResponseCode::NXDomain),
+                ..InterceptRuleConfig::default()
+            },
+            InterceptRuleConfig {
+                types: vec![RecordType::AAAA],
+                rcode: Some(ResponseCode::NoError),
+                ..InterceptRuleConfig::default()
+            },
+        ]);
+
+        let LookupControlFlow::Break(Err(e)) =
+            lookup(&authority, "x.ads.example.com.", RecordType::A).await
+        else {
+            panic!("expected intercepted error");
+        };
+        assert!(e.is_nx_domain());
+
+        assert!(matches!(
+            lookup(&authority, "x.ads.example.com.", RecordType::MX).await,
+            LookupControlFlow::Skip
+        ));
+
+        let LookupControlFlow::Break(Ok(answer)) =
+            lookup(&authority, "www.example.com.", RecordType::AAAA).await
+        else {
+            panic!("expected empty answer");
+        };
+        assert_eq!(answer.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        for rule in [
+            InterceptRuleConfig::default(),
+            InterceptRuleConfig {
+                rcode: Some(ResponseCode::Refused),
+                records: vec!["A 192.0.2.1".to_string()],
+                ..InterceptRuleConfig::default()
+            },
+            InterceptRuleConfig {
+                regex: Some("(".to_string()),
+                rcode: Some(ResponseCode::Refused),
+                ..InterceptRuleConfig::default()
+            },
+            InterceptRuleConfig {
+                records: vec!["A not-an-address".to_string()],
+                ..InterceptRuleConfig::default()
+            },
+        ] {
+            let config = InterceptConfig {
+                rules: vec![rule],
+                ..InterceptConfig::default()
+            };
+            assert!(InterceptAuthority::try_from_config(Name::root(), &config).is_err());
+        }
+    }
+}
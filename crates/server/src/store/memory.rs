@@ -0,0 +1,284 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Memory accounting of the data stored by authorities
+
+use std::{
+    mem,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use tracing::warn;
+
+use crate::proto::{
+    rr::{LowerName, Record, RecordSet, RrKey},
+    serialize::binary::{BinEncodable, BinEncoder},
+};
+#[cfg(feature = "metrics")]
+use crate::store::metrics::ZoneMemoryMetrics;
+
+/// A memory limit shared by several zones
+///
+/// Every zone given the same budget reserves the memory of its data from it, so that the zones
+/// together stay within `limit` bytes even if none of them reaches its own limit.
+#[derive(Debug)]
+pub struct MemoryBudget {
+    limit: u64,
+    used: AtomicU64,
+}
+
+impl MemoryBudget {
+    /// Creates a budget of `limit` bytes
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: AtomicU64::new(0),
+        }
+    }
+
+    /// The number of bytes the zones may use together
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// The estimated number of bytes used, or reserved for updates, by all zones of this budget
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    fn try_reserve(&self, bytes: u64) -> bool {
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(bytes).filter(|used| *used <= self.limit)
+            })
+            .is_ok()
+    }
+
+    fn replace(&self, released: u64, added: u64) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(released) + added)
+            });
+    }
+}
+
+/// Memory reserved for an update of a zone, see [`InMemoryAuthority::reserve_memory`]
+///
+/// [`InMemoryAuthority::reserve_memory`]: super::in_memory::InMemoryAuthority::reserve_memory
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryReservation {
+    pub(super) reserved: u64,
+}
+
+/// The memory usage and limits of a single zone
+pub(crate) struct ZoneMemory {
+    max_memory: Option<u64>,
+    budget: Option<Arc<MemoryBudget>>,
+    /// The measured usage, plus the memory reserved for updates in progress
+    usage: AtomicU64,
+    /// The usage of the zone when it was last measured
+    measured: AtomicU64,
+    #[cfg(feature = "metrics")]
+    metrics: ZoneMemoryMetrics,
+}
+
+impl ZoneMemory {
+    /// Accounts for `usage` bytes, an error if that already exceeds one of the limits
+    pub(crate) fn new(
+        origin: &LowerName,
+        usage: u64,
+        max_memory: Option<u64>,
+        budget: Option<Arc<MemoryBudget>>,
+    ) -> Result<Self, String> {
+        if let Some(max_memory) = max_memory {
+            if usage > max_memory {
+                return Err(format!(
+                    "zone {origin} uses {usage} bytes, more than its limit of {max_memory} bytes"
+                ));
+            }
+        }
+
+        if let Some(budget) = &budget {
+            if !budget.try_reserve(usage) {
+                return Err(format!(
+                    "zone {origin} uses {usage} bytes, more than the {} bytes left of the shared limit",
+                    budget.limit().saturating_sub(budget.used())
+                ));
+            }
+        }
+
+        let this = Self {
+            max_memory,
+            budget,
+            usage: AtomicU64::new(usage),
+            measured: AtomicU64::new(usage),
+            #[cfg(feature = "metrics")]
+            metrics: ZoneMemoryMetrics::new(origin),
+        };
+
+        #[cfg(feature = "metrics")]
+        this.metrics.usage.set(usage as f64);
+        Ok(this)
+    }
+
+    /// The usage of the zone when it was last measured
+    pub(crate) fn usage(&self) -> u64 {
+        self.measured.load(Ordering::Relaxed)
+    }
+
+    /// Reserves `bytes` for data about to be added, false if that exceeds one of the limits
+    pub(crate) fn try_reserve(&self, origin: &LowerName, bytes: u64) -> bool {
+        let max_memory = self.max_memory;
+        let reserved = self
+            .usage
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+                usage
+                    .checked_add(bytes)
+                    .filter(|usage| !matches!(max_memory, Some(max) if *usage > max))
+            })
+            .is_ok();
+
+        let reserved = reserved
+            && match &self.budget {
+                Some(budget) if !budget.try_reserve(bytes) => {
+                    self.usage.fetch_sub(bytes, Ordering::Relaxed);
+                    false
+                }
+                _ => true,
+            };
+
+        if !reserved {
+            warn!(%origin, bytes, usage = self.usage(), "memory limit of zone reached");
+            #[cfg(feature = "metrics")]
+            self.metrics.refused.increment(1);
+        }
+
+        reserved
+    }
+
+    /// Releases the `reserved` bytes, and replaces the last measured usage by `measured`
+    ///
+    /// Must be called while the zone can't be changed, e.g. with its write lock held, so that
+    /// `measured` is consistent and concurrent updates are settled one after another. The zone may
+    /// exceed its limits by the amount the reservation underestimated the update.
+    pub(crate) fn settle(&self, reserved: u64, measured: u64) {
+        let released = reserved + self.measured.swap(measured, Ordering::Relaxed);
+        let _ = self
+            .usage
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+                Some(usage.saturating_sub(released) + measured)
+            });
+
+        if let Some(budget) = &self.budget {
+            budget.replace(released, measured);
+        }
+
+        #[cfg(feature = "metrics")]
+        self.metrics.usage.set(measured as f64);
+    }
+}
+
+impl Drop for ZoneMemory {
+    fn drop(&mut self) {
+        if let Some(budget) = &self.budget {
+            budget.replace(self.usage.load(Ordering::Relaxed), 0);
+        }
+    }
+}
+
+/// Estimates the memory in bytes used by an RRset and its key in a zone
+pub(crate) fn rrset_size(rrset: &RecordSet, buffer: &mut Vec<u8>) -> u64 {
+    (mem::size_of::<RrKey>() + mem::size_of::<RecordSet>()) as u64
+        + rrset
+            .records_without_rrsigs()
+            .chain(rrset.rrsigs())
+            .map(|record| record_size(record, buffer))
+            .sum::<u64>()
+}
+
+/// Estimates the memory in bytes used by a record, `buffer` is used as scratch space
+pub(crate) fn record_size(record: &Record, buffer: &mut Vec<u8>) -> u64 {
+    buffer.clear();
+    let rdata_len = match record.data().emit(&mut BinEncoder::new(buffer)) {
+        Ok(()) => buffer.len(),
+        Err(_) => 0,
+    };
+    (mem::size_of::<Record>() + record.name().len() + rdata_len) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_zone_limit() {
+        let origin = LowerName::from_str("example.com.").unwrap();
+        assert!(ZoneMemory::new(&origin, 200, Some(100), None).is_err());
+
+        let memory = ZoneMemory::new(&origin, 60, Some(100), None).unwrap();
+        assert!(!memory.try_reserve(&origin, 50));
+        assert_eq!(memory.usage(), 60);
+
+        assert!(memory.try_reserve(&origin, 40));
+        assert!(!memory.try_reserve(&origin, 1));
+
+        // the update grew the zone from 60 to 80 bytes
+        memory.settle(40, 80);
+        assert_eq!(memory.usage(), 80);
+        assert!(memory.try_reserve(&origin, 20));
+    }
+
+    #[test]
+    fn test_concurrent_updates() {
+        let origin = LowerName::from_str("example.com.").unwrap();
+        let memory = ZoneMemory::new(&origin, 60, Some(100), None).unwrap();
+
+        assert!(memory.try_reserve(&origin, 20));
+        assert!(memory.try_reserve(&origin, 20));
+
+        // the first update settles after both were applied, the zone is measured with both
+        memory.settle(20, 90);
+        assert_eq!(memory.usage(), 90);
+        assert!(!memory.try_reserve(&origin, 1));
+
+        memory.settle(20, 90);
+        assert_eq!(memory.usage(), 90);
+        assert!(memory.try_reserve(&origin, 10));
+    }
+
+    #[test]
+    fn test_shared_budget() {
+        let budget = Arc::new(MemoryBudget::new(100));
+        let example = LowerName::from_str("example.com.").unwrap();
+        let other = LowerName::from_str("example.net.").unwrap();
+
+        let first = ZoneMemory::new(&example, 60, None, Some(budget.clone())).unwrap();
+        assert!(ZoneMemory::new(&other, 50, None, Some(budget.clone())).is_err());
+
+        let second = ZoneMemory::new(&other, 30, Some(1_000), Some(budget.clone())).unwrap();
+        assert_eq!(budget.used(), 90);
+
+        // the shared limit applies, although the zone is within its own limit
+        assert!(!second.try_reserve(&other, 20));
+        assert_eq!(second.usage(), 30);
+        assert!(first.try_reserve(&example, 10));
+        assert_eq!(budget.used(), 100);
+
+        // the reservation is released when the update settles
+        first.settle(10, 65);
+        assert_eq!(budget.used(), 95);
+
+        drop(first);
+        assert_eq!(budget.used(), 30);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::authority::{AuthLookup, LookupControlFlow};
+use crate::proto::rr::LowerName;
 use metrics::{Counter, Gauge, Unit, counter, describe_counter, describe_gauge, gauge};
 
 pub(super) struct StoreMetrics {
@@ -127,3 +128,33 @@ impl QueryStoreMetrics {
         }
     }
 }
+
+pub(super) struct ZoneMemoryMetrics {
+    pub(super) usage: Gauge,
+    pub(super) refused: Counter,
+}
+
+impl ZoneMemoryMetrics {
+    pub(super) fn new(zone: &LowerName) -> Self {
+        let zone_key = "zone";
+        let zone = zone.to_string();
+
+        let zone_memory_name = "hickory_zone_memory_bytes";
+        let usage = gauge!(zone_memory_name, zone_key => zone.clone());
+        describe_gauge!(
+            zone_memory_name,
+            Unit::Bytes,
+            "estimated memory used by the records of a dns zone"
+        );
+
+        let zone_memory_refused_name = "hickory_zone_memory_refused_total";
+        let refused = counter!(zone_memory_refused_name, zone_key => zone);
+        describe_counter!(
+            zone_memory_refused_name,
+            Unit::Count,
+            "number of dns zone updates refused because of a memory limit"
+        );
+
+        Self { usage, refused }
+    }
+}
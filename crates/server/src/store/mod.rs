@@ -11,8 +11,11 @@ pub mod blocklist;
 pub mod file;
 pub mod forwarder;
 pub mod in_memory;
+pub mod intercept;
+pub mod memory;
 #[cfg(feature = "metrics")]
 mod metrics;
 pub mod recursor;
+pub mod rpz;
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
@@ -75,6 +75,9 @@ impl<P: RuntimeProvider> RecursiveAuthority<P> {
         if let Some(response_cache_size) = config.response_cache_size {
             builder = builder.response_cache_size(response_cache_size);
         }
+        if let Some(server_damping) = config.server_damping {
+            builder = builder.server_damping(Duration::from_secs(server_damping));
+        }
 
         let recursor = builder
             .dnssec_policy(config.dnssec_policy.load().map_err(|e| e.to_string())?)
@@ -272,6 +275,10 @@ pub struct RecursiveConfig {
     /// [draft-vixie-dnsext-dns0x20-00](https://datatracker.ietf.org/doc/html/draft-vixie-dnsext-dns0x20-00).
     #[serde(default)]
     pub case_randomization: bool,
+
+    /// Number of seconds a name server is avoided for a zone after it timed out, refused a query
+    /// or was found to be lame for it. Disabled when unset or 0.
+    pub server_damping: Option<u64>,
 }
 
 impl RecursiveConfig {
@@ -0,0 +1,550 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Response Policy Zones compiled from block lists
+//!
+//! A Response Policy Zone (RPZ) distributes a block list to other resolvers, which load it with
+//! zone transfers, e.g. BIND with `response-policy` or Unbound with `rpz:` clauses.
+
+#![cfg(feature = "blocklist")]
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+use tokio::fs;
+use tracing::{debug, info, warn};
+
+#[cfg(feature = "__dnssec")]
+use crate::dnssec::NxProofKind;
+use crate::{
+    authority::{AxfrPolicy, ZoneType},
+    proto::rr::{
+        LowerName, Name, RData, Record, RecordSet, RecordType, RrKey,
+        rdata::{CNAME, NS, SOA},
+    },
+    store::{blocklist::parse_entries, in_memory::InMemoryAuthority, memory::MemoryBudget},
+};
+
+/// The policy action applied to the names on the block lists
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RpzAction {
+    /// Answer NXDOMAIN, encoded as `CNAME .`
+    #[default]
+    Nxdomain,
+    /// Answer NOERROR without records, encoded as `CNAME *.`
+    Nodata,
+    /// Do not answer at all, encoded as `CNAME rpz-drop.`
+    Drop,
+}
+
+impl RpzAction {
+    fn target(self) -> Name {
+        match self {
+            Self::Nxdomain => Name::root(),
+            Self::Nodata => Name::root().prepend_label("*").expect("valid label"),
+            Self::Drop => Name::root().prepend_label("rpz-drop").expect("valid label"),
+        }
+    }
+}
+
+/// Configuration for zones compiled from block lists
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpzConfig {
+    /// Block lists to compile, in the format accepted by
+    /// [`BlocklistAuthority::add`](super::blocklist::BlocklistAuthority::add). These should be
+    /// specified as relative (to the server zone directory) paths in the config file.
+    ///
+    /// Wildcard entries like `*.example.com` are compiled into wildcard triggers, which match the
+    /// names below `example.com` but not `example.com` itself.
+    pub lists: Vec<String>,
+
+    /// The action for the names on the block lists. Defaults to NXDOMAIN.
+    pub action: RpzAction,
+
+    /// TTL of the records of the zone, in seconds.  Defaults to 300 seconds.
+    pub ttl: u32,
+
+    /// Interval in seconds at which the block lists are checked for changes, if set. Changed lists
+    /// are compiled again and the serial of the zone is incremented, so that secondaries transfer
+    /// the zone again.
+    pub refresh_interval: Option<u64>,
+}
+
+impl Default for RpzConfig {
+    fn default() -> Self {
+        Self {
+            lists: vec![],
+            action: RpzAction::default(),
+            ttl: 300,
+            refresh_interval: None,
+        }
+    }
+}
+
+/// A Response Policy Zone compiled from block lists
+///
+/// The zone is served by an [`InMemoryAuthority`], see [`RpzZone::authority`]. It contains an SOA
+/// and an NS record at the apex, as required for a valid zone, and a policy record for every
+/// name on the block lists.
+pub struct RpzZone {
+    origin: Name,
+    action: RpzAction,
+    ttl: u32,
+    authority: Arc<InMemoryAuthority>,
+    sources: Vec<Source>,
+    /// Number of block lists every compiled name is on
+    names: HashMap<LowerName, usize>,
+}
+
+impl RpzZone {
+    /// Compiles the block lists of the configuration into a new zone
+    ///
+    /// The initial serial of the zone is the current UNIX time, so that it increases across
+    /// restarts of the server.
+    pub async fn try_from_config(
+        origin: Name,
+        config: &RpzConfig,
+        axfr_policy: AxfrPolicy,
+        base_dir: Option<&Path>,
+        #[cfg(feature = "__dnssec")] nx_proof_kind: Option<NxProofKind>,
+    ) -> Result<Self, String> {
+        info!("compiling response policy zone: {origin}");
+
+        let Some(base_dir) = base_dir else {
+            return Err(format!(
+                "invalid rpz (zone directory) base path specified: '{base_dir:?}'"
+            ));
+        };
+
+        let serial = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |now| now.as_secs() as u32);
+        let apex = [
+            Record::from_rdata(
+                origin.clone(),
+                config.ttl,
+                RData::SOA(SOA::new(
+                    Name::from_ascii("localhost.").expect("valid name"),
+                    Name::from_ascii("hostmaster.localhost.").expect("valid name"),
+                    serial,
+                    3_600,
+                    600,
+                    86_400,
+                    config.ttl,
+                )),
+            ),
+            Record::from_rdata(
+                origin.clone(),
+                config.ttl,
+                RData::NS(NS(Name::from_ascii("localhost.").expect("valid name"))),
+            ),
+        ];
+
+        let mut records = BTreeMap::new();
+        for record in apex {
+            let mut rrset = RecordSet::new(record.name().clone(), record.record_type(), serial);
+            rrset.insert(record, serial);
+            records.insert(
+                RrKey::new(origin.clone().into(), rrset.record_type()),
+                rrset,
+            );
+        }
+
+        let authority = InMemoryAuthority::new(
+            origin.clone(),
+            records,
+            ZoneType::Primary,
+            axfr_policy,
+            #[cfg(feature = "__dnssec")]
+            nx_proof_kind,
+        )?;
+
+        let mut zone = Self {
+            origin,
+            action: config.action,
+            ttl: config.ttl,
+            authority: Arc::new(authority),
+            sources: config
+                .lists
+                .iter()
+                .map(|list| Source::new(base_dir.join(list)))
+                .collect(),
+            names: HashMap::new(),
+        };
+
+        // the serial of the initial contents is the one of the SOA record
+        zone.compile(false)
+            .await
+            .map_err(|e| format!("unable to compile response policy zone: {e}"))?;
+
+        Ok(zone)
+    }
+
+    /// The authority that serves the zone
+    pub fn authority(&self) -> Arc<InMemoryAuthority> {
+        self.authority.clone()
+    }
+
+    /// Compiles the block lists that changed since they were last compiled into the zone
+    ///
+    /// Block lists are detected as changed by their modification time and size. Only the names
+    /// that were added to or removed from the block lists are updated in the zone. If there are
+    /// any, the serial of the zone is incremented.
+    ///
+    /// # Return value
+    ///
+    /// `true` if the zone changed.
+    pub async fn rebuild(&mut self) -> Result<bool, String> {
+        self.compile(true).await
+    }
+
+    /// Limit the memory used by the records of the zone
+    ///
+    /// Rebuilds that would exceed `max_memory` bytes, or the memory left of the `budget` shared
+    /// with other zones, fail and leave the zone unchanged, see
+    /// [`InMemoryAuthority::set_memory_limits`]. Must be called before the authority is shared.
+    pub fn set_memory_limits(
+        &mut self,
+        max_memory: Option<u64>,
+        budget: Option<Arc<MemoryBudget>>,
+    ) -> Result<(), String> {
+        let Some(authority) = Arc::get_mut(&mut self.authority) else {
+            return Err(format!(
+                "memory limits of response policy zone {} set after it was shared",
+                self.origin
+            ));
+        };
+
+        authority.set_memory_limits(max_memory, budget)
+    }
+
+    async fn compile(&mut self, increment_serial: bool) -> Result<bool, String> {
+        let mut changed = Vec::new();
+        for (index, source) in self.sources.iter().enumerate() {
+            if let Some((fingerprint, names)) = source.read_if_changed().await? {
+                debug!("compiling block list {}", source.path.display());
+                changed.push((index, fingerprint, names));
+            }
+        }
+
+        // the number of block lists the names are on after the changes
+        let mut counts = HashMap::new();
+        for (index, _, names) in &changed {
+            let source = &self.sources[*index];
+            for name in names.difference(&source.names) {
+                *self.count(&mut counts, name) += 1;
+            }
+
+            for name in source.names.difference(names) {
+                let count = self.count(&mut counts, name);
+                *count = count.saturating_sub(1);
+            }
+        }
+
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        for (name, count) in &counts {
+            match (self.names.contains_key(name), *count > 0) {
+                (false, true) => added.push(name.clone()),
+                (true, false) => removed.push(name.clone()),
+                _ => {}
+            }
+        }
+
+        // reserve the memory of the new records before anything is changed, so that a refused
+        // rebuild is tried again with the same block lists
+        let target = self.action.target();
+        let records = added
+            .iter()
+            .filter_map(|name| self.owner(name))
+            .map(|owner| {
+                Record::from_rdata(owner.into(), self.ttl, RData::CNAME(CNAME(target.clone())))
+            })
+            .collect::<Vec<_>>();
+        let reservation = self.authority.reserve_memory(&records).map_err(|_| {
+            format!(
+                "memory limit of response policy zone {} reached, {} names not added",
+                self.origin,
+                added.len()
+            )
+        })?;
+
+        for (name, count) in counts {
+            match count {
+                0 => self.names.remove(&name),
+                count => self.names.insert(name, count),
+            };
+        }
+
+        for (index, fingerprint, names) in changed {
+            let source = &mut self.sources[index];
+            source.fingerprint = fingerprint;
+            source.names = names;
+        }
+
+        if added.is_empty() && removed.is_empty() {
+            return Ok(false);
+        }
+
+        let serial = if increment_serial {
+            self.authority.increment_soa_serial().await
+        } else {
+            self.authority.serial().await
+        };
+
+        {
+            let mut records = self.authority.records_mut().await;
+            for name in &removed {
+                let Some(owner) = self.owner(name) else {
+                    continue;
+                };
+
+                records.remove(&RrKey::new(owner, RecordType::CNAME));
+            }
+        }
+
+        for record in records {
+            self.authority.upsert(record, serial).await;
+        }
+        self.authority.settle_memory(reservation).await;
+
+        info!(
+            "response policy zone {} serial {serial}: {} names added, {} removed",
+            self.origin,
+            added.len(),
+            removed.len()
+        );
+
+        Ok(true)
+    }
+
+    /// The entry of `name` in `counts`, starting from the number of block lists it is on
+    fn count<'a>(
+        &self,
+        counts: &'a mut HashMap<LowerName, usize>,
+        name: &LowerName,
+    ) -> &'a mut usize {
+        counts
+            .entry(name.clone())
+            .or_insert_with(|| self.names.get(name).copied().unwrap_or_default())
+    }
+
+    /// The owner name of the policy record for a blocked name
+    fn owner(&self, name: &LowerName) -> Option<LowerName> {
+        match Name::from(name).append_domain(&self.origin) {
+            Ok(owner) => Some(owner.into()),
+            Err(e) => {
+                warn!(
+                    "unable to add {name} to response policy zone {}: {e}",
+                    self.origin
+                );
+                None
+            }
+        }
+    }
+}
+
+/// A block list and the names it contained when it was last compiled
+struct Source {
+    path: PathBuf,
+    /// Modification time and size of the file when it was last compiled
+    fingerprint: Option<Fingerprint>,
+    names: HashSet<LowerName>,
+}
+
+impl Source {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            fingerprint: None,
+            names: HashSet::new(),
+        }
+    }
+
+    /// Returns the fingerprint and the names of the block list if it changed since it was compiled
+    async fn read_if_changed(
+        &self,
+    ) -> Result<Option<(Option<Fingerprint>, HashSet<LowerName>)>, String> {
+        let path = self.path.display();
+        let metadata = fs::metadata(&self.path)
+            .await
+            .map_err(|e| format!("unable to read block list {path}: {e}"))?;
+        let fingerprint = metadata
+            .modified()
+            .map(|modified| (modified, metadata.len()))
+            .ok();
+        if fingerprint.is_some() && fingerprint == self.fingerprint {
+            return Ok(None);
+        }
+
+        let contents = fs::read_to_string(&self.path)
+            .await
+            .map_err(|e| format!("unable to read block list {path}: {e}"))?;
+
+        let names = parse_entries(&contents)
+            .into_iter()
+            .filter(|name| {
+                // only the leftmost label of a trigger may be a wildcard
+                let valid = name.iter().skip(1).all(|label| label != b"*");
+                if !valid {
+                    warn!("invalid wildcard in block list entry {name}; skipping entry");
+                }
+                valid
+            })
+            .map(LowerName::from)
+            .collect();
+
+        Ok(Some((fingerprint, names)))
+    }
+}
+
+/// Modification time and size of a block list
+type Fingerprint = (SystemTime, u64);
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, str::FromStr};
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::authority::{Authority, LookupOptions};
+
+    async fn zone(dir: &TempDir, lists: &[&str], action: RpzAction) -> RpzZone {
+        let config = RpzConfig {
+            lists: lists.iter().map(|list| list.to_string()).collect(),
+            action,
+            ..RpzConfig::default()
+        };
+
+        RpzZone::try_from_config(
+            Name::from_str("rpz.example.").unwrap(),
+            &config,
+            AxfrPolicy::AllowAll,
+            Some(dir.path()),
+            #[cfg(feature = "__dnssec")]
+            None,
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn target(zone: &RpzZone, name: &str) -> Option<Name> {
+        let name = LowerName::from_str(name).unwrap();
+        let lookup = zone
+            .authority()
+            .lookup(&name, RecordType::CNAME, LookupOptions::default())
+            .await
+            .map_result()?
+            .ok()?;
+
+        lookup
+            .iter()
+            .find_map(|record| record.data().as_cname().map(|cname| cname.0.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_compile() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("one.txt"),
+            "malware.example.com\n0.0.0.0 ads.example.net # hosts format\n*.tracker.example.org\n",
+        )
+        .unwrap();
+
+        let zone = zone(&dir, &["one.txt"], RpzAction::Nodata).await;
+
+        let nodata = Name::from_str("*.").unwrap();
+        assert_eq!(
+            target(&zone, "malware.example.com.rpz.example.").await,
+            Some(nodata.clone())
+        );
+        assert_eq!(
+            target(&zone, "ads.example.net.rpz.example.").await,
+            Some(nodata.clone())
+        );
+        assert_eq!(
+            target(&zone, "www.tracker.example.org.rpz.example.").await,
+            Some(nodata)
+        );
+        assert_eq!(
+            target(&zone, "tracker.example.org.rpz.example.").await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rebuild() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("one.txt"), "a.example.com\nb.example.com\n").unwrap();
+        fs::write(dir.path().join("two.txt"), "b.example.com\n").unwrap();
+
+        let mut zone = zone(&dir, &["one.txt", "two.txt"], RpzAction::Nxdomain).await;
+        let serial = zone.authority().serial().await;
+        assert!(!zone.rebuild().await.unwrap());
+
+        // b.example.com is still on two.txt
+        fs::write(dir.path().join("one.txt"), "c.example.com\n").unwrap();
+        assert!(zone.rebuild().await.unwrap());
+        assert!(zone.authority().serial().await > serial);
+
+        assert_eq!(target(&zone, "a.example.com.rpz.example.").await, None);
+        assert_eq!(
+            target(&zone, "b.example.com.rpz.example.").await,
+            Some(Name::root())
+        );
+        assert_eq!(
+            target(&zone, "c.example.com.rpz.example.").await,
+            Some(Name::root())
+        );
+
+        fs::write(dir.path().join("two.txt"), "").unwrap();
+        assert!(zone.rebuild().await.unwrap());
+        assert_eq!(target(&zone, "b.example.com.rpz.example.").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_over_memory_limit() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("one.txt"), "a.example.com\n").unwrap();
+
+        let mut zone = zone(&dir, &["one.txt"], RpzAction::Nxdomain).await;
+        zone.set_memory_limits(None, None).unwrap();
+        let usage = zone.authority().memory_usage().unwrap();
+        zone.set_memory_limits(Some(usage + 1), None).unwrap();
+        let serial = zone.authority().serial().await;
+
+        // the zone is left unchanged, and the block list is compiled again on the next rebuild
+        fs::write(dir.path().join("one.txt"), "b.example.com\n").unwrap();
+        assert!(zone.rebuild().await.is_err());
+        assert!(zone.rebuild().await.is_err());
+        assert_eq!(zone.authority().serial().await, serial);
+        assert_eq!(zone.authority().memory_usage(), Some(usage));
+        assert_eq!(
+            target(&zone, "a.example.com.rpz.example.").await,
+            Some(Name::root())
+        );
+        assert_eq!(target(&zone, "b.example.com.rpz.example.").await, None);
+
+        // removing names does not need more memory
+        fs::write(dir.path().join("one.txt"), "").unwrap();
+        assert!(zone.rebuild().await.unwrap());
+        assert!(zone.authority().memory_usage().unwrap() < usage);
+        assert_eq!(target(&zone, "a.example.com.rpz.example.").await, None);
+
+        let authority = zone.authority();
+        assert!(zone.set_memory_limits(None, None).is_err());
+        drop(authority);
+    }
+}
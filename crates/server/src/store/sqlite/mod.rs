@@ -20,6 +20,7 @@ use std::{
 };
 
 use futures_util::lock::Mutex;
+use ipnet::IpNet;
 use serde::Deserialize;
 use tracing::{debug, error, info, warn};
 
@@ -28,7 +29,7 @@ use crate::store::metrics::StoreMetrics;
 use crate::{
     authority::{
         AuthLookup, Authority, AxfrPolicy, LookupControlFlow, LookupError, LookupOptions,
-        UpdateResult, ZoneType,
+        MinimalResponses, UpdateResult, ZoneType,
     },
     error::{PersistenceError, PersistenceErrorKind},
     proto::{
@@ -47,7 +48,7 @@ use crate::{
     authority::{DnssecAuthority, Nsec3QueryInfo, UpdateRequest},
     dnssec::NxProofKind,
     proto::dnssec::{
-        DnsSecResult, SigSigner, Verifier,
+        DnsSecResult, Sig0Keyring, SigSigner,
         rdata::{
             DNSSECRData,
             key::KEY,
@@ -682,6 +683,9 @@ impl SqliteAuthority {
 
     /// Updates the specified records according to the update section.
     ///
+    /// Updates that could exceed the memory limits of the zone are refused, see
+    /// [`InMemoryAuthority::set_memory_limits`].
+    ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
     ///
     /// ```text
@@ -705,6 +709,19 @@ impl SqliteAuthority {
         &self,
         records: &[Record],
         auto_signing_and_increment: bool,
+    ) -> UpdateResult<bool> {
+        let reservation = self.in_memory.reserve_memory(records)?;
+        let updated = self
+            .apply_update_records(records, auto_signing_and_increment)
+            .await;
+        self.in_memory.settle_memory(reservation).await;
+        updated
+    }
+
+    async fn apply_update_records(
+        &self,
+        records: &[Record],
+        auto_signing_and_increment: bool,
     ) -> UpdateResult<bool> {
         let mut updated = false;
         let serial: u32 = self.in_memory.serial().await;
@@ -925,25 +942,20 @@ impl SqliteAuthority {
         };
 
         debug!("found keys {keys:?}");
-        let verified = keys
-            .iter()
-            .filter_map(|rr_set| rr_set.data().as_dnssec().and_then(DNSSECRData::as_key))
-            .any(
-                |key| match key.verify_message(&request.message, sig0.sig(), sig0.input()) {
-                    Ok(_) => {
-                        info!("verified sig: {sig0:?} with key: {key:?}");
-                        true
-                    }
-                    Err(_) => {
-                        debug!("did not verify sig: {sig0:?} with key: {key:?}");
-                        false
-                    }
-                },
-            );
-        match verified {
-            true => Ok(()),
-            false => {
-                warn!("invalid sig0 signature: id {}", request.id());
+        let mut keyring = Sig0Keyring::empty();
+        keyring.extend_from_records(keys.iter());
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_secs() as u32)
+            .unwrap_or_default();
+        match keyring.verify_message(&request.message, sig0, now) {
+            Ok(key) => {
+                info!("verified sig: {sig0:?} with key: {key:?}");
+                Ok(())
+            }
+            Err(err) => {
+                warn!("invalid sig0 signature: id {}: {err}", request.id());
                 Err(ResponseCode::Refused)
             }
         }
@@ -1030,6 +1042,16 @@ impl Authority for SqliteAuthority {
         self.axfr_policy
     }
 
+    /// Which optional records are added to responses from this zone
+    fn minimal_responses(&self) -> MinimalResponses {
+        self.in_memory.minimal_responses()
+    }
+
+    /// Networks of the secondaries which must transfer this zone over TLS
+    fn xot_required(&self) -> &[IpNet] {
+        self.in_memory.xot_required()
+    }
+
     /// Takes the UpdateMessage, extracts the Records, and applies the changes to the record set.
     ///
     /// # Arguments
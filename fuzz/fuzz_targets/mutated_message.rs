@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use hickory_proto::{
+    op::{Message, Mutator},
+    serialize::binary::BinDecodable,
+};
+
+// corrupted variants of valid messages may be rejected, but decoding them must not panic
+fuzz_target!(|original: Message| {
+    let Ok(mutator) = Mutator::new(&original) else {
+        return;
+    };
+
+    for (_, bytes) in mutator.variants() {
+        let _ = Message::from_bytes(&bytes);
+    }
+});
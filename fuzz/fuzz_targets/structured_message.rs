@@ -0,0 +1,27 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pretty_assertions::assert_eq;
+
+use hickory_proto::{
+    op::Message,
+    serialize::binary::{BinDecodable, BinEncodable},
+};
+
+// messages generated from the fuzzer input are always valid, so encoding them must not fail
+fuzz_target!(|original: Message| {
+    let encoded = original.to_bytes().unwrap();
+    let decoded = match Message::from_bytes(&encoded) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            eprintln!("{original:?}");
+            panic!("Message failed to deserialize: {e:?}");
+        }
+    };
+
+    assert_eq!(original.queries(), decoded.queries());
+    assert_eq!(original.answers(), decoded.answers());
+    assert_eq!(original.name_servers(), decoded.name_servers());
+    assert_eq!(original.additionals(), decoded.additionals());
+    assert_eq!(original.extensions(), decoded.extensions());
+    assert_eq!(encoded, decoded.to_bytes().unwrap());
+});
@@ -1,12 +1,16 @@
-use std::{str::FromStr, sync::Arc};
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use hickory_proto::{
-    op::{Edns, Message, MessageType, Query, ResponseCode},
+    op::{Edns, Message, MessageType, OpCode, Query, ResponseCode},
     rr::{
         DNSClass, LowerName, Name, RData, Record, RecordType,
         rdata::{
-            A, AAAA, CNAME, NS, SOA,
-            opt::{EdnsCode, EdnsOption, NSIDPayload},
+            A, AAAA, CNAME, MX, NS, SOA,
+            opt::{EdnsCode, EdnsOption, ExtendedDnsErrorCode, NSIDPayload},
         },
     },
     serialize::binary::BinEncodable,
@@ -14,13 +18,19 @@ use hickory_proto::{
 };
 #[cfg(feature = "__dnssec")]
 use hickory_server::dnssec::NxProofKind;
+use ipnet::IpNet;
+
 use hickory_server::{
-    authority::{Authority, AxfrPolicy, Catalog, ZoneType},
+    authority::{Authority, AxfrPolicy, Catalog, MinimalResponses, TtlPolicy, ZoneType},
     server::{Request, RequestHandler},
-    store::in_memory::InMemoryAuthority,
+    store::{
+        forwarder::{ForwardAuthority, ForwardConfig},
+        in_memory::InMemoryAuthority,
+    },
 };
 
 use hickory_integration::{example_authority::create_example, *};
+use hickory_resolver::config::{NameServerConfig, ResolverOpts};
 use test_support::subscribe;
 
 #[allow(clippy::unreadable_literal)]
@@ -567,6 +577,185 @@ async fn test_axfr_deny_unsigned() {
     assert!(result.additionals().is_empty());
 }
 
+#[tokio::test]
+async fn test_axfr_xot_required() {
+    subscribe();
+
+    let mut test = create_test();
+    test.set_axfr_policy(AxfrPolicy::AllowAll);
+    test.set_xot_required(vec![IpNet::from_str("192.0.2.0/24").unwrap()]);
+
+    let origin = test.origin().clone();
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(origin.clone(), vec![Arc::new(test)]);
+
+    let mut query = Query::new();
+    query.set_name(origin.into());
+    query.set_query_type(RecordType::AXFR);
+
+    let mut question = Message::query();
+    question.add_query(query);
+    let question_bytes = question.to_bytes().unwrap();
+
+    // a secondary which must use TLS is refused over TCP
+    let question_req = Request::from_bytes(
+        question_bytes.clone(),
+        ([192, 0, 2, 53], 5553).into(),
+        Protocol::Tcp,
+    )
+    .unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert!(result.answers().is_empty());
+
+    // other secondaries are still allowed by the AXFR policy
+    let question_req =
+        Request::from_bytes(question_bytes, ([127, 0, 0, 1], 5553).into(), Protocol::Tcp).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(!result.answers().is_empty());
+}
+
+#[tokio::test]
+async fn test_forward_request_timeout() {
+    subscribe();
+
+    // an upstream which never answers
+    let upstream = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let upstream_addr = upstream.local_addr().unwrap();
+
+    let mut name_server = NameServerConfig::udp(upstream_addr.ip());
+    name_server.connections[0].port = upstream_addr.port();
+    let authority = ForwardAuthority::builder_tokio(ForwardConfig {
+        name_servers: vec![name_server],
+        options: Some(ResolverOpts::default()),
+        request_timeout: Some(1),
+        ttl_policy: TtlPolicy::default(),
+        #[cfg(feature = "__tls")]
+        tls: Vec::new(),
+    })
+    .build()
+    .unwrap();
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(Name::root().into(), vec![Arc::new(authority)]);
+
+    let mut question = Message::query();
+    question.add_query(Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    question.set_recursion_desired(true);
+    let question_req = Request::from_bytes(
+        question.to_bytes().unwrap(),
+        ([127, 0, 0, 1], 5553).into(),
+        Protocol::Udp,
+    )
+    .unwrap();
+
+    let start = Instant::now();
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, Some(Edns::new()), response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    // the resolver alone would only give up after 5 second timeouts for each attempt
+    assert!(start.elapsed() < Duration::from_secs(4));
+    assert_eq!(result.response_code(), ResponseCode::ServFail);
+    let Some(EdnsOption::ExtendedDnsError(ede)) = result
+        .extensions()
+        .as_ref()
+        .and_then(|edns| edns.option(EdnsCode::ExtendedDnsError))
+    else {
+        panic!("expected an extended DNS error");
+    };
+    assert_eq!(ede.info_code(), ExtendedDnsErrorCode::NetworkError);
+}
+
+#[tokio::test]
+async fn test_forward_ttl_policy() {
+    subscribe();
+
+    // an upstream answering with a week long TTL
+    let upstream = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let upstream_addr = upstream.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut buf = [0; 512];
+        loop {
+            let (len, src) = upstream.recv_from(&mut buf).await.unwrap();
+            let request = Message::from_vec(&buf[..len]).unwrap();
+            let mut response = Message::response(request.id(), OpCode::Query);
+            response.set_recursion_available(true);
+            response.add_queries(request.queries().to_vec());
+            response.add_answer(Record::from_rdata(
+                request.queries()[0].name().clone(),
+                604_800,
+                RData::A(A::new(192, 0, 2, 1)),
+            ));
+            upstream
+                .send_to(&response.to_vec().unwrap(), src)
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut name_server = NameServerConfig::udp(upstream_addr.ip());
+    name_server.connections[0].port = upstream_addr.port();
+    let authority = ForwardAuthority::builder_tokio(ForwardConfig {
+        name_servers: vec![name_server],
+        options: Some(ResolverOpts::default()),
+        request_timeout: None,
+        ttl_policy: TtlPolicy {
+            min_ttl: None,
+            max_ttl: Some(3600),
+        },
+        #[cfg(feature = "__tls")]
+        tls: Vec::new(),
+    })
+    .build()
+    .unwrap();
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(Name::root().into(), vec![Arc::new(authority)]);
+
+    let mut question = Message::query();
+    question.add_query(Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    question.set_recursion_desired(true);
+    let question_req = Request::from_bytes(
+        question.to_bytes().unwrap(),
+        ([127, 0, 0, 1], 5553).into(),
+        Protocol::Udp,
+    )
+    .unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(result.answers().len(), 1);
+    assert_eq!(result.answers()[0].ttl(), 3600);
+}
+
 // Test that requesting NSID produces no NSID response when a payload isn't configured.
 #[tokio::test]
 async fn test_nsid_disabled_requested() {
@@ -745,6 +934,134 @@ async fn test_cname_additionals() {
     );
 }
 
+#[tokio::test]
+async fn test_minimal_responses() {
+    subscribe();
+
+    async fn lookup(
+        minimal_responses: MinimalResponses,
+        name: &str,
+        query_type: RecordType,
+    ) -> Message {
+        let mut example = create_example();
+        let origin = example.origin().clone();
+        example.upsert_mut(
+            Record::from_rdata(
+                origin.clone().into(),
+                86400,
+                RData::MX(MX::new(10, Name::from_str("www.example.com.").unwrap())),
+            ),
+            0,
+        );
+        example.set_minimal_responses(minimal_responses);
+
+        let mut catalog = Catalog::new();
+        catalog.upsert(origin, vec![Arc::new(example)]);
+
+        let mut question = Message::query();
+        question.add_query(Query::query(Name::from_str(name).unwrap(), query_type));
+        let question_bytes = question.to_bytes().unwrap();
+        let question_req =
+            Request::from_bytes(question_bytes, ([127, 0, 0, 1], 5553).into(), Protocol::Udp)
+                .unwrap();
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .lookup(&question_req, None, response_handler.clone())
+            .await;
+        response_handler.into_message().await
+    }
+
+    let result = lookup(MinimalResponses::No, "example.com.", RecordType::SOA).await;
+    assert_eq!(result.name_servers().len(), 2);
+    let result = lookup(MinimalResponses::NoAuth, "example.com.", RecordType::SOA).await;
+    assert_eq!(result.answers().len(), 1);
+    assert!(result.name_servers().is_empty());
+
+    // the addresses of the mail exchange
+    let result = lookup(MinimalResponses::NoAuth, "example.com.", RecordType::MX).await;
+    assert_eq!(result.answers().len(), 1);
+    assert_eq!(result.additionals().len(), 2);
+    let result = lookup(MinimalResponses::Yes, "example.com.", RecordType::MX).await;
+    assert_eq!(result.answers().len(), 1);
+    assert!(result.additionals().is_empty());
+
+    // the target of an alias is part of the answer
+    let result = lookup(MinimalResponses::Yes, "alias.example.com.", RecordType::A).await;
+    assert_eq!(result.answers()[0].record_type(), RecordType::CNAME);
+    assert_eq!(result.additionals().len(), 1);
+
+    // negative responses still carry the SOA record
+    let result = lookup(MinimalResponses::Yes, "nx.example.com.", RecordType::A).await;
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    assert_eq!(result.name_servers()[0].record_type(), RecordType::SOA);
+}
+
+#[tokio::test]
+async fn test_referral() {
+    subscribe();
+
+    let mut example = create_example();
+    let origin = example.origin().clone();
+    let sub = Name::from_str("sub.example.com.").unwrap();
+    for ns in ["ns.sub.example.com.", "ns.example.net."] {
+        example.upsert_mut(
+            Record::from_rdata(
+                sub.clone(),
+                86400,
+                RData::NS(NS(Name::from_str(ns).unwrap())),
+            ),
+            0,
+        );
+    }
+
+    // only names within the zone are served as glue
+    assert!(example.add_glue_mut(
+        Name::from_str("ns.sub.example.com.").unwrap(),
+        [192, 0, 2, 1].into(),
+        86400,
+    ));
+    assert!(!example.add_glue_mut(
+        Name::from_str("ns.example.net.").unwrap(),
+        [192, 0, 2, 2].into(),
+        86400,
+    ));
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(origin, vec![Arc::new(example)]);
+
+    let mut question = Message::query();
+    question.add_query(Query::query(
+        Name::from_str("www.sub.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req =
+        Request::from_bytes(question_bytes, ([127, 0, 0, 1], 5553).into(), Protocol::Udp).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(!result.authoritative());
+    assert!(result.answers().is_empty());
+    assert_eq!(result.name_servers().len(), 2);
+    assert!(
+        result
+            .name_servers()
+            .iter()
+            .all(|record| record.record_type() == RecordType::NS && *record.name() == sub)
+    );
+    assert_eq!(result.additionals().len(), 1);
+    assert_eq!(
+        result.additionals()[0].data(),
+        &RData::A(A::new(192, 0, 2, 1))
+    );
+}
+
 #[tokio::test]
 async fn test_multiple_cname_additionals() {
     subscribe();
@@ -43,6 +43,10 @@ async fn chained_authority_test() {
             "breakerr.example.com.",
             Some((ResponseType::BreakErr, all_zeros)),
         ),
+        (
+            "breakrefused.example.com.",
+            Some((ResponseType::BreakRefused, all_zeros)),
+        ),
         (
             "continueboth.example.com.",
             Some((ResponseType::ContinueOk, pri_lookup_ip)),
@@ -150,6 +154,10 @@ async fn chained_authority_test() {
     // Eighth test -- Primary returns Break(Err); secondary consult WOULD result in a record
     // returned; verify no records
     error_test(&catalog, "breakerr.example.com.", ResponseCode::NXDomain).await;
+
+    // Ninth test -- Primary returns Break(Err) with a response code other than NXDomain, verify
+    // the response code of the external authority is returned instead of ServFail
+    error_test(&catalog, "breakrefused.example.com.", ResponseCode::Refused).await;
 }
 
 struct TestAuthority {
@@ -272,6 +280,7 @@ enum ResponseType {
     BreakOk,
     ContinueErr,
     BreakErr,
+    BreakRefused,
     Skip,
 }
 
@@ -327,6 +336,9 @@ fn inner_lookup(
                         ResponseCode::NXDomain,
                     ))));
                 }
+                ResponseType::BreakRefused => {
+                    return Some(Break(Err(LookupError::ResponseCode(ResponseCode::Refused))));
+                }
                 ResponseType::Skip => return Some(LookupControlFlow::Skip),
             }
         }
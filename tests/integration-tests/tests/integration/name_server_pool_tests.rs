@@ -14,12 +14,15 @@ use futures::executor::block_on;
 use hickory_integration::mock_client::*;
 use hickory_proto::op::{Query, ResponseCode};
 use hickory_proto::rr::{Name, RecordType};
-use hickory_proto::xfer::{DnsHandle, DnsResponse, FirstAnswer};
+use hickory_proto::xfer::{
+    DnsHandle, DnsRequest, DnsRequestOptions, DnsResponse, FirstAnswer, TruncationPolicy,
+};
 use hickory_proto::{NoRecords, ProtoError, ProtoErrorKind};
 use hickory_resolver::config::{
-    ConnectionConfig, NameServerConfig, ProtocolConfig, ResolverOpts, ServerOrderingStrategy,
+    ConnectionConfig, NameServerConfig, PrivacyProfile, ProtocolConfig, ResolverOpts,
+    ServerOrderingStrategy,
 };
-use hickory_resolver::name_server::{NameServer, NameServerPool};
+use hickory_resolver::name_server::{NameServer, NameServerPool, PrivacyStats};
 use test_support::subscribe;
 
 const DEFAULT_SERVER_ADDR: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
@@ -260,6 +263,86 @@ fn test_datagram_stream_upgrade_on_truncation_despite_udp() {
     assert_eq!(response.answers(), &[tcp_record1, tcp_record2]);
 }
 
+fn build_request_with_policy(query: Query, truncation_policy: TruncationPolicy) -> DnsRequest {
+    let mut options = DnsRequestOptions::default();
+    options.truncation_policy = truncation_policy;
+    let (message, _) = build_request(query).into_parts();
+    DnsRequest::new(message, options)
+}
+
+#[test]
+fn test_datagram_returns_truncated() {
+    subscribe();
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+
+    let udp_record = v4_record(query.name().clone(), Ipv4Addr::LOCALHOST);
+    let tcp_record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 2));
+
+    let mut udp_message = message(query.clone(), vec![udp_record.clone()], vec![], vec![]);
+    udp_message.set_truncated(true);
+    let tcp_message = message(query.clone(), vec![tcp_record], vec![], vec![]);
+
+    let udp_nameserver = mock_nameserver(
+        vec![Ok(DnsResponse::from_message(udp_message).unwrap())],
+        ProtocolConfig::Udp,
+        Default::default(),
+    );
+    let tcp_nameserver = mock_nameserver(
+        vec![Ok(DnsResponse::from_message(tcp_message).unwrap())],
+        ProtocolConfig::Tcp,
+        Default::default(),
+    );
+
+    let mut opts = ResolverOpts::default();
+    opts.num_concurrent_reqs = 1;
+    let pool = mock_nameserver_pool(vec![udp_nameserver, tcp_nameserver], None, opts);
+
+    let request = build_request_with_policy(query, TruncationPolicy::ReturnTruncated);
+    let response = block_on(pool.send(request).first_answer()).unwrap();
+    assert!(response.truncated());
+    assert_eq!(response.answers(), &[udp_record]);
+}
+
+#[test]
+fn test_datagram_retries_with_larger_edns_on_truncation() {
+    subscribe();
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+
+    let udp_record = v4_record(query.name().clone(), Ipv4Addr::LOCALHOST);
+    let tcp_record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 2));
+
+    let mut truncated_message = message(query.clone(), vec![], vec![], vec![]);
+    truncated_message.set_truncated(true);
+    let udp_message = message(query.clone(), vec![udp_record.clone()], vec![], vec![]);
+    let tcp_message = message(query.clone(), vec![tcp_record], vec![], vec![]);
+
+    // the messages are popped off the back, so the truncated response comes first
+    let udp_nameserver = mock_nameserver(
+        vec![
+            Ok(DnsResponse::from_message(udp_message).unwrap()),
+            Ok(DnsResponse::from_message(truncated_message).unwrap()),
+        ],
+        ProtocolConfig::Udp,
+        Default::default(),
+    );
+    let tcp_nameserver = mock_nameserver(
+        vec![Ok(DnsResponse::from_message(tcp_message).unwrap())],
+        ProtocolConfig::Tcp,
+        Default::default(),
+    );
+
+    let mut opts = ResolverOpts::default();
+    opts.num_concurrent_reqs = 1;
+    let pool = mock_nameserver_pool(vec![udp_nameserver, tcp_nameserver], None, opts);
+
+    let request = build_request_with_policy(query, TruncationPolicy::RetryLargerEdns);
+    let response = block_on(pool.send(request).first_answer()).unwrap();
+    assert!(!response.truncated());
+    assert_eq!(response.answers(), &[udp_record]);
+}
+
 #[test]
 fn test_datagram_fails_to_stream() {
     // Lookup to UDP should fail, and then the query should be retried on TCP because
@@ -608,7 +691,7 @@ fn test_distrust_nx_responses() {
         let response = block_on(fut).expect("query did not eventually succeed");
         assert_eq!(
             response.answers(),
-            [v4_record.clone()],
+            std::slice::from_ref(&v4_record),
             "did not see expected fallback behavior on response code `{}`",
             response_code
         );
@@ -941,3 +1024,176 @@ fn test_concurrent_requests_0_conn() {
     let response = block_on(future).unwrap();
     assert_eq!(response.answers()[0], udp_record);
 }
+
+#[cfg(test)]
+fn mock_nameserver_with_privacy(
+    messages: Vec<Result<DnsResponse, ProtoError>>,
+    protocol: ProtocolConfig,
+    privacy: PrivacyProfile,
+) -> MockedNameServer<DefaultOnSend> {
+    let client = MockClientHandle::mock_on_send(messages, DefaultOnSend);
+
+    let mut config = NameServerConfig::new(
+        DEFAULT_SERVER_ADDR,
+        true,
+        vec![ConnectionConfig::new(protocol)],
+    );
+    config.privacy = privacy;
+    let connection_config = config.connections.first().unwrap().clone();
+
+    NameServer::from_conn(
+        &config,
+        connection_config,
+        Arc::new(ResolverOpts::default()),
+        client,
+        MockConnProvider {
+            on_send: DefaultOnSend,
+        },
+    )
+}
+
+#[test]
+fn test_privacy_fallback_is_tried_last() {
+    subscribe();
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+    let fallback_record = v4_record(query.name().clone(), Ipv4Addr::LOCALHOST);
+    let tcp_record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 2));
+
+    let pool = |tcp_response: Result<DnsResponse, ProtoError>| {
+        // the unencrypted UDP connection of an opportunistic name server is only used once the
+        // TCP connection failed, even though UDP is preferred otherwise
+        let fallback = mock_nameserver_with_privacy(
+            vec![Ok(DnsResponse::from_message(message(
+                query.clone(),
+                vec![fallback_record.clone()],
+                vec![],
+                vec![],
+            ))
+            .unwrap())],
+            ProtocolConfig::Udp,
+            PrivacyProfile::Opportunistic,
+        );
+        let tcp = mock_nameserver_with_privacy(
+            vec![tcp_response],
+            ProtocolConfig::Tcp,
+            PrivacyProfile::Any,
+        );
+
+        let mut options = ResolverOpts::default();
+        options.num_concurrent_reqs = 1;
+        mock_nameserver_pool(vec![fallback, tcp], None, options)
+    };
+
+    let tcp_message = message(query.clone(), vec![tcp_record.clone()], vec![], vec![]);
+    let pool1 = pool(Ok(DnsResponse::from_message(tcp_message).unwrap()));
+    let response = block_on(pool1.send(build_request(query.clone())).first_answer()).unwrap();
+    assert_eq!(response.answers()[0], tcp_record);
+    assert_eq!(pool1.privacy_stats(), PrivacyStats::default());
+
+    let io_error = std::io::Error::other("Some I/O Error");
+    let pool2 = pool(Err(ProtoError::from(io_error)));
+    let response = block_on(pool2.send(build_request(query)).first_answer()).unwrap();
+    assert_eq!(response.answers()[0], fallback_record);
+    assert_eq!(
+        pool2.privacy_stats(),
+        PrivacyStats {
+            downgrades: 1,
+            outages: 0,
+        }
+    );
+}
+
+#[test]
+fn test_privacy_outage() {
+    subscribe();
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+    let io_error = || Err(ProtoError::from(std::io::Error::other("Some I/O Error")));
+
+    let fallback = mock_nameserver_with_privacy(
+        vec![io_error()],
+        ProtocolConfig::Udp,
+        PrivacyProfile::Opportunistic,
+    );
+    let tcp =
+        mock_nameserver_with_privacy(vec![io_error()], ProtocolConfig::Tcp, PrivacyProfile::Any);
+    let pool = mock_nameserver_pool(vec![fallback, tcp], None, ResolverOpts::default());
+
+    assert!(block_on(pool.send(build_request(query)).first_answer()).is_err());
+    assert_eq!(
+        pool.privacy_stats(),
+        PrivacyStats {
+            downgrades: 0,
+            outages: 1,
+        }
+    );
+}
+
+#[test]
+fn test_strict_privacy_ignores_unencrypted_connections() {
+    subscribe();
+
+    let mut config = NameServerConfig::udp_and_tcp(DEFAULT_SERVER_ADDR);
+    config.privacy = PrivacyProfile::Strict;
+    let pool = NameServerPool::from_config(
+        &[config],
+        Arc::new(ResolverOpts::default()),
+        MockConnProvider {
+            on_send: DefaultOnSend,
+        },
+    );
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+    let error = block_on(pool.send(build_request(query)).first_answer()).unwrap_err();
+    assert!(matches!(error.kind(), ProtoErrorKind::NoConnections));
+    assert_eq!(pool.privacy_stats().outages, 1);
+}
+
+#[test]
+fn test_send_attributed() {
+    subscribe();
+
+    let mut options = ResolverOpts::default();
+    options.num_concurrent_reqs = 1;
+    options.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+    let response = || {
+        Ok(DnsResponse::from_message(message(
+            query.clone(),
+            vec![v4_record(query.name().clone(), Ipv4Addr::LOCALHOST)],
+            vec![],
+            vec![],
+        ))
+        .unwrap())
+    };
+
+    let first_addr = IpAddr::from([128, 0, 0, 1]);
+    let second_addr = IpAddr::from([129, 0, 0, 1]);
+    let first = mock_udp_nameserver_with_addr(vec![response()], first_addr, options.clone());
+    let second = mock_udp_nameserver_with_addr(vec![response()], second_addr, options.clone());
+    let pool = mock_nameserver_pool(vec![first, second], None, options);
+
+    // the answer is attributed to the server it was received from
+    let (server, result) = block_on(
+        pool.without(&[first_addr])
+            .send_attributed(build_request(query.clone())),
+    );
+    assert_eq!(server, Some(second_addr));
+    assert!(result.is_ok());
+
+    let (server, result) = block_on(pool.send_attributed(build_request(query.clone())));
+    assert_eq!(server, Some(first_addr));
+    assert!(result.is_ok());
+
+    let (server, result) = block_on(
+        pool.without(&[first_addr, second_addr])
+            .send_attributed(build_request(query)),
+    );
+    assert_eq!(server, None);
+    assert!(matches!(
+        result.unwrap_err().kind(),
+        ProtoErrorKind::NoConnections
+    ));
+}
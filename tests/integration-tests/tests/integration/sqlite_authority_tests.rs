@@ -3,7 +3,6 @@
 use std::net::SocketAddr;
 use std::net::{IpAddr, Ipv4Addr};
 use std::str::FromStr;
-#[cfg(feature = "__dnssec")]
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use rusqlite::*;
@@ -26,7 +25,7 @@ use hickory_proto::serialize::binary::{BinEncodable, BinEncoder, EncodeMode};
 use hickory_proto::xfer::Protocol;
 use hickory_server::authority::{
     Authority, AxfrPolicy, LookupError, LookupOptions, MessageRequest, MessageResponseBuilder,
-    ZoneType,
+    SerialPolicy, ZoneType,
 };
 #[cfg(feature = "__dnssec")]
 use hickory_server::dnssec::NxProofKind;
@@ -863,6 +862,84 @@ async fn test_update() {
     assert_eq!(serial + 6, authority.serial().await);
 }
 
+#[tokio::test]
+async fn test_update_serial_policy() {
+    subscribe();
+    let new_name = Name::from_str("new.example.com.").unwrap();
+    let mut authority = create_example();
+    authority.set_allow_update(true);
+    authority.set_serial_policy(SerialPolicy::UnixTime);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32;
+
+    let add_record = &[
+        Record::from_rdata(new_name, 86400, RData::A(A::new(93, 184, 216, 24)))
+            .set_dns_class(DNSClass::IN)
+            .clone(),
+    ];
+    assert!(
+        authority
+            .update_records(add_record, true)
+            .await
+            .expect("update failed")
+    );
+
+    let serial = authority.serial().await;
+    assert!(serial >= now);
+}
+
+#[tokio::test]
+async fn test_update_memory_limit() {
+    subscribe();
+    let new_name = Name::from_str("new.example.com.").unwrap();
+    let mut authority = create_example();
+    authority.set_allow_update(true);
+
+    authority.set_memory_limits(None, None).unwrap();
+    let usage = authority.memory_usage().unwrap();
+    authority.set_memory_limits(Some(usage), None).unwrap();
+
+    let add_record =
+        &[
+            Record::from_rdata(new_name.clone(), 86400, RData::A(A::new(93, 184, 216, 24)))
+                .set_dns_class(DNSClass::IN)
+                .clone(),
+        ];
+    assert_eq!(
+        authority.update_records(add_record, true).await,
+        Err(ResponseCode::Refused)
+    );
+    assert_eq!(authority.memory_usage(), Some(usage));
+
+    authority
+        .set_memory_limits(Some(usage + 1_000), None)
+        .unwrap();
+    assert!(
+        authority
+            .update_records(add_record, true)
+            .await
+            .expect("update failed")
+    );
+
+    let updated = authority.memory_usage().unwrap();
+    assert!(updated > usage && updated <= usage + 1_000);
+
+    // deletions are always accepted
+    let delete_record = &[Record::update0(new_name, 0, RecordType::A)
+        .set_dns_class(DNSClass::ANY)
+        .clone()];
+    assert!(
+        authority
+            .update_records(delete_record, true)
+            .await
+            .expect("update failed")
+    );
+    assert_eq!(authority.memory_usage(), Some(usage));
+}
+
 #[cfg(feature = "__dnssec")]
 #[tokio::test]
 async fn test_update_tsig_valid() {
@@ -1033,7 +1110,7 @@ async fn test_update_tsig_invalid_unknown_signer() {
         .unwrap();
 
     // The TSIG RR should be unsigned.
-    assert_eq!(tsig_rr.mac(), &[]);
+    assert!(tsig_rr.mac().is_empty());
     // The TSIG RR should have the expected TSIG error RCODE.
     assert_eq!(tsig_rr.error(), &Some(TsigError::BadKey));
 }
@@ -1094,7 +1171,7 @@ async fn test_update_tsig_invalid_sig() {
         .unwrap();
 
     // The TSIG RR should be unsigned.
-    assert_eq!(tsig_rr.mac(), &[]);
+    assert!(tsig_rr.mac().is_empty());
     // The TSIG RR should have the expected TSIG error RCODE.
     assert_eq!(tsig_rr.error(), &Some(TsigError::BadSig));
 }
@@ -32,7 +32,7 @@ use hickory_resolver::{
 };
 use hickory_server::{
     Server,
-    authority::{AxfrPolicy, Catalog, ZoneType},
+    authority::{AxfrPolicy, Catalog, TtlPolicy, ZoneType},
     store::{
         forwarder::{ForwardAuthority, ForwardConfig},
         in_memory::InMemoryAuthority,
@@ -259,6 +259,10 @@ async fn setup_client_forwarder(
     let mut authority_builder = ForwardAuthority::builder_tokio(ForwardConfig {
         name_servers: vec![config],
         options: Some(ResolverOpts::default()),
+        request_timeout: None,
+        ttl_policy: TtlPolicy::default(),
+        #[cfg(feature = "__tls")]
+        tls: Vec::new(),
     });
 
     if let Some(public_key) = public_key {
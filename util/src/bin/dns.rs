@@ -536,7 +536,7 @@ async fn handle_request(
             let response = response.into_message();
 
             println!("; received response");
-            println!("{response}");
+            println!("{}", response.dig_display());
 
             #[cfg(not(feature = "__dnssec"))]
             {
@@ -625,7 +625,7 @@ async fn handle_request(
 
     let response = response.into_message();
     println!("; received response");
-    println!("{response}");
+    println!("{}", response.dig_display());
     Ok(())
 }
 
@@ -21,13 +21,15 @@
 )]
 
 use std::{
+    error::Error,
     fs::File,
     io::{BufRead, BufReader},
     net::{IpAddr, SocketAddr},
     ops::Deref,
     path::PathBuf,
+    str::FromStr,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use clap::{ArgGroup, Parser};
@@ -36,10 +38,12 @@ use tokio::task::JoinSet;
 use tokio::time::MissedTickBehavior;
 
 use hickory_proto::{
-    ProtoError, ProtoErrorKind,
-    rr::{Record, RecordData, RecordType},
+    ProtoError, ProtoErrorKind, ROOTS,
+    op::Query,
+    rr::{Name, Record, RecordData, RecordType},
     runtime::TokioRuntimeProvider,
 };
+use hickory_recursor::{DelegationSecurity, Recursor, TraceStep};
 use hickory_resolver::{
     TokioResolver,
     config::{
@@ -144,6 +148,12 @@ struct Opts {
     #[clap(long)]
     error: bool,
 
+    /// Follow the delegations from the root name servers to the name, like `dig +trace`,
+    /// printing each referral and whether it is secured by DNSSEC. The configured name servers
+    /// are not used.
+    #[clap(long, conflicts_with_all(&["happy", "reverse", "inputfile"]))]
+    trace: bool,
+
     /// Set the time interval between requests (in seconds, useful with --file)
     #[clap(long, default_value = "1.0")]
     interval: f32,
@@ -224,6 +234,75 @@ fn log_query(name: &str, ty: RecordType, name_servers: &str, opts: &Opts) {
     }
 }
 
+fn print_trace_step(step: &TraceStep) {
+    let servers = step
+        .servers
+        .iter()
+        .map(IpAddr::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!(
+        "{} {} from {}",
+        style("Zone").green(),
+        style(&step.zone).blue(),
+        style(servers).blue(),
+    );
+
+    for r in step
+        .response
+        .answers()
+        .iter()
+        .chain(step.response.name_servers())
+    {
+        print_record(&r);
+    }
+
+    let Some(delegation) = &step.delegation else {
+        return;
+    };
+
+    let security = match delegation.security {
+        DelegationSecurity::Secure { key_tag } => {
+            style(format!("secure, DS matches DNSKEY {key_tag}")).green()
+        }
+        DelegationSecurity::Insecure => style("insecure, no DS records".to_string()).yellow(),
+        DelegationSecurity::Bogus => style("bogus, no DS matches a DNSKEY".to_string()).red(),
+        DelegationSecurity::Unverified => style("DS records not verified".to_string()).yellow(),
+        _ => style("unknown".to_string()).yellow(),
+    };
+    println!(
+        "\tdelegation to {zone}: {security}",
+        zone = style(&delegation.zone).blue(),
+    );
+}
+
+async fn trace(name: &str, ty: RecordType, ipv4: bool, ipv6: bool) -> Result<(), Box<dyn Error>> {
+    let mut name = Name::from_str(name)?;
+    name.set_fqdn(true);
+
+    let roots = ROOTS
+        .iter()
+        .copied()
+        .filter(|ip| (ipv4 && ip.is_ipv4()) || (ipv6 && ip.is_ipv6()))
+        .collect::<Vec<_>>();
+    let recursor = Recursor::builder().build(&roots)?;
+
+    println!(
+        "Tracing {name} {ty} from roots",
+        name = style(&name).yellow(),
+        ty = style(ty).yellow(),
+    );
+
+    let steps = recursor
+        .trace(Query::query(name, ty), Instant::now())
+        .await?;
+    for step in &steps {
+        print_trace_step(step);
+    }
+
+    Ok(())
+}
+
 async fn execute_query(
     resolver: Arc<TokioResolver>,
     name: String,
@@ -245,7 +324,7 @@ async fn execute_query(
 
 /// Run the resolve program
 #[tokio::main]
-pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
+pub async fn main() -> Result<(), Box<dyn Error>> {
     let opts: Opts = Opts::parse();
 
     // enable logging early
@@ -263,6 +342,14 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     hickory_util::logger(env!("CARGO_BIN_NAME"), log_level);
 
+    let ipv4 = opts.ipv4 || !opts.ipv6;
+    let ipv6 = opts.ipv6 || !opts.ipv4;
+
+    if opts.trace {
+        let domainname = opts.domainname.as_deref().unwrap_or_default();
+        return trace(domainname, opts.ty, ipv4, ipv6).await;
+    }
+
     // read system configuration
     let (sys_config, sys_options): (Option<ResolverConfig>, Option<ResolverOpts>) = if opts.system {
         let (config, options) = hickory_resolver::system_conf::read_system_conf()?;
@@ -298,9 +385,6 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
         name_servers.extend(GOOGLE.udp_and_tcp());
     }
 
-    let ipv4 = opts.ipv4 || !opts.ipv6;
-    let ipv6 = opts.ipv6 || !opts.ipv4;
-
     let udp = opts.udp || !opts.tcp;
     let tcp = opts.tcp || !opts.udp;
 
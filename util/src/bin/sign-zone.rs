@@ -0,0 +1,326 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The offline zone signing program
+
+// BINARY WARNINGS
+#![warn(
+    clippy::default_trait_access,
+    clippy::dbg_macro,
+    clippy::unimplemented,
+    missing_copy_implementations,
+    missing_docs,
+    non_snake_case,
+    non_upper_case_globals,
+    rust_2018_idioms,
+    unreachable_pub
+)]
+
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::{error::Error, fmt::Write as _, path::PathBuf, sync::Arc, time::Duration};
+
+use clap::{Parser, ValueEnum};
+use data_encoding::HEXLOWER_PERMISSIVE;
+use rustls_pki_types::{PrivateKeyDer, pem::PemObject};
+use tracing::info;
+
+use hickory_proto::{
+    dnssec::{
+        Algorithm, DigestType, Nsec3HashAlgorithm, SigSigner, SigningKey,
+        crypto::{EcdsaSigningKey, Ed25519SigningKey, signing_key_from_der},
+        rdata::{DNSKEY, DS},
+    },
+    rr::{Name, Record},
+    serialize::txt::Parser as ZoneParser,
+};
+use hickory_server::{
+    authority::{AxfrPolicy, ZoneType},
+    dnssec::NxProofKind,
+    store::in_memory::InMemoryAuthority,
+};
+
+/// A CLI interface for signing zone files offline.
+///
+/// This utility reads a zone file, signs it with the given keys (or newly generated ones) using
+/// the same signer as the hickory-server, and writes the signed zone together with the DS records
+/// to publish in the parent zone.
+#[derive(Debug, Parser)]
+#[clap(name = "sign-zone", version)]
+struct Opts {
+    /// The zone file to sign
+    zone_file: PathBuf,
+
+    /// The origin of the zone, defaults to the `$ORIGIN` of the zone file
+    #[clap(short = 'o', long)]
+    origin: Option<Name>,
+
+    /// Private keys to sign the zone with, in PKCS#8 DER or PEM format
+    #[clap(short = 'k', long = "key", required_unless_present = "generate_key")]
+    keys: Vec<PathBuf>,
+
+    /// Generate a new private key, written in PKCS#8 DER format to this path, and sign with it
+    ///
+    /// The path must not exist yet, the key is created readable only by its owner.
+    #[clap(short = 'g', long)]
+    generate_key: Option<PathBuf>,
+
+    /// The algorithm of the keys
+    #[clap(short = 'a', long, default_value = "ecdsap256sha256", value_enum)]
+    algorithm: KeyAlgorithm,
+
+    /// Use NSEC3 rather than NSEC records to prove the non-existence of names
+    #[clap(long)]
+    nsec3: bool,
+
+    /// The NSEC3 salt, in hex, defaults to no salt
+    #[clap(long, requires = "nsec3", value_parser = parse_salt)]
+    salt: Option<Arc<[u8]>>,
+
+    /// The number of additional NSEC3 hash iterations
+    #[clap(long, requires = "nsec3", default_value_t = 0)]
+    iterations: u16,
+
+    /// Set the NSEC3 Opt-Out flag, so that insecure delegations are not covered
+    #[clap(long, requires = "nsec3")]
+    opt_out: bool,
+
+    /// The validity of the signatures, in days
+    #[clap(long, default_value_t = 365)]
+    signature_days: u64,
+
+    /// The digest type of the DS records
+    #[clap(long, default_value = "sha256", value_enum)]
+    digest_type: Digest,
+
+    /// Write the signed zone to this file, rather than to stdout
+    #[clap(short = 'f', long)]
+    output: Option<PathBuf>,
+
+    /// Write the DS records to this file, rather than to stderr
+    #[clap(long)]
+    ds_output: Option<PathBuf>,
+
+    /// Enable debug and all logging
+    #[clap(long)]
+    debug: bool,
+
+    /// Enable info + warning + error logging
+    #[clap(long)]
+    info: bool,
+
+    /// Enable warning + error logging
+    #[clap(long)]
+    warn: bool,
+
+    /// Enable error logging
+    #[clap(long)]
+    error: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum KeyAlgorithm {
+    Rsasha256,
+    Rsasha512,
+    Ecdsap256sha256,
+    Ecdsap384sha384,
+    Ed25519,
+}
+
+impl From<KeyAlgorithm> for Algorithm {
+    fn from(algorithm: KeyAlgorithm) -> Self {
+        match algorithm {
+            KeyAlgorithm::Rsasha256 => Self::RSASHA256,
+            KeyAlgorithm::Rsasha512 => Self::RSASHA512,
+            KeyAlgorithm::Ecdsap256sha256 => Self::ECDSAP256SHA256,
+            KeyAlgorithm::Ecdsap384sha384 => Self::ECDSAP384SHA384,
+            KeyAlgorithm::Ed25519 => Self::ED25519,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Digest {
+    Sha1,
+    Sha256,
+    Sha384,
+}
+
+impl From<Digest> for DigestType {
+    fn from(digest: Digest) -> Self {
+        match digest {
+            Digest::Sha1 => Self::SHA1,
+            Digest::Sha256 => Self::SHA256,
+            Digest::Sha384 => Self::SHA384,
+        }
+    }
+}
+
+fn parse_salt(salt: &str) -> Result<Arc<[u8]>, String> {
+    // `-` is the conventional notation for an empty salt
+    if salt == "-" {
+        return Ok(Arc::new([]));
+    }
+
+    HEXLOWER_PERMISSIVE
+        .decode(salt.as_bytes())
+        .map(Arc::from)
+        .map_err(|e| format!("invalid hex salt: {e}"))
+}
+
+fn read_key(path: &PathBuf, algorithm: Algorithm) -> Result<Box<dyn SigningKey>, String> {
+    info!("reading key: {path:?}");
+    let buf = fs::read(path).map_err(|e| format!("could not read key from {path:?}: {e}"))?;
+
+    let start = buf
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(buf.len());
+    let key = match buf[start..].starts_with(b"-----BEGIN ") {
+        true => PrivateKeyDer::from_pem_slice(&buf)
+            .map_err(|e| format!("could not read pem from {path:?}: {e}"))?,
+        false => PrivateKeyDer::try_from(buf)
+            .map_err(|e| format!("could not read der from {path:?}: {e}"))?,
+    };
+
+    signing_key_from_der(&key, algorithm)
+        .map_err(|e| format!("could not decode key from {path:?}: {e}"))
+}
+
+fn generate_key(path: &PathBuf, algorithm: Algorithm) -> Result<Box<dyn SigningKey>, String> {
+    let pkcs8 = match algorithm {
+        Algorithm::ECDSAP256SHA256 | Algorithm::ECDSAP384SHA384 => {
+            EcdsaSigningKey::generate_pkcs8(algorithm)
+        }
+        Algorithm::ED25519 => Ed25519SigningKey::generate_pkcs8(),
+        _ => return Err(format!("generating {algorithm} keys is not supported")),
+    }
+    .map_err(|e| format!("could not generate key: {e}"))?;
+
+    info!("writing generated key: {path:?}");
+    // the key is only readable by its owner, and an existing key is never overwritten
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(pkcs8.secret_pkcs8_der()))
+        .map_err(|e| format!("could not write key to {path:?}: {e}"))?;
+
+    signing_key_from_der(&PrivateKeyDer::Pkcs8(pkcs8), algorithm)
+        .map_err(|e| format!("could not decode generated key: {e}"))
+}
+
+/// Run the program
+#[tokio::main]
+pub async fn main() -> Result<(), Box<dyn Error>> {
+    let opts = Opts::parse();
+
+    // enable logging early
+    let log_level = if opts.debug {
+        Some(tracing::Level::DEBUG)
+    } else if opts.info {
+        Some(tracing::Level::INFO)
+    } else if opts.warn {
+        Some(tracing::Level::WARN)
+    } else if opts.error {
+        Some(tracing::Level::ERROR)
+    } else {
+        None
+    };
+
+    hickory_util::logger(env!("CARGO_BIN_NAME"), log_level);
+
+    let algorithm = Algorithm::from(opts.algorithm);
+    let digest_type = DigestType::from(opts.digest_type);
+
+    let mut keys = opts
+        .keys
+        .iter()
+        .map(|path| read_key(path, algorithm))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(path) = &opts.generate_key {
+        keys.push(generate_key(path, algorithm)?);
+    }
+
+    // read the zone
+    let zone = fs::read_to_string(&opts.zone_file)
+        .map_err(|e| format!("could not read {:?}: {e}", opts.zone_file))?;
+    let (origin, records) = ZoneParser::new(zone, Some(opts.zone_file.clone()), opts.origin)
+        .parse()
+        .map_err(|e| format!("could not parse {:?}: {e}", opts.zone_file))?;
+
+    let nx_proof_kind = match opts.nsec3 {
+        true => NxProofKind::Nsec3 {
+            algorithm: Nsec3HashAlgorithm::SHA1,
+            salt: opts.salt.unwrap_or_else(|| Arc::new([])),
+            iterations: opts.iterations,
+            opt_out: opts.opt_out,
+        },
+        false => NxProofKind::Nsec,
+    };
+
+    let mut authority = InMemoryAuthority::new(
+        origin.clone(),
+        records,
+        ZoneType::Primary,
+        AxfrPolicy::Deny,
+        Some(nx_proof_kind),
+    )?;
+
+    // add the keys, and collect the DS records for the parent zone
+    let ttl = authority.minimum_ttl().await;
+    let mut ds_records = String::new();
+    for key in keys {
+        let public_key = key.to_public_key()?;
+        let dnskey = DNSKEY::from_key(&public_key);
+
+        let ds = DS::new(
+            dnskey.calculate_key_tag()?,
+            algorithm,
+            digest_type,
+            dnskey.to_digest(&origin, digest_type)?.as_ref().to_owned(),
+        );
+        writeln!(
+            ds_records,
+            "{}",
+            Record::from_rdata(origin.clone(), ttl, ds)
+        )?;
+
+        let signer = SigSigner::dnssec(
+            dnskey,
+            key,
+            origin.clone(),
+            Duration::from_secs(opts.signature_days * 24 * 60 * 60),
+        );
+        signer.test_key()?;
+        authority.add_zone_signing_key_mut(signer)?;
+    }
+
+    info!("signing zone: {origin}");
+    authority.secure_zone_mut()?;
+
+    let mut signed = String::new();
+    authority.write_zone_file(&mut signed).await?;
+
+    match &opts.output {
+        Some(path) => fs::write(path, signed)
+            .map_err(|e| format!("could not write signed zone to {path:?}: {e}"))?,
+        None => print!("{signed}"),
+    }
+
+    match &opts.ds_output {
+        Some(path) => fs::write(path, ds_records)
+            .map_err(|e| format!("could not write DS records to {path:?}: {e}"))?,
+        None => eprint!("{ds_records}"),
+    }
+
+    Ok(())
+}
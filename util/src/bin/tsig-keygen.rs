@@ -0,0 +1,183 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The TSIG key generation program
+
+// BINARY WARNINGS
+#![warn(
+    clippy::default_trait_access,
+    clippy::dbg_macro,
+    clippy::unimplemented,
+    missing_copy_implementations,
+    missing_docs,
+    non_snake_case,
+    non_upper_case_globals,
+    rust_2018_idioms,
+    unreachable_pub
+)]
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::{error::Error, path::PathBuf};
+
+use clap::{Parser, ValueEnum};
+use data_encoding::BASE64;
+use rand::{TryRngCore, rngs::OsRng};
+use tracing::info;
+
+use hickory_proto::rr::Name;
+
+/// A CLI interface for generating TSIG keys.
+///
+/// This utility generates a random key for one of the supported HMAC algorithms, writes the raw
+/// key data as expected by the `key_file` of the hickory-dns TSIG key configuration, and outputs
+/// the matching BIND key statement and hickory-dns configuration.
+#[derive(Debug, Parser)]
+#[clap(name = "tsig-keygen", version)]
+struct Opts {
+    /// The name of the key, e.g. update.example.com.
+    name: Name,
+
+    /// The HMAC algorithm of the key
+    #[clap(short = 'a', long, default_value = "hmac-sha256", value_enum)]
+    algorithm: HmacAlgorithm,
+
+    /// The size of the key in bytes, defaults to the output size of the algorithm
+    #[clap(short = 's', long, value_parser = clap::value_parser!(u16).range(16..=1024))]
+    size: Option<u16>,
+
+    /// Write the raw key data to this file
+    #[clap(short = 'k', long)]
+    key_file: PathBuf,
+
+    /// Write the BIND key statement to this file, rather than to stdout
+    #[clap(long)]
+    bind_output: Option<PathBuf>,
+
+    /// Write the hickory-dns TSIG key configuration to this file, rather than to stdout
+    #[clap(long)]
+    config_output: Option<PathBuf>,
+
+    /// Enable debug and all logging
+    #[clap(long)]
+    debug: bool,
+
+    /// Enable info + warning + error logging
+    #[clap(long)]
+    info: bool,
+
+    /// Enable warning + error logging
+    #[clap(long)]
+    warn: bool,
+
+    /// Enable error logging
+    #[clap(long)]
+    error: bool,
+}
+
+/// The TSIG algorithms which are supported by hickory-dns for signing
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum HmacAlgorithm {
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+}
+
+impl HmacAlgorithm {
+    /// The name of the algorithm in BIND and hickory-dns configurations
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::HmacSha256 => "hmac-sha256",
+            Self::HmacSha384 => "hmac-sha384",
+            Self::HmacSha512 => "hmac-sha512",
+        }
+    }
+
+    /// The output size of the HMAC, which is the recommended key size
+    fn output_size(self) -> u16 {
+        match self {
+            Self::HmacSha256 => 32,
+            Self::HmacSha384 => 48,
+            Self::HmacSha512 => 64,
+        }
+    }
+}
+
+/// Writes `contents` to a file readable only by its owner, as it may hold the secret
+fn write_secret(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(path)?.write_all(contents)
+}
+
+fn write_output(path: Option<&PathBuf>, output: &str) -> Result<(), String> {
+    match path {
+        Some(path) => {
+            info!("writing: {path:?}");
+            write_secret(path, output.as_bytes())
+                .map_err(|e| format!("could not write to {path:?}: {e}"))
+        }
+        None => {
+            print!("{output}");
+            Ok(())
+        }
+    }
+}
+
+/// Run the program
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let opts = Opts::parse();
+
+    // enable logging early
+    let log_level = if opts.debug {
+        Some(tracing::Level::DEBUG)
+    } else if opts.info {
+        Some(tracing::Level::INFO)
+    } else if opts.warn {
+        Some(tracing::Level::WARN)
+    } else if opts.error {
+        Some(tracing::Level::ERROR)
+    } else {
+        None
+    };
+
+    hickory_util::logger(env!("CARGO_BIN_NAME"), log_level);
+
+    let mut name = opts.name;
+    name.set_fqdn(true);
+    let algorithm = opts.algorithm;
+
+    let mut key = vec![0; usize::from(opts.size.unwrap_or(algorithm.output_size()))];
+    OsRng
+        .try_fill_bytes(&mut key)
+        .map_err(|e| format!("could not generate key: {e}"))?;
+
+    info!("writing key: {:?}", opts.key_file);
+    write_secret(&opts.key_file, &key)
+        .map_err(|e| format!("could not write key to {:?}: {e}", opts.key_file))?;
+
+    let bind = format!(
+        "key \"{name}\" {{\n\talgorithm {algorithm};\n\tsecret \"{secret}\";\n}};\n",
+        algorithm = algorithm.as_str(),
+        secret = BASE64.encode(&key),
+    );
+    write_output(opts.bind_output.as_ref(), &bind)?;
+
+    let config = format!(
+        "[[zones.stores.tsig_keys]]\nname = \"{name}\"\nkey_file = \"{key_file}\"\nalgorithm = \"{algorithm}\"\n",
+        key_file = opts.key_file.display(),
+        algorithm = algorithm.as_str(),
+    );
+    write_output(opts.config_output.as_ref(), &config)?;
+
+    Ok(())
+}